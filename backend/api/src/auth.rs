@@ -0,0 +1,261 @@
+//! Optional API-key authentication
+//!
+//! When `AUTH_ENABLED` is set, every route except the health checks requires
+//! an `Authorization: Bearer <key>` header matching one of `API_KEYS`. Each
+//! key maps to an [`Identity`] that owns the dumps created with it.
+
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Request, State},
+    http::{header, request::Parts, HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use crate::config::AppConfig;
+use crate::error::ErrorResponse;
+
+/// Identity that can see every dump
+pub const ADMIN_IDENTITY: &str = "admin";
+
+/// Identity of keys configured without an `identity:` prefix
+const DEFAULT_IDENTITY: &str = "default";
+
+/// Caller identity, added to request extensions by [`require_api_key`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identity(pub String);
+
+impl Identity {
+    pub fn is_admin(&self) -> bool {
+        self.0 == ADMIN_IDENTITY
+    }
+}
+
+/// Owner a caller's dump lookups are restricted to
+///
+/// None when auth is disabled or the caller is the admin, who see every dump.
+pub fn owner_scope(identity: Option<&Identity>) -> Option<&str> {
+    identity.filter(|i| !i.is_admin()).map(|i| i.0.as_str())
+}
+
+/// [`owner_scope`] of the caller, for handlers that look up dumps by id
+///
+/// Lookups given an owner treat dumps of other owners as not found.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Owner(pub Option<String>);
+
+impl Owner {
+    pub fn of(identity: Option<&Identity>) -> Self {
+        Owner(owner_scope(identity).map(str::to_string))
+    }
+
+    pub fn as_deref(&self) -> Option<&str> {
+        self.0.as_deref()
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Owner {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Owner::of(parts.extensions.get::<Identity>()))
+    }
+}
+
+/// Accepted API keys and their identities; authentication is skipped when
+/// disabled
+#[derive(Debug, Clone, Default)]
+pub struct ApiKeyAuth {
+    enabled: bool,
+    keys: Vec<(Identity, String)>,
+}
+
+impl ApiKeyAuth {
+    /// Keys are `identity:key`, or a bare key for the `default` identity
+    pub fn new(enabled: bool, keys: Vec<String>) -> Self {
+        let keys = keys
+            .into_iter()
+            .map(|entry| match entry.split_once(':') {
+                Some((identity, key)) => (Identity(identity.to_string()), key.to_string()),
+                None => (Identity(DEFAULT_IDENTITY.to_string()), entry),
+            })
+            .collect();
+        Self { enabled, keys }
+    }
+
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self::new(config.auth_enabled, config.api_keys.clone())
+    }
+
+    /// Identity of a request with these headers
+    ///
+    /// `Ok(None)` when auth is disabled, `Err(())` when the key is missing or
+    /// unknown.
+    fn authenticate(&self, headers: &HeaderMap) -> Result<Option<Identity>, ()> {
+        if !self.enabled {
+            return Ok(None);
+        }
+        let key = bearer_token(headers).ok_or(())?;
+        // Check every key so the response time does not reveal which matched
+        self.keys
+            .iter()
+            .fold(None, |found, (identity, k)| {
+                if constant_time_eq(k, key) {
+                    Some(identity.clone())
+                } else {
+                    found
+                }
+            })
+            .map(Some)
+            .ok_or(())
+    }
+}
+
+/// Token of an `Authorization: Bearer <token>` header
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+    scheme
+        .eq_ignore_ascii_case("bearer")
+        .then(|| token.trim())
+        .filter(|t| !t.is_empty())
+}
+
+/// Compare two strings in time independent of where they differ
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+/// Middleware rejecting requests without a valid API key with 401
+///
+/// Authenticated requests carry their [`Identity`] as an extension.
+pub async fn require_api_key(
+    State(auth): State<Arc<ApiKeyAuth>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    if let Ok(identity) = auth.authenticate(request.headers()) {
+        if let Some(identity) = identity {
+            request.extensions_mut().insert(identity);
+        }
+        return next.run(request).await;
+    }
+
+    let body = ErrorResponse {
+        error: "unauthorized".to_string(),
+        message: "A valid API key is required (Authorization: Bearer <key>)".to_string(),
+    };
+    (
+        StatusCode::UNAUTHORIZED,
+        [(header::WWW_AUTHENTICATE, "Bearer")],
+        Json(body),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::Body, http::Request as HttpRequest, middleware::from_fn_with_state, routing::get,
+        Router,
+    };
+    use tower::ServiceExt;
+
+    fn app(auth: ApiKeyAuth) -> Router {
+        Router::new()
+            .route("/api/dumps", get(|| async { "ok" }))
+            .route_layer(from_fn_with_state(Arc::new(auth), require_api_key))
+            .route("/health", get(|| async { "ok" }))
+    }
+
+    async fn status(app: Router, uri: &str, authorization: Option<&str>) -> StatusCode {
+        let mut request = HttpRequest::get(uri);
+        if let Some(value) = authorization {
+            request = request.header(header::AUTHORIZATION, value);
+        }
+        app.oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn test_enabled_rejects_missing_or_invalid_key() {
+        let app = app(ApiKeyAuth::new(true, vec!["s3cret".to_string()]));
+
+        assert_eq!(
+            status(app.clone(), "/api/dumps", None).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(app.clone(), "/api/dumps", Some("Bearer wrong")).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(app.clone(), "/api/dumps", Some("Basic s3cret")).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(app.clone(), "/api/dumps", Some("Bearer s3cret")).await,
+            StatusCode::OK
+        );
+        // Health checks stay open for probes
+        assert_eq!(status(app, "/health", None).await, StatusCode::OK);
+    }
+
+    #[test]
+    fn test_keys_map_to_identities() {
+        let auth = ApiKeyAuth::new(true, vec!["alice:k1".to_string(), "k2".to_string()]);
+        let headers = |key: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(
+                header::AUTHORIZATION,
+                format!("Bearer {}", key).parse().unwrap(),
+            );
+            headers
+        };
+
+        assert_eq!(
+            auth.authenticate(&headers("k1")),
+            Ok(Some(Identity("alice".to_string())))
+        );
+        assert_eq!(
+            auth.authenticate(&headers("k2")),
+            Ok(Some(Identity("default".to_string())))
+        );
+        assert_eq!(auth.authenticate(&headers("alice:k1")), Err(()));
+        assert_eq!(ApiKeyAuth::default().authenticate(&headers("x")), Ok(None));
+    }
+
+    #[test]
+    fn test_owner_scope() {
+        let alice = Identity("alice".to_string());
+        let admin = Identity(ADMIN_IDENTITY.to_string());
+        assert_eq!(owner_scope(Some(&alice)), Some("alice"));
+        assert_eq!(owner_scope(Some(&admin)), None);
+        assert_eq!(owner_scope(None), None);
+    }
+
+    #[tokio::test]
+    async fn test_disabled_passes_through() {
+        let app = app(ApiKeyAuth::default());
+        assert_eq!(status(app, "/api/dumps", None).await, StatusCode::OK);
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq("abc", "abc"));
+        assert!(!constant_time_eq("abc", "abd"));
+        assert!(!constant_time_eq("abc", "abcd"));
+    }
+}
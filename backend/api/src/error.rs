@@ -1,7 +1,7 @@
 //! API error types
 
 use axum::{
-    http::StatusCode,
+    http::{header, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
@@ -24,6 +24,14 @@ pub enum ApiError {
     #[error("Conflict: {0}")]
     Conflict(String),
 
+    /// The resource existed but has been removed for good
+    #[error("Gone: {0}")]
+    Gone(String),
+
+    /// Rate limit exceeded; the value is the `Retry-After` delay in seconds
+    #[error("Too many requests, retry after {0} seconds")]
+    TooManyRequests(u64),
+
     #[error("Internal error: {0}")]
     Internal(String),
 
@@ -34,6 +42,17 @@ pub enum ApiError {
     Core(#[from] db_viewer_core::CoreError),
 }
 
+/// SQLSTATE raised when a query is cancelled by `statement_timeout`
+const QUERY_CANCELED: &str = "57014";
+
+/// Whether a database error was caused by the sandbox statement timeout
+fn is_statement_timeout(error: &sqlx::Error) -> bool {
+    match error {
+        sqlx::Error::Database(db_err) => db_err.code().as_deref() == Some(QUERY_CANCELED),
+        _ => false,
+    }
+}
+
 /// Error response body
 #[derive(Serialize)]
 pub struct ErrorResponse {
@@ -48,7 +67,12 @@ impl IntoResponse for ApiError {
             ApiError::BadRequest(_) => (StatusCode::BAD_REQUEST, "bad_request"),
             ApiError::Validation(_) => (StatusCode::UNPROCESSABLE_ENTITY, "validation_error"),
             ApiError::Conflict(_) => (StatusCode::CONFLICT, "conflict"),
+            ApiError::Gone(_) => (StatusCode::GONE, "gone"),
+            ApiError::TooManyRequests(_) => (StatusCode::TOO_MANY_REQUESTS, "too_many_requests"),
             ApiError::Internal(_) => (StatusCode::INTERNAL_SERVER_ERROR, "internal_error"),
+            ApiError::Database(e) if is_statement_timeout(e) => {
+                (StatusCode::BAD_REQUEST, "statement_timeout")
+            }
             ApiError::Database(_) => (StatusCode::INTERNAL_SERVER_ERROR, "database_error"),
             ApiError::Core(_) => (StatusCode::INTERNAL_SERVER_ERROR, "core_error"),
         };
@@ -58,6 +82,14 @@ impl IntoResponse for ApiError {
             message: self.to_string(),
         };
 
+        if let ApiError::TooManyRequests(retry_after) = self {
+            return (
+                status,
+                [(header::RETRY_AFTER, retry_after.to_string())],
+                Json(body),
+            )
+                .into_response();
+        }
         (status, Json(body)).into_response()
     }
 }
@@ -81,4 +113,20 @@ mod tests {
         let response = error.into_response();
         assert_eq!(response.status(), StatusCode::BAD_REQUEST);
     }
+
+    #[test]
+    fn test_gone_status() {
+        let error = ApiError::Gone("upload deleted".to_string());
+        assert_eq!(error.into_response().status(), StatusCode::GONE);
+    }
+
+    #[test]
+    fn test_other_database_errors_are_internal() {
+        let error = ApiError::Database(sqlx::Error::RowNotFound);
+        assert!(!is_statement_timeout(&sqlx::Error::RowNotFound));
+        assert_eq!(
+            error.into_response().status(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
 }
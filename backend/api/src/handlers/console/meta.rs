@@ -621,15 +621,17 @@ async fn connect(
         }];
     };
 
-    let sandbox_db = match resolve_sandbox_db(&state.db_pool, session.dump_id, Some(&db)).await {
-        Ok(s) => s,
-        // Keep the old connection alive, matching psql behaviour.
-        Err(e) => {
-            return vec![Block::Error {
-                text: format!("ERROR:  {}", e),
-            }]
-        }
-    };
+    let sandbox_db =
+        match resolve_sandbox_db(state, session.dump_id, session.owner.as_deref(), Some(&db)).await
+        {
+            Ok(s) => s,
+            // Keep the old connection alive, matching psql behaviour.
+            Err(e) => {
+                return vec![Block::Error {
+                    text: format!("ERROR:  {}", e),
+                }]
+            }
+        };
 
     let new_conn = match open_session_conn(&state.config, &sandbox_db).await {
         Ok(c) => c,
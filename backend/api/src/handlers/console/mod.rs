@@ -21,8 +21,9 @@ use serde::{Deserialize, Serialize};
 use sqlx::Row;
 use uuid::Uuid;
 
+use crate::auth::Owner;
 use crate::error::{ApiError, ApiResult};
-use crate::handlers::sandbox::{extract_original_db_name, resolve_sandbox_db};
+use crate::handlers::sandbox::{extract_original_db_name, reject_archived, resolve_sandbox_db};
 use crate::state::AppState;
 use session::ConsoleSession;
 
@@ -86,17 +87,21 @@ fn prompt_for(db: &str) -> String {
 /// `POST /api/dumps/:id/console` — create a console session.
 pub async fn create_session(
     State(state): State<AppState>,
+    owner: Owner,
     Path(id): Path<Uuid>,
     Json(req): Json<CreateSessionRequest>,
 ) -> ApiResult<Json<CreateSessionResponse>> {
     // Ensure the dump is READY before touching the sandbox.
-    let status_row = sqlx::query("SELECT status FROM dumps WHERE id = $1")
-        .bind(id)
-        .fetch_optional(&state.db_pool)
-        .await?;
+    let status_row =
+        sqlx::query("SELECT status FROM dumps WHERE id = $1 AND ($2::text IS NULL OR owner = $2)")
+            .bind(id)
+            .bind(owner.as_deref())
+            .fetch_optional(&state.db_pool)
+            .await?;
     let status_row =
         status_row.ok_or_else(|| ApiError::NotFound(format!("Dump {} not found", id)))?;
     let status: String = status_row.get("status");
+    reject_archived(&state, id, &status).await?;
     if status != "READY" {
         return Err(ApiError::BadRequest(format!(
             "Dump is not ready for queries (status: {})",
@@ -104,7 +109,8 @@ pub async fn create_session(
         )));
     }
 
-    let sandbox_db = resolve_sandbox_db(&state.db_pool, id, req.database.as_deref()).await?;
+    let sandbox_db =
+        resolve_sandbox_db(&state, id, owner.as_deref(), req.database.as_deref()).await?;
     let conn = session::open_session_conn(&state.config, &sandbox_db).await?;
 
     let database = req
@@ -115,6 +121,7 @@ pub async fn create_session(
     let cs = ConsoleSession {
         conn,
         dump_id: id,
+        owner: owner.0,
         sandbox_db,
         database: database.clone(),
         expanded: false,
@@ -132,6 +139,7 @@ pub async fn create_session(
 /// `POST /api/console/:session_id` — run one complete input.
 pub async fn execute(
     State(state): State<AppState>,
+    owner: Owner,
     Path(session_id): Path<Uuid>,
     Json(req): Json<ExecuteRequest>,
 ) -> ApiResult<Json<ExecuteResponse>> {
@@ -142,7 +150,7 @@ pub async fn execute(
 
     let (session_arc, last_used) = state
         .console_sessions
-        .get(session_id)
+        .get(session_id, owner.as_deref())
         .ok_or_else(|| ApiError::NotFound("Console session not found or expired".to_string()))?;
 
     // `try_lock` so a concurrent in-flight command surfaces as 409 rather than
@@ -191,7 +199,7 @@ pub async fn execute(
     drop(guard);
 
     if ended {
-        if let Some(s) = state.console_sessions.remove(session_id) {
+        if let Some(s) = state.console_sessions.remove(session_id, owner.as_deref()) {
             session::close_session(s).await;
         }
     }
@@ -210,9 +218,10 @@ pub async fn execute(
 /// `DELETE /api/console/:session_id` — close a session (idempotent).
 pub async fn close_session(
     State(state): State<AppState>,
+    owner: Owner,
     Path(session_id): Path<Uuid>,
 ) -> StatusCode {
-    if let Some(s) = state.console_sessions.remove(session_id) {
+    if let Some(s) = state.console_sessions.remove(session_id, owner.as_deref()) {
         session::close_session(s).await;
     }
     StatusCode::NO_CONTENT
@@ -24,7 +24,6 @@ use uuid::Uuid;
 
 use crate::config::AppConfig;
 use crate::error::{ApiError, ApiResult};
-use crate::handlers::sandbox::build_sandbox_url;
 
 /// Hard cap on concurrent console sessions across all dumps.
 pub const MAX_SESSIONS_TOTAL: usize = 32;
@@ -34,8 +33,6 @@ pub const MAX_SESSIONS_PER_DUMP: usize = 4;
 pub const IDLE_TTL_SECS: u64 = 900;
 /// Interval between idle sweeps (seconds).
 pub const SWEEP_INTERVAL_SECS: u64 = 60;
-/// Statement timeout applied to each session connection (milliseconds).
-pub const STATEMENT_TIMEOUT_MS: i64 = 30_000;
 /// Maximum number of result rows returned per query.
 pub const MAX_ROWS: i64 = 500;
 
@@ -45,6 +42,8 @@ pub struct ConsoleSession {
     pub conn: PgConnection,
     /// The dump this session belongs to.
     pub dump_id: Uuid,
+    /// Owner scope of the caller that opened it; other owners cannot use it.
+    pub owner: Option<String>,
     /// The actual sandbox database name currently connected to.
     pub sandbox_db: String,
     /// The user-friendly database name (used for the prompt).
@@ -58,6 +57,7 @@ pub struct ConsoleSession {
 /// Internal map entry pairing a session with bookkeeping.
 struct SessionEntry {
     dump_id: Uuid,
+    owner: Option<String>,
     last_used: Arc<AtomicU64>,
     session: Arc<tokio::sync::Mutex<ConsoleSession>>,
 }
@@ -81,6 +81,12 @@ fn is_expired(now: u64, last_used: u64, ttl: u64) -> bool {
     now.saturating_sub(last_used) > ttl
 }
 
+/// Whether a caller scoped to `owner` may use a session; unscoped callers
+/// (admin, auth disabled) see every session.
+fn visible_to(entry: &SessionEntry, owner: Option<&str>) -> bool {
+    owner.is_none() || entry.owner.as_deref() == owner
+}
+
 /// Evict one least-recently-used, currently-unlocked entry.
 ///
 /// `dump_filter` restricts candidates to a single dump when `Some`. Returns
@@ -104,6 +110,7 @@ impl SessionManager {
     /// Insert a new session, enforcing per-dump and total caps via LRU eviction.
     pub fn insert(&self, session: ConsoleSession) -> Result<Uuid, ApiError> {
         let dump_id = session.dump_id;
+        let owner = session.owner.clone();
         let mut inner = self.inner.lock().unwrap();
 
         // Per-dump cap.
@@ -126,6 +133,7 @@ impl SessionManager {
             id,
             SessionEntry {
                 dump_id,
+                owner,
                 last_used: Arc::new(AtomicU64::new(now_secs())),
                 session: Arc::new(tokio::sync::Mutex::new(session)),
             },
@@ -133,20 +141,31 @@ impl SessionManager {
         Ok(id)
     }
 
-    /// Fetch the session handle and its last-used clock, if present.
+    /// Fetch the session handle and its last-used clock, if present and
+    /// visible to `owner`.
     pub fn get(
         &self,
         id: Uuid,
+        owner: Option<&str>,
     ) -> Option<(Arc<tokio::sync::Mutex<ConsoleSession>>, Arc<AtomicU64>)> {
         let inner = self.inner.lock().unwrap();
         inner
             .get(&id)
+            .filter(|e| visible_to(e, owner))
             .map(|e| (e.session.clone(), e.last_used.clone()))
     }
 
-    /// Remove a session, returning its handle so the caller can close it.
-    pub fn remove(&self, id: Uuid) -> Option<Arc<tokio::sync::Mutex<ConsoleSession>>> {
+    /// Remove a session visible to `owner`, returning its handle so the
+    /// caller can close it.
+    pub fn remove(
+        &self,
+        id: Uuid,
+        owner: Option<&str>,
+    ) -> Option<Arc<tokio::sync::Mutex<ConsoleSession>>> {
         let mut inner = self.inner.lock().unwrap();
+        if !inner.get(&id).is_some_and(|e| visible_to(e, owner)) {
+            return None;
+        }
         inner.remove(&id).map(|e| e.session)
     }
 
@@ -171,14 +190,17 @@ impl SessionManager {
 ///
 /// Used both when creating a session and when switching databases via `\c`.
 pub async fn open_session_conn(config: &AppConfig, sandbox_db: &str) -> ApiResult<PgConnection> {
-    let url = build_sandbox_url(config, sandbox_db);
+    let url = config.sandbox_url(sandbox_db);
     let mut conn = PgConnection::connect(&url)
         .await
         .map_err(|e| ApiError::Internal(format!("Failed to connect to sandbox: {}", e)))?;
-    sqlx::query(&format!("SET statement_timeout = {}", STATEMENT_TIMEOUT_MS))
-        .execute(&mut conn)
-        .await
-        .map_err(|e| ApiError::Internal(format!("Failed to set statement timeout: {}", e)))?;
+    sqlx::query(&format!(
+        "SET statement_timeout = {}",
+        config.statement_timeout_ms
+    ))
+    .execute(&mut conn)
+    .await
+    .map_err(|e| ApiError::Internal(format!("Failed to set statement timeout: {}", e)))?;
     Ok(conn)
 }
 
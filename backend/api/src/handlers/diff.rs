@@ -1,16 +1,31 @@
 //! Dump diff comparison handlers
 
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::time::Duration;
+
 use axum::{
     extract::{Path, Query, State},
+    http::header,
+    response::IntoResponse,
     Json,
 };
-use db_viewer_core::diff::{compare_schemas, SchemaDiff};
-use db_viewer_core::domain::ForeignKey;
+use db_viewer_core::adapter::{DbAdapter, PostgresAdapter};
+use db_viewer_core::diff::{
+    biggest_row_movers, compare_schemas_with, compare_with_database, CompareOptions,
+    RowCountChange, SchemaDiff,
+};
+use db_viewer_core::domain::{ForeignKey, NormalizedType, SchemaGraph, UniqueConstraint};
+use db_viewer_core::sql_gen::quote_ident;
 use serde::{Deserialize, Serialize};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
 use sqlx::PgPool;
 use uuid::Uuid;
 
+use crate::auth::Owner;
+use crate::config::AppConfig;
 use crate::error::ApiError;
+use crate::handlers::sandbox::{record_view, reject_archived};
 use crate::state::AppState;
 
 /// Type alias for foreign key query result to reduce type complexity
@@ -26,12 +41,36 @@ type FkQueryRow = (
     String,
 );
 
+/// Type alias for column query result: name, type, nullable, primary key,
+/// default, position, identity, identity generation, generation expression
+type ColumnQueryRow = (
+    String,
+    String,
+    bool,
+    bool,
+    Option<String>,
+    i32,
+    bool,
+    Option<String>,
+    Option<String>,
+);
+
 /// Query parameters for diff comparison
 #[derive(Debug, Deserialize)]
 pub struct DiffQuery {
     /// Database name within the dump (for multi-database dumps)
     #[serde(default)]
     pub database: Option<String>,
+    /// Report columns that only changed position
+    #[serde(default)]
+    pub detect_reorder: bool,
+    /// Rows hashed per table when checking for data changes
+    /// (default: `DIFF_CHECKSUM_ROWS`)
+    #[serde(default)]
+    pub checksum_rows: Option<i64>,
+    /// Hash every row instead of a sample
+    #[serde(default)]
+    pub exact: bool,
 }
 
 /// Response for schema diff comparison
@@ -39,15 +78,24 @@ pub struct DiffQuery {
 pub struct SchemaDiffResponse {
     /// Base dump ID
     pub base_dump_id: Uuid,
-    /// Compare dump ID
-    pub compare_dump_id: Uuid,
+    /// Compare dump ID (absent when comparing against a live database)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compare_dump_id: Option<Uuid>,
+    /// Live database compared against, as `host:port/database`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub live_database: Option<String>,
     /// Database name compared
     pub database_name: String,
     /// The schema diff result
     #[serde(flatten)]
     pub diff: SchemaDiff,
+    /// Tables whose row count changed the most, largest first
+    pub top_movers: Vec<RowCountChange>,
 }
 
+/// Tables listed in [`SchemaDiffResponse::top_movers`]
+const TOP_ROW_MOVERS: usize = 10;
+
 /// Compare schemas between two dumps
 ///
 /// GET /api/dumps/:base_id/compare/:compare_id
@@ -56,6 +104,7 @@ pub struct SchemaDiffResponse {
 /// Returns tables/columns added, removed, or modified.
 pub async fn compare_dumps(
     State(state): State<AppState>,
+    owner: Owner,
     Path((base_id, compare_id)): Path<(Uuid, Uuid)>,
     Query(query): Query<DiffQuery>,
 ) -> Result<Json<SchemaDiffResponse>, ApiError> {
@@ -66,9 +115,267 @@ pub async fn compare_dumps(
         query
     );
 
+    let options = CompareOptions {
+        detect_reorder: query.detect_reorder,
+    };
+    let checksums = ChecksumOptions::new(&state.config, query.checksum_rows, query.exact)?;
+    let (db_name, diff) = build_schema_diff(
+        &state,
+        owner.as_deref(),
+        base_id,
+        compare_id,
+        query.database.as_deref(),
+        &options,
+        Some(&checksums),
+    )
+    .await?;
+
+    Ok(Json(SchemaDiffResponse {
+        base_dump_id: base_id,
+        compare_dump_id: Some(compare_id),
+        live_database: None,
+        database_name: db_name,
+        top_movers: biggest_row_movers(&diff, TOP_ROW_MOVERS),
+        diff,
+    }))
+}
+
+/// How long to wait for a connection to a live database
+const LIVE_CONNECT_TIMEOUT_SECS: u64 = 10;
+
+/// Request body for comparing a dump against a live database
+///
+/// Exactly one of `url` and `connection` must be given.
+#[derive(Debug, Deserialize)]
+pub struct CompareLiveRequest {
+    /// Connection URL; its host must be listed in `LIVE_COMPARE_ALLOWED_HOSTS`
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Name of a connection configured in `LIVE_CONNECTIONS`
+    #[serde(default)]
+    pub connection: Option<String>,
+    /// Database name within the dump (for multi-database dumps)
+    #[serde(default)]
+    pub database: Option<String>,
+    /// Report columns that only changed position
+    #[serde(default)]
+    pub detect_reorder: bool,
+}
+
+/// Compare a dump's schema against a live database
+///
+/// POST /api/dumps/:id/compare-live
+///
+/// The dump is the base side of the diff and the live database the compare
+/// side. The live database is only read: connections are read-only and use
+/// the sandbox statement timeout.
+#[tracing::instrument(skip_all, fields(dump_id = %id))]
+pub async fn compare_live(
+    State(state): State<AppState>,
+    owner: Owner,
+    Path(id): Path<Uuid>,
+    Json(request): Json<CompareLiveRequest>,
+) -> Result<Json<SchemaDiffResponse>, ApiError> {
+    let live_options = live_connect_options(&state.config, &request)?;
+    let live_db = live_options
+        .get_database()
+        .ok_or_else(|| {
+            ApiError::BadRequest("The live connection URL must name a database".to_string())
+        })?
+        .to_string();
+    let live_database = format!(
+        "{}:{}/{}",
+        live_options.get_host(),
+        live_options.get_port(),
+        live_db
+    );
+
+    let dump = get_dump_record(&state, id, owner.as_deref()).await?;
+    if dump.status != "READY" {
+        return Err(ApiError::BadRequest(format!(
+            "Dump {} is not ready (status: {})",
+            id, dump.status
+        )));
+    }
+    let sandbox_db = select_sandbox_db(&dump, request.database.as_deref(), "Base")?;
+
+    // Introspect both sides the same way so identical schemas compare equal
+    let sandbox_pool = (*state.sandbox_pools.get(&sandbox_db).await?).clone();
+    let base_schema = PostgresAdapter::new(sandbox_pool.clone(), state.config.sandbox_connection())
+        .with_db_pool(&sandbox_db, sandbox_pool)
+        .build_schema_graph(&sandbox_db)
+        .await?;
+
+    let live_pool = PgPoolOptions::new()
+        .max_connections(1)
+        .acquire_timeout(Duration::from_secs(LIVE_CONNECT_TIMEOUT_SECS))
+        .connect_with(live_options)
+        .await
+        .map_err(|e| {
+            ApiError::BadRequest(format!("Failed to connect to {}: {}", live_database, e))
+        })?;
+    let live_adapter = PostgresAdapter::new(live_pool.clone(), state.config.sandbox_connection())
+        .with_db_pool(&live_db, live_pool.clone());
+    let options = CompareOptions {
+        detect_reorder: request.detect_reorder,
+    };
+    let diff = compare_with_database(&base_schema, &live_adapter, &live_db, &options).await;
+    live_pool.close().await;
+
+    let diff = diff?;
+    Ok(Json(SchemaDiffResponse {
+        base_dump_id: id,
+        compare_dump_id: None,
+        live_database: Some(live_database),
+        database_name: request.database.unwrap_or(sandbox_db),
+        top_movers: biggest_row_movers(&diff, TOP_ROW_MOVERS),
+        diff,
+    }))
+}
+
+/// Validated, read-only connection options for a live database
+///
+/// Ad-hoc URLs are only accepted for allowlisted hosts, so the endpoint
+/// cannot be used to probe arbitrary servers from inside the network.
+fn live_connect_options(
+    config: &AppConfig,
+    request: &CompareLiveRequest,
+) -> Result<PgConnectOptions, ApiError> {
+    let url = match (&request.url, &request.connection) {
+        (Some(url), None) => url.as_str(),
+        (None, Some(name)) => config
+            .live_connection(name)
+            .ok_or_else(|| ApiError::BadRequest(format!("Unknown live connection '{}'", name)))?,
+        _ => {
+            return Err(ApiError::BadRequest(
+                "Specify either url or connection".to_string(),
+            ))
+        }
+    };
+
+    // The URL may carry a password, so it is never echoed back
+    let options = PgConnectOptions::from_str(url)
+        .map_err(|_| ApiError::BadRequest("Invalid live connection URL".to_string()))?;
+
+    if request.url.is_some() {
+        let host = options.get_host();
+        let allowed = config
+            .live_compare_allowed_hosts
+            .iter()
+            .any(|h| h.eq_ignore_ascii_case(host));
+        if !allowed {
+            return Err(ApiError::BadRequest(format!(
+                "Host '{}' is not allowed for live comparison (see LIVE_COMPARE_ALLOWED_HOSTS)",
+                host
+            )));
+        }
+    }
+
+    Ok(options.application_name("pgdumplens").options([
+        ("default_transaction_read_only", "on".to_string()),
+        ("statement_timeout", config.statement_timeout_ms.to_string()),
+    ]))
+}
+
+/// Version of the schema diff export format, bumped on breaking changes
+pub const SCHEMA_DIFF_EXPORT_VERSION: u32 = 1;
+
+/// Query parameters for the schema diff export
+#[derive(Debug, Deserialize)]
+pub struct DiffExportQuery {
+    /// Database name within the dump (for multi-database dumps)
+    #[serde(default)]
+    pub database: Option<String>,
+    /// Also compare table contents via per-table checksums (slow on big dumps)
+    #[serde(default)]
+    pub data: bool,
+    /// Report columns that only changed position
+    #[serde(default)]
+    pub detect_reorder: bool,
+    /// Rows hashed per table with `data=true`
+    /// (default: `DIFF_CHECKSUM_ROWS`)
+    #[serde(default)]
+    pub checksum_rows: Option<i64>,
+    /// Hash every row instead of a sample with `data=true`
+    #[serde(default)]
+    pub exact: bool,
+}
+
+/// Stable schema diff artifact for CI pipelines
+#[derive(Debug, Serialize)]
+pub struct SchemaDiffExport {
+    pub schema_version: u32,
+    pub base_dump_id: Uuid,
+    pub compare_dump_id: Uuid,
+    pub database_name: String,
+    /// Whether table contents were compared (`has_data_change` is only
+    /// meaningful when true)
+    pub data_checked: bool,
+    #[serde(flatten)]
+    pub diff: SchemaDiff,
+}
+
+/// Export the schema diff as a downloadable JSON file
+///
+/// GET /api/dumps/:base_id/compare/:compare_id/export.json
+///
+/// All diff lists are sorted so identical schemas always produce identical
+/// files. Data checksums are skipped unless `?data=true`.
+pub async fn export_schema_diff(
+    State(state): State<AppState>,
+    owner: Owner,
+    Path((base_id, compare_id)): Path<(Uuid, Uuid)>,
+    Query(query): Query<DiffExportQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let options = CompareOptions {
+        detect_reorder: query.detect_reorder,
+    };
+    let checksums = ChecksumOptions::new(&state.config, query.checksum_rows, query.exact)?;
+    let (database_name, mut diff) = build_schema_diff(
+        &state,
+        owner.as_deref(),
+        base_id,
+        compare_id,
+        query.database.as_deref(),
+        &options,
+        query.data.then_some(&checksums),
+    )
+    .await?;
+    diff.sort();
+
+    let filename = format!("schema-diff-{}-{}.json", base_id, compare_id);
+    Ok((
+        [(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", filename),
+        )],
+        Json(SchemaDiffExport {
+            schema_version: SCHEMA_DIFF_EXPORT_VERSION,
+            base_dump_id: base_id,
+            compare_dump_id: compare_id,
+            database_name,
+            data_checked: query.data,
+            diff,
+        }),
+    ))
+}
+
+/// Load both schema graphs and diff them
+///
+/// Returns the user-facing database name with the diff. Per-table data
+/// checksums are only computed when `checksums` is set.
+async fn build_schema_diff(
+    state: &AppState,
+    owner: Option<&str>,
+    base_id: Uuid,
+    compare_id: Uuid,
+    database: Option<&str>,
+    options: &CompareOptions,
+    checksums: Option<&ChecksumOptions>,
+) -> Result<(String, SchemaDiff), ApiError> {
     // Get both dumps from metadata DB
-    let base_dump = get_dump_record(&state.db_pool, base_id).await?;
-    let compare_dump = get_dump_record(&state.db_pool, compare_id).await?;
+    let base_dump = get_dump_record(state, base_id, owner).await?;
+    let compare_dump = get_dump_record(state, compare_id, owner).await?;
 
     tracing::info!(
         "base_dump: sandbox_db={:?}, status={}",
@@ -97,85 +404,28 @@ pub async fn compare_dumps(
 
     // Determine which sandbox database to compare
     // For pg_dumpall dumps, each original database is stored with a prefixed name: sandbox_{dump_id}_{original_db_name}
-    // If query.database is specified, we need to find the corresponding sandbox database from sandbox_databases array
+    // If database is specified, we need to find the corresponding sandbox database from sandbox_databases array
     // Otherwise, fall back to the dump's sandbox_db_name (for backward compatibility with single-db dumps)
 
-    let base_sandbox_db = if let Some(ref selected_db) = query.database {
-        // User selected a specific database - find it in sandbox_databases
-        find_sandbox_db_for_original(&base_dump, selected_db)
-            .or_else(|| {
-                // Fall back to sandbox_db_name if not found (legacy single-database dumps)
-                base_dump.sandbox_db_name.clone()
-            })
-            .ok_or_else(|| {
-                ApiError::BadRequest(format!("Database {} not found in base dump", selected_db))
-            })?
-    } else {
-        // No database selected - use the dump's default sandbox_db_name or first from sandbox_databases
-        base_dump
-            .sandbox_db_name
-            .clone()
-            .or_else(|| {
-                base_dump
-                    .sandbox_databases
-                    .as_ref()
-                    .and_then(|dbs| dbs.first().cloned())
-            })
-            .ok_or_else(|| {
-                ApiError::BadRequest(
-                    "Base dump has no sandbox database. Please select a database.".to_string(),
-                )
-            })?
-    };
-
-    let compare_sandbox_db = if let Some(ref selected_db) = query.database {
-        // User selected a specific database - find it in sandbox_databases
-        find_sandbox_db_for_original(&compare_dump, selected_db)
-            .or_else(|| {
-                // Fall back to sandbox_db_name if not found
-                compare_dump.sandbox_db_name.clone()
-            })
-            .ok_or_else(|| {
-                ApiError::BadRequest(format!(
-                    "Database {} not found in compare dump",
-                    selected_db
-                ))
-            })?
-    } else {
-        // No database selected - use the dump's default sandbox_db_name or first from sandbox_databases
-        compare_dump
-            .sandbox_db_name
-            .clone()
-            .or_else(|| {
-                compare_dump
-                    .sandbox_databases
-                    .as_ref()
-                    .and_then(|dbs| dbs.first().cloned())
-            })
-            .ok_or_else(|| {
-                ApiError::BadRequest(
-                    "Compare dump has no sandbox database. Please select a database.".to_string(),
-                )
-            })?
-    };
+    let base_sandbox_db = select_sandbox_db(&base_dump, database, "Base")?;
+    let compare_sandbox_db = select_sandbox_db(&compare_dump, database, "Compare")?;
 
     // Database name for response (user-friendly name)
-    let db_name = query
-        .database
-        .clone()
+    let db_name = database
+        .map(str::to_string)
         .or_else(|| base_dump.sandbox_db_name.clone())
         .unwrap_or_else(|| "unknown".to_string());
 
     tracing::info!(
         "Database selection: selected={:?}, base_sandbox={}, compare_sandbox={}",
-        query.database,
+        database,
         base_sandbox_db,
         compare_sandbox_db
     );
 
     // Get schema info from both sandbox databases
     tracing::info!("Loading base schema from: {}", base_sandbox_db);
-    let base_schema = load_schema_graph(&state.config, &base_sandbox_db).await?;
+    let base_schema = load_schema_graph(state, &base_sandbox_db).await?;
     tracing::info!(
         "Base schema: {} tables, {} FKs",
         base_schema.tables.len(),
@@ -183,7 +433,7 @@ pub async fn compare_dumps(
     );
 
     tracing::info!("Loading compare schema from: {}", compare_sandbox_db);
-    let compare_schema = load_schema_graph(&state.config, &compare_sandbox_db).await?;
+    let compare_schema = load_schema_graph(state, &compare_sandbox_db).await?;
     tracing::info!(
         "Compare schema: {} tables, {} FKs",
         compare_schema.tables.len(),
@@ -191,17 +441,47 @@ pub async fn compare_dumps(
     );
 
     // Compare schemas
-    let mut diff = compare_schemas(&base_schema, &compare_schema);
+    let mut diff = compare_schemas_with(&base_schema, &compare_schema, options);
     tracing::info!(
         "Diff result: {} table diffs, {} FK diffs",
         diff.table_diffs.len(),
         diff.fk_diffs.len()
     );
 
+    if let Some(checksums) = checksums {
+        detect_data_changes(
+            state,
+            &base_sandbox_db,
+            &compare_sandbox_db,
+            &base_schema,
+            &compare_schema,
+            checksums,
+            &mut diff,
+        )
+        .await?;
+    }
+
+    Ok((db_name, diff))
+}
+
+/// Flag tables whose contents differ between the two sandbox databases
+///
+/// Compares per-table checksums of every table present in both dumps, marking
+/// existing table diffs and adding data-only diffs for unchanged schemas.
+/// Tables whose checksum fails (e.g. hits the timeout) are skipped.
+async fn detect_data_changes(
+    state: &AppState,
+    base_sandbox_db: &str,
+    compare_sandbox_db: &str,
+    base_schema: &SchemaGraph,
+    compare_schema: &SchemaGraph,
+    checksums: &ChecksumOptions,
+    diff: &mut SchemaDiff,
+) -> Result<(), ApiError> {
     // Check for data changes in tables that exist in both dumps
     // This detects content changes even when row count is the same
-    let base_pool = create_sandbox_pool(&state.config, &base_sandbox_db).await?;
-    let compare_pool = create_sandbox_pool(&state.config, &compare_sandbox_db).await?;
+    let base_pool = state.sandbox_pools.get(base_sandbox_db).await?;
+    let compare_pool = state.sandbox_pools.get(compare_sandbox_db).await?;
 
     // Build set of tables in both dumps (excluding added/removed)
     let base_tables: std::collections::HashSet<_> = base_schema
@@ -222,8 +502,8 @@ pub async fn compare_dumps(
 
     for (schema, table) in &common_tables {
         match (
-            calculate_table_checksum(&base_pool, schema, table).await,
-            calculate_table_checksum(&compare_pool, schema, table).await,
+            calculate_table_checksum(&base_pool, schema, table, checksums).await,
+            calculate_table_checksum(&compare_pool, schema, table, checksums).await,
         ) {
             (Ok(base_checksum), Ok(compare_checksum)) => {
                 if base_checksum != compare_checksum {
@@ -297,41 +577,94 @@ pub async fn compare_dumps(
         tables_with_data_changes.len()
     );
 
-    Ok(Json(SchemaDiffResponse {
-        base_dump_id: base_id,
-        compare_dump_id: compare_id,
-        database_name: db_name,
-        diff,
-    }))
+    Ok(())
+}
+
+/// How [`calculate_table_checksum`] hashes a table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ChecksumOptions {
+    /// Rows hashed per table; None hashes every row
+    sample_rows: Option<i64>,
+    /// `statement_timeout` for each checksum query (milliseconds)
+    timeout_ms: u64,
+}
+
+impl ChecksumOptions {
+    /// Options for a request, defaulting to the configured sample size
+    fn new(config: &AppConfig, sample_rows: Option<i64>, exact: bool) -> Result<Self, ApiError> {
+        let sample_rows = sample_rows.unwrap_or(config.diff_checksum_rows);
+        if !exact && sample_rows <= 0 {
+            return Err(ApiError::BadRequest(
+                "checksum_rows must be positive".to_string(),
+            ));
+        }
+        Ok(Self {
+            sample_rows: (!exact).then_some(sample_rows),
+            timeout_ms: config.diff_checksum_timeout_ms,
+        })
+    }
 }
 
-/// Calculate a checksum for all data in a table
-/// Uses PostgreSQL's md5 function to hash all row data
+/// Calculate a checksum of a table's data
+///
+/// Runs in its own transaction under the checksum statement timeout, so a
+/// huge table fails fast instead of stalling the comparison.
 async fn calculate_table_checksum(
     pool: &sqlx::PgPool,
     schema: &str,
     table: &str,
+    options: &ChecksumOptions,
 ) -> Result<Option<String>, ApiError> {
-    // Hash the first 10000 rows of data to detect changes
-    // This is efficient while still detecting most data changes
-    let query = format!(
-        r#"
+    let query = build_checksum_query(schema, table, options.sample_rows);
+
+    let mut tx = pool.begin().await?;
+    sqlx::query(&format!(
+        "SET LOCAL statement_timeout = {}",
+        options.timeout_ms
+    ))
+    .execute(&mut *tx)
+    .await?;
+    let result: Option<(Option<String>,)> = sqlx::query_as(&query).fetch_optional(&mut *tx).await?;
+    tx.commit().await?;
+
+    Ok(result.and_then(|(checksum,)| checksum))
+}
+
+/// Build the checksum query for [`calculate_table_checksum`]
+///
+/// With `sample_rows`, the MD5s of the first rows read are sorted and hashed
+/// together, which is quick but misses changes past the sample. Without it,
+/// every row is hashed and folded into a running sum of 64-bit hash prefixes
+/// plus the row count, which never holds more than one row in memory and
+/// does not depend on row order.
+fn build_checksum_query(schema: &str, table: &str, sample_rows: Option<i64>) -> String {
+    let relation = format!("{}.{}", quote_ident(schema), quote_ident(table));
+    match sample_rows {
+        Some(limit) => format!(
+            r#"
         SELECT md5(COALESCE(
             (SELECT string_agg(row_hash, '' ORDER BY row_hash)
              FROM (
                  SELECT md5(t::text) as row_hash
-                 FROM "{}"."{}" t
-                 LIMIT 10000
+                 FROM {} t
+                 LIMIT {}
              ) sub),
             ''
         )) as checksum
         "#,
-        schema, table
-    );
-
-    let result: Option<(Option<String>,)> = sqlx::query_as(&query).fetch_optional(pool).await?;
-
-    Ok(result.and_then(|(checksum,)| checksum))
+            relation, limit
+        ),
+        None => format!(
+            r#"
+        SELECT count(*)::text || ':' || COALESCE(
+            sum(('x' || left(md5(t::text), 16))::bit(64)::bigint::numeric),
+            0
+        )::text as checksum
+        FROM {} t
+        "#,
+            relation
+        ),
+    }
 }
 
 /// Internal dump record for validation
@@ -343,19 +676,69 @@ struct DumpRecord {
 }
 
 /// Get dump record from metadata DB
-async fn get_dump_record(pool: &PgPool, dump_id: Uuid) -> Result<DumpRecord, ApiError> {
+///
+/// Dumps of owners other than `owner` are reported as not found.
+async fn get_dump_record(
+    state: &AppState,
+    dump_id: Uuid,
+    owner: Option<&str>,
+) -> Result<DumpRecord, ApiError> {
     let record: Option<DumpRecord> = sqlx::query_as(
         r#"
         SELECT status, sandbox_db_name, sandbox_databases
         FROM dumps
-        WHERE id = $1
+        WHERE id = $1 AND ($2::text IS NULL OR owner = $2)
         "#,
     )
     .bind(dump_id)
-    .fetch_optional(pool)
+    .bind(owner)
+    .fetch_optional(&state.db_pool)
     .await?;
 
-    record.ok_or_else(|| ApiError::NotFound(format!("Dump {} not found", dump_id)))
+    let record = record.ok_or_else(|| ApiError::NotFound(format!("Dump {} not found", dump_id)))?;
+    reject_archived(state, dump_id, &record.status).await?;
+    record_view(&state.db_pool, dump_id).await;
+    Ok(record)
+}
+
+/// Sandbox database of a dump to compare
+///
+/// `label` names the dump in error messages ("Base" or "Compare").
+fn select_sandbox_db(
+    dump: &DumpRecord,
+    database: Option<&str>,
+    label: &str,
+) -> Result<String, ApiError> {
+    if let Some(selected_db) = database {
+        // User selected a specific database - find it in sandbox_databases
+        find_sandbox_db_for_original(dump, selected_db)
+            .or_else(|| {
+                // Fall back to sandbox_db_name if not found (legacy single-database dumps)
+                dump.sandbox_db_name.clone()
+            })
+            .ok_or_else(|| {
+                ApiError::BadRequest(format!(
+                    "Database {} not found in {} dump",
+                    selected_db,
+                    label.to_lowercase()
+                ))
+            })
+    } else {
+        // No database selected - use the dump's default sandbox_db_name or first from sandbox_databases
+        dump.sandbox_db_name
+            .clone()
+            .or_else(|| {
+                dump.sandbox_databases
+                    .as_ref()
+                    .and_then(|dbs| dbs.first().cloned())
+            })
+            .ok_or_else(|| {
+                ApiError::BadRequest(format!(
+                    "{} dump has no sandbox database. Please select a database.",
+                    label
+                ))
+            })
+    }
 }
 
 /// Find sandbox database name for a given original database name
@@ -380,22 +763,12 @@ fn find_sandbox_db_for_original(dump: &DumpRecord, original_db_name: &str) -> Op
 
 /// Load schema graph from a sandbox database
 async fn load_schema_graph(
-    config: &crate::config::AppConfig,
+    state: &AppState,
     sandbox_db_name: &str,
 ) -> Result<db_viewer_core::domain::SchemaGraph, ApiError> {
-    use db_viewer_core::domain::{ColumnInfo, SchemaGraph, TableInfo};
-
-    // Connect to sandbox database
-    let sandbox_url = format!(
-        "postgres://{}:{}@{}:{}/{}",
-        config.sandbox_user,
-        config.sandbox_password.as_deref().unwrap_or("postgres"),
-        config.sandbox_host,
-        config.sandbox_port,
-        sandbox_db_name
-    );
+    use db_viewer_core::domain::{ColumnInfo, EnumType, SchemaGraph, TableInfo};
 
-    let sandbox_pool = sqlx::PgPool::connect(&sandbox_url).await?;
+    let sandbox_pool = state.sandbox_pools.get(sandbox_db_name).await?;
 
     // Get tables with row counts
     let tables: Vec<(String, String, i64)> = sqlx::query_as(
@@ -409,18 +782,21 @@ async fn load_schema_graph(
         ORDER BY schemaname, relname
         "#,
     )
-    .fetch_all(&sandbox_pool)
+    .fetch_all(&*sandbox_pool)
     .await?;
 
     let mut table_infos = Vec::new();
 
     for (schema_name, table_name, row_count) in tables {
         // Get columns for this table
-        let columns: Vec<(String, String, bool, bool, Option<String>)> = sqlx::query_as(
+        let columns: Vec<ColumnQueryRow> = sqlx::query_as(
             r#"
             SELECT 
                 c.column_name::text,
-                c.data_type::text,
+                CASE WHEN c.data_type = 'USER-DEFINED'
+                    THEN format('%I.%I', c.udt_schema, c.udt_name)
+                    ELSE c.data_type::text
+                END as data_type,
                 c.is_nullable = 'YES' as is_nullable,
                 COALESCE(
                     EXISTS (
@@ -434,7 +810,11 @@ async fn load_schema_graph(
                     ),
                     false
                 ) as is_pk,
-                c.column_default::text
+                c.column_default::text,
+                c.ordinal_position::int,
+                c.is_identity = 'YES' as is_identity,
+                c.identity_generation::text,
+                c.generation_expression::text
             FROM information_schema.columns c
             WHERE c.table_schema = $1 AND c.table_name = $2
             ORDER BY c.ordinal_position
@@ -442,18 +822,33 @@ async fn load_schema_graph(
         )
         .bind(&schema_name)
         .bind(&table_name)
-        .fetch_all(&sandbox_pool)
+        .fetch_all(&*sandbox_pool)
         .await?;
 
         let column_infos: Vec<ColumnInfo> = columns
             .into_iter()
             .map(
-                |(name, data_type, is_nullable, is_pk, default_value)| ColumnInfo {
+                |(
+                    name,
+                    data_type,
+                    is_nullable,
+                    is_pk,
+                    default_value,
+                    position,
+                    is_identity,
+                    identity_generation,
+                    generation_expression,
+                )| ColumnInfo {
                     name,
                     data_type,
                     is_nullable,
                     is_primary_key: is_pk,
                     default_value,
+                    comment: None,
+                    ordinal_position: Some(position),
+                    is_identity,
+                    identity_generation,
+                    generation_expression,
                 },
             )
             .collect();
@@ -463,6 +858,10 @@ async fn load_schema_graph(
             table_name,
             estimated_row_count: row_count,
             columns: column_infos,
+            comment: None,
+            is_partition: false,
+            partition_of: None,
+            partition_strategy: None,
         });
     }
 
@@ -491,7 +890,7 @@ async fn load_schema_graph(
             AND tc.table_schema NOT IN ('pg_catalog', 'information_schema')
         "#,
     )
-    .fetch_all(&sandbox_pool)
+    .fetch_all(&*sandbox_pool)
     .await?;
 
     let foreign_keys: Vec<ForeignKey> = fk_rows
@@ -523,9 +922,42 @@ async fn load_schema_graph(
         )
         .collect();
 
+    // Get enum types so added/removed labels show up in the diff
+    let enum_rows: Vec<(String, String, Vec<String>)> = sqlx::query_as(
+        r#"
+        SELECT
+            n.nspname::text,
+            t.typname::text,
+            array_agg(e.enumlabel::text ORDER BY e.enumsortorder)
+        FROM pg_type t
+        JOIN pg_namespace n ON n.oid = t.typnamespace
+        JOIN pg_enum e ON e.enumtypid = t.oid
+        WHERE n.nspname NOT IN ('pg_catalog', 'information_schema')
+        GROUP BY n.nspname, t.typname
+        "#,
+    )
+    .fetch_all(&*sandbox_pool)
+    .await?;
+
+    let enum_types = enum_rows
+        .into_iter()
+        .map(|(schema, name, values)| EnumType {
+            schema,
+            name,
+            values,
+        })
+        .collect();
+
+    let sequences = db_viewer_core::adapter::postgres::fetch_sequences(&sandbox_pool).await?;
+
     Ok(SchemaGraph {
         tables: table_infos,
         foreign_keys,
+        indexes: vec![],
+        views: vec![],
+        triggers: vec![],
+        enum_types,
+        sequences,
     })
 }
 
@@ -550,16 +982,29 @@ pub struct TableDataDiffQuery {
     /// Database name within the dump (for multi-database dumps)
     #[serde(default)]
     pub database: Option<String>,
+    /// Page through both tables in primary key order and merge them instead
+    /// of comparing a fixed window of rows (only used when a PK exists)
+    #[serde(default)]
+    pub merge: bool,
+    /// Compare json/jsonb columns (and arrays of them) structurally instead
+    /// of by their exact JSON representation
+    #[serde(default)]
+    pub normalize_json: bool,
 }
 
 fn default_sample_limit() -> usize {
     100
 }
 
+/// Rows fetched per side and page by the primary key merge diff
+const MERGE_DIFF_PAGE_SIZE: usize = 1000;
+
 /// Single row difference
 #[derive(Debug, Serialize)]
 pub struct RowDiff {
-    /// The primary key value(s) for this row
+    /// Key of this row: the value itself for a single key column, otherwise
+    /// an object mapping each key column to its value (see
+    /// [`TableDataDiffResponse::primary_key_columns`] for the column order)
     pub pk: serde_json::Value,
     /// Type of change
     pub change_type: String,
@@ -571,14 +1016,35 @@ pub struct RowDiff {
     pub changed_columns: Vec<String>,
 }
 
+/// Where the columns matching rows between the two dumps came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffKeySource {
+    PrimaryKey,
+    UniqueConstraint,
+    UniqueIndex,
+    /// No key: rows are compared whole, so changes show as removed + added
+    AllColumns,
+}
+
 /// Response for table data diff
 #[derive(Debug, Serialize)]
 pub struct TableDataDiffResponse {
     pub base_dump_id: Uuid,
     pub compare_dump_id: Uuid,
+    /// Original database names when two databases of one dump are compared
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_database: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compare_database: Option<String>,
     pub schema_name: String,
     pub table_name: String,
+    /// Columns rows were matched on (the primary key unless `key_source`
+    /// says otherwise)
     pub primary_key_columns: Vec<String>,
+    pub key_source: DiffKeySource,
+    /// Name of the unique constraint or index used as the key
+    pub key_name: Option<String>,
     pub total_added: i64,
     pub total_removed: i64,
     pub total_modified: i64,
@@ -591,6 +1057,7 @@ pub struct TableDataDiffResponse {
 /// GET /api/dumps/:base_id/compare/:compare_id/table/:schema/:table
 pub async fn compare_table_data(
     State(state): State<AppState>,
+    owner: Owner,
     Path((base_id, compare_id, schema, table)): Path<(Uuid, Uuid, String, String)>,
     Query(query): Query<TableDataDiffQuery>,
 ) -> Result<Json<TableDataDiffResponse>, ApiError> {
@@ -603,8 +1070,8 @@ pub async fn compare_table_data(
     );
 
     // Get both dumps
-    let base_dump = get_dump_record(&state.db_pool, base_id).await?;
-    let compare_dump = get_dump_record(&state.db_pool, compare_id).await?;
+    let base_dump = get_dump_record(&state, base_id, owner.as_deref()).await?;
+    let compare_dump = get_dump_record(&state, compare_id, owner.as_deref()).await?;
 
     if base_dump.status != "READY" || compare_dump.status != "READY" {
         return Err(ApiError::BadRequest(
@@ -612,50 +1079,9 @@ pub async fn compare_table_data(
         ));
     }
 
-    // Determine which sandbox database to use
-    // Same logic as compare_dumps: if query.database is specified, find in sandbox_databases
-    let base_sandbox_db = if let Some(ref selected_db) = query.database {
-        find_sandbox_db_for_original(&base_dump, selected_db)
-            .or_else(|| base_dump.sandbox_db_name.clone())
-            .ok_or_else(|| {
-                ApiError::BadRequest(format!("Database {} not found in base dump", selected_db))
-            })?
-    } else {
-        base_dump
-            .sandbox_db_name
-            .clone()
-            .or_else(|| {
-                base_dump
-                    .sandbox_databases
-                    .as_ref()
-                    .and_then(|dbs| dbs.first().cloned())
-            })
-            .ok_or_else(|| ApiError::BadRequest("Base dump has no sandbox database".to_string()))?
-    };
-
-    let compare_sandbox_db = if let Some(ref selected_db) = query.database {
-        find_sandbox_db_for_original(&compare_dump, selected_db)
-            .or_else(|| compare_dump.sandbox_db_name.clone())
-            .ok_or_else(|| {
-                ApiError::BadRequest(format!(
-                    "Database {} not found in compare dump",
-                    selected_db
-                ))
-            })?
-    } else {
-        compare_dump
-            .sandbox_db_name
-            .clone()
-            .or_else(|| {
-                compare_dump
-                    .sandbox_databases
-                    .as_ref()
-                    .and_then(|dbs| dbs.first().cloned())
-            })
-            .ok_or_else(|| {
-                ApiError::BadRequest("Compare dump has no sandbox database".to_string())
-            })?
-    };
+    let base_sandbox_db = select_sandbox_db(&base_dump, query.database.as_deref(), "Base")?;
+    let compare_sandbox_db =
+        select_sandbox_db(&compare_dump, query.database.as_deref(), "Compare")?;
 
     tracing::info!(
         "compare_table_data: using base_sandbox={}, compare_sandbox={}",
@@ -663,51 +1089,202 @@ pub async fn compare_table_data(
         compare_sandbox_db
     );
 
+    let base = DiffSide {
+        dump_id: base_id,
+        sandbox_db: base_sandbox_db,
+    };
+    let compare = DiffSide {
+        dump_id: compare_id,
+        sandbox_db: compare_sandbox_db,
+    };
+    diff_table_data(&state, &base, &compare, schema, table, &query)
+        .await
+        .map(Json)
+}
+
+/// Get data diff for a specific table between two databases of one dump
+/// (e.g. `prod` and `staging` captured together by pg_dumpall)
+///
+/// GET /api/dumps/:id/databases/:base_db/compare/:compare_db/table/:schema/:table
+pub async fn compare_database_table_data(
+    State(state): State<AppState>,
+    owner: Owner,
+    Path((id, base_db, compare_db, schema, table)): Path<(Uuid, String, String, String, String)>,
+    Query(query): Query<TableDataDiffQuery>,
+) -> Result<Json<TableDataDiffResponse>, ApiError> {
+    tracing::info!(
+        "compare_database_table_data: dump={}, base_db={}, compare_db={}, table={}.{}",
+        id,
+        base_db,
+        compare_db,
+        schema,
+        table
+    );
+
+    let dump = get_dump_record(&state, id, owner.as_deref()).await?;
+    if dump.status != "READY" {
+        return Err(ApiError::BadRequest(
+            "Dump must be in READY state".to_string(),
+        ));
+    }
+    let (base, compare) = resolve_database_pair(id, &dump, &base_db, &compare_db)?;
+
+    let mut response = diff_table_data(&state, &base, &compare, schema, table, &query).await?;
+    response.base_database = Some(base_db);
+    response.compare_database = Some(compare_db);
+    Ok(Json(response))
+}
+
+/// Sandbox database of one side of a table data diff
+struct DiffSide {
+    dump_id: Uuid,
+    sandbox_db: String,
+}
+
+/// Sandbox databases holding two original databases of a dump
+fn resolve_database_pair(
+    id: Uuid,
+    dump: &DumpRecord,
+    base_db: &str,
+    compare_db: &str,
+) -> Result<(DiffSide, DiffSide), ApiError> {
+    if base_db == compare_db {
+        return Err(ApiError::BadRequest(
+            "Choose two different databases to compare".to_string(),
+        ));
+    }
+    let side = |name: &str| {
+        find_sandbox_db_for_original(dump, name)
+            .map(|sandbox_db| DiffSide {
+                dump_id: id,
+                sandbox_db,
+            })
+            .ok_or_else(|| ApiError::NotFound(format!("Database {} not found in dump", name)))
+    };
+    Ok((side(base_db)?, side(compare_db)?))
+}
+
+/// Diff a table's rows between two sandbox databases
+async fn diff_table_data(
+    state: &AppState,
+    base: &DiffSide,
+    compare: &DiffSide,
+    schema: String,
+    table: String,
+    query: &TableDataDiffQuery,
+) -> Result<TableDataDiffResponse, ApiError> {
+    let (base_sandbox_db, compare_sandbox_db) = (&base.sandbox_db, &compare.sandbox_db);
+
     // Connect to both sandbox databases
-    let base_pool = create_sandbox_pool(&state.config, &base_sandbox_db).await?;
-    let compare_pool = create_sandbox_pool(&state.config, &compare_sandbox_db).await?;
+    let base_pool = state.sandbox_pools.get(base_sandbox_db).await?;
+    let compare_pool = state.sandbox_pools.get(compare_sandbox_db).await?;
 
     // Get primary key columns
     let pk_columns = get_primary_key_columns(&base_pool, &schema, &table).await?;
 
     // Get all column names
-    let all_columns = get_table_columns(&base_pool, &schema, &table).await?;
+    let mut columns = get_table_columns(&base_pool, &schema, &table).await?;
+    if !query.normalize_json {
+        columns.json_columns.clear();
+    }
+    let all_columns = &columns.names;
+
+    // Without a PK, fall back to a unique constraint, then a unique index, as
+    // the natural key before resorting to whole-row comparison
+    let unique_constraints = if pk_columns.is_empty() {
+        PostgresAdapter::new((*base_pool).clone(), state.config.sandbox_connection())
+            .with_db_pool(base_sandbox_db, (*base_pool).clone())
+            .list_unique_constraints(base_sandbox_db, &schema, &table)
+            .await?
+    } else {
+        Vec::new()
+    };
+    let unique_index =
+        if pk_columns.is_empty() && !unique_constraints.iter().any(|c| c.all_not_null) {
+            get_unique_index(&base_pool, &schema, &table).await?
+        } else {
+            None
+        };
+
+    let key = choose_diff_key(&pk_columns, &unique_constraints, unique_index, all_columns);
+    let can_detect_modified = key.source != DiffKeySource::AllColumns;
+    if pk_columns.is_empty() {
+        if can_detect_modified {
+            tracing::info!(
+                "Table {}.{} has no primary key, using {:?} {} columns {:?} for comparison",
+                schema,
+                table,
+                key.source,
+                key.name.as_deref().unwrap_or_default(),
+                key.columns
+            );
+        } else {
+            tracing::info!(
+                "Table {}.{} has no primary key or unique key, using all columns for comparison",
+                schema,
+                table
+            );
+        }
+    }
+
+    let limit = query.limit.min(1000); // Cap at 1000 rows for output
 
-    // If no primary key, use all columns as the key for comparison
-    // This means we can only detect added/removed rows, not modified rows
-    let (key_columns, can_detect_modified) = if pk_columns.is_empty() {
+    if query.merge && !pk_columns.is_empty() {
+        let RowDiffResult {
+            rows,
+            total_added,
+            total_removed,
+            total_modified,
+        } = merge_diff_by_primary_key(
+            &base_pool,
+            &compare_pool,
+            &schema,
+            &table,
+            &columns,
+            &pk_columns,
+            limit,
+        )
+        .await?;
+
+        let truncated = rows.len() >= limit;
+
+        return Ok(TableDataDiffResponse {
+            base_dump_id: base.dump_id,
+            compare_dump_id: compare.dump_id,
+            base_database: None,
+            compare_database: None,
+            schema_name: schema,
+            table_name: table,
+            primary_key_columns: pk_columns,
+            key_source: DiffKeySource::PrimaryKey,
+            key_name: None,
+            total_added,
+            total_removed,
+            total_modified,
+            rows,
+            truncated,
+        });
+    }
+    if query.merge {
         tracing::info!(
-            "Table {}.{} has no primary key, using all columns for comparison",
+            "Table {}.{} has no primary key, falling back to windowed comparison",
             schema,
             table
         );
-        (all_columns.clone(), false)
-    } else {
-        (pk_columns.clone(), true)
-    };
-
-    let limit = query.limit.min(1000); // Cap at 1000 rows for output
+    }
 
-    // For tables without PK, we need to fetch more rows to detect differences accurately
-    // since we're comparing entire row contents
+    // For tables without a usable key, we need to fetch more rows to detect differences
+    // accurately since we're comparing entire row contents
     let fetch_limit = if can_detect_modified {
-        limit * 3 // With PK, we can be more selective
+        limit * 3 // With a key, we can be more selective
     } else {
-        10000 // Without PK, fetch more rows for accurate comparison
+        10000 // Without a key, fetch more rows for accurate comparison
     };
 
-    // For detecting changes, compare non-key columns (only meaningful if we have a real PK)
-    let non_pk_columns: Vec<_> = all_columns
-        .iter()
-        .filter(|c| !key_columns.contains(c))
-        .cloned()
-        .collect();
-
     // Query each table separately and compare in Rust
-    let base_rows =
-        fetch_table_rows(&base_pool, &schema, &table, &all_columns, fetch_limit).await?;
+    let base_rows = fetch_table_rows(&base_pool, &schema, &table, all_columns, fetch_limit).await?;
     let compare_rows =
-        fetch_table_rows(&compare_pool, &schema, &table, &all_columns, fetch_limit).await?;
+        fetch_table_rows(&compare_pool, &schema, &table, all_columns, fetch_limit).await?;
 
     tracing::info!(
         "compare_table_data: fetched {} base rows, {} compare rows (fetch_limit={})",
@@ -716,10 +1293,129 @@ pub async fn compare_table_data(
         fetch_limit
     );
 
+    let RowDiffResult {
+        rows,
+        total_added,
+        total_removed,
+        total_modified,
+    } = diff_rows(
+        &base_rows,
+        &compare_rows,
+        &key.columns,
+        &columns,
+        can_detect_modified,
+        limit,
+    );
+
+    let truncated = rows.len() >= limit;
+
+    Ok(TableDataDiffResponse {
+        base_dump_id: base.dump_id,
+        compare_dump_id: compare.dump_id,
+        base_database: None,
+        compare_database: None,
+        schema_name: schema,
+        table_name: table,
+        primary_key_columns: key.columns, // Return the actual key columns used
+        key_source: key.source,
+        key_name: key.name,
+        total_added,
+        total_removed,
+        total_modified,
+        rows,
+        truncated,
+    })
+}
+
+/// Columns used to match rows between the two dumps
+#[derive(Debug, PartialEq)]
+struct DiffKey {
+    source: DiffKeySource,
+    name: Option<String>,
+    columns: Vec<String>,
+}
+
+/// Pick the columns used to match rows between the two dumps.
+///
+/// Prefers the primary key, then the first unique constraint whose columns
+/// are all NOT NULL (see [`DbAdapter::list_unique_constraints`] for the
+/// order), then a unique index given as `(name, columns)`, and finally falls
+/// back to all columns. Unique columns allow any number of rows with NULLs,
+/// so a nullable key could match unrelated rows. Modified rows can only be
+/// detected when the key is narrower than the whole row.
+fn choose_diff_key(
+    pk_columns: &[String],
+    unique_constraints: &[UniqueConstraint],
+    unique_index: Option<(String, Vec<String>)>,
+    all_columns: &[String],
+) -> DiffKey {
+    if !pk_columns.is_empty() {
+        DiffKey {
+            source: DiffKeySource::PrimaryKey,
+            name: None,
+            columns: pk_columns.to_vec(),
+        }
+    } else if let Some(constraint) = unique_constraints.iter().find(|c| c.all_not_null) {
+        DiffKey {
+            source: DiffKeySource::UniqueConstraint,
+            name: Some(constraint.name.clone()),
+            columns: constraint.columns.clone(),
+        }
+    } else if let Some((name, columns)) = unique_index.filter(|(_, c)| !c.is_empty()) {
+        DiffKey {
+            source: DiffKeySource::UniqueIndex,
+            name: Some(name),
+            columns,
+        }
+    } else {
+        DiffKey {
+            source: DiffKeySource::AllColumns,
+            name: None,
+            columns: all_columns.to_vec(),
+        }
+    }
+}
+
+/// Columns of a diffed table
+#[derive(Debug, Default)]
+struct TableColumns {
+    /// Column names in table order
+    names: Vec<String>,
+    /// Columns compared structurally (see [`find_changed_columns`])
+    json_columns: HashSet<String>,
+}
+
+/// Outcome of comparing two sets of rows
+struct RowDiffResult {
+    rows: Vec<RowDiff>,
+    total_added: i64,
+    total_removed: i64,
+    total_modified: i64,
+}
+
+/// Compare base and compare rows keyed by `key_columns`
+///
+/// At most `limit` row diffs are returned, but the totals cover every row.
+fn diff_rows(
+    base_rows: &[serde_json::Value],
+    compare_rows: &[serde_json::Value],
+    key_columns: &[String],
+    columns: &TableColumns,
+    can_detect_modified: bool,
+    limit: usize,
+) -> RowDiffResult {
+    // For detecting changes, compare non-key columns (only meaningful if we have a real key)
+    let non_key_columns: Vec<_> = columns
+        .names
+        .iter()
+        .filter(|c| !key_columns.contains(c))
+        .cloned()
+        .collect();
+
     // Build maps by key columns
-    // For tables without PK, we use count maps to handle duplicate rows
-    let base_count_map = build_row_count_map(&base_rows, &key_columns);
-    let compare_count_map = build_row_count_map(&compare_rows, &key_columns);
+    // For tables without a key, we use count maps to handle duplicate rows
+    let base_count_map = build_row_count_map(base_rows, key_columns);
+    let compare_count_map = build_row_count_map(compare_rows, key_columns);
 
     tracing::info!(
         "compare_table_data: base_count_map has {} unique keys (from {} rows), compare_count_map has {} unique keys (from {} rows)",
@@ -734,7 +1430,6 @@ pub async fn compare_table_data(
     let mut total_removed: i64 = 0;
     let mut total_modified: i64 = 0;
 
-    // For tables without PK, compare counts to find added/removed rows
     // Find added rows: keys in compare that are not in base, or have higher count in compare
     for (key, (compare_count, compare_row)) in &compare_count_map {
         let base_count = base_count_map.get(key).map(|(c, _)| *c).unwrap_or(0);
@@ -773,9 +1468,14 @@ pub async fn compare_table_data(
                 }
             }
 
-            // Check if modified (only if we have a real PK to compare non-key columns)
-            if can_detect_modified && !non_pk_columns.is_empty() {
-                let changed_cols = find_changed_columns(base_row, compare_row, &non_pk_columns);
+            // Check if modified (only if we have a real key to compare non-key columns)
+            if can_detect_modified && !non_key_columns.is_empty() {
+                let changed_cols = find_changed_columns(
+                    base_row,
+                    compare_row,
+                    &non_key_columns,
+                    &columns.json_columns,
+                );
                 if !changed_cols.is_empty() {
                     total_modified += 1;
                     if rows.len() < limit {
@@ -805,36 +1505,187 @@ pub async fn compare_table_data(
         }
     }
 
-    let truncated = rows.len() >= limit;
-
-    Ok(Json(TableDataDiffResponse {
-        base_dump_id: base_id,
-        compare_dump_id: compare_id,
-        schema_name: schema,
-        table_name: table,
-        primary_key_columns: key_columns, // Return the actual key columns used
+    RowDiffResult {
+        rows,
         total_added,
         total_removed,
         total_modified,
-        rows,
-        truncated,
-    }))
+    }
 }
 
-/// Create a connection pool for a sandbox database
-async fn create_sandbox_pool(
-    config: &crate::config::AppConfig,
-    sandbox_db_name: &str,
-) -> Result<sqlx::PgPool, ApiError> {
-    let url = format!(
-        "postgres://{}:{}@{}:{}/{}",
-        config.sandbox_user,
-        config.sandbox_password.as_deref().unwrap_or("postgres"),
-        config.sandbox_host,
-        config.sandbox_port,
-        sandbox_db_name
+/// Diff two tables by walking both in primary key order
+///
+/// Each step reads the next page from the base side, then reads the compare
+/// side up to the last base key. If the compare page fills up first, the
+/// window shrinks to the last compare key and the base page is re-read. Both
+/// pages then cover exactly the same key range and are diffed with
+/// [`diff_rows`], so the whole table is compared without a fixed fetch cap
+/// while at most two pages are held in memory. Key comparisons are done by
+/// Postgres, so ordering always matches the column types and collations.
+async fn merge_diff_by_primary_key(
+    base_pool: &PgPool,
+    compare_pool: &PgPool,
+    schema: &str,
+    table: &str,
+    columns: &TableColumns,
+    pk_columns: &[String],
+    limit: usize,
+) -> Result<RowDiffResult, ApiError> {
+    let mut result = RowDiffResult {
+        rows: Vec::new(),
+        total_added: 0,
+        total_removed: 0,
+        total_modified: 0,
+    };
+    let mut after: Option<serde_json::Value> = None;
+
+    loop {
+        let mut base_page = fetch_key_range(
+            base_pool,
+            schema,
+            table,
+            &columns.names,
+            pk_columns,
+            after.as_ref(),
+            None,
+        )
+        .await?;
+        let mut upper = if base_page.len() == MERGE_DIFF_PAGE_SIZE {
+            base_page.last().cloned()
+        } else {
+            None
+        };
+
+        let compare_page = fetch_key_range(
+            compare_pool,
+            schema,
+            table,
+            &columns.names,
+            pk_columns,
+            after.as_ref(),
+            upper.as_ref(),
+        )
+        .await?;
+        if compare_page.len() == MERGE_DIFF_PAGE_SIZE {
+            upper = compare_page.last().cloned();
+            base_page = fetch_key_range(
+                base_pool,
+                schema,
+                table,
+                &columns.names,
+                pk_columns,
+                after.as_ref(),
+                upper.as_ref(),
+            )
+            .await?;
+        }
+
+        let window = diff_rows(
+            &base_page,
+            &compare_page,
+            pk_columns,
+            columns,
+            true,
+            limit.saturating_sub(result.rows.len()),
+        );
+        result.rows.extend(window.rows);
+        result.total_added += window.total_added;
+        result.total_removed += window.total_removed;
+        result.total_modified += window.total_modified;
+
+        match upper {
+            Some(bound) => after = Some(bound),
+            None => break,
+        }
+    }
+
+    Ok(result)
+}
+
+/// Fetch up to [`MERGE_DIFF_PAGE_SIZE`] rows ordered by primary key with
+/// `after < key <= upper`
+///
+/// Bounds are rows previously returned by `row_to_json` and are converted
+/// back to the table's row type on the server for comparison.
+async fn fetch_key_range(
+    pool: &PgPool,
+    schema: &str,
+    table: &str,
+    columns: &[String],
+    pk_columns: &[String],
+    after: Option<&serde_json::Value>,
+    upper: Option<&serde_json::Value>,
+) -> Result<Vec<serde_json::Value>, ApiError> {
+    let query = build_key_range_query(
+        schema,
+        table,
+        columns,
+        pk_columns,
+        after.is_some(),
+        upper.is_some(),
+        MERGE_DIFF_PAGE_SIZE,
     );
-    Ok(sqlx::PgPool::connect(&url).await?)
+
+    let mut q = sqlx::query_as::<_, (serde_json::Value,)>(&query);
+    if let Some(after) = after {
+        q = q.bind(sqlx::types::Json(after));
+    }
+    if let Some(upper) = upper {
+        q = q.bind(sqlx::types::Json(upper));
+    }
+    let rows = q.fetch_all(pool).await?;
+
+    Ok(rows.into_iter().map(|(v,)| v).collect())
+}
+
+/// Build the keyset query used by [`fetch_key_range`]
+fn build_key_range_query(
+    schema: &str,
+    table: &str,
+    columns: &[String],
+    pk_columns: &[String],
+    has_after: bool,
+    has_upper: bool,
+    limit: usize,
+) -> String {
+    let relation = format!("{}.{}", quote_ident(schema), quote_ident(table));
+    let cols = columns
+        .iter()
+        .map(|c| quote_ident(c))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let key = pk_columns
+        .iter()
+        .map(|c| quote_ident(c))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let bound_key = pk_columns
+        .iter()
+        .map(|c| format!("b.{}", quote_ident(c)))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let mut conditions = Vec::new();
+    let mut param = 0;
+    for (enabled, op) in [(has_after, ">"), (has_upper, "<=")] {
+        if enabled {
+            param += 1;
+            conditions.push(format!(
+                "ROW({}) {} (SELECT ROW({}) FROM jsonb_populate_record(NULL::{}, ${}) b)",
+                key, op, bound_key, relation, param
+            ));
+        }
+    }
+    let where_clause = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!(" WHERE {}", conditions.join(" AND "))
+    };
+
+    format!(
+        "SELECT row_to_json(t) FROM (SELECT {} FROM {}{} ORDER BY {} LIMIT {}) t",
+        cols, relation, where_clause, key, limit
+    )
 }
 
 /// Get primary key columns for a table
@@ -864,15 +1715,62 @@ async fn get_primary_key_columns(
     Ok(rows.into_iter().map(|(c,)| c).collect())
 }
 
+/// Get the columns of the best unique index usable as a natural key
+///
+/// Partial and expression indexes are skipped, as are indexes with a
+/// nullable column (NULLs never collide in a unique index). The narrowest
+/// index wins. Returns `None` if no suitable index exists.
+async fn get_unique_index(
+    pool: &sqlx::PgPool,
+    schema: &str,
+    table: &str,
+) -> Result<Option<(String, Vec<String>)>, ApiError> {
+    let rows: Vec<(String, Vec<String>)> = sqlx::query_as(
+        r#"
+        SELECT
+            ic.relname::text AS index_name,
+            array_agg(a.attname::text ORDER BY k.ord) AS columns
+        FROM pg_index i
+        JOIN pg_class c ON c.oid = i.indrelid
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        JOIN pg_class ic ON ic.oid = i.indexrelid
+        CROSS JOIN LATERAL unnest(i.indkey::int2[]) WITH ORDINALITY AS k(attnum, ord)
+        JOIN pg_attribute a ON a.attrelid = c.oid AND a.attnum = k.attnum
+        WHERE i.indisunique
+            AND NOT i.indisprimary
+            AND i.indpred IS NULL
+            AND i.indexprs IS NULL
+            AND n.nspname = $1
+            AND c.relname = $2
+        GROUP BY ic.relname
+        HAVING bool_and(a.attnotnull)
+        ORDER BY count(*) ASC, ic.relname ASC
+        LIMIT 1
+        "#,
+    )
+    .bind(schema)
+    .bind(table)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().next())
+}
+
 /// Get all column names for a table
 async fn get_table_columns(
     pool: &sqlx::PgPool,
     schema: &str,
     table: &str,
-) -> Result<Vec<String>, ApiError> {
-    let rows: Vec<(String,)> = sqlx::query_as(
+) -> Result<TableColumns, ApiError> {
+    // Arrays are reported as `ARRAY`; spell out the element type from the
+    // `_element` udt name
+    let rows: Vec<(String, String)> = sqlx::query_as(
         r#"
-        SELECT column_name::text
+        SELECT column_name::text,
+               CASE WHEN data_type = 'ARRAY'
+                   THEN substr(udt_name::text, 2) || '[]'
+                   ELSE data_type::text
+               END
         FROM information_schema.columns
         WHERE table_schema = $1 AND table_name = $2
         ORDER BY ordinal_position
@@ -883,7 +1781,14 @@ async fn get_table_columns(
     .fetch_all(pool)
     .await?;
 
-    Ok(rows.into_iter().map(|(c,)| c).collect())
+    let mut columns = TableColumns::default();
+    for (name, data_type) in rows {
+        if is_json_type(&NormalizedType::from_pg_type(&data_type, &[])) {
+            columns.json_columns.insert(name.clone());
+        }
+        columns.names.push(name);
+    }
+    Ok(columns)
 }
 
 /// Fetch rows from a table as JSON
@@ -896,13 +1801,16 @@ async fn fetch_table_rows(
 ) -> Result<Vec<serde_json::Value>, ApiError> {
     let cols = columns
         .iter()
-        .map(|c| format!("\"{}\"", c))
+        .map(|c| quote_ident(c))
         .collect::<Vec<_>>()
         .join(", ");
 
     let query = format!(
-        "SELECT row_to_json(t) FROM (SELECT {} FROM \"{}\".\"{}\" LIMIT {}) t",
-        cols, schema, table, limit
+        "SELECT row_to_json(t) FROM (SELECT {} FROM {}.{} LIMIT {}) t",
+        cols,
+        quote_ident(schema),
+        quote_ident(table),
+        limit
     );
 
     let rows: Vec<(serde_json::Value,)> = sqlx::query_as(&query).fetch_all(pool).await?;
@@ -927,10 +1835,15 @@ fn build_row_count_map(
                     .cloned()
                     .unwrap_or(serde_json::Value::Null)
             } else {
-                serde_json::json!(key_columns
-                    .iter()
-                    .map(|c| obj.get(c).cloned().unwrap_or(serde_json::Value::Null))
-                    .collect::<Vec<_>>())
+                serde_json::Value::Object(
+                    key_columns
+                        .iter()
+                        .map(|c| {
+                            let value = obj.get(c).cloned().unwrap_or(serde_json::Value::Null);
+                            (c.clone(), value)
+                        })
+                        .collect(),
+                )
             };
 
             map.entry(key_value)
@@ -943,10 +1856,14 @@ fn build_row_count_map(
 }
 
 /// Find which columns have changed between two row values
+///
+/// Values of `json_columns` are compared with [`json_equivalent`], all
+/// others strictly.
 fn find_changed_columns(
     base: &serde_json::Value,
     compare: &serde_json::Value,
     non_pk_columns: &[String],
+    json_columns: &HashSet<String>,
 ) -> Vec<String> {
     let mut changed = Vec::new();
 
@@ -954,7 +1871,11 @@ fn find_changed_columns(
         for col in non_pk_columns {
             let base_val = base_obj.get(col);
             let compare_val = compare_obj.get(col);
-            if base_val != compare_val {
+            let equal = match (base_val, compare_val) {
+                (Some(a), Some(b)) if json_columns.contains(col) => json_equivalent(a, b),
+                _ => base_val == compare_val,
+            };
+            if !equal {
                 changed.push(col.clone());
             }
         }
@@ -963,6 +1884,39 @@ fn find_changed_columns(
     changed
 }
 
+/// Whether a column holds JSON documents: `json`, `jsonb` or arrays of them
+fn is_json_type(ty: &NormalizedType) -> bool {
+    match ty {
+        NormalizedType::Json => true,
+        NormalizedType::Array(element) => is_json_type(element),
+        _ => false,
+    }
+}
+
+/// Structural equality of JSON documents
+///
+/// Objects match regardless of key order and numbers by value (`1.0` equals
+/// `1`); array elements must match in order.
+fn json_equivalent(a: &serde_json::Value, b: &serde_json::Value) -> bool {
+    use serde_json::Value;
+
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => match (x.as_i64(), y.as_i64()) {
+            (Some(x), Some(y)) => x == y,
+            _ => x.as_f64() == y.as_f64(),
+        },
+        (Value::Array(x), Value::Array(y)) => {
+            x.len() == y.len() && x.iter().zip(y).all(|(x, y)| json_equivalent(x, y))
+        }
+        (Value::Object(x), Value::Object(y)) => {
+            x.len() == y.len()
+                && x.iter()
+                    .all(|(key, x)| y.get(key).is_some_and(|y| json_equivalent(x, y)))
+        }
+        _ => a == b,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -971,5 +1925,452 @@ mod tests {
     fn test_diff_query_defaults() {
         let query: DiffQuery = serde_json::from_str("{}").unwrap();
         assert!(query.database.is_none());
+        assert!(query.checksum_rows.is_none());
+        assert!(!query.exact);
+    }
+
+    #[test]
+    fn test_build_checksum_query() {
+        let sampled = build_checksum_query("public", "users", Some(10000));
+        assert!(sampled.contains(r#"FROM "public"."users" t"#));
+        assert!(sampled.contains("LIMIT 10000"));
+
+        let exact = build_checksum_query("public", "users", None);
+        assert!(!exact.contains("LIMIT"));
+        assert!(!exact.contains("string_agg"));
+        assert!(exact.contains("count(*)"));
+    }
+
+    #[test]
+    fn test_live_connect_options_allowlist() {
+        let config = AppConfig {
+            live_compare_allowed_hosts: vec!["replica.internal".to_string()],
+            live_connections: vec![(
+                "prod".to_string(),
+                "postgres://viewer:pw@prod.internal:5433/app".to_string(),
+            )],
+            ..crate::config::tests::test_config()
+        };
+        let request = |url: Option<&str>, connection: Option<&str>| CompareLiveRequest {
+            url: url.map(str::to_string),
+            connection: connection.map(str::to_string),
+            database: None,
+            detect_reorder: false,
+        };
+
+        let options = live_connect_options(
+            &config,
+            &request(Some("postgres://u:p@REPLICA.internal/app"), None),
+        )
+        .unwrap();
+        assert_eq!(options.get_database(), Some("app"));
+
+        // Named connections are trusted configuration
+        let options = live_connect_options(&config, &request(None, Some("prod"))).unwrap();
+        assert_eq!(options.get_host(), "prod.internal");
+        assert_eq!(options.get_port(), 5433);
+
+        for (url, connection) in [
+            (Some("postgres://u:p@169.254.169.254/app"), None),
+            (Some("postgres://u:p@replica.internal.evil.com/app"), None),
+            (Some("not a url"), None),
+            (None, Some("staging")),
+            (None, None),
+            (Some("postgres://u:p@replica.internal/app"), Some("prod")),
+        ] {
+            assert!(matches!(
+                live_connect_options(&config, &request(url, connection)),
+                Err(ApiError::BadRequest(_))
+            ));
+        }
+    }
+
+    /// Requires a sandbox server in `TEST_SANDBOX_URL`
+    #[tokio::test]
+    #[ignore = "requires TEST_SANDBOX_URL"]
+    async fn test_exact_checksum_detects_change_past_sample() {
+        let url = std::env::var("TEST_SANDBOX_URL").expect("TEST_SANDBOX_URL is required");
+        let pool = PgPool::connect(&url).await.unwrap();
+        for schema in ["checksum_base", "checksum_compare"] {
+            sqlx::query(&format!("DROP SCHEMA IF EXISTS {} CASCADE", schema))
+                .execute(&pool)
+                .await
+                .unwrap();
+            sqlx::query(&format!("CREATE SCHEMA {}", schema))
+                .execute(&pool)
+                .await
+                .unwrap();
+            sqlx::query(&format!(
+                "CREATE TABLE {}.items AS SELECT g AS id, 'item ' || g AS name FROM generate_series(1, 10001) g",
+                schema
+            ))
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        sqlx::query("UPDATE checksum_compare.items SET name = 'changed' WHERE id = 10001")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let checksum = |schema: &'static str, sample_rows: Option<i64>| {
+            let pool = pool.clone();
+            async move {
+                let options = ChecksumOptions {
+                    sample_rows,
+                    timeout_ms: 30_000,
+                };
+                calculate_table_checksum(&pool, schema, "items", &options)
+                    .await
+                    .unwrap()
+            }
+        };
+        let exact_base = checksum("checksum_base", None).await;
+        let exact_compare = checksum("checksum_compare", None).await;
+
+        for schema in ["checksum_base", "checksum_compare"] {
+            sqlx::query(&format!("DROP SCHEMA {} CASCADE", schema))
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+
+        assert!(exact_base.is_some());
+        assert_ne!(exact_base, exact_compare);
+    }
+
+    fn cols(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn plain(names: &[String]) -> TableColumns {
+        TableColumns {
+            names: names.to_vec(),
+            json_columns: HashSet::new(),
+        }
+    }
+
+    #[test]
+    fn test_table_data_diff_query_defaults() {
+        let query: TableDataDiffQuery = serde_json::from_str("{}").unwrap();
+        assert_eq!(query.limit, 100);
+        assert!(!query.merge);
+        assert!(!query.normalize_json);
+    }
+
+    #[test]
+    fn test_build_key_range_query_without_bounds() {
+        let sql = build_key_range_query(
+            "public",
+            "order_items",
+            &cols(&["order_id", "line", "qty"]),
+            &cols(&["order_id", "line"]),
+            false,
+            false,
+            1000,
+        );
+        assert_eq!(
+            sql,
+            "SELECT row_to_json(t) FROM (SELECT \"order_id\", \"line\", \"qty\" \
+             FROM \"public\".\"order_items\" ORDER BY \"order_id\", \"line\" LIMIT 1000) t"
+        );
+    }
+
+    #[test]
+    fn test_build_key_range_query_with_bounds() {
+        let sql = build_key_range_query(
+            "public",
+            "order_items",
+            &cols(&["order_id", "line", "qty"]),
+            &cols(&["order_id", "line"]),
+            true,
+            true,
+            1000,
+        );
+        assert!(sql.contains(
+            "WHERE ROW(\"order_id\", \"line\") > (SELECT ROW(b.\"order_id\", b.\"line\") \
+             FROM jsonb_populate_record(NULL::\"public\".\"order_items\", $1) b)"
+        ));
+        assert!(sql.contains(
+            "AND ROW(\"order_id\", \"line\") <= (SELECT ROW(b.\"order_id\", b.\"line\") \
+             FROM jsonb_populate_record(NULL::\"public\".\"order_items\", $2) b)"
+        ));
+
+        let upper_only = build_key_range_query(
+            "public",
+            "order_items",
+            &cols(&["order_id", "line", "qty"]),
+            &cols(&["order_id", "line"]),
+            false,
+            true,
+            1000,
+        );
+        assert!(upper_only.contains("<= (SELECT"));
+        assert!(upper_only.contains("$1"));
+        assert!(!upper_only.contains("$2"));
+    }
+
+    fn unique_constraint(name: &str, columns: &[&str]) -> UniqueConstraint {
+        UniqueConstraint {
+            name: name.to_string(),
+            columns: cols(columns),
+            all_not_null: true,
+        }
+    }
+
+    #[test]
+    fn test_choose_diff_key_prefers_pk() {
+        let key = choose_diff_key(
+            &cols(&["id"]),
+            &[unique_constraint("users_email_key", &["email"])],
+            Some(("users_email_idx".to_string(), cols(&["email"]))),
+            &cols(&["id", "email"]),
+        );
+        assert_eq!(key.source, DiffKeySource::PrimaryKey);
+        assert_eq!(key.columns, cols(&["id"]));
+        assert_eq!(key.name, None);
+    }
+
+    #[test]
+    fn test_choose_diff_key_prefers_unique_constraint_over_index() {
+        let key = choose_diff_key(
+            &[],
+            &[unique_constraint("users_email_key", &["email"])],
+            Some(("users_name_idx".to_string(), cols(&["name"]))),
+            &cols(&["email", "name"]),
+        );
+        assert_eq!(
+            key,
+            DiffKey {
+                source: DiffKeySource::UniqueConstraint,
+                name: Some("users_email_key".to_string()),
+                columns: cols(&["email"]),
+            }
+        );
+    }
+
+    #[test]
+    fn test_choose_diff_key_skips_nullable_unique_constraints() {
+        let nullable = UniqueConstraint {
+            all_not_null: false,
+            ..unique_constraint("users_nickname_key", &["nickname"])
+        };
+        let key = choose_diff_key(
+            &[],
+            &[
+                nullable.clone(),
+                unique_constraint("users_email_key", &["email"]),
+            ],
+            None,
+            &cols(&["email", "nickname"]),
+        );
+        assert_eq!(key.name.as_deref(), Some("users_email_key"));
+
+        let key = choose_diff_key(
+            &[],
+            std::slice::from_ref(&nullable),
+            Some(("users_email_idx".to_string(), cols(&["email"]))),
+            &cols(&["email", "nickname"]),
+        );
+        assert_eq!(key.source, DiffKeySource::UniqueIndex);
+
+        let key = choose_diff_key(&[], &[nullable], None, &cols(&["email", "nickname"]));
+        assert_eq!(key.source, DiffKeySource::AllColumns);
+    }
+
+    #[test]
+    fn test_choose_diff_key_falls_back_to_all_columns() {
+        let key = choose_diff_key(&[], &[], None, &cols(&["a", "b"]));
+        assert_eq!(key.source, DiffKeySource::AllColumns);
+        assert_eq!(key.columns, cols(&["a", "b"]));
+    }
+
+    #[test]
+    fn test_diff_rows_unique_constraint_detects_modified() {
+        // Table has no PK, only a UNIQUE (tenant, email) constraint
+        let all_columns = cols(&["tenant", "email", "name"]);
+        let key = choose_diff_key(
+            &[],
+            &[unique_constraint(
+                "users_tenant_email_key",
+                &["tenant", "email"],
+            )],
+            None,
+            &all_columns,
+        );
+        assert_eq!(key.source, DiffKeySource::UniqueConstraint);
+
+        let base = vec![
+            serde_json::json!({"tenant": 1, "email": "a@example.com", "name": "Alice"}),
+            serde_json::json!({"tenant": 2, "email": "a@example.com", "name": "Ann"}),
+        ];
+        let compare = vec![
+            serde_json::json!({"tenant": 1, "email": "a@example.com", "name": "Alicia"}),
+            serde_json::json!({"tenant": 2, "email": "a@example.com", "name": "Ann"}),
+        ];
+
+        let result = diff_rows(
+            &base,
+            &compare,
+            &key.columns,
+            &plain(&all_columns),
+            true,
+            100,
+        );
+
+        assert_eq!(result.total_modified, 1);
+        assert_eq!(result.total_added, 0);
+        assert_eq!(result.total_removed, 0);
+        assert_eq!(
+            result.rows[0].pk,
+            serde_json::json!({"tenant": 1, "email": "a@example.com"})
+        );
+        assert_eq!(result.rows[0].changed_columns, cols(&["name"]));
+    }
+
+    #[test]
+    fn test_diff_rows_unique_index_detects_modified() {
+        // Table has no PK, only a UNIQUE index on email
+        let all_columns = cols(&["email", "name"]);
+        let key = choose_diff_key(
+            &[],
+            &[],
+            Some(("users_email_idx".to_string(), cols(&["email"]))),
+            &all_columns,
+        );
+        let key_columns = key.columns;
+        let can_modify = key.source != DiffKeySource::AllColumns;
+        assert!(can_modify);
+
+        let base = vec![
+            serde_json::json!({"email": "a@example.com", "name": "Alice"}),
+            serde_json::json!({"email": "b@example.com", "name": "Bob"}),
+        ];
+        let compare = vec![
+            serde_json::json!({"email": "a@example.com", "name": "Alicia"}),
+            serde_json::json!({"email": "b@example.com", "name": "Bob"}),
+        ];
+
+        let result = diff_rows(
+            &base,
+            &compare,
+            &key_columns,
+            &plain(&all_columns),
+            can_modify,
+            100,
+        );
+
+        assert_eq!(result.total_modified, 1);
+        assert_eq!(result.total_added, 0);
+        assert_eq!(result.total_removed, 0);
+        assert_eq!(result.rows.len(), 1);
+        assert_eq!(result.rows[0].change_type, "modified");
+        assert_eq!(result.rows[0].pk, serde_json::json!("a@example.com"));
+        assert_eq!(result.rows[0].changed_columns, cols(&["name"]));
+    }
+
+    #[test]
+    fn test_diff_rows_composite_key_is_object() {
+        let all_columns = cols(&["order_id", "line", "qty"]);
+        let key_columns = cols(&["order_id", "line"]);
+        let base = vec![
+            serde_json::json!({"order_id": 7, "line": 1, "qty": 2}),
+            serde_json::json!({"order_id": 7, "line": 2, "qty": 1}),
+        ];
+        let compare = vec![serde_json::json!({"order_id": 7, "line": 1, "qty": 5})];
+
+        let mut result = diff_rows(
+            &base,
+            &compare,
+            &key_columns,
+            &plain(&all_columns),
+            true,
+            100,
+        );
+        result
+            .rows
+            .sort_by(|a, b| a.change_type.cmp(&b.change_type));
+
+        assert_eq!(result.rows.len(), 2);
+        assert_eq!(result.rows[0].change_type, "modified");
+        assert_eq!(
+            result.rows[0].pk,
+            serde_json::json!({"order_id": 7, "line": 1})
+        );
+        assert_eq!(result.rows[1].change_type, "removed");
+        assert_eq!(
+            result.rows[1].pk,
+            serde_json::json!({"order_id": 7, "line": 2})
+        );
+    }
+
+    #[test]
+    fn test_json_columns_compare_structurally() {
+        let columns = TableColumns {
+            names: cols(&["id", "payload", "tags", "price"]),
+            json_columns: ["payload".to_string(), "tags".to_string()].into(),
+        };
+        let base = vec![serde_json::from_str::<serde_json::Value>(
+            r#"{"id": 1, "payload": {"b": [1, 2], "a": {"y": 1.0, "x": null}},
+                "tags": [{"k": 1, "v": 2}], "price": 1.0}"#,
+        )
+        .unwrap()];
+        let compare = vec![serde_json::from_str::<serde_json::Value>(
+            r#"{"id": 1, "payload": {"a": {"x": null, "y": 1}, "b": [1, 2]},
+                "tags": [{"v": 2, "k": 1}], "price": 1}"#,
+        )
+        .unwrap()];
+
+        let result = diff_rows(&base, &compare, &cols(&["id"]), &columns, true, 100);
+
+        // Only the scalar column keeps strict comparison
+        assert_eq!(result.total_modified, 1);
+        assert_eq!(result.rows[0].changed_columns, cols(&["price"]));
+    }
+
+    #[test]
+    fn test_json_equivalent() {
+        let value = |s: &str| serde_json::from_str::<serde_json::Value>(s).unwrap();
+        assert!(json_equivalent(
+            &value(r#"{"a": 1, "b": {"c": 2, "d": 3}}"#),
+            &value(r#"{"b": {"d": 3, "c": 2}, "a": 1}"#)
+        ));
+        assert!(!json_equivalent(&value("[1, 2]"), &value("[2, 1]")));
+        assert!(!json_equivalent(
+            &value(r#"{"a": 1}"#),
+            &value(r#"{"a": 1, "b": null}"#)
+        ));
+        assert!(json_equivalent(&value("1e2"), &value("100")));
+    }
+
+    #[test]
+    fn test_resolve_database_pair() {
+        let id = Uuid::new_v4();
+        let dump = DumpRecord {
+            status: "READY".to_string(),
+            sandbox_db_name: None,
+            sandbox_databases: Some(vec![
+                format!("sandbox_{}_prod", id.simple()),
+                format!("sandbox_{}_staging", id.simple()),
+            ]),
+        };
+
+        let (base, compare) = resolve_database_pair(id, &dump, "prod", "staging").unwrap();
+        assert_eq!(base.dump_id, id);
+        assert_eq!(compare.dump_id, id);
+        assert_eq!(base.sandbox_db, format!("sandbox_{}_prod", id.simple()));
+        assert_eq!(
+            compare.sandbox_db,
+            format!("sandbox_{}_staging", id.simple())
+        );
+
+        assert!(matches!(
+            resolve_database_pair(id, &dump, "prod", "dev"),
+            Err(ApiError::NotFound(_))
+        ));
+        assert!(matches!(
+            resolve_database_pair(id, &dump, "prod", "prod"),
+            Err(ApiError::BadRequest(_))
+        ));
     }
 }
@@ -1,19 +1,37 @@
 //! Dump management handlers
 
 use axum::{
-    extract::{Multipart, Path, State},
-    Json,
+    body::Body,
+    extract::{Multipart, Path, Query, State},
+    http::{
+        header::{self, CONTENT_RANGE},
+        HeaderMap,
+    },
+    response::sse::{Event, KeepAlive, Sse},
+    response::{IntoResponse, Response},
+    Extension, Json,
 };
 use chrono::{Duration, Utc};
+use futures::{stream, Stream, StreamExt};
 use serde::{Deserialize, Serialize};
 use sqlx::Row;
+use std::convert::Infallible;
+use tokio::io::{AsyncWriteExt, BufWriter};
 use uuid::Uuid;
 
+use crate::auth::{Identity, Owner};
 use crate::error::{ApiError, ApiResult};
+use crate::routes::MAX_UPLOAD_SIZE;
 use crate::state::AppState;
-use db_viewer_core::adapter::DbAdapter;
 use db_viewer_core::adapter::TablePreview;
-use db_viewer_core::domain::{Dump, DumpStatus};
+use db_viewer_core::adapter::{validate_dump_header, DbAdapter};
+use db_viewer_core::domain::{Dump, DumpFormat, DumpLayout, DumpProgress, DumpStatus};
+use db_viewer_core::dump_files::assemble_upload;
+use db_viewer_core::masking::{mask_statements, ColumnMask};
+use db_viewer_core::CoreError;
+
+/// How often [`dump_events`] checks the dump for changes
+const DUMP_EVENTS_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
 
 /// Create dump request
 #[derive(Debug, Deserialize)]
@@ -33,52 +51,55 @@ pub struct CreateDumpResponse {
     pub upload_url: String,
 }
 
-/// Create a new dump session
+/// Create a new dump session, owned by the caller's identity
 pub async fn create_dump(
     State(state): State<AppState>,
+    identity: Option<Extension<Identity>>,
     Json(req): Json<CreateDumpRequest>,
 ) -> ApiResult<Json<CreateDumpResponse>> {
     let id = Uuid::new_v4();
     let now = Utc::now();
     let expires_at = now + Duration::days(state.config.ttl_days as i64);
 
-    // Generate slug
-    let slug = match req.slug {
-        Some(s) => slugify(&s),
-        None => generate_short_id(),
-    };
-
-    // Check slug uniqueness
-    let existing: Option<(Uuid,)> =
-        sqlx::query_as("SELECT id FROM dumps WHERE slug = $1 AND status != 'DELETED'")
-            .bind(&slug)
-            .fetch_optional(&state.db_pool)
-            .await?;
-
-    if existing.is_some() {
-        return Err(ApiError::Conflict(format!(
-            "Slug '{}' already exists",
-            slug
-        )));
-    }
-
-    // Insert dump record
+    let requested_slug = req.slug.as_deref().map(slugify);
     let is_private = req.is_private.unwrap_or(true);
-    sqlx::query(
-        r#"
-        INSERT INTO dumps (id, slug, name, status, created_at, updated_at, expires_at, is_private)
-        VALUES ($1, $2, $3, $4, $5, $5, $6, $7)
-        "#,
-    )
-    .bind(id)
-    .bind(&slug)
-    .bind(&req.name)
-    .bind(DumpStatus::Created.as_str())
-    .bind(now)
-    .bind(expires_at)
-    .bind(is_private)
-    .execute(&state.db_pool)
-    .await?;
+    let owner = identity.map(|Extension(Identity(owner))| owner);
+
+    // Insert the dump record under the first free slug. The unique index on
+    // `slug` arbitrates between concurrent creates: a taken slug inserts
+    // nothing and the next candidate is tried.
+    let mut slug = None;
+    for attempt in 0..SLUG_ATTEMPTS {
+        let candidate = slug_candidate(requested_slug.as_deref(), attempt);
+        let inserted: Option<(Uuid,)> = sqlx::query_as(
+            r#"
+            INSERT INTO dumps (id, slug, name, status, created_at, updated_at, expires_at, is_private, owner)
+            VALUES ($1, $2, $3, $4, $5, $5, $6, $7, $8)
+            ON CONFLICT (slug) DO NOTHING
+            RETURNING id
+            "#,
+        )
+        .bind(id)
+        .bind(&candidate)
+        .bind(&req.name)
+        .bind(DumpStatus::Created.as_str())
+        .bind(now)
+        .bind(expires_at)
+        .bind(is_private)
+        .bind(&owner)
+        .fetch_optional(&state.db_pool)
+        .await?;
+        if inserted.is_some() {
+            slug = Some(candidate);
+            break;
+        }
+    }
+    let slug = slug.ok_or_else(|| {
+        ApiError::Conflict(match &requested_slug {
+            Some(requested) => format!("Slug '{}' already exists", requested),
+            None => "Could not generate a unique slug".to_string(),
+        })
+    })?;
 
     Ok(Json(CreateDumpResponse {
         id,
@@ -87,17 +108,22 @@ pub async fn create_dump(
     }))
 }
 
-/// List all dumps
-pub async fn list_dumps(State(state): State<AppState>) -> ApiResult<Json<Vec<DumpSummary>>> {
+/// List public dumps visible to the caller
+pub async fn list_dumps(
+    State(state): State<AppState>,
+    owner: Owner,
+) -> ApiResult<Json<Vec<DumpSummary>>> {
     let rows = sqlx::query(
         r#"
         SELECT id, slug, name, status, file_size, created_at, expires_at
         FROM dumps
         WHERE status != 'DELETED' AND is_private = false
+          AND ($1::text IS NULL OR owner = $1)
         ORDER BY created_at DESC
         LIMIT 100
         "#,
     )
+    .bind(owner.as_deref())
     .fetch_all(&state.db_pool)
     .await?;
 
@@ -130,28 +156,37 @@ pub struct DumpSummary {
 }
 
 /// Get dump by ID
+///
+/// Dumps owned by another identity are reported as not found.
+#[tracing::instrument(skip_all, fields(dump_id = %id))]
 pub async fn get_dump(
     State(state): State<AppState>,
+    owner: Owner,
     Path(id): Path<Uuid>,
 ) -> ApiResult<Json<Dump>> {
-    let dump = fetch_dump_by_id(&state, id).await?;
+    let dump = fetch_dump_by_id(&state, id, owner.as_deref()).await?;
     Ok(Json(dump))
 }
 
 /// Get dump by slug
 pub async fn get_dump_by_slug(
     State(state): State<AppState>,
+    owner: Owner,
     Path(slug): Path<String>,
 ) -> ApiResult<Json<Dump>> {
     let row = sqlx::query(
         r#"
         SELECT id, slug, original_filename, name, status, error_message,
-               file_size, created_at, updated_at, expires_at, sandbox_db_name
+               file_size, created_at, updated_at, expires_at, sandbox_db_name,
+               restore_timings, restore_warnings, dump_format, dump_layout,
+               upload_purged_at, last_viewed_at
         FROM dumps
         WHERE slug = $1 AND status != 'DELETED'
+          AND ($2::text IS NULL OR owner = $2)
         "#,
     )
     .bind(&slug)
+    .bind(owner.as_deref())
     .fetch_optional(&state.db_pool)
     .await?;
 
@@ -164,14 +199,118 @@ pub async fn get_dump_by_slug(
     }
 }
 
+/// Snapshot of a dump's processing state, sent as a `status` event by
+/// [`dump_events`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DumpEvent {
+    pub status: DumpStatus,
+    /// Progress of the running restore/analysis job, if any
+    pub progress: Option<DumpProgress>,
+    pub error_message: Option<String>,
+}
+
+impl DumpEvent {
+    /// No further changes are expected once a dump is ready, failed,
+    /// deleted or archived
+    fn is_terminal(&self) -> bool {
+        matches!(
+            self.status,
+            DumpStatus::Ready | DumpStatus::Error | DumpStatus::Deleted | DumpStatus::Archived
+        )
+    }
+}
+
+async fn fetch_dump_event(
+    state: &AppState,
+    id: Uuid,
+    owner: Option<&str>,
+) -> ApiResult<Option<DumpEvent>> {
+    let row = sqlx::query(
+        r#"
+        SELECT status, progress, error_message
+        FROM dumps
+        WHERE id = $1 AND ($2::text IS NULL OR owner = $2)
+        "#,
+    )
+    .bind(id)
+    .bind(owner)
+    .fetch_optional(&state.db_pool)
+    .await?;
+
+    Ok(row.map(|row| DumpEvent {
+        status: parse_status(row.get("status")),
+        progress: row
+            .get::<Option<serde_json::Value>, _>("progress")
+            .and_then(|v| serde_json::from_value(v).ok()),
+        error_message: row.get("error_message"),
+    }))
+}
+
+/// Stream status and restore progress changes as Server-Sent Events
+///
+/// The current state is sent immediately, then again whenever it changes.
+/// The stream ends after a terminal state (READY, ERROR, DELETED or ARCHIVED)
+/// has been sent, or if the dump disappears.
+#[tracing::instrument(skip_all, fields(dump_id = %id))]
+pub async fn dump_events(
+    State(state): State<AppState>,
+    owner: Owner,
+    Path(id): Path<Uuid>,
+) -> ApiResult<Sse<impl Stream<Item = Result<Event, Infallible>>>> {
+    let first = fetch_dump_event(&state, id, owner.as_deref())
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Dump {} not found", id)))?;
+
+    // State: the last event sent, and the next one if it is already known
+    let stream = stream::unfold(
+        (None::<DumpEvent>, Some(first)),
+        move |(last, mut pending)| {
+            let state = state.clone();
+            let owner = owner.clone();
+            async move {
+                if last.as_ref().is_some_and(DumpEvent::is_terminal) {
+                    return None;
+                }
+                loop {
+                    let current = match pending.take() {
+                        Some(event) => event,
+                        None => {
+                            tokio::time::sleep(DUMP_EVENTS_POLL_INTERVAL).await;
+                            match fetch_dump_event(&state, id, owner.as_deref()).await {
+                                Ok(Some(event)) => event,
+                                Ok(None) => return None,
+                                Err(e) => {
+                                    tracing::warn!("Stopping events for dump {}: {}", id, e);
+                                    return None;
+                                }
+                            }
+                        }
+                    };
+                    if last.as_ref() != Some(&current) {
+                        let event = Event::default()
+                            .event("status")
+                            .json_data(&current)
+                            .unwrap_or_default();
+                        return Some((Ok(event), (Some(current), None)));
+                    }
+                }
+            }
+        },
+    );
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
 /// Upload dump file
+#[tracing::instrument(skip_all, fields(dump_id = %id))]
 pub async fn upload_dump(
     State(state): State<AppState>,
+    owner: Owner,
     Path(id): Path<Uuid>,
     mut multipart: Multipart,
 ) -> ApiResult<Json<Dump>> {
     // Verify dump exists and is in correct state
-    let dump = fetch_dump_by_id(&state, id).await?;
+    let dump = fetch_dump_by_id(&state, id, owner.as_deref()).await?;
     if dump.status != DumpStatus::Created {
         return Err(ApiError::BadRequest(format!(
             "Dump is in '{}' state, expected 'CREATED'",
@@ -185,56 +324,466 @@ pub async fn upload_dump(
         .await
         .map_err(|e| ApiError::Internal(format!("Failed to create upload directory: {}", e)))?;
 
-    let mut file_size: i64 = 0;
-    let mut original_filename: Option<String> = None;
+    let mut parts = Vec::new();
+    let mut file_size: u64 = 0;
+    let mut filenames: Vec<String> = Vec::new();
 
-    // Process multipart upload
-    while let Some(field) = multipart
+    // Process multipart upload, streaming each file to disk chunk by chunk so
+    // multi-GB dumps never have to fit in memory. Several `file` fields (or a
+    // tar archive) make up a multi-file dump.
+    while let Some(mut field) = multipart
         .next_field()
         .await
         .map_err(|e| ApiError::BadRequest(format!("Multipart error: {}", e)))?
     {
-        if field.name() == Some("file") {
-            original_filename = field.file_name().map(|s| s.to_string());
-            let data = field
-                .bytes()
+        if matches!(field.name(), Some("file" | "files" | "files[]")) {
+            filenames.extend(field.file_name().map(|s| s.to_string()));
+
+            let part_path = upload_part_path(&state, id, parts.len());
+            let file = tokio::fs::File::create(&part_path)
                 .await
-                .map_err(|e| ApiError::BadRequest(format!("Failed to read file: {}", e)))?;
+                .map_err(|e| ApiError::Internal(format!("Failed to create file: {}", e)))?;
+            let mut writer = BufWriter::new(file);
+            let mut written: u64 = 0;
 
-            file_size = data.len() as i64;
-            let file_path = format!("{}/dump.sql", upload_dir);
+            while let Some(chunk) = field
+                .chunk()
+                .await
+                .map_err(|e| ApiError::BadRequest(format!("Failed to read file: {}", e)))?
+            {
+                writer
+                    .write_all(&chunk)
+                    .await
+                    .map_err(|e| ApiError::Internal(format!("Failed to write file: {}", e)))?;
+                written += chunk.len() as u64;
+            }
 
-            tokio::fs::write(&file_path, &data)
+            writer
+                .flush()
                 .await
                 .map_err(|e| ApiError::Internal(format!("Failed to write file: {}", e)))?;
+            verify_uploaded_size(&part_path, written).await?;
+            file_size += written;
+            parts.push(part_path);
         }
     }
 
-    // Update dump record
+    if parts.is_empty() {
+        return Err(ApiError::BadRequest(
+            "Multipart body has no 'file' field".to_string(),
+        ));
+    }
+    let layout = arrange_upload(&upload_dir, parts).await?;
+    let format = detect_dump_format(&upload_dump_path(&state, id, layout)).await?;
+
+    let original_filename = (!filenames.is_empty()).then(|| filenames.join(", "));
+    mark_uploaded(
+        &state.db_pool,
+        id,
+        original_filename.as_deref(),
+        file_size,
+        format,
+        layout,
+    )
+    .await?;
+
+    fetch_dump_by_id(&state, id, owner.as_deref())
+        .await
+        .map(Json)
+}
+
+/// Resumable upload query parameters
+#[derive(Debug, Deserialize)]
+pub struct ResumableUploadQuery {
+    /// Original filename, recorded when provided
+    pub filename: Option<String>,
+}
+
+/// Parsed `Content-Range: bytes <start>-<end>/<total>` header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContentRange {
+    pub start: u64,
+    /// Inclusive end offset
+    pub end: u64,
+    /// Total size, `None` when sent as `*` (not yet known)
+    pub total: Option<u64>,
+}
+
+impl ContentRange {
+    /// Number of bytes covered by the range
+    pub fn len(&self) -> u64 {
+        self.end - self.start + 1
+    }
+
+    /// Whether this range completes the upload
+    pub fn is_final(&self) -> bool {
+        self.total == Some(self.end + 1)
+    }
+}
+
+/// Parse a `Content-Range` header value
+pub fn parse_content_range(value: &str) -> Option<ContentRange> {
+    let (range, total) = value.trim().strip_prefix("bytes ")?.split_once('/')?;
+    let (start, end) = range.split_once('-')?;
+    let start: u64 = start.trim().parse().ok()?;
+    let end: u64 = end.trim().parse().ok()?;
+    let total = match total.trim() {
+        "*" => None,
+        t => Some(t.parse().ok()?),
+    };
+
+    if end < start || total.is_some_and(|t| end >= t) {
+        return None;
+    }
+
+    Some(ContentRange { start, end, total })
+}
+
+/// Minutes after which the claim of a chunk upload that never finished (the
+/// API process died while writing it) can be taken over
+const UPLOAD_CLAIM_TIMEOUT_MINS: i64 = 60;
+
+/// Claim on a dump's upload, held while one request writes a chunk
+///
+/// Recorded in `upload_claim` by a short transaction, so no connection is
+/// held while the body is read. A claim dropped without [`UploadClaim::release`]
+/// (the request was cancelled) is released in the background.
+struct UploadClaim {
+    pool: sqlx::PgPool,
+    id: Uuid,
+    token: Uuid,
+    released: bool,
+}
+
+impl UploadClaim {
+    /// Clear the claim unless the chunk's final update already did
+    async fn release(mut self) {
+        self.released = true;
+        if let Err(e) = release_upload_claim(&self.pool, self.id, self.token).await {
+            tracing::warn!("Failed to release upload claim of dump {}: {}", self.id, e);
+        }
+    }
+}
+
+impl Drop for UploadClaim {
+    fn drop(&mut self) {
+        if !self.released {
+            let (pool, id, token) = (self.pool.clone(), self.id, self.token);
+            tokio::spawn(async move {
+                let _ = release_upload_claim(&pool, id, token).await;
+            });
+        }
+    }
+}
+
+async fn release_upload_claim(pool: &sqlx::PgPool, id: Uuid, token: Uuid) -> sqlx::Result<()> {
     sqlx::query(
+        "UPDATE dumps SET upload_claim = NULL, upload_claimed_at = NULL \
+         WHERE id = $1 AND upload_claim = $2",
+    )
+    .bind(id)
+    .bind(token)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Upload a chunk of a dump file, resuming an interrupted upload
+///
+/// The body is the raw chunk and `Content-Range` gives its position. A chunk
+/// must start exactly where the stored data ends; on mismatch a 409 is
+/// returned carrying the current offset so the client can resume from there.
+/// The first total size declared is stored and later chunks must agree with
+/// it; the chunk ending at that size completes the upload.
+///
+/// Chunks of one dump are processed one at a time: a chunk claims the upload
+/// before its body is read and a concurrent chunk gets a 409. The claim is
+/// checked and cleared again when the chunk is recorded.
+#[tracing::instrument(skip_all, fields(dump_id = %id))]
+pub async fn upload_dump_chunk(
+    State(state): State<AppState>,
+    owner: Owner,
+    Path(id): Path<Uuid>,
+    Query(query): Query<ResumableUploadQuery>,
+    headers: HeaderMap,
+    body: Body,
+) -> ApiResult<Json<Dump>> {
+    let mut range = headers
+        .get(CONTENT_RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(parse_content_range)
+        .ok_or_else(|| {
+            ApiError::BadRequest(
+                "Missing or invalid Content-Range header, expected 'bytes <start>-<end>/<total>'"
+                    .to_string(),
+            )
+        })?;
+
+    let mut tx = state.db_pool.begin().await?;
+    let row = sqlx::query(
         r#"
-        UPDATE dumps
-        SET status = $1, original_filename = $2, file_size = $3, updated_at = $4
-        WHERE id = $5
+        SELECT status, original_filename, upload_total_size, upload_claimed_at
+        FROM dumps
+        WHERE id = $1 AND ($2::text IS NULL OR owner = $2)
+        FOR UPDATE
         "#,
     )
-    .bind(DumpStatus::Uploaded.as_str())
-    .bind(&original_filename)
-    .bind(file_size)
-    .bind(Utc::now())
     .bind(id)
-    .execute(&state.db_pool)
-    .await?;
+    .bind(owner.as_deref())
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| ApiError::NotFound(format!("Dump {} not found", id)))?;
+    let status = parse_status(row.get("status"));
+    if status != DumpStatus::Created && status != DumpStatus::Uploading {
+        return Err(ApiError::BadRequest(format!(
+            "Dump is in '{}' state, expected 'CREATED' or 'UPLOADING'",
+            status.as_str()
+        )));
+    }
+    let now = Utc::now();
+    let claim_cutoff = now - Duration::minutes(UPLOAD_CLAIM_TIMEOUT_MINS);
+    if row
+        .get::<Option<chrono::DateTime<Utc>>, _>("upload_claimed_at")
+        .is_some_and(|claimed_at| claimed_at > claim_cutoff)
+    {
+        return Err(ApiError::Conflict(
+            "Another chunk of this dump is being uploaded; retry once it completes".to_string(),
+        ));
+    }
+    let declared_total = row
+        .get::<Option<i64>, _>("upload_total_size")
+        .map(|t| t as u64);
+
+    match (declared_total, range.total) {
+        (Some(declared), Some(total)) if declared != total => {
+            return Err(ApiError::BadRequest(format!(
+                "Content-Range declares a total of {} bytes, but the upload was started with {}",
+                total, declared
+            )));
+        }
+        (Some(declared), None) if range.end >= declared => {
+            return Err(ApiError::BadRequest(format!(
+                "Chunk ends past the declared total of {} bytes",
+                declared
+            )));
+        }
+        _ => range.total = range.total.or(declared_total),
+    }
+
+    if range.total.unwrap_or(range.end + 1) > MAX_UPLOAD_SIZE as u64 {
+        return Err(ApiError::BadRequest(format!(
+            "Upload exceeds the maximum size of {} bytes",
+            MAX_UPLOAD_SIZE
+        )));
+    }
+
+    let token = Uuid::new_v4();
+    sqlx::query("UPDATE dumps SET upload_claim = $1, upload_claimed_at = $2 WHERE id = $3")
+        .bind(token)
+        .bind(now)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    let claim = UploadClaim {
+        pool: state.db_pool.clone(),
+        id,
+        token,
+        released: false,
+    };
+
+    let original_filename = query
+        .filename
+        .or_else(|| row.get::<Option<String>, _>("original_filename"));
+    let result = store_chunk(&state, &claim, range, body, original_filename).await;
+    claim.release().await;
+    result?;
+
+    fetch_dump_by_id(&state, id, owner.as_deref())
+        .await
+        .map(Json)
+}
+
+/// Append a claimed chunk to the upload and record it, completing the upload
+/// on the final chunk
+async fn store_chunk(
+    state: &AppState,
+    claim: &UploadClaim,
+    range: ContentRange,
+    body: Body,
+    original_filename: Option<String>,
+) -> ApiResult<()> {
+    let id = claim.id;
+    let upload_dir = format!("{}/{}", state.config.upload_dir, id);
+    tokio::fs::create_dir_all(&upload_dir)
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to create upload directory: {}", e)))?;
+
+    let file_path = upload_file_path(state, id);
+    let current_size = match tokio::fs::metadata(&file_path).await {
+        Ok(meta) => meta.len(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => 0,
+        Err(e) => return Err(ApiError::Internal(format!("Failed to stat file: {}", e))),
+    };
+
+    if range.start != current_size {
+        return Err(ApiError::Conflict(format!(
+            "Chunk starts at byte {}, but {} bytes have been uploaded; resume from offset {}",
+            range.start, current_size, current_size
+        )));
+    }
+
+    let file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&file_path)
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to open file: {}", e)))?;
+    let mut writer = BufWriter::new(file);
+    let mut written: u64 = 0;
+
+    let mut stream = body.into_data_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk =
+            chunk.map_err(|e| ApiError::BadRequest(format!("Failed to read chunk: {}", e)))?;
+        if written + chunk.len() as u64 > range.len() {
+            return Err(ApiError::BadRequest(format!(
+                "Chunk body is larger than the {} bytes declared in Content-Range",
+                range.len()
+            )));
+        }
+        writer
+            .write_all(&chunk)
+            .await
+            .map_err(|e| ApiError::Internal(format!("Failed to write file: {}", e)))?;
+        written += chunk.len() as u64;
+    }
+
+    writer
+        .flush()
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to write file: {}", e)))?;
 
-    fetch_dump_by_id(&state, id).await.map(Json)
+    if written != range.len() {
+        return Err(ApiError::BadRequest(format!(
+            "Chunk body has {} bytes, but Content-Range declares {}",
+            written,
+            range.len()
+        )));
+    }
+
+    let uploaded = current_size + written;
+    let completed = if range.is_final() {
+        verify_uploaded_size(&file_path, uploaded).await?;
+        // The completed file may be a tar archive of a multi-file dump
+        let part_path = upload_part_path(state, id, 0);
+        tokio::fs::rename(&file_path, &part_path)
+            .await
+            .map_err(|e| ApiError::Internal(format!("Failed to move file: {}", e)))?;
+        let layout = arrange_upload(&upload_dir, vec![part_path]).await?;
+        let format = detect_dump_format(&upload_dump_path(state, id, layout)).await?;
+        Some((format, layout))
+    } else {
+        None
+    };
+
+    // Record the chunk only while the claim is still ours
+    let mut tx = state.db_pool.begin().await?;
+    let still_claimed = sqlx::query(
+        "UPDATE dumps SET upload_claim = NULL, upload_claimed_at = NULL \
+         WHERE id = $1 AND upload_claim = $2",
+    )
+    .bind(id)
+    .bind(claim.token)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected()
+        > 0;
+    if !still_claimed {
+        return Err(ApiError::Conflict(
+            "The upload claim of this chunk expired before it was recorded".to_string(),
+        ));
+    }
+    if let Some((format, layout)) = completed {
+        mark_uploaded(
+            &mut *tx,
+            id,
+            original_filename.as_deref(),
+            uploaded,
+            format,
+            layout,
+        )
+        .await?;
+    } else {
+        sqlx::query(
+            r#"
+            UPDATE dumps
+            SET status = $1, original_filename = $2, file_size = $3, upload_total_size = $4,
+                updated_at = $5
+            WHERE id = $6
+            "#,
+        )
+        .bind(DumpStatus::Uploading.as_str())
+        .bind(&original_filename)
+        .bind(uploaded as i64)
+        .bind(range.total.map(|t| t as i64))
+        .bind(Utc::now())
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Optional body of the restore trigger
+#[derive(Debug, Default, Deserialize)]
+pub struct RestoreRequest {
+    /// Restore only these schemas (custom-format dumps only)
+    pub schemas: Option<Vec<String>>,
+    /// Columns to mask after the restore, before the dump becomes READY
+    pub masks: Option<Vec<ColumnMask>>,
+}
+
+/// Validate column masks, returning the value stored in `mask_columns`
+fn masks_column(masks: Option<Vec<ColumnMask>>) -> ApiResult<Option<serde_json::Value>> {
+    let masks = masks.unwrap_or_default();
+    if masks.is_empty() {
+        return Ok(None);
+    }
+    mask_statements(&masks).map_err(|e| match e {
+        CoreError::Validation(msg) => ApiError::Validation(msg),
+        other => ApiError::Core(other),
+    })?;
+    serde_json::to_value(&masks)
+        .map(Some)
+        .map_err(|e| ApiError::Internal(e.to_string()))
+}
+
+/// Trim schema names, dropping blanks and duplicates
+fn normalize_schemas(schemas: Vec<String>) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for schema in schemas {
+        let schema = schema.trim();
+        if !schema.is_empty() && !normalized.iter().any(|s| s == schema) {
+            normalized.push(schema.to_string());
+        }
+    }
+    normalized
 }
 
 /// Trigger dump restore
+///
+/// An optional JSON body `{"schemas": [...]}` limits the restore to those
+/// schemas; `"masks"` lists columns to anonymize before the dump is READY.
+#[tracing::instrument(skip_all, fields(dump_id = %id))]
 pub async fn restore_dump(
     State(state): State<AppState>,
+    owner: Owner,
     Path(id): Path<Uuid>,
+    body: Option<Json<RestoreRequest>>,
 ) -> ApiResult<Json<Dump>> {
-    let dump = fetch_dump_by_id(&state, id).await?;
+    let dump = fetch_dump_by_id(&state, id, owner.as_deref()).await?;
 
     if dump.status != DumpStatus::Uploaded {
         return Err(ApiError::BadRequest(format!(
@@ -243,35 +792,161 @@ pub async fn restore_dump(
         )));
     }
 
+    let req = body.map(|Json(req)| req).unwrap_or_default();
+    let masks = masks_column(req.masks)?;
+    let schemas = req.schemas.map(normalize_schemas).unwrap_or_default();
+    if !schemas.is_empty()
+        && matches!(
+            dump.dump_format,
+            Some(DumpFormat::PlainSql | DumpFormat::PgDumpall)
+        )
+    {
+        return Err(ApiError::BadRequest(
+            "Restoring a subset of schemas requires a custom or directory format \
+             (pg_dump -Fc or -Fd) dump"
+                .to_string(),
+        ));
+    }
+
     // Update status to restoring (worker will pick it up)
     sqlx::query(
         r#"
         UPDATE dumps
-        SET status = $1, updated_at = $2
-        WHERE id = $3
+        SET status = $1, updated_at = $2, included_schemas = $3, mask_columns = $4
+        WHERE id = $5
         "#,
     )
     .bind(DumpStatus::Restoring.as_str())
     .bind(Utc::now())
+    .bind((!schemas.is_empty()).then_some(&schemas))
+    .bind(masks)
     .bind(id)
     .execute(&state.db_pool)
     .await?;
+    state.schema_cache.invalidate_dump(id);
 
-    fetch_dump_by_id(&state, id).await.map(Json)
+    fetch_dump_by_id(&state, id, owner.as_deref())
+        .await
+        .map(Json)
 }
 
 // Helper functions
 
-async fn fetch_dump_by_id(state: &AppState, id: Uuid) -> ApiResult<Dump> {
+fn upload_file_path(state: &AppState, id: Uuid) -> String {
+    upload_dump_path(state, id, DumpLayout::SingleFile)
+}
+
+/// Path of the dump the worker restores for an upload in `layout`
+fn upload_dump_path(state: &AppState, id: Uuid, layout: DumpLayout) -> String {
+    format!("{}/{}/{}", state.config.upload_dir, id, layout.dump_name())
+}
+
+/// Path of the `index`th file of an upload, before [`arrange_upload`]
+fn upload_part_path(state: &AppState, id: Uuid, index: usize) -> String {
+    format!("{}/{}/part-{}", state.config.upload_dir, id, index)
+}
+
+/// Arrange the uploaded files for the worker (see [`assemble_upload`])
+async fn arrange_upload(upload_dir: &str, parts: Vec<String>) -> ApiResult<DumpLayout> {
+    let upload_dir = std::path::PathBuf::from(upload_dir);
+    let parts: Vec<_> = parts.into_iter().map(std::path::PathBuf::from).collect();
+    tokio::task::spawn_blocking(move || {
+        assemble_upload(&parts, &upload_dir, MAX_UPLOAD_SIZE as u64)
+    })
+    .await
+    .map_err(|e| ApiError::Internal(format!("Upload assembly task failed: {}", e)))?
+    .map_err(|e| match e {
+        CoreError::Validation(msg) => ApiError::BadRequest(msg),
+        e => ApiError::Internal(format!("Failed to arrange upload: {}", e)),
+    })
+}
+
+/// Check that the file on disk has exactly the expected number of bytes
+async fn verify_uploaded_size(file_path: &str, expected: u64) -> ApiResult<()> {
+    let actual = tokio::fs::metadata(file_path)
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to stat file: {}", e)))?
+        .len();
+
+    if actual != expected {
+        return Err(ApiError::Internal(format!(
+            "Uploaded file has {} bytes, expected {}",
+            actual, expected
+        )));
+    }
+    Ok(())
+}
+
+/// Detect the format of a completed upload, rejecting files that are not
+/// PostgreSQL dumps
+///
+/// A rejected file is removed so the dump can be uploaded again.
+async fn detect_dump_format(file_path: &str) -> ApiResult<DumpFormat> {
+    let path = file_path.to_string();
+    let format = tokio::task::spawn_blocking(move || validate_dump_header(&path))
+        .await
+        .map_err(|e| ApiError::Internal(format!("Format detection task failed: {}", e)))??;
+
+    if format == DumpFormat::Unknown {
+        let removed = if std::path::Path::new(file_path).is_dir() {
+            tokio::fs::remove_dir_all(file_path).await
+        } else {
+            tokio::fs::remove_file(file_path).await
+        };
+        if let Err(e) = removed {
+            tracing::warn!("Failed to remove rejected upload {}: {}", file_path, e);
+        }
+        return Err(ApiError::BadRequest(
+            "File is not a PostgreSQL dump (expected pg_dump custom or directory format, \
+             plain SQL or pg_dumpall output, optionally gzip/zstd/xz compressed)"
+                .to_string(),
+        ));
+    }
+    Ok(format)
+}
+
+async fn mark_uploaded(
+    executor: impl sqlx::PgExecutor<'_>,
+    id: Uuid,
+    original_filename: Option<&str>,
+    file_size: u64,
+    format: DumpFormat,
+    layout: DumpLayout,
+) -> ApiResult<()> {
+    sqlx::query(
+        r#"
+        UPDATE dumps
+        SET status = $1, original_filename = $2, file_size = $3, dump_format = $4,
+            dump_layout = $5, updated_at = $6
+        WHERE id = $7
+        "#,
+    )
+    .bind(DumpStatus::Uploaded.as_str())
+    .bind(original_filename)
+    .bind(file_size as i64)
+    .bind(format.as_str())
+    .bind(layout.as_str())
+    .bind(Utc::now())
+    .bind(id)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Fetch a dump, treating dumps of owners other than `owner` as missing
+async fn fetch_dump_by_id(state: &AppState, id: Uuid, owner: Option<&str>) -> ApiResult<Dump> {
     let row = sqlx::query(
         r#"
         SELECT id, slug, original_filename, name, status, error_message,
-               file_size, created_at, updated_at, expires_at, sandbox_db_name
+               file_size, created_at, updated_at, expires_at, sandbox_db_name,
+               restore_timings, restore_warnings, dump_format, dump_layout,
+               upload_purged_at, last_viewed_at
         FROM dumps
-        WHERE id = $1
+        WHERE id = $1 AND ($2::text IS NULL OR owner = $2)
         "#,
     )
     .bind(id)
+    .bind(owner)
     .fetch_optional(&state.db_pool)
     .await?;
 
@@ -281,9 +956,8 @@ async fn fetch_dump_by_id(state: &AppState, id: Uuid) -> ApiResult<Dump> {
     }
 }
 
-fn row_to_dump(row: &sqlx::postgres::PgRow) -> Dump {
-    let status_str: String = row.get("status");
-    let status = match status_str.as_str() {
+fn parse_status(status: &str) -> DumpStatus {
+    match status {
         "CREATED" => DumpStatus::Created,
         "UPLOADING" => DumpStatus::Uploading,
         "UPLOADED" => DumpStatus::Uploaded,
@@ -292,8 +966,13 @@ fn row_to_dump(row: &sqlx::postgres::PgRow) -> Dump {
         "READY" => DumpStatus::Ready,
         "ERROR" => DumpStatus::Error,
         "DELETED" => DumpStatus::Deleted,
+        "ARCHIVED" => DumpStatus::Archived,
         _ => DumpStatus::Error,
-    };
+    }
+}
+
+fn row_to_dump(row: &sqlx::postgres::PgRow) -> Dump {
+    let status = parse_status(row.get("status"));
 
     Dump {
         id: row.get("id"),
@@ -307,6 +986,20 @@ fn row_to_dump(row: &sqlx::postgres::PgRow) -> Dump {
         updated_at: row.get("updated_at"),
         expires_at: row.get("expires_at"),
         sandbox_db_name: row.get("sandbox_db_name"),
+        restore_timings: row
+            .get::<Option<serde_json::Value>, _>("restore_timings")
+            .and_then(|v| serde_json::from_value(v).ok()),
+        restore_warnings: row.get("restore_warnings"),
+        dump_format: row
+            .get::<Option<String>, _>("dump_format")
+            .as_deref()
+            .and_then(DumpFormat::parse),
+        dump_layout: row
+            .get::<Option<String>, _>("dump_layout")
+            .as_deref()
+            .and_then(DumpLayout::parse),
+        upload_purged_at: row.get("upload_purged_at"),
+        last_viewed_at: row.get("last_viewed_at"),
     }
 }
 
@@ -319,6 +1012,19 @@ fn generate_short_id() -> String {
     id.to_string()[..8].to_string()
 }
 
+/// Slugs tried by [`create_dump`] before giving up with a conflict
+const SLUG_ATTEMPTS: usize = 5;
+
+/// Slug to try on the given (0-based) attempt: the requested slug, then
+/// `slug-2`, `slug-3`, ...; without a requested slug, a fresh short id
+fn slug_candidate(requested: Option<&str>, attempt: usize) -> String {
+    match requested {
+        Some(slug) if attempt == 0 => slug.to_string(),
+        Some(slug) => format!("{}-{}", slug, attempt + 1),
+        None => generate_short_id(),
+    }
+}
+
 /// Response for sandbox databases list
 #[derive(Debug, Serialize)]
 pub struct DatabaseListResponse {
@@ -327,8 +1033,10 @@ pub struct DatabaseListResponse {
 }
 
 /// Get list of databases available in a dump
+#[tracing::instrument(skip_all, fields(dump_id = %id))]
 pub async fn get_dump_databases(
     State(state): State<AppState>,
+    owner: Owner,
     Path(id): Path<Uuid>,
 ) -> ApiResult<Json<DatabaseListResponse>> {
     let row = sqlx::query(
@@ -336,9 +1044,11 @@ pub async fn get_dump_databases(
         SELECT sandbox_db_name, sandbox_databases
         FROM dumps
         WHERE id = $1 AND status IN ('ANALYZING', 'READY')
+          AND ($2::text IS NULL OR owner = $2)
         "#,
     )
     .bind(id)
+    .bind(owner.as_deref())
     .fetch_optional(&state.db_pool)
     .await?;
 
@@ -401,19 +1111,22 @@ pub struct TablePreviewResponse {
 
 /// Get preview of tables in an uploaded dump file (before restore)
 /// This allows users to select which tables to exclude from restore
+#[tracing::instrument(skip_all, fields(dump_id = %id))]
 pub async fn preview_tables(
     State(state): State<AppState>,
+    owner: Owner,
     Path(id): Path<Uuid>,
 ) -> ApiResult<Json<TablePreviewResponse>> {
     // Check if dump exists and has been uploaded
     let row = sqlx::query(
         r#"
-        SELECT status
+        SELECT status, dump_layout
         FROM dumps
-        WHERE id = $1
+        WHERE id = $1 AND ($2::text IS NULL OR owner = $2)
         "#,
     )
     .bind(id)
+    .bind(owner.as_deref())
     .fetch_optional(&state.db_pool)
     .await?;
 
@@ -429,8 +1142,13 @@ pub async fn preview_tables(
     }
 
     // Find the dump file
+    let layout = row
+        .get::<Option<String>, _>("dump_layout")
+        .as_deref()
+        .and_then(DumpLayout::parse)
+        .unwrap_or(DumpLayout::SingleFile);
     let dump_dir = std::path::Path::new(&state.config.upload_dir).join(id.to_string());
-    let dump_file = dump_dir.join("dump.sql");
+    let dump_file = dump_dir.join(layout.dump_name());
 
     if !dump_file.exists() {
         return Err(ApiError::NotFound(
@@ -443,10 +1161,7 @@ pub async fn preview_tables(
     let dummy_pool = state.db_pool.clone(); // Just for creating adapter, not used
     let adapter = db_viewer_core::adapter::PostgresAdapter::new(
         dummy_pool,
-        state.config.sandbox_host.clone(),
-        state.config.sandbox_port,
-        state.config.sandbox_user.clone(),
-        state.config.sandbox_password.clone(),
+        state.config.sandbox_connection(),
     );
 
     let tables = adapter
@@ -466,11 +1181,15 @@ pub async fn preview_tables(
 pub struct RestoreWithExclusionsRequest {
     /// List of tables to exclude (format: "schema.table_name")
     pub excluded_tables: Option<Vec<String>>,
+    /// Columns to mask after the restore, before the dump becomes READY
+    pub masks: Option<Vec<ColumnMask>>,
 }
 
 /// Restore a dump with table exclusions
+#[tracing::instrument(skip_all, fields(dump_id = %id))]
 pub async fn restore_dump_with_exclusions(
     State(state): State<AppState>,
+    owner: Owner,
     Path(id): Path<Uuid>,
     Json(req): Json<RestoreWithExclusionsRequest>,
 ) -> ApiResult<Json<Dump>> {
@@ -479,10 +1198,11 @@ pub async fn restore_dump_with_exclusions(
         r#"
         SELECT status
         FROM dumps
-        WHERE id = $1
+        WHERE id = $1 AND ($2::text IS NULL OR owner = $2)
         "#,
     )
     .bind(id)
+    .bind(owner.as_deref())
     .fetch_optional(&state.db_pool)
     .await?;
 
@@ -496,29 +1216,100 @@ pub async fn restore_dump_with_exclusions(
         )));
     }
 
+    let masks = masks_column(req.masks)?;
+
     // Save excluded tables to database
     let excluded_tables = req.excluded_tables.unwrap_or_default();
 
     sqlx::query(
         r#"
         UPDATE dumps
-        SET status = $1, updated_at = $2, excluded_tables = $3
-        WHERE id = $4
+        SET status = $1, updated_at = $2, excluded_tables = $3, mask_columns = $4
+        WHERE id = $5
         "#,
     )
     .bind(DumpStatus::Restoring.as_str())
     .bind(Utc::now())
     .bind(&excluded_tables)
+    .bind(masks)
     .bind(id)
     .execute(&state.db_pool)
     .await?;
+    state.schema_cache.invalidate_dump(id);
+
+    fetch_dump_by_id(&state, id, owner.as_deref())
+        .await
+        .map(Json)
+}
+
+/// Download the uploaded dump
+///
+/// Multi-file uploads are served as the combined SQL the worker restored.
+/// Directory-format dumps have no single file to serve. Once the worker has
+/// deleted the upload after restoring it (`DELETE_UPLOAD_AFTER_RESTORE`), the
+/// response is 410 Gone.
+#[tracing::instrument(skip_all, fields(dump_id = %id))]
+pub async fn download_dump(
+    State(state): State<AppState>,
+    owner: Owner,
+    Path(id): Path<Uuid>,
+) -> ApiResult<Response> {
+    let dump = fetch_dump_by_id(&state, id, owner.as_deref()).await?;
 
-    fetch_dump_by_id(&state, id).await.map(Json)
+    if dump.upload_purged_at.is_some() {
+        return Err(ApiError::Gone(
+            "The uploaded file was deleted after the dump was restored".to_string(),
+        ));
+    }
+    let layout = dump.dump_layout.unwrap_or(DumpLayout::SingleFile);
+    if layout == DumpLayout::Directory {
+        return Err(ApiError::BadRequest(
+            "Directory-format dumps cannot be downloaded as a single file".to_string(),
+        ));
+    }
+
+    let path = upload_dump_path(&state, id, layout);
+    let file = match tokio::fs::File::open(&path).await {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(ApiError::NotFound(
+                "Dump file not found. Please upload the dump file first.".to_string(),
+            ));
+        }
+        Err(e) => return Err(ApiError::Internal(format!("Failed to open dump: {}", e))),
+    };
+    let len = file
+        .metadata()
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to read dump metadata: {}", e)))?
+        .len();
+
+    let filename = match (layout, &dump.original_filename) {
+        (DumpLayout::SingleFile, Some(name)) => name.clone(),
+        _ => format!("{}.sql", dump.slug),
+    }
+    .replace('"', "");
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/octet-stream".to_string()),
+            (header::CONTENT_LENGTH, len.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+        ],
+        Body::from_stream(tokio_util::io::ReaderStream::new(file)),
+    )
+        .into_response())
 }
 
 /// Delete a dump and clean up associated resources
+///
+/// Only the owner (or the admin) can delete a dump; others get 404.
+#[tracing::instrument(skip_all, fields(dump_id = %id))]
 pub async fn delete_dump(
     State(state): State<AppState>,
+    owner: Owner,
     Path(id): Path<Uuid>,
 ) -> ApiResult<Json<serde_json::Value>> {
     // Get dump info
@@ -527,9 +1318,11 @@ pub async fn delete_dump(
         SELECT sandbox_db_name, sandbox_databases, status
         FROM dumps
         WHERE id = $1 AND status != 'DELETED'
+          AND ($2::text IS NULL OR owner = $2)
         "#,
     )
     .bind(id)
+    .bind(owner.as_deref())
     .fetch_optional(&state.db_pool)
     .await?;
 
@@ -542,42 +1335,20 @@ pub async fn delete_dump(
     // Drop sandbox databases if they exist
     if let Some(ref db_name) = sandbox_db_name {
         if status != "CREATED" && status != "UPLOADED" {
-            // Build sandbox DB URL
-            let sandbox_url = if let Some(ref password) = state.config.sandbox_password {
-                format!(
-                    "postgres://{}:{}@{}:{}/postgres",
-                    state.config.sandbox_user,
-                    password,
-                    state.config.sandbox_host,
-                    state.config.sandbox_port
-                )
-            } else {
-                format!(
-                    "postgres://{}@{}:{}/postgres",
-                    state.config.sandbox_user, state.config.sandbox_host, state.config.sandbox_port
-                )
-            };
-
-            let sandbox_pool = sqlx::PgPool::connect(&sandbox_url)
-                .await
-                .map_err(|e| ApiError::Internal(format!("Failed to connect to sandbox: {}", e)))?;
+            // Server-level connection for DROP DATABASE
+            let sandbox_pool = state.sandbox_pools.get_privileged("postgres").await?;
 
             let adapter = db_viewer_core::adapter::postgres::PostgresAdapter::new(
-                sandbox_pool,
-                state.config.sandbox_host.clone(),
-                state.config.sandbox_port,
-                state.config.sandbox_user.clone(),
-                state.config.sandbox_password.clone(),
+                (*sandbox_pool).clone(),
+                state.config.sandbox_connection(),
             );
 
-            // Drop all databases in sandbox_databases array
-            if let Some(dbs) = sandbox_databases {
-                for db in dbs {
-                    let _ = adapter.drop_database(&db).await; // Ignore errors
-                }
-            } else {
-                // Fallback to primary database
-                let _ = adapter.drop_database(db_name).await; // Ignore errors
+            // Drop all databases in sandbox_databases array, closing their
+            // cached pools first
+            let dbs = sandbox_databases.unwrap_or_else(|| vec![db_name.clone()]);
+            for db in dbs {
+                state.sandbox_pools.evict(&db).await;
+                let _ = adapter.drop_database(&db).await; // Ignore errors
             }
         }
     }
@@ -587,6 +1358,7 @@ pub async fn delete_dump(
         .bind(id)
         .execute(&state.db_pool)
         .await?;
+    state.schema_cache.invalidate_dump(id);
 
     // Delete upload files
     let upload_dir = std::path::Path::new(&state.config.upload_dir).join(id.to_string());
@@ -601,18 +1373,386 @@ pub async fn delete_dump(
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
 
+    #[test]
+    fn test_normalize_schemas() {
+        let schemas = vec![
+            " sales ".to_string(),
+            "".to_string(),
+            "public".to_string(),
+            "sales".to_string(),
+        ];
+        assert_eq!(normalize_schemas(schemas), vec!["sales", "public"]);
+    }
+
+    #[test]
+    fn test_masks_column_rejects_invalid_target() {
+        let masks: Vec<ColumnMask> =
+            serde_json::from_str(r#"[{"column": "users.email", "strategy": "null"}]"#).unwrap();
+        assert!(matches!(
+            masks_column(Some(masks)),
+            Err(ApiError::Validation(_))
+        ));
+        assert!(masks_column(Some(Vec::new())).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_dump_event_terminal_states() {
+        let event = |status| DumpEvent {
+            status,
+            progress: None,
+            error_message: None,
+        };
+        assert!(!event(parse_status("RESTORING")).is_terminal());
+        assert!(!event(parse_status("ANALYZING")).is_terminal());
+        assert!(event(parse_status("READY")).is_terminal());
+        assert!(event(parse_status("ERROR")).is_terminal());
+        assert!(event(parse_status("DELETED")).is_terminal());
+        assert!(event(parse_status("ARCHIVED")).is_terminal());
+    }
+
+    #[test]
+    fn test_parse_status_round_trips() {
+        for status in [
+            DumpStatus::Created,
+            DumpStatus::Uploading,
+            DumpStatus::Uploaded,
+            DumpStatus::Restoring,
+            DumpStatus::Analyzing,
+            DumpStatus::Ready,
+            DumpStatus::Error,
+            DumpStatus::Deleted,
+            DumpStatus::Archived,
+        ] {
+            assert_eq!(parse_status(status.as_str()), status);
+        }
+    }
+
     #[test]
     fn test_slugify() {
         assert_eq!(slugify("My Test Dump"), "my-test-dump");
         assert_eq!(slugify("Test 123"), "test-123");
     }
 
+    #[test]
+    fn test_parse_content_range() {
+        assert_eq!(
+            parse_content_range("bytes 0-99/1000"),
+            Some(ContentRange {
+                start: 0,
+                end: 99,
+                total: Some(1000)
+            })
+        );
+        assert_eq!(
+            parse_content_range("bytes 100-199/*"),
+            Some(ContentRange {
+                start: 100,
+                end: 199,
+                total: None
+            })
+        );
+        assert!(parse_content_range("bytes 900-999/1000")
+            .unwrap()
+            .is_final());
+        assert!(!parse_content_range("bytes 0-99/1000").unwrap().is_final());
+        assert_eq!(parse_content_range("bytes 0-99/1000").unwrap().len(), 100);
+
+        assert_eq!(parse_content_range("0-99/1000"), None);
+        assert_eq!(parse_content_range("bytes 99-0/1000"), None);
+        assert_eq!(parse_content_range("bytes 0-1000/1000"), None);
+        assert_eq!(parse_content_range("bytes a-b/c"), None);
+    }
+
     #[test]
     fn test_generate_short_id() {
         let id = generate_short_id();
         assert_eq!(id.len(), 8);
     }
+
+    #[test]
+    fn test_slug_candidate() {
+        assert_eq!(slug_candidate(Some("nightly"), 0), "nightly");
+        assert_eq!(slug_candidate(Some("nightly"), 1), "nightly-2");
+        assert_eq!(slug_candidate(Some("nightly"), 2), "nightly-3");
+        assert_ne!(slug_candidate(None, 0), slug_candidate(None, 1));
+    }
+
+    /// State over the migrated metadata database in `TEST_DATABASE_URL`
+    pub(crate) async fn test_state() -> AppState {
+        use crate::config::AppConfig;
+        use crate::handlers::console::session::SessionManager;
+        use crate::rate_limit::RateLimiter;
+        use crate::state::{SandboxPoolCache, SchemaGraphCache};
+        use sqlx::PgPool;
+        use std::sync::Arc;
+
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL is required");
+        let config = Arc::new(AppConfig {
+            database_url: url.clone(),
+            sandbox_host: "127.0.0.1".to_string(),
+            sandbox_password: None,
+            sandbox_pool_max_connections: 1,
+            statement_timeout_ms: 1000,
+            cache_ttl_secs: 0,
+            auth_enabled: true,
+            ..crate::config::tests::test_config()
+        });
+        AppState {
+            db_pool: PgPool::connect(&url).await.unwrap(),
+            config: config.clone(),
+            console_sessions: Arc::new(SessionManager::default()),
+            sandbox_pools: Arc::new(SandboxPoolCache::new(config)),
+            rate_limiter: Arc::new(RateLimiter::new(0, std::time::Duration::ZERO)),
+            schema_cache: Arc::new(SchemaGraphCache::new(std::time::Duration::ZERO)),
+        }
+    }
+
+    /// Requires a migrated metadata database in `TEST_DATABASE_URL`
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_dumps_are_scoped_to_owner() {
+        let state = test_state().await;
+        let as_user = |name: &str| Some(Extension(Identity(name.to_string())));
+        let scope = |name: &str| Owner::of(Some(&Identity(name.to_string())));
+
+        let Json(created) = create_dump(
+            State(state.clone()),
+            as_user("alice"),
+            Json(CreateDumpRequest {
+                name: None,
+                slug: None,
+                is_private: Some(false),
+            }),
+        )
+        .await
+        .unwrap();
+
+        let bob_get = get_dump(State(state.clone()), scope("bob"), Path(created.id)).await;
+        let bob_slug = get_dump_by_slug(
+            State(state.clone()),
+            scope("bob"),
+            Path(created.slug.clone()),
+        )
+        .await;
+        let Json(bob_list) = list_dumps(State(state.clone()), scope("bob"))
+            .await
+            .unwrap();
+        let bob_delete = delete_dump(State(state.clone()), scope("bob"), Path(created.id)).await;
+        let alice_get = get_dump(State(state.clone()), scope("alice"), Path(created.id)).await;
+        let admin_get = get_dump(
+            State(state.clone()),
+            scope(crate::auth::ADMIN_IDENTITY),
+            Path(created.id),
+        )
+        .await;
+
+        sqlx::query("DELETE FROM dumps WHERE id = $1")
+            .bind(created.id)
+            .execute(&state.db_pool)
+            .await
+            .unwrap();
+
+        assert!(matches!(bob_get, Err(ApiError::NotFound(_))));
+        assert!(matches!(bob_slug, Err(ApiError::NotFound(_))));
+        assert!(matches!(bob_delete, Err(ApiError::NotFound(_))));
+        assert!(bob_list.iter().all(|d| d.id != created.id));
+        assert!(alice_get.is_ok());
+        assert!(admin_get.is_ok());
+    }
+
+    /// Requires a migrated metadata database in `TEST_DATABASE_URL`
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_data_routes_are_scoped_to_owner() {
+        use crate::handlers::query::{execute_query, QueryRequest};
+        use crate::handlers::schema::get_table_data;
+
+        let state = test_state().await;
+        let scope = |name: &str| Owner::of(Some(&Identity(name.to_string())));
+        let id = Uuid::new_v4();
+        // The sandbox does not exist: the owner gets past the lookup and
+        // fails connecting, anyone else must not get that far
+        sqlx::query(
+            r#"
+            INSERT INTO dumps (id, slug, status, sandbox_db_name, owner, expires_at)
+            VALUES ($1, $2, 'READY', 'sandbox_missing', 'alice', NOW() + INTERVAL '1 hour')
+            "#,
+        )
+        .bind(id)
+        .bind(format!("owned-data-{}", id))
+        .execute(&state.db_pool)
+        .await
+        .unwrap();
+
+        let table_data = |owner: Owner| {
+            get_table_data(
+                State(state.clone()),
+                owner,
+                Path((id, "public.users".to_string())),
+                Query(serde_json::from_value(serde_json::json!({})).unwrap()),
+            )
+        };
+        let query = |owner: Owner| {
+            execute_query(
+                State(state.clone()),
+                owner,
+                Path(id),
+                Json(QueryRequest {
+                    sql: "SELECT 1".to_string(),
+                    database: None,
+                    max_rows: None,
+                }),
+            )
+        };
+        let bob_rows = table_data(scope("bob")).await;
+        let bob_query = query(scope("bob")).await;
+        let alice_query = query(scope("alice")).await;
+
+        sqlx::query("DELETE FROM dumps WHERE id = $1")
+            .bind(id)
+            .execute(&state.db_pool)
+            .await
+            .unwrap();
+
+        assert!(matches!(bob_rows, Err(ApiError::NotFound(_))));
+        assert!(matches!(bob_query, Err(ApiError::NotFound(_))));
+        assert!(!matches!(alice_query, Err(ApiError::NotFound(_))));
+    }
+
+    /// Requires a migrated metadata database in `TEST_DATABASE_URL`
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_upload_chunks_are_serialized_and_keep_their_total() {
+        let state = test_state().await;
+        let upload_dir = tempfile::tempdir().unwrap();
+        // A single connection: a chunk must not hold it while its body arrives
+        let db_pool = sqlx::postgres::PgPoolOptions::new()
+            .max_connections(1)
+            .acquire_timeout(std::time::Duration::from_secs(5))
+            .connect(&state.config.database_url)
+            .await
+            .unwrap();
+        let state = AppState {
+            db_pool,
+            config: std::sync::Arc::new(crate::config::AppConfig {
+                upload_dir: upload_dir.path().to_str().unwrap().to_string(),
+                ..(*state.config).clone()
+            }),
+            ..state
+        };
+        let Json(created) = create_dump(
+            State(state.clone()),
+            None,
+            Json(CreateDumpRequest {
+                name: None,
+                slug: None,
+                is_private: None,
+            }),
+        )
+        .await
+        .unwrap();
+        let sql = b"CREATE TABLE t (id int);\n";
+        let chunk_with_body = |start: usize, end: usize, total: usize, body: Body| {
+            let mut headers = HeaderMap::new();
+            headers.insert(
+                CONTENT_RANGE,
+                format!("bytes {}-{}/{}", start, end - 1, total)
+                    .parse()
+                    .unwrap(),
+            );
+            upload_dump_chunk(
+                State(state.clone()),
+                Owner::default(),
+                Path(created.id),
+                Query(ResumableUploadQuery { filename: None }),
+                headers,
+                body,
+            )
+        };
+        let chunk = |start: usize, end: usize, total: usize| {
+            chunk_with_body(start, end, total, Body::from(sql[start..end].to_vec()))
+        };
+
+        // The first chunk's body arrives slowly; a second chunk meanwhile
+        // conflicts instead of waiting
+        let (body_tx, body_rx) =
+            futures::channel::mpsc::unbounded::<Result<axum::body::Bytes, std::io::Error>>();
+        let first = tokio::spawn(chunk_with_body(
+            0,
+            10,
+            sql.len(),
+            Body::from_stream(body_rx),
+        ));
+        body_tx
+            .unbounded_send(Ok(axum::body::Bytes::from_static(&sql[..5])))
+            .unwrap();
+        loop {
+            let claim: Option<Uuid> =
+                sqlx::query_scalar("SELECT upload_claim FROM dumps WHERE id = $1")
+                    .bind(created.id)
+                    .fetch_one(&state.db_pool)
+                    .await
+                    .unwrap();
+            if claim.is_some() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        let second = chunk(0, 10, sql.len()).await;
+        body_tx
+            .unbounded_send(Ok(axum::body::Bytes::from_static(&sql[5..10])))
+            .unwrap();
+        drop(body_tx);
+        let first = first.await.unwrap();
+        let resent = chunk(0, 10, sql.len()).await;
+        let other_total = chunk(10, sql.len(), sql.len() + 5).await;
+        let last = chunk(10, sql.len(), sql.len()).await;
+
+        sqlx::query("DELETE FROM dumps WHERE id = $1")
+            .bind(created.id)
+            .execute(&state.db_pool)
+            .await
+            .unwrap();
+
+        assert!(first.is_ok());
+        assert!(matches!(second, Err(ApiError::Conflict(_))));
+        assert!(matches!(resent, Err(ApiError::Conflict(_))));
+        assert!(matches!(other_total, Err(ApiError::BadRequest(_))));
+        let Json(dump) = last.unwrap();
+        assert_eq!(dump.status, DumpStatus::Uploaded);
+        assert_eq!(dump.file_size, Some(sql.len() as i64));
+    }
+
+    /// Requires a migrated metadata database in `TEST_DATABASE_URL`
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_colliding_slug_gets_suffix() {
+        let state = test_state().await;
+        let slug = format!("Collision {}", Uuid::new_v4().simple());
+        let create = || {
+            create_dump(
+                State(state.clone()),
+                None,
+                Json(CreateDumpRequest {
+                    name: None,
+                    slug: Some(slug.clone()),
+                    is_private: None,
+                }),
+            )
+        };
+
+        let Json(first) = create().await.unwrap();
+        let Json(second) = create().await.unwrap();
+
+        sqlx::query("DELETE FROM dumps WHERE id = ANY($1)")
+            .bind(vec![first.id, second.id])
+            .execute(&state.db_pool)
+            .await
+            .unwrap();
+
+        assert_eq!(second.slug, format!("{}-2", first.slug));
+    }
 }
@@ -0,0 +1,183 @@
+//! Service metrics in the Prometheus text format
+//!
+//! Everything is read from the metadata database on each scrape, so the
+//! numbers are consistent across API replicas without shared state.
+
+use std::fmt::Write;
+
+use axum::{extract::State, http::header, response::IntoResponse};
+use sqlx::postgres::PgPool;
+
+use crate::error::ApiResult;
+use crate::state::AppState;
+
+/// Restore duration buckets (seconds), matching the worker's phase histogram
+const RESTORE_DURATION_BUCKETS: &[f64] = &[
+    0.1, 0.5, 1.0, 5.0, 15.0, 30.0, 60.0, 300.0, 900.0, 1800.0, 3600.0,
+];
+
+/// Values rendered by [`render_metrics`]
+#[derive(Debug, Default)]
+pub struct MetricsSnapshot {
+    /// Number of dumps per status, e.g. `("READY", 3)`
+    pub status_counts: Vec<(String, i64)>,
+    /// Sandbox databases held by dumps that are not deleted
+    pub sandbox_databases: i64,
+    /// Total size of uploaded dump files that are not deleted
+    pub upload_bytes: i64,
+    /// Duration of each completed restore, in seconds
+    pub restore_durations: Vec<f64>,
+}
+
+/// Prometheus metrics
+///
+/// GET /metrics
+pub async fn get_metrics(State(state): State<AppState>) -> ApiResult<impl IntoResponse> {
+    let snapshot = load_snapshot(&state.db_pool).await?;
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        render_metrics(&snapshot),
+    ))
+}
+
+async fn load_snapshot(pool: &PgPool) -> ApiResult<MetricsSnapshot> {
+    let status_counts: Vec<(String, i64)> =
+        sqlx::query_as("SELECT status, COUNT(*) FROM dumps GROUP BY status ORDER BY status")
+            .fetch_all(pool)
+            .await?;
+
+    let (sandbox_databases, upload_bytes): (i64, i64) = sqlx::query_as(
+        r#"
+        SELECT
+            COALESCE(SUM(COALESCE(
+                cardinality(sandbox_databases),
+                CASE WHEN sandbox_db_name IS NULL THEN 0 ELSE 1 END
+            )), 0)::bigint,
+            COALESCE(SUM(file_size), 0)::bigint
+        FROM dumps
+        WHERE status <> 'DELETED'
+        "#,
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let restore_durations: Vec<f64> = sqlx::query_scalar(
+        r#"
+        SELECT EXTRACT(EPOCH FROM restore_finished_at - restore_started_at)::float8
+        FROM dumps
+        WHERE restore_started_at IS NOT NULL
+          AND restore_finished_at >= restore_started_at
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(MetricsSnapshot {
+        status_counts,
+        sandbox_databases,
+        upload_bytes,
+        restore_durations,
+    })
+}
+
+/// Render a snapshot in the Prometheus text exposition format
+pub fn render_metrics(snapshot: &MetricsSnapshot) -> String {
+    let mut out = String::new();
+
+    out.push_str("# HELP pgdumplens_dumps Number of dumps by status\n");
+    out.push_str("# TYPE pgdumplens_dumps gauge\n");
+    for (status, count) in &snapshot.status_counts {
+        let _ = writeln!(out, "pgdumplens_dumps{{status=\"{}\"}} {}", status, count);
+    }
+
+    out.push_str("# HELP pgdumplens_sandbox_databases Sandbox databases held by dumps\n");
+    out.push_str("# TYPE pgdumplens_sandbox_databases gauge\n");
+    let _ = writeln!(
+        out,
+        "pgdumplens_sandbox_databases {}",
+        snapshot.sandbox_databases
+    );
+
+    out.push_str("# HELP pgdumplens_upload_bytes Total size of uploaded dump files\n");
+    out.push_str("# TYPE pgdumplens_upload_bytes gauge\n");
+    let _ = writeln!(out, "pgdumplens_upload_bytes {}", snapshot.upload_bytes);
+
+    out.push_str(
+        "# HELP pgdumplens_restore_duration_seconds Wall-clock duration of completed restores\n",
+    );
+    out.push_str("# TYPE pgdumplens_restore_duration_seconds histogram\n");
+    for bound in RESTORE_DURATION_BUCKETS {
+        let count = snapshot
+            .restore_durations
+            .iter()
+            .filter(|d| **d <= *bound)
+            .count();
+        let _ = writeln!(
+            out,
+            "pgdumplens_restore_duration_seconds_bucket{{le=\"{}\"}} {}",
+            bound, count
+        );
+    }
+    let _ = writeln!(
+        out,
+        "pgdumplens_restore_duration_seconds_bucket{{le=\"+Inf\"}} {}",
+        snapshot.restore_durations.len()
+    );
+    let _ = writeln!(
+        out,
+        "pgdumplens_restore_duration_seconds_sum {}",
+        snapshot
+            .restore_durations
+            .iter()
+            .fold(0.0, |sum, d| sum + d)
+    );
+    let _ = writeln!(
+        out,
+        "pgdumplens_restore_duration_seconds_count {}",
+        snapshot.restore_durations.len()
+    );
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_metrics_status_counts() {
+        let snapshot = MetricsSnapshot {
+            status_counts: vec![
+                ("ERROR".to_string(), 1),
+                ("READY".to_string(), 3),
+                ("RESTORING".to_string(), 2),
+            ],
+            sandbox_databases: 4,
+            upload_bytes: 1_048_576,
+            restore_durations: vec![0.4, 12.0, 4000.0],
+        };
+
+        let output = render_metrics(&snapshot);
+
+        assert!(output.contains("pgdumplens_dumps{status=\"ERROR\"} 1\n"));
+        assert!(output.contains("pgdumplens_dumps{status=\"READY\"} 3\n"));
+        assert!(output.contains("pgdumplens_dumps{status=\"RESTORING\"} 2\n"));
+        assert!(output.contains("pgdumplens_sandbox_databases 4\n"));
+        assert!(output.contains("pgdumplens_upload_bytes 1048576\n"));
+        // Buckets are cumulative
+        assert!(output.contains("pgdumplens_restore_duration_seconds_bucket{le=\"0.1\"} 0\n"));
+        assert!(output.contains("pgdumplens_restore_duration_seconds_bucket{le=\"0.5\"} 1\n"));
+        assert!(output.contains("pgdumplens_restore_duration_seconds_bucket{le=\"15\"} 2\n"));
+        assert!(output.contains("pgdumplens_restore_duration_seconds_bucket{le=\"3600\"} 2\n"));
+        assert!(output.contains("pgdumplens_restore_duration_seconds_bucket{le=\"+Inf\"} 3\n"));
+        assert!(output.contains("pgdumplens_restore_duration_seconds_count 3\n"));
+    }
+
+    #[test]
+    fn test_render_metrics_empty() {
+        let output = render_metrics(&MetricsSnapshot::default());
+        assert!(!output.contains("pgdumplens_dumps{"));
+        assert!(output.contains("pgdumplens_restore_duration_seconds_count 0\n"));
+        assert!(output.contains("pgdumplens_restore_duration_seconds_sum 0\n"));
+    }
+}
@@ -3,6 +3,7 @@
 pub mod console;
 pub mod diff;
 pub mod dumps;
+pub mod metrics;
 pub mod query;
 pub mod relation;
 pub mod risk;
@@ -10,8 +11,18 @@ pub mod sandbox;
 pub mod schema;
 pub mod search;
 
-use axum::Json;
+use std::future::Future;
+use std::time::Duration;
+
+use axum::{extract::State, http::StatusCode, Json};
 use serde::Serialize;
+use sqlx::{postgres::PgPool, Connection, PgConnection};
+
+use crate::config::AppConfig;
+use crate::state::AppState;
+
+/// Time allowed for each dependency check of the readiness probe
+const READINESS_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
 
 /// Health check response
 #[derive(Serialize)]
@@ -28,13 +39,127 @@ pub async fn health_check() -> Json<HealthResponse> {
     })
 }
 
+/// Result of checking one dependency
+#[derive(Debug, Serialize)]
+pub struct ComponentHealth {
+    /// Dependency name: `metadata_db` or `sandbox`
+    pub name: String,
+    pub ok: bool,
+    /// Why the check failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Readiness probe response
+#[derive(Debug, Serialize)]
+pub struct ReadinessResponse {
+    /// `ok` when every component is healthy, `unavailable` otherwise
+    pub status: String,
+    pub components: Vec<ComponentHealth>,
+}
+
+/// Run a dependency check, failing it if it takes too long
+async fn check_component<F>(name: &str, check: F) -> ComponentHealth
+where
+    F: Future<Output = Result<(), sqlx::Error>>,
+{
+    let error = match tokio::time::timeout(READINESS_CHECK_TIMEOUT, check).await {
+        Ok(Ok(())) => None,
+        Ok(Err(e)) => Some(e.to_string()),
+        Err(_) => Some(format!(
+            "timed out after {}s",
+            READINESS_CHECK_TIMEOUT.as_secs()
+        )),
+    };
+    ComponentHealth {
+        name: name.to_string(),
+        ok: error.is_none(),
+        error,
+    }
+}
+
+async fn ping_metadata_db(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query("SELECT 1").execute(pool).await.map(|_| ())
+}
+
+/// Open (and close) a fresh connection to the sandbox server
+async fn ping_sandbox(config: &AppConfig) -> Result<(), sqlx::Error> {
+    let conn = PgConnection::connect(&config.sandbox_url("postgres")).await?;
+    conn.close().await
+}
+
+/// Readiness probe: checks the metadata database and the sandbox server
+///
+/// Returns 503 when any dependency is unreachable. `/health` stays a
+/// dependency-free liveness probe.
+pub async fn readiness_check(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<ReadinessResponse>) {
+    let (metadata_db, sandbox) = tokio::join!(
+        check_component("metadata_db", ping_metadata_db(&state.db_pool)),
+        check_component("sandbox", ping_sandbox(&state.config)),
+    );
+    let components = vec![metadata_db, sandbox];
+
+    let ready = components.iter().all(|c| c.ok);
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (
+        status,
+        Json(ReadinessResponse {
+            status: if ready { "ok" } else { "unavailable" }.to_string(),
+            components,
+        }),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::handlers::console::session::SessionManager;
+    use crate::rate_limit::RateLimiter;
+    use crate::state::{SandboxPoolCache, SchemaGraphCache};
+    use sqlx::postgres::PgPoolOptions;
+    use std::sync::Arc;
 
     #[tokio::test]
     async fn test_health_check() {
         let response = health_check().await;
         assert_eq!(response.status, "ok");
     }
+
+    #[tokio::test]
+    async fn test_readiness_check_unavailable_with_closed_pool() {
+        let config = Arc::new(AppConfig {
+            sandbox_host: "127.0.0.1".to_string(),
+            // Nothing listens on port 1, so the connection is refused
+            sandbox_port: 1,
+            sandbox_pool_max_connections: 1,
+            statement_timeout_ms: 1000,
+            ..crate::config::tests::test_config()
+        });
+        let db_pool = PgPoolOptions::new()
+            .connect_lazy(&config.database_url)
+            .unwrap();
+        db_pool.close().await;
+        let state = AppState {
+            db_pool,
+            config: config.clone(),
+            console_sessions: Arc::new(SessionManager::default()),
+            sandbox_pools: Arc::new(SandboxPoolCache::new(config)),
+            rate_limiter: Arc::new(RateLimiter::new(0, std::time::Duration::ZERO)),
+            schema_cache: Arc::new(SchemaGraphCache::new(std::time::Duration::ZERO)),
+        };
+
+        let (status, Json(body)) = readiness_check(State(state)).await;
+
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body.status, "unavailable");
+        let names: Vec<&str> = body.components.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["metadata_db", "sandbox"]);
+        assert!(body.components.iter().all(|c| !c.ok && c.error.is_some()));
+    }
 }
@@ -24,16 +24,15 @@ use serde::{Deserialize, Serialize};
 use sqlx::{Column, Connection, Executor, Row};
 use uuid::Uuid;
 
+use crate::auth::Owner;
 use crate::error::{ApiError, ApiResult};
-use crate::handlers::sandbox::{build_sandbox_url, resolve_sandbox_db};
+use crate::handlers::sandbox::{reject_archived, resolve_sandbox_db};
 use crate::state::AppState;
 
 /// Maximum number of rows that may be requested.
 const MAX_ROWS_CAP: i64 = 2000;
 /// Default number of rows returned when `max_rows` is not specified.
 const DEFAULT_MAX_ROWS: i64 = 500;
-/// Statement timeout applied to every console query (milliseconds).
-const STATEMENT_TIMEOUT_MS: i64 = 30_000;
 
 /// Request body for the SQL console.
 #[derive(Debug, Deserialize)]
@@ -111,8 +110,10 @@ fn map_sql_error(e: sqlx::Error) -> ApiError {
 }
 
 /// Execute a single SQL statement against a dump's sandbox database.
+#[tracing::instrument(skip_all, fields(dump_id = %id))]
 pub async fn execute_query(
     State(state): State<AppState>,
+    owner: Owner,
     Path(id): Path<Uuid>,
     Json(req): Json<QueryRequest>,
 ) -> ApiResult<Json<QueryResponse>> {
@@ -128,13 +129,16 @@ pub async fn execute_query(
         .clamp(1, MAX_ROWS_CAP);
 
     // Ensure the dump is READY before touching the sandbox.
-    let status_row = sqlx::query("SELECT status FROM dumps WHERE id = $1")
-        .bind(id)
-        .fetch_optional(&state.db_pool)
-        .await?;
+    let status_row =
+        sqlx::query("SELECT status FROM dumps WHERE id = $1 AND ($2::text IS NULL OR owner = $2)")
+            .bind(id)
+            .bind(owner.as_deref())
+            .fetch_optional(&state.db_pool)
+            .await?;
     let status_row =
         status_row.ok_or_else(|| ApiError::NotFound(format!("Dump {} not found", id)))?;
     let status: String = status_row.get("status");
+    reject_archived(&state, id, &status).await?;
     if status != "READY" {
         return Err(ApiError::BadRequest(format!(
             "Dump is not ready for queries (status: {})",
@@ -142,8 +146,9 @@ pub async fn execute_query(
         )));
     }
 
-    let sandbox_db = resolve_sandbox_db(&state.db_pool, id, req.database.as_deref()).await?;
-    let sandbox_url = build_sandbox_url(&state.config, &sandbox_db);
+    let sandbox_db =
+        resolve_sandbox_db(&state, id, owner.as_deref(), req.database.as_deref()).await?;
+    let sandbox_url = state.config.sandbox_url(&sandbox_db);
 
     // A single dedicated connection is required so that `SET` applies to the
     // same session as the query (a pooled connection could differ).
@@ -152,10 +157,13 @@ pub async fn execute_query(
         .map_err(|e| ApiError::Internal(format!("Failed to connect to sandbox: {}", e)))?;
 
     // Guard long-running queries.
-    sqlx::query(&format!("SET statement_timeout = {}", STATEMENT_TIMEOUT_MS))
-        .execute(&mut conn)
-        .await
-        .map_err(|e| ApiError::Internal(format!("Failed to set statement timeout: {}", e)))?;
+    sqlx::query(&format!(
+        "SET statement_timeout = {}",
+        state.config.statement_timeout_ms
+    ))
+    .execute(&mut conn)
+    .await
+    .map_err(|e| ApiError::Internal(format!("Failed to set statement timeout: {}", e)))?;
 
     let start = Instant::now();
 
@@ -5,15 +5,20 @@ use axum::{
     Json,
 };
 use serde::{Deserialize, Serialize};
-use sqlx::types::Json as SqlxJson;
-use sqlx::Row;
+use sqlx::{PgPool, Row};
+use std::collections::HashMap;
 use uuid::Uuid;
 
+use crate::auth::Owner;
 use crate::error::{ApiError, ApiResult};
+use crate::handlers::sandbox::{record_view, reject_archived, resolve_sandbox_db};
 use crate::state::AppState;
-use db_viewer_core::domain::{RelationDirection, RelationExplanation, SchemaGraph};
+use db_viewer_core::adapter::{DbAdapter, PostgresAdapter};
+use db_viewer_core::domain::{ForeignKey, RelationDirection, RelationExplanation};
+use db_viewer_core::filter::{FilterOp, FilterPredicate};
 use db_viewer_core::risk::RiskCalculator;
-use db_viewer_core::sql_gen::SqlGenerator;
+use db_viewer_core::schema::{find_related_tables, RelationType};
+use db_viewer_core::sql_gen::{quote_ident, SqlGenerator};
 
 /// Explain relation request
 #[derive(Debug, Deserialize)]
@@ -35,6 +40,53 @@ pub struct ExplainRelationResponse {
     pub sql_examples: Vec<String>,
 }
 
+/// Referencing rows sampled per inbound relationship
+const SAMPLE_ROWS_PER_RELATION: usize = 5;
+
+/// Sample the rows of `fk`'s source table that reference `value` in the
+/// target `column`; failures only cost the samples
+async fn sample_referencing_rows(
+    adapter: &PostgresAdapter,
+    db_name: &str,
+    fk: &ForeignKey,
+    column: &str,
+    value: &str,
+) -> Vec<serde_json::Value> {
+    let Some(source_column) = fk
+        .target_columns
+        .iter()
+        .position(|c| c == column)
+        .and_then(|i| fk.source_columns.get(i))
+    else {
+        return vec![];
+    };
+    let filter = [FilterPredicate {
+        column: source_column.clone(),
+        op: FilterOp::Eq,
+        value: Some(value.to_string()),
+    }];
+    adapter
+        .fetch_sample_rows_filtered(
+            db_name,
+            &fk.source_schema,
+            &fk.source_table,
+            &filter,
+            None,
+            SAMPLE_ROWS_PER_RELATION,
+        )
+        .await
+        .unwrap_or_else(|e| {
+            tracing::warn!(
+                "explain_relation: failed to sample {}.{} rows via {}: {}",
+                fk.source_schema,
+                fk.source_table,
+                fk.constraint_name,
+                e
+            );
+            vec![]
+        })
+}
+
 /// Find sandbox database name for a given original database name
 ///
 /// For pg_dumpall dumps, sandbox databases are named: sandbox_{dump_id}_{original_db_name}
@@ -54,25 +106,30 @@ fn find_sandbox_db_for_original(
 }
 
 /// Explain relationships for a value
+#[tracing::instrument(skip_all, fields(dump_id = %id))]
 pub async fn explain_relation(
     State(state): State<AppState>,
+    owner: Owner,
     Path(id): Path<Uuid>,
     Json(req): Json<ExplainRelationRequest>,
 ) -> ApiResult<Json<ExplainRelationResponse>> {
-    let _max_hops = req.max_hops.unwrap_or(2).min(5);
+    let max_hops = req.max_hops.unwrap_or(2).clamp(1, 5);
 
     // Get dump info including sandbox databases
     let row = sqlx::query(
         r#"
-        SELECT sandbox_db_name, sandbox_databases
+        SELECT status, sandbox_db_name, sandbox_databases
         FROM dumps
-        WHERE id = $1
+        WHERE id = $1 AND ($2::text IS NULL OR owner = $2)
         "#,
     )
     .bind(id)
+    .bind(owner.as_deref())
     .fetch_optional(&state.db_pool)
     .await?
     .ok_or_else(|| ApiError::NotFound(format!("Dump {} not found", id)))?;
+    reject_archived(&state, id, row.get("status")).await?;
+    record_view(&state.db_pool, id).await;
 
     let primary_sandbox_db: Option<String> = row.get("sandbox_db_name");
     let sandbox_databases: Option<Vec<String>> = row.get("sandbox_databases");
@@ -109,19 +166,8 @@ pub async fn explain_relation(
 
     // Fetch schema graph for the specific database
     // dump_schemas stores the full sandbox database name (e.g., sandbox_abc123_platform)
-    let schema_row = sqlx::query(
-        "SELECT schema_graph FROM dump_schemas WHERE dump_id = $1 AND database_name = $2",
-    )
-    .bind(id)
-    .bind(&sandbox_db_name)
-    .fetch_optional(&state.db_pool)
-    .await?;
-
-    let schema_graph: SchemaGraph = match schema_row {
-        Some(row) => {
-            let SqlxJson(graph): SqlxJson<SchemaGraph> = row.get("schema_graph");
-            graph
-        }
+    let schema_graph = match state.schema_graph(id, &sandbox_db_name).await? {
+        Some(graph) => graph,
         None => {
             return Err(ApiError::NotFound(format!(
                 "Schema not found for dump {}",
@@ -130,6 +176,25 @@ pub async fn explain_relation(
         }
     };
 
+    // Referencing rows are counted in the sandbox; without it explanations
+    // are still returned, just without counts
+    let sandbox_pool = match state.sandbox_pools.get(&sandbox_db_name).await {
+        Ok(pool) => Some(pool),
+        Err(e) => {
+            tracing::warn!(
+                "explain_relation: cannot connect to sandbox {}, skipping referencing counts: {}",
+                sandbox_db_name,
+                e
+            );
+            None
+        }
+    };
+    let value_text = json_value_as_text(&req.value);
+    let sampler = sandbox_pool.as_ref().map(|pool| {
+        PostgresAdapter::new((**pool).clone(), state.config.sandbox_connection())
+            .with_db_pool(&sandbox_db_name, (**pool).clone())
+    });
+
     let risk_calc = RiskCalculator::new(&schema_graph);
     let mut explanations = Vec::new();
 
@@ -139,12 +204,38 @@ pub async fn explain_relation(
             && fk.target_table == req.table
             && fk.target_columns.contains(&req.column)
         {
+            let referencing_count = match (&sandbox_pool, &value_text) {
+                (Some(pool), Some(value)) => {
+                    match count_referencing_rows(pool, fk, &req.column, value).await {
+                        Ok(count) => Some(count),
+                        Err(e) => {
+                            tracing::warn!(
+                                "explain_relation: failed to count rows referencing {}.{}.{} via {}: {}",
+                                req.schema,
+                                req.table,
+                                req.column,
+                                fk.constraint_name,
+                                e
+                            );
+                            None
+                        }
+                    }
+                }
+                _ => None,
+            };
+
             let risk = risk_calc.calculate_column_risk(
                 &req.schema,
                 &req.table,
                 &req.column,
-                0, // TODO: Get actual referencing count
+                referencing_count.unwrap_or(0),
             );
+            let sample_rows = match (&sampler, &value_text) {
+                (Some(adapter), Some(value)) if referencing_count != Some(0) => {
+                    sample_referencing_rows(adapter, &sandbox_db_name, fk, &req.column, value).await
+                }
+                _ => vec![],
+            };
 
             explanations.push(RelationExplanation {
                 source_table: format!("{}.{}", fk.source_schema, fk.source_table),
@@ -153,10 +244,15 @@ pub async fn explain_relation(
                 target_column: fk.target_columns.join(", "),
                 direction: RelationDirection::Inbound,
                 path_length: 1,
-                sample_rows: vec![],
-                sql_example: SqlGenerator::generate_referencing_query(fk, "$1", 50),
+                sample_rows,
+                sql_example: SqlGenerator::generate_referencing_query(
+                    fk,
+                    &SqlGenerator::positional_placeholders(fk.source_columns.len()),
+                    50,
+                ),
                 risk_score: risk.score,
                 risk_reasons: risk.reasons,
+                referencing_count,
             });
         }
     }
@@ -175,22 +271,78 @@ pub async fn explain_relation(
                 direction: RelationDirection::Outbound,
                 path_length: 1,
                 sample_rows: vec![],
-                sql_example: SqlGenerator::generate_join_query(fk, "$1", 50),
+                sql_example: SqlGenerator::generate_join_query(
+                    fk,
+                    &SqlGenerator::positional_placeholders(fk.source_columns.len()),
+                    50,
+                ),
+                risk_score: 0,
+                risk_reasons: vec![],
+                referencing_count: None,
+            });
+        }
+    }
+
+    // Tables two or more hops away, explained by the last foreign key on
+    // their path and explored with a recursive query
+    let nhop_cte = (max_hops > 1).then(|| {
+        SqlGenerator::generate_nhop_cte(
+            &schema_graph,
+            &req.schema,
+            &req.table,
+            &req.column,
+            "$1",
+            max_hops,
+        )
+    });
+    if let Some(cte) = &nhop_cte {
+        for related in find_related_tables(&schema_graph, &req.schema, &req.table, max_hops) {
+            if related.hop_count < 2 {
+                continue;
+            }
+            let Some(fk) = related.path.last().and_then(|constraint| {
+                schema_graph.foreign_keys.iter().find(|fk| {
+                    &fk.constraint_name == constraint
+                        && ((fk.source_schema == related.schema
+                            && fk.source_table == related.table)
+                            || (fk.target_schema == related.schema
+                                && fk.target_table == related.table))
+                })
+            }) else {
+                continue;
+            };
+
+            let direction = match related.relationship {
+                RelationType::References => RelationDirection::Inbound,
+                RelationType::ReferencedBy => RelationDirection::Outbound,
+            };
+
+            explanations.push(RelationExplanation {
+                source_table: format!("{}.{}", fk.source_schema, fk.source_table),
+                source_column: fk.source_columns.join(", "),
+                target_table: format!("{}.{}", fk.target_schema, fk.target_table),
+                target_column: fk.target_columns.join(", "),
+                direction,
+                path_length: related.hop_count,
+                sample_rows: vec![],
+                sql_example: cte.clone(),
                 risk_score: 0,
                 risk_reasons: vec![],
+                referencing_count: None,
             });
         }
     }
 
     // Generate SQL examples
-    let sql_examples = SqlGenerator::generate_relationship_sql(
+    let mut sql_examples = SqlGenerator::generate_relationship_sql(
         &schema_graph,
         &req.schema,
         &req.table,
         &req.column,
         RelationDirection::Inbound,
-        "$1",
+        &["$1"],
     );
+    sql_examples.extend(nhop_cte);
 
     Ok(Json(ExplainRelationResponse {
         explanations,
@@ -198,6 +350,226 @@ pub async fn explain_relation(
     }))
 }
 
+/// Delete preview request
+#[derive(Debug, Deserialize)]
+pub struct DeletePreviewRequest {
+    pub schema: String,
+    pub table: String,
+    pub column: String,
+    pub value: serde_json::Value,
+    /// Optional database name for multi-database dumps
+    pub database: Option<String>,
+}
+
+/// Rows of one table a delete would remove or change
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AffectedTable {
+    pub schema_name: String,
+    pub table_name: String,
+    /// Rows deleted, directly or by `ON DELETE CASCADE`
+    pub deleted: i64,
+    /// Rows updated by `ON DELETE SET NULL` / `SET DEFAULT`
+    pub updated: i64,
+}
+
+/// Delete preview response
+#[derive(Debug, Serialize)]
+pub struct DeletePreviewResponse {
+    pub schema: String,
+    pub table: String,
+    /// Rows deleted across all tables
+    pub total_deleted: i64,
+    /// Affected tables, most deleted rows first
+    pub tables: Vec<AffectedTable>,
+    /// Foreign key constraint that would reject the delete (`RESTRICT` or
+    /// `NO ACTION` with referencing rows); `tables` is empty when set
+    pub blocked_by: Option<String>,
+}
+
+/// Preview a delete and everything it cascades to
+///
+/// POST /api/dumps/:id/delete-preview
+///
+/// Runs the `DELETE` in the sandbox inside a transaction that is always
+/// rolled back, and reads the per-table counts PostgreSQL keeps for the
+/// transaction. Unlike the estimates of
+/// [`SqlGenerator::generate_delete_impact_query`], these are the exact rows
+/// the delete would touch, including cascades several levels deep.
+#[tracing::instrument(skip_all, fields(dump_id = %id))]
+pub async fn preview_delete(
+    State(state): State<AppState>,
+    owner: Owner,
+    Path(id): Path<Uuid>,
+    Json(req): Json<DeletePreviewRequest>,
+) -> ApiResult<Json<DeletePreviewResponse>> {
+    let sandbox_db =
+        resolve_sandbox_db(&state, id, owner.as_deref(), req.database.as_deref()).await?;
+    let schema_graph = state
+        .schema_graph(id, &sandbox_db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Schema not found for dump {}", id)))?;
+    let table = schema_graph
+        .tables
+        .iter()
+        .find(|t| t.schema_name == req.schema && t.table_name == req.table)
+        .ok_or_else(|| {
+            ApiError::NotFound(format!("Table {}.{} not found", req.schema, req.table))
+        })?;
+    if !table.columns.iter().any(|c| c.name == req.column) {
+        return Err(ApiError::NotFound(format!(
+            "Column {} not found in {}.{}",
+            req.column, req.schema, req.table
+        )));
+    }
+    let value = json_value_as_text(&req.value)
+        .ok_or_else(|| ApiError::BadRequest("value must not be null".to_string()))?;
+
+    // The read-only role cannot delete, even in a rolled-back transaction
+    let pool = state.sandbox_pools.get_privileged(&sandbox_db).await?;
+    let preview = run_delete_preview(&pool, &req.schema, &req.table, &req.column, &value).await?;
+
+    Ok(Json(DeletePreviewResponse {
+        schema: req.schema,
+        table: req.table,
+        total_deleted: preview.tables.iter().map(|t| t.deleted).sum(),
+        tables: preview.tables,
+        blocked_by: preview.blocked_by,
+    }))
+}
+
+/// Outcome of a rolled-back delete
+struct DeletePreview {
+    tables: Vec<AffectedTable>,
+    blocked_by: Option<String>,
+}
+
+/// SQLSTATE of a foreign key violation
+const FOREIGN_KEY_VIOLATION: &str = "23503";
+
+/// Rows deleted and updated so far in the current transaction, per table
+async fn transaction_row_counts(
+    conn: &mut sqlx::PgConnection,
+) -> Result<HashMap<(String, String), (i64, i64)>, sqlx::Error> {
+    let rows: Vec<(String, String, i64, i64)> = sqlx::query_as(
+        r#"
+        SELECT schemaname::text, relname::text, n_tup_del, n_tup_upd
+        FROM pg_stat_xact_user_tables
+        "#,
+    )
+    .fetch_all(conn)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|(schema, table, deleted, updated)| ((schema, table), (deleted, updated)))
+        .collect())
+}
+
+/// Tables whose counts changed between two [`transaction_row_counts`]
+/// snapshots, most deleted rows first
+fn affected_tables(
+    before: &HashMap<(String, String), (i64, i64)>,
+    after: HashMap<(String, String), (i64, i64)>,
+) -> Vec<AffectedTable> {
+    let mut tables: Vec<AffectedTable> = after
+        .into_iter()
+        .map(|(key, (deleted, updated))| {
+            let (deleted_before, updated_before) = before.get(&key).copied().unwrap_or_default();
+            AffectedTable {
+                schema_name: key.0,
+                table_name: key.1,
+                deleted: deleted - deleted_before,
+                updated: updated - updated_before,
+            }
+        })
+        .filter(|t| t.deleted > 0 || t.updated > 0)
+        .collect();
+    tables.sort_by(|a, b| {
+        b.deleted
+            .cmp(&a.deleted)
+            .then_with(|| b.updated.cmp(&a.updated))
+            .then_with(|| (&a.schema_name, &a.table_name).cmp(&(&b.schema_name, &b.table_name)))
+    });
+    tables
+}
+
+/// Delete the rows of `schema.table` whose `column` equals `value` (compared
+/// as text), count what changed and roll everything back
+async fn run_delete_preview(
+    pool: &PgPool,
+    schema: &str,
+    table: &str,
+    column: &str,
+    value: &str,
+) -> Result<DeletePreview, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let before = transaction_row_counts(&mut tx).await?;
+
+    let delete = format!(
+        "DELETE FROM {}.{} WHERE {}::text = $1",
+        quote_ident(schema),
+        quote_ident(table),
+        quote_ident(column)
+    );
+    let result = sqlx::query(&delete).bind(value).execute(&mut *tx).await;
+    let preview = match result {
+        Ok(_) => DeletePreview {
+            tables: affected_tables(&before, transaction_row_counts(&mut tx).await?),
+            blocked_by: None,
+        },
+        Err(sqlx::Error::Database(e)) if e.code().as_deref() == Some(FOREIGN_KEY_VIOLATION) => {
+            DeletePreview {
+                tables: Vec::new(),
+                blocked_by: Some(e.constraint().unwrap_or_else(|| e.message()).to_string()),
+            }
+        }
+        Err(e) => return Err(e),
+    };
+
+    tx.rollback().await?;
+    Ok(preview)
+}
+
+/// Text form of a JSON value for comparison against a column cast to text
+///
+/// `null` never equals anything in SQL, so it yields `None`.
+fn json_value_as_text(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::Null => None,
+        serde_json::Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+/// Count rows of the FK's source table whose column referencing
+/// `target_column` equals `value`
+///
+/// The value is bound as a parameter and compared with the column's text
+/// form, so it works regardless of the column type.
+async fn count_referencing_rows(
+    pool: &PgPool,
+    fk: &ForeignKey,
+    target_column: &str,
+    value: &str,
+) -> Result<i64, sqlx::Error> {
+    let Some(source_column) = fk
+        .target_columns
+        .iter()
+        .position(|c| c == target_column)
+        .and_then(|i| fk.source_columns.get(i))
+    else {
+        return Ok(0);
+    };
+
+    let query = format!(
+        "SELECT COUNT(*) FROM {}.{} WHERE {}::text = $1",
+        quote_ident(&fk.source_schema),
+        quote_ident(&fk.source_table),
+        quote_ident(source_column)
+    );
+
+    sqlx::query_scalar(&query).bind(value).fetch_one(pool).await
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -217,4 +589,123 @@ mod tests {
         assert_eq!(req.column, "id");
         assert_eq!(req.max_hops, None);
     }
+
+    #[test]
+    fn test_json_value_as_text() {
+        assert_eq!(
+            json_value_as_text(&serde_json::json!(42)).as_deref(),
+            Some("42")
+        );
+        assert_eq!(
+            json_value_as_text(&serde_json::json!("abc")).as_deref(),
+            Some("abc")
+        );
+        assert_eq!(
+            json_value_as_text(&serde_json::json!(true)).as_deref(),
+            Some("true")
+        );
+        assert_eq!(json_value_as_text(&serde_json::Value::Null), None);
+    }
+
+    #[test]
+    fn test_affected_tables_subtracts_earlier_counts() {
+        let key = |t: &str| ("public".to_string(), t.to_string());
+        let before = HashMap::from([(key("orders"), (2, 0))]);
+        let after = HashMap::from([
+            (key("orders"), (5, 0)),
+            (key("customers"), (1, 0)),
+            (key("reviews"), (0, 4)),
+            (key("untouched"), (0, 0)),
+        ]);
+
+        let tables = affected_tables(&before, after);
+
+        let summary: Vec<(&str, i64, i64)> = tables
+            .iter()
+            .map(|t| (t.table_name.as_str(), t.deleted, t.updated))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("orders", 3, 0), ("customers", 1, 0), ("reviews", 0, 4)]
+        );
+    }
+
+    /// Requires a PostgreSQL server in `TEST_SANDBOX_URL`
+    #[tokio::test]
+    #[ignore = "requires TEST_SANDBOX_URL"]
+    async fn test_delete_preview_counts_cascades_and_rolls_back() {
+        let url = std::env::var("TEST_SANDBOX_URL").expect("TEST_SANDBOX_URL is required");
+        let pool = PgPool::connect(&url).await.unwrap();
+        let schema = format!("delete_preview_{}", Uuid::new_v4().simple());
+        let setup = format!(
+            r#"
+            CREATE SCHEMA {s};
+            CREATE TABLE {s}.customers (id integer PRIMARY KEY);
+            CREATE TABLE {s}.orders (
+                id integer PRIMARY KEY,
+                customer_id integer REFERENCES {s}.customers ON DELETE CASCADE
+            );
+            CREATE TABLE {s}.order_lines (
+                id integer PRIMARY KEY,
+                order_id integer REFERENCES {s}.orders ON DELETE CASCADE
+            );
+            CREATE TABLE {s}.notes (
+                id integer PRIMARY KEY,
+                order_id integer REFERENCES {s}.orders ON DELETE SET NULL
+            );
+            CREATE TABLE {s}.invoices (
+                id integer PRIMARY KEY,
+                customer_id integer REFERENCES {s}.customers
+            );
+            INSERT INTO {s}.customers VALUES (1), (2), (3);
+            INSERT INTO {s}.orders VALUES (10, 1), (11, 1), (12, 2);
+            INSERT INTO {s}.order_lines VALUES (100, 10), (101, 10), (102, 11), (103, 12);
+            INSERT INTO {s}.notes VALUES (1000, 11);
+            INSERT INTO {s}.invoices VALUES (5000, 3);
+            "#,
+            s = schema
+        );
+        sqlx::raw_sql(&setup).execute(&pool).await.unwrap();
+
+        let preview = run_delete_preview(&pool, &schema, "customers", "id", "1").await;
+        let blocked = run_delete_preview(&pool, &schema, "customers", "id", "3").await;
+        let remaining: i64 = sqlx::query_scalar(&format!(
+            "SELECT (SELECT COUNT(*) FROM {s}.customers) + (SELECT COUNT(*) FROM {s}.order_lines)",
+            s = schema
+        ))
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        sqlx::query(&format!("DROP SCHEMA {} CASCADE", schema))
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let preview = preview.unwrap();
+        let summary: Vec<(&str, i64, i64)> = preview
+            .tables
+            .iter()
+            .map(|t| (t.table_name.as_str(), t.deleted, t.updated))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("order_lines", 3, 0),
+                ("orders", 2, 0),
+                ("customers", 1, 0),
+                ("notes", 0, 1),
+            ]
+        );
+        assert_eq!(preview.blocked_by, None);
+
+        let blocked = blocked.unwrap();
+        assert!(blocked.tables.is_empty());
+        assert_eq!(
+            blocked.blocked_by.as_deref(),
+            Some("invoices_customer_id_fkey")
+        );
+
+        // Both previews were rolled back
+        assert_eq!(remaining, 3 + 4);
+    }
 }
@@ -1,40 +1,57 @@
 //! Risk assessment handlers
 
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     Json,
 };
-use serde::Serialize;
-use sqlx::types::Json as SqlxJson;
-use sqlx::Row;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 use uuid::Uuid;
 
+use crate::auth::Owner;
 use crate::error::{ApiError, ApiResult};
 use crate::state::AppState;
 use db_viewer_core::domain::SchemaGraph;
-use db_viewer_core::risk::{RiskCalculator, RiskScore};
+use db_viewer_core::risk::{RiskCalculator, RiskOperation, RiskScore};
+
+/// Risk query parameters
+#[derive(Debug, Default, Deserialize)]
+pub struct RiskQuery {
+    /// `delete` (default) or `update`
+    #[serde(default)]
+    pub operation: RiskOperation,
+}
 
 /// Risk response with additional context
 #[derive(Debug, Serialize)]
 pub struct RiskResponse {
     #[serde(flatten)]
     pub risk: RiskScore,
+    /// Operation the score was calculated for
+    pub operation: RiskOperation,
     pub schema: String,
     pub table: String,
     pub column: Option<String>,
 }
 
 /// Get table-level risk score
+///
+/// `?operation=update` scores changing a row's key (`ON UPDATE` rules)
+/// instead of deleting it.
+#[tracing::instrument(skip_all, fields(dump_id = %id))]
 pub async fn get_table_risk(
     State(state): State<AppState>,
+    owner: Owner,
     Path((id, schema, table)): Path<(Uuid, String, String)>,
+    Query(query): Query<RiskQuery>,
 ) -> ApiResult<Json<RiskResponse>> {
-    let schema_graph = fetch_schema_graph(&state, id).await?;
+    let schema_graph = fetch_schema_graph(&state, id, owner.as_deref()).await?;
     let calc = RiskCalculator::new(&schema_graph);
-    let risk = calc.calculate_table_risk(&schema, &table);
+    let risk = calc.calculate_risk(query.operation, &schema, &table);
 
     Ok(Json(RiskResponse {
         risk,
+        operation: query.operation,
         schema,
         table,
         column: None,
@@ -42,40 +59,58 @@ pub async fn get_table_risk(
 }
 
 /// Get column-level risk score
+///
+/// Accepts the same `operation` parameter as [`get_table_risk`].
+#[tracing::instrument(skip_all, fields(dump_id = %id))]
 pub async fn get_column_risk(
     State(state): State<AppState>,
+    owner: Owner,
     Path((id, schema, table, column)): Path<(Uuid, String, String, String)>,
+    Query(query): Query<RiskQuery>,
 ) -> ApiResult<Json<RiskResponse>> {
-    let schema_graph = fetch_schema_graph(&state, id).await?;
+    let schema_graph = fetch_schema_graph(&state, id, owner.as_deref()).await?;
     let calc = RiskCalculator::new(&schema_graph);
 
     // For now, use 0 as referencing count (would need actual query in production)
-    let risk = calc.calculate_column_risk(&schema, &table, &column, 0);
+    let risk = calc.calculate_column_risk_for(query.operation, &schema, &table, &column, 0);
 
     Ok(Json(RiskResponse {
         risk,
+        operation: query.operation,
         schema,
         table,
         column: Some(column),
     }))
 }
 
-async fn fetch_schema_graph(state: &AppState, dump_id: Uuid) -> ApiResult<SchemaGraph> {
-    let row = sqlx::query("SELECT schema_graph FROM dump_schemas WHERE dump_id = $1")
-        .bind(dump_id)
-        .fetch_optional(&state.db_pool)
-        .await?;
+/// Schema graph of the dump's first analyzed database
+///
+/// Dumps of owners other than `owner` are reported as not found.
+async fn fetch_schema_graph(
+    state: &AppState,
+    dump_id: Uuid,
+    owner: Option<&str>,
+) -> ApiResult<Arc<SchemaGraph>> {
+    let database: Option<String> = sqlx::query_scalar(
+        r#"
+        SELECT s.database_name
+        FROM dump_schemas s
+        JOIN dumps d ON d.id = s.dump_id
+        WHERE s.dump_id = $1 AND ($2::text IS NULL OR d.owner = $2)
+        ORDER BY s.database_name
+        LIMIT 1
+        "#,
+    )
+    .bind(dump_id)
+    .bind(owner)
+    .fetch_optional(&state.db_pool)
+    .await?;
 
-    match row {
-        Some(row) => {
-            let SqlxJson(schema_graph): SqlxJson<SchemaGraph> = row.get("schema_graph");
-            Ok(schema_graph)
-        }
-        None => Err(ApiError::NotFound(format!(
-            "Schema not found for dump {}",
-            dump_id
-        ))),
-    }
+    let graph = match database {
+        Some(database) => state.schema_graph(dump_id, &database).await?,
+        None => None,
+    };
+    graph.ok_or_else(|| ApiError::NotFound(format!("Schema not found for dump {}", dump_id)))
 }
 
 #[cfg(test)]
@@ -91,6 +126,7 @@ mod tests {
                 level: RiskLevel::High,
                 reasons: vec!["Test reason".to_string()],
             },
+            operation: RiskOperation::Update,
             schema: "public".to_string(),
             table: "users".to_string(),
             column: Some("id".to_string()),
@@ -99,5 +135,17 @@ mod tests {
         let json = serde_json::to_string(&response).unwrap();
         assert!(json.contains("\"score\":75"));
         assert!(json.contains("\"level\":\"high\""));
+        assert!(json.contains("\"operation\":\"update\""));
+    }
+
+    #[test]
+    fn test_risk_query_operation() {
+        let parse = |query: &str| {
+            Query::<RiskQuery>::try_from_uri(&format!("/risk?{}", query).parse().unwrap())
+                .map(|Query(q)| q.operation)
+        };
+        assert_eq!(parse("").unwrap(), RiskOperation::Delete);
+        assert_eq!(parse("operation=update").unwrap(), RiskOperation::Update);
+        assert!(parse("operation=truncate").is_err());
     }
 }
@@ -5,12 +5,14 @@
 //! turning a user-friendly database name into the actual sandbox database and
 //! building a connection URL.
 
+use chrono::Utc;
+use db_viewer_core::domain::DumpStatus;
 use sqlx::postgres::PgPool;
 use sqlx::Row;
 use uuid::Uuid;
 
-use crate::config::AppConfig;
 use crate::error::{ApiError, ApiResult};
+use crate::state::AppState;
 
 /// Extract the original database name from a sandbox database name.
 ///
@@ -45,24 +47,94 @@ pub fn find_sandbox_db_name(
     }
 }
 
+/// Record that a dump's sandbox was queried, for idle eviction
+///
+/// Writes at most once a minute per dump. Failures are only logged: they
+/// must not fail the query that triggered them.
+pub async fn record_view(db_pool: &PgPool, dump_id: Uuid) {
+    let result = sqlx::query(
+        r#"
+        UPDATE dumps
+        SET last_viewed_at = NOW()
+        WHERE id = $1
+          AND (last_viewed_at IS NULL OR last_viewed_at < NOW() - INTERVAL '1 minute')
+        "#,
+    )
+    .bind(dump_id)
+    .execute(db_pool)
+    .await;
+    if let Err(e) = result {
+        tracing::warn!("Failed to record view of dump {}: {}", dump_id, e);
+    }
+}
+
+/// Fail with 409 Conflict when a dump is ARCHIVED, queueing it for restore
+///
+/// Idle eviction drops an archived dump's sandbox databases but keeps its
+/// upload, so the worker can restore it again. Only the request that moves
+/// the dump from ARCHIVED to RESTORING queues the restore, and drops the
+/// schema graphs cached from its old databases; the others get the same
+/// error.
+pub async fn reject_archived(state: &AppState, dump_id: Uuid, status: &str) -> ApiResult<()> {
+    if status != DumpStatus::Archived.as_str() {
+        return Ok(());
+    }
+
+    let requeued = sqlx::query(
+        r#"
+        UPDATE dumps
+        SET status = $1, error_message = NULL, claimed_at = NULL, attempts = 0,
+            next_retry_at = NULL, updated_at = $2
+        WHERE id = $3 AND status = $4
+        "#,
+    )
+    .bind(DumpStatus::Restoring.as_str())
+    .bind(Utc::now())
+    .bind(dump_id)
+    .bind(DumpStatus::Archived.as_str())
+    .execute(&state.db_pool)
+    .await?
+    .rows_affected()
+        > 0;
+    if requeued {
+        state.schema_cache.invalidate_dump(dump_id);
+        tracing::info!("Restoring archived dump {} on access", dump_id);
+    }
+
+    Err(ApiError::Conflict(format!(
+        "Dump {} was archived after going unviewed and is being restored; retry once it is READY",
+        dump_id
+    )))
+}
+
 /// Resolve the sandbox database name for a dump.
 ///
 /// Looks up `sandbox_db_name` / `sandbox_databases` from the `dumps` table and,
 /// given an optional user-friendly database name, returns the actual sandbox
 /// database to connect to. When no database is requested, the first available
-/// (or primary) database is used.
+/// (or primary) database is used. Dumps of owners other than `owner` are
+/// reported as not found.
 pub async fn resolve_sandbox_db(
-    db_pool: &PgPool,
+    state: &AppState,
     dump_id: Uuid,
+    owner: Option<&str>,
     requested_db: Option<&str>,
 ) -> ApiResult<String> {
-    let dump_row =
-        sqlx::query("SELECT sandbox_db_name, sandbox_databases FROM dumps WHERE id = $1")
-            .bind(dump_id)
-            .fetch_optional(db_pool)
-            .await?;
+    let dump_row = sqlx::query(
+        r#"
+        SELECT status, sandbox_db_name, sandbox_databases
+        FROM dumps
+        WHERE id = $1 AND ($2::text IS NULL OR owner = $2)
+        "#,
+    )
+    .bind(dump_id)
+    .bind(owner)
+    .fetch_optional(&state.db_pool)
+    .await?;
 
     let row = dump_row.ok_or_else(|| ApiError::NotFound(format!("Dump {} not found", dump_id)))?;
+    reject_archived(state, dump_id, row.get("status")).await?;
+    record_view(&state.db_pool, dump_id).await;
 
     let primary_db: Option<String> = row.get("sandbox_db_name");
     let available_dbs: Option<Vec<String>> = row.get("sandbox_databases");
@@ -101,21 +173,6 @@ pub async fn resolve_sandbox_db(
     }
 }
 
-/// Build a PostgreSQL connection URL for a sandbox database.
-pub fn build_sandbox_url(config: &AppConfig, db_name: &str) -> String {
-    if let Some(ref password) = config.sandbox_password {
-        format!(
-            "postgres://{}:{}@{}:{}/{}",
-            config.sandbox_user, password, config.sandbox_host, config.sandbox_port, db_name
-        )
-    } else {
-        format!(
-            "postgres://{}@{}:{}/{}",
-            config.sandbox_user, config.sandbox_host, config.sandbox_port, db_name
-        )
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -143,6 +200,46 @@ mod tests {
         );
     }
 
+    /// Requires a migrated metadata database in `TEST_DATABASE_URL`
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_archived_dump_is_queued_for_restore() {
+        let state = crate::handlers::dumps::tests::test_state().await;
+        let pool = &state.db_pool;
+        let id = Uuid::new_v4();
+        sqlx::query(
+            r#"
+            INSERT INTO dumps (id, slug, status, sandbox_db_name, attempts, expires_at)
+            VALUES ($1, $2, 'ARCHIVED', 'sandbox_archived', 2, NOW() + INTERVAL '1 hour')
+            "#,
+        )
+        .bind(id)
+        .bind(format!("archived-test-{}", id))
+        .execute(pool)
+        .await
+        .unwrap();
+
+        let resolved = resolve_sandbox_db(&state, id, None, None).await;
+        let row = sqlx::query("SELECT status, attempts FROM dumps WHERE id = $1")
+            .bind(id)
+            .fetch_one(pool)
+            .await
+            .unwrap();
+        let status: String = row.get("status");
+        let attempts: i32 = row.get("attempts");
+        let again = reject_archived(&state, id, &status).await;
+        sqlx::query("DELETE FROM dumps WHERE id = $1")
+            .bind(id)
+            .execute(pool)
+            .await
+            .unwrap();
+
+        assert!(matches!(resolved, Err(ApiError::Conflict(_))));
+        assert_eq!(status, "RESTORING");
+        assert_eq!(attempts, 0);
+        assert!(again.is_ok());
+    }
+
     #[test]
     fn test_find_sandbox_db_name_none() {
         let dbs = Some(vec!["sandbox_x_salesdb".to_string()]);
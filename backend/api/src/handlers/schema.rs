@@ -1,27 +1,42 @@
 //! Schema and data handlers
 
+use std::collections::HashMap;
+use std::sync::Arc;
+
 use axum::{
+    body::Body,
     extract::{Path, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
     Json,
 };
+use futures::StreamExt;
+use parquet::errors::ParquetError;
 use serde::{Deserialize, Serialize};
-use sqlx::types::Json as SqlxJson;
 use sqlx::Row;
 use uuid::Uuid;
 
+use crate::auth::Owner;
 use crate::error::{ApiError, ApiResult};
 use crate::handlers::sandbox::{
-    build_sandbox_url, extract_original_db_name, find_sandbox_db_name, resolve_sandbox_db,
+    extract_original_db_name, find_sandbox_db_name, record_view, reject_archived,
+    resolve_sandbox_db,
 };
+use crate::parquet_export::{ParquetColumn, ParquetEncoder};
 use crate::state::AppState;
-use db_viewer_core::domain::SchemaGraph;
-use db_viewer_core::schema::generate_mermaid_er;
-
-/// Quote a SQL identifier safely by wrapping it in double quotes and escaping
-/// any embedded double quotes.
-fn quote_ident(s: &str) -> String {
-    format!("\"{}\"", s.replace('"', "\"\""))
-}
+use db_viewer_core::adapter::{DbAdapter, PostgresAdapter};
+use db_viewer_core::ddl;
+use db_viewer_core::domain::{
+    CheckConstraint, ColumnInfo, ForeignKey, IndexInfo, RelationDirection, SchemaGraph,
+    SequenceInfo, TableInfo, UniqueConstraint,
+};
+use db_viewer_core::filter::{parse_filter, render_filter, RenderedFilter};
+use db_viewer_core::schema::{
+    find_fk_cycles, find_referencing_tables, generate_dot, generate_json_schema,
+    generate_mermaid_er, group_partitions, schema_stats, topological_table_order, validate_graph,
+    GraphWarning, SchemaStats,
+};
+use db_viewer_core::sql_gen::quote_ident;
 
 /// Escape a value for use inside a `LIKE` / `ILIKE` pattern so that `%`, `_`
 /// and `\` are treated literally (used together with `ESCAPE '\'`).
@@ -36,6 +51,8 @@ fn escape_like(s: &str) -> String {
 pub struct SchemaResponse {
     pub schema_graph: SchemaGraph,
     pub mermaid_er: String,
+    /// Table, row and foreign key counts per schema
+    pub schema_stats: Vec<SchemaStats>,
 }
 
 /// Schema query parameters
@@ -43,32 +60,788 @@ pub struct SchemaResponse {
 pub struct SchemaQuery {
     /// Optional database name for pg_dumpall dumps with multiple databases
     pub database: Option<String>,
+    /// Replace estimated row counts with exact `COUNT(*)` results (slow on
+    /// large tables)
+    #[serde(default)]
+    pub exact: bool,
+    /// Response format: `json` (default) or `dot` for a Graphviz digraph
+    pub format: Option<String>,
+    /// Roll partitions into their partitioned table, summing row counts
+    #[serde(default)]
+    pub group_partitions: bool,
 }
 
 /// Get schema for a dump
+#[tracing::instrument(skip_all, fields(dump_id = %id))]
 pub async fn get_schema(
     State(state): State<AppState>,
+    owner: Owner,
     Path(id): Path<Uuid>,
     Query(query): Query<SchemaQuery>,
-) -> ApiResult<Json<SchemaResponse>> {
+) -> ApiResult<Response> {
+    let as_dot = match query.format.as_deref() {
+        None | Some("json") => false,
+        Some("dot") => true,
+        Some(other) => {
+            return Err(ApiError::BadRequest(format!(
+                "Unsupported schema format '{}'. Use 'json' or 'dot'",
+                other
+            )))
+        }
+    };
+
+    let (requested_db, schema_graph) =
+        load_cached_schema(&state, id, owner.as_deref(), query.database.as_deref()).await?;
+    let mut schema_graph = Arc::unwrap_or_clone(schema_graph);
+
+    // Before exact counts, which count a parent's rows across its partitions
+    if query.group_partitions {
+        schema_graph = group_partitions(&schema_graph);
+    }
+
+    if query.exact {
+        let sandbox_pool = state.sandbox_pools.get(&requested_db).await?;
+        for table in &mut schema_graph.tables {
+            let count_query = format!(
+                "SELECT COUNT(*) as cnt FROM {}.{}",
+                quote_ident(&table.schema_name),
+                quote_ident(&table.table_name)
+            );
+            let row = sqlx::query(&count_query).fetch_one(&*sandbox_pool).await?;
+            table.estimated_row_count = row.get("cnt");
+        }
+    }
+
+    if as_dot {
+        return Ok((
+            [(header::CONTENT_TYPE, "text/vnd.graphviz; charset=utf-8")],
+            generate_dot(&schema_graph),
+        )
+            .into_response());
+    }
+
+    let mermaid_er = generate_mermaid_er(&schema_graph);
+    let schema_stats = schema_stats(&schema_graph);
+
+    Ok(Json(SchemaResponse {
+        schema_graph,
+        mermaid_er,
+        schema_stats,
+    })
+    .into_response())
+}
+
+/// Query parameters for schema graph analyses (cycles, restore order)
+#[derive(Debug, Deserialize)]
+pub struct SchemaGraphQuery {
+    /// Optional database name for pg_dumpall dumps with multiple databases
+    pub database: Option<String>,
+}
+
+/// A table reference in schema graph analysis responses
+#[derive(Debug, Serialize)]
+pub struct TableRef {
+    pub schema: String,
+    pub table: String,
+}
+
+/// Foreign key cycles response
+#[derive(Debug, Serialize)]
+pub struct FkCyclesResponse {
+    pub cycles: Vec<Vec<TableRef>>,
+}
+
+/// List foreign key cycles (including self-references) in a dump's schema
+#[tracing::instrument(skip_all, fields(dump_id = %id))]
+pub async fn get_fk_cycles(
+    State(state): State<AppState>,
+    owner: Owner,
+    Path(id): Path<Uuid>,
+    Query(query): Query<SchemaGraphQuery>,
+) -> ApiResult<Json<FkCyclesResponse>> {
+    let (_, schema_graph) =
+        load_cached_schema(&state, id, owner.as_deref(), query.database.as_deref()).await?;
+
+    let cycles = find_fk_cycles(&schema_graph)
+        .into_iter()
+        .map(|cycle| {
+            cycle
+                .into_iter()
+                .map(|(schema, table)| TableRef { schema, table })
+                .collect()
+        })
+        .collect();
+
+    Ok(Json(FkCyclesResponse { cycles }))
+}
+
+/// Schema graph validation response
+#[derive(Debug, Serialize)]
+pub struct SchemaValidationResponse {
+    /// True when no warnings were found
+    pub valid: bool,
+    pub warnings: Vec<GraphWarning>,
+}
+
+/// Check a dump's schema graph for dangling or malformed foreign keys
+///
+/// Warnings usually mean some tables failed to restore, so the ER diagram
+/// shows relationships that cannot be followed.
+#[tracing::instrument(skip_all, fields(dump_id = %id))]
+pub async fn validate_schema(
+    State(state): State<AppState>,
+    owner: Owner,
+    Path(id): Path<Uuid>,
+    Query(query): Query<SchemaGraphQuery>,
+) -> ApiResult<Json<SchemaValidationResponse>> {
+    let (_, schema_graph) =
+        load_cached_schema(&state, id, owner.as_deref(), query.database.as_deref()).await?;
+    let warnings = validate_graph(&schema_graph);
+
+    Ok(Json(SchemaValidationResponse {
+        valid: warnings.is_empty(),
+        warnings,
+    }))
+}
+
+/// Restore order response
+#[derive(Debug, Serialize)]
+pub struct RestoreOrderResponse {
+    pub tables: Vec<TableRef>,
+}
+
+/// Get a table creation order in which referenced tables come first
+///
+/// Returns 409 Conflict listing the affected tables when the foreign keys
+/// form a cycle.
+#[tracing::instrument(skip_all, fields(dump_id = %id))]
+pub async fn get_restore_order(
+    State(state): State<AppState>,
+    owner: Owner,
+    Path(id): Path<Uuid>,
+    Query(query): Query<SchemaGraphQuery>,
+) -> ApiResult<Json<RestoreOrderResponse>> {
+    let (_, schema_graph) =
+        load_cached_schema(&state, id, owner.as_deref(), query.database.as_deref()).await?;
+
+    let tables = topological_table_order(&schema_graph)
+        .map_err(|e| ApiError::Conflict(e.to_string()))?
+        .into_iter()
+        .map(|(schema, table)| TableRef { schema, table })
+        .collect();
+
+    Ok(Json(RestoreOrderResponse { tables }))
+}
+
+/// Download the DDL recreating a dump's schema
+///
+/// Statements are rebuilt from the cached schema graph in dependency order;
+/// see [`ddl::schema_script`] for what is covered.
+#[tracing::instrument(skip_all, fields(dump_id = %id))]
+pub async fn get_schema_sql(
+    State(state): State<AppState>,
+    owner: Owner,
+    Path(id): Path<Uuid>,
+    Query(query): Query<SchemaGraphQuery>,
+) -> ApiResult<Response> {
+    let (_, schema_graph) =
+        load_cached_schema(&state, id, owner.as_deref(), query.database.as_deref()).await?;
+
+    let filename = match &query.database {
+        Some(database) => format!("schema-{}-{}.sql", id, database).replace('"', ""),
+        None => format!("schema-{}.sql", id),
+    };
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                "text/plain; charset=utf-8".to_string(),
+            ),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+        ],
+        ddl::schema_script(&schema_graph),
+    )
+        .into_response())
+}
+
+/// Sequence list response
+#[derive(Debug, Serialize)]
+pub struct SequenceListResponse {
+    pub sequences: Vec<SequenceInfo>,
+}
+
+/// List a dump's sequences with their owning column and last value
+///
+/// Values are as of the restore, read from the cached schema graph.
+#[tracing::instrument(skip_all, fields(dump_id = %id))]
+pub async fn list_sequences(
+    State(state): State<AppState>,
+    owner: Owner,
+    Path(id): Path<Uuid>,
+    Query(query): Query<SchemaGraphQuery>,
+) -> ApiResult<Json<SequenceListResponse>> {
+    let (_, schema_graph) =
+        load_cached_schema(&state, id, owner.as_deref(), query.database.as_deref()).await?;
+
+    Ok(Json(SequenceListResponse {
+        sequences: schema_graph.sequences.clone(),
+    }))
+}
+
+/// Table JSON Schema query parameters
+#[derive(Debug, Deserialize)]
+pub struct TableSchemaQuery {
+    pub schema: Option<String>,
+    /// Optional database name for pg_dumpall dumps with multiple databases
+    pub database: Option<String>,
+}
+
+/// Describe a table's rows as a JSON Schema document
+///
+/// Built from the cached schema graph, so no sandbox connection is needed.
+#[tracing::instrument(skip_all, fields(dump_id = %id))]
+pub async fn get_table_json_schema(
+    State(state): State<AppState>,
+    owner: Owner,
+    Path((id, table_path)): Path<(Uuid, String)>,
+    Query(query): Query<TableSchemaQuery>,
+) -> ApiResult<Json<serde_json::Value>> {
+    // Parse schema.table format or use query parameter
+    let (schema, table) = match table_path.split_once('.') {
+        Some((schema, table)) => (schema.to_string(), table.to_string()),
+        None => (
+            query.schema.unwrap_or_else(|| "public".to_string()),
+            table_path,
+        ),
+    };
+
+    let (_, schema_graph) =
+        load_cached_schema(&state, id, owner.as_deref(), query.database.as_deref()).await?;
+    let table_info = schema_graph
+        .tables
+        .iter()
+        .find(|t| t.schema_name == schema && t.table_name == table)
+        .ok_or_else(|| ApiError::NotFound(format!("Table {}.{} not found", schema, table)))?;
+
+    Ok(Json(generate_json_schema(&schema_graph, table_info)))
+}
+
+/// Primary key of a described table
+#[derive(Debug, Serialize)]
+pub struct PrimaryKeyInfo {
+    /// Constraint name, when index metadata is available
+    pub name: Option<String>,
+    pub columns: Vec<String>,
+}
+
+/// Full definition of a table
+#[derive(Debug, Serialize)]
+pub struct TableDescription {
+    pub schema: String,
+    pub table: String,
+    pub comment: Option<String>,
+    pub estimated_row_count: i64,
+    pub columns: Vec<ColumnInfo>,
+    pub primary_key: Option<PrimaryKeyInfo>,
+    pub unique_constraints: Vec<UniqueConstraint>,
+    pub check_constraints: Vec<CheckConstraint>,
+    pub indexes: Vec<IndexInfo>,
+    /// Foreign keys of this table
+    pub outbound_foreign_keys: Vec<ForeignKey>,
+    /// Foreign keys of other tables referencing this one
+    pub inbound_foreign_keys: Vec<ForeignKey>,
+    /// `CREATE TABLE` (and `CREATE INDEX`) statements rebuilt from the above
+    pub ddl: String,
+}
+
+/// Combine the cached graph with the constraints queried from the sandbox
+///
+/// `indexes` are the table's indexes; indexes backing the primary key or a
+/// unique constraint appear in the DDL as table constraints, the others as
+/// `CREATE INDEX` statements.
+fn describe_table_definition(
+    schema_graph: &SchemaGraph,
+    table: &TableInfo,
+    indexes: Vec<IndexInfo>,
+    unique_constraints: Vec<UniqueConstraint>,
+    check_constraints: Vec<CheckConstraint>,
+) -> TableDescription {
+    let (outbound_foreign_keys, inbound_foreign_keys): (Vec<_>, Vec<_>) = schema_graph
+        .foreign_keys
+        .iter()
+        .filter(|fk| {
+            (fk.source_schema == table.schema_name && fk.source_table == table.table_name)
+                || (fk.target_schema == table.schema_name && fk.target_table == table.table_name)
+        })
+        .cloned()
+        .partition(|fk| {
+            fk.source_schema == table.schema_name && fk.source_table == table.table_name
+        });
+
+    let pk_columns = ddl::primary_key_columns(&indexes, table);
+    let primary_key = (!pk_columns.is_empty()).then(|| PrimaryKeyInfo {
+        name: ddl::primary_key_index(&indexes, table).map(|i| i.name.clone()),
+        columns: pk_columns,
+    });
+
+    let constraints: Vec<String> = ddl::primary_key_clause(&indexes, table)
+        .into_iter()
+        .chain(unique_constraints.iter().map(ddl::unique_clause))
+        .chain(check_constraints.iter().map(ddl::check_clause))
+        .chain(outbound_foreign_keys.iter().map(ddl::foreign_key_clause))
+        .collect();
+    let mut statements = vec![ddl::create_table(table, &constraints)];
+    statements.extend(
+        indexes
+            .iter()
+            .filter(|i| !i.is_primary && !unique_constraints.iter().any(|u| u.name == i.name))
+            .map(ddl::create_index),
+    );
+
+    TableDescription {
+        schema: table.schema_name.clone(),
+        table: table.table_name.clone(),
+        comment: table.comment.clone(),
+        estimated_row_count: table.estimated_row_count,
+        columns: table.columns.clone(),
+        primary_key,
+        unique_constraints,
+        check_constraints,
+        indexes,
+        outbound_foreign_keys,
+        inbound_foreign_keys,
+        ddl: statements.join("\n\n"),
+    }
+}
+
+/// Describe a table: columns, keys, constraints, indexes, foreign keys in
+/// both directions and a reconstructed `CREATE TABLE`
+///
+/// Columns, indexes and foreign keys come from the cached schema graph;
+/// unique and check constraints, which the graph does not hold, are queried
+/// from the sandbox. Graphs cached before indexes were introspected have
+/// their indexes queried as well.
+#[tracing::instrument(skip_all, fields(dump_id = %id))]
+pub async fn describe_table(
+    State(state): State<AppState>,
+    owner: Owner,
+    Path((id, table_path)): Path<(Uuid, String)>,
+    Query(query): Query<TableSchemaQuery>,
+) -> ApiResult<Json<TableDescription>> {
+    let (schema, table) = match table_path.split_once('.') {
+        Some((schema, table)) => (schema.to_string(), table.to_string()),
+        None => (
+            query.schema.unwrap_or_else(|| "public".to_string()),
+            table_path,
+        ),
+    };
+
+    let (sandbox_db, schema_graph) =
+        load_cached_schema(&state, id, owner.as_deref(), query.database.as_deref()).await?;
+    let table_info = schema_graph
+        .tables
+        .iter()
+        .find(|t| t.schema_name == schema && t.table_name == table)
+        .ok_or_else(|| ApiError::NotFound(format!("Table {}.{} not found", schema, table)))?;
+
+    let pool = state.sandbox_pools.get(&sandbox_db).await?;
+    let adapter = PostgresAdapter::new((*pool).clone(), state.config.sandbox_connection())
+        .with_db_pool(&sandbox_db, (*pool).clone());
+    let unique_constraints = adapter
+        .list_unique_constraints(&sandbox_db, &schema, &table)
+        .await?;
+    let check_constraints = adapter
+        .list_check_constraints(&sandbox_db, &schema, &table)
+        .await?;
+    let live_indexes;
+    let all_indexes = if schema_graph.indexes.is_empty() {
+        live_indexes = adapter.list_indexes(&sandbox_db).await?;
+        &live_indexes
+    } else {
+        &schema_graph.indexes
+    };
+    let indexes = all_indexes
+        .iter()
+        .filter(|i| i.schema_name == schema && i.table_name == table)
+        .cloned()
+        .collect();
+
+    Ok(Json(describe_table_definition(
+        &schema_graph,
+        table_info,
+        indexes,
+        unique_constraints,
+        check_constraints,
+    )))
+}
+
+/// Reverse dependency query parameters
+#[derive(Debug, Default, Deserialize)]
+pub struct ReferentsQuery {
+    pub schema: Option<String>,
+    /// Optional database name for pg_dumpall dumps with multiple databases
+    pub database: Option<String>,
+    /// Foreign key hops to follow (default 2, at most 5)
+    pub hops: Option<usize>,
+}
+
+/// A table referencing the requested one, directly or transitively
+#[derive(Debug, Serialize)]
+pub struct Referent {
+    pub schema: String,
+    pub table: String,
+    pub hop_count: usize,
+    /// Constraint names from the requested table out to this one
+    pub path: Vec<String>,
+}
+
+/// Tables that would be affected by dropping or changing a table
+#[derive(Debug, Serialize)]
+pub struct ReferentsResponse {
+    pub schema: String,
+    pub table: String,
+    pub hops: usize,
+    /// Closest referents first
+    pub referents: Vec<Referent>,
+}
+
+/// List the tables that reference a table through foreign keys, following
+/// referencing tables up to `hops` levels
+///
+/// Built from the cached schema graph, so no sandbox connection is needed.
+#[tracing::instrument(skip_all, fields(dump_id = %id))]
+pub async fn get_table_referents(
+    State(state): State<AppState>,
+    owner: Owner,
+    Path((id, table_path)): Path<(Uuid, String)>,
+    Query(query): Query<ReferentsQuery>,
+) -> ApiResult<Json<ReferentsResponse>> {
+    let (schema, table) = match table_path.split_once('.') {
+        Some((schema, table)) => (schema.to_string(), table.to_string()),
+        None => (
+            query.schema.unwrap_or_else(|| "public".to_string()),
+            table_path,
+        ),
+    };
+    let hops = query.hops.unwrap_or(2).clamp(1, 5);
+
+    let (_, schema_graph) =
+        load_cached_schema(&state, id, owner.as_deref(), query.database.as_deref()).await?;
+    if !schema_graph
+        .tables
+        .iter()
+        .any(|t| t.schema_name == schema && t.table_name == table)
+    {
+        return Err(ApiError::NotFound(format!(
+            "Table {}.{} not found",
+            schema, table
+        )));
+    }
+
+    let referents = find_referencing_tables(&schema_graph, &schema, &table, hops)
+        .into_iter()
+        .map(|r| Referent {
+            schema: r.schema,
+            table: r.table,
+            hop_count: r.hop_count,
+            path: r.path,
+        })
+        .collect();
+
+    Ok(Json(ReferentsResponse {
+        schema,
+        table,
+        hops,
+        referents,
+    }))
+}
+
+/// Default and maximum page size of the table list
+const TABLE_LIST_DEFAULT_LIMIT: usize = 100;
+const TABLE_LIST_MAX_LIMIT: usize = 1000;
+
+/// Table list query parameters
+#[derive(Debug, Default, Deserialize)]
+pub struct TableListQuery {
+    /// Optional database name for pg_dumpall dumps with multiple databases
+    pub database: Option<String>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+    /// Only list tables in this schema
+    pub schema: Option<String>,
+    /// Case-insensitive substring of the table name
+    pub q: Option<String>,
+    /// `name` (default, ascending) or `rows` (largest first)
+    pub sort: Option<String>,
+    /// Include each table's columns
+    #[serde(default)]
+    pub columns: bool,
+}
+
+/// Table list response
+#[derive(Debug, Serialize)]
+pub struct TableListResponse {
+    pub tables: Vec<TableInfo>,
+    /// Number of tables matching the filters, across all pages
+    pub total_count: usize,
+    pub limit: usize,
+    pub offset: usize,
+}
+
+/// Filter, sort and paginate tables; returns the page and the match count
+fn table_list_page(
+    tables: Vec<TableInfo>,
+    query: &TableListQuery,
+) -> ApiResult<(Vec<TableInfo>, usize)> {
+    let by_rows = match query.sort.as_deref() {
+        None | Some("name") => false,
+        Some("rows") => true,
+        Some(other) => {
+            return Err(ApiError::BadRequest(format!(
+                "Unsupported sort '{}'. Use 'name' or 'rows'",
+                other
+            )))
+        }
+    };
+    let needle = query
+        .q
+        .as_deref()
+        .map(|q| q.trim().to_lowercase())
+        .filter(|q| !q.is_empty());
+
+    let mut matching: Vec<TableInfo> = tables
+        .into_iter()
+        .filter(|t| query.schema.as_ref().is_none_or(|s| t.schema_name == *s))
+        .filter(|t| {
+            needle
+                .as_ref()
+                .is_none_or(|n| t.table_name.to_lowercase().contains(n))
+        })
+        .collect();
+
+    let by_name = |a: &TableInfo, b: &TableInfo| {
+        (&a.schema_name, &a.table_name).cmp(&(&b.schema_name, &b.table_name))
+    };
+    if by_rows {
+        matching.sort_by(|a, b| {
+            b.estimated_row_count
+                .cmp(&a.estimated_row_count)
+                .then_with(|| by_name(a, b))
+        });
+    } else {
+        matching.sort_by(by_name);
+    }
+
+    let total = matching.len();
+    let limit = query
+        .limit
+        .unwrap_or(TABLE_LIST_DEFAULT_LIMIT)
+        .min(TABLE_LIST_MAX_LIMIT);
+    let page = matching
+        .into_iter()
+        .skip(query.offset.unwrap_or(0))
+        .take(limit)
+        .map(|mut t| {
+            if !query.columns {
+                t.columns = Vec::new();
+            }
+            t
+        })
+        .collect();
+
+    Ok((page, total))
+}
+
+/// List a dump's tables page by page from the cached schema graph
+#[tracing::instrument(skip_all, fields(dump_id = %id))]
+pub async fn list_tables(
+    State(state): State<AppState>,
+    owner: Owner,
+    Path(id): Path<Uuid>,
+    Query(query): Query<TableListQuery>,
+) -> ApiResult<Json<TableListResponse>> {
+    let (_, schema_graph) =
+        load_cached_schema(&state, id, owner.as_deref(), query.database.as_deref()).await?;
+    let (tables, total_count) = table_list_page(schema_graph.tables.clone(), &query)?;
+
+    Ok(Json(TableListResponse {
+        tables,
+        total_count,
+        limit: query
+            .limit
+            .unwrap_or(TABLE_LIST_DEFAULT_LIMIT)
+            .min(TABLE_LIST_MAX_LIMIT),
+        offset: query.offset.unwrap_or(0),
+    }))
+}
+
+/// Default and maximum number of tables returned by the largest-tables list
+const TOP_TABLES_DEFAULT_LIMIT: usize = 10;
+const TOP_TABLES_MAX_LIMIT: usize = 100;
+
+/// Largest-tables query parameters
+#[derive(Debug, Default, Deserialize)]
+pub struct TopTablesQuery {
+    /// Optional database name for pg_dumpall dumps with multiple databases
+    pub database: Option<String>,
+    /// `rows` (default, estimated row count) or `size` (on-disk bytes)
+    pub by: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// One of the largest tables
+#[derive(Debug, Serialize)]
+pub struct TopTable {
+    pub schema_name: String,
+    pub table_name: String,
+    pub estimated_row_count: i64,
+    /// `pg_total_relation_size` (table, indexes and TOAST); only with `by=size`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size_bytes: Option<i64>,
+}
+
+/// Largest tables response
+#[derive(Debug, Serialize)]
+pub struct TopTablesResponse {
+    pub by: String,
+    pub tables: Vec<TopTable>,
+}
+
+/// The `limit` tables with the most estimated rows, ties by name
+fn top_tables_by_rows(mut tables: Vec<TableInfo>, limit: usize) -> Vec<TopTable> {
+    tables.sort_by(|a, b| {
+        b.estimated_row_count
+            .cmp(&a.estimated_row_count)
+            .then_with(|| (&a.schema_name, &a.table_name).cmp(&(&b.schema_name, &b.table_name)))
+    });
+    tables
+        .into_iter()
+        .take(limit)
+        .map(|t| TopTable {
+            schema_name: t.schema_name,
+            table_name: t.table_name,
+            estimated_row_count: t.estimated_row_count,
+            size_bytes: None,
+        })
+        .collect()
+}
+
+/// List a dump's largest tables
+///
+/// GET /api/dumps/:id/tables/top?by=rows|size&limit=N
+///
+/// `by=rows` only reads the cached schema graph; `by=size` asks the sandbox
+/// for each table's total relation size.
+#[tracing::instrument(skip_all, fields(dump_id = %id))]
+pub async fn get_top_tables(
+    State(state): State<AppState>,
+    owner: Owner,
+    Path(id): Path<Uuid>,
+    Query(query): Query<TopTablesQuery>,
+) -> ApiResult<Json<TopTablesResponse>> {
+    let by = query.by.as_deref().unwrap_or("rows");
+    if by != "rows" && by != "size" {
+        return Err(ApiError::BadRequest(format!(
+            "Unsupported ordering '{}'. Use 'rows' or 'size'",
+            by
+        )));
+    }
+    let limit = query
+        .limit
+        .unwrap_or(TOP_TABLES_DEFAULT_LIMIT)
+        .min(TOP_TABLES_MAX_LIMIT);
+
+    let (sandbox_db, schema_graph) =
+        load_cached_schema(&state, id, owner.as_deref(), query.database.as_deref()).await?;
+
+    let tables = if by == "rows" {
+        top_tables_by_rows(schema_graph.tables.clone(), limit)
+    } else {
+        let sandbox_pool = state.sandbox_pools.get(&sandbox_db).await?;
+        let sizes: Vec<(String, String, i64)> = sqlx::query_as(
+            r#"
+            SELECT n.nspname::text, c.relname::text, pg_total_relation_size(c.oid)
+            FROM pg_class c
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            WHERE c.relkind = 'r'
+              AND n.nspname NOT IN ('pg_catalog', 'information_schema')
+              AND n.nspname NOT LIKE 'pg_toast%'
+            ORDER BY 3 DESC, 1, 2
+            LIMIT $1
+            "#,
+        )
+        .bind(limit as i64)
+        .fetch_all(&*sandbox_pool)
+        .await?;
+
+        // Row counts come from the cached graph, as for by=rows
+        let rows: HashMap<(&str, &str), i64> = schema_graph
+            .tables
+            .iter()
+            .map(|t| {
+                (
+                    (t.schema_name.as_str(), t.table_name.as_str()),
+                    t.estimated_row_count,
+                )
+            })
+            .collect();
+        sizes
+            .into_iter()
+            .map(|(schema_name, table_name, size)| TopTable {
+                estimated_row_count: rows
+                    .get(&(schema_name.as_str(), table_name.as_str()))
+                    .copied()
+                    .unwrap_or(0),
+                schema_name,
+                table_name,
+                size_bytes: Some(size),
+            })
+            .collect()
+    };
+
+    Ok(Json(TopTablesResponse {
+        by: by.to_string(),
+        tables,
+    }))
+}
+
+/// Load the cached schema graph of a READY dump
+///
+/// `database` is the user-friendly database name for pg_dumpall dumps; when
+/// omitted the first available database is used. Returns the resolved
+/// sandbox database name together with its schema graph. Dumps of owners
+/// other than `owner` are reported as not found.
+async fn load_cached_schema(
+    state: &AppState,
+    id: Uuid,
+    owner: Option<&str>,
+    database: Option<&str>,
+) -> ApiResult<(String, Arc<SchemaGraph>)> {
     // First, fetch dump info
     let dump_row = sqlx::query(
         r#"
-        SELECT sandbox_databases, sandbox_db_name
+        SELECT status, sandbox_databases, sandbox_db_name
         FROM dumps
-        WHERE id = $1 AND status = 'READY'
+        WHERE id = $1 AND status IN ('READY', 'ARCHIVED')
+          AND ($2::text IS NULL OR owner = $2)
         "#,
     )
     .bind(id)
+    .bind(owner)
     .fetch_optional(&state.db_pool)
     .await?
     .ok_or_else(|| ApiError::NotFound(format!("Dump {} not found or not ready", id)))?;
+    reject_archived(state, id, dump_row.get("status")).await?;
+    record_view(&state.db_pool, id).await;
 
     let available_dbs: Option<Vec<String>> = dump_row.get("sandbox_databases");
     let primary_db: Option<String> = dump_row.get("sandbox_db_name");
 
     // Determine which database to use
-    let requested_db = if let Some(ref user_db) = query.database {
+    let requested_db = if let Some(user_db) = database {
         // User requested a specific database by user-friendly name
         // Find the corresponding sandbox database name
         let sandbox_db = find_sandbox_db_name(&available_dbs, user_db).or_else(|| {
@@ -109,29 +882,8 @@ pub async fn get_schema(
             .ok_or_else(|| ApiError::NotFound(format!("No database found for dump {}", id)))?
     };
 
-    // Fetch cached schema from metadata DB
-    let row = sqlx::query(
-        r#"
-        SELECT schema_graph
-        FROM dump_schemas
-        WHERE dump_id = $1 AND database_name = $2
-        "#,
-    )
-    .bind(id)
-    .bind(&requested_db)
-    .fetch_optional(&state.db_pool)
-    .await?;
-
-    match row {
-        Some(row) => {
-            let SqlxJson(schema_graph): SqlxJson<SchemaGraph> = row.get("schema_graph");
-            let mermaid_er = generate_mermaid_er(&schema_graph);
-
-            Ok(Json(SchemaResponse {
-                schema_graph,
-                mermaid_er,
-            }))
-        }
+    match state.schema_graph(id, &requested_db).await? {
+        Some(schema_graph) => Ok((requested_db, schema_graph)),
         None => Err(ApiError::NotFound(format!(
             "Schema not found for dump {} database '{}'. Ensure the dump is in READY state.",
             id, requested_db
@@ -150,11 +902,38 @@ pub struct TableDataQuery {
     /// Optional column to restrict the filter to. When omitted, the filter is
     /// applied across all columns (free-text search).
     pub filter_column: Option<String>,
+    /// Typed filter expression (`column op value` clauses joined by `AND`),
+    /// combined with `filter` when both are given. See [`parse_filter`].
+    #[serde(rename = "where")]
+    pub where_expr: Option<String>,
+    /// Keyset cursor returned as `next_cursor` by the previous page. Only
+    /// honoured for tables with a primary key; `offset` is ignored when set.
+    pub after: Option<String>,
     /// Optional database name for pg_dumpall dumps with multiple databases
     pub database: Option<String>,
+    /// Always count rows exactly; by default unfiltered tables use the
+    /// planner estimate to avoid a full scan
+    #[serde(default)]
+    pub exact: bool,
+}
+
+/// How a page of table data was selected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PaginationMode {
+    /// `LIMIT/OFFSET`; used for tables without a primary key or when no
+    /// cursor was supplied
+    Offset,
+    /// `WHERE pk > cursor ORDER BY pk LIMIT n`; cost does not grow with depth
+    Keyset,
 }
 
 /// Table data response
+///
+/// Tables with a primary key are returned in key order together with a
+/// `next_cursor`, which can be passed back as `after` to fetch the following
+/// page by keyset instead of `offset`. Tables without a primary key only
+/// support `offset` paging and never return a cursor.
 #[derive(Debug, Serialize)]
 pub struct TableDataResponse {
     pub schema: String,
@@ -162,42 +941,722 @@ pub struct TableDataResponse {
     pub columns: Vec<String>,
     pub rows: Vec<serde_json::Value>,
     pub total_count: i64,
+    /// Whether `total_count` is an exact count rather than an estimate
+    pub total_count_exact: bool,
     pub limit: usize,
     pub offset: usize,
     /// Echo of the applied filter (if any).
     pub filter: Option<String>,
+    /// Echo of the applied filter expression (if any).
+    #[serde(rename = "where")]
+    pub where_expr: Option<String>,
+    pub pagination: PaginationMode,
+    /// Cursor for the next page; `None` on the last page or without a primary key
+    pub next_cursor: Option<String>,
+}
+
+/// Encode primary key values (as text) into an opaque page cursor
+fn encode_cursor(key: &[String]) -> String {
+    serde_json::to_string(key).unwrap_or_default()
+}
+
+/// Decode a page cursor, checking it matches the primary key arity
+fn decode_cursor(cursor: &str, key_len: usize) -> ApiResult<Vec<String>> {
+    match serde_json::from_str::<Vec<String>>(cursor) {
+        Ok(key) if key.len() == key_len => Ok(key),
+        _ => Err(ApiError::BadRequest(format!(
+            "Invalid cursor '{}': expected a JSON array of {} primary key value(s)",
+            cursor, key_len
+        ))),
+    }
+}
+
+/// Cursor for the page after `page_keys`, or `None` when the page was short
+fn next_cursor(page_keys: &[Vec<String>], limit: usize) -> Option<String> {
+    if page_keys.len() < limit {
+        return None;
+    }
+    page_keys.last().map(|key| encode_cursor(key))
+}
+
+/// Row-comparison predicate selecting keys after the cursor bound at
+/// `$first_param..`; values are cast to the key column types
+fn keyset_condition(pk: &[(String, String)], first_param: usize) -> String {
+    let columns: Vec<String> = pk
+        .iter()
+        .map(|(name, _)| format!("t.{}", quote_ident(name)))
+        .collect();
+    let values: Vec<String> = pk
+        .iter()
+        .enumerate()
+        .map(|(i, (_, data_type))| format!("CAST(${} AS {})", first_param + i, data_type))
+        .collect();
+    format!("({}) > ({})", columns.join(", "), values.join(", "))
 }
 
 /// Get table data
+#[tracing::instrument(skip_all, fields(dump_id = %id))]
 pub async fn get_table_data(
     State(state): State<AppState>,
+    owner: Owner,
+    Path((id, table_path)): Path<(Uuid, String)>,
+    Query(query): Query<TableDataQuery>,
+) -> ApiResult<Json<TableDataResponse>> {
+    // Parse schema.table format or use query parameter
+    let parts: Vec<&str> = table_path.split('.').collect();
+    let (schema, table) = if parts.len() == 2 {
+        (parts[0].to_string(), parts[1].to_string())
+    } else {
+        // Use query parameter for schema, default to "public"
+        let schema = query.schema.clone().unwrap_or_else(|| "public".to_string());
+        (schema, parts[0].to_string())
+    };
+
+    // Get sandbox database name - use query.database if specified, otherwise fallback to sandbox_db_name
+    let sandbox_db =
+        resolve_sandbox_db(&state, id, owner.as_deref(), query.database.as_deref()).await?;
+
+    let limit = query.limit.unwrap_or(50).min(1000);
+    let offset = query.offset.unwrap_or(0);
+
+    // Connect to sandbox and fetch data
+    let sandbox_pool = state.sandbox_pools.get(&sandbox_db).await?;
+
+    // Get column names and types (the types are used to cast filter values)
+    let column_types: Vec<(String, String)> = sqlx::query(
+        r#"
+        SELECT column_name, udt_schema, udt_name
+        FROM information_schema.columns
+        WHERE table_schema = $1 AND table_name = $2
+        ORDER BY ordinal_position
+        "#,
+    )
+    .bind(&schema)
+    .bind(&table)
+    .fetch_all(&*sandbox_pool)
+    .await?
+    .iter()
+    .map(|row| {
+        let udt_schema: String = row.get("udt_schema");
+        let udt_name: String = row.get("udt_name");
+        (
+            row.get("column_name"),
+            format!("{}.{}", quote_ident(&udt_schema), quote_ident(&udt_name)),
+        )
+    })
+    .collect();
+    let columns: Vec<String> = column_types.iter().map(|(c, _)| c.clone()).collect();
+
+    if columns.is_empty() {
+        return Err(ApiError::NotFound(format!(
+            "Table {}.{} not found",
+            schema, table
+        )));
+    }
+
+    // Build an optional WHERE clause for server-side filtering. The filter value
+    // is always bound as $1 (never interpolated) so it is safe against quotes;
+    // identifiers are quoted via `quote_ident`.
+    let filter_value = query
+        .filter
+        .as_deref()
+        .map(str::trim)
+        .filter(|f| !f.is_empty());
+
+    let mut conditions = Vec::new();
+    if filter_value.is_some() {
+        if let Some(ref col) = query.filter_column {
+            if !columns.iter().any(|c| c == col) {
+                return Err(ApiError::BadRequest(format!(
+                    "Filter column '{}' does not exist in table {}.{}",
+                    col, schema, table
+                )));
+            }
+            conditions.push(format!("t.{}::text ILIKE $1 ESCAPE '\\'", quote_ident(col)));
+        } else {
+            // Free-text search across all columns
+            let any_column: Vec<String> = columns
+                .iter()
+                .map(|c| format!("t.{}::text ILIKE $1 ESCAPE '\\'", quote_ident(c)))
+                .collect();
+            conditions.push(format!("({})", any_column.join(" OR ")));
+        }
+    }
+
+    let bind_pattern = filter_value.map(|f| format!("%{}%", escape_like(f)));
+
+    // The typed filter expression binds its values after the substring pattern
+    let where_expr = query
+        .where_expr
+        .as_deref()
+        .map(str::trim)
+        .filter(|w| !w.is_empty());
+    let rendered = match where_expr {
+        Some(expr) => parse_filter(expr)
+            .and_then(|predicates| {
+                render_filter(
+                    &predicates,
+                    &column_types,
+                    if bind_pattern.is_some() { 2 } else { 1 },
+                )
+            })
+            .map_err(|e| ApiError::BadRequest(e.to_string()))?,
+        None => RenderedFilter::default(),
+    };
+    conditions.extend(rendered.conditions);
+
+    let where_clause = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
+    };
+
+    let table_ref = format!("{}.{}", quote_ident(&schema), quote_ident(&table));
+
+    // Primary key columns (in key order) with their types for keyset paging
+    let pk_columns: Vec<(String, String)> = sqlx::query(
+        r#"
+        SELECT a.attname::text AS column_name
+        FROM pg_index i
+        JOIN pg_attribute a ON a.attrelid = i.indrelid AND a.attnum = ANY(i.indkey)
+        WHERE i.indrelid = to_regclass($1) AND i.indisprimary
+        ORDER BY array_position(i.indkey::int2[], a.attnum)
+        "#,
+    )
+    .bind(&table_ref)
+    .fetch_all(&*sandbox_pool)
+    .await?
+    .iter()
+    .filter_map(|row| {
+        let name: String = row.get("column_name");
+        column_types.iter().find(|(c, _)| *c == name).cloned()
+    })
+    .collect();
+
+    let cursor = match (&query.after, pk_columns.is_empty()) {
+        (Some(after), false) => Some(decode_cursor(after, pk_columns.len())?),
+        _ => None,
+    };
+
+    // Unfiltered tables use the planner estimate unless an exact count was
+    // requested; it is missing (-1) for tables that were never analyzed
+    let estimated_count = if query.exact || !where_clause.is_empty() {
+        None
+    } else {
+        sqlx::query(
+            r#"
+            SELECT c.reltuples::bigint as estimate
+            FROM pg_class c
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            WHERE n.nspname = $1 AND c.relname = $2
+            "#,
+        )
+        .bind(&schema)
+        .bind(&table)
+        .fetch_optional(&*sandbox_pool)
+        .await?
+        .map(|row| row.get::<i64, _>("estimate"))
+        .filter(|estimate| *estimate >= 0)
+    };
+
+    let (total_count, total_count_exact) = match estimated_count {
+        Some(estimate) => (estimate, false),
+        None => {
+            // Get total count (with the same filter applied)
+            let count_query = format!(
+                "SELECT COUNT(*) as cnt FROM {} t {}",
+                table_ref, where_clause
+            );
+            let mut count_q = sqlx::query(&count_query);
+            if let Some(ref pattern) = bind_pattern {
+                count_q = count_q.bind(pattern);
+            }
+            for param in &rendered.params {
+                count_q = count_q.bind(param);
+            }
+            let count_row = count_q.fetch_one(&*sandbox_pool).await?;
+            (count_row.get::<i64, _>("cnt"), true)
+        }
+    };
+
+    // Fetch rows (limit/offset are clamped usize values, safe to interpolate).
+    // Tables with a primary key are ordered by it so pages are stable and the
+    // last key can serve as the next cursor.
+    let (key_select, order_by) = if pk_columns.is_empty() {
+        (String::new(), String::new())
+    } else {
+        let keys: Vec<String> = pk_columns
+            .iter()
+            .map(|(name, _)| format!("t.{}", quote_ident(name)))
+            .collect();
+        let as_text: Vec<String> = keys.iter().map(|k| format!("{}::text", k)).collect();
+        (
+            format!(", ARRAY[{}] as cursor_key", as_text.join(", ")),
+            format!("ORDER BY {}", keys.join(", ")),
+        )
+    };
+    let (data_where, paging, pagination) = match cursor {
+        Some(_) => {
+            let first_param = 1 + bind_pattern.iter().count() + rendered.params.len();
+            let condition = keyset_condition(&pk_columns, first_param);
+            let data_where = if where_clause.is_empty() {
+                format!("WHERE {}", condition)
+            } else {
+                format!("{} AND {}", where_clause, condition)
+            };
+            (
+                data_where,
+                format!("LIMIT {}", limit),
+                PaginationMode::Keyset,
+            )
+        }
+        None => (
+            where_clause.clone(),
+            format!("LIMIT {} OFFSET {}", limit, offset),
+            PaginationMode::Offset,
+        ),
+    };
+    let data_query = format!(
+        "SELECT to_jsonb(t.*) as row_data{} FROM {} t {} {} {}",
+        key_select, table_ref, data_where, order_by, paging
+    );
+    let mut data_q = sqlx::query(&data_query);
+    if let Some(ref pattern) = bind_pattern {
+        data_q = data_q.bind(pattern);
+    }
+    for param in &rendered.params {
+        data_q = data_q.bind(param);
+    }
+    for value in cursor.iter().flatten() {
+        data_q = data_q.bind(value);
+    }
+    let data_rows = data_q.fetch_all(&*sandbox_pool).await?;
+    let rows: Vec<serde_json::Value> = data_rows.iter().map(|row| row.get("row_data")).collect();
+    let next_cursor = if pk_columns.is_empty() {
+        None
+    } else {
+        let page_keys: Vec<Vec<String>> =
+            data_rows.iter().map(|row| row.get("cursor_key")).collect();
+        next_cursor(&page_keys, limit)
+    };
+
+    Ok(Json(TableDataResponse {
+        schema,
+        table,
+        columns,
+        rows,
+        total_count,
+        total_count_exact,
+        limit,
+        offset: if pagination == PaginationMode::Keyset {
+            0
+        } else {
+            offset
+        },
+        filter: filter_value.map(|f| f.to_string()),
+        where_expr: where_expr.map(|w| w.to_string()),
+        pagination,
+        next_cursor,
+    }))
+}
+
+/// Most tables in one batch table data request
+const BATCH_MAX_TABLES: usize = 20;
+/// Most rows a batch table data request may ask for across all its tables
+const BATCH_MAX_TOTAL_ROWS: usize = 5000;
+/// Rows per table when a batch entry gives no limit
+const BATCH_DEFAULT_LIMIT: usize = 50;
+
+/// One table of a batch table data request
+#[derive(Debug, Deserialize)]
+pub struct BatchTableRequest {
+    /// Defaults to `public`
+    pub schema: Option<String>,
+    pub table: String,
+    /// Rows to return, at most 1000 (default 50)
+    pub limit: Option<usize>,
+}
+
+/// Batch table data request body
+#[derive(Debug, Deserialize)]
+pub struct BatchTableDataRequest {
+    pub tables: Vec<BatchTableRequest>,
+    /// Optional database name for pg_dumpall dumps with multiple databases
+    pub database: Option<String>,
+}
+
+/// First rows of one table of a batch
+#[derive(Debug, Serialize)]
+pub struct BatchTableData {
+    pub schema: String,
+    pub table: String,
+    pub columns: Vec<String>,
+    pub rows: Vec<serde_json::Value>,
+    pub limit: usize,
+}
+
+/// Batch table data response, in request order
+#[derive(Debug, Serialize)]
+pub struct BatchTableDataResponse {
+    pub tables: Vec<BatchTableData>,
+}
+
+/// Check a batch against the table and row caps, resolving each entry to
+/// `(schema, table, limit)`
+fn plan_batch(requests: &[BatchTableRequest]) -> ApiResult<Vec<(String, String, usize)>> {
+    if requests.is_empty() || requests.len() > BATCH_MAX_TABLES {
+        return Err(ApiError::BadRequest(format!(
+            "A batch must name between 1 and {} tables, got {}",
+            BATCH_MAX_TABLES,
+            requests.len()
+        )));
+    }
+
+    let plan: Vec<(String, String, usize)> = requests
+        .iter()
+        .map(|r| {
+            (
+                r.schema.clone().unwrap_or_else(|| "public".to_string()),
+                r.table.clone(),
+                r.limit.unwrap_or(BATCH_DEFAULT_LIMIT).min(1000),
+            )
+        })
+        .collect();
+    let total_rows: usize = plan.iter().map(|(_, _, limit)| limit).sum();
+    if total_rows > BATCH_MAX_TOTAL_ROWS {
+        return Err(ApiError::BadRequest(format!(
+            "A batch may request at most {} rows in total, got {}",
+            BATCH_MAX_TOTAL_ROWS, total_rows
+        )));
+    }
+    Ok(plan)
+}
+
+/// Read the first rows of each planned table over one connection
+///
+/// Tables come from the schema graph, so names are checked before any query
+/// runs. Rows are ordered by primary key when there is one.
+async fn fetch_batch(
+    conn: &mut sqlx::PgConnection,
+    schema_graph: &SchemaGraph,
+    plan: Vec<(String, String, usize)>,
+) -> ApiResult<Vec<BatchTableData>> {
+    let tables = plan
+        .iter()
+        .map(|(schema, table, _)| {
+            schema_graph
+                .tables
+                .iter()
+                .find(|t| t.schema_name == *schema && t.table_name == *table)
+                .ok_or_else(|| ApiError::NotFound(format!("Table {}.{} not found", schema, table)))
+        })
+        .collect::<ApiResult<Vec<&TableInfo>>>()?;
+
+    let mut results = Vec::with_capacity(tables.len());
+    for (table_info, (schema, table, limit)) in tables.into_iter().zip(plan) {
+        let pk_columns = ddl::primary_key_columns(&schema_graph.indexes, table_info);
+        let order_by = if pk_columns.is_empty() {
+            String::new()
+        } else {
+            let keys: Vec<String> = pk_columns
+                .iter()
+                .map(|c| format!("t.{}", quote_ident(c)))
+                .collect();
+            format!("ORDER BY {}", keys.join(", "))
+        };
+        let data_query = format!(
+            "SELECT to_jsonb(t.*) as row_data FROM {}.{} t {} LIMIT {}",
+            quote_ident(&schema),
+            quote_ident(&table),
+            order_by,
+            limit
+        );
+        let rows = sqlx::query(&data_query)
+            .fetch_all(&mut *conn)
+            .await?
+            .iter()
+            .map(|row| row.get("row_data"))
+            .collect();
+
+        results.push(BatchTableData {
+            columns: table_info.columns.iter().map(|c| c.name.clone()).collect(),
+            schema,
+            table,
+            rows,
+            limit,
+        });
+    }
+    Ok(results)
+}
+
+/// Get the first rows of several tables in one request
+///
+/// POST /api/dumps/:id/tables/batch
+///
+/// Meant for dashboards showing many small lookup tables: all tables are
+/// read over a single sandbox connection. A batch holds at most
+/// 20 tables and 5000 rows in total.
+#[tracing::instrument(skip_all, fields(dump_id = %id))]
+pub async fn get_table_data_batch(
+    State(state): State<AppState>,
+    owner: Owner,
+    Path(id): Path<Uuid>,
+    Json(req): Json<BatchTableDataRequest>,
+) -> ApiResult<Json<BatchTableDataResponse>> {
+    let plan = plan_batch(&req.tables)?;
+    let (sandbox_db, schema_graph) =
+        load_cached_schema(&state, id, owner.as_deref(), req.database.as_deref()).await?;
+
+    let sandbox_pool = state.sandbox_pools.get(&sandbox_db).await?;
+    let mut conn = sandbox_pool.acquire().await?;
+    let tables = fetch_batch(&mut conn, &schema_graph, plan).await?;
+
+    Ok(Json(BatchTableDataResponse { tables }))
+}
+
+/// Default and maximum related rows returned per foreign key
+const RELATED_ROWS_DEFAULT_LIMIT: usize = 5;
+const RELATED_ROWS_MAX_LIMIT: usize = 50;
+
+/// Related rows query parameters
+#[derive(Debug, Deserialize)]
+pub struct RelatedRowsQuery {
+    pub schema: Option<String>,
+    /// Optional database name for pg_dumpall dumps with multiple databases
+    pub database: Option<String>,
+    /// Rows returned per foreign key
+    pub limit: Option<usize>,
+}
+
+/// Rows on the other side of one foreign key
+#[derive(Debug, Serialize)]
+pub struct RelatedRows {
+    pub constraint_name: String,
+    /// `outbound` for rows the row references, `inbound` for rows referencing it
+    pub direction: RelationDirection,
+    pub schema_name: String,
+    pub table_name: String,
+    /// Key columns of the related table
+    pub columns: Vec<String>,
+    pub rows: Vec<serde_json::Value>,
+}
+
+/// A row with its related rows
+#[derive(Debug, Serialize)]
+pub struct RelatedRowsResponse {
+    pub schema_name: String,
+    pub table_name: String,
+    pub key: String,
+    pub row: serde_json::Value,
+    pub relations: Vec<RelatedRows>,
+}
+
+/// Key columns of the table `direction` is relative to
+fn own_key_columns(fk: &ForeignKey, direction: RelationDirection) -> &[String] {
+    match direction {
+        RelationDirection::Outbound => &fk.source_columns,
+        RelationDirection::Inbound => &fk.target_columns,
+    }
+}
+
+/// Get a row and the rows related to it through each foreign key
+///
+/// GET /api/dumps/:id/tables/:table/rows/:key/related
+///
+/// `key` is the row's primary key value; tables need a single-column
+/// primary key. Self-referencing foreign keys are listed in both directions.
+#[tracing::instrument(skip_all, fields(dump_id = %id))]
+pub async fn get_related_rows(
+    State(state): State<AppState>,
+    owner: Owner,
+    Path((id, table_path, key)): Path<(Uuid, String, String)>,
+    Query(query): Query<RelatedRowsQuery>,
+) -> ApiResult<Json<RelatedRowsResponse>> {
+    // Parse schema.table format or use query parameter
+    let (schema, table) = match table_path.split_once('.') {
+        Some((schema, table)) => (schema.to_string(), table.to_string()),
+        None => (
+            query.schema.unwrap_or_else(|| "public".to_string()),
+            table_path,
+        ),
+    };
+    let limit = query
+        .limit
+        .unwrap_or(RELATED_ROWS_DEFAULT_LIMIT)
+        .min(RELATED_ROWS_MAX_LIMIT);
+
+    let (sandbox_db, schema_graph) =
+        load_cached_schema(&state, id, owner.as_deref(), query.database.as_deref()).await?;
+    let table_info = schema_graph
+        .tables
+        .iter()
+        .find(|t| t.schema_name == schema && t.table_name == table)
+        .ok_or_else(|| ApiError::NotFound(format!("Table {}.{} not found", schema, table)))?;
+    let pk_columns: Vec<&str> = table_info
+        .columns
+        .iter()
+        .filter(|c| c.is_primary_key)
+        .map(|c| c.name.as_str())
+        .collect();
+    let [pk_column] = pk_columns.as_slice() else {
+        return Err(ApiError::BadRequest(format!(
+            "Related rows need a single-column primary key; {}.{} has {}",
+            schema,
+            table,
+            pk_columns.len()
+        )));
+    };
+
+    let fks: Vec<(RelationDirection, &ForeignKey)> = schema_graph
+        .foreign_keys
+        .iter()
+        .flat_map(|fk| {
+            let outbound = (fk.source_schema == schema && fk.source_table == table)
+                .then_some((RelationDirection::Outbound, fk));
+            let inbound = (fk.target_schema == schema && fk.target_table == table)
+                .then_some((RelationDirection::Inbound, fk));
+            outbound.into_iter().chain(inbound)
+        })
+        .collect();
+
+    // Read the row and, as text, every key column the foreign keys need
+    let mut key_columns: Vec<&str> = fks
+        .iter()
+        .flat_map(|(direction, fk)| own_key_columns(fk, *direction))
+        .map(String::as_str)
+        .collect();
+    key_columns.sort_unstable();
+    key_columns.dedup();
+    let key_array = if key_columns.is_empty() {
+        "ARRAY[]::text[]".to_string()
+    } else {
+        format!(
+            "ARRAY[{}]",
+            key_columns
+                .iter()
+                .map(|c| format!("t.{}::text", quote_ident(c)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    };
+    let row_query = format!(
+        "SELECT to_jsonb(t.*) AS row_data, {} AS key_text FROM {}.{} t WHERE t.{}::text = $1 LIMIT 1",
+        key_array,
+        quote_ident(&schema),
+        quote_ident(&table),
+        quote_ident(pk_column)
+    );
+
+    let sandbox_pool = state.sandbox_pools.get(&sandbox_db).await?;
+    let row = sqlx::query(&row_query)
+        .bind(&key)
+        .fetch_optional(&*sandbox_pool)
+        .await?
+        .ok_or_else(|| {
+            ApiError::NotFound(format!("Row {} not found in {}.{}", key, schema, table))
+        })?;
+    let row_data: serde_json::Value = row.get("row_data");
+    let key_text: Vec<Option<String>> = row.get("key_text");
+    let key_values: HashMap<&str, Option<String>> = key_columns.into_iter().zip(key_text).collect();
+
+    let adapter = PostgresAdapter::new((*sandbox_pool).clone(), state.config.sandbox_connection())
+        .with_db_pool(&sandbox_db, (*sandbox_pool).clone());
+    let mut relations = Vec::with_capacity(fks.len());
+    for (direction, fk) in fks {
+        // A NULL in the key references nothing
+        let values: Option<Vec<String>> = own_key_columns(fk, direction)
+            .iter()
+            .map(|c| key_values.get(c.as_str()).cloned().flatten())
+            .collect();
+        let rows = match values {
+            Some(values) => {
+                adapter
+                    .fetch_related_rows(&sandbox_db, fk, direction, &values, limit)
+                    .await?
+            }
+            None => Vec::new(),
+        };
+
+        let (schema_name, table_name, columns) = match direction {
+            RelationDirection::Outbound => {
+                (&fk.target_schema, &fk.target_table, &fk.target_columns)
+            }
+            RelationDirection::Inbound => (&fk.source_schema, &fk.source_table, &fk.source_columns),
+        };
+        relations.push(RelatedRows {
+            constraint_name: fk.constraint_name.clone(),
+            direction,
+            schema_name: schema_name.clone(),
+            table_name: table_name.clone(),
+            columns: columns.clone(),
+            rows,
+        });
+    }
+
+    Ok(Json(RelatedRowsResponse {
+        schema_name: schema,
+        table_name: table,
+        key,
+        row: row_data,
+        relations,
+    }))
+}
+
+/// CSV and Parquet export query parameters
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    pub schema: Option<String>,
+    /// Optional database name for pg_dumpall dumps with multiple databases
+    pub database: Option<String>,
+}
+
+/// Quote a CSV field if needed (RFC 4180)
+///
+/// NULL becomes an empty field while an empty string is written as `""`, the
+/// same convention as PostgreSQL's `COPY ... CSV`.
+fn csv_field(value: Option<&str>) -> String {
+    match value {
+        None => String::new(),
+        Some("") => "\"\"".to_string(),
+        Some(v) if v.contains([',', '"', '\n', '\r']) => format!("\"{}\"", v.replace('"', "\"\"")),
+        Some(v) => v.to_string(),
+    }
+}
+
+/// Build one CSV record terminated by CRLF
+fn csv_line<'a>(values: impl IntoIterator<Item = Option<&'a str>>) -> String {
+    let mut line = values
+        .into_iter()
+        .map(csv_field)
+        .collect::<Vec<_>>()
+        .join(",");
+    line.push_str("\r\n");
+    line
+}
+
+/// Stream a table as CSV
+///
+/// Rows are read through a cursor and written to the response as they arrive,
+/// so the table is never buffered in memory.
+#[tracing::instrument(skip_all, fields(dump_id = %id))]
+pub async fn export_table_csv(
+    State(state): State<AppState>,
+    owner: Owner,
     Path((id, table_path)): Path<(Uuid, String)>,
-    Query(query): Query<TableDataQuery>,
-) -> ApiResult<Json<TableDataResponse>> {
-    // Parse schema.table format or use query parameter
+    Query(query): Query<ExportQuery>,
+) -> ApiResult<Response> {
     let parts: Vec<&str> = table_path.split('.').collect();
     let (schema, table) = if parts.len() == 2 {
         (parts[0].to_string(), parts[1].to_string())
     } else {
-        // Use query parameter for schema, default to "public"
         let schema = query.schema.clone().unwrap_or_else(|| "public".to_string());
         (schema, parts[0].to_string())
     };
 
-    // Get sandbox database name - use query.database if specified, otherwise fallback to sandbox_db_name
-    let sandbox_db = resolve_sandbox_db(&state.db_pool, id, query.database.as_deref()).await?;
-
-    let limit = query.limit.unwrap_or(50).min(1000);
-    let offset = query.offset.unwrap_or(0);
-
-    // Connect to sandbox and fetch data
-    let sandbox_url = build_sandbox_url(&state.config, &sandbox_db);
-
-    let sandbox_pool = sqlx::postgres::PgPool::connect(&sandbox_url)
-        .await
-        .map_err(|e| ApiError::Internal(format!("Failed to connect to sandbox: {}", e)))?;
+    let sandbox_db =
+        resolve_sandbox_db(&state, id, owner.as_deref(), query.database.as_deref()).await?;
+    let sandbox_pool = state.sandbox_pools.get(&sandbox_db).await?;
 
-    // Get column names
     let columns: Vec<String> = sqlx::query(
         r#"
         SELECT column_name
@@ -208,7 +1667,7 @@ pub async fn get_table_data(
     )
     .bind(&schema)
     .bind(&table)
-    .fetch_all(&sandbox_pool)
+    .fetch_all(&*sandbox_pool)
     .await?
     .iter()
     .map(|row| row.get("column_name"))
@@ -221,78 +1680,153 @@ pub async fn get_table_data(
         )));
     }
 
-    // Build an optional WHERE clause for server-side filtering. The filter value
-    // is always bound as $1 (never interpolated) so it is safe against quotes;
-    // identifiers are quoted via `quote_ident`.
-    let filter_value = query
-        .filter
-        .as_deref()
-        .map(str::trim)
-        .filter(|f| !f.is_empty());
+    let select_list = columns
+        .iter()
+        .map(|c| format!("{}::text", quote_ident(c)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let data_query = format!(
+        "SELECT {} FROM {}.{}",
+        select_list,
+        quote_ident(&schema),
+        quote_ident(&table)
+    );
+    let header = csv_line(columns.iter().map(|c| Some(c.as_str())));
+    let column_count = columns.len();
 
-    let where_clause = if filter_value.is_some() {
-        if let Some(ref col) = query.filter_column {
-            if !columns.iter().any(|c| c == col) {
-                return Err(ApiError::BadRequest(format!(
-                    "Filter column '{}' does not exist in table {}.{}",
-                    col, schema, table
-                )));
+    // The task owns the pool, keeping the connection alive until the last row
+    // has been sent; it stops early if the client disconnects.
+    let (tx, rx) = tokio::sync::mpsc::channel::<Result<String, sqlx::Error>>(64);
+    tokio::spawn(async move {
+        if tx.send(Ok(header)).await.is_err() {
+            return;
+        }
+        let mut rows = sqlx::query(&data_query).fetch(&*sandbox_pool);
+        while let Some(row) = rows.next().await {
+            let chunk = row.and_then(|row| {
+                let values = (0..column_count)
+                    .map(|i| row.try_get::<Option<String>, _>(i))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(csv_line(values.iter().map(|v| v.as_deref())))
+            });
+            let failed = chunk.is_err();
+            if tx.send(chunk).await.is_err() || failed {
+                return;
             }
-            format!("WHERE t.{}::text ILIKE $1 ESCAPE '\\'", quote_ident(col))
-        } else {
-            // Free-text search across all columns
-            let conditions: Vec<String> = columns
-                .iter()
-                .map(|c| format!("t.{}::text ILIKE $1 ESCAPE '\\'", quote_ident(c)))
-                .collect();
-            format!("WHERE ({})", conditions.join(" OR "))
         }
-    } else {
-        String::new()
-    };
+    });
 
-    let bind_pattern = filter_value.map(|f| format!("%{}%", escape_like(f)));
+    let stream = futures::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    });
 
-    let table_ref = format!("{}.{}", quote_ident(&schema), quote_ident(&table));
+    let filename = format!("{}.{}.csv", schema, table).replace('"', "");
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+        ],
+        Body::from_stream(stream),
+    )
+        .into_response())
+}
 
-    // Get total count (with the same filter applied)
-    let count_query = format!(
-        "SELECT COUNT(*) as cnt FROM {} t {}",
-        table_ref, where_clause
-    );
-    let mut count_q = sqlx::query(&count_query);
-    if let Some(ref pattern) = bind_pattern {
-        count_q = count_q.bind(pattern);
-    }
-    let count_row = count_q.fetch_one(&sandbox_pool).await?;
-    let total_count: i64 = count_row.get("cnt");
+/// Stream a table as Parquet
+///
+/// The Arrow schema is inferred from the column types of the cached schema.
+/// Like the CSV export, rows are read through a cursor; the encoder sends
+/// each completed row group as it is written.
+#[tracing::instrument(skip_all, fields(dump_id = %id))]
+pub async fn export_table_parquet(
+    State(state): State<AppState>,
+    owner: Owner,
+    Path((id, table_path)): Path<(Uuid, String)>,
+    Query(query): Query<ExportQuery>,
+) -> ApiResult<Response> {
+    let parts: Vec<&str> = table_path.split('.').collect();
+    let (schema, table) = if parts.len() == 2 {
+        (parts[0].to_string(), parts[1].to_string())
+    } else {
+        let schema = query.schema.clone().unwrap_or_else(|| "public".to_string());
+        (schema, parts[0].to_string())
+    };
 
-    // Fetch rows (limit/offset are clamped usize values, safe to interpolate)
-    let data_query = format!(
-        "SELECT to_jsonb(t.*) as row_data FROM {} t {} LIMIT {} OFFSET {}",
-        table_ref, where_clause, limit, offset
-    );
-    let mut data_q = sqlx::query(&data_query);
-    if let Some(ref pattern) = bind_pattern {
-        data_q = data_q.bind(pattern);
-    }
-    let rows: Vec<serde_json::Value> = data_q
-        .fetch_all(&sandbox_pool)
-        .await?
+    let (sandbox_db, schema_graph) =
+        load_cached_schema(&state, id, owner.as_deref(), query.database.as_deref()).await?;
+    let columns: Vec<ParquetColumn> = schema_graph
+        .tables
+        .iter()
+        .find(|t| t.schema_name == schema && t.table_name == table)
+        .ok_or_else(|| ApiError::NotFound(format!("Table {}.{} not found", schema, table)))?
+        .columns
         .iter()
-        .map(|row| row.get("row_data"))
+        .map(ParquetColumn::new)
         .collect();
+    let sandbox_pool = state.sandbox_pools.get(&sandbox_db).await?;
 
-    Ok(Json(TableDataResponse {
-        schema,
-        table,
-        columns,
-        rows,
-        total_count,
-        limit,
-        offset,
-        filter: filter_value.map(|f| f.to_string()),
-    }))
+    let select_list = columns
+        .iter()
+        .map(ParquetColumn::select_expr)
+        .collect::<Vec<_>>()
+        .join(", ");
+    let data_query = format!(
+        "SELECT {} FROM {}.{}",
+        select_list,
+        quote_ident(&schema),
+        quote_ident(&table)
+    );
+    let column_count = columns.len();
+    let mut encoder =
+        ParquetEncoder::new(columns).map_err(|e| ApiError::Internal(e.to_string()))?;
+
+    // Same ownership as the CSV export: the task keeps the pool alive and
+    // stops early if the client disconnects
+    let (tx, rx) = tokio::sync::mpsc::channel::<Result<Vec<u8>, ParquetError>>(64);
+    tokio::spawn(async move {
+        let mut rows = sqlx::query(&data_query).fetch(&*sandbox_pool);
+        while let Some(row) = rows.next().await {
+            let chunk = row
+                .and_then(|row| {
+                    (0..column_count)
+                        .map(|i| row.try_get::<Option<String>, _>(i))
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .map_err(|e| ParquetError::External(Box::new(e)))
+                .and_then(|values| {
+                    encoder.push_row(values.iter().map(|v| v.as_deref()))?;
+                    Ok(encoder.take_output())
+                });
+            let failed = chunk.is_err();
+            let ready = chunk.as_ref().map_or(true, |bytes| !bytes.is_empty());
+            if ready && (tx.send(chunk).await.is_err() || failed) {
+                return;
+            }
+        }
+        let _ = tx.send(encoder.finish()).await;
+    });
+
+    let stream = futures::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    });
+
+    let filename = format!("{}.{}.parquet", schema, table).replace('"', "");
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                "application/vnd.apache.parquet".to_string(),
+            ),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+        ],
+        Body::from_stream(stream),
+    )
+        .into_response())
 }
 
 /// Suggest query parameters
@@ -319,9 +1853,66 @@ pub struct SuggestItem {
     pub source: String,
 }
 
+/// Check that `schema.table.column` exists in the schema graph and return it
+fn validate_column<'a>(
+    graph: &'a SchemaGraph,
+    schema: &str,
+    table: &str,
+    column: &str,
+) -> ApiResult<&'a ColumnInfo> {
+    let table_info = graph
+        .tables
+        .iter()
+        .find(|t| t.schema_name == schema && t.table_name == table)
+        .ok_or_else(|| ApiError::BadRequest(format!("Unknown table {}.{}", schema, table)))?;
+
+    table_info
+        .columns
+        .iter()
+        .find(|c| c.name == column)
+        .ok_or_else(|| {
+            ApiError::BadRequest(format!(
+                "Unknown column '{}' in table {}.{}",
+                column, schema, table
+            ))
+        })
+}
+
+/// Build the most-frequent-values query for a column. With `with_prefix` the
+/// prefix pattern is expected as `$1`; `limit` is a clamped usize.
+fn build_suggest_query(
+    schema: &str,
+    table: &str,
+    column: &str,
+    with_prefix: bool,
+    limit: usize,
+) -> String {
+    let column = quote_ident(column);
+    let where_clause = if with_prefix {
+        format!("WHERE {}::text ILIKE $1", column)
+    } else {
+        String::new()
+    };
+
+    format!(
+        r#"
+        SELECT {column} as value, COUNT(*) as frequency
+        FROM {}.{}
+        {where_clause}
+        GROUP BY {column}
+        ORDER BY frequency DESC
+        LIMIT {limit}
+        "#,
+        quote_ident(schema),
+        quote_ident(table),
+    )
+}
+
 /// Get value suggestions
+#[tracing::instrument(skip_all, fields(dump_id = %id))]
 pub async fn suggest_values(
     State(state): State<AppState>,
+    owner: Owner,
     Path(id): Path<Uuid>,
     Query(query): Query<SuggestQuery>,
 ) -> ApiResult<Json<SuggestResponse>> {
@@ -329,57 +1920,54 @@ pub async fn suggest_values(
     let limit = query.limit.unwrap_or(10).min(50);
 
     // Get sandbox database
-    let dump_row = sqlx::query("SELECT sandbox_db_name FROM dumps WHERE id = $1")
-        .bind(id)
-        .fetch_optional(&state.db_pool)
-        .await?;
+    let dump_row = sqlx::query(
+        r#"
+        SELECT status, sandbox_db_name
+        FROM dumps
+        WHERE id = $1 AND ($2::text IS NULL OR owner = $2)
+        "#,
+    )
+    .bind(id)
+    .bind(owner.as_deref())
+    .fetch_optional(&state.db_pool)
+    .await?;
 
     let sandbox_db: String = match dump_row {
-        Some(row) => row
-            .get::<Option<String>, _>("sandbox_db_name")
-            .ok_or_else(|| ApiError::BadRequest("Dump not restored yet".to_string()))?,
+        Some(row) => {
+            reject_archived(&state, id, row.get("status")).await?;
+            row.get::<Option<String>, _>("sandbox_db_name")
+                .ok_or_else(|| ApiError::BadRequest("Dump not restored yet".to_string()))?
+        }
         None => return Err(ApiError::NotFound(format!("Dump {} not found", id))),
     };
+    record_view(&state.db_pool, id).await;
 
-    let sandbox_url = build_sandbox_url(&state.config, &sandbox_db);
+    // Only allow columns that exist in the dump's schema graph
+    let schema_graph = state
+        .schema_graph(id, &sandbox_db)
+        .await?
+        .ok_or_else(|| ApiError::BadRequest("Dump schema not analyzed yet".to_string()))?;
+    validate_column(&schema_graph, schema, &query.table, &query.column)?;
 
-    let sandbox_pool = sqlx::postgres::PgPool::connect(&sandbox_url)
-        .await
-        .map_err(|e| ApiError::Internal(format!("Failed to connect to sandbox: {}", e)))?;
+    let sandbox_pool = state.sandbox_pools.get(&sandbox_db).await?;
 
-    // Build suggestion query
-    let suggest_query = if let Some(ref _prefix) = query.prefix {
-        format!(
-            r#"
-            SELECT "{}" as value, COUNT(*) as frequency
-            FROM "{}"."{}"
-            WHERE "{}"::text ILIKE $1
-            GROUP BY "{}"
-            ORDER BY frequency DESC
-            LIMIT {}
-            "#,
-            query.column, schema, query.table, query.column, query.column, limit
-        )
-    } else {
-        format!(
-            r#"
-            SELECT "{}" as value, COUNT(*) as frequency
-            FROM "{}"."{}"
-            GROUP BY "{}"
-            ORDER BY frequency DESC
-            LIMIT {}
-            "#,
-            query.column, schema, query.table, query.column, limit
-        )
-    };
+    let suggest_query = build_suggest_query(
+        schema,
+        &query.table,
+        &query.column,
+        query.prefix.is_some(),
+        limit,
+    );
 
     let rows = if let Some(prefix) = &query.prefix {
         sqlx::query(&suggest_query)
             .bind(format!("{}%", prefix))
-            .fetch_all(&sandbox_pool)
+            .fetch_all(&*sandbox_pool)
             .await?
     } else {
-        sqlx::query(&suggest_query).fetch_all(&sandbox_pool).await?
+        sqlx::query(&suggest_query)
+            .fetch_all(&*sandbox_pool)
+            .await?
     };
 
     let suggestions: Vec<SuggestItem> = rows
@@ -394,6 +1982,172 @@ pub async fn suggest_values(
     Ok(Json(SuggestResponse { suggestions }))
 }
 
+/// Column distribution query parameters
+#[derive(Debug, Deserialize)]
+pub struct DistributionQuery {
+    pub schema: Option<String>,
+    /// Number of most frequent values to return (default: 20, max: 100)
+    pub limit: Option<usize>,
+    /// Optional database name for pg_dumpall dumps with multiple databases
+    pub database: Option<String>,
+}
+
+/// A value together with how many rows hold it
+#[derive(Debug, Serialize)]
+pub struct ValueFrequency {
+    pub value: serde_json::Value,
+    pub count: i64,
+}
+
+/// Column distribution response
+#[derive(Debug, Serialize)]
+pub struct DistributionResponse {
+    pub schema_name: String,
+    pub table_name: String,
+    pub column_name: String,
+    pub data_type: String,
+    pub total_count: i64,
+    pub null_count: i64,
+    pub distinct_count: i64,
+    /// Most frequent non-null values, most frequent first
+    pub top_values: Vec<ValueFrequency>,
+    /// Minimum, maximum and average (numeric and date/time columns only)
+    pub min: Option<serde_json::Value>,
+    pub max: Option<serde_json::Value>,
+    pub avg: Option<serde_json::Value>,
+}
+
+/// Kind of range statistics available for a column type
+#[derive(Debug, PartialEq)]
+enum RangeStats {
+    Numeric,
+    Temporal,
+    None,
+}
+
+fn range_stats_for(data_type: &str) -> RangeStats {
+    match data_type.to_lowercase().as_str() {
+        "smallint" | "integer" | "bigint" | "numeric" | "real" | "double precision" => {
+            RangeStats::Numeric
+        }
+        "date" => RangeStats::Temporal,
+        t if t.starts_with("timestamp") => RangeStats::Temporal,
+        _ => RangeStats::None,
+    }
+}
+
+/// Build the summary statistics query for a column
+///
+/// Values are compared as `jsonb` so `json` columns, which have no equality
+/// operator, can be counted too.
+fn build_distribution_stats_query(
+    schema: &str,
+    table: &str,
+    column: &str,
+    data_type: &str,
+) -> String {
+    let column = quote_ident(column);
+    let range = match range_stats_for(data_type) {
+        RangeStats::Numeric => format!(
+            "to_jsonb(MIN({column})) AS min, to_jsonb(MAX({column})) AS max, \
+             to_jsonb(AVG({column})) AS avg"
+        ),
+        RangeStats::Temporal => format!(
+            "to_jsonb(MIN({column})) AS min, to_jsonb(MAX({column})) AS max, \
+             to_jsonb(to_timestamp(AVG(EXTRACT(EPOCH FROM {column})))) AS avg"
+        ),
+        RangeStats::None => "NULL::jsonb AS min, NULL::jsonb AS max, NULL::jsonb AS avg".into(),
+    };
+
+    format!(
+        r#"
+        SELECT
+            COUNT(*) AS total_count,
+            COUNT(*) - COUNT({column}) AS null_count,
+            COUNT(DISTINCT to_jsonb({column})) AS distinct_count,
+            {range}
+        FROM {}.{}
+        "#,
+        quote_ident(schema),
+        quote_ident(table),
+    )
+}
+
+/// Build the most-frequent-values query for a column distribution
+fn build_top_values_query(schema: &str, table: &str, column: &str, limit: usize) -> String {
+    let column = quote_ident(column);
+    format!(
+        r#"
+        SELECT to_jsonb({column}) AS value, COUNT(*) AS frequency
+        FROM {}.{}
+        WHERE {column} IS NOT NULL
+        GROUP BY to_jsonb({column})
+        ORDER BY frequency DESC, value
+        LIMIT {limit}
+        "#,
+        quote_ident(schema),
+        quote_ident(table),
+    )
+}
+
+/// Get the value distribution of a column
+#[tracing::instrument(skip_all, fields(dump_id = %id))]
+pub async fn get_column_distribution(
+    State(state): State<AppState>,
+    owner: Owner,
+    Path((id, table_path, column)): Path<(Uuid, String, String)>,
+    Query(query): Query<DistributionQuery>,
+) -> ApiResult<Json<DistributionResponse>> {
+    // Parse schema.table format or use query parameter
+    let (schema, table) = match table_path.split_once('.') {
+        Some((schema, table)) => (schema.to_string(), table.to_string()),
+        None => (
+            query.schema.clone().unwrap_or_else(|| "public".to_string()),
+            table_path,
+        ),
+    };
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+
+    let (sandbox_db, schema_graph) =
+        load_cached_schema(&state, id, owner.as_deref(), query.database.as_deref()).await?;
+    let column_info = validate_column(&schema_graph, &schema, &table, &column)?;
+
+    let sandbox_pool = state.sandbox_pools.get(&sandbox_db).await?;
+
+    let stats = sqlx::query(&build_distribution_stats_query(
+        &schema,
+        &table,
+        &column,
+        &column_info.data_type,
+    ))
+    .fetch_one(&*sandbox_pool)
+    .await?;
+
+    let top_values = sqlx::query(&build_top_values_query(&schema, &table, &column, limit))
+        .fetch_all(&*sandbox_pool)
+        .await?
+        .iter()
+        .map(|row| ValueFrequency {
+            value: row.get("value"),
+            count: row.get("frequency"),
+        })
+        .collect();
+
+    Ok(Json(DistributionResponse {
+        data_type: column_info.data_type.clone(),
+        schema_name: schema,
+        table_name: table,
+        column_name: column,
+        total_count: stats.get("total_count"),
+        null_count: stats.get("null_count"),
+        distinct_count: stats.get("distinct_count"),
+        top_values,
+        min: stats.get("min"),
+        max: stats.get("max"),
+        avg: stats.get("avg"),
+    }))
+}
+
 #[cfg(test)]
 mod tests {
     #[allow(unused_imports)]
@@ -445,4 +2199,433 @@ mod tests {
         // Backslash must be escaped before % and _ to avoid double-escaping.
         assert_eq!(escape_like("\\%"), "\\\\\\%");
     }
+
+    fn suggest_graph() -> SchemaGraph {
+        SchemaGraph {
+            tables: vec![db_viewer_core::domain::TableInfo {
+                schema_name: "public".to_string(),
+                table_name: "users".to_string(),
+                estimated_row_count: 0,
+                columns: vec![db_viewer_core::domain::ColumnInfo {
+                    name: "email".to_string(),
+                    data_type: "text".to_string(),
+                    is_nullable: false,
+                    is_primary_key: false,
+                    default_value: None,
+                    comment: None,
+                    ordinal_position: None,
+                    is_identity: false,
+                    identity_generation: None,
+                    generation_expression: None,
+                }],
+                comment: None,
+                is_partition: false,
+                partition_of: None,
+                partition_strategy: None,
+            }],
+            foreign_keys: vec![],
+            indexes: vec![],
+            views: vec![],
+            triggers: vec![],
+            enum_types: vec![],
+            sequences: vec![],
+        }
+    }
+
+    #[test]
+    fn test_describe_table_with_primary_and_foreign_key() {
+        let column = |name: &str, is_primary_key: bool| ColumnInfo {
+            name: name.to_string(),
+            data_type: "integer".to_string(),
+            is_nullable: !is_primary_key,
+            is_primary_key,
+            default_value: None,
+            comment: None,
+            ordinal_position: None,
+            is_identity: false,
+            identity_generation: None,
+            generation_expression: None,
+        };
+        let table = |name: &str, columns: Vec<ColumnInfo>| TableInfo {
+            schema_name: "public".to_string(),
+            table_name: name.to_string(),
+            estimated_row_count: 0,
+            columns,
+            comment: None,
+            is_partition: false,
+            partition_of: None,
+            partition_strategy: None,
+        };
+        let index = |name: &str, table: &str, column: &str, is_primary: bool| IndexInfo {
+            name: name.to_string(),
+            schema_name: "public".to_string(),
+            table_name: table.to_string(),
+            columns: vec![column.to_string()],
+            is_unique: is_primary,
+            is_primary,
+            method: "btree".to_string(),
+            predicate: None,
+        };
+        let orders = table("orders", vec![column("id", true), column("user_id", false)]);
+        let graph = SchemaGraph {
+            tables: vec![table("users", vec![column("id", true)]), orders.clone()],
+            foreign_keys: vec![ForeignKey {
+                constraint_name: "orders_user_id_fkey".to_string(),
+                source_schema: "public".to_string(),
+                source_table: "orders".to_string(),
+                source_columns: vec!["user_id".to_string()],
+                target_schema: "public".to_string(),
+                target_table: "users".to_string(),
+                target_columns: vec!["id".to_string()],
+                on_delete: Default::default(),
+                on_update: Default::default(),
+            }],
+            ..Default::default()
+        };
+        let indexes = vec![
+            index("orders_pkey", "orders", "id", true),
+            index("orders_user_id_idx", "orders", "user_id", false),
+        ];
+
+        let orders_description =
+            describe_table_definition(&graph, &orders, indexes, vec![], vec![]);
+        let users_description =
+            describe_table_definition(&graph, &graph.tables[0], vec![], vec![], vec![]);
+
+        let pk = orders_description.primary_key.unwrap();
+        assert_eq!(pk.name.as_deref(), Some("orders_pkey"));
+        assert_eq!(pk.columns, vec!["id"]);
+        assert_eq!(orders_description.outbound_foreign_keys.len(), 1);
+        assert!(orders_description.inbound_foreign_keys.is_empty());
+        assert_eq!(
+            orders_description.ddl,
+            "CREATE TABLE \"public\".\"orders\" (\n    \
+             \"id\" integer NOT NULL,\n    \
+             \"user_id\" integer,\n    \
+             CONSTRAINT \"orders_pkey\" PRIMARY KEY (\"id\"),\n    \
+             CONSTRAINT \"orders_user_id_fkey\" FOREIGN KEY (\"user_id\") \
+             REFERENCES \"public\".\"users\" (\"id\")\n);\n\n\
+             CREATE INDEX \"orders_user_id_idx\" ON \"public\".\"orders\" USING btree (user_id);"
+        );
+        assert_eq!(users_description.inbound_foreign_keys.len(), 1);
+        assert!(users_description.outbound_foreign_keys.is_empty());
+        assert_eq!(
+            users_description.primary_key.unwrap().name,
+            None,
+            "without index metadata the primary key is unnamed"
+        );
+    }
+
+    #[test]
+    fn test_validate_column_rejects_unknown_identifiers() {
+        let graph = suggest_graph();
+        assert!(validate_column(&graph, "public", "users", "email").is_ok());
+        assert!(matches!(
+            validate_column(&graph, "public", "users", "email\"; DROP TABLE users; --"),
+            Err(ApiError::BadRequest(_))
+        ));
+        assert!(matches!(
+            validate_column(&graph, "public", "users; --", "email"),
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_column_returns_column_info() {
+        let graph = suggest_graph();
+        let column = validate_column(&graph, "public", "users", "email").unwrap();
+        assert_eq!(column.name, "email");
+    }
+
+    #[test]
+    fn test_build_distribution_stats_query_numeric() {
+        let sql = build_distribution_stats_query("public", "orders", "total", "numeric");
+        assert!(sql.contains("COUNT(*) - COUNT(\"total\") AS null_count"));
+        assert!(sql.contains("COUNT(DISTINCT to_jsonb(\"total\")) AS distinct_count"));
+        assert!(sql.contains("to_jsonb(AVG(\"total\")) AS avg"));
+        assert!(sql.contains("FROM \"public\".\"orders\""));
+    }
+
+    #[test]
+    fn test_build_distribution_stats_query_by_type() {
+        let sql = build_distribution_stats_query(
+            "public",
+            "orders",
+            "created_at",
+            "timestamp with time zone",
+        );
+        assert!(sql.contains("EXTRACT(EPOCH FROM \"created_at\")"));
+
+        let sql = build_distribution_stats_query("public", "users", "email", "text");
+        assert!(sql.contains("NULL::jsonb AS min"));
+        assert!(!sql.contains("MIN("));
+    }
+
+    #[test]
+    fn test_build_top_values_query_quotes_identifiers() {
+        let sql = build_top_values_query("public", "us\"ers", "e\"mail", 20);
+        assert!(sql.contains("FROM \"public\".\"us\"\"ers\""));
+        assert!(sql.contains("WHERE \"e\"\"mail\" IS NOT NULL"));
+        assert!(sql.contains("LIMIT 20"));
+    }
+
+    #[test]
+    fn test_build_suggest_query_quotes_identifiers() {
+        let sql = build_suggest_query("public", "us\"ers", "e\"mail; --", true, 10);
+        assert!(sql.contains(r#"FROM "public"."us""ers""#));
+        assert!(sql.contains(r#"SELECT "e""mail; --" as value"#));
+        assert!(sql.contains(r#"WHERE "e""mail; --"::text ILIKE $1"#));
+        assert!(sql.contains("LIMIT 10"));
+        assert!(!build_suggest_query("public", "users", "email", false, 5).contains("WHERE"));
+    }
+
+    /// 50 tables over two schemas with distinct row counts
+    fn wide_tables() -> Vec<TableInfo> {
+        (0..50)
+            .map(|i| TableInfo {
+                schema_name: if i % 2 == 0 { "public" } else { "audit" }.to_string(),
+                table_name: format!("table_{:02}", i),
+                estimated_row_count: (i * 7 % 50) as i64,
+                columns: vec![ColumnInfo {
+                    name: "id".to_string(),
+                    data_type: "integer".to_string(),
+                    is_nullable: false,
+                    is_primary_key: true,
+                    default_value: None,
+                    comment: None,
+                    ordinal_position: Some(1),
+                    is_identity: false,
+                    identity_generation: None,
+                    generation_expression: None,
+                }],
+                comment: None,
+                is_partition: false,
+                partition_of: None,
+                partition_strategy: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_top_tables_by_rows() {
+        let top = top_tables_by_rows(wide_tables(), 3);
+        let names: Vec<(&str, i64)> = top
+            .iter()
+            .map(|t| (t.table_name.as_str(), t.estimated_row_count))
+            .collect();
+        // i * 7 % 50 == 49 for i = 7, 48 for i = 14, 47 for i = 21
+        assert_eq!(
+            names,
+            vec![("table_07", 49), ("table_14", 48), ("table_21", 47)]
+        );
+        assert!(top.iter().all(|t| t.size_bytes.is_none()));
+    }
+
+    #[test]
+    fn test_table_list_paginates_by_name() {
+        let query = TableListQuery {
+            limit: Some(10),
+            offset: Some(20),
+            ..Default::default()
+        };
+        let (page, total) = table_list_page(wide_tables(), &query).unwrap();
+        assert_eq!(total, 50);
+        assert_eq!(page.len(), 10);
+        // audit.* (25 tables) sorts before public.*
+        assert_eq!(page[0].schema_name, "audit");
+        assert_eq!(page[5].schema_name, "public");
+        assert_eq!(page[5].table_name, "table_00");
+        assert!(page.iter().all(|t| t.columns.is_empty()));
+
+        let (last, _) = table_list_page(
+            wide_tables(),
+            &TableListQuery {
+                offset: Some(45),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(last.len(), 5);
+    }
+
+    #[test]
+    fn test_table_list_sorts_by_rows_and_filters() {
+        let query = TableListQuery {
+            sort: Some("rows".to_string()),
+            schema: Some("public".to_string()),
+            columns: true,
+            ..Default::default()
+        };
+        let (page, total) = table_list_page(wide_tables(), &query).unwrap();
+        assert_eq!(total, 25);
+        assert!(page
+            .windows(2)
+            .all(|w| w[0].estimated_row_count >= w[1].estimated_row_count));
+        assert!(page.iter().all(|t| t.columns.len() == 1));
+
+        let query = TableListQuery {
+            q: Some("TABLE_4".to_string()),
+            ..Default::default()
+        };
+        let (page, total) = table_list_page(wide_tables(), &query).unwrap();
+        assert_eq!(total, 10);
+        assert!(page.iter().all(|t| t.table_name.starts_with("table_4")));
+
+        let query = TableListQuery {
+            sort: Some("size".to_string()),
+            ..Default::default()
+        };
+        assert!(table_list_page(wide_tables(), &query).is_err());
+    }
+
+    #[test]
+    fn test_csv_field_quoting() {
+        assert_eq!(csv_field(None), "");
+        assert_eq!(csv_field(Some("")), "\"\"");
+        assert_eq!(csv_field(Some("plain")), "plain");
+        assert_eq!(csv_field(Some("a,b")), "\"a,b\"");
+        assert_eq!(csv_field(Some("say \"hi\"")), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field(Some("line\nbreak")), "\"line\nbreak\"");
+    }
+
+    #[test]
+    fn test_keyset_cursor_advances_to_last_key() {
+        let pk = vec![("id".to_string(), "\"pg_catalog\".\"int4\"".to_string())];
+        let first_page = vec![vec!["1".to_string()], vec!["2".to_string()]];
+        let cursor = next_cursor(&first_page, 2).expect("full page has a cursor");
+        assert_eq!(decode_cursor(&cursor, pk.len()).unwrap(), vec!["2"]);
+
+        let second_page = vec![vec!["3".to_string()], vec!["4".to_string()]];
+        let cursor = next_cursor(&second_page, 2).unwrap();
+        assert_eq!(decode_cursor(&cursor, 1).unwrap(), vec!["4"]);
+
+        // A short page is the last one
+        assert_eq!(next_cursor(&[vec!["5".to_string()]], 2), None);
+        assert_eq!(
+            keyset_condition(&pk, 3),
+            "(t.\"id\") > (CAST($3 AS \"pg_catalog\".\"int4\"))"
+        );
+    }
+
+    #[test]
+    fn test_keyset_cursor_composite_key() {
+        let pk = vec![
+            ("tenant".to_string(), "text".to_string()),
+            ("seq".to_string(), "int8".to_string()),
+        ];
+        let cursor = encode_cursor(&["acme".to_string(), "7".to_string()]);
+        assert_eq!(decode_cursor(&cursor, 2).unwrap(), vec!["acme", "7"]);
+        assert!(decode_cursor(&cursor, 1).is_err());
+        assert!(decode_cursor("not json", 2).is_err());
+        assert_eq!(
+            keyset_condition(&pk, 1),
+            "(t.\"tenant\", t.\"seq\") > (CAST($1 AS text), CAST($2 AS int8))"
+        );
+    }
+
+    #[test]
+    fn test_csv_line_with_nulls() {
+        assert_eq!(csv_line([Some("1"), None, Some("x,y")]), "1,,\"x,y\"\r\n");
+    }
+
+    fn batch_entry(table: &str, limit: Option<usize>) -> BatchTableRequest {
+        BatchTableRequest {
+            schema: None,
+            table: table.to_string(),
+            limit,
+        }
+    }
+
+    #[test]
+    fn test_plan_batch_caps_tables_and_rows() {
+        let plan = plan_batch(&[batch_entry("a", None), batch_entry("b", Some(5000))]).unwrap();
+        assert_eq!(
+            plan,
+            vec![
+                ("public".to_string(), "a".to_string(), 50),
+                ("public".to_string(), "b".to_string(), 1000),
+            ]
+        );
+
+        let too_many: Vec<BatchTableRequest> = (0..=BATCH_MAX_TABLES)
+            .map(|i| batch_entry(&format!("t{}", i), Some(1)))
+            .collect();
+        assert!(matches!(
+            plan_batch(&too_many),
+            Err(ApiError::BadRequest(_))
+        ));
+        assert!(matches!(plan_batch(&[]), Err(ApiError::BadRequest(_))));
+
+        let too_many_rows: Vec<BatchTableRequest> = (0..6)
+            .map(|i| batch_entry(&format!("t{}", i), Some(1000)))
+            .collect();
+        assert!(matches!(
+            plan_batch(&too_many_rows),
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+
+    /// Requires a database in `TEST_DATABASE_URL` to create scratch tables in
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_fetch_batch_returns_every_table() {
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL is required");
+        let mut conn = <sqlx::PgConnection as sqlx::Connection>::connect(&url)
+            .await
+            .unwrap();
+        let schema = format!("batch_test_{}", Uuid::new_v4().simple());
+        sqlx::Executor::execute(
+            &mut conn,
+            format!(
+                "CREATE SCHEMA {s};
+                 CREATE TABLE {s}.countries (code text PRIMARY KEY);
+                 INSERT INTO {s}.countries VALUES ('jp'), ('de'), ('fr');
+                 CREATE TABLE {s}.currencies (code text PRIMARY KEY);
+                 INSERT INTO {s}.currencies VALUES ('EUR'), ('JPY');
+                 CREATE TABLE {s}.units (code text);
+                 INSERT INTO {s}.units VALUES ('kg');",
+                s = schema
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+        let mut graph = suggest_graph();
+        graph.tables = ["countries", "currencies", "units"]
+            .iter()
+            .map(|name| {
+                let mut table = suggest_graph().tables.remove(0);
+                table.schema_name = schema.clone();
+                table.table_name = name.to_string();
+                table.columns[0].name = "code".to_string();
+                table.columns[0].is_primary_key = *name != "units";
+                table
+            })
+            .collect();
+        let plan = ["countries", "currencies", "units"]
+            .iter()
+            .map(|name| (schema.clone(), name.to_string(), 2))
+            .collect();
+
+        let result = fetch_batch(&mut conn, &graph, plan).await;
+        sqlx::Executor::execute(
+            &mut conn,
+            format!("DROP SCHEMA {} CASCADE", schema).as_str(),
+        )
+        .await
+        .unwrap();
+
+        let tables = result.unwrap();
+        let summary: Vec<(&str, usize)> = tables
+            .iter()
+            .map(|t| (t.table.as_str(), t.rows.len()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("countries", 2), ("currencies", 2), ("units", 1)]
+        );
+        assert_eq!(tables[0].rows[0], serde_json::json!({ "code": "de" }));
+        assert_eq!(tables[0].columns, vec!["code"]);
+    }
 }
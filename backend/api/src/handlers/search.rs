@@ -5,13 +5,16 @@ use axum::{
     Json,
 };
 use serde::{Deserialize, Serialize};
-use sqlx::types::Json as SqlxJson;
-use sqlx::Row;
+use sqlx::{PgPool, Row};
+use std::sync::Arc;
 use uuid::Uuid;
 
+use crate::auth::Owner;
 use crate::error::{ApiError, ApiResult};
+use crate::handlers::sandbox::{record_view, reject_archived};
 use crate::state::AppState;
-use db_viewer_core::domain::SchemaGraph;
+use db_viewer_core::domain::{NormalizedType, SchemaGraph, TableInfo};
+use db_viewer_core::sql_gen::quote_ident;
 
 /// Search query parameters
 #[derive(Debug, Deserialize)]
@@ -22,6 +25,114 @@ pub struct SearchQuery {
     pub limit: Option<usize>,
     /// Optional database name filter
     pub database: Option<String>,
+    /// Number of results to skip across all tables (default: 0)
+    pub offset: Option<usize>,
+    /// Maximum results returned in this page (default and max: 100)
+    pub page_size: Option<usize>,
+    /// Match text columns with `LIKE` / `~` instead of `ILIKE` / `~*`
+    /// (default: false)
+    #[serde(default)]
+    pub case_sensitive: bool,
+    /// How text columns are matched (default: substring)
+    #[serde(default)]
+    pub mode: SearchMode,
+}
+
+/// How text columns are matched against the search term
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchMode {
+    /// `LIKE` / `ILIKE` on `%term%`
+    #[default]
+    Substring,
+    /// PostgreSQL regular expression (`~` / `~*`); only text columns are
+    /// searched
+    Regex,
+}
+
+/// SQLSTATE of `invalid_regular_expression`
+const INVALID_REGULAR_EXPRESSION: &str = "2201B";
+
+/// Tables and columns a search is restricted to
+///
+/// Given as repeated `tables[]` / `columns[]` query parameters. Tables are
+/// `schema.table` or a bare name matching that table in any schema; columns
+/// are names matched in every searched table. Empty lists do not restrict.
+#[derive(Debug, Default, PartialEq)]
+struct SearchScope {
+    tables: Vec<String>,
+    columns: Vec<String>,
+}
+
+impl SearchScope {
+    fn from_query(pairs: &[(String, String)]) -> Self {
+        Self {
+            tables: list_param(pairs, "tables"),
+            columns: list_param(pairs, "columns"),
+        }
+    }
+
+    fn includes_table(&self, table: &TableInfo) -> bool {
+        self.tables.is_empty()
+            || self
+                .tables
+                .iter()
+                .any(|t| table_matches(t, &table.schema_name, &table.table_name))
+    }
+
+    fn includes_column(&self, column: &str) -> bool {
+        self.columns.is_empty() || self.columns.iter().any(|c| c == column)
+    }
+
+    /// Reject tables and columns that exist in none of the searched schemas,
+    /// so a typo fails loudly instead of silently finding nothing
+    fn validate(&self, graphs: &[&SchemaGraph]) -> ApiResult<()> {
+        let tables: Vec<&TableInfo> = graphs.iter().flat_map(|g| &g.tables).collect();
+        if let Some(unknown) = self.tables.iter().find(|name| {
+            !tables
+                .iter()
+                .any(|t| table_matches(name, &t.schema_name, &t.table_name))
+        }) {
+            return Err(ApiError::BadRequest(format!("Unknown table '{}'", unknown)));
+        }
+        if let Some(unknown) = self.columns.iter().find(|name| {
+            !tables
+                .iter()
+                .filter(|t| self.includes_table(t))
+                .any(|t| t.columns.iter().any(|c| &c.name == *name))
+        }) {
+            return Err(ApiError::BadRequest(format!(
+                "Unknown column '{}' in the searched tables",
+                unknown
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Whether `name` (`schema.table` or `table`) refers to this table
+fn table_matches(name: &str, schema: &str, table: &str) -> bool {
+    match name.split_once('.') {
+        Some((s, t)) => s == schema && t == table,
+        None => name == table,
+    }
+}
+
+/// Values of a list query parameter, given as `name[]=a&name[]=b`,
+/// `name=a&name=b` or `name=a,b`
+fn list_param(pairs: &[(String, String)], name: &str) -> Vec<String> {
+    let mut values: Vec<String> = Vec::new();
+    for (key, value) in pairs {
+        if key.strip_suffix("[]").unwrap_or(key) != name {
+            continue;
+        }
+        for value in value.split(',').map(str::trim).filter(|v| !v.is_empty()) {
+            if !values.iter().any(|v| v == value) {
+                values.push(value.to_string());
+            }
+        }
+    }
+    values
 }
 
 /// Search result item
@@ -43,16 +154,200 @@ pub struct SearchResponse {
     pub total_results: usize,
     pub results: Vec<SearchResult>,
     pub searched_tables: usize,
+    /// Offset of the first result in this page
+    pub offset: usize,
+    /// Offset to request for the next page, if more results exist
+    pub next_offset: Option<usize>,
+}
+
+/// Maximum number of results returned per search page
+const MAX_SEARCH_PAGE_SIZE: usize = 100;
+
+/// What to do with the next matching row while paging through results
+#[derive(Debug, PartialEq)]
+enum WindowSlot {
+    /// Before the requested offset
+    Skip,
+    /// Inside the requested page
+    Take,
+    /// Past the page; at least one more result exists
+    Full,
+}
+
+/// Tracks the position of matches in the overall result order
+struct ResultWindow {
+    offset: usize,
+    size: usize,
+    seen: usize,
+    has_more: bool,
+    results: Vec<SearchResult>,
+}
+
+impl ResultWindow {
+    fn new(offset: usize, size: usize) -> Self {
+        Self {
+            offset,
+            size,
+            seen: 0,
+            has_more: false,
+            results: Vec::new(),
+        }
+    }
+
+    /// Classify the next match in result order
+    fn next_slot(&mut self) -> WindowSlot {
+        let position = self.seen;
+        self.seen += 1;
+        if position < self.offset {
+            WindowSlot::Skip
+        } else if position < self.offset + self.size {
+            WindowSlot::Take
+        } else {
+            self.has_more = true;
+            WindowSlot::Full
+        }
+    }
+
+    fn next_offset(&self) -> Option<usize> {
+        self.has_more.then_some(self.offset + self.size)
+    }
+}
+
+/// WHERE condition used to search one column
+#[derive(Debug, PartialEq)]
+struct SearchPredicate {
+    /// Condition with the search value as `$1`
+    condition: String,
+    /// Value bound to `$1`
+    bind: String,
+    /// Condition with the value inlined, for the reproduction query
+    display: String,
+}
+
+/// Build the search condition for a column, or `None` if the term cannot
+/// match a column of this type
+///
+/// Text-like columns (including json and enum columns) use a substring or
+/// regex match, case-insensitive unless `case_sensitive` is set. In
+/// substring mode, numeric, boolean, uuid, date and timestamp columns are compared for equality when
+/// the term parses as that type; a plain date also matches timestamps on that
+/// day.
+fn search_predicate(
+    column: &str,
+    ty: &NormalizedType,
+    term: &str,
+    mode: SearchMode,
+    case_sensitive: bool,
+) -> Option<SearchPredicate> {
+    let column = quote_ident(column);
+    let literal = term.replace('\'', "''");
+
+    if ty.is_textual() {
+        let (operator, pattern) = match (mode, case_sensitive) {
+            (SearchMode::Substring, false) => ("ILIKE", format!("%{}%", term)),
+            (SearchMode::Substring, true) => ("LIKE", format!("%{}%", term)),
+            (SearchMode::Regex, false) => ("~*", term.to_string()),
+            (SearchMode::Regex, true) => ("~", term.to_string()),
+        };
+        return Some(SearchPredicate {
+            condition: format!("CAST({} AS TEXT) {} $1", column, operator),
+            display: format!(
+                "CAST({} AS TEXT) {} '{}'",
+                column,
+                operator,
+                pattern.replace('\'', "''")
+            ),
+            bind: pattern,
+        });
+    }
+    if mode == SearchMode::Regex {
+        return None;
+    }
+
+    let is_date = chrono::NaiveDate::parse_from_str(term, "%Y-%m-%d").is_ok();
+    let (target, cast) = match ty {
+        NormalizedType::Integer { .. } if term.parse::<i64>().is_ok() => (column.clone(), "bigint"),
+        NormalizedType::Decimal { .. } | NormalizedType::Float { .. }
+            if term.parse::<f64>().is_ok_and(|v| v.is_finite()) =>
+        {
+            (column.clone(), "numeric")
+        }
+        NormalizedType::Bool if matches!(term.to_lowercase().as_str(), "true" | "false") => {
+            (column.clone(), "boolean")
+        }
+        NormalizedType::Uuid if Uuid::parse_str(term).is_ok() => (column.clone(), "uuid"),
+        NormalizedType::Date if is_date => (column.clone(), "date"),
+        NormalizedType::Timestamp { .. } if is_date => {
+            (format!("CAST({} AS date)", column), "date")
+        }
+        NormalizedType::Timestamp { with_time_zone } if parses_as_timestamp(term) => (
+            column.clone(),
+            if *with_time_zone {
+                "timestamptz"
+            } else {
+                "timestamp"
+            },
+        ),
+        _ => return None,
+    };
+
+    Some(SearchPredicate {
+        condition: format!("{} = $1::{}", target, cast),
+        bind: term.to_string(),
+        display: format!("{} = '{}'::{}", target, literal, cast),
+    })
+}
+
+/// Check that a regular expression compiles in PostgreSQL
+///
+/// Runs a probe match so an invalid pattern is reported as a bad request
+/// instead of failing (and being skipped) on every column.
+async fn validate_regex(pool: &PgPool, pattern: &str) -> ApiResult<()> {
+    match sqlx::query("SELECT '' ~ $1")
+        .bind(pattern)
+        .execute(pool)
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(sqlx::Error::Database(e))
+            if e.code().as_deref() == Some(INVALID_REGULAR_EXPRESSION) =>
+        {
+            Err(ApiError::BadRequest(format!(
+                "Invalid regular expression: {}",
+                e.message()
+            )))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Whether a term looks like a full timestamp (ISO 8601 / RFC 3339)
+fn parses_as_timestamp(term: &str) -> bool {
+    chrono::DateTime::parse_from_rfc3339(term).is_ok()
+        || ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"]
+            .iter()
+            .any(|fmt| chrono::NaiveDateTime::parse_from_str(term, fmt).is_ok())
 }
 
 /// Search across all tables in a dump
+#[tracing::instrument(skip_all, fields(dump_id = %id))]
 pub async fn search_in_dump(
     State(state): State<AppState>,
+    owner: Owner,
     Path(id): Path<Uuid>,
     Query(query): Query<SearchQuery>,
+    Query(pairs): Query<Vec<(String, String)>>,
 ) -> ApiResult<Json<SearchResponse>> {
     let limit = query.limit.unwrap_or(10).min(100);
+    let page_size = query
+        .page_size
+        .unwrap_or(MAX_SEARCH_PAGE_SIZE)
+        .clamp(1, MAX_SEARCH_PAGE_SIZE);
     let search_term = query.q.trim();
+    let scope = SearchScope::from_query(&pairs);
+    if query.mode == SearchMode::Regex {
+        validate_regex(&state.db_pool, search_term).await?;
+    }
 
     if search_term.is_empty() {
         return Err(ApiError::BadRequest(
@@ -65,22 +360,25 @@ pub async fn search_in_dump(
         r#"
         SELECT sandbox_db_name, sandbox_databases, status
         FROM dumps
-        WHERE id = $1
+        WHERE id = $1 AND ($2::text IS NULL OR owner = $2)
         "#,
     )
     .bind(id)
+    .bind(owner.as_deref())
     .fetch_optional(&state.db_pool)
     .await?;
 
     let row = dump_row.ok_or_else(|| ApiError::NotFound(format!("Dump {} not found", id)))?;
 
     let status: String = row.get("status");
+    reject_archived(&state, id, &status).await?;
     if status != "READY" {
         return Err(ApiError::BadRequest(format!(
             "Dump is not ready for search (status: {})",
             status
         )));
     }
+    record_view(&state.db_pool, id).await;
 
     let sandbox_db_name: Option<String> = row.get("sandbox_db_name");
     let sandbox_databases: Option<Vec<String>> = row.get("sandbox_databases");
@@ -94,87 +392,77 @@ pub async fn search_in_dump(
             .unwrap_or_else(|| sandbox_db_name.clone().map_or(vec![], |db| vec![db]))
     };
 
-    // Get schema graph for table information
-    let schema_rows = sqlx::query(
-        r#"
-        SELECT database_name, schema_graph
-        FROM dump_schemas
-        WHERE dump_id = $1
-        "#,
-    )
-    .bind(id)
-    .fetch_all(&state.db_pool)
-    .await?;
-
-    let mut all_results = Vec::new();
-    let mut searched_tables = 0;
+    // Visit databases, tables and columns in a fixed order so that offsets
+    // address the same results across requests
+    let mut databases_to_search = databases_to_search;
+    databases_to_search.sort();
 
+    // Find the schema graph of each database
+    let mut graphs: Vec<(String, Arc<SchemaGraph>)> = Vec::new();
     for db_name in databases_to_search {
-        // Find matching schema graph
-        let schema_graph: Option<SchemaGraph> = schema_rows
-            .iter()
-            .find(|r| {
-                let db: String = r.get("database_name");
-                db == db_name
-            })
-            .map(|r| {
-                let SqlxJson(graph): SqlxJson<SchemaGraph> = r.get("schema_graph");
-                graph
-            });
-
-        if schema_graph.is_none() {
-            continue;
+        if let Some(graph) = state.schema_graph(id, &db_name).await? {
+            graphs.push((db_name, graph));
         }
+    }
+    scope.validate(&graphs.iter().map(|(_, graph)| &**graph).collect::<Vec<_>>())?;
 
-        let graph = schema_graph.unwrap();
-
-        // Connect to sandbox database
-        let db_url = format!(
-            "postgres://{}:{}@{}:{}/{}",
-            state.config.sandbox_user,
-            state.config.sandbox_password.as_deref().unwrap_or(""),
-            state.config.sandbox_host,
-            state.config.sandbox_port,
-            db_name
-        );
+    let mut window = ResultWindow::new(query.offset.unwrap_or(0), page_size);
+    let mut searched_tables = 0;
 
-        let db_pool = match sqlx::PgPool::connect(&db_url).await {
+    'search: for (db_name, graph) in graphs {
+        let db_pool = match state.sandbox_pools.get(&db_name).await {
             Ok(pool) => pool,
             Err(_) => continue,
         };
 
+        let mut tables: Vec<&TableInfo> = graph.tables.iter().collect();
+        tables
+            .sort_by(|a, b| (&a.schema_name, &a.table_name).cmp(&(&b.schema_name, &b.table_name)));
+
         // Search in each table
-        for table in &graph.tables {
+        for table in tables {
+            if !scope.includes_table(table)
+                || !table.columns.iter().any(|c| scope.includes_column(&c.name))
+            {
+                continue;
+            }
             searched_tables += 1;
 
-            // Search in each text-like column
+            // Search in each column whose type can hold the search term
             for column in &table.columns {
-                let column_type = column.data_type.to_lowercase();
-
-                // Only search in text-compatible columns
-                if !column_type.contains("char")
-                    && !column_type.contains("text")
-                    && !column_type.contains("json")
-                {
+                if !scope.includes_column(&column.name) {
                     continue;
                 }
+                let Some(predicate) = search_predicate(
+                    &column.name,
+                    &column.normalized_type(&graph.enum_types),
+                    search_term,
+                    query.mode,
+                    query.case_sensitive,
+                ) else {
+                    continue;
+                };
 
-                // Build search query
+                // Build search query (ordered by physical row position so the
+                // row ordinal is stable)
                 let search_query = format!(
                     r#"
-                    SELECT to_jsonb(t.*) as row_data, "{}" as matched_value
-                    FROM "{}"."{}" t
-                    WHERE CAST("{}" AS TEXT) ILIKE $1
+                    SELECT to_jsonb(t.*) as row_data, to_jsonb({}) as matched_value
+                    FROM {}.{} t
+                    WHERE {}
+                    ORDER BY t.ctid
                     LIMIT {}
                     "#,
-                    column.name, table.schema_name, table.table_name, column.name, limit
+                    quote_ident(&column.name),
+                    quote_ident(&table.schema_name),
+                    quote_ident(&table.table_name),
+                    predicate.condition,
+                    limit
                 );
 
-                let search_pattern = format!("%{}%", search_term);
-
                 let rows = match sqlx::query(&search_query)
-                    .bind(&search_pattern)
-                    .fetch_all(&db_pool)
+                    .bind(&predicate.bind)
+                    .fetch_all(&*db_pool)
                     .await
                 {
                     Ok(rows) => rows,
@@ -182,28 +470,32 @@ pub async fn search_in_dump(
                 };
 
                 for row in rows {
+                    match window.next_slot() {
+                        WindowSlot::Skip => continue,
+                        WindowSlot::Full => break 'search,
+                        WindowSlot::Take => {}
+                    }
+
                     let row_data: serde_json::Value = row.get("row_data");
-                    let matched_value_str: String = row.get("matched_value");
-                    let matched_value = serde_json::Value::String(matched_value_str);
+                    let matched_value: serde_json::Value = row.get("matched_value");
 
                     // Generate SQL for reproducing this search
                     let sql_query = format!(
                         r#"-- Search in {}.{}.{}.{}
-SELECT * FROM "{}"."{}"
-WHERE CAST("{}" AS TEXT) ILIKE '%{}%'
+SELECT * FROM {}.{}
+WHERE {}
 LIMIT {};"#,
                         db_name,
                         table.schema_name,
                         table.table_name,
                         column.name,
-                        table.schema_name,
-                        table.table_name,
-                        column.name,
-                        search_term,
+                        quote_ident(&table.schema_name),
+                        quote_ident(&table.table_name),
+                        predicate.display,
                         limit
                     );
 
-                    all_results.push(SearchResult {
+                    window.results.push(SearchResult {
                         database_name: db_name.clone(),
                         schema_name: table.schema_name.clone(),
                         table_name: table.table_name.clone(),
@@ -212,31 +504,330 @@ LIMIT {};"#,
                         row_data,
                         sql_query,
                     });
-
-                    if all_results.len() >= 100 {
-                        break;
-                    }
-                }
-
-                if all_results.len() >= 100 {
-                    break;
                 }
             }
-
-            if all_results.len() >= 100 {
-                break;
-            }
-        }
-
-        if all_results.len() >= 100 {
-            break;
         }
     }
 
+    let next_offset = window.next_offset();
+    let all_results = window.results;
+
     Ok(Json(SearchResponse {
         query: search_term.to_string(),
         total_results: all_results.len(),
         results: all_results,
         searched_tables,
+        offset: query.offset.unwrap_or(0),
+        next_offset,
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn predicate_for(
+        column: &str,
+        data_type: &str,
+        term: &str,
+        mode: SearchMode,
+        case_sensitive: bool,
+    ) -> Option<SearchPredicate> {
+        let ty = NormalizedType::from_pg_type(data_type, &[]);
+        search_predicate(column, &ty, term, mode, case_sensitive)
+    }
+
+    #[test]
+    fn test_result_window_pages() {
+        let mut window = ResultWindow::new(2, 2);
+        let slots: Vec<WindowSlot> = (0..5).map(|_| window.next_slot()).collect();
+        assert_eq!(
+            slots,
+            vec![
+                WindowSlot::Skip,
+                WindowSlot::Skip,
+                WindowSlot::Take,
+                WindowSlot::Take,
+                WindowSlot::Full,
+            ]
+        );
+        assert_eq!(window.next_offset(), Some(4));
+    }
+
+    #[test]
+    fn test_result_window_last_page() {
+        let mut window = ResultWindow::new(0, 3);
+        for _ in 0..3 {
+            assert_eq!(window.next_slot(), WindowSlot::Take);
+        }
+        assert_eq!(window.next_offset(), None);
+    }
+
+    #[test]
+    fn test_search_predicate_text_uses_ilike() {
+        let predicate = predicate_for(
+            "name",
+            "character varying",
+            "o'brien",
+            SearchMode::Substring,
+            false,
+        )
+        .unwrap();
+        assert_eq!(predicate.condition, "CAST(\"name\" AS TEXT) ILIKE $1");
+        assert_eq!(predicate.bind, "%o'brien%");
+        assert_eq!(
+            predicate.display,
+            "CAST(\"name\" AS TEXT) ILIKE '%o''brien%'"
+        );
+    }
+
+    #[test]
+    fn test_search_predicate_integer_match() {
+        let predicate = predicate_for("id", "integer", "42", SearchMode::Substring, false).unwrap();
+        assert_eq!(predicate.condition, "\"id\" = $1::bigint");
+        assert_eq!(predicate.bind, "42");
+        assert_eq!(predicate.display, "\"id\" = '42'::bigint");
+
+        assert!(predicate_for("id", "integer", "abc", SearchMode::Substring, false).is_none());
+        assert!(predicate_for("id", "bigint", "4.2", SearchMode::Substring, false).is_none());
+    }
+
+    #[test]
+    fn test_search_predicate_uuid_match() {
+        let term = "6f9619ff-8b86-d011-b42d-00c04fc964ff";
+        let predicate =
+            predicate_for("external_id", "uuid", term, SearchMode::Substring, false).unwrap();
+        assert_eq!(predicate.condition, "\"external_id\" = $1::uuid");
+        assert_eq!(predicate.bind, term);
+
+        assert!(predicate_for(
+            "external_id",
+            "uuid",
+            "not-a-uuid",
+            SearchMode::Substring,
+            false
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_search_predicate_other_types() {
+        assert_eq!(
+            predicate_for("price", "numeric", "9.99", SearchMode::Substring, false)
+                .unwrap()
+                .condition,
+            "\"price\" = $1::numeric"
+        );
+        assert_eq!(
+            predicate_for("active", "boolean", "TRUE", SearchMode::Substring, false)
+                .unwrap()
+                .condition,
+            "\"active\" = $1::boolean"
+        );
+        assert!(
+            predicate_for("active", "boolean", "maybe", SearchMode::Substring, false).is_none()
+        );
+        assert_eq!(
+            predicate_for(
+                "created_at",
+                "timestamp with time zone",
+                "2024-01-15",
+                SearchMode::Substring,
+                false
+            )
+            .unwrap()
+            .condition,
+            "CAST(\"created_at\" AS date) = $1::date"
+        );
+        assert_eq!(
+            predicate_for(
+                "created_at",
+                "timestamp without time zone",
+                "2024-01-15 10:30:00",
+                SearchMode::Substring,
+                false
+            )
+            .unwrap()
+            .condition,
+            "\"created_at\" = $1::timestamp"
+        );
+        assert!(predicate_for("payload", "bytea", "42", SearchMode::Substring, false).is_none());
+    }
+
+    #[test]
+    fn test_search_predicate_case_sensitive_uses_like() {
+        let predicate =
+            predicate_for("name", "text", "Alice", SearchMode::Substring, true).unwrap();
+        assert_eq!(predicate.condition, "CAST(\"name\" AS TEXT) LIKE $1");
+        assert_eq!(predicate.bind, "%Alice%");
+        assert_eq!(predicate.display, "CAST(\"name\" AS TEXT) LIKE '%Alice%'");
+        // Only text matching depends on the flag
+        assert_eq!(
+            predicate_for("id", "integer", "7", SearchMode::Substring, true)
+                .unwrap()
+                .condition,
+            "\"id\" = $1::bigint"
+        );
+    }
+
+    fn graph() -> SchemaGraph {
+        let column = |name: &str| db_viewer_core::domain::ColumnInfo {
+            name: name.to_string(),
+            data_type: "text".to_string(),
+            is_nullable: true,
+            is_primary_key: false,
+            default_value: None,
+            comment: None,
+            ordinal_position: None,
+            is_identity: false,
+            identity_generation: None,
+            generation_expression: None,
+        };
+        let table = |schema: &str, name: &str, columns: &[&str]| TableInfo {
+            schema_name: schema.to_string(),
+            table_name: name.to_string(),
+            estimated_row_count: 0,
+            columns: columns.iter().map(|c| column(c)).collect(),
+            comment: None,
+            is_partition: false,
+            partition_of: None,
+            partition_strategy: None,
+        };
+        SchemaGraph {
+            tables: vec![
+                table("public", "users", &["id", "email"]),
+                table("public", "orders", &["id", "note"]),
+                table("audit", "users", &["id", "action"]),
+            ],
+            ..Default::default()
+        }
+    }
+
+    fn pairs(query: &[(&str, &str)]) -> Vec<(String, String)> {
+        query
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_search_scope_from_query() {
+        let scope = SearchScope::from_query(&pairs(&[
+            ("q", "x"),
+            ("tables[]", "public.users"),
+            ("tables[]", "orders"),
+            ("columns", "email,note"),
+            ("columns[]", "email"),
+        ]));
+        assert_eq!(scope.tables, vec!["public.users", "orders"]);
+        assert_eq!(scope.columns, vec!["email", "note"]);
+        assert_eq!(
+            SearchScope::from_query(&pairs(&[("q", "x")])),
+            SearchScope::default()
+        );
+    }
+
+    #[test]
+    fn test_search_scope_restricts_columns() {
+        let graph = graph();
+        let scope = SearchScope::from_query(&pairs(&[("columns[]", "email")]));
+        scope.validate(&[&graph]).unwrap();
+
+        let searched: Vec<(String, String)> = graph
+            .tables
+            .iter()
+            .filter(|t| scope.includes_table(t))
+            .flat_map(|t| {
+                t.columns
+                    .iter()
+                    .filter(|c| scope.includes_column(&c.name))
+                    .map(move |c| (t.table_name.clone(), c.name.clone()))
+            })
+            .collect();
+        assert_eq!(searched, vec![("users".to_string(), "email".to_string())]);
+    }
+
+    #[test]
+    fn test_search_scope_restricts_tables() {
+        let graph = graph();
+        let scope = SearchScope::from_query(&pairs(&[("tables[]", "public.users")]));
+        scope.validate(&[&graph]).unwrap();
+        let searched: Vec<&str> = graph
+            .tables
+            .iter()
+            .filter(|t| scope.includes_table(t))
+            .map(|t| t.schema_name.as_str())
+            .collect();
+        assert_eq!(searched, vec!["public"]);
+
+        // A bare name matches the table in every schema
+        let scope = SearchScope::from_query(&pairs(&[("tables[]", "users")]));
+        assert_eq!(
+            graph
+                .tables
+                .iter()
+                .filter(|t| scope.includes_table(t))
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_search_scope_rejects_unknown_names() {
+        let graph = graph();
+        let scope = SearchScope::from_query(&pairs(&[("tables[]", "public.missing")]));
+        assert!(scope.validate(&[&graph]).is_err());
+        // `action` exists, but not in the selected table
+        let scope = SearchScope::from_query(&pairs(&[
+            ("tables[]", "public.users"),
+            ("columns[]", "action"),
+        ]));
+        assert!(scope.validate(&[&graph]).is_err());
+    }
+
+    #[test]
+    fn test_search_predicate_regex() {
+        let predicate =
+            predicate_for("note", "text", "^order-[0-9]+'s$", SearchMode::Regex, false).unwrap();
+        assert_eq!(predicate.condition, "CAST(\"note\" AS TEXT) ~* $1");
+        assert_eq!(predicate.bind, "^order-[0-9]+'s$");
+        assert_eq!(
+            predicate.display,
+            "CAST(\"note\" AS TEXT) ~* '^order-[0-9]+''s$'"
+        );
+        assert_eq!(
+            predicate_for("note", "text", "x", SearchMode::Regex, true)
+                .unwrap()
+                .condition,
+            "CAST(\"note\" AS TEXT) ~ $1"
+        );
+        // Typed columns are not compared against a pattern
+        assert!(predicate_for("id", "integer", "42", SearchMode::Regex, false).is_none());
+    }
+
+    /// Requires a PostgreSQL server in `TEST_SANDBOX_URL`
+    #[tokio::test]
+    #[ignore = "requires TEST_SANDBOX_URL"]
+    async fn test_regex_search_matches_and_rejects_invalid_pattern() {
+        let url = std::env::var("TEST_SANDBOX_URL").expect("TEST_SANDBOX_URL is required");
+        let pool = PgPool::connect(&url).await.unwrap();
+
+        let pattern = "^order-[0-9]+$";
+        validate_regex(&pool, pattern).await.unwrap();
+        let predicate = predicate_for("note", "text", pattern, SearchMode::Regex, false).unwrap();
+        let matches: Vec<String> = sqlx::query_scalar(&format!(
+            "SELECT note FROM (VALUES ('Order-42'), ('order-x'), ('reorder-1')) t(note) WHERE {}",
+            predicate.condition
+        ))
+        .bind(&predicate.bind)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(matches, vec!["Order-42"]);
+
+        assert!(matches!(
+            validate_regex(&pool, "order-(").await,
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+}
@@ -1,8 +1,11 @@
 //! DB Viewer API Server
 
+mod auth;
 mod config;
 mod error;
 mod handlers;
+mod parquet_export;
+mod rate_limit;
 mod routes;
 mod state;
 
@@ -13,16 +16,24 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    // Load .env first so RUST_LOG and LOG_FORMAT can come from it
+    dotenvy::dotenv().ok();
+
     // Initialize tracing
-    tracing_subscriber::registry()
-        .with(tracing_subscriber::EnvFilter::new(
-            std::env::var("RUST_LOG").unwrap_or_else(|_| "info,db_viewer_api=debug".into()),
-        ))
-        .with(tracing_subscriber::fmt::layer())
-        .init();
+    let registry = tracing_subscriber::registry().with(tracing_subscriber::EnvFilter::new(
+        std::env::var("RUST_LOG").unwrap_or_else(|_| "info,db_viewer_api=debug".into()),
+    ));
+    // LOG_FORMAT=json emits one JSON object per line (with span fields such
+    // as dump_id) for log aggregation; text is the default
+    if std::env::var("LOG_FORMAT").is_ok_and(|f| f.eq_ignore_ascii_case("json")) {
+        registry
+            .with(tracing_subscriber::fmt::layer().json().flatten_event(true))
+            .init();
+    } else {
+        registry.with(tracing_subscriber::fmt::layer()).init();
+    }
 
     // Load configuration
-    dotenvy::dotenv().ok();
     let config = config::AppConfig::from_env()?;
 
     info!("Starting DB Viewer API Server");
@@ -46,13 +57,26 @@ async fn main() -> anyhow::Result<()> {
     });
 
     // Build router
+    let pool_state = state.clone();
     let app = routes::create_router(state);
 
     // Start server
     let addr: SocketAddr = format!("{}:{}", config.host, config.port).parse()?;
     let listener = tokio::net::TcpListener::bind(addr).await?;
 
-    axum::serve(listener, app).await?;
+    // Peer addresses identify clients for rate limiting. On SIGTERM/SIGINT
+    // the listener stops accepting and in-flight requests are drained.
+    let shutdown = db_viewer_core::shutdown::shutdown_signal()?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown)
+    .await?;
+
+    info!("Closing database connections");
+    pool_state.sandbox_pools.close_all().await;
+    pool_state.db_pool.close().await;
 
     Ok(())
 }
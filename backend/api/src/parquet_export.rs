@@ -0,0 +1,520 @@
+//! Parquet encoding of table rows
+//!
+//! The Arrow schema is inferred from the cached [`ColumnInfo`] types. Every
+//! column is selected as text in a canonical format (see
+//! [`ParquetColumn::select_expr`]) and parsed back into its Arrow type here,
+//! so the encoder does not depend on sqlx type mappings or session settings.
+//! Rows are encoded in batches and the output is handed out one row group at
+//! a time, keeping memory bounded by the row group size.
+
+use std::sync::Arc;
+
+use arrow_array::builder::{
+    BooleanBuilder, Date32Builder, Decimal128Builder, Float32Builder, Float64Builder, Int16Builder,
+    Int32Builder, Int64Builder, StringBuilder, TimestampMicrosecondBuilder,
+};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use chrono::{NaiveDate, NaiveDateTime};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::errors::{ParquetError, Result};
+use parquet::file::properties::WriterProperties;
+
+use db_viewer_core::domain::{ColumnInfo, NormalizedType};
+use db_viewer_core::sql_gen::quote_ident;
+
+/// Rows parsed into Arrow arrays before being handed to the writer
+const BATCH_ROWS: usize = 8192;
+
+/// Rows per Parquet row group, which the writer buffers before flushing
+const ROW_GROUP_ROWS: usize = 64 * 1024;
+
+/// Timestamp text format produced by [`ParquetColumn::select_expr`]
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.f";
+
+/// Largest precision an Arrow `Decimal128` can hold
+const MAX_DECIMAL_PRECISION: u8 = 38;
+
+/// Arrow representation of a PostgreSQL column type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColumnKind {
+    Int16,
+    Int32,
+    Int64,
+    Boolean,
+    Float32,
+    Float64,
+    /// `numeric(p, s)`; unconstrained `numeric` is exported as `Float64`
+    Decimal {
+        precision: u8,
+        scale: i8,
+    },
+    Timestamp,
+    /// `timestamptz`, stored in UTC
+    TimestampTz,
+    Date,
+    /// Everything else, as its PostgreSQL text representation
+    Text,
+}
+
+impl ColumnKind {
+    fn from_normalized(ty: &NormalizedType) -> Self {
+        match ty {
+            NormalizedType::Integer { bits: 16 } => Self::Int16,
+            NormalizedType::Integer { bits: 64 } => Self::Int64,
+            NormalizedType::Integer { .. } => Self::Int32,
+            NormalizedType::Bool => Self::Boolean,
+            NormalizedType::Float { bits: 32 } => Self::Float32,
+            NormalizedType::Float { .. } => Self::Float64,
+            NormalizedType::Decimal { precision, scale } => decimal_modifiers(*precision, *scale)
+                .map(|(precision, scale)| Self::Decimal { precision, scale })
+                .unwrap_or(Self::Float64),
+            NormalizedType::Timestamp {
+                with_time_zone: false,
+            } => Self::Timestamp,
+            NormalizedType::Timestamp {
+                with_time_zone: true,
+            } => Self::TimestampTz,
+            NormalizedType::Date => Self::Date,
+            _ => Self::Text,
+        }
+    }
+
+    fn data_type(&self) -> DataType {
+        match self {
+            Self::Int16 => DataType::Int16,
+            Self::Int32 => DataType::Int32,
+            Self::Int64 => DataType::Int64,
+            Self::Boolean => DataType::Boolean,
+            Self::Float32 => DataType::Float32,
+            Self::Float64 => DataType::Float64,
+            Self::Decimal { precision, scale } => DataType::Decimal128(*precision, *scale),
+            Self::Timestamp => DataType::Timestamp(TimeUnit::Microsecond, None),
+            Self::TimestampTz => DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+            Self::Date => DataType::Date32,
+            Self::Text => DataType::Utf8,
+        }
+    }
+}
+
+/// `(precision, scale)` of a `numeric(p, s)` modifier that fits `Decimal128`
+fn decimal_modifiers(precision: Option<u16>, scale: Option<i16>) -> Option<(u8, i8)> {
+    let precision = u8::try_from(precision?).ok()?;
+    let scale = i8::try_from(scale.unwrap_or(0)).ok()?;
+    (precision <= MAX_DECIMAL_PRECISION && (0..=precision as i8).contains(&scale))
+        .then_some((precision, scale))
+}
+
+/// A column of the exported table
+#[derive(Debug, Clone)]
+pub struct ParquetColumn {
+    name: String,
+    kind: ColumnKind,
+}
+
+impl ParquetColumn {
+    pub fn new(column: &ColumnInfo) -> Self {
+        Self {
+            name: column.name.clone(),
+            kind: ColumnKind::from_normalized(&column.normalized_type(&[])),
+        }
+    }
+
+    /// SQL expression selecting the column as text the encoder can parse
+    ///
+    /// Date and time values are formatted explicitly (timestamptz in UTC) so
+    /// the output does not depend on the session's `DateStyle` or `TimeZone`;
+    /// infinite values format as NULL.
+    pub fn select_expr(&self) -> String {
+        let ident = quote_ident(&self.name);
+        match self.kind {
+            ColumnKind::Timestamp => {
+                format!("to_char({}, 'YYYY-MM-DD\"T\"HH24:MI:SS.US')", ident)
+            }
+            ColumnKind::TimestampTz => format!(
+                "to_char({} AT TIME ZONE 'UTC', 'YYYY-MM-DD\"T\"HH24:MI:SS.US')",
+                ident
+            ),
+            ColumnKind::Date => format!("to_char({}, 'YYYY-MM-DD')", ident),
+            _ => format!("{}::text", ident),
+        }
+    }
+}
+
+/// Arrow array under construction for one column
+enum ColumnBuilder {
+    Int16(Int16Builder),
+    Int32(Int32Builder),
+    Int64(Int64Builder),
+    Boolean(BooleanBuilder),
+    Float32(Float32Builder),
+    Float64(Float64Builder),
+    Decimal(Decimal128Builder, i8),
+    Timestamp(TimestampMicrosecondBuilder),
+    Date(Date32Builder),
+    Text(StringBuilder),
+}
+
+impl ColumnBuilder {
+    fn new(kind: ColumnKind) -> Result<Self> {
+        Ok(match kind {
+            ColumnKind::Int16 => Self::Int16(Int16Builder::new()),
+            ColumnKind::Int32 => Self::Int32(Int32Builder::new()),
+            ColumnKind::Int64 => Self::Int64(Int64Builder::new()),
+            ColumnKind::Boolean => Self::Boolean(BooleanBuilder::new()),
+            ColumnKind::Float32 => Self::Float32(Float32Builder::new()),
+            ColumnKind::Float64 => Self::Float64(Float64Builder::new()),
+            ColumnKind::Decimal { precision, scale } => Self::Decimal(
+                Decimal128Builder::new().with_precision_and_scale(precision, scale)?,
+                scale,
+            ),
+            ColumnKind::Timestamp => Self::Timestamp(TimestampMicrosecondBuilder::new()),
+            ColumnKind::TimestampTz => {
+                Self::Timestamp(TimestampMicrosecondBuilder::new().with_timezone("UTC"))
+            }
+            ColumnKind::Date => Self::Date(Date32Builder::new()),
+            ColumnKind::Text => Self::Text(StringBuilder::new()),
+        })
+    }
+
+    /// Append one value; `None` is NULL
+    ///
+    /// Returns `Err(())` when the text cannot be parsed as the column type.
+    fn append(&mut self, value: Option<&str>) -> std::result::Result<(), ()> {
+        match self {
+            Self::Int16(b) => b.append_option(value.map(str::parse).transpose().map_err(drop)?),
+            Self::Int32(b) => b.append_option(value.map(str::parse).transpose().map_err(drop)?),
+            Self::Int64(b) => b.append_option(value.map(str::parse).transpose().map_err(drop)?),
+            Self::Boolean(b) => b.append_option(value.map(parse_bool).transpose()?),
+            // PostgreSQL's Infinity / -Infinity / NaN parse as Rust floats
+            Self::Float32(b) => b.append_option(value.map(str::parse).transpose().map_err(drop)?),
+            Self::Float64(b) => b.append_option(value.map(str::parse).transpose().map_err(drop)?),
+            Self::Decimal(b, scale) => {
+                let scale = *scale;
+                b.append_option(value.map(|v| parse_decimal(v, scale)).transpose()?)
+            }
+            Self::Timestamp(b) => b.append_option(
+                value
+                    .map(|v| {
+                        NaiveDateTime::parse_from_str(v, TIMESTAMP_FORMAT)
+                            .map(|t| t.and_utc().timestamp_micros())
+                    })
+                    .transpose()
+                    .map_err(drop)?,
+            ),
+            Self::Date(b) => b.append_option(
+                value
+                    .map(|v| {
+                        NaiveDate::parse_from_str(v, "%Y-%m-%d")
+                            .map(|d| (d - NaiveDate::default()).num_days() as i32)
+                    })
+                    .transpose()
+                    .map_err(drop)?,
+            ),
+            Self::Text(b) => b.append_option(value),
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        match self {
+            Self::Int16(b) => Arc::new(b.finish()),
+            Self::Int32(b) => Arc::new(b.finish()),
+            Self::Int64(b) => Arc::new(b.finish()),
+            Self::Boolean(b) => Arc::new(b.finish()),
+            Self::Float32(b) => Arc::new(b.finish()),
+            Self::Float64(b) => Arc::new(b.finish()),
+            Self::Decimal(b, _) => Arc::new(b.finish()),
+            Self::Timestamp(b) => Arc::new(b.finish()),
+            Self::Date(b) => Arc::new(b.finish()),
+            Self::Text(b) => Arc::new(b.finish()),
+        }
+    }
+}
+
+fn parse_bool(value: &str) -> std::result::Result<bool, ()> {
+    match value {
+        "true" | "t" => Ok(true),
+        "false" | "f" => Ok(false),
+        _ => Err(()),
+    }
+}
+
+/// Parse a decimal string into an integer scaled by `10^scale`
+///
+/// Extra fractional digits are truncated; PostgreSQL already rounds values to
+/// the column's scale, so this only matters for malformed input. `NaN` fails.
+fn parse_decimal(value: &str, scale: i8) -> std::result::Result<i128, ()> {
+    let (negative, digits) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value),
+    };
+    let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    let scale = scale as usize;
+    let fraction: String = fraction
+        .chars()
+        .chain(std::iter::repeat('0'))
+        .take(scale)
+        .collect();
+    let text = format!("{}{}", whole, fraction);
+    if text.is_empty() || !text.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(());
+    }
+    let unsigned: i128 = text.parse().map_err(drop)?;
+    Ok(if negative { -unsigned } else { unsigned })
+}
+
+/// Incremental Parquet writer over text rows
+pub struct ParquetEncoder {
+    columns: Vec<ParquetColumn>,
+    schema: SchemaRef,
+    builders: Vec<ColumnBuilder>,
+    buffered_rows: usize,
+    writer: ArrowWriter<Vec<u8>>,
+}
+
+impl ParquetEncoder {
+    pub fn new(columns: Vec<ParquetColumn>) -> Result<Self> {
+        let schema = Arc::new(Schema::new(
+            columns
+                .iter()
+                .map(|c| Field::new(&c.name, c.kind.data_type(), true))
+                .collect::<Vec<_>>(),
+        ));
+        let builders = columns
+            .iter()
+            .map(|c| ColumnBuilder::new(c.kind))
+            .collect::<Result<Vec<_>>>()?;
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .set_max_row_group_size(ROW_GROUP_ROWS)
+            .build();
+        let writer = ArrowWriter::try_new(Vec::new(), schema.clone(), Some(properties))?;
+        Ok(Self {
+            columns,
+            schema,
+            builders,
+            buffered_rows: 0,
+            writer,
+        })
+    }
+
+    /// Append one row, values in column order as produced by
+    /// [`ParquetColumn::select_expr`]
+    pub fn push_row<'a>(
+        &mut self,
+        values: impl IntoIterator<Item = Option<&'a str>>,
+    ) -> Result<()> {
+        let mut count = 0;
+        for (value, (builder, column)) in values
+            .into_iter()
+            .zip(self.builders.iter_mut().zip(&self.columns))
+        {
+            builder.append(value).map_err(|()| {
+                ParquetError::General(format!(
+                    "Invalid value {:?} for column {} ({:?})",
+                    value.unwrap_or_default(),
+                    column.name,
+                    column.kind.data_type()
+                ))
+            })?;
+            count += 1;
+        }
+        if count != self.columns.len() {
+            return Err(ParquetError::General(format!(
+                "Expected {} values per row, got {}",
+                self.columns.len(),
+                count
+            )));
+        }
+
+        self.buffered_rows += 1;
+        if self.buffered_rows >= BATCH_ROWS {
+            self.write_batch()?;
+        }
+        Ok(())
+    }
+
+    fn write_batch(&mut self) -> Result<()> {
+        if self.buffered_rows == 0 {
+            return Ok(());
+        }
+        let arrays = self
+            .builders
+            .iter_mut()
+            .map(ColumnBuilder::finish)
+            .collect();
+        let batch = RecordBatch::try_new(self.schema.clone(), arrays)?;
+        self.buffered_rows = 0;
+        self.writer.write(&batch)
+    }
+
+    /// Encoded bytes ready to be sent, i.e. completed row groups
+    pub fn take_output(&mut self) -> Vec<u8> {
+        std::mem::take(self.writer.inner_mut())
+    }
+
+    /// Write the remaining rows and the footer, returning the last bytes
+    pub fn finish(mut self) -> Result<Vec<u8>> {
+        self.write_batch()?;
+        self.writer.into_inner()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{
+        Date32Type, Decimal128Type, Float64Type, Int32Type, Int64Type, TimestampMicrosecondType,
+    };
+    use arrow_array::Array;
+    use axum::body::Bytes;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    fn kind(data_type: &str) -> ColumnKind {
+        ColumnKind::from_normalized(&NormalizedType::from_pg_type(data_type, &[]))
+    }
+
+    fn column(name: &str, data_type: &str) -> ParquetColumn {
+        ParquetColumn {
+            name: name.to_string(),
+            kind: kind(data_type),
+        }
+    }
+
+    #[test]
+    fn test_column_kind_from_pg_type() {
+        assert_eq!(kind("integer"), ColumnKind::Int32);
+        assert_eq!(kind("bigint"), ColumnKind::Int64);
+        assert_eq!(
+            kind("numeric(10,2)"),
+            ColumnKind::Decimal {
+                precision: 10,
+                scale: 2
+            }
+        );
+        assert_eq!(kind("numeric"), ColumnKind::Float64);
+        assert_eq!(kind("numeric(60,2)"), ColumnKind::Float64);
+        assert_eq!(kind("timestamp(3) with time zone"), ColumnKind::TimestampTz);
+        assert_eq!(kind("character varying(255)"), ColumnKind::Text);
+        assert_eq!(kind("public.mood"), ColumnKind::Text);
+    }
+
+    #[test]
+    fn test_select_expr() {
+        assert_eq!(column("id", "integer").select_expr(), "\"id\"::text");
+        assert_eq!(
+            column("at", "timestamp with time zone").select_expr(),
+            "to_char(\"at\" AT TIME ZONE 'UTC', 'YYYY-MM-DD\"T\"HH24:MI:SS.US')"
+        );
+    }
+
+    #[test]
+    fn test_parse_decimal() {
+        assert_eq!(parse_decimal("12.50", 2), Ok(1250));
+        assert_eq!(parse_decimal("-0.5", 2), Ok(-50));
+        assert_eq!(parse_decimal("7", 3), Ok(7000));
+        assert_eq!(parse_decimal("NaN", 2), Err(()));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let columns = vec![
+            column("id", "integer"),
+            column("views", "bigint"),
+            column("name", "text"),
+            column("active", "boolean"),
+            column("created_at", "timestamp with time zone"),
+            column("birthday", "date"),
+            column("price", "numeric(10,2)"),
+            column("score", "numeric"),
+        ];
+        let rows: [[Option<&str>; 8]; 3] = [
+            [
+                Some("1"),
+                Some("9000000000"),
+                Some("alice"),
+                Some("true"),
+                Some("2024-01-02T03:04:05.000006"),
+                Some("1990-05-17"),
+                Some("19.99"),
+                Some("0.25"),
+            ],
+            [Some("2"), None, None, None, None, None, None, None],
+            [
+                Some("3"),
+                Some("0"),
+                Some(""),
+                Some("false"),
+                Some("1970-01-01T00:00:00.000000"),
+                Some("1970-01-02"),
+                Some("-1.00"),
+                Some("-Infinity"),
+            ],
+        ];
+
+        let mut encoder = ParquetEncoder::new(columns).unwrap();
+        let mut output = Vec::new();
+        for row in rows {
+            encoder.push_row(row).unwrap();
+            output.extend(encoder.take_output());
+        }
+        output.extend(encoder.finish().unwrap());
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(Bytes::from(output))
+            .unwrap()
+            .build()
+            .unwrap();
+        let batches: Vec<RecordBatch> = reader.collect::<std::result::Result<_, _>>().unwrap();
+        assert_eq!(batches.len(), 1);
+        let batch = &batches[0];
+        assert_eq!(batch.num_rows(), 3);
+
+        let ids = batch.column(0).as_primitive::<Int32Type>();
+        assert_eq!(ids.values().to_vec(), vec![1, 2, 3]);
+
+        let views = batch.column(1).as_primitive::<Int64Type>();
+        assert_eq!(views.value(0), 9_000_000_000);
+        assert!(views.is_null(1));
+
+        let names = batch.column(2).as_string::<i32>();
+        assert_eq!(names.value(0), "alice");
+        assert!(names.is_null(1));
+        assert_eq!(names.value(2), "");
+
+        let active = batch.column(3).as_boolean();
+        assert!(active.value(0));
+        assert!(active.is_null(1));
+        assert!(!active.value(2));
+
+        assert_eq!(
+            batch.schema().field(4).data_type(),
+            &DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into()))
+        );
+        let created = batch.column(4).as_primitive::<TimestampMicrosecondType>();
+        assert_eq!(created.value(0), 1_704_164_645_000_006);
+        assert_eq!(created.value(2), 0);
+
+        let birthday = batch.column(5).as_primitive::<Date32Type>();
+        assert_eq!(birthday.value(0), 7441);
+        assert_eq!(birthday.value(2), 1);
+
+        let price = batch.column(6).as_primitive::<Decimal128Type>();
+        assert_eq!(price.data_type(), &DataType::Decimal128(10, 2));
+        assert_eq!(price.value(0), 1999);
+        assert_eq!(price.value(2), -100);
+
+        let score = batch.column(7).as_primitive::<Float64Type>();
+        assert_eq!(score.value(0), 0.25);
+        assert!(score.is_null(1));
+        assert_eq!(score.value(2), f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn test_invalid_value_is_an_error() {
+        let mut encoder = ParquetEncoder::new(vec![column("id", "integer")]).unwrap();
+        assert!(encoder.push_row([Some("abc")]).is_err());
+    }
+}
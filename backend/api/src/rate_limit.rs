@@ -0,0 +1,175 @@
+//! Per-client rate limiting for expensive endpoints
+//!
+//! Search, table data and data diffs run heavy queries against the sandbox
+//! server. [`RateLimiter`] counts requests per client IP in fixed windows and
+//! [`rate_limit`] rejects requests over the limit with 429 and `Retry-After`.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::error::ApiError;
+
+/// Clients tracked before windows that have ended are pruned
+const PRUNE_THRESHOLD: usize = 1024;
+
+/// Requests counted for one client in the current window
+struct Window {
+    started: Instant,
+    count: u32,
+}
+
+/// Fixed-window request counter keyed by client IP
+pub struct RateLimiter {
+    max_requests: u32,
+    window: Duration,
+    clients: Mutex<HashMap<IpAddr, Window>>,
+}
+
+impl RateLimiter {
+    /// Allow `max_requests` per `window` per client; 0 disables the limit
+    pub fn new(max_requests: u32, window: Duration) -> Self {
+        Self {
+            max_requests,
+            window,
+            clients: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Count a request from `client`
+    ///
+    /// Returns the time until the client's window resets when the request is
+    /// over the limit.
+    pub fn check(&self, client: IpAddr) -> Result<(), Duration> {
+        self.check_at(client, Instant::now())
+    }
+
+    fn check_at(&self, client: IpAddr, now: Instant) -> Result<(), Duration> {
+        if self.max_requests == 0 {
+            return Ok(());
+        }
+
+        let mut clients = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+        if clients.len() >= PRUNE_THRESHOLD {
+            clients.retain(|_, w| now.duration_since(w.started) < self.window);
+        }
+
+        let window = clients.entry(client).or_insert(Window {
+            started: now,
+            count: 0,
+        });
+        let elapsed = now.duration_since(window.started);
+        if elapsed >= self.window {
+            window.started = now;
+            window.count = 0;
+        }
+
+        if window.count >= self.max_requests {
+            return Err(self.window - now.duration_since(window.started));
+        }
+        window.count += 1;
+        Ok(())
+    }
+}
+
+/// Middleware rejecting clients over the limit with 429 Too Many Requests
+///
+/// Clients are identified by the peer address; requests without one (e.g.
+/// in tests) share a single bucket.
+pub async fn rate_limit(
+    State(limiter): State<Arc<RateLimiter>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let client = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip())
+        .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+
+    match limiter.check(client) {
+        Ok(()) => next.run(request).await,
+        // Round up so clients never retry before the window resets
+        Err(retry_after) => {
+            ApiError::TooManyRequests(retry_after.as_secs_f64().ceil().max(1.0) as u64)
+                .into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::Body,
+        http::{header, Request as HttpRequest, StatusCode},
+        middleware::from_fn_with_state,
+        routing::get,
+        Router,
+    };
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_window_resets() {
+        let limiter = RateLimiter::new(2, Duration::from_secs(10));
+        let client = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let other = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let start = Instant::now();
+
+        assert!(limiter.check_at(client, start).is_ok());
+        assert!(limiter.check_at(client, start).is_ok());
+        assert_eq!(
+            limiter.check_at(client, start + Duration::from_secs(4)),
+            Err(Duration::from_secs(6))
+        );
+        // Other clients have their own window
+        assert!(limiter.check_at(other, start).is_ok());
+        assert!(limiter
+            .check_at(client, start + Duration::from_secs(10))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_zero_disables_limit() {
+        let limiter = RateLimiter::new(0, Duration::from_secs(1));
+        for _ in 0..100 {
+            assert!(limiter.check(IpAddr::V4(Ipv4Addr::LOCALHOST)).is_ok());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_over_limit_gets_429() {
+        let limiter = Arc::new(RateLimiter::new(3, Duration::from_secs(60)));
+        let app = Router::new()
+            .route("/search", get(|| async { "ok" }))
+            .layer(from_fn_with_state(limiter, rate_limit));
+
+        for _ in 0..3 {
+            let response = app
+                .clone()
+                .oneshot(HttpRequest::get("/search").body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let response = app
+            .oneshot(HttpRequest::get("/search").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let retry_after: u64 = response.headers()[header::RETRY_AFTER]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!((1..=60).contains(&retry_after));
+    }
+}
@@ -1,37 +1,147 @@
 //! API route definitions
 
 use axum::{
+    body::Body,
     extract::DefaultBodyLimit,
+    http::{header, HeaderName, HeaderValue, Method, Request},
+    middleware::from_fn_with_state,
     routing::{delete, get, post, put},
     Router,
 };
-use tower_http::cors::{Any, CorsLayer};
+use std::sync::Arc;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
 use tower_http::trace::TraceLayer;
+use tracing::Span;
 
+use crate::auth::{require_api_key, ApiKeyAuth};
 use crate::handlers;
+use crate::rate_limit::rate_limit;
 use crate::state::AppState;
 
 /// Maximum upload size: 5GB
-const MAX_UPLOAD_SIZE: usize = 5 * 1024 * 1024 * 1024;
+pub(crate) const MAX_UPLOAD_SIZE: usize = 5 * 1024 * 1024 * 1024;
+
+/// Header carrying the request id, generated unless the client sends one
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Span wrapping each request, so every log line it produces carries the
+/// request id
+fn request_span(request: &Request<Body>) -> Span {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    tracing::info_span!(
+        "request",
+        method = %request.method(),
+        uri = %request.uri(),
+        request_id,
+    )
+}
+
+/// CORS layer allowing `origins`, or any origin when the list is empty
+///
+/// Origins that are not valid header values are skipped with a warning.
+fn cors_layer(origins: &[String]) -> CorsLayer {
+    if origins.is_empty() {
+        return CorsLayer::new()
+            .allow_origin(Any)
+            .allow_methods(Any)
+            .allow_headers(Any);
+    }
+
+    let origins = origins.iter().filter_map(|origin| {
+        HeaderValue::from_str(origin)
+            .map_err(|_| tracing::warn!("Ignoring invalid CORS origin {:?}", origin))
+            .ok()
+    });
+    CorsLayer::new()
+        .allow_origin(AllowOrigin::list(origins))
+        .allow_methods([
+            Method::GET,
+            Method::POST,
+            Method::PUT,
+            Method::PATCH,
+            Method::DELETE,
+        ])
+        .allow_headers([
+            header::AUTHORIZATION,
+            header::CONTENT_TYPE,
+            header::CONTENT_RANGE,
+            HeaderName::from_static(REQUEST_ID_HEADER),
+        ])
+        .expose_headers([HeaderName::from_static(REQUEST_ID_HEADER)])
+}
 
 /// Create the main application router
 pub fn create_router(state: AppState) -> Router {
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
+    let cors = cors_layer(&state.config.allowed_origins);
+
+    // Endpoints that scan sandbox tables, limited per client
+    let expensive = Router::new()
+        .route(
+            "/api/dumps/:id/tables/:table",
+            get(handlers::schema::get_table_data),
+        )
+        .route(
+            "/api/dumps/:id/tables/batch",
+            post(handlers::schema::get_table_data_batch),
+        )
+        .route(
+            "/api/dumps/:id/tables/:table/export.csv",
+            get(handlers::schema::export_table_csv),
+        )
+        .route(
+            "/api/dumps/:id/tables/:table/export.parquet",
+            get(handlers::schema::export_table_parquet),
+        )
+        .route(
+            "/api/dumps/:id/tables/:table/columns/:column/distribution",
+            get(handlers::schema::get_column_distribution),
+        )
+        .route(
+            "/api/dumps/:id/tables/:table/rows/:key/related",
+            get(handlers::schema::get_related_rows),
+        )
+        .route(
+            "/api/dumps/:id/suggest",
+            get(handlers::schema::suggest_values),
+        )
+        .route(
+            "/api/dumps/:base_id/compare/:compare_id/table/:schema/:table",
+            get(handlers::diff::compare_table_data),
+        )
+        .route(
+            "/api/dumps/:id/databases/:base_db/compare/:compare_db/table/:schema/:table",
+            get(handlers::diff::compare_database_table_data),
+        )
+        .route(
+            "/api/dumps/:id/search",
+            get(handlers::search::search_in_dump),
+        )
+        .route(
+            "/api/dumps/:id/delete-preview",
+            post(handlers::relation::preview_delete),
+        )
+        .route_layer(from_fn_with_state(state.rate_limiter.clone(), rate_limit));
+
+    let auth = Arc::new(ApiKeyAuth::from_config(&state.config));
 
     Router::new()
-        // Health check
-        .route("/health", get(handlers::health_check))
+        .route("/metrics", get(handlers::metrics::get_metrics))
         // Dump management
         .route("/api/dumps", post(handlers::dumps::create_dump))
         .route("/api/dumps", get(handlers::dumps::list_dumps))
         .route("/api/dumps/:id", get(handlers::dumps::get_dump))
         .route("/api/dumps/:id", delete(handlers::dumps::delete_dump))
+        .route("/api/dumps/:id/events", get(handlers::dumps::dump_events))
         .route(
             "/api/dumps/:id/upload",
-            put(handlers::dumps::upload_dump).layer(DefaultBodyLimit::max(MAX_UPLOAD_SIZE)),
+            put(handlers::dumps::upload_dump)
+                .patch(handlers::dumps::upload_dump_chunk)
+                .layer(DefaultBodyLimit::max(MAX_UPLOAD_SIZE)),
         )
         .route(
             "/api/dumps/:id/restore",
@@ -49,15 +159,48 @@ pub fn create_router(state: AppState) -> Router {
             "/api/dumps/:id/databases",
             get(handlers::dumps::get_dump_databases),
         )
+        .route(
+            "/api/dumps/:id/download",
+            get(handlers::dumps::download_dump),
+        )
         // Schema & Data
         .route("/api/dumps/:id/schema", get(handlers::schema::get_schema))
         .route(
-            "/api/dumps/:id/tables/:table",
-            get(handlers::schema::get_table_data),
+            "/api/dumps/:id/schema.sql",
+            get(handlers::schema::get_schema_sql),
         )
         .route(
-            "/api/dumps/:id/suggest",
-            get(handlers::schema::suggest_values),
+            "/api/dumps/:id/schema/cycles",
+            get(handlers::schema::get_fk_cycles),
+        )
+        .route(
+            "/api/dumps/:id/schema/restore-order",
+            get(handlers::schema::get_restore_order),
+        )
+        .route(
+            "/api/dumps/:id/schema/validate",
+            get(handlers::schema::validate_schema),
+        )
+        .route("/api/dumps/:id/tables", get(handlers::schema::list_tables))
+        .route(
+            "/api/dumps/:id/tables/top",
+            get(handlers::schema::get_top_tables),
+        )
+        .route(
+            "/api/dumps/:id/sequences",
+            get(handlers::schema::list_sequences),
+        )
+        .route(
+            "/api/dumps/:id/tables/:table/schema.json",
+            get(handlers::schema::get_table_json_schema),
+        )
+        .route(
+            "/api/dumps/:id/tables/:table/describe",
+            get(handlers::schema::describe_table),
+        )
+        .route(
+            "/api/dumps/:id/tables/:table/referents",
+            get(handlers::schema::get_table_referents),
         )
         .route("/api/dumps/:id/query", post(handlers::query::execute_query))
         // Interactive psql-like console
@@ -75,13 +218,12 @@ pub fn create_router(state: AppState) -> Router {
             get(handlers::diff::compare_dumps),
         )
         .route(
-            "/api/dumps/:base_id/compare/:compare_id/table/:schema/:table",
-            get(handlers::diff::compare_table_data),
+            "/api/dumps/:base_id/compare/:compare_id/export.json",
+            get(handlers::diff::export_schema_diff),
         )
-        // Search
         .route(
-            "/api/dumps/:id/search",
-            get(handlers::search::search_in_dump),
+            "/api/dumps/:id/compare-live",
+            post(handlers::diff::compare_live),
         )
         // Relationships & Risk
         .route(
@@ -101,8 +243,74 @@ pub fn create_router(state: AppState) -> Router {
             "/api/dumps/by-slug/:slug",
             get(handlers::dumps::get_dump_by_slug),
         )
-        // Layers
-        .layer(TraceLayer::new_for_http())
+        .merge(expensive)
+        // Everything above requires an API key when auth is enabled
+        .route_layer(from_fn_with_state(auth, require_api_key))
+        // Health checks stay open for load balancers and probes
+        .route("/health", get(handlers::health_check))
+        .route("/health/ready", get(handlers::readiness_check))
+        // Layers (the last added runs first)
+        .layer(TraceLayer::new_for_http().make_span_with(request_span))
+        .layer(PropagateRequestIdLayer::new(HeaderName::from_static(
+            REQUEST_ID_HEADER,
+        )))
+        .layer(SetRequestIdLayer::new(
+            HeaderName::from_static(REQUEST_ID_HEADER),
+            MakeRequestUuid,
+        ))
         .layer(cors)
         .with_state(state)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tower::ServiceExt;
+
+    /// Preflight request from `origin`, returning the allowed origin header
+    async fn preflight(app: Router, origin: &str) -> Option<HeaderValue> {
+        let request = Request::builder()
+            .method(Method::OPTIONS)
+            .uri("/api/dumps")
+            .header(header::ORIGIN, origin)
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        response
+            .headers()
+            .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+            .cloned()
+    }
+
+    #[tokio::test]
+    async fn test_cors_layer_allows_configured_origins() {
+        let origins = vec![
+            "https://lens.example.com".to_string(),
+            "http://localhost:3000".to_string(),
+        ];
+        let app = Router::new()
+            .route("/api/dumps", post(|| async { "ok" }))
+            .layer(cors_layer(&origins));
+
+        for origin in &origins {
+            assert_eq!(
+                preflight(app.clone(), origin).await.unwrap(),
+                origin.as_str()
+            );
+        }
+        assert!(preflight(app, "https://evil.example.com").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cors_layer_without_origins_allows_any() {
+        let app = Router::new()
+            .route("/api/dumps", post(|| async { "ok" }))
+            .layer(cors_layer(&[]));
+
+        assert_eq!(
+            preflight(app, "https://evil.example.com").await.unwrap(),
+            "*"
+        );
+    }
+}
@@ -1,11 +1,26 @@
 //! Application state
 
 use anyhow::Result;
-use sqlx::postgres::PgPool;
-use std::sync::Arc;
+use db_viewer_core::domain::SchemaGraph;
+use db_viewer_core::schema_codec::stored_schema_graph;
+use sqlx::postgres::{PgPool, PgPoolOptions};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use uuid::Uuid;
 
 use crate::config::AppConfig;
+use crate::error::{ApiError, ApiResult};
 use crate::handlers::console;
+use crate::rate_limit::RateLimiter;
+
+/// Idle sandbox connections are closed after this long
+const SANDBOX_POOL_IDLE_TIMEOUT_SECS: u64 = 300;
+
+/// Schema graphs kept in memory before the least recently used is dropped
+const SCHEMA_CACHE_CAPACITY: usize = 64;
 
 /// Shared application state
 #[derive(Clone)]
@@ -16,17 +31,357 @@ pub struct AppState {
     pub config: Arc<AppConfig>,
     /// Active interactive console sessions.
     pub console_sessions: Arc<console::session::SessionManager>,
+    /// Connection pools for sandbox databases
+    pub sandbox_pools: Arc<SandboxPoolCache>,
+    /// Request limiter for expensive endpoints
+    pub rate_limiter: Arc<RateLimiter>,
+    /// Parsed schema graphs of recently viewed dumps
+    pub schema_cache: Arc<SchemaGraphCache>,
 }
 
 impl AppState {
     /// Create new application state
     pub async fn new(config: &AppConfig) -> Result<Self> {
         let db_pool = PgPool::connect(&config.database_url).await?;
+        let config = Arc::new(config.clone());
 
         Ok(Self {
             db_pool,
-            config: Arc::new(config.clone()),
+            config: config.clone(),
             console_sessions: Arc::new(console::session::SessionManager::default()),
+            rate_limiter: Arc::new(RateLimiter::new(
+                config.rate_limit_requests,
+                Duration::from_secs(config.rate_limit_window_secs),
+            )),
+            schema_cache: Arc::new(SchemaGraphCache::new(Duration::from_secs(
+                config.cache_ttl_secs,
+            ))),
+            sandbox_pools: Arc::new(SandboxPoolCache::new(config)),
         })
     }
+
+    /// Schema graph of one of a dump's databases, from the cache when fresh
+    ///
+    /// `None` when the dump has no analyzed schema for that database. Callers
+    /// check the dump's status first; the cache only saves reading and
+    /// deserializing the stored graph.
+    pub async fn schema_graph(
+        &self,
+        dump_id: Uuid,
+        database: &str,
+    ) -> ApiResult<Option<Arc<SchemaGraph>>> {
+        self.schema_cache
+            .get_or_load(dump_id, database, || async {
+                let row: Option<(Option<serde_json::Value>, Option<Vec<u8>>)> = sqlx::query_as(
+                    "SELECT schema_graph, schema_graph_compressed FROM dump_schemas \
+                     WHERE dump_id = $1 AND database_name = $2",
+                )
+                .bind(dump_id)
+                .bind(database)
+                .fetch_optional(&self.db_pool)
+                .await?;
+                Ok(match row {
+                    Some((json, compressed)) => stored_schema_graph(json, compressed.as_deref())?,
+                    None => None,
+                })
+            })
+            .await
+    }
+}
+
+/// Cached schema graph
+struct CachedGraph {
+    graph: Arc<SchemaGraph>,
+    loaded_at: Instant,
+    /// Value of [`CachedGraphs::clock`] when last read
+    last_used: u64,
+}
+
+/// Entries of a [`SchemaGraphCache`]
+#[derive(Default)]
+struct CachedGraphs {
+    /// Incremented on every access, ordering entries by recency
+    clock: u64,
+    graphs: HashMap<(Uuid, String), CachedGraph>,
+}
+
+/// In-memory LRU cache of schema graphs keyed by dump and database
+///
+/// Entries expire after the configured TTL, which bounds how long a graph
+/// rewritten by the worker can be served stale; API routes that restore or
+/// delete a dump invalidate its entries right away.
+pub struct SchemaGraphCache {
+    ttl: Duration,
+    entries: Mutex<CachedGraphs>,
+}
+
+impl SchemaGraphCache {
+    /// Cache graphs for `ttl`; a zero TTL disables caching
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(CachedGraphs::default()),
+        }
+    }
+
+    /// Return the cached graph, or run `load` and cache what it finds
+    ///
+    /// Missing graphs are not cached, so a dump becomes visible as soon as
+    /// its analysis is stored.
+    pub async fn get_or_load<F, Fut>(
+        &self,
+        dump_id: Uuid,
+        database: &str,
+        load: F,
+    ) -> ApiResult<Option<Arc<SchemaGraph>>>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = ApiResult<Option<SchemaGraph>>>,
+    {
+        let key = (dump_id, database.to_string());
+        if let Some(graph) = self.get(&key) {
+            return Ok(Some(graph));
+        }
+
+        let Some(graph) = load().await?.map(Arc::new) else {
+            return Ok(None);
+        };
+        if !self.ttl.is_zero() {
+            self.insert(key, graph.clone());
+        }
+        Ok(Some(graph))
+    }
+
+    fn get(&self, key: &(Uuid, String)) -> Option<Arc<SchemaGraph>> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.clock += 1;
+        let clock = entries.clock;
+        match entries.graphs.get_mut(key) {
+            Some(entry) if entry.loaded_at.elapsed() < self.ttl => {
+                entry.last_used = clock;
+                Some(entry.graph.clone())
+            }
+            Some(_) => {
+                entries.graphs.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn insert(&self, key: (Uuid, String), graph: Arc<SchemaGraph>) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.clock += 1;
+        if !entries.graphs.contains_key(&key) && entries.graphs.len() >= SCHEMA_CACHE_CAPACITY {
+            let oldest = entries
+                .graphs
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                entries.graphs.remove(&oldest);
+            }
+        }
+        let last_used = entries.clock;
+        entries.graphs.insert(
+            key,
+            CachedGraph {
+                graph,
+                loaded_at: Instant::now(),
+                last_used,
+            },
+        );
+    }
+
+    /// Drop every cached graph of a dump, e.g. when it is deleted or restored
+    /// again
+    pub fn invalidate_dump(&self, dump_id: Uuid) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.graphs.retain(|(id, _), _| *id != dump_id);
+    }
+}
+
+/// Connection pools for sandbox databases, keyed by database name and role
+///
+/// Pools are created lazily on first use and shared by all requests, so each
+/// sandbox database holds at most `sandbox_pool_max_connections` connections
+/// per role.
+pub struct SandboxPoolCache {
+    config: Arc<AppConfig>,
+    pools: RwLock<HashMap<(String, Access), Arc<PgPool>>>,
+}
+
+/// Role a cached pool connects as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Access {
+    /// `sandbox_readonly_user`, for every query endpoint
+    ReadOnly,
+    /// `sandbox_user`, for creating and dropping databases
+    Privileged,
+}
+
+impl SandboxPoolCache {
+    /// Create an empty cache
+    pub fn new(config: Arc<AppConfig>) -> Self {
+        Self {
+            config,
+            pools: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Get the read-only pool for a sandbox database, connecting on first use
+    pub async fn get(&self, db_name: &str) -> ApiResult<Arc<PgPool>> {
+        self.get_as(db_name, Access::ReadOnly).await
+    }
+
+    /// Get a pool connected as the privileged sandbox user
+    ///
+    /// Only for server-level administration such as `DROP DATABASE`; queries
+    /// against restored data go through [`SandboxPoolCache::get`].
+    pub async fn get_privileged(&self, db_name: &str) -> ApiResult<Arc<PgPool>> {
+        self.get_as(db_name, Access::Privileged).await
+    }
+
+    async fn get_as(&self, db_name: &str, access: Access) -> ApiResult<Arc<PgPool>> {
+        let key = (db_name.to_string(), access);
+        if let Some(pool) = self.pools.read().await.get(&key) {
+            return Ok(pool.clone());
+        }
+
+        let url = match access {
+            Access::ReadOnly => self.config.sandbox_read_only_url(db_name),
+            Access::Privileged => self.config.sandbox_url(db_name),
+        };
+
+        // Connect without holding the lock; if another request won the race
+        // its pool is kept and this one is dropped
+        let statement_timeout_ms = self.config.statement_timeout_ms;
+        let pool = PgPoolOptions::new()
+            .max_connections(self.config.sandbox_pool_max_connections)
+            .idle_timeout(Duration::from_secs(SANDBOX_POOL_IDLE_TIMEOUT_SECS))
+            .after_connect(move |conn, _meta| {
+                Box::pin(async move {
+                    // Abort runaway queries (e.g. COUNT(*) on huge tables)
+                    sqlx::query(&format!("SET statement_timeout = {}", statement_timeout_ms))
+                        .execute(conn)
+                        .await?;
+                    Ok(())
+                })
+            })
+            .connect(&url)
+            .await
+            .map_err(|e| ApiError::Internal(format!("Failed to connect to sandbox: {}", e)))?;
+
+        let mut pools = self.pools.write().await;
+        Ok(pools.entry(key).or_insert_with(|| Arc::new(pool)).clone())
+    }
+
+    /// Close and remove the pools for a database, e.g. before it is dropped
+    pub async fn evict(&self, db_name: &str) {
+        let evicted: Vec<Arc<PgPool>> = {
+            let mut pools = self.pools.write().await;
+            [Access::ReadOnly, Access::Privileged]
+                .into_iter()
+                .filter_map(|access| pools.remove(&(db_name.to_string(), access)))
+                .collect()
+        };
+        for pool in evicted {
+            pool.close().await;
+        }
+    }
+
+    /// Close and remove every pool, e.g. on shutdown
+    pub async fn close_all(&self) {
+        let pools: Vec<Arc<PgPool>> = self.pools.write().await.drain().map(|(_, p)| p).collect();
+        for pool in pools {
+            pool.close().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Load through the cache, counting how often the loader runs
+    async fn load(
+        cache: &SchemaGraphCache,
+        loads: &AtomicUsize,
+        dump_id: Uuid,
+        database: &str,
+    ) -> Option<Arc<SchemaGraph>> {
+        cache
+            .get_or_load(dump_id, database, || async {
+                loads.fetch_add(1, Ordering::SeqCst);
+                Ok(Some(SchemaGraph::default()))
+            })
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_second_fetch_hits_cache() {
+        let cache = SchemaGraphCache::new(Duration::from_secs(60));
+        let loads = AtomicUsize::new(0);
+        let dump_id = Uuid::new_v4();
+
+        let first = load(&cache, &loads, dump_id, "app").await.unwrap();
+        let second = load(&cache, &loads, dump_id, "app").await.unwrap();
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+        assert!(Arc::ptr_eq(&first, &second));
+
+        // Each database of a dump is cached separately
+        load(&cache, &loads, dump_id, "other").await;
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+
+        cache.invalidate_dump(dump_id);
+        load(&cache, &loads, dump_id, "app").await;
+        assert_eq!(loads.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_zero_ttl_and_missing_graphs_are_not_cached() {
+        let cache = SchemaGraphCache::new(Duration::ZERO);
+        let loads = AtomicUsize::new(0);
+        let dump_id = Uuid::new_v4();
+        load(&cache, &loads, dump_id, "app").await;
+        load(&cache, &loads, dump_id, "app").await;
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+
+        let cache = SchemaGraphCache::new(Duration::from_secs(60));
+        for _ in 0..2 {
+            let graph = cache
+                .get_or_load(dump_id, "app", || async {
+                    loads.fetch_add(1, Ordering::SeqCst);
+                    Ok(None)
+                })
+                .await
+                .unwrap();
+            assert!(graph.is_none());
+        }
+        assert_eq!(loads.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_least_recently_used_is_evicted() {
+        let cache = SchemaGraphCache::new(Duration::from_secs(60));
+        let loads = AtomicUsize::new(0);
+        let dumps: Vec<Uuid> = (0..=SCHEMA_CACHE_CAPACITY)
+            .map(|_| Uuid::new_v4())
+            .collect();
+
+        for dump_id in &dumps[..SCHEMA_CACHE_CAPACITY] {
+            load(&cache, &loads, *dump_id, "app").await;
+        }
+        // Touch the oldest entry so the second one becomes least recently used
+        load(&cache, &loads, dumps[0], "app").await;
+        load(&cache, &loads, dumps[SCHEMA_CACHE_CAPACITY], "app").await;
+        let before = loads.load(Ordering::SeqCst);
+
+        load(&cache, &loads, dumps[0], "app").await;
+        assert_eq!(loads.load(Ordering::SeqCst), before);
+        load(&cache, &loads, dumps[1], "app").await;
+        assert_eq!(loads.load(Ordering::SeqCst), before + 1);
+    }
 }
@@ -1,13 +1,19 @@
 //! Database adapter abstraction for supporting multiple database types
 
-use crate::domain::{ForeignKey, SchemaGraph, TableInfo};
+use crate::connection::ReadOnlyRole;
+use crate::domain::{
+    CheckConstraint, EnumType, ForeignKey, IndexInfo, RelationDirection, RestoreOutcome,
+    SchemaGraph, SequenceInfo, TableInfo, TriggerInfo, UniqueConstraint, ViewInfo,
+};
 use crate::error::Result;
+use crate::filter::{FilterPredicate, OrderBy};
+use crate::masking::ColumnMask;
 use async_trait::async_trait;
 
 pub mod postgres;
 
-pub use postgres::PostgresAdapter;
 pub use postgres::TablePreview;
+pub use postgres::{validate_dump_header, PostgresAdapter};
 
 /// Abstract database adapter trait
 ///
@@ -15,10 +21,15 @@ pub use postgres::TablePreview;
 /// Each database type (PostgreSQL, MySQL, etc.) implements this trait.
 #[async_trait]
 pub trait DbAdapter: Send + Sync {
+    /// Decompress the dump file if it is compressed
+    /// Returns the path to restore from (the original path if uncompressed,
+    /// or if `restore_dump` can stream the decompressed content itself)
+    async fn decompress_dump(&self, dump_path: &str) -> Result<String>;
+
     /// Restore a dump file into the sandbox database
-    /// Returns a list of database names where data was restored
-    /// (for pg_dumpall format, multiple databases may be created)
-    async fn restore_dump(&self, dump_path: &str, db_name: &str) -> Result<Vec<String>>;
+    /// Returns the database names where data was restored (for pg_dumpall
+    /// format, multiple databases may be created) and any non-fatal warnings
+    async fn restore_dump(&self, dump_path: &str, db_name: &str) -> Result<RestoreOutcome>;
 
     /// Restore a dump file with table exclusions
     /// excluded_tables is a list of "schema.table_name" to skip
@@ -27,7 +38,17 @@ pub trait DbAdapter: Send + Sync {
         dump_path: &str,
         db_name: &str,
         excluded_tables: &[String],
-    ) -> Result<Vec<String>>;
+    ) -> Result<RestoreOutcome>;
+
+    /// Restore only the given schemas of a dump
+    /// An empty include_schemas restores everything; only custom-format
+    /// dumps can be filtered by schema
+    async fn restore_dump_filtered(
+        &self,
+        dump_path: &str,
+        db_name: &str,
+        include_schemas: &[String],
+    ) -> Result<RestoreOutcome>;
 
     /// List all tables in the database
     async fn list_tables(&self, db_name: &str) -> Result<Vec<TableInfo>>;
@@ -35,19 +56,70 @@ pub trait DbAdapter: Send + Sync {
     /// List all foreign keys in the database
     async fn list_foreign_keys(&self, db_name: &str) -> Result<Vec<ForeignKey>>;
 
+    /// List all indexes in the database
+    async fn list_indexes(&self, db_name: &str) -> Result<Vec<IndexInfo>>;
+
+    /// List all views and materialized views in the database
+    async fn list_views(&self, db_name: &str) -> Result<Vec<ViewInfo>>;
+
+    /// List all table triggers in the database
+    async fn list_triggers(&self, db_name: &str) -> Result<Vec<TriggerInfo>>;
+
+    /// List all enum types in the database with their values in sort order
+    async fn list_enum_types(&self, db_name: &str) -> Result<Vec<EnumType>>;
+
+    /// List sequences with their owning column and current value
+    async fn list_sequences(&self, db_name: &str) -> Result<Vec<SequenceInfo>>;
+
+    /// List the UNIQUE constraints of a table, excluding its primary key
+    ///
+    /// Constraints whose columns are all NOT NULL come first, then narrower
+    /// ones, so the first entry is the best natural key.
+    async fn list_unique_constraints(
+        &self,
+        db_name: &str,
+        schema: &str,
+        table: &str,
+    ) -> Result<Vec<UniqueConstraint>>;
+
+    /// List the CHECK constraints of a table by name (NOT NULL is not included)
+    async fn list_check_constraints(
+        &self,
+        db_name: &str,
+        schema: &str,
+        table: &str,
+    ) -> Result<Vec<CheckConstraint>>;
+
     /// Build the complete schema graph
     async fn build_schema_graph(&self, db_name: &str) -> Result<SchemaGraph> {
         let tables = self.list_tables(db_name).await?;
         let foreign_keys = self.list_foreign_keys(db_name).await?;
+        let indexes = self.list_indexes(db_name).await?;
+        let views = self.list_views(db_name).await?;
+        let triggers = self.list_triggers(db_name).await?;
+        let enum_types = self.list_enum_types(db_name).await?;
+        let sequences = self.list_sequences(db_name).await?;
         Ok(SchemaGraph {
             tables,
             foreign_keys,
+            indexes,
+            views,
+            triggers,
+            enum_types,
+            sequences,
         })
     }
 
     /// Estimate row counts for all tables
     async fn estimate_row_counts(&self, db_name: &str) -> Result<Vec<(String, String, i64)>>;
 
+    /// Count the rows of a table exactly with `SELECT COUNT(*)`
+    ///
+    /// Unlike `estimate_row_counts`, which reads `pg_stat_user_tables` and is
+    /// stale (often 0) until ANALYZE has run, this is always accurate but scans
+    /// the whole table, so it can take a long time on very large tables.
+    async fn exact_row_count(&self, db_name: &str, schema: &str, table: &str) -> Result<i64>;
+
     /// Fetch sample rows from a table
     async fn fetch_sample_rows(
         &self,
@@ -55,6 +127,39 @@ pub trait DbAdapter: Send + Sync {
         schema: &str,
         table: &str,
         limit: usize,
+    ) -> Result<Vec<serde_json::Value>> {
+        self.fetch_sample_rows_filtered(db_name, schema, table, &[], None, limit)
+            .await
+    }
+
+    /// Fetch sample rows matching `filter`, optionally ordered by a column
+    ///
+    /// The filter is rendered with [`crate::filter::render_filter`], so
+    /// values are bound as parameters; unknown filter or order columns are
+    /// rejected with [`crate::CoreError::Validation`].
+    async fn fetch_sample_rows_filtered(
+        &self,
+        db_name: &str,
+        schema: &str,
+        table: &str,
+        filter: &[FilterPredicate],
+        order_by: Option<OrderBy>,
+        limit: usize,
+    ) -> Result<Vec<serde_json::Value>>;
+
+    /// Fetch the rows on the other side of a foreign key from a known row
+    ///
+    /// `direction` is relative to the known row's table: `Outbound` fetches
+    /// the rows it references, `Inbound` the rows referencing it. `key_values`
+    /// are the known row's values of its own side's key columns, as text, in
+    /// foreign key column order; they are bound as query parameters.
+    async fn fetch_related_rows(
+        &self,
+        db_name: &str,
+        fk: &ForeignKey,
+        direction: RelationDirection,
+        key_values: &[String],
+        limit: usize,
     ) -> Result<Vec<serde_json::Value>>;
 
     /// Drop the sandbox database
@@ -64,10 +169,40 @@ pub trait DbAdapter: Send + Sync {
     async fn database_exists(&self, db_name: &str) -> Result<bool>;
 
     /// Create a new database
-    async fn create_database(&self, db_name: &str) -> Result<()>;
+    ///
+    /// An existing database of the same name is dropped and recreated only
+    /// with `overwrite`; otherwise this fails with
+    /// [`CoreError::DatabaseExists`](crate::CoreError::DatabaseExists), so a
+    /// name collision never destroys data silently.
+    async fn create_database(&self, db_name: &str, overwrite: bool) -> Result<()>;
+
+    /// Run ANALYZE to update table statistics
+    ///
+    /// Called after a restore and before the schema graph is built, so
+    /// `estimated_row_count` is meaningful without waiting for autovacuum.
+    /// Adapters for databases without planner statistics keep the default
+    /// no-op.
+    async fn analyze_database(&self, _db_name: &str) -> Result<()> {
+        Ok(())
+    }
 
-    /// Run ANALYZE to update table statistics (required for accurate row counts)
-    async fn analyze_database(&self, db_name: &str) -> Result<()>;
+    /// Overwrite masked columns in a restored database
+    ///
+    /// All updates run in one transaction. Masks whose table does not exist
+    /// in this database are skipped; returns the `(schema, table)` of each
+    /// table that was masked.
+    async fn mask_columns(
+        &self,
+        db_name: &str,
+        masks: &[ColumnMask],
+    ) -> Result<Vec<(String, String)>>;
+
+    /// Give a read-only login role access to a restored database
+    ///
+    /// The role is created on first use (with its password, if any) and granted
+    /// `CONNECT` plus `SELECT` on every table of the restored schemas, and
+    /// nothing else, so it cannot write or run DDL.
+    async fn grant_read_only(&self, db_name: &str, role: &ReadOnlyRole) -> Result<()>;
 }
 
 #[cfg(test)]
@@ -80,17 +215,42 @@ mod tests {
 
         #[async_trait]
         impl DbAdapter for TestAdapter {
-            async fn restore_dump(&self, dump_path: &str, db_name: &str) -> Result<Vec<String>>;
+            async fn decompress_dump(&self, dump_path: &str) -> Result<String>;
+            async fn restore_dump(&self, dump_path: &str, db_name: &str) -> Result<RestoreOutcome>;
             async fn restore_dump_with_exclusions(
                 &self,
                 dump_path: &str,
                 db_name: &str,
                 excluded_tables: &[String],
-            ) -> Result<Vec<String>>;
+            ) -> Result<RestoreOutcome>;
+            async fn restore_dump_filtered(
+                &self,
+                dump_path: &str,
+                db_name: &str,
+                include_schemas: &[String],
+            ) -> Result<RestoreOutcome>;
             async fn list_tables(&self, db_name: &str) -> Result<Vec<TableInfo>>;
             async fn list_foreign_keys(&self, db_name: &str) -> Result<Vec<ForeignKey>>;
+            async fn list_indexes(&self, db_name: &str) -> Result<Vec<IndexInfo>>;
+            async fn list_views(&self, db_name: &str) -> Result<Vec<ViewInfo>>;
+            async fn list_triggers(&self, db_name: &str) -> Result<Vec<TriggerInfo>>;
+            async fn list_enum_types(&self, db_name: &str) -> Result<Vec<EnumType>>;
+            async fn list_sequences(&self, db_name: &str) -> Result<Vec<SequenceInfo>>;
+            async fn list_unique_constraints(
+                &self,
+                db_name: &str,
+                schema: &str,
+                table: &str,
+            ) -> Result<Vec<UniqueConstraint>>;
+            async fn list_check_constraints(
+                &self,
+                db_name: &str,
+                schema: &str,
+                table: &str,
+            ) -> Result<Vec<CheckConstraint>>;
             async fn build_schema_graph(&self, db_name: &str) -> Result<SchemaGraph>;
             async fn estimate_row_counts(&self, db_name: &str) -> Result<Vec<(String, String, i64)>>;
+            async fn exact_row_count(&self, db_name: &str, schema: &str, table: &str) -> Result<i64>;
             async fn fetch_sample_rows(
                 &self,
                 db_name: &str,
@@ -98,10 +258,33 @@ mod tests {
                 table: &str,
                 limit: usize,
             ) -> Result<Vec<serde_json::Value>>;
+            async fn fetch_sample_rows_filtered(
+                &self,
+                db_name: &str,
+                schema: &str,
+                table: &str,
+                filter: &[FilterPredicate],
+                order_by: Option<OrderBy>,
+                limit: usize,
+            ) -> Result<Vec<serde_json::Value>>;
+            async fn fetch_related_rows(
+                &self,
+                db_name: &str,
+                fk: &ForeignKey,
+                direction: RelationDirection,
+                key_values: &[String],
+                limit: usize,
+            ) -> Result<Vec<serde_json::Value>>;
             async fn drop_database(&self, db_name: &str) -> Result<()>;
             async fn database_exists(&self, db_name: &str) -> Result<bool>;
-            async fn create_database(&self, db_name: &str) -> Result<()>;
+            async fn create_database(&self, db_name: &str, overwrite: bool) -> Result<()>;
             async fn analyze_database(&self, db_name: &str) -> Result<()>;
+            async fn mask_columns(
+                &self,
+                db_name: &str,
+                masks: &[ColumnMask],
+            ) -> Result<Vec<(String, String)>>;
+            async fn grant_read_only(&self, db_name: &str, role: &ReadOnlyRole) -> Result<()>;
         }
     }
 
@@ -115,4 +298,54 @@ mod tests {
         let result = mock.database_exists("test_db").await;
         assert!(result.unwrap());
     }
+
+    #[tokio::test]
+    async fn test_compare_with_live_schema_graph() {
+        use crate::diff::{compare_with_database, CompareOptions};
+        use crate::domain::ColumnInfo;
+
+        let table = |columns: &[&str]| TableInfo {
+            schema_name: "public".to_string(),
+            table_name: "users".to_string(),
+            estimated_row_count: 0,
+            columns: columns
+                .iter()
+                .map(|name| ColumnInfo {
+                    name: name.to_string(),
+                    data_type: "text".to_string(),
+                    is_nullable: true,
+                    is_primary_key: false,
+                    default_value: None,
+                    comment: None,
+                    ordinal_position: None,
+                    is_identity: false,
+                    identity_generation: None,
+                    generation_expression: None,
+                })
+                .collect(),
+            comment: None,
+            is_partition: false,
+            partition_of: None,
+            partition_strategy: None,
+        };
+        let base = SchemaGraph {
+            tables: vec![table(&["id"])],
+            ..Default::default()
+        };
+        let live = SchemaGraph {
+            tables: vec![table(&["id", "email"])],
+            ..Default::default()
+        };
+
+        let mut mock = MockTestAdapter::new();
+        mock.expect_build_schema_graph()
+            .with(mockall::predicate::eq("production"))
+            .returning(move |_| Ok(live.clone()));
+
+        let diff = compare_with_database(&base, &mock, "production", &CompareOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(diff.summary.tables_modified, 1);
+        assert_eq!(diff.summary.columns_added, 1);
+    }
 }
@@ -2,68 +2,467 @@
 
 use async_trait::async_trait;
 use flate2::read::GzDecoder;
-use sqlx::{postgres::PgPool, Row};
+use sqlx::{
+    postgres::{PgPool, PgPoolOptions},
+    Row,
+};
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufReader, Read};
 use std::path::Path;
-use std::process::Command;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::time::Duration;
 use tracing::{info, warn};
+use xz2::read::XzDecoder;
+use zstd::stream::read::Decoder as ZstdDecoder;
 
 use crate::adapter::DbAdapter;
-use crate::domain::{ColumnInfo, FkAction, ForeignKey, TableInfo};
+use crate::connection::{ReadOnlyRole, SandboxConnection};
+use crate::domain::{
+    CheckConstraint, ColumnInfo, DumpFormat, EnumType, FkAction, ForeignKey, IndexInfo,
+    RelationDirection, RestoreOutcome, SequenceInfo, TableInfo, TriggerInfo, UniqueConstraint,
+    ViewInfo,
+};
+use crate::dump_files::{is_directory_dump, DIRECTORY_DUMP_TOC};
 use crate::error::{CoreError, Result};
+use crate::filter::{render_filter, FilterPredicate, OrderBy};
+use crate::masking::{mask_statements, ColumnMask};
+use crate::sql_gen::{quote_ident, quote_literal, SqlGenerator};
 
 /// Magic bytes for pg_dump custom format
 const PG_DUMP_CUSTOM_MAGIC: [u8; 5] = [0x50, 0x47, 0x44, 0x4D, 0x50]; // "PGDMP"
 
+/// Maximum connections per sandbox database pool
+const DB_POOL_MAX_CONNECTIONS: u32 = 5;
+
+/// Idle connections in sandbox database pools are closed after this long
+const DB_POOL_IDLE_TIMEOUT_SECS: u64 = 300;
+
 /// Magic bytes for gzip compression
 const GZIP_MAGIC: [u8; 2] = [0x1F, 0x8B];
 
+/// Magic bytes for zstd compression
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+/// Magic bytes for xz compression
+const XZ_MAGIC: [u8; 6] = [0xFD, 0x37, 0x7A, 0x58, 0x5A, 0x00];
+
+/// Compression applied to an uploaded dump file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Compression {
+    Gzip,
+    Zstd,
+    Xz,
+    None,
+}
+
+impl Compression {
+    /// Detect compression from the leading bytes of a file
+    fn detect(magic: &[u8]) -> Self {
+        if magic.starts_with(&GZIP_MAGIC) {
+            Compression::Gzip
+        } else if magic.starts_with(&ZSTD_MAGIC) {
+            Compression::Zstd
+        } else if magic.starts_with(&XZ_MAGIC) {
+            Compression::Xz
+        } else {
+            Compression::None
+        }
+    }
+
+    /// Name used in logs
+    fn name(self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+            Compression::Xz => "xz",
+            Compression::None => "none",
+        }
+    }
+
+    /// Conventional file extension, stripped from the decompressed file name
+    fn extension(self) -> &'static str {
+        match self {
+            Compression::Gzip => ".gz",
+            Compression::Zstd => ".zst",
+            Compression::Xz => ".xz",
+            Compression::None => "",
+        }
+    }
+
+    /// Reader streaming the decompressed contents of `reader`
+    fn decoder<R: Read + Send + 'static>(self, reader: R) -> std::io::Result<Box<dyn Read + Send>> {
+        Ok(match self {
+            Compression::Gzip => Box::new(GzDecoder::new(reader)),
+            Compression::Zstd => Box::new(ZstdDecoder::new(reader)?),
+            // Decode concatenated streams too, like `xz -d`
+            Compression::Xz => Box::new(XzDecoder::new_multi_decoder(reader)),
+            Compression::None => Box::new(reader),
+        })
+    }
+}
+
+/// stderr fragments meaning the sandbox server could not be reached
+const CONNECTION_ERROR_MARKERS: &[&str] = &[
+    "could not connect to server",
+    "connection to server",
+    "Connection refused",
+    "server closed the connection unexpectedly",
+    "the database system is starting up",
+    "the database system is shutting down",
+    "terminating connection due to administrator command",
+    "could not translate host name",
+    "timeout expired",
+];
+
+/// stderr fragments meaning the dump itself is broken
+const SYNTAX_ERROR_MARKERS: &[&str] = &[
+    "syntax error",
+    "invalid input syntax",
+    "did not find magic string",
+    "input file does not appear to be a valid archive",
+    "input file appears to be a text format dump",
+    "unsupported version",
+    "unrecognized",
+];
+
+/// Classify a fatal pg_restore / psql stderr into a restore error kind
+fn classify_restore_stderr(stderr: &str) -> CoreError {
+    let message = stderr.trim().to_string();
+    if CONNECTION_ERROR_MARKERS.iter().any(|m| stderr.contains(m)) {
+        CoreError::RestoreConnection(message)
+    } else if SYNTAX_ERROR_MARKERS.iter().any(|m| stderr.contains(m)) {
+        CoreError::RestoreSyntaxError(message)
+    } else {
+        CoreError::RestoreUnknown(message)
+    }
+}
+
+/// Error for a restore tool that could not be started
+fn restore_tool_error(program: &str, e: std::io::Error) -> CoreError {
+    if e.kind() == std::io::ErrorKind::NotFound {
+        CoreError::RestoreToolMissing(format!("{} is not installed: {}", program, e))
+    } else {
+        CoreError::RestoreUnknown(format!("Failed to execute {}: {}", program, e))
+    }
+}
+
+/// Run a restore tool to completion, capturing its stdout and stderr
+///
+/// The child runs under `tokio::process` so a long restore does not block a
+/// runtime thread. Both pipes are drained concurrently while waiting, so a
+/// chatty tool cannot deadlock on a full pipe. The child is killed if the
+/// returned future is dropped, which is how a restore is cancelled; a child
+/// terminated by a signal is reported as [`CoreError::RestoreCancelled`].
+async fn run_restore_tool(cmd: Command, program: &str) -> Result<std::process::Output> {
+    let mut cmd = tokio::process::Command::from(cmd);
+    let child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| restore_tool_error(program, e))?;
+
+    let output = child
+        .wait_with_output()
+        .await
+        .map_err(|e| CoreError::RestoreUnknown(format!("Failed to wait for {}: {}", program, e)))?;
+    check_not_terminated(output, program)
+}
+
+/// Like [`run_restore_tool`], but feeds `input` to the tool's stdin
+///
+/// `input` is read in chunks on the blocking thread pool (decoders such as
+/// [`GzDecoder`] are synchronous) and written to the pipe as the tool
+/// consumes it, so the content never has to exist as a file. stdin is closed
+/// once `input` is exhausted. If the tool exits early the rest of `input` is
+/// discarded and the tool's own exit status is reported.
+async fn run_restore_tool_with_stdin(
+    cmd: Command,
+    program: &str,
+    mut input: Box<dyn Read + Send>,
+) -> Result<std::process::Output> {
+    use tokio::io::AsyncWriteExt;
+
+    let mut cmd = tokio::process::Command::from(cmd);
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| restore_tool_error(program, e))?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+
+    let feed = async move {
+        loop {
+            let (returned, chunk) = tokio::task::spawn_blocking(move || {
+                let mut chunk = vec![0; STDIN_CHUNK_BYTES];
+                let read = input.read(&mut chunk).map(|n| {
+                    chunk.truncate(n);
+                    chunk
+                });
+                (input, read)
+            })
+            .await
+            .map_err(std::io::Error::other)?;
+            input = returned;
+            let chunk = chunk?;
+            if chunk.is_empty() {
+                // Dropping stdin closes the pipe, signalling end of input
+                return Ok(());
+            }
+            stdin.write_all(&chunk).await?;
+        }
+    };
+    let (fed, output): (std::io::Result<()>, _) = tokio::join!(feed, child.wait_with_output());
+
+    let output = output
+        .map_err(|e| CoreError::RestoreUnknown(format!("Failed to wait for {}: {}", program, e)))?;
+    let output = check_not_terminated(output, program)?;
+    match fed {
+        // The tool stopped reading; its exit status explains why
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(output),
+        Err(e) => Err(CoreError::RestoreUnknown(format!(
+            "Failed to stream dump into {}: {}",
+            program, e
+        ))),
+        Ok(()) => Ok(output),
+    }
+}
+
+/// Bytes read from a decoder per write to a restore tool's stdin
+const STDIN_CHUNK_BYTES: usize = 64 * 1024;
+
+/// Report a tool killed by a signal as [`CoreError::RestoreCancelled`]
+fn check_not_terminated(
+    output: std::process::Output,
+    program: &str,
+) -> Result<std::process::Output> {
+    // No exit code means the process was killed by a signal
+    if output.status.code().is_none() {
+        return Err(CoreError::RestoreCancelled(format!(
+            "{} was terminated ({})",
+            program, output.status
+        )));
+    }
+    Ok(output)
+}
+
+/// Bytes of (decompressed) dump content inspected by `validate_dump_header`
+const DUMP_HEADER_PEEK_BYTES: u64 = 64 * 1024;
+
+/// Lines of a plain SQL dump searched for format signatures
+const DUMP_HEADER_PEEK_LINES: usize = 100;
+
+/// Whether a plain SQL line carries a pg_dumpall signature
+fn is_pg_dumpall_signature(line: &str) -> bool {
+    // pg_dumpall output starts with a "database cluster dump" comment and
+    // recreates roles before any database
+    line.contains("database cluster dump") || line.starts_with("CREATE ROLE")
+}
+
+/// Whether a line looks like part of a SQL script
+fn looks_like_sql(line: &str) -> bool {
+    const SQL_PREFIXES: &[&str] = &[
+        "--",
+        "SET ",
+        "SELECT ",
+        "CREATE ",
+        "ALTER ",
+        "COPY ",
+        "INSERT ",
+        "BEGIN",
+        "\\CONNECT",
+    ];
+    let line = line.trim_start().to_ascii_uppercase();
+    SQL_PREFIXES.iter().any(|prefix| line.starts_with(prefix))
+}
+
+/// Classify a dump from its leading (already decompressed) bytes
+pub fn classify_dump_header(head: &[u8]) -> DumpFormat {
+    if head.starts_with(&PG_DUMP_CUSTOM_MAGIC) {
+        return DumpFormat::CustomFormat;
+    }
+
+    // Plain dumps are text; the peek window may cut a multi-byte character
+    let text = match std::str::from_utf8(head) {
+        Ok(text) => text,
+        Err(e) if e.error_len().is_none() => {
+            std::str::from_utf8(&head[..e.valid_up_to()]).unwrap_or_default()
+        }
+        Err(_) => return DumpFormat::Unknown,
+    };
+    if text.contains('\0') {
+        return DumpFormat::Unknown;
+    }
+
+    let lines: Vec<&str> = text.lines().take(DUMP_HEADER_PEEK_LINES).collect();
+    if lines.iter().any(|line| is_pg_dumpall_signature(line)) {
+        DumpFormat::PgDumpall
+    } else if lines.iter().any(|line| looks_like_sql(line)) {
+        DumpFormat::PlainSql
+    } else {
+        DumpFormat::Unknown
+    }
+}
+
+/// Detect the format of an uploaded dump without restoring it
+///
+/// Compressed files are decompressed on the fly and only the first
+/// `DUMP_HEADER_PEEK_BYTES` are inspected, so this stays cheap for
+/// multi-GB uploads.
+pub fn validate_dump_header(dump_path: &str) -> Result<DumpFormat> {
+    let path = Path::new(dump_path);
+    if path.is_dir() {
+        let mut magic = Vec::with_capacity(PG_DUMP_CUSTOM_MAGIC.len());
+        if is_directory_dump(path) {
+            File::open(path.join(DIRECTORY_DUMP_TOC))?
+                .take(PG_DUMP_CUSTOM_MAGIC.len() as u64)
+                .read_to_end(&mut magic)?;
+        }
+        return Ok(if magic == PG_DUMP_CUSTOM_MAGIC {
+            DumpFormat::Directory
+        } else {
+            DumpFormat::Unknown
+        });
+    }
+
+    let mut magic = Vec::with_capacity(XZ_MAGIC.len());
+    File::open(dump_path)?
+        .take(XZ_MAGIC.len() as u64)
+        .read_to_end(&mut magic)?;
+
+    let mut head = Vec::new();
+    let peek = |reader: &mut dyn Read, head: &mut Vec<u8>| {
+        // A corrupt compressed stream still classifies whatever decoded
+        // cleanly (usually nothing, i.e. Unknown)
+        if let Err(e) = reader.take(DUMP_HEADER_PEEK_BYTES).read_to_end(head) {
+            warn!("Stopped reading dump header of {}: {}", dump_path, e);
+        }
+    };
+
+    match Compression::detect(&magic).decoder(File::open(dump_path)?) {
+        Ok(mut decoder) => peek(&mut decoder, &mut head),
+        Err(e) => warn!("Could not decompress dump header of {}: {}", dump_path, e),
+    }
+
+    Ok(classify_dump_header(&head))
+}
+
 /// PostgreSQL database adapter
 pub struct PostgresAdapter {
     /// Connection pool to the sandbox PostgreSQL server
     pool: PgPool,
-    /// PostgreSQL host for pg_restore
-    host: String,
-    /// PostgreSQL port
-    port: u16,
-    /// PostgreSQL user
-    user: String,
-    /// PostgreSQL password (for pg_restore)
-    password: Option<String>,
+    /// Sandbox server settings for per-database pools and pg_restore/psql
+    connection: SandboxConnection,
+    /// Parallel jobs for pg_restore (`-j`), custom-format archives only
+    restore_parallelism: u32,
+    /// Read tables, columns and foreign keys from `pg_catalog` rather than
+    /// `information_schema`
+    catalog_introspection: bool,
+    /// Restores replace an existing database of the same name instead of
+    /// failing
+    restore_overwrite: bool,
+    /// Pools for individual sandbox databases, created on first use
+    db_pools: Mutex<HashMap<String, PgPool>>,
 }
 
 impl PostgresAdapter {
     /// Create a new PostgreSQL adapter
-    pub fn new(
-        pool: PgPool,
-        host: String,
-        port: u16,
-        user: String,
-        password: Option<String>,
-    ) -> Self {
+    pub fn new(pool: PgPool, connection: SandboxConnection) -> Self {
         Self {
             pool,
-            host,
-            port,
-            user,
-            password,
+            connection,
+            restore_parallelism: 1,
+            catalog_introspection: false,
+            restore_overwrite: false,
+            db_pools: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Restore custom-format archives with `pg_restore -j jobs`
+    ///
+    /// Parallel restore needs a seekable custom-format archive, so plain SQL
+    /// and pg_dumpall dumps (restored through psql) stay single-threaded. Each
+    /// job holds its own sandbox connection.
+    pub fn with_restore_parallelism(mut self, jobs: u32) -> Self {
+        self.restore_parallelism = jobs;
+        self
+    }
+
+    /// Introspect tables, columns and foreign keys through `pg_catalog`
+    ///
+    /// The `information_schema` views check privileges and join through
+    /// several layers for every row, which gets slow with tens of thousands
+    /// of tables. The catalog queries build the same graph without the
+    /// privilege checks, which filter nothing for the sandbox superuser, and
+    /// read every table's columns in one query.
+    pub fn with_catalog_introspection(mut self, enabled: bool) -> Self {
+        self.catalog_introspection = enabled;
+        self
+    }
+
+    /// Let restores drop and recreate an existing database of the same name
+    ///
+    /// Off by default, so restoring into a name that is taken fails with
+    /// [`CoreError::DatabaseExists`]. Only enable it when the restore targets
+    /// are names the caller generated itself.
+    pub fn with_restore_overwrite(mut self, overwrite: bool) -> Self {
+        self.restore_overwrite = overwrite;
+        self
+    }
+
+    /// Introspect `db_name` through an existing pool instead of connecting
+    ///
+    /// For databases the sandbox settings cannot reach, e.g. a live database
+    /// compared against a dump; the pool's connection options (read-only,
+    /// timeouts) apply to every query the adapter runs on that database.
+    pub fn with_db_pool(self, db_name: &str, pool: PgPool) -> Self {
+        self.db_pools
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(db_name.to_string(), pool);
+        self
+    }
+
+    /// Get the pool for a sandbox database, connecting on first use
+    async fn db_pool(&self, db_name: &str) -> Result<PgPool> {
+        if let Some(pool) = self.cached_pool(db_name) {
+            return Ok(pool);
+        }
+
+        let pool = PgPoolOptions::new()
+            .max_connections(DB_POOL_MAX_CONNECTIONS)
+            .idle_timeout(Duration::from_secs(DB_POOL_IDLE_TIMEOUT_SECS))
+            .connect(&self.build_db_url(db_name))
+            .await?;
+
+        let mut pools = self.db_pools.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(pools.entry(db_name.to_string()).or_insert(pool).clone())
+    }
+
+    fn cached_pool(&self, db_name: &str) -> Option<PgPool> {
+        let pools = self.db_pools.lock().unwrap_or_else(|e| e.into_inner());
+        pools.get(db_name).cloned()
+    }
+
+    /// Close and forget the pool for a database (before it is dropped)
+    async fn evict_db_pool(&self, db_name: &str) {
+        let pool = {
+            let mut pools = self.db_pools.lock().unwrap_or_else(|e| e.into_inner());
+            pools.remove(db_name)
+        };
+        if let Some(pool) = pool {
+            pool.close().await;
         }
     }
 
     /// Build connection URL for a specific database
     fn build_db_url(&self, db_name: &str) -> String {
-        if let Some(ref password) = self.password {
-            format!(
-                "postgres://{}:{}@{}:{}/{}",
-                self.user, password, self.host, self.port, db_name
-            )
-        } else {
-            format!(
-                "postgres://{}@{}:{}/{}",
-                self.user, self.host, self.port, db_name
-            )
-        }
+        self.connection.url(db_name)
     }
 
     /// Parse FK action from PostgreSQL string
@@ -77,53 +476,152 @@ impl PostgresAdapter {
         }
     }
 
-    /// Detect if file is gzip compressed and decompress if needed
-    /// Returns the path to the (possibly decompressed) file
-    async fn decompress_if_needed(&self, dump_path: &str) -> Result<String> {
-        let path = Path::new(dump_path);
-        let file = File::open(path)
-            .map_err(|e| CoreError::RestoreFailed(format!("Failed to open dump file: {}", e)))?;
-        let mut reader = BufReader::new(file);
-        let mut magic = [0u8; 2];
+    /// Detect the compression of a dump file from its magic bytes
+    fn detect_compression(dump_path: &str) -> Result<Compression> {
+        // Directory-format dumps compress each data file themselves
+        if Path::new(dump_path).is_dir() {
+            return Ok(Compression::None);
+        }
+        let file = File::open(dump_path)
+            .map_err(|e| CoreError::RestoreUnknown(format!("Failed to open dump file: {}", e)))?;
+        let mut magic = Vec::with_capacity(XZ_MAGIC.len());
+        file.take(XZ_MAGIC.len() as u64)
+            .read_to_end(&mut magic)
+            .map_err(|e| CoreError::RestoreUnknown(format!("Failed to read dump file: {}", e)))?;
+        Ok(Compression::detect(&magic))
+    }
 
-        if reader.read_exact(&mut magic).is_ok() && magic == GZIP_MAGIC {
-            info!("Detected gzip-compressed dump, decompressing...");
+    /// Compression of a dump that [`DbAdapter::restore_dump`] can stream
+    /// straight into psql
+    ///
+    /// Only compressed plain SQL dumps qualify: custom-format archives need a
+    /// seekable file for pg_restore, and pg_dumpall dumps are rewritten to
+    /// prefix their database names first.
+    fn streamable_compression(dump_path: &str) -> Result<Option<Compression>> {
+        let compression = Self::detect_compression(dump_path)?;
+        if compression == Compression::None {
+            return Ok(None);
+        }
+        let format = validate_dump_header(dump_path)?;
+        Ok((format == DumpFormat::PlainSql).then_some(compression))
+    }
 
-            // Create decompressed file path
-            let decompressed_path = if dump_path.ends_with(".gz") {
-                dump_path.strip_suffix(".gz").unwrap().to_string()
-            } else {
-                format!("{}.decompressed", dump_path)
-            };
+    /// Open a decompressing reader over a compressed dump
+    fn open_decompressed(
+        dump_path: &str,
+        compression: Compression,
+    ) -> Result<Box<dyn Read + Send>> {
+        let file = File::open(dump_path)
+            .map_err(|e| CoreError::RestoreUnknown(format!("Failed to open dump file: {}", e)))?;
+        compression.decoder(BufReader::new(file)).map_err(|e| {
+            CoreError::RestoreUnknown(format!(
+                "Failed to decompress {} file: {}",
+                compression.name(),
+                e
+            ))
+        })
+    }
 
-            // Reopen file for decompression
-            let file = File::open(path).map_err(|e| {
-                CoreError::RestoreFailed(format!("Failed to open dump file: {}", e))
-            })?;
+    /// Restore a compressed plain SQL dump by piping the decompressed SQL
+    /// into psql's stdin, without writing a decompressed copy to disk
+    async fn restore_plain_sql_streaming(
+        &self,
+        dump_path: &str,
+        db_name: &str,
+        compression: Compression,
+    ) -> Result<RestoreOutcome> {
+        info!(
+            "Streaming {}-compressed plain SQL dump into psql",
+            compression.name()
+        );
+        self.create_database(db_name, self.restore_overwrite)
+            .await?;
 
-            let mut decoder = GzDecoder::new(file);
-            let mut output_file = File::create(&decompressed_path).map_err(|e| {
-                CoreError::RestoreFailed(format!("Failed to create decompressed file: {}", e))
-            })?;
+        let mut cmd = Command::new("psql");
+        cmd.args([
+            "-h",
+            &self.connection.host,
+            "-p",
+            &self.connection.port.to_string(),
+            "-U",
+            &self.connection.user,
+            "-d",
+            db_name,
+            "-v",
+            "ON_ERROR_STOP=0", // Continue on errors
+            "-f",
+            "-", // Read the script from stdin
+        ]);
+        self.connection.apply_env(&mut cmd);
+
+        let input = Self::open_decompressed(dump_path, compression)?;
+        let result = run_restore_tool_with_stdin(cmd, "psql", input).await;
+
+        let mut warnings = Vec::new();
+        match result {
+            Ok(output) => check_psql_output(&output, &mut warnings)?,
+            Err(CoreError::RestoreToolMissing(e)) => {
+                // psql not available; the SQLx fallback needs a file
+                warn!("{}, falling back to SQLx execution", e);
+                let sql_path = self.decompress_if_needed(dump_path).await?;
+                self.execute_sql_with_sqlx(&sql_path, db_name).await?;
+            }
+            Err(e) => return Err(e),
+        }
 
-            std::io::copy(&mut decoder, &mut output_file).map_err(|e| {
-                CoreError::RestoreFailed(format!("Failed to decompress gzip file: {}", e))
-            })?;
+        info!("Successfully restored streamed dump into {}", db_name);
+        Ok(RestoreOutcome {
+            databases: vec![db_name.to_string()],
+            warnings,
+        })
+    }
 
-            info!("Decompressed to: {}", decompressed_path);
-            return Ok(decompressed_path);
+    /// Detect if file is gzip compressed and decompress if needed
+    /// Returns the path to the (possibly decompressed) file
+    async fn decompress_if_needed(&self, dump_path: &str) -> Result<String> {
+        let compression = Self::detect_compression(dump_path)?;
+        if compression == Compression::None {
+            // Not compressed, return original path
+            return Ok(dump_path.to_string());
         }
 
-        // Not compressed, return original path
-        Ok(dump_path.to_string())
+        info!(
+            "Detected {}-compressed dump, decompressing...",
+            compression.name()
+        );
+
+        // Create decompressed file path
+        let decompressed_path = match dump_path.strip_suffix(compression.extension()) {
+            Some(stripped) => stripped.to_string(),
+            None => format!("{}.decompressed", dump_path),
+        };
+
+        let mut output_file = File::create(&decompressed_path).map_err(|e| {
+            CoreError::RestoreUnknown(format!("Failed to create decompressed file: {}", e))
+        })?;
+
+        let mut decoder = Self::open_decompressed(dump_path, compression)?;
+        std::io::copy(&mut decoder, &mut output_file).map_err(|e| {
+            CoreError::RestoreUnknown(format!(
+                "Failed to decompress {} file: {}",
+                compression.name(),
+                e
+            ))
+        })?;
+
+        info!("Decompressed to: {}", decompressed_path);
+        Ok(decompressed_path)
     }
 
     /// Detect pg_dump format by reading magic bytes
     /// Returns true for custom/tar format, false for plain SQL
     fn detect_pg_dump_format(&self, dump_path: &str) -> Result<bool> {
         let path = Path::new(dump_path);
+        if path.is_dir() {
+            return Ok(is_directory_dump(path));
+        }
         let file = File::open(path).map_err(|e| {
-            CoreError::RestoreFailed(format!(
+            CoreError::RestoreUnknown(format!(
                 "Failed to open dump file for format detection: {}",
                 e
             ))
@@ -151,20 +649,19 @@ impl PostgresAdapter {
 
         let path = Path::new(dump_path);
         let file = File::open(path)
-            .map_err(|e| CoreError::RestoreFailed(format!("Failed to open dump file: {}", e)))?;
+            .map_err(|e| CoreError::RestoreUnknown(format!("Failed to open dump file: {}", e)))?;
         let reader = BufReader::new(file);
 
         let mut databases = Vec::new();
         let mut is_pg_dumpall = false;
 
         // Check first 100 lines for pg_dumpall signatures
-        for line in reader.lines().take(100).map_while(|r| r.ok()) {
-            // pg_dumpall typically has "database cluster dump" comment
-            if line.contains("database cluster dump") {
-                is_pg_dumpall = true;
-            }
-            // Check for CREATE ROLE statements (another pg_dumpall signature)
-            if line.starts_with("CREATE ROLE") {
+        for line in reader
+            .lines()
+            .take(DUMP_HEADER_PEEK_LINES)
+            .map_while(|r| r.ok())
+        {
+            if is_pg_dumpall_signature(&line) {
                 is_pg_dumpall = true;
             }
         }
@@ -175,7 +672,7 @@ impl PostgresAdapter {
 
         // Re-read file to find all CREATE DATABASE statements
         let file = File::open(path)
-            .map_err(|e| CoreError::RestoreFailed(format!("Failed to open dump file: {}", e)))?;
+            .map_err(|e| CoreError::RestoreUnknown(format!("Failed to open dump file: {}", e)))?;
         let reader = BufReader::new(file);
 
         for line in reader.lines().map_while(|r| r.ok()) {
@@ -207,13 +704,13 @@ impl PostgresAdapter {
 
         let path = Path::new(dump_path);
         let file = File::open(path)
-            .map_err(|e| CoreError::RestoreFailed(format!("Failed to open dump file: {}", e)))?;
+            .map_err(|e| CoreError::RestoreUnknown(format!("Failed to open dump file: {}", e)))?;
         let reader = BufReader::new(file);
 
         // Output file with rewritten content
         let rewritten_path = format!("{}.rewritten", dump_path);
         let mut output = File::create(&rewritten_path).map_err(|e| {
-            CoreError::RestoreFailed(format!("Failed to create rewritten dump: {}", e))
+            CoreError::RestoreUnknown(format!("Failed to create rewritten dump: {}", e))
         })?;
 
         let mut databases = Vec::new();
@@ -221,6 +718,8 @@ impl PostgresAdapter {
             std::collections::HashMap::new();
 
         // First pass: identify database names
+        // The list keeps the order in which databases appear in the dump so the
+        // first entry is stable across runs
         for line in reader.lines().map_while(|r| r.ok()) {
             if line.starts_with("CREATE DATABASE ") {
                 let parts: Vec<&str> = line.split_whitespace().collect();
@@ -229,27 +728,24 @@ impl PostgresAdapter {
                     if original_name != "template0"
                         && original_name != "template1"
                         && original_name != "postgres"
+                        && !db_name_map.contains_key(&original_name)
                     {
                         let new_name = format!("{}_{}", prefix, original_name);
+                        databases.push(new_name.clone());
                         db_name_map.insert(original_name, new_name);
                     }
                 }
             }
         }
 
-        // Build prefixed database list
-        for new_name in db_name_map.values() {
-            databases.push(new_name.clone());
-        }
-
         // Second pass: rewrite the dump
         let file = File::open(path)
-            .map_err(|e| CoreError::RestoreFailed(format!("Failed to open dump file: {}", e)))?;
+            .map_err(|e| CoreError::RestoreUnknown(format!("Failed to open dump file: {}", e)))?;
         let reader = BufReader::new(file);
 
         for line in reader.lines() {
             let line =
-                line.map_err(|e| CoreError::RestoreFailed(format!("Failed to read line: {}", e)))?;
+                line.map_err(|e| CoreError::RestoreUnknown(format!("Failed to read line: {}", e)))?;
 
             let mut rewritten_line = line.clone();
 
@@ -291,7 +787,7 @@ impl PostgresAdapter {
             }
 
             writeln!(output, "{}", rewritten_line).map_err(|e| {
-                CoreError::RestoreFailed(format!("Failed to write rewritten dump: {}", e))
+                CoreError::RestoreUnknown(format!("Failed to write rewritten dump: {}", e))
             })?;
         }
 
@@ -796,8 +1292,22 @@ pub struct TablePreview {
 
 #[async_trait]
 impl DbAdapter for PostgresAdapter {
-    async fn restore_dump(&self, dump_path: &str, db_name: &str) -> Result<Vec<String>> {
+    async fn decompress_dump(&self, dump_path: &str) -> Result<String> {
+        // restore_dump streams these itself; skip the decompressed copy
+        if Self::streamable_compression(dump_path)?.is_some() {
+            return Ok(dump_path.to_string());
+        }
+        self.decompress_if_needed(dump_path).await
+    }
+
+    async fn restore_dump(&self, dump_path: &str, db_name: &str) -> Result<RestoreOutcome> {
         info!("Restoring dump {} to database {}", dump_path, db_name);
+        if let Some(compression) = Self::streamable_compression(dump_path)? {
+            return self
+                .restore_plain_sql_streaming(dump_path, db_name, compression)
+                .await;
+        }
+        let mut warnings = Vec::new();
 
         // Detect dump format from magic bytes, not extension
         let actual_path = self.decompress_if_needed(dump_path).await?;
@@ -827,17 +1337,24 @@ impl DbAdapter for PostgresAdapter {
 
         // Create database first (only for non-pg_dumpall dumps)
         if pg_dumpall_databases.is_empty() {
-            self.create_database(db_name).await?;
+            self.create_database(db_name, self.restore_overwrite)
+                .await?;
         }
 
+        let format = if is_custom_format {
+            archive_format(&actual_path)
+        } else if !pg_dumpall_databases.is_empty() {
+            DumpFormat::PgDumpall
+        } else {
+            DumpFormat::PlainSql
+        };
         info!(
             "Detected dump format: {}",
-            if is_custom_format {
-                "custom/tar"
-            } else if !pg_dumpall_databases.is_empty() {
-                "pg_dumpall (cluster)"
-            } else {
-                "plain SQL"
+            match format {
+                DumpFormat::CustomFormat => "custom/tar",
+                DumpFormat::Directory => "directory",
+                DumpFormat::PgDumpall => "pg_dumpall (cluster)",
+                _ => "plain SQL",
             }
         );
 
@@ -846,29 +1363,27 @@ impl DbAdapter for PostgresAdapter {
             let mut cmd = Command::new("pg_restore");
             cmd.args([
                 "-h",
-                &self.host,
+                &self.connection.host,
                 "-p",
-                &self.port.to_string(),
+                &self.connection.port.to_string(),
                 "-U",
-                &self.user,
+                &self.connection.user,
                 "-d",
                 db_name,
                 "--no-owner",
                 "--no-privileges",
                 "--no-tablespaces", // Ignore tablespace settings from source DB
-                &actual_path,
             ]);
+            cmd.args(pg_restore_format_args(format));
+            cmd.args(pg_restore_parallel_args(format, self.restore_parallelism));
+            cmd.arg(&actual_path);
 
-            if let Some(ref password) = self.password {
-                cmd.env("PGPASSWORD", password);
-            }
+            self.connection.apply_env(&mut cmd);
 
-            let output = cmd.output().map_err(|e| {
-                CoreError::RestoreFailed(format!("Failed to execute pg_restore: {}", e))
-            })?;
+            let output = run_restore_tool(cmd, "pg_restore").await?;
 
+            let stderr = String::from_utf8_lossy(&output.stderr);
             if !output.status.success() {
-                let stderr = String::from_utf8_lossy(&output.stderr);
                 // Only fail on fatal errors, not on ignorable warnings like
                 // tablespace, transaction_timeout, or permission issues
                 let is_fatal = stderr.contains("FATAL")
@@ -877,9 +1392,12 @@ impl DbAdapter for PostgresAdapter {
                         && !stderr.contains("transaction_timeout")
                         && !stderr.contains("errors ignored on restore"));
                 if is_fatal {
-                    return Err(CoreError::RestoreFailed(stderr.to_string()));
+                    return Err(classify_restore_stderr(&stderr));
                 }
+            }
+            if !stderr.trim().is_empty() {
                 warn!("pg_restore completed with warnings: {}", stderr);
+                warnings.extend(restore_warning_lines(&stderr));
             }
         } else {
             // Plain SQL format - use psql command for proper handling of COPY statements
@@ -896,11 +1414,11 @@ impl DbAdapter for PostgresAdapter {
             let mut cmd = Command::new("psql");
             cmd.args([
                 "-h",
-                &self.host,
+                &self.connection.host,
                 "-p",
-                &self.port.to_string(),
+                &self.connection.port.to_string(),
                 "-U",
-                &self.user,
+                &self.connection.user,
                 "-d",
                 connect_db,
                 "-v",
@@ -909,33 +1427,16 @@ impl DbAdapter for PostgresAdapter {
                 &restore_path, // Use the (possibly rewritten) dump path
             ]);
 
-            if let Some(ref password) = self.password {
-                cmd.env("PGPASSWORD", password);
-            }
-
-            let output = cmd.output();
+            self.connection.apply_env(&mut cmd);
 
-            match output {
-                Ok(output) => {
-                    if !output.status.success() {
-                        let stderr = String::from_utf8_lossy(&output.stderr);
-                        // Only fail on fatal errors, not warnings or role errors
-                        if stderr.contains("FATAL") {
-                            return Err(CoreError::RestoreFailed(stderr.to_string()));
-                        }
-                        warn!("psql completed with warnings: {}", stderr);
-                    }
-                    let stdout = String::from_utf8_lossy(&output.stdout);
-                    info!(
-                        "psql output: {}",
-                        stdout.chars().take(500).collect::<String>()
-                    );
-                }
-                Err(e) => {
+            match run_restore_tool(cmd, "psql").await {
+                Ok(output) => check_psql_output(&output, &mut warnings)?,
+                Err(CoreError::RestoreToolMissing(e)) => {
                     // psql not available, fall back to SQLx line-by-line execution
-                    warn!("psql not available ({}), falling back to SQLx execution", e);
+                    warn!("{}, falling back to SQLx execution", e);
                     self.execute_sql_with_sqlx(&actual_path, db_name).await?;
                 }
+                Err(e) => return Err(e),
             }
         }
 
@@ -943,7 +1444,10 @@ impl DbAdapter for PostgresAdapter {
             "Successfully restored dump, available databases: {:?}",
             restored_databases
         );
-        Ok(restored_databases)
+        Ok(RestoreOutcome {
+            databases: restored_databases,
+            warnings,
+        })
     }
 
     async fn restore_dump_with_exclusions(
@@ -951,7 +1455,7 @@ impl DbAdapter for PostgresAdapter {
         dump_path: &str,
         db_name: &str,
         excluded_tables: &[String],
-    ) -> Result<Vec<String>> {
+    ) -> Result<RestoreOutcome> {
         if excluded_tables.is_empty() {
             // No exclusions, use regular restore
             return self.restore_dump(dump_path, db_name).await;
@@ -969,7 +1473,9 @@ impl DbAdapter for PostgresAdapter {
         let is_custom_format = self.detect_pg_dump_format(&actual_path)?;
 
         // Create database first
-        self.create_database(db_name).await?;
+        self.create_database(db_name, self.restore_overwrite)
+            .await?;
+        let mut warnings = Vec::new();
 
         if is_custom_format {
             // Custom format - use pg_restore with TOC filtering
@@ -982,17 +1488,13 @@ impl DbAdapter for PostgresAdapter {
             let mut list_cmd = Command::new("pg_restore");
             list_cmd.arg("-l").arg(&actual_path);
 
-            if let Some(ref password) = self.password {
-                list_cmd.env("PGPASSWORD", password);
-            }
+            self.connection.apply_env(&mut list_cmd);
 
-            let list_output = list_cmd.output().map_err(|e| {
-                CoreError::RestoreFailed(format!("Failed to execute pg_restore -l: {}", e))
-            })?;
+            let list_output = run_restore_tool(list_cmd, "pg_restore -l").await?;
 
             if !list_output.status.success() {
                 let stderr = String::from_utf8_lossy(&list_output.stderr);
-                return Err(CoreError::RestoreFailed(format!(
+                return Err(CoreError::RestoreUnknown(format!(
                     "pg_restore -l failed: {}",
                     stderr
                 )));
@@ -1071,18 +1573,18 @@ impl DbAdapter for PostgresAdapter {
             // Step 3: Write filtered TOC to a temp file
             let toc_path = format!("{}.filtered_toc", actual_path);
             std::fs::write(&toc_path, &filtered_toc).map_err(|e| {
-                CoreError::RestoreFailed(format!("Failed to write filtered TOC: {}", e))
+                CoreError::RestoreUnknown(format!("Failed to write filtered TOC: {}", e))
             })?;
 
             // Step 4: Restore using filtered TOC
             let mut cmd = Command::new("pg_restore");
             cmd.args([
                 "-h",
-                &self.host,
+                &self.connection.host,
                 "-p",
-                &self.port.to_string(),
+                &self.connection.port.to_string(),
                 "-U",
-                &self.user,
+                &self.connection.user,
                 "-d",
                 db_name,
                 "--no-owner",
@@ -1091,22 +1593,20 @@ impl DbAdapter for PostgresAdapter {
                 "-L",
                 &toc_path,
             ]);
-
+            let format = archive_format(&actual_path);
+            cmd.args(pg_restore_format_args(format));
+            cmd.args(pg_restore_parallel_args(format, self.restore_parallelism));
             cmd.arg(&actual_path);
 
-            if let Some(ref password) = self.password {
-                cmd.env("PGPASSWORD", password);
-            }
+            self.connection.apply_env(&mut cmd);
 
-            let output = cmd.output().map_err(|e| {
-                CoreError::RestoreFailed(format!("Failed to execute pg_restore: {}", e))
-            })?;
+            let output = run_restore_tool(cmd, "pg_restore").await?;
 
             // Clean up temp TOC file
             let _ = std::fs::remove_file(&toc_path);
 
+            let stderr = String::from_utf8_lossy(&output.stderr);
             if !output.status.success() {
-                let stderr = String::from_utf8_lossy(&output.stderr);
                 let is_fatal = stderr.contains("FATAL")
                     || stderr.contains("unrecognized")
                     || (stderr.contains("ERROR")
@@ -1114,9 +1614,12 @@ impl DbAdapter for PostgresAdapter {
                         && !stderr.contains("transaction_timeout")
                         && !stderr.contains("errors ignored on restore"));
                 if is_fatal {
-                    return Err(CoreError::RestoreFailed(stderr.to_string()));
+                    return Err(classify_restore_stderr(&stderr));
                 }
+            }
+            if !stderr.trim().is_empty() {
                 warn!("pg_restore completed with warnings: {}", stderr);
+                warnings.extend(restore_warning_lines(&stderr));
             }
         } else {
             // Plain SQL format - filter out data only (keep schema)
@@ -1130,11 +1633,11 @@ impl DbAdapter for PostgresAdapter {
             let mut cmd = Command::new("psql");
             cmd.args([
                 "-h",
-                &self.host,
+                &self.connection.host,
                 "-p",
-                &self.port.to_string(),
+                &self.connection.port.to_string(),
                 "-U",
-                &self.user,
+                &self.connection.user,
                 "-d",
                 db_name,
                 "-v",
@@ -1143,26 +1646,24 @@ impl DbAdapter for PostgresAdapter {
                 &filtered_path,
             ]);
 
-            if let Some(ref password) = self.password {
-                cmd.env("PGPASSWORD", password);
-            }
-
-            let output = cmd.output();
+            self.connection.apply_env(&mut cmd);
 
-            match output {
+            match run_restore_tool(cmd, "psql").await {
                 Ok(output) => {
-                    if !output.status.success() {
-                        let stderr = String::from_utf8_lossy(&output.stderr);
-                        if stderr.contains("FATAL") {
-                            return Err(CoreError::RestoreFailed(stderr.to_string()));
-                        }
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+                    if !output.status.success() && stderr.contains("FATAL") {
+                        return Err(classify_restore_stderr(&stderr));
+                    }
+                    if !stderr.trim().is_empty() {
                         warn!("psql completed with warnings: {}", stderr);
+                        warnings.extend(restore_warning_lines(&stderr));
                     }
                 }
-                Err(e) => {
-                    warn!("psql not available ({}), falling back to SQLx execution", e);
+                Err(CoreError::RestoreToolMissing(e)) => {
+                    warn!("{}, falling back to SQLx execution", e);
                     self.execute_sql_with_sqlx(&filtered_path, db_name).await?;
                 }
+                Err(e) => return Err(e),
             }
 
             // Clean up filtered file
@@ -1173,27 +1674,131 @@ impl DbAdapter for PostgresAdapter {
             "Successfully restored dump with exclusions, database: {}",
             db_name
         );
-        Ok(vec![db_name.to_string()])
+        Ok(RestoreOutcome {
+            databases: vec![db_name.to_string()],
+            warnings,
+        })
+    }
+
+    async fn restore_dump_filtered(
+        &self,
+        dump_path: &str,
+        db_name: &str,
+        include_schemas: &[String],
+    ) -> Result<RestoreOutcome> {
+        if include_schemas.is_empty() {
+            return self.restore_dump(dump_path, db_name).await;
+        }
+
+        info!(
+            "Restoring dump {} to database {} limited to schemas {:?}",
+            dump_path, db_name, include_schemas
+        );
+
+        let actual_path = self.decompress_if_needed(dump_path).await?;
+        if !self.detect_pg_dump_format(&actual_path)? {
+            // Plain SQL has no per-object TOC to select from
+            return Err(CoreError::Validation(
+                "Restoring a subset of schemas is not supported for plain SQL dumps".to_string(),
+            ));
+        }
+
+        self.create_database(db_name, self.restore_overwrite)
+            .await?;
+
+        let mut cmd = Command::new("pg_restore");
+        cmd.args([
+            "-h",
+            &self.connection.host,
+            "-p",
+            &self.connection.port.to_string(),
+            "-U",
+            &self.connection.user,
+            "-d",
+            db_name,
+            "--no-owner",
+            "--no-privileges",
+            "--no-tablespaces",
+        ]);
+        cmd.args(pg_restore_schema_args(include_schemas));
+        let format = archive_format(&actual_path);
+        cmd.args(pg_restore_format_args(format));
+        cmd.args(pg_restore_parallel_args(format, self.restore_parallelism));
+        cmd.arg(&actual_path);
+
+        self.connection.apply_env(&mut cmd);
+
+        let output = run_restore_tool(cmd, "pg_restore").await?;
+
+        let mut warnings = Vec::new();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !output.status.success() {
+            let is_fatal = stderr.contains("FATAL")
+                || (stderr.contains("ERROR")
+                    && !stderr.contains("tablespace")
+                    && !stderr.contains("transaction_timeout")
+                    && !stderr.contains("errors ignored on restore"));
+            if is_fatal {
+                return Err(classify_restore_stderr(&stderr));
+            }
+        }
+        if !stderr.trim().is_empty() {
+            warn!("pg_restore completed with warnings: {}", stderr);
+            warnings.extend(restore_warning_lines(&stderr));
+        }
+
+        info!(
+            "Successfully restored schemas {:?} into database {}",
+            include_schemas, db_name
+        );
+        Ok(RestoreOutcome {
+            databases: vec![db_name.to_string()],
+            warnings,
+        })
     }
 
     async fn list_tables(&self, db_name: &str) -> Result<Vec<TableInfo>> {
+        if self.catalog_introspection {
+            let db_pool = self.db_pool(db_name).await?;
+            return self.list_tables_from_catalog(&db_pool).await;
+        }
+
         let query = r#"
             SELECT 
                 t.table_schema,
                 t.table_name,
-                COALESCE(s.n_live_tup, 0) as estimated_rows                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                 
+                COALESCE(s.n_live_tup, 0) as estimated_rows,
+                obj_description(
+                    format('%I.%I', t.table_schema, t.table_name)::regclass,
+                    'pg_class'
+                ) as table_comment,
+                c.relispartition as is_partition,
+                (
+                    SELECT format('%s.%s', pn.nspname, pc.relname)
+                    FROM pg_inherits i
+                    JOIN pg_class pc ON pc.oid = i.inhparent
+                    JOIN pg_namespace pn ON pn.oid = pc.relnamespace
+                    WHERE i.inhrelid = c.oid AND c.relispartition
+                ) as partition_of,
+                CASE pt.partstrat
+                    WHEN 'r' THEN 'range'
+                    WHEN 'l' THEN 'list'
+                    WHEN 'h' THEN 'hash'
+                END as partition_strategy
             FROM information_schema.tables t
             LEFT JOIN pg_stat_user_tables s 
                 ON s.schemaname = t.table_schema 
                 AND s.relname = t.table_name
+            JOIN pg_namespace n ON n.nspname = t.table_schema
+            JOIN pg_class c ON c.relnamespace = n.oid AND c.relname = t.table_name
+            LEFT JOIN pg_partitioned_table pt ON pt.partrelid = c.oid
             WHERE t.table_type = 'BASE TABLE'
                 AND t.table_schema NOT IN ('pg_catalog', 'information_schema')
             ORDER BY t.table_schema, t.table_name
         "#;
 
         // Connect to the specific database
-        let db_url = self.build_db_url(db_name);
-        let db_pool = PgPool::connect(&db_url).await?;
+        let db_pool = self.db_pool(db_name).await?;
 
         let rows = sqlx::query(query).fetch_all(&db_pool).await?;
 
@@ -1202,6 +1807,10 @@ impl DbAdapter for PostgresAdapter {
             let schema_name: String = row.get("table_schema");
             let table_name: String = row.get("table_name");
             let estimated_row_count: i64 = row.get("estimated_rows");
+            let comment: Option<String> = row.get("table_comment");
+            let is_partition: bool = row.get("is_partition");
+            let partition_of: Option<String> = row.get("partition_of");
+            let partition_strategy: Option<String> = row.get("partition_strategy");
 
             // Get columns for this table
             let columns = self
@@ -1213,6 +1822,10 @@ impl DbAdapter for PostgresAdapter {
                 table_name,
                 estimated_row_count,
                 columns,
+                comment,
+                is_partition,
+                partition_of,
+                partition_strategy,
             });
         }
 
@@ -1220,6 +1833,11 @@ impl DbAdapter for PostgresAdapter {
     }
 
     async fn list_foreign_keys(&self, db_name: &str) -> Result<Vec<ForeignKey>> {
+        if self.catalog_introspection {
+            let db_pool = self.db_pool(db_name).await?;
+            return self.list_foreign_keys_from_catalog(&db_pool).await;
+        }
+
         let query = r#"
             SELECT DISTINCT
                 tc.constraint_name,
@@ -1248,8 +1866,7 @@ impl DbAdapter for PostgresAdapter {
             ORDER BY tc.constraint_name, kcu.ordinal_position
         "#;
 
-        let db_url = self.build_db_url(db_name);
-        let db_pool = PgPool::connect(&db_url).await?;
+        let db_pool = self.db_pool(db_name).await?;
 
         let rows = sqlx::query(query).fetch_all(&db_pool).await?;
 
@@ -1286,75 +1903,418 @@ impl DbAdapter for PostgresAdapter {
         Ok(fk_map.into_values().collect())
     }
 
-    async fn estimate_row_counts(&self, db_name: &str) -> Result<Vec<(String, String, i64)>> {
+    async fn list_indexes(&self, db_name: &str) -> Result<Vec<IndexInfo>> {
+        // Key columns only (INCLUDE columns are skipped); expression keys are
+        // rendered with pg_get_indexdef
         let query = r#"
-            SELECT schemaname, relname, n_live_tup
-            FROM pg_stat_user_tables
-            ORDER BY schemaname, relname
+            SELECT
+                n.nspname as schema_name,
+                t.relname as table_name,
+                i.relname as index_name,
+                ix.indisunique as is_unique,
+                ix.indisprimary as is_primary,
+                am.amname as method,
+                pg_get_expr(ix.indpred, ix.indrelid, true) as predicate,
+                array_agg(pg_get_indexdef(ix.indexrelid, k.ord::int, true) ORDER BY k.ord) as columns
+            FROM pg_index ix
+            JOIN pg_class i ON i.oid = ix.indexrelid
+            JOIN pg_class t ON t.oid = ix.indrelid
+            JOIN pg_namespace n ON n.oid = t.relnamespace
+            JOIN pg_am am ON am.oid = i.relam
+            CROSS JOIN LATERAL unnest(ix.indkey::int2[]) WITH ORDINALITY AS k(attnum, ord)
+            WHERE n.nspname NOT IN ('pg_catalog', 'information_schema')
+                AND n.nspname NOT LIKE 'pg_toast%'
+                AND k.ord <= ix.indnkeyatts
+            GROUP BY n.nspname, t.relname, i.relname, ix.indisunique, ix.indisprimary, am.amname,
+                pg_get_expr(ix.indpred, ix.indrelid, true)
+            ORDER BY n.nspname, t.relname, i.relname
         "#;
 
-        let db_url = self.build_db_url(db_name);
-        let db_pool = PgPool::connect(&db_url).await?;
+        let db_pool = self.db_pool(db_name).await?;
 
         let rows = sqlx::query(query).fetch_all(&db_pool).await?;
 
-        let counts = rows
+        Ok(rows
             .iter()
-            .map(|row| {
-                (
-                    row.get::<String, _>("schemaname"),
-                    row.get::<String, _>("relname"),
-                    row.get::<i64, _>("n_live_tup"),
-                )
+            .map(|row| IndexInfo {
+                name: row.get("index_name"),
+                schema_name: row.get("schema_name"),
+                table_name: row.get("table_name"),
+                columns: row.get("columns"),
+                is_unique: row.get("is_unique"),
+                is_primary: row.get("is_primary"),
+                method: row.get("method"),
+                predicate: row.get("predicate"),
             })
-            .collect();
-
-        Ok(counts)
+            .collect())
     }
 
-    async fn fetch_sample_rows(
-        &self,
-        db_name: &str,
-        schema: &str,
-        table: &str,
-        limit: usize,
-    ) -> Result<Vec<serde_json::Value>> {
-        let db_url = self.build_db_url(db_name);
-        let db_pool = PgPool::connect(&db_url).await?;
+    async fn list_views(&self, db_name: &str) -> Result<Vec<ViewInfo>> {
+        let views_query = r#"
+            SELECT
+                table_schema::text as schema_name,
+                table_name::text as view_name,
+                COALESCE(view_definition, '')::text as definition,
+                false as is_materialized
+            FROM information_schema.views
+            WHERE table_schema NOT IN ('pg_catalog', 'information_schema')
+            UNION ALL
+            SELECT
+                schemaname::text,
+                matviewname::text,
+                COALESCE(definition, '')::text,
+                true
+            FROM pg_matviews
+            WHERE schemaname NOT IN ('pg_catalog', 'information_schema')
+            ORDER BY schema_name, view_name
+        "#;
 
-        // Use quote_ident equivalent for safety
-        let query = format!(
-            "SELECT to_jsonb(t.*) as row_data FROM \"{}\".\"{}\" t LIMIT {}",
-            schema, table, limit
-        );
+        // information_schema.columns omits materialized views, so read the
+        // columns of both kinds from pg_attribute
+        let columns_query = r#"
+            SELECT
+                n.nspname as schema_name,
+                c.relname as view_name,
+                a.attname as column_name,
+                format_type(a.atttypid, a.atttypmod) as data_type,
+                NOT a.attnotnull as is_nullable,
+                col_description(a.attrelid, a.attnum) as column_comment,
+                a.attnum::int as ordinal_position
+            FROM pg_attribute a
+            JOIN pg_class c ON c.oid = a.attrelid
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            WHERE c.relkind IN ('v', 'm')
+                AND a.attnum > 0
+                AND NOT a.attisdropped
+                AND n.nspname NOT IN ('pg_catalog', 'information_schema')
+            ORDER BY n.nspname, c.relname, a.attnum
+        "#;
 
-        let rows = sqlx::query(&query).fetch_all(&db_pool).await?;
+        let db_pool = self.db_pool(db_name).await?;
 
-        let result: Vec<serde_json::Value> = rows
-            .iter()
-            .map(|row| row.get::<serde_json::Value, _>("row_data"))
-            .collect();
+        let view_rows = sqlx::query(views_query).fetch_all(&db_pool).await?;
+        let column_rows = sqlx::query(columns_query).fetch_all(&db_pool).await?;
+
+        let mut columns_map: HashMap<(String, String), Vec<ColumnInfo>> = HashMap::new();
+        for row in column_rows {
+            let key: (String, String) = (row.get("schema_name"), row.get("view_name"));
+            columns_map.entry(key).or_default().push(ColumnInfo {
+                name: row.get("column_name"),
+                data_type: row.get("data_type"),
+                is_nullable: row.get("is_nullable"),
+                is_primary_key: false,
+                default_value: None,
+                comment: row.get("column_comment"),
+                ordinal_position: row.get("ordinal_position"),
+                is_identity: false,
+                identity_generation: None,
+                generation_expression: None,
+            });
+        }
 
-        Ok(result)
+        Ok(view_rows
+            .iter()
+            .map(|row| {
+                let schema: String = row.get("schema_name");
+                let name: String = row.get("view_name");
+                let columns = columns_map
+                    .remove(&(schema.clone(), name.clone()))
+                    .unwrap_or_default();
+                ViewInfo {
+                    schema,
+                    name,
+                    definition: row.get("definition"),
+                    is_materialized: row.get("is_materialized"),
+                    columns,
+                }
+            })
+            .collect())
     }
 
-    async fn drop_database(&self, db_name: &str) -> Result<()> {
-        // Terminate existing connections first
-        let terminate_query = format!(
-            "SELECT pg_terminate_backend(pid) FROM pg_stat_activity WHERE datname = '{}'",
-            db_name
-        );
-        sqlx::query(&terminate_query).execute(&self.pool).await?;
+    async fn list_triggers(&self, db_name: &str) -> Result<Vec<TriggerInfo>> {
+        let query = r#"
+            SELECT
+                trigger_name::text,
+                event_object_schema::text,
+                event_object_table::text,
+                action_timing::text,
+                event_manipulation::text,
+                action_statement::text
+            FROM information_schema.triggers
+            WHERE event_object_schema NOT IN ('pg_catalog', 'information_schema')
+            ORDER BY event_object_schema, event_object_table, trigger_name, event_manipulation
+        "#;
 
-        // Drop the database
-        let drop_query = format!("DROP DATABASE IF EXISTS \"{}\"", db_name);
-        sqlx::query(&drop_query).execute(&self.pool).await?;
+        let db_pool = self.db_pool(db_name).await?;
+        let rows = sqlx::query(query).fetch_all(&db_pool).await?;
 
-        Ok(())
+        Ok(rows
+            .iter()
+            .map(|row| TriggerInfo {
+                name: row.get("trigger_name"),
+                schema: row.get("event_object_schema"),
+                table: row.get("event_object_table"),
+                timing: row.get("action_timing"),
+                event: row.get("event_manipulation"),
+                function: row.get("action_statement"),
+            })
+            .collect())
     }
 
-    async fn database_exists(&self, db_name: &str) -> Result<bool> {
-        let query = "SELECT EXISTS(SELECT 1 FROM pg_database WHERE datname = $1)";
+    async fn list_enum_types(&self, db_name: &str) -> Result<Vec<EnumType>> {
+        let query = r#"
+            SELECT
+                n.nspname::text as schema_name,
+                t.typname::text as type_name,
+                array_agg(e.enumlabel::text ORDER BY e.enumsortorder) as enum_values
+            FROM pg_type t
+            JOIN pg_namespace n ON n.oid = t.typnamespace
+            JOIN pg_enum e ON e.enumtypid = t.oid
+            WHERE n.nspname NOT IN ('pg_catalog', 'information_schema')
+            GROUP BY n.nspname, t.typname
+            ORDER BY n.nspname, t.typname
+        "#;
+
+        let db_pool = self.db_pool(db_name).await?;
+        let rows = sqlx::query(query).fetch_all(&db_pool).await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| EnumType {
+                schema: row.get("schema_name"),
+                name: row.get("type_name"),
+                values: row.get("enum_values"),
+            })
+            .collect())
+    }
+
+    async fn list_sequences(&self, db_name: &str) -> Result<Vec<SequenceInfo>> {
+        let db_pool = self.db_pool(db_name).await?;
+        fetch_sequences(&db_pool).await
+    }
+
+    async fn list_unique_constraints(
+        &self,
+        db_name: &str,
+        schema: &str,
+        table: &str,
+    ) -> Result<Vec<UniqueConstraint>> {
+        let query = r#"
+            SELECT
+                con.conname::text as constraint_name,
+                array_agg(a.attname::text ORDER BY k.ord) as columns,
+                bool_and(a.attnotnull) as all_not_null
+            FROM pg_constraint con
+            JOIN pg_class c ON c.oid = con.conrelid
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            CROSS JOIN LATERAL unnest(con.conkey) WITH ORDINALITY AS k(attnum, ord)
+            JOIN pg_attribute a ON a.attrelid = c.oid AND a.attnum = k.attnum
+            WHERE con.contype = 'u'
+                AND n.nspname = $1
+                AND c.relname = $2
+            GROUP BY con.conname
+            ORDER BY bool_and(a.attnotnull) DESC, count(*) ASC, con.conname ASC
+        "#;
+
+        let db_pool = self.db_pool(db_name).await?;
+
+        let rows = sqlx::query(query)
+            .bind(schema)
+            .bind(table)
+            .fetch_all(&db_pool)
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| UniqueConstraint {
+                name: row.get("constraint_name"),
+                columns: row.get("columns"),
+                all_not_null: row.get("all_not_null"),
+            })
+            .collect())
+    }
+
+    async fn list_check_constraints(
+        &self,
+        db_name: &str,
+        schema: &str,
+        table: &str,
+    ) -> Result<Vec<CheckConstraint>> {
+        let query = r#"
+            SELECT
+                con.conname::text as constraint_name,
+                pg_get_constraintdef(con.oid) as definition
+            FROM pg_constraint con
+            JOIN pg_class c ON c.oid = con.conrelid
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            WHERE con.contype = 'c'
+                AND n.nspname = $1
+                AND c.relname = $2
+            ORDER BY con.conname
+        "#;
+
+        let db_pool = self.db_pool(db_name).await?;
+
+        let rows = sqlx::query(query)
+            .bind(schema)
+            .bind(table)
+            .fetch_all(&db_pool)
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| CheckConstraint {
+                name: row.get("constraint_name"),
+                definition: row.get("definition"),
+            })
+            .collect())
+    }
+
+    async fn estimate_row_counts(&self, db_name: &str) -> Result<Vec<(String, String, i64)>> {
+        let query = r#"
+            SELECT schemaname, relname, n_live_tup
+            FROM pg_stat_user_tables
+            ORDER BY schemaname, relname
+        "#;
+
+        let db_pool = self.db_pool(db_name).await?;
+
+        let rows = sqlx::query(query).fetch_all(&db_pool).await?;
+
+        let counts = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.get::<String, _>("schemaname"),
+                    row.get::<String, _>("relname"),
+                    row.get::<i64, _>("n_live_tup"),
+                )
+            })
+            .collect();
+
+        Ok(counts)
+    }
+
+    async fn exact_row_count(&self, db_name: &str, schema: &str, table: &str) -> Result<i64> {
+        let db_pool = self.db_pool(db_name).await?;
+
+        let query = format!(
+            "SELECT COUNT(*) as cnt FROM {}.{}",
+            quote_ident(schema),
+            quote_ident(table)
+        );
+        let row = sqlx::query(&query).fetch_one(&db_pool).await?;
+
+        Ok(row.get("cnt"))
+    }
+
+    async fn fetch_sample_rows_filtered(
+        &self,
+        db_name: &str,
+        schema: &str,
+        table: &str,
+        filter: &[FilterPredicate],
+        order_by: Option<OrderBy>,
+        limit: usize,
+    ) -> Result<Vec<serde_json::Value>> {
+        let db_pool = self.db_pool(db_name).await?;
+
+        // Column names and types; the types cast filter values
+        let columns: Vec<(String, String)> = sqlx::query(
+            r#"
+            SELECT column_name::text AS column_name,
+                   format('%I.%I', udt_schema, udt_name) AS data_type
+            FROM information_schema.columns
+            WHERE table_schema = $1 AND table_name = $2
+            ORDER BY ordinal_position
+            "#,
+        )
+        .bind(schema)
+        .bind(table)
+        .fetch_all(&db_pool)
+        .await?
+        .iter()
+        .map(|row| (row.get("column_name"), row.get("data_type")))
+        .collect();
+        if columns.is_empty() {
+            return Err(CoreError::TableNotFound {
+                schema: schema.to_string(),
+                table: table.to_string(),
+            });
+        }
+        if let Some(order) = &order_by {
+            if !columns.iter().any(|(name, _)| *name == order.column) {
+                return Err(CoreError::Validation(format!(
+                    "Unknown order column '{}'",
+                    order.column
+                )));
+            }
+        }
+
+        let rendered = render_filter(filter, &columns, 1)?;
+        let query = SqlGenerator::generate_sample_rows_query(
+            schema,
+            table,
+            &rendered.conditions,
+            order_by.as_ref(),
+            limit,
+        );
+        let mut q = sqlx::query_scalar::<_, serde_json::Value>(&query);
+        for param in &rendered.params {
+            q = q.bind(param);
+        }
+        Ok(q.fetch_all(&db_pool).await?)
+    }
+
+    async fn fetch_related_rows(
+        &self,
+        db_name: &str,
+        fk: &ForeignKey,
+        direction: RelationDirection,
+        key_values: &[String],
+        limit: usize,
+    ) -> Result<Vec<serde_json::Value>> {
+        let key_columns = match direction {
+            RelationDirection::Outbound => &fk.source_columns,
+            RelationDirection::Inbound => &fk.target_columns,
+        };
+        if key_values.len() != key_columns.len() {
+            return Err(CoreError::Validation(format!(
+                "Foreign key {} has {} column(s), got {} key value(s)",
+                fk.constraint_name,
+                key_columns.len(),
+                key_values.len()
+            )));
+        }
+
+        let db_pool = self.db_pool(db_name).await?;
+        let query = SqlGenerator::generate_related_rows_query(fk, direction, limit);
+        let mut q = sqlx::query_scalar::<_, serde_json::Value>(&query);
+        for value in key_values {
+            q = q.bind(value);
+        }
+        Ok(q.fetch_all(&db_pool).await?)
+    }
+
+    async fn drop_database(&self, db_name: &str) -> Result<()> {
+        self.evict_db_pool(db_name).await;
+
+        // Terminate existing connections first
+        let terminate_query = format!(
+            "SELECT pg_terminate_backend(pid) FROM pg_stat_activity WHERE datname = '{}'",
+            db_name
+        );
+        sqlx::query(&terminate_query).execute(&self.pool).await?;
+
+        // Drop the database
+        let drop_query = format!("DROP DATABASE IF EXISTS \"{}\"", db_name);
+        sqlx::query(&drop_query).execute(&self.pool).await?;
+
+        Ok(())
+    }
+
+    async fn database_exists(&self, db_name: &str) -> Result<bool> {
+        let query = "SELECT EXISTS(SELECT 1 FROM pg_database WHERE datname = $1)";
         let row = sqlx::query(query)
             .bind(db_name)
             .fetch_one(&self.pool)
@@ -1363,8 +2323,11 @@ impl DbAdapter for PostgresAdapter {
         Ok(row.get::<bool, _>(0))
     }
 
-    async fn create_database(&self, db_name: &str) -> Result<()> {
+    async fn create_database(&self, db_name: &str, overwrite: bool) -> Result<()> {
         if self.database_exists(db_name).await? {
+            if !overwrite {
+                return Err(CoreError::DatabaseExists(db_name.to_string()));
+            }
             info!("Database {} already exists, dropping first", db_name);
             self.drop_database(db_name).await?;
         }
@@ -1379,8 +2342,7 @@ impl DbAdapter for PostgresAdapter {
     async fn analyze_database(&self, db_name: &str) -> Result<()> {
         info!("Running ANALYZE on database {}", db_name);
 
-        let db_url = self.build_db_url(db_name);
-        let db_pool = PgPool::connect(&db_url).await?;
+        let db_pool = self.db_pool(db_name).await?;
 
         // Run ANALYZE on all tables to update statistics
         sqlx::query("ANALYZE").execute(&db_pool).await?;
@@ -1388,6 +2350,138 @@ impl DbAdapter for PostgresAdapter {
         info!("ANALYZE completed for database {}", db_name);
         Ok(())
     }
+
+    async fn mask_columns(
+        &self,
+        db_name: &str,
+        masks: &[ColumnMask],
+    ) -> Result<Vec<(String, String)>> {
+        let statements = mask_statements(masks)?;
+        if statements.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let db_pool = self.db_pool(db_name).await?;
+        let mut tx = db_pool.begin().await?;
+        let mut masked = Vec::new();
+
+        for statement in &statements {
+            // pg_dumpall restores several databases; a mask only applies to
+            // the ones that actually contain its table
+            let exists: bool = sqlx::query_scalar("SELECT to_regclass($1) IS NOT NULL")
+                .bind(format!(
+                    "{}.{}",
+                    quote_ident(&statement.schema),
+                    quote_ident(&statement.table)
+                ))
+                .fetch_one(&mut *tx)
+                .await?;
+            if !exists {
+                continue;
+            }
+
+            let result = sqlx::query(&statement.sql)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| {
+                    CoreError::Validation(format!(
+                        "Failed to mask {}.{}: {}",
+                        statement.schema, statement.table, e
+                    ))
+                })?;
+            info!(
+                "Masked {} rows of {}.{} in {}",
+                result.rows_affected(),
+                statement.schema,
+                statement.table,
+                db_name
+            );
+            masked.push((statement.schema.clone(), statement.table.clone()));
+        }
+
+        tx.commit().await?;
+        Ok(masked)
+    }
+
+    async fn grant_read_only(&self, db_name: &str, role: &ReadOnlyRole) -> Result<()> {
+        let role_ident = quote_ident(&role.user);
+
+        // Concurrent restores may race to create the role
+        let login = match role.password {
+            Some(ref password) => format!("LOGIN PASSWORD {}", quote_literal(password)),
+            None => "LOGIN".to_string(),
+        };
+        let create_role = format!(
+            "DO $$ BEGIN \
+                 CREATE ROLE {} {} NOSUPERUSER NOCREATEDB NOCREATEROLE NOINHERIT; \
+             EXCEPTION WHEN duplicate_object THEN NULL; \
+             END $$",
+            role_ident, login
+        );
+        sqlx::query(&create_role).execute(&self.pool).await?;
+        if role.password.is_some() {
+            // Pick up a rotated password for an existing role
+            sqlx::query(&format!("ALTER ROLE {} {}", role_ident, login))
+                .execute(&self.pool)
+                .await?;
+        }
+        sqlx::query(&format!(
+            "GRANT CONNECT ON DATABASE {} TO {}",
+            quote_ident(db_name),
+            role_ident
+        ))
+        .execute(&self.pool)
+        .await?;
+
+        let db_pool = self.db_pool(db_name).await?;
+        let schemas: Vec<String> = sqlx::query_scalar(
+            r#"
+            SELECT nspname::text FROM pg_namespace
+            WHERE nspname NOT IN ('pg_catalog', 'information_schema')
+              AND nspname NOT LIKE 'pg\_toast%'
+              AND nspname NOT LIKE 'pg\_temp%'
+            "#,
+        )
+        .fetch_all(&db_pool)
+        .await?;
+
+        let mut tx = db_pool.begin().await?;
+        for schema in &schemas {
+            let schema = quote_ident(schema);
+            // PostgreSQL < 15 lets PUBLIC create tables in `public`
+            sqlx::query(&format!("REVOKE CREATE ON SCHEMA {} FROM PUBLIC", schema))
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query(&format!(
+                "GRANT USAGE ON SCHEMA {} TO {}",
+                schema, role_ident
+            ))
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query(&format!(
+                "GRANT SELECT ON ALL TABLES IN SCHEMA {} TO {}",
+                schema, role_ident
+            ))
+            .execute(&mut *tx)
+            .await?;
+            // SELECT on a sequence allows reading last_value, not nextval
+            sqlx::query(&format!(
+                "GRANT SELECT ON ALL SEQUENCES IN SCHEMA {} TO {}",
+                schema, role_ident
+            ))
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        info!(
+            "Granted read-only access on {} ({} schemas) to {}",
+            db_name,
+            schemas.len(),
+            role.user
+        );
+        Ok(())
+    }
 }
 
 impl PostgresAdapter {
@@ -1400,10 +2494,21 @@ impl PostgresAdapter {
         let query = r#"
             SELECT 
                 c.column_name,
-                c.data_type,
+                CASE WHEN c.data_type = 'USER-DEFINED'
+                    THEN format('%I.%I', c.udt_schema, c.udt_name)
+                    ELSE c.data_type
+                END as data_type,
                 c.is_nullable = 'YES' as is_nullable,
                 c.column_default,
-                COALESCE(pk.is_pk, false) as is_primary_key
+                COALESCE(pk.is_pk, false) as is_primary_key,
+                col_description(
+                    format('%I.%I', c.table_schema, c.table_name)::regclass,
+                    c.ordinal_position::int
+                ) as column_comment,
+                c.ordinal_position::int as ordinal_position,
+                c.is_identity = 'YES' as is_identity,
+                c.identity_generation::text as identity_generation,
+                c.generation_expression::text as generation_expression
             FROM information_schema.columns c
             LEFT JOIN (
                 SELECT kcu.column_name, true as is_pk
@@ -1432,12 +2537,220 @@ impl PostgresAdapter {
                 is_nullable: row.get("is_nullable"),
                 is_primary_key: row.get("is_primary_key"),
                 default_value: row.get("column_default"),
+                comment: row.get("column_comment"),
+                ordinal_position: row.get("ordinal_position"),
+                is_identity: row.get("is_identity"),
+                identity_generation: row.get("identity_generation"),
+                generation_expression: row.get("generation_expression"),
             })
             .collect();
 
         Ok(columns)
     }
 
+    /// [`DbAdapter::list_tables`] over `pg_catalog`
+    async fn list_tables_from_catalog(&self, pool: &PgPool) -> Result<Vec<TableInfo>> {
+        // Base and partitioned tables: what information_schema.tables reports
+        // as BASE TABLE
+        let query = r#"
+            SELECT
+                n.nspname as table_schema,
+                c.relname as table_name,
+                COALESCE(s.n_live_tup, 0) as estimated_rows,
+                obj_description(c.oid, 'pg_class') as table_comment,
+                c.relispartition as is_partition,
+                (
+                    SELECT format('%s.%s', pn.nspname, pc.relname)
+                    FROM pg_inherits i
+                    JOIN pg_class pc ON pc.oid = i.inhparent
+                    JOIN pg_namespace pn ON pn.oid = pc.relnamespace
+                    WHERE i.inhrelid = c.oid AND c.relispartition
+                ) as partition_of,
+                CASE pt.partstrat
+                    WHEN 'r' THEN 'range'
+                    WHEN 'l' THEN 'list'
+                    WHEN 'h' THEN 'hash'
+                END as partition_strategy
+            FROM pg_class c
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            LEFT JOIN pg_stat_user_tables s ON s.relid = c.oid
+            LEFT JOIN pg_partitioned_table pt ON pt.partrelid = c.oid
+            WHERE c.relkind IN ('r', 'p')
+                AND n.nspname NOT IN ('pg_catalog', 'information_schema')
+                AND n.nspname NOT LIKE 'pg_toast%'
+                AND NOT pg_is_other_temp_schema(n.oid)
+            ORDER BY n.nspname, c.relname
+        "#;
+
+        let rows = sqlx::query(query).fetch_all(pool).await?;
+        let mut columns = self.get_columns_from_catalog(pool).await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let schema_name: String = row.get("table_schema");
+                let table_name: String = row.get("table_name");
+                let columns = columns
+                    .remove(&(schema_name.clone(), table_name.clone()))
+                    .unwrap_or_default();
+                TableInfo {
+                    schema_name,
+                    table_name,
+                    estimated_row_count: row.get("estimated_rows"),
+                    columns,
+                    comment: row.get("table_comment"),
+                    is_partition: row.get("is_partition"),
+                    partition_of: row.get("partition_of"),
+                    partition_strategy: row.get("partition_strategy"),
+                }
+            })
+            .collect())
+    }
+
+    /// Columns of every table, keyed by `(schema, table)`, read from
+    /// `pg_attribute`
+    ///
+    /// `data_type` follows `information_schema.columns`: SQL names for
+    /// built-in types, `ARRAY` for arrays, the base type for domains and the
+    /// quoted qualified name for other user-defined types.
+    async fn get_columns_from_catalog(
+        &self,
+        pool: &PgPool,
+    ) -> Result<HashMap<(String, String), Vec<ColumnInfo>>> {
+        let query = r#"
+            SELECT
+                n.nspname as table_schema,
+                c.relname as table_name,
+                a.attname as column_name,
+                CASE
+                    WHEN t.typtype = 'd' THEN
+                        CASE
+                            WHEN bt.typelem <> 0 AND bt.typlen = -1 THEN 'ARRAY'
+                            WHEN nbt.nspname = 'pg_catalog' THEN format_type(t.typbasetype, NULL)
+                            ELSE format('%I.%I', nbt.nspname, bt.typname)
+                        END
+                    WHEN t.typelem <> 0 AND t.typlen = -1 THEN 'ARRAY'
+                    WHEN nt.nspname = 'pg_catalog' THEN format_type(a.atttypid, NULL)
+                    ELSE format('%I.%I', nt.nspname, t.typname)
+                END as data_type,
+                NOT (a.attnotnull OR (t.typtype = 'd' AND t.typnotnull)) as is_nullable,
+                CASE WHEN a.attgenerated = '' THEN pg_get_expr(ad.adbin, ad.adrelid) END
+                    as column_default,
+                EXISTS (
+                    SELECT 1 FROM pg_index i
+                    WHERE i.indrelid = c.oid AND i.indisprimary AND a.attnum = ANY(i.indkey)
+                ) as is_primary_key,
+                col_description(c.oid, a.attnum) as column_comment,
+                a.attnum::int as ordinal_position,
+                a.attidentity IN ('a', 'd') as is_identity,
+                CASE a.attidentity
+                    WHEN 'a' THEN 'ALWAYS'
+                    WHEN 'd' THEN 'BY DEFAULT'
+                END as identity_generation,
+                CASE WHEN a.attgenerated <> '' THEN pg_get_expr(ad.adbin, ad.adrelid) END
+                    as generation_expression
+            FROM pg_attribute a
+            JOIN pg_class c ON c.oid = a.attrelid
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            JOIN pg_type t ON t.oid = a.atttypid
+            JOIN pg_namespace nt ON nt.oid = t.typnamespace
+            LEFT JOIN (pg_type bt JOIN pg_namespace nbt ON nbt.oid = bt.typnamespace)
+                ON t.typtype = 'd' AND bt.oid = t.typbasetype
+            LEFT JOIN pg_attrdef ad ON ad.adrelid = a.attrelid AND ad.adnum = a.attnum
+            WHERE a.attnum > 0
+                AND NOT a.attisdropped
+                AND c.relkind IN ('r', 'p')
+                AND n.nspname NOT IN ('pg_catalog', 'information_schema')
+            ORDER BY n.nspname, c.relname, a.attnum
+        "#;
+
+        let rows = sqlx::query(query).fetch_all(pool).await?;
+
+        let mut columns: HashMap<(String, String), Vec<ColumnInfo>> = HashMap::new();
+        for row in rows {
+            columns
+                .entry((row.get("table_schema"), row.get("table_name")))
+                .or_default()
+                .push(ColumnInfo {
+                    name: row.get("column_name"),
+                    data_type: row.get("data_type"),
+                    is_nullable: row.get("is_nullable"),
+                    is_primary_key: row.get("is_primary_key"),
+                    default_value: row.get("column_default"),
+                    comment: row.get("column_comment"),
+                    ordinal_position: row.get("ordinal_position"),
+                    is_identity: row.get("is_identity"),
+                    identity_generation: row.get("identity_generation"),
+                    generation_expression: row.get("generation_expression"),
+                });
+        }
+        Ok(columns)
+    }
+
+    /// [`DbAdapter::list_foreign_keys`] over `pg_constraint`
+    ///
+    /// Column pairs come from `conkey`/`confkey` in constraint order. The
+    /// copies of a partitioned table's foreign key on its partitions are
+    /// left out; the parent's constraint stands for them.
+    async fn list_foreign_keys_from_catalog(&self, pool: &PgPool) -> Result<Vec<ForeignKey>> {
+        let query = r#"
+            SELECT
+                con.conname as constraint_name,
+                sn.nspname as source_schema,
+                sc.relname as source_table,
+                tn.nspname as target_schema,
+                tc.relname as target_table,
+                array_agg(sa.attname::text ORDER BY k.ord) as source_columns,
+                array_agg(ta.attname::text ORDER BY k.ord) as target_columns,
+                CASE con.confdeltype
+                    WHEN 'c' THEN 'CASCADE'
+                    WHEN 'n' THEN 'SET NULL'
+                    WHEN 'd' THEN 'SET DEFAULT'
+                    WHEN 'r' THEN 'RESTRICT'
+                    ELSE 'NO ACTION'
+                END as delete_rule,
+                CASE con.confupdtype
+                    WHEN 'c' THEN 'CASCADE'
+                    WHEN 'n' THEN 'SET NULL'
+                    WHEN 'd' THEN 'SET DEFAULT'
+                    WHEN 'r' THEN 'RESTRICT'
+                    ELSE 'NO ACTION'
+                END as update_rule
+            FROM pg_constraint con
+            JOIN pg_class sc ON sc.oid = con.conrelid
+            JOIN pg_namespace sn ON sn.oid = sc.relnamespace
+            JOIN pg_class tc ON tc.oid = con.confrelid
+            JOIN pg_namespace tn ON tn.oid = tc.relnamespace
+            CROSS JOIN LATERAL unnest(con.conkey, con.confkey)
+                WITH ORDINALITY AS k(source_attnum, target_attnum, ord)
+            JOIN pg_attribute sa ON sa.attrelid = con.conrelid AND sa.attnum = k.source_attnum
+            JOIN pg_attribute ta ON ta.attrelid = con.confrelid AND ta.attnum = k.target_attnum
+            WHERE con.contype = 'f'
+                AND con.conparentid = 0
+                AND sn.nspname NOT IN ('pg_catalog', 'information_schema')
+            GROUP BY con.oid, con.conname, sn.nspname, sc.relname, tn.nspname, tc.relname,
+                con.confdeltype, con.confupdtype
+            ORDER BY con.conname
+        "#;
+
+        let rows = sqlx::query(query).fetch_all(pool).await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| ForeignKey {
+                constraint_name: row.get("constraint_name"),
+                source_schema: row.get("source_schema"),
+                source_table: row.get("source_table"),
+                source_columns: row.get("source_columns"),
+                target_schema: row.get("target_schema"),
+                target_table: row.get("target_table"),
+                target_columns: row.get("target_columns"),
+                on_delete: Self::parse_fk_action(row.get("delete_rule")),
+                on_update: Self::parse_fk_action(row.get("update_rule")),
+            })
+            .collect())
+    }
+
     /// Fallback SQL execution when psql is not available
     /// This handles simple SQL but may not work with COPY commands
     async fn execute_sql_with_sqlx(&self, sql_path: &str, db_name: &str) -> Result<()> {
@@ -1445,11 +2758,11 @@ impl PostgresAdapter {
 
         let sql_content = tokio::fs::read_to_string(sql_path)
             .await
-            .map_err(|e| CoreError::RestoreFailed(format!("Failed to read SQL file: {}", e)))?;
+            .map_err(|e| CoreError::RestoreUnknown(format!("Failed to read SQL file: {}", e)))?;
 
         let db_url = self.build_db_url(db_name);
         let db_pool = PgPool::connect(&db_url).await.map_err(|e| {
-            CoreError::RestoreFailed(format!("Failed to connect to database: {}", e))
+            CoreError::RestoreConnection(format!("Failed to connect to database: {}", e))
         })?;
 
         let mut executed = 0;
@@ -1551,10 +2864,384 @@ impl PostgresAdapter {
     }
 }
 
+/// `pg_restore` flags selecting the given schemas (one `-n` per schema)
+/// Read every user sequence of the database `pool` is connected to
+///
+/// `last_value` comes from `pg_sequences` and is NULL for sequences that were
+/// never used (or that the connecting role cannot read).
+pub async fn fetch_sequences(pool: &PgPool) -> Result<Vec<SequenceInfo>> {
+    let query = r#"
+        SELECT
+            s.sequence_schema::text AS schema_name,
+            s.sequence_name::text AS sequence_name,
+            s.increment::bigint AS increment,
+            ps.last_value,
+            owner.owned_by
+        FROM information_schema.sequences s
+        LEFT JOIN pg_sequences ps
+            ON ps.schemaname = s.sequence_schema AND ps.sequencename = s.sequence_name
+        LEFT JOIN LATERAL (
+            -- 'a': serial / OWNED BY, 'i': identity column
+            SELECT format('%s.%s.%s', tn.nspname, t.relname, a.attname) AS owned_by
+            FROM pg_class sc
+            JOIN pg_namespace sn ON sn.oid = sc.relnamespace
+            JOIN pg_depend d
+                ON d.classid = 'pg_class'::regclass
+                AND d.objid = sc.oid
+                AND d.refclassid = 'pg_class'::regclass
+                AND d.deptype IN ('a', 'i')
+            JOIN pg_class t ON t.oid = d.refobjid
+            JOIN pg_namespace tn ON tn.oid = t.relnamespace
+            JOIN pg_attribute a ON a.attrelid = t.oid AND a.attnum = d.refobjsubid
+            WHERE sn.nspname = s.sequence_schema AND sc.relname = s.sequence_name
+            LIMIT 1
+        ) owner ON true
+        WHERE s.sequence_schema NOT IN ('pg_catalog', 'information_schema')
+        ORDER BY s.sequence_schema, s.sequence_name
+    "#;
+
+    let rows = sqlx::query(query).fetch_all(pool).await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| SequenceInfo {
+            schema: row.get("schema_name"),
+            name: row.get("sequence_name"),
+            owned_by: row.get("owned_by"),
+            last_value: row.get("last_value"),
+            increment: row.get("increment"),
+        })
+        .collect())
+}
+
+fn pg_restore_schema_args(include_schemas: &[String]) -> Vec<String> {
+    include_schemas
+        .iter()
+        .flat_map(|schema| ["-n".to_string(), schema.clone()])
+        .collect()
+}
+
+/// Format of an archive pg_restore reads: a directory-format dump or a
+/// custom-format file
+fn archive_format(dump_path: &str) -> DumpFormat {
+    if is_directory_dump(Path::new(dump_path)) {
+        DumpFormat::Directory
+    } else {
+        DumpFormat::CustomFormat
+    }
+}
+
+/// `pg_restore` flags naming the archive format
+///
+/// pg_restore detects custom-format files itself; directories are passed
+/// with `-Fd` so a directory missing its `toc.dat` fails clearly.
+fn pg_restore_format_args(format: DumpFormat) -> Vec<String> {
+    match format {
+        DumpFormat::Directory => vec!["-Fd".to_string()],
+        _ => Vec::new(),
+    }
+}
+
+/// `pg_restore` flags for a parallel restore (`-j N`)
+///
+/// Only custom-format and directory-format archives can be restored in
+/// parallel; pg_restore rejects `-j` for anything else, and psql-restored
+/// dumps ignore it anyway.
+fn pg_restore_parallel_args(format: DumpFormat, jobs: u32) -> Vec<String> {
+    if matches!(format, DumpFormat::CustomFormat | DumpFormat::Directory) && jobs > 1 {
+        vec!["-j".to_string(), jobs.to_string()]
+    } else {
+        Vec::new()
+    }
+}
+
+/// Check the output of a psql restore, collecting its warnings
+fn check_psql_output(output: &std::process::Output, warnings: &mut Vec<String>) -> Result<()> {
+    // With ON_ERROR_STOP=0 psql exits 0 even when statements fail, so
+    // collect stderr regardless of the exit status
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    // Only fail on fatal errors, not warnings or role errors
+    if !output.status.success() && stderr.contains("FATAL") {
+        return Err(classify_restore_stderr(&stderr));
+    }
+    if !stderr.trim().is_empty() {
+        warn!("psql completed with warnings: {}", stderr);
+        warnings.extend(restore_warning_lines(&stderr));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    info!(
+        "psql output: {}",
+        stdout.chars().take(500).collect::<String>()
+    );
+    Ok(())
+}
+
+/// Split pg_restore / psql stderr into non-empty warning lines
+fn restore_warning_lines(stderr: &str) -> Vec<String> {
+    stderr
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const PLAIN_SQL_HEADER: &str = "--\n-- PostgreSQL database dump\n--\n\n\
+                                    SET statement_timeout = 0;\n\
+                                    CREATE TABLE public.users (id integer);\n";
+
+    const PG_DUMPALL_HEADER: &str = "--\n-- PostgreSQL database cluster dump\n--\n\n\
+                                     SET default_transaction_read_only = off;\n\
+                                     CREATE ROLE app;\n";
+
+    #[test]
+    fn test_classify_dump_header() {
+        let mut custom = PG_DUMP_CUSTOM_MAGIC.to_vec();
+        custom.extend_from_slice(&[0x01, 0x0E, 0x00, 0x04, 0x08]);
+        assert_eq!(classify_dump_header(&custom), DumpFormat::CustomFormat);
+
+        assert_eq!(
+            classify_dump_header(PLAIN_SQL_HEADER.as_bytes()),
+            DumpFormat::PlainSql
+        );
+        assert_eq!(
+            classify_dump_header(PG_DUMPALL_HEADER.as_bytes()),
+            DumpFormat::PgDumpall
+        );
+
+        // Binary garbage, prose and empty files are rejected
+        assert_eq!(
+            classify_dump_header(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]),
+            DumpFormat::Unknown
+        );
+        assert_eq!(
+            classify_dump_header(b"Dear diary,\ntoday I uploaded a file.\n"),
+            DumpFormat::Unknown
+        );
+        assert_eq!(classify_dump_header(b""), DumpFormat::Unknown);
+    }
+
+    #[test]
+    fn test_classify_dump_header_truncated_utf8() {
+        let mut head = PLAIN_SQL_HEADER.as_bytes().to_vec();
+        // First two bytes of a three-byte character cut off by the peek window
+        head.extend_from_slice(&[0xE3, 0x81]);
+        assert_eq!(classify_dump_header(&head), DumpFormat::PlainSql);
+    }
+
+    #[test]
+    fn test_validate_dump_header_gzip() {
+        use flate2::{write::GzEncoder, Compression as GzLevel};
+        use std::io::Write;
+
+        let path = std::env::temp_dir().join(format!("dump-{}.sql.gz", uuid::Uuid::new_v4()));
+        let mut encoder = GzEncoder::new(File::create(&path).unwrap(), GzLevel::default());
+        encoder.write_all(PG_DUMPALL_HEADER.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+
+        let format = validate_dump_header(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(format.unwrap(), DumpFormat::PgDumpall);
+    }
+
+    #[test]
+    fn test_validate_dump_header_zstd_and_xz() {
+        use std::io::Write;
+
+        let zstd_path = std::env::temp_dir().join(format!("dump-{}.sql.zst", uuid::Uuid::new_v4()));
+        let compressed = zstd::encode_all(PG_DUMPALL_HEADER.as_bytes(), 0).unwrap();
+        std::fs::write(&zstd_path, compressed).unwrap();
+        let xz_path = std::env::temp_dir().join(format!("dump-{}.sql.xz", uuid::Uuid::new_v4()));
+        let mut encoder = xz2::write::XzEncoder::new(File::create(&xz_path).unwrap(), 6);
+        encoder.write_all(PLAIN_SQL_HEADER.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+
+        let zstd_format = validate_dump_header(zstd_path.to_str().unwrap());
+        let xz_format = validate_dump_header(xz_path.to_str().unwrap());
+        let xz_input =
+            PostgresAdapter::open_decompressed(xz_path.to_str().unwrap(), Compression::Xz)
+                .and_then(|mut input| {
+                    let mut sql = String::new();
+                    input.read_to_string(&mut sql)?;
+                    Ok(sql)
+                });
+        std::fs::remove_file(&zstd_path).unwrap();
+        std::fs::remove_file(&xz_path).unwrap();
+
+        assert_eq!(zstd_format.unwrap(), DumpFormat::PgDumpall);
+        assert_eq!(xz_format.unwrap(), DumpFormat::PlainSql);
+        assert_eq!(xz_input.unwrap(), PLAIN_SQL_HEADER);
+    }
+
+    #[test]
+    fn test_validate_dump_header_directory() {
+        let dir = std::env::temp_dir().join(format!("dump-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        let empty = validate_dump_header(dir.to_str().unwrap()).unwrap();
+        std::fs::write(dir.join(DIRECTORY_DUMP_TOC), b"PGDMP\x01\x0e\x00").unwrap();
+        let format = validate_dump_header(dir.to_str().unwrap()).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(empty, DumpFormat::Unknown);
+        assert_eq!(format, DumpFormat::Directory);
+        assert_eq!(
+            archive_format(dir.to_str().unwrap()),
+            DumpFormat::CustomFormat
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_restore_tool_captures_large_output() {
+        // More than a pipe buffer on both streams; must not deadlock
+        let mut cmd = Command::new("sh");
+        cmd.args([
+            "-c",
+            "head -c 200000 /dev/zero | tr '\\0' o; head -c 200000 /dev/zero | tr '\\0' e >&2",
+        ]);
+        let output = run_restore_tool(cmd, "sh").await.unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout.len(), 200_000);
+        assert_eq!(output.stderr.len(), 200_000);
+    }
+
+    #[tokio::test]
+    async fn test_run_restore_tool_with_stdin_streams_gzipped_sql() {
+        use flate2::{write::GzEncoder, Compression as GzLevel};
+        use std::io::Write;
+
+        let sql = "CREATE TABLE t (id int);\nINSERT INTO t VALUES (1);\n".repeat(5000);
+        let path = std::env::temp_dir().join(format!("dump-{}.sql.gz", uuid::Uuid::new_v4()));
+        let mut encoder = GzEncoder::new(File::create(&path).unwrap(), GzLevel::default());
+        encoder.write_all(sql.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+        let path = path.to_str().unwrap().to_string();
+
+        assert_eq!(
+            PostgresAdapter::streamable_compression(&path).unwrap(),
+            Some(Compression::Gzip)
+        );
+        let input = PostgresAdapter::open_decompressed(&path, Compression::Gzip).unwrap();
+
+        // cat stands in for psql reading the script from stdin
+        let output = run_restore_tool_with_stdin(Command::new("cat"), "psql", input)
+            .await
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), sql);
+    }
+
+    #[tokio::test]
+    async fn test_run_restore_tool_with_stdin_tool_exits_early() {
+        let input: Box<dyn Read + Send> = Box::new(std::io::repeat(b'x').take(10_000_000));
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "head -c 10 >/dev/null; exit 3"]);
+        let output = run_restore_tool_with_stdin(cmd, "psql", input)
+            .await
+            .unwrap();
+        assert_eq!(output.status.code(), Some(3));
+    }
+
+    #[tokio::test]
+    async fn test_run_restore_tool_killed_child_is_cancelled() {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "kill -9 $$"]);
+        let err = run_restore_tool(cmd, "pg_restore").await.unwrap_err();
+        assert!(matches!(err, CoreError::RestoreCancelled(_)));
+        assert_eq!(err.restore_kind(), Some("RESTORE_CANCELLED"));
+    }
+
+    #[tokio::test]
+    async fn test_run_restore_tool_missing_program() {
+        let cmd = Command::new("pg_restore_does_not_exist");
+        let err = run_restore_tool(cmd, "pg_restore").await.unwrap_err();
+        assert!(matches!(err, CoreError::RestoreToolMissing(_)));
+    }
+
+    #[test]
+    fn test_classify_restore_stderr() {
+        assert!(matches!(
+            classify_restore_stderr(
+                "pg_restore: error: connection to server at \"sandbox\" (10.0.0.5), port 5432 failed: Connection refused"
+            ),
+            CoreError::RestoreConnection(_)
+        ));
+        assert!(matches!(
+            classify_restore_stderr("psql: FATAL:  the database system is starting up"),
+            CoreError::RestoreConnection(_)
+        ));
+        assert!(matches!(
+            classify_restore_stderr("pg_restore: error: did not find magic string in file header"),
+            CoreError::RestoreSyntaxError(_)
+        ));
+        assert!(matches!(
+            classify_restore_stderr("psql:dump.sql:3: ERROR:  syntax error at or near \"CREATE\""),
+            CoreError::RestoreSyntaxError(_)
+        ));
+        assert!(matches!(
+            classify_restore_stderr("pg_restore: error: out of memory"),
+            CoreError::RestoreUnknown(_)
+        ));
+    }
+
+    #[test]
+    fn test_restore_tool_error() {
+        let missing = std::io::Error::from(std::io::ErrorKind::NotFound);
+        let error = restore_tool_error("pg_restore", missing);
+        assert_eq!(error.restore_kind(), Some("RESTORE_TOOL_MISSING"));
+
+        let denied = std::io::Error::from(std::io::ErrorKind::PermissionDenied);
+        assert!(matches!(
+            restore_tool_error("psql", denied),
+            CoreError::RestoreUnknown(_)
+        ));
+    }
+
+    #[test]
+    fn test_pg_restore_schema_args() {
+        let schemas = vec!["public".to_string(), "Sales Data".to_string()];
+        assert_eq!(
+            pg_restore_schema_args(&schemas),
+            vec!["-n", "public", "-n", "Sales Data"]
+        );
+        assert!(pg_restore_schema_args(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_pg_restore_parallel_args() {
+        assert_eq!(
+            pg_restore_parallel_args(DumpFormat::CustomFormat, 4),
+            vec!["-j", "4"]
+        );
+        assert!(pg_restore_parallel_args(DumpFormat::CustomFormat, 1).is_empty());
+        assert!(pg_restore_parallel_args(DumpFormat::CustomFormat, 0).is_empty());
+        assert_eq!(
+            pg_restore_parallel_args(DumpFormat::Directory, 2),
+            vec!["-j".to_string(), "2".to_string()]
+        );
+        assert!(pg_restore_parallel_args(DumpFormat::PlainSql, 4).is_empty());
+        assert!(pg_restore_parallel_args(DumpFormat::PgDumpall, 4).is_empty());
+    }
+
+    #[test]
+    fn test_restore_warning_lines() {
+        let stderr = "pg_restore: warning: errors ignored on restore: 2\n\n  \
+                      psql:dump.sql:12: ERROR:  role \"app\" does not exist\n";
+        assert_eq!(
+            restore_warning_lines(stderr),
+            vec![
+                "pg_restore: warning: errors ignored on restore: 2",
+                "psql:dump.sql:12: ERROR:  role \"app\" does not exist",
+            ]
+        );
+        assert!(restore_warning_lines(" \n").is_empty());
+    }
+
     #[test]
     fn test_parse_fk_action() {
         assert_eq!(
@@ -1578,4 +3265,389 @@ mod tests {
             FkAction::NoAction
         );
     }
+
+    #[test]
+    fn test_detect_compression() {
+        assert_eq!(Compression::detect(&[0x1F, 0x8B, 0x08]), Compression::Gzip);
+        assert_eq!(
+            Compression::detect(&[0x28, 0xB5, 0x2F, 0xFD, 0x00]),
+            Compression::Zstd
+        );
+        assert_eq!(
+            Compression::detect(&[0xFD, 0x37, 0x7A, 0x58, 0x5A, 0x00]),
+            Compression::Xz
+        );
+        assert_eq!(Compression::detect(b"PGDMP"), Compression::None);
+        assert_eq!(Compression::detect(b"--"), Compression::None);
+        assert_eq!(Compression::detect(&[]), Compression::None);
+    }
+
+    /// Adapter for the scratch server in `TEST_SANDBOX_URL`
+    async fn test_sandbox_adapter() -> (PostgresAdapter, SandboxConnection) {
+        use crate::connection::SslMode;
+
+        let url = std::env::var("TEST_SANDBOX_URL").expect("TEST_SANDBOX_URL is required");
+        let parsed = url::Url::parse(&url).unwrap();
+        let connection = SandboxConnection {
+            host: parsed.host_str().unwrap().to_string(),
+            port: parsed.port().unwrap_or(5432),
+            user: urlencoding::decode(parsed.username()).unwrap().into_owned(),
+            password: parsed
+                .password()
+                .map(|p| urlencoding::decode(p).unwrap().into_owned()),
+            sslmode: SslMode::Prefer,
+            sslrootcert: None,
+        };
+        let adapter =
+            PostgresAdapter::new(PgPool::connect(&url).await.unwrap(), connection.clone());
+        (adapter, connection)
+    }
+
+    /// Requires a superuser connection to a scratch server in
+    /// `TEST_SANDBOX_URL`, e.g. `postgres://postgres:pw@localhost:5433/postgres`
+    #[tokio::test]
+    #[ignore = "requires TEST_SANDBOX_URL"]
+    async fn test_create_database_refuses_to_replace_without_overwrite() {
+        let (adapter, _) = test_sandbox_adapter().await;
+        let db_name = format!("sandbox_create_test_{}", uuid::Uuid::new_v4().simple());
+
+        adapter.create_database(&db_name, false).await.unwrap();
+        let admin = adapter.db_pool(&db_name).await.unwrap();
+        sqlx::query("CREATE TABLE public.keep (id integer)")
+            .execute(&admin)
+            .await
+            .unwrap();
+
+        let conflict = adapter.create_database(&db_name, false).await;
+        let kept: bool = sqlx::query_scalar("SELECT to_regclass('public.keep') IS NOT NULL")
+            .fetch_one(&admin)
+            .await
+            .unwrap();
+
+        adapter.create_database(&db_name, true).await.unwrap();
+        let recreated = adapter.db_pool(&db_name).await.unwrap();
+        let replaced: bool = sqlx::query_scalar("SELECT to_regclass('public.keep') IS NULL")
+            .fetch_one(&recreated)
+            .await
+            .unwrap();
+        adapter.drop_database(&db_name).await.unwrap();
+
+        assert!(matches!(conflict, Err(CoreError::DatabaseExists(name)) if name == db_name));
+        assert!(kept);
+        assert!(replaced);
+    }
+
+    /// Requires a superuser connection to a scratch server in
+    /// `TEST_SANDBOX_URL`, e.g. `postgres://postgres:pw@localhost:5433/postgres`
+    #[tokio::test]
+    #[ignore = "requires TEST_SANDBOX_URL"]
+    async fn test_restore_refuses_existing_database_without_overwrite() {
+        let (adapter, _) = test_sandbox_adapter().await;
+        let db_name = format!("sandbox_restore_test_{}", uuid::Uuid::new_v4().simple());
+        let dir = tempfile::tempdir().unwrap();
+        let dump = dir.path().join("dump.sql");
+        std::fs::write(&dump, "CREATE TABLE public.restored (id integer);\n").unwrap();
+        let dump = dump.to_str().unwrap();
+
+        adapter.create_database(&db_name, false).await.unwrap();
+        let refused = adapter.restore_dump(dump, &db_name).await;
+        let overwritten = adapter
+            .with_restore_overwrite(true)
+            .restore_dump(dump, &db_name)
+            .await;
+        let (adapter, _) = test_sandbox_adapter().await;
+        adapter.drop_database(&db_name).await.unwrap();
+
+        assert!(matches!(refused, Err(CoreError::DatabaseExists(name)) if name == db_name));
+        assert!(overwritten.is_ok());
+    }
+
+    /// Requires a superuser connection to a scratch server in
+    /// `TEST_SANDBOX_URL`, e.g. `postgres://postgres:pw@localhost:5433/postgres`
+    #[tokio::test]
+    #[ignore = "requires TEST_SANDBOX_URL"]
+    async fn test_read_only_role_cannot_write() {
+        let (adapter, connection) = test_sandbox_adapter().await;
+        let db_name = format!("sandbox_ro_test_{}", uuid::Uuid::new_v4().simple());
+        let role = ReadOnlyRole {
+            user: "pgdumplens_ro_test".to_string(),
+            password: Some("ro-secret".to_string()),
+        };
+        adapter.create_database(&db_name, false).await.unwrap();
+        let admin = adapter.db_pool(&db_name).await.unwrap();
+        sqlx::query("CREATE TABLE public.users (id integer)")
+            .execute(&admin)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO public.users VALUES (1)")
+            .execute(&admin)
+            .await
+            .unwrap();
+
+        adapter.grant_read_only(&db_name, &role).await.unwrap();
+
+        let read_only = PgPool::connect(&connection.as_read_only(&role).url(&db_name))
+            .await
+            .unwrap();
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM public.users")
+            .fetch_one(&read_only)
+            .await
+            .unwrap();
+        let insert = sqlx::query("INSERT INTO public.users VALUES (2)")
+            .execute(&read_only)
+            .await;
+        let drop = sqlx::query("DROP TABLE public.users")
+            .execute(&read_only)
+            .await;
+        let create = sqlx::query("CREATE TABLE public.scratch (id integer)")
+            .execute(&read_only)
+            .await;
+        read_only.close().await;
+
+        adapter.drop_database(&db_name).await.unwrap();
+        sqlx::query(&format!("DROP ROLE IF EXISTS {}", quote_ident(&role.user)))
+            .execute(&adapter.pool)
+            .await
+            .unwrap();
+
+        assert_eq!(count, 1);
+        assert!(insert.is_err());
+        assert!(drop.is_err());
+        assert!(create.is_err());
+    }
+
+    /// Requires a superuser connection to a scratch server in
+    /// `TEST_SANDBOX_URL`, e.g. `postgres://postgres:pw@localhost:5433/postgres`
+    #[tokio::test]
+    #[ignore = "requires TEST_SANDBOX_URL"]
+    async fn test_list_unique_constraints() {
+        let (adapter, _) = test_sandbox_adapter().await;
+        let db_name = format!("sandbox_unique_test_{}", uuid::Uuid::new_v4().simple());
+        adapter.create_database(&db_name, false).await.unwrap();
+        let admin = adapter.db_pool(&db_name).await.unwrap();
+        sqlx::query(
+            "CREATE TABLE public.accounts (
+                id integer PRIMARY KEY,
+                nickname text UNIQUE,
+                tenant integer NOT NULL,
+                email text NOT NULL,
+                CONSTRAINT accounts_tenant_email_key UNIQUE (tenant, email)
+            )",
+        )
+        .execute(&admin)
+        .await
+        .unwrap();
+
+        let constraints = adapter
+            .list_unique_constraints(&db_name, "public", "accounts")
+            .await
+            .unwrap();
+        adapter.drop_database(&db_name).await.unwrap();
+
+        assert_eq!(
+            constraints,
+            vec![
+                UniqueConstraint {
+                    name: "accounts_tenant_email_key".to_string(),
+                    columns: vec!["tenant".to_string(), "email".to_string()],
+                    all_not_null: true,
+                },
+                UniqueConstraint {
+                    name: "accounts_nickname_key".to_string(),
+                    columns: vec!["nickname".to_string()],
+                    all_not_null: false,
+                },
+            ]
+        );
+    }
+
+    /// Requires a superuser connection to a scratch server in
+    /// `TEST_SANDBOX_URL`, e.g. `postgres://postgres:pw@localhost:5433/postgres`
+    #[tokio::test]
+    #[ignore = "requires TEST_SANDBOX_URL"]
+    async fn test_catalog_introspection_matches_information_schema() {
+        let (adapter, connection) = test_sandbox_adapter().await;
+        let db_name = format!("sandbox_catalog_test_{}", uuid::Uuid::new_v4().simple());
+        adapter.create_database(&db_name, false).await.unwrap();
+        let admin = adapter.db_pool(&db_name).await.unwrap();
+        // The demo schema plus column kinds it does not cover
+        let sample = concat!(
+            include_str!("../../../../demo/ecommerce_demo.sql"),
+            "
+            CREATE TYPE public.mood AS ENUM ('ok', 'meh');
+            CREATE DOMAIN public.positive AS integer NOT NULL CHECK (VALUE > 0);
+            CREATE TABLE public.kinds (
+                id bigint GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+                mood public.mood,
+                moods public.mood[],
+                tags text[],
+                qty public.positive,
+                price numeric(10, 2),
+                total numeric GENERATED ALWAYS AS (price * qty) STORED
+            );
+            ALTER TABLE public.kinds DROP COLUMN tags;
+            "
+        );
+        sqlx::Executor::execute(&admin, sample).await.unwrap();
+
+        let catalog = PostgresAdapter::new(adapter.pool.clone(), connection)
+            .with_catalog_introspection(true)
+            .with_db_pool(&db_name, admin.clone());
+        let tables = |mut tables: Vec<TableInfo>| {
+            // Statistics may be flushed between the two reads
+            for table in &mut tables {
+                table.estimated_row_count = 0;
+            }
+            serde_json::to_value(tables).unwrap()
+        };
+        let fks = |mut fks: Vec<ForeignKey>| {
+            fks.sort_by(|a, b| a.constraint_name.cmp(&b.constraint_name));
+            serde_json::to_value(fks).unwrap()
+        };
+        let portable_tables = tables(adapter.list_tables(&db_name).await.unwrap());
+        let catalog_tables = tables(catalog.list_tables(&db_name).await.unwrap());
+        let portable_fks = fks(adapter.list_foreign_keys(&db_name).await.unwrap());
+        let catalog_fks = fks(catalog.list_foreign_keys(&db_name).await.unwrap());
+        adapter.drop_database(&db_name).await.unwrap();
+
+        assert_eq!(catalog_tables, portable_tables);
+        assert_eq!(catalog_fks, portable_fks);
+        assert!(catalog_tables.as_array().unwrap().len() > 10);
+    }
+
+    /// Requires a superuser connection to a scratch server in
+    /// `TEST_SANDBOX_URL`, e.g. `postgres://postgres:pw@localhost:5433/postgres`
+    #[tokio::test]
+    #[ignore = "requires TEST_SANDBOX_URL"]
+    async fn test_list_check_constraints() {
+        let (adapter, _) = test_sandbox_adapter().await;
+        let db_name = format!("sandbox_check_test_{}", uuid::Uuid::new_v4().simple());
+        adapter.create_database(&db_name, false).await.unwrap();
+        let admin = adapter.db_pool(&db_name).await.unwrap();
+        sqlx::query(
+            "CREATE TABLE public.products (
+                id integer PRIMARY KEY,
+                price numeric NOT NULL CHECK (price > 0),
+                discount numeric,
+                CONSTRAINT products_discount_check CHECK (discount < price)
+            )",
+        )
+        .execute(&admin)
+        .await
+        .unwrap();
+
+        let constraints = adapter
+            .list_check_constraints(&db_name, "public", "products")
+            .await
+            .unwrap();
+        adapter.drop_database(&db_name).await.unwrap();
+
+        assert_eq!(
+            constraints,
+            vec![
+                CheckConstraint {
+                    name: "products_discount_check".to_string(),
+                    definition: "CHECK ((discount < price))".to_string(),
+                },
+                CheckConstraint {
+                    name: "products_price_check".to_string(),
+                    definition: "CHECK ((price > (0)::numeric))".to_string(),
+                },
+            ]
+        );
+    }
+
+    /// Requires a superuser connection to a scratch server in
+    /// `TEST_SANDBOX_URL`, e.g. `postgres://postgres:pw@localhost:5433/postgres`
+    #[tokio::test]
+    #[ignore = "requires TEST_SANDBOX_URL"]
+    async fn test_fetch_sample_rows_filtered_and_ordered() {
+        use crate::filter::{parse_filter, SortDirection};
+
+        let (adapter, _) = test_sandbox_adapter().await;
+        let db_name = format!("sandbox_sample_test_{}", uuid::Uuid::new_v4().simple());
+        adapter.create_database(&db_name, false).await.unwrap();
+        let admin = adapter.db_pool(&db_name).await.unwrap();
+        for statement in [
+            "CREATE TABLE public.orders (id integer PRIMARY KEY, status text, total numeric)",
+            "INSERT INTO public.orders VALUES
+                (1, 'open', 10), (2, 'paid', 25), (3, 'open', 7), (4, 'open', NULL)",
+        ] {
+            sqlx::query(statement).execute(&admin).await.unwrap();
+        }
+        let ids = |rows: Vec<serde_json::Value>| -> Vec<i64> {
+            rows.iter().map(|r| r["id"].as_i64().unwrap()).collect()
+        };
+        let by_total = |direction| {
+            Some(OrderBy {
+                column: "total".to_string(),
+                direction,
+            })
+        };
+        let open = parse_filter("status = 'open'").unwrap();
+
+        let filtered = adapter
+            .fetch_sample_rows_filtered(&db_name, "public", "orders", &open, None, 10)
+            .await
+            .unwrap();
+        let largest_open = adapter
+            .fetch_sample_rows_filtered(
+                &db_name,
+                "public",
+                "orders",
+                &open,
+                by_total(SortDirection::Desc),
+                2,
+            )
+            .await
+            .unwrap();
+        let ascending = adapter
+            .fetch_sample_rows_filtered(
+                &db_name,
+                "public",
+                "orders",
+                &[],
+                by_total(SortDirection::Asc),
+                10,
+            )
+            .await
+            .unwrap();
+        let unfiltered = adapter
+            .fetch_sample_rows(&db_name, "public", "orders", 10)
+            .await
+            .unwrap();
+        let bad_order = adapter
+            .fetch_sample_rows_filtered(
+                &db_name,
+                "public",
+                "orders",
+                &[],
+                Some(OrderBy {
+                    column: "id; DROP TABLE orders".to_string(),
+                    direction: SortDirection::Asc,
+                }),
+                10,
+            )
+            .await;
+        let bad_filter = adapter
+            .fetch_sample_rows_filtered(
+                &db_name,
+                "public",
+                "orders",
+                &parse_filter("missing = 1").unwrap(),
+                None,
+                10,
+            )
+            .await;
+        adapter.drop_database(&db_name).await.unwrap();
+
+        let mut filtered = ids(filtered);
+        filtered.sort();
+        assert_eq!(filtered, vec![1, 3, 4]);
+        assert_eq!(ids(largest_open), vec![1, 3]);
+        assert_eq!(ids(ascending), vec![3, 1, 2, 4]);
+        assert_eq!(unfiltered.len(), 4);
+        assert!(matches!(bad_order, Err(CoreError::Validation(_))));
+        assert!(matches!(bad_filter, Err(CoreError::Validation(_))));
+    }
 }
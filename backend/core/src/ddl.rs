@@ -0,0 +1,576 @@
+//! DDL reconstruction from the schema graph
+//!
+//! The statements are rebuilt from introspected metadata, not copied from the
+//! dump, so column types use the names the schema graph records.
+
+use std::collections::HashSet;
+
+use crate::domain::{
+    CheckConstraint, ColumnInfo, EnumType, FkAction, ForeignKey, IndexInfo, SchemaGraph,
+    SequenceInfo, TableInfo, UniqueConstraint, ViewInfo,
+};
+use crate::schema::{topological_table_order, TableKey};
+use crate::sql_gen::{quote_ident, quote_literal};
+
+/// `"schema"."name"`
+pub fn qualified_name(schema: &str, name: &str) -> String {
+    format!("{}.{}", quote_ident(schema), quote_ident(name))
+}
+
+/// Comma-separated quoted column list
+fn column_list(columns: &[String]) -> String {
+    columns
+        .iter()
+        .map(|c| quote_ident(c))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Column definition as it appears inside `CREATE TABLE`
+pub fn column_definition(column: &ColumnInfo) -> String {
+    let mut definition = format!("{} {}", quote_ident(&column.name), column.data_type);
+    if let Some(expression) = &column.generation_expression {
+        definition.push_str(&format!(" GENERATED ALWAYS AS ({}) STORED", expression));
+    } else if column.is_identity {
+        let generation = column
+            .identity_generation
+            .as_deref()
+            .unwrap_or("BY DEFAULT");
+        definition.push_str(&format!(" GENERATED {} AS IDENTITY", generation));
+    } else if let Some(default) = &column.default_value {
+        definition.push_str(&format!(" DEFAULT {}", default));
+    }
+    if !column.is_nullable {
+        definition.push_str(" NOT NULL");
+    }
+    definition
+}
+
+/// Index backing a table's primary key, among `indexes`
+pub fn primary_key_index<'a>(indexes: &'a [IndexInfo], table: &TableInfo) -> Option<&'a IndexInfo> {
+    indexes.iter().find(|i| {
+        i.is_primary && i.schema_name == table.schema_name && i.table_name == table.table_name
+    })
+}
+
+/// Primary key columns of a table in key order
+///
+/// The key order comes from the primary key index when `indexes` has it
+/// (index keys are printed by `pg_get_indexdef`, so quoted when needed);
+/// otherwise the flagged columns are taken in table order.
+pub fn primary_key_columns(indexes: &[IndexInfo], table: &TableInfo) -> Vec<String> {
+    let mut columns: Vec<&ColumnInfo> = table.columns.iter().filter(|c| c.is_primary_key).collect();
+    if let Some(index) = primary_key_index(indexes, table) {
+        columns.sort_by_key(|c| {
+            index
+                .columns
+                .iter()
+                .position(|key| *key == c.name || *key == quote_ident(&c.name))
+                .unwrap_or(usize::MAX)
+        });
+    }
+    columns.into_iter().map(|c| c.name.clone()).collect()
+}
+
+/// `PRIMARY KEY` table constraint, named after the primary key index when known
+pub fn primary_key_clause(indexes: &[IndexInfo], table: &TableInfo) -> Option<String> {
+    let columns = primary_key_columns(indexes, table);
+    if columns.is_empty() {
+        return None;
+    }
+    Some(match primary_key_index(indexes, table) {
+        Some(index) => format!(
+            "CONSTRAINT {} PRIMARY KEY ({})",
+            quote_ident(&index.name),
+            column_list(&columns)
+        ),
+        None => format!("PRIMARY KEY ({})", column_list(&columns)),
+    })
+}
+
+/// `FOREIGN KEY` table constraint; `NO ACTION` rules are left implicit
+pub fn foreign_key_clause(fk: &ForeignKey) -> String {
+    let mut clause = format!(
+        "CONSTRAINT {} FOREIGN KEY ({}) REFERENCES {} ({})",
+        quote_ident(&fk.constraint_name),
+        column_list(&fk.source_columns),
+        qualified_name(&fk.target_schema, &fk.target_table),
+        column_list(&fk.target_columns)
+    );
+    if fk.on_update != FkAction::NoAction {
+        clause.push_str(&format!(" ON UPDATE {}", fk.on_update));
+    }
+    if fk.on_delete != FkAction::NoAction {
+        clause.push_str(&format!(" ON DELETE {}", fk.on_delete));
+    }
+    clause
+}
+
+/// `UNIQUE` table constraint
+pub fn unique_clause(constraint: &UniqueConstraint) -> String {
+    format!(
+        "CONSTRAINT {} UNIQUE ({})",
+        quote_ident(&constraint.name),
+        column_list(&constraint.columns)
+    )
+}
+
+/// `CHECK` table constraint
+pub fn check_clause(constraint: &CheckConstraint) -> String {
+    format!(
+        "CONSTRAINT {} {}",
+        quote_ident(&constraint.name),
+        constraint.definition
+    )
+}
+
+/// `CREATE TABLE` statement with the given table constraints after the columns
+pub fn create_table(table: &TableInfo, constraints: &[String]) -> String {
+    let lines: Vec<String> = table
+        .columns
+        .iter()
+        .map(column_definition)
+        .chain(constraints.iter().cloned())
+        .map(|line| format!("    {}", line))
+        .collect();
+    format!(
+        "CREATE TABLE {} (\n{}\n);",
+        qualified_name(&table.schema_name, &table.table_name),
+        lines.join(",\n")
+    )
+}
+
+/// `CREATE INDEX` statement; index keys and predicates are already valid SQL
+pub fn create_index(index: &IndexInfo) -> String {
+    format!(
+        "CREATE {}INDEX {} ON {} USING {} ({}){};",
+        if index.is_unique { "UNIQUE " } else { "" },
+        quote_ident(&index.name),
+        qualified_name(&index.schema_name, &index.table_name),
+        index.method,
+        index.columns.join(", "),
+        index
+            .predicate
+            .as_ref()
+            .map(|p| format!(" WHERE {}", p))
+            .unwrap_or_default()
+    )
+}
+
+/// `CREATE TYPE ... AS ENUM` statement
+pub fn create_enum(enum_type: &EnumType) -> String {
+    format!(
+        "CREATE TYPE {} AS ENUM ({});",
+        qualified_name(&enum_type.schema, &enum_type.name),
+        enum_type
+            .values
+            .iter()
+            .map(|v| quote_literal(v))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+/// `CREATE SEQUENCE` statement
+pub fn create_sequence(sequence: &SequenceInfo) -> String {
+    format!(
+        "CREATE SEQUENCE {} INCREMENT BY {};",
+        qualified_name(&sequence.schema, &sequence.name),
+        sequence.increment
+    )
+}
+
+/// `CREATE [MATERIALIZED] VIEW` statement
+pub fn create_view(view: &ViewInfo) -> String {
+    format!(
+        "CREATE {}VIEW {} AS\n{};",
+        if view.is_materialized {
+            "MATERIALIZED "
+        } else {
+            ""
+        },
+        qualified_name(&view.schema, &view.name),
+        view.definition.trim().trim_end_matches(';')
+    )
+}
+
+/// Column owning a sequence, matched against the sequence's unquoted
+/// `schema.table.column`
+fn sequence_owner<'a>(
+    schema_graph: &'a SchemaGraph,
+    sequence: &SequenceInfo,
+) -> Option<(&'a TableInfo, &'a ColumnInfo)> {
+    let owned_by = sequence.owned_by.as_deref()?;
+    schema_graph.tables.iter().find_map(|table| {
+        table
+            .columns
+            .iter()
+            .find(|c| owned_by == format!("{}.{}.{}", table.schema_name, table.table_name, c.name))
+            .map(|column| (table, column))
+    })
+}
+
+/// Creation order when FKs form a cycle: the first table (by name) whose
+/// referenced tables all exist, or, when every remaining table waits on
+/// another, the first remaining one
+fn cyclic_table_order(schema_graph: &SchemaGraph) -> Vec<TableKey> {
+    let mut remaining: Vec<TableKey> = schema_graph
+        .tables
+        .iter()
+        .map(|t| (t.schema_name.clone(), t.table_name.clone()))
+        .collect();
+    remaining.sort();
+
+    let mut order: Vec<TableKey> = Vec::with_capacity(remaining.len());
+    while !remaining.is_empty() {
+        let ready = remaining
+            .iter()
+            .position(|key| {
+                schema_graph
+                    .foreign_keys
+                    .iter()
+                    .filter(|fk| fk.source_schema == key.0 && fk.source_table == key.1)
+                    .all(|fk| {
+                        (fk.target_schema == key.0 && fk.target_table == key.1)
+                            || order
+                                .iter()
+                                .any(|o| o.0 == fk.target_schema && o.1 == fk.target_table)
+                    })
+            })
+            .unwrap_or(0);
+        order.push(remaining.remove(ready));
+    }
+    order
+}
+
+/// Full DDL script recreating the schema graph
+///
+/// Schemas, enum types and sequences come first, then each table followed by
+/// its indexes, in dependency order (see [`topological_table_order`]).
+/// Foreign keys are declared inline when the referenced table already
+/// exists and added with `ALTER TABLE` at the end otherwise, which only
+/// happens on reference cycles. Sequences of identity columns are left to
+/// the identity; serial sequences are attached with `OWNED BY` once their
+/// table exists. Views come last. Partitions, check constraints and
+/// triggers are not in the graph in enough detail to recreate and are
+/// skipped.
+pub fn schema_script(schema_graph: &SchemaGraph) -> String {
+    let mut statements = Vec::new();
+
+    let schemas: std::collections::BTreeSet<&str> = schema_graph
+        .tables
+        .iter()
+        .map(|t| t.schema_name.as_str())
+        .chain(schema_graph.enum_types.iter().map(|e| e.schema.as_str()))
+        .chain(schema_graph.sequences.iter().map(|s| s.schema.as_str()))
+        .chain(schema_graph.views.iter().map(|v| v.schema.as_str()))
+        .collect();
+    for schema in schemas {
+        statements.push(format!(
+            "CREATE SCHEMA IF NOT EXISTS {};",
+            quote_ident(schema)
+        ));
+    }
+    statements.extend(schema_graph.enum_types.iter().map(create_enum));
+
+    let mut owned_sequences = Vec::new();
+    for sequence in &schema_graph.sequences {
+        match sequence_owner(schema_graph, sequence) {
+            Some((_, column)) if column.is_identity => continue,
+            Some((table, column)) => owned_sequences.push(format!(
+                "ALTER SEQUENCE {} OWNED BY {}.{};",
+                qualified_name(&sequence.schema, &sequence.name),
+                qualified_name(&table.schema_name, &table.table_name),
+                quote_ident(&column.name)
+            )),
+            None => {}
+        }
+        statements.push(create_sequence(sequence));
+    }
+
+    let order =
+        topological_table_order(schema_graph).unwrap_or_else(|_| cyclic_table_order(schema_graph));
+
+    let mut created: HashSet<TableKey> = HashSet::new();
+    let mut deferred_fks = Vec::new();
+    for key in order {
+        let Some(table) = schema_graph
+            .tables
+            .iter()
+            .find(|t| t.schema_name == key.0 && t.table_name == key.1)
+        else {
+            continue;
+        };
+        if let Some(parent) = &table.partition_of {
+            statements.push(format!(
+                "-- {} is a partition of {}; partition bounds are not recorded",
+                qualified_name(&table.schema_name, &table.table_name),
+                parent
+            ));
+            continue;
+        }
+
+        let mut constraints: Vec<String> = primary_key_clause(&schema_graph.indexes, table)
+            .into_iter()
+            .collect();
+        for fk in schema_graph
+            .foreign_keys
+            .iter()
+            .filter(|fk| fk.source_schema == key.0 && fk.source_table == key.1)
+        {
+            let target = (fk.target_schema.clone(), fk.target_table.clone());
+            if target == key || created.contains(&target) {
+                constraints.push(foreign_key_clause(fk));
+            } else {
+                deferred_fks.push(format!(
+                    "ALTER TABLE {} ADD {};",
+                    qualified_name(&fk.source_schema, &fk.source_table),
+                    foreign_key_clause(fk)
+                ));
+            }
+        }
+        statements.push(create_table(table, &constraints));
+        statements.extend(
+            schema_graph
+                .indexes
+                .iter()
+                .filter(|i| !i.is_primary && i.schema_name == key.0 && i.table_name == key.1)
+                .map(create_index),
+        );
+        created.insert(key);
+    }
+
+    statements.extend(deferred_fks);
+    statements.extend(owned_sequences);
+    statements.extend(schema_graph.views.iter().map(create_view));
+
+    let mut script = statements.join("\n\n");
+    script.push('\n');
+    script
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(name: &str, data_type: &str, is_nullable: bool) -> ColumnInfo {
+        ColumnInfo {
+            name: name.to_string(),
+            data_type: data_type.to_string(),
+            is_nullable,
+            is_primary_key: false,
+            default_value: None,
+            comment: None,
+            ordinal_position: None,
+            is_identity: false,
+            identity_generation: None,
+            generation_expression: None,
+        }
+    }
+
+    #[test]
+    fn test_column_definition_clauses() {
+        let mut id = column("id", "bigint", false);
+        id.is_identity = true;
+        id.identity_generation = Some("ALWAYS".to_string());
+        let mut total = column("total", "numeric", true);
+        total.generation_expression = Some("price * qty".to_string());
+        let mut status = column("status", "text", false);
+        status.default_value = Some("'new'::text".to_string());
+
+        assert_eq!(
+            column_definition(&id),
+            "\"id\" bigint GENERATED ALWAYS AS IDENTITY NOT NULL"
+        );
+        assert_eq!(
+            column_definition(&total),
+            "\"total\" numeric GENERATED ALWAYS AS (price * qty) STORED"
+        );
+        assert_eq!(
+            column_definition(&status),
+            "\"status\" text DEFAULT 'new'::text NOT NULL"
+        );
+    }
+
+    #[test]
+    fn test_create_table_with_primary_and_foreign_key() {
+        let mut id = column("id", "integer", false);
+        id.is_primary_key = true;
+        let table = TableInfo {
+            schema_name: "public".to_string(),
+            table_name: "orders".to_string(),
+            estimated_row_count: 0,
+            columns: vec![id, column("user_id", "integer", true)],
+            comment: None,
+            is_partition: false,
+            partition_of: None,
+            partition_strategy: None,
+        };
+        let fk = ForeignKey {
+            constraint_name: "orders_user_id_fkey".to_string(),
+            source_schema: "public".to_string(),
+            source_table: "orders".to_string(),
+            source_columns: vec!["user_id".to_string()],
+            target_schema: "public".to_string(),
+            target_table: "users".to_string(),
+            target_columns: vec!["id".to_string()],
+            on_delete: FkAction::Cascade,
+            on_update: FkAction::NoAction,
+        };
+        let indexes = vec![IndexInfo {
+            name: "orders_pkey".to_string(),
+            schema_name: "public".to_string(),
+            table_name: "orders".to_string(),
+            columns: vec!["id".to_string()],
+            is_unique: true,
+            is_primary: true,
+            method: "btree".to_string(),
+            predicate: None,
+        }];
+
+        let constraints = vec![
+            primary_key_clause(&indexes, &table).unwrap(),
+            foreign_key_clause(&fk),
+        ];
+        assert_eq!(
+            create_table(&table, &constraints),
+            "CREATE TABLE \"public\".\"orders\" (\n    \
+             \"id\" integer NOT NULL,\n    \
+             \"user_id\" integer,\n    \
+             CONSTRAINT \"orders_pkey\" PRIMARY KEY (\"id\"),\n    \
+             CONSTRAINT \"orders_user_id_fkey\" FOREIGN KEY (\"user_id\") \
+             REFERENCES \"public\".\"users\" (\"id\") ON DELETE CASCADE\n);"
+        );
+    }
+
+    #[test]
+    fn test_primary_key_columns_follow_index_order() {
+        let mut tenant = column("TenantId", "integer", false);
+        tenant.is_primary_key = true;
+        let mut id = column("id", "integer", false);
+        id.is_primary_key = true;
+        let table = TableInfo {
+            schema_name: "public".to_string(),
+            table_name: "items".to_string(),
+            estimated_row_count: 0,
+            columns: vec![id, tenant],
+            comment: None,
+            is_partition: false,
+            partition_of: None,
+            partition_strategy: None,
+        };
+        let index = IndexInfo {
+            name: "items_pkey".to_string(),
+            schema_name: "public".to_string(),
+            table_name: "items".to_string(),
+            columns: vec!["\"TenantId\"".to_string(), "id".to_string()],
+            is_unique: true,
+            is_primary: true,
+            method: "btree".to_string(),
+            predicate: None,
+        };
+
+        assert_eq!(primary_key_columns(&[], &table), vec!["id", "TenantId"]);
+        assert_eq!(
+            primary_key_columns(std::slice::from_ref(&index), &table),
+            vec!["TenantId", "id"]
+        );
+        assert_eq!(
+            create_index(&IndexInfo {
+                name: "items_lower_idx".to_string(),
+                is_primary: false,
+                is_unique: false,
+                columns: vec!["lower(name)".to_string()],
+                predicate: Some("deleted_at IS NULL".to_string()),
+                ..index
+            }),
+            "CREATE INDEX \"items_lower_idx\" ON \"public\".\"items\" USING btree (lower(name)) \
+             WHERE deleted_at IS NULL;"
+        );
+    }
+
+    fn table(name: &str, columns: Vec<ColumnInfo>) -> TableInfo {
+        TableInfo {
+            schema_name: "public".to_string(),
+            table_name: name.to_string(),
+            estimated_row_count: 0,
+            columns,
+            comment: None,
+            is_partition: false,
+            partition_of: None,
+            partition_strategy: None,
+        }
+    }
+
+    fn fk(name: &str, source: &str, target: &str) -> ForeignKey {
+        ForeignKey {
+            constraint_name: name.to_string(),
+            source_schema: "public".to_string(),
+            source_table: source.to_string(),
+            source_columns: vec!["ref_id".to_string()],
+            target_schema: "public".to_string(),
+            target_table: target.to_string(),
+            target_columns: vec!["id".to_string()],
+            on_delete: FkAction::NoAction,
+            on_update: FkAction::NoAction,
+        }
+    }
+
+    fn position(script: &str, needle: &str) -> usize {
+        script
+            .find(needle)
+            .unwrap_or_else(|| panic!("{} missing from script:\n{}", needle, script))
+    }
+
+    #[test]
+    fn test_schema_script_creates_referenced_tables_first() {
+        let columns = || {
+            vec![
+                column("id", "integer", false),
+                column("ref_id", "integer", true),
+            ]
+        };
+        let graph = SchemaGraph {
+            // Listed so name order would be wrong
+            tables: vec![
+                table("a_orders", columns()),
+                table("b_users", columns()),
+                table("c_cycle", columns()),
+                table("d_cycle", columns()),
+            ],
+            foreign_keys: vec![
+                fk("orders_user_fkey", "a_orders", "b_users"),
+                fk("c_d_fkey", "c_cycle", "d_cycle"),
+                fk("d_c_fkey", "d_cycle", "c_cycle"),
+            ],
+            enum_types: vec![EnumType {
+                schema: "public".to_string(),
+                name: "mood".to_string(),
+                values: vec!["ok".to_string(), "it's fine".to_string()],
+            }],
+            ..Default::default()
+        };
+
+        let script = schema_script(&graph);
+
+        for fk in &graph.foreign_keys {
+            let constraint = position(&script, &format!("CONSTRAINT \"{}\"", fk.constraint_name));
+            let target = position(
+                &script,
+                &format!("CREATE TABLE \"public\".\"{}\"", fk.target_table),
+            );
+            assert!(
+                target < constraint,
+                "{} precedes {}",
+                fk.constraint_name,
+                fk.target_table
+            );
+        }
+        assert!(script.contains("ALTER TABLE \"public\".\"c_cycle\" ADD CONSTRAINT \"c_d_fkey\""));
+        assert!(
+            position(&script, "CREATE SCHEMA IF NOT EXISTS \"public\"")
+                < position(&script, "CREATE TYPE")
+        );
+        assert!(script.contains("CREATE TYPE \"public\".\"mood\" AS ENUM ('ok', 'it''s fine');"));
+    }
+}
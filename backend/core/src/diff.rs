@@ -1,6 +1,9 @@
 //! Schema and data diff comparison logic
 
-use crate::domain::{ColumnInfo, ForeignKey, SchemaGraph, TableInfo};
+use crate::adapter::DbAdapter;
+use crate::domain::{ColumnInfo, EnumType, ForeignKey, SchemaGraph, SequenceInfo, TableInfo};
+use crate::error::Result;
+use crate::sql_gen::{quote_ident, quote_literal};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 
@@ -32,6 +35,24 @@ pub struct DiffSummary {
     pub fk_added: usize,
     /// Total number of foreign keys removed
     pub fk_removed: usize,
+    /// Total number of foreign keys modified
+    #[serde(default)]
+    pub fk_modified: usize,
+    /// Total number of enum types added
+    #[serde(default)]
+    pub enums_added: usize,
+    /// Total number of enum types removed
+    #[serde(default)]
+    pub enums_removed: usize,
+    /// Total number of enum types whose values changed
+    #[serde(default)]
+    pub enums_modified: usize,
+    /// Total number of sequences added
+    #[serde(default)]
+    pub sequences_added: usize,
+    /// Total number of sequences removed
+    #[serde(default)]
+    pub sequences_removed: usize,
     /// Net change in total row count
     pub row_count_change: i64,
 }
@@ -71,6 +92,9 @@ pub struct ColumnDiffInfo {
     pub is_nullable: bool,
     pub is_primary_key: bool,
     pub default_value: Option<String>,
+    /// Position of the column in its table
+    #[serde(default)]
+    pub ordinal_position: Option<i32>,
 }
 
 impl From<&ColumnInfo> for ColumnDiffInfo {
@@ -80,10 +104,20 @@ impl From<&ColumnInfo> for ColumnDiffInfo {
             is_nullable: col.is_nullable,
             is_primary_key: col.is_primary_key,
             default_value: col.default_value.clone(),
+            ordinal_position: col.ordinal_position,
         }
     }
 }
 
+/// Options controlling what `compare_schemas_with` reports
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CompareOptions {
+    /// Report columns whose position changed relative to the other columns
+    /// of the table as `Modified` (off by default, as reorders are usually
+    /// harmless)
+    pub detect_reorder: bool,
+}
+
 /// Difference in a foreign key
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ForeignKeyDiff {
@@ -91,8 +125,43 @@ pub struct ForeignKeyDiff {
     pub change_type: ChangeType,
     pub source_table: String,
     pub target_table: String,
-    /// Full FK info for added/removed
+    /// Full FK info (the compare version for modified FKs)
     pub fk_info: Option<ForeignKey>,
+    /// Base FK definition (None if the FK was added)
+    #[serde(default)]
+    pub base_fk: Option<ForeignKey>,
+    /// Compare FK definition (None if the FK was removed)
+    #[serde(default)]
+    pub compare_fk: Option<ForeignKey>,
+}
+
+/// Difference in an enum type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnumTypeDiff {
+    pub schema_name: String,
+    pub type_name: String,
+    pub change_type: ChangeType,
+    /// Values only present in the compare dump
+    pub added_values: Vec<String>,
+    /// Values only present in the base dump
+    pub removed_values: Vec<String>,
+    /// Whether values present in both dumps sort in a different order
+    pub reordered: bool,
+    /// Base values in sort order (None if the type was added)
+    pub base_values: Option<Vec<String>>,
+    /// Compare values in sort order (None if the type was removed)
+    pub compare_values: Option<Vec<String>>,
+}
+
+/// A sequence present in only one of the dumps
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SequenceDiff {
+    pub schema_name: String,
+    pub sequence_name: String,
+    /// `Added` or `Removed`
+    pub change_type: ChangeType,
+    /// The sequence as it exists in the dump that has it
+    pub sequence: SequenceInfo,
 }
 
 /// Complete diff result between two schema graphs
@@ -101,6 +170,39 @@ pub struct SchemaDiff {
     pub summary: DiffSummary,
     pub table_diffs: Vec<TableDiff>,
     pub fk_diffs: Vec<ForeignKeyDiff>,
+    #[serde(default)]
+    pub enum_diffs: Vec<EnumTypeDiff>,
+    #[serde(default)]
+    pub sequence_diffs: Vec<SequenceDiff>,
+}
+
+impl SchemaDiff {
+    /// Sort every diff list into a deterministic order
+    ///
+    /// Tables by schema and name (columns by name within each), foreign keys
+    /// by constraint name, then source and target table, enum types and
+    /// sequences by schema and name.
+    pub fn sort(&mut self) {
+        self.table_diffs
+            .sort_by(|a, b| (&a.schema_name, &a.table_name).cmp(&(&b.schema_name, &b.table_name)));
+        for table in &mut self.table_diffs {
+            table
+                .column_diffs
+                .sort_by(|a, b| a.column_name.cmp(&b.column_name));
+        }
+        self.fk_diffs.sort_by(|a, b| {
+            (&a.constraint_name, &a.source_table, &a.target_table).cmp(&(
+                &b.constraint_name,
+                &b.source_table,
+                &b.target_table,
+            ))
+        });
+        self.enum_diffs
+            .sort_by(|a, b| (&a.schema_name, &a.type_name).cmp(&(&b.schema_name, &b.type_name)));
+        self.sequence_diffs.sort_by(|a, b| {
+            (&a.schema_name, &a.sequence_name).cmp(&(&b.schema_name, &b.sequence_name))
+        });
+    }
 }
 
 /// Row-level diff for a specific table
@@ -120,6 +222,47 @@ pub struct TableDataDiff {
     pub sample_modified: Vec<RowModification>,
 }
 
+/// Row count change of one table between the two dumps
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RowCountChange {
+    pub schema_name: String,
+    pub table_name: String,
+    /// Row count in base dump (None if table was added)
+    pub base_row_count: Option<i64>,
+    /// Row count in compare dump (None if table was removed)
+    pub compare_row_count: Option<i64>,
+    /// `compare_row_count - base_row_count`, counting a missing side as 0
+    pub delta: i64,
+}
+
+/// The `n` tables whose row count changed the most, by absolute delta
+///
+/// Largest change first, ties by schema and table name; tables whose row
+/// count did not change are left out. Deltas add up the same way as
+/// [`DiffSummary::row_count_change`].
+pub fn biggest_row_movers(diff: &SchemaDiff, n: usize) -> Vec<RowCountChange> {
+    let mut movers: Vec<RowCountChange> = diff
+        .table_diffs
+        .iter()
+        .map(|table| RowCountChange {
+            schema_name: table.schema_name.clone(),
+            table_name: table.table_name.clone(),
+            base_row_count: table.base_row_count,
+            compare_row_count: table.compare_row_count,
+            delta: table.compare_row_count.unwrap_or(0) - table.base_row_count.unwrap_or(0),
+        })
+        .filter(|change| change.delta != 0)
+        .collect();
+    movers.sort_by(|a, b| {
+        b.delta
+            .unsigned_abs()
+            .cmp(&a.delta.unsigned_abs())
+            .then_with(|| (&a.schema_name, &a.table_name).cmp(&(&b.schema_name, &b.table_name)))
+    });
+    movers.truncate(n);
+    movers
+}
+
 /// A single row modification
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RowModification {
@@ -131,6 +274,30 @@ pub struct RowModification {
 
 /// Compare two schema graphs and return differences
 pub fn compare_schemas(base: &SchemaGraph, compare: &SchemaGraph) -> SchemaDiff {
+    compare_schemas_with(base, compare, &CompareOptions::default())
+}
+
+/// Compare a schema graph with the database `db_name` reached through
+/// `adapter`, e.g. a live database
+///
+/// The database is introspected with [`DbAdapter::build_schema_graph`] and
+/// becomes the compare side of the diff.
+pub async fn compare_with_database<A: DbAdapter + ?Sized>(
+    base: &SchemaGraph,
+    adapter: &A,
+    db_name: &str,
+    options: &CompareOptions,
+) -> Result<SchemaDiff> {
+    let compare = adapter.build_schema_graph(db_name).await?;
+    Ok(compare_schemas_with(base, &compare, options))
+}
+
+/// Compare two schema graphs with explicit options
+pub fn compare_schemas_with(
+    base: &SchemaGraph,
+    compare: &SchemaGraph,
+    options: &CompareOptions,
+) -> SchemaDiff {
     let mut summary = DiffSummary::default();
     let mut table_diffs = Vec::new();
     let mut fk_diffs = Vec::new();
@@ -210,7 +377,7 @@ pub fn compare_schemas(base: &SchemaGraph, compare: &SchemaGraph) -> SchemaDiff
         let base_table = base_tables[key];
         let compare_table = compare_tables[key];
 
-        let column_diffs = compare_columns(&base_table.columns, &compare_table.columns);
+        let column_diffs = compare_columns(&base_table.columns, &compare_table.columns, options);
 
         let row_diff = compare_table.estimated_row_count - base_table.estimated_row_count;
         summary.row_count_change += row_diff;
@@ -274,6 +441,8 @@ pub fn compare_schemas(base: &SchemaGraph, compare: &SchemaGraph) -> SchemaDiff
             source_table: format!("{}.{}", fk.source_schema, fk.source_table),
             target_table: format!("{}.{}", fk.target_schema, fk.target_table),
             fk_info: Some(fk.clone()),
+            base_fk: None,
+            compare_fk: Some(fk.clone()),
         });
     }
 
@@ -287,9 +456,33 @@ pub fn compare_schemas(base: &SchemaGraph, compare: &SchemaGraph) -> SchemaDiff
             source_table: format!("{}.{}", fk.source_schema, fk.source_table),
             target_table: format!("{}.{}", fk.target_schema, fk.target_table),
             fk_info: Some(fk.clone()),
+            base_fk: Some(fk.clone()),
+            compare_fk: None,
         });
     }
 
+    // Modified FKs
+    for name in base_fk_names.intersection(&compare_fk_names) {
+        let base_fk = base_fks[name];
+        let compare_fk = compare_fks[name];
+
+        if is_foreign_key_modified(base_fk, compare_fk) {
+            summary.fk_modified += 1;
+            fk_diffs.push(ForeignKeyDiff {
+                constraint_name: compare_fk.constraint_name.clone(),
+                change_type: ChangeType::Modified,
+                source_table: format!("{}.{}", compare_fk.source_schema, compare_fk.source_table),
+                target_table: format!("{}.{}", compare_fk.target_schema, compare_fk.target_table),
+                fk_info: Some(compare_fk.clone()),
+                base_fk: Some(base_fk.clone()),
+                compare_fk: Some(compare_fk.clone()),
+            });
+        }
+    }
+
+    let enum_diffs = compare_enum_types(&base.enum_types, &compare.enum_types, &mut summary);
+    let sequence_diffs = compare_sequences(&base.sequences, &compare.sequences, &mut summary);
+
     // Sort diffs for consistent output
     table_diffs
         .sort_by(|a, b| (&a.schema_name, &a.table_name).cmp(&(&b.schema_name, &b.table_name)));
@@ -299,11 +492,425 @@ pub fn compare_schemas(base: &SchemaGraph, compare: &SchemaGraph) -> SchemaDiff
         summary,
         table_diffs,
         fk_diffs,
+        enum_diffs,
+        sequence_diffs,
+    }
+}
+
+/// Find sequences added or removed between the dumps, counting them in
+/// `summary`
+///
+/// Current values are expected to differ between dumps and are not compared.
+fn compare_sequences(
+    base: &[SequenceInfo],
+    compare: &[SequenceInfo],
+    summary: &mut DiffSummary,
+) -> Vec<SequenceDiff> {
+    let key = |s: &SequenceInfo| (s.schema.clone(), s.name.clone());
+    let base_keys: HashSet<(String, String)> = base.iter().map(key).collect();
+    let compare_keys: HashSet<(String, String)> = compare.iter().map(key).collect();
+
+    let added = compare
+        .iter()
+        .filter(|s| !base_keys.contains(&key(s)))
+        .map(|s| (ChangeType::Added, s));
+    let removed = base
+        .iter()
+        .filter(|s| !compare_keys.contains(&key(s)))
+        .map(|s| (ChangeType::Removed, s));
+
+    let mut diffs: Vec<SequenceDiff> = added
+        .chain(removed)
+        .map(|(change_type, sequence)| {
+            match change_type {
+                ChangeType::Added => summary.sequences_added += 1,
+                _ => summary.sequences_removed += 1,
+            }
+            SequenceDiff {
+                schema_name: sequence.schema.clone(),
+                sequence_name: sequence.name.clone(),
+                change_type,
+                sequence: sequence.clone(),
+            }
+        })
+        .collect();
+
+    diffs.sort_by(|a, b| {
+        (&a.schema_name, &a.sequence_name).cmp(&(&b.schema_name, &b.sequence_name))
+    });
+    diffs
+}
+
+/// Compare enum types by schema and name, counting changes in `summary`
+fn compare_enum_types(
+    base: &[EnumType],
+    compare: &[EnumType],
+    summary: &mut DiffSummary,
+) -> Vec<EnumTypeDiff> {
+    let key = |e: &EnumType| (e.schema.clone(), e.name.clone());
+    let base_types: HashMap<(String, String), &EnumType> =
+        base.iter().map(|e| (key(e), e)).collect();
+    let compare_types: HashMap<(String, String), &EnumType> =
+        compare.iter().map(|e| (key(e), e)).collect();
+
+    let mut diffs = Vec::new();
+
+    for enum_type in compare {
+        if !base_types.contains_key(&key(enum_type)) {
+            summary.enums_added += 1;
+            diffs.push(EnumTypeDiff {
+                schema_name: enum_type.schema.clone(),
+                type_name: enum_type.name.clone(),
+                change_type: ChangeType::Added,
+                added_values: enum_type.values.clone(),
+                removed_values: vec![],
+                reordered: false,
+                base_values: None,
+                compare_values: Some(enum_type.values.clone()),
+            });
+        }
+    }
+
+    for base_type in base {
+        let Some(compare_type) = compare_types.get(&key(base_type)) else {
+            summary.enums_removed += 1;
+            diffs.push(EnumTypeDiff {
+                schema_name: base_type.schema.clone(),
+                type_name: base_type.name.clone(),
+                change_type: ChangeType::Removed,
+                added_values: vec![],
+                removed_values: base_type.values.clone(),
+                reordered: false,
+                base_values: Some(base_type.values.clone()),
+                compare_values: None,
+            });
+            continue;
+        };
+
+        let base_values: HashSet<&String> = base_type.values.iter().collect();
+        let compare_values: HashSet<&String> = compare_type.values.iter().collect();
+        let added_values: Vec<String> = compare_type
+            .values
+            .iter()
+            .filter(|v| !base_values.contains(v))
+            .cloned()
+            .collect();
+        let removed_values: Vec<String> = base_type
+            .values
+            .iter()
+            .filter(|v| !compare_values.contains(v))
+            .cloned()
+            .collect();
+        // Only the relative order of shared values matters; inserting a new
+        // value does not reorder the existing ones
+        let reordered = base_type
+            .values
+            .iter()
+            .filter(|v| compare_values.contains(v))
+            .ne(compare_type
+                .values
+                .iter()
+                .filter(|v| base_values.contains(v)));
+
+        if added_values.is_empty() && removed_values.is_empty() && !reordered {
+            continue;
+        }
+
+        summary.enums_modified += 1;
+        diffs.push(EnumTypeDiff {
+            schema_name: base_type.schema.clone(),
+            type_name: base_type.name.clone(),
+            change_type: ChangeType::Modified,
+            added_values,
+            removed_values,
+            reordered,
+            base_values: Some(base_type.values.clone()),
+            compare_values: Some(compare_type.values.clone()),
+        });
+    }
+
+    diffs.sort_by(|a, b| (&a.schema_name, &a.type_name).cmp(&(&b.schema_name, &b.type_name)));
+    diffs
+}
+
+/// Generate DDL that migrates the base schema to the compare schema
+///
+/// Statements are ordered so each one only depends on objects that already
+/// exist: changed/removed FKs are dropped first, then enum types are created
+/// or extended, sequences and tables are created and columns altered, then
+/// new FKs are added, and removed tables, sequences and enum types are dropped
+/// last. Column types come from introspection (`character varying` without
+/// a length, for example), so review the script before running it.
+pub fn generate_migration_sql(diff: &SchemaDiff) -> String {
+    let mut sql = String::from(
+        "-- Migration generated by PgDumpLens from a schema diff\n\
+         -- Review before running: types are as introspected and data is not migrated\n",
+    );
+
+    let qualified =
+        |schema: &str, table: &str| format!("{}.{}", quote_ident(schema), quote_ident(table));
+
+    // 1. Drop foreign keys that are removed or about to be redefined
+    let dropped_fks: Vec<&ForeignKey> = diff
+        .fk_diffs
+        .iter()
+        .filter(|d| d.change_type != ChangeType::Added)
+        .filter_map(|d| d.base_fk.as_ref().or(d.fk_info.as_ref()))
+        .collect();
+    if !dropped_fks.is_empty() {
+        sql.push_str("\n-- Drop foreign keys\n");
+    }
+    for fk in dropped_fks {
+        sql.push_str(&format!(
+            "ALTER TABLE {} DROP CONSTRAINT {};\n",
+            qualified(&fk.source_schema, &fk.source_table),
+            quote_ident(&fk.constraint_name)
+        ));
+    }
+
+    // 2. Create added enum types and add new values before columns use them
+    for enum_diff in &diff.enum_diffs {
+        let name = qualified(&enum_diff.schema_name, &enum_diff.type_name);
+        let Some(values) = &enum_diff.compare_values else {
+            continue;
+        };
+        if enum_diff.change_type == ChangeType::Added {
+            let labels: Vec<String> = values.iter().map(|v| quote_literal(v)).collect();
+            sql.push_str(&format!(
+                "\nCREATE TYPE {} AS ENUM ({});\n",
+                name,
+                labels.join(", ")
+            ));
+            continue;
+        }
+
+        sql.push_str(&format!("\n-- {}\n", name));
+        for value in &enum_diff.added_values {
+            // Values are added in order, so the previous one always exists
+            let position = values.iter().position(|v| v == value).unwrap_or_default();
+            let placement = match position {
+                0 => values
+                    .iter()
+                    .find(|v| !enum_diff.added_values.contains(v))
+                    .map(|next| format!(" BEFORE {}", quote_literal(next))),
+                i => Some(format!(" AFTER {}", quote_literal(&values[i - 1]))),
+            };
+            sql.push_str(&format!(
+                "ALTER TYPE {} ADD VALUE {}{};\n",
+                name,
+                quote_literal(value),
+                placement.unwrap_or_default()
+            ));
+        }
+        if !enum_diff.removed_values.is_empty() || enum_diff.reordered {
+            sql.push_str(&format!(
+                "-- Values of {} were removed or reordered; PostgreSQL cannot drop enum values, recreate the type manually\n",
+                name
+            ));
+        }
+    }
+
+    // 3. Create added sequences, which column defaults may reference
+    let added_sequences: Vec<&SequenceDiff> = diff
+        .sequence_diffs
+        .iter()
+        .filter(|s| s.change_type == ChangeType::Added)
+        .collect();
+    if !added_sequences.is_empty() {
+        sql.push_str("\n-- Create sequences\n");
+    }
+    for seq in added_sequences {
+        sql.push_str(&format!(
+            "CREATE SEQUENCE IF NOT EXISTS {} INCREMENT BY {};\n",
+            qualified(&seq.schema_name, &seq.sequence_name),
+            seq.sequence.increment
+        ));
+    }
+
+    // 4. Create added tables
+    for table in diff
+        .table_diffs
+        .iter()
+        .filter(|t| t.change_type == ChangeType::Added)
+    {
+        let mut lines: Vec<String> = table
+            .column_diffs
+            .iter()
+            .filter_map(|c| c.compare_info.as_ref().map(|info| (c, info)))
+            .map(|(c, info)| format!("    {}", column_definition(&c.column_name, info)))
+            .collect();
+        let pk: Vec<String> = table
+            .column_diffs
+            .iter()
+            .filter(|c| c.compare_info.as_ref().is_some_and(|i| i.is_primary_key))
+            .map(|c| quote_ident(&c.column_name))
+            .collect();
+        if !pk.is_empty() {
+            lines.push(format!("    PRIMARY KEY ({})", pk.join(", ")));
+        }
+        sql.push_str(&format!(
+            "\nCREATE TABLE {} (\n{}\n);\n",
+            qualified(&table.schema_name, &table.table_name),
+            lines.join(",\n")
+        ));
+    }
+
+    // 5. Alter columns of modified tables
+    for table in diff
+        .table_diffs
+        .iter()
+        .filter(|t| t.change_type == ChangeType::Modified && !t.column_diffs.is_empty())
+    {
+        let name = qualified(&table.schema_name, &table.table_name);
+        sql.push_str(&format!("\n-- {}\n", name));
+        for col in &table.column_diffs {
+            let column = quote_ident(&col.column_name);
+            match (col.change_type, &col.base_info, &col.compare_info) {
+                (ChangeType::Added, _, Some(info)) => sql.push_str(&format!(
+                    "ALTER TABLE {} ADD COLUMN {};\n",
+                    name,
+                    column_definition(&col.column_name, info)
+                )),
+                (ChangeType::Removed, _, _) => {
+                    sql.push_str(&format!("ALTER TABLE {} DROP COLUMN {};\n", name, column))
+                }
+                (ChangeType::Modified, Some(base), Some(compare)) => {
+                    if base.data_type != compare.data_type {
+                        sql.push_str(&format!(
+                            "ALTER TABLE {} ALTER COLUMN {} TYPE {} USING {}::{};\n",
+                            name, column, compare.data_type, column, compare.data_type
+                        ));
+                    }
+                    if base.default_value != compare.default_value {
+                        match &compare.default_value {
+                            Some(default) => sql.push_str(&format!(
+                                "ALTER TABLE {} ALTER COLUMN {} SET DEFAULT {};\n",
+                                name, column, default
+                            )),
+                            None => sql.push_str(&format!(
+                                "ALTER TABLE {} ALTER COLUMN {} DROP DEFAULT;\n",
+                                name, column
+                            )),
+                        }
+                    }
+                    if base.is_nullable != compare.is_nullable {
+                        sql.push_str(&format!(
+                            "ALTER TABLE {} ALTER COLUMN {} {} NOT NULL;\n",
+                            name,
+                            column,
+                            if compare.is_nullable { "DROP" } else { "SET" }
+                        ));
+                    }
+                    if base.ordinal_position != compare.ordinal_position {
+                        sql.push_str(&format!(
+                            "-- {} moved from position {} to {}; PostgreSQL cannot reorder columns in place\n",
+                            column,
+                            base.ordinal_position.unwrap_or_default(),
+                            compare.ordinal_position.unwrap_or_default()
+                        ));
+                    }
+                    if base.is_primary_key != compare.is_primary_key {
+                        sql.push_str(&format!(
+                            "-- Primary key membership of {} changed; update the constraint manually\n",
+                            column
+                        ));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // 6. Add new and redefined foreign keys, now that every table exists
+    let added_fks: Vec<&ForeignKey> = diff
+        .fk_diffs
+        .iter()
+        .filter(|d| d.change_type != ChangeType::Removed)
+        .filter_map(|d| d.compare_fk.as_ref().or(d.fk_info.as_ref()))
+        .collect();
+    if !added_fks.is_empty() {
+        sql.push_str("\n-- Add foreign keys\n");
+    }
+    for fk in added_fks {
+        let columns = |cols: &[String]| {
+            cols.iter()
+                .map(|c| quote_ident(c))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        sql.push_str(&format!(
+            "ALTER TABLE {} ADD CONSTRAINT {} FOREIGN KEY ({}) REFERENCES {} ({}) ON DELETE {} ON UPDATE {};\n",
+            qualified(&fk.source_schema, &fk.source_table),
+            quote_ident(&fk.constraint_name),
+            columns(&fk.source_columns),
+            qualified(&fk.target_schema, &fk.target_table),
+            columns(&fk.target_columns),
+            fk.on_delete,
+            fk.on_update
+        ));
+    }
+
+    // 7. Drop removed tables (their foreign keys are already gone)
+    let removed: Vec<&TableDiff> = diff
+        .table_diffs
+        .iter()
+        .filter(|t| t.change_type == ChangeType::Removed)
+        .collect();
+    if !removed.is_empty() {
+        sql.push_str("\n-- Drop tables\n");
+    }
+    for table in removed.into_iter().rev() {
+        sql.push_str(&format!(
+            "DROP TABLE {};\n",
+            qualified(&table.schema_name, &table.table_name)
+        ));
+    }
+
+    // 8. Drop removed sequences; those owned by a dropped table are gone already
+    for seq in diff
+        .sequence_diffs
+        .iter()
+        .filter(|s| s.change_type == ChangeType::Removed)
+    {
+        sql.push_str(&format!(
+            "DROP SEQUENCE IF EXISTS {};\n",
+            qualified(&seq.schema_name, &seq.sequence_name)
+        ));
+    }
+
+    // 9. Drop removed enum types, now that no table uses them
+    for enum_diff in diff
+        .enum_diffs
+        .iter()
+        .filter(|e| e.change_type == ChangeType::Removed)
+    {
+        sql.push_str(&format!(
+            "DROP TYPE {};\n",
+            qualified(&enum_diff.schema_name, &enum_diff.type_name)
+        ));
     }
+
+    sql
+}
+
+/// Column definition for CREATE TABLE / ADD COLUMN
+fn column_definition(name: &str, info: &ColumnDiffInfo) -> String {
+    let mut definition = format!("{} {}", quote_ident(name), info.data_type);
+    if let Some(default) = &info.default_value {
+        definition.push_str(&format!(" DEFAULT {}", default));
+    }
+    if !info.is_nullable {
+        definition.push_str(" NOT NULL");
+    }
+    definition
 }
 
 /// Compare columns between two tables
-fn compare_columns(base: &[ColumnInfo], compare: &[ColumnInfo]) -> Vec<ColumnDiff> {
+fn compare_columns(
+    base: &[ColumnInfo],
+    compare: &[ColumnInfo],
+    options: &CompareOptions,
+) -> Vec<ColumnDiff> {
     let mut diffs = Vec::new();
 
     let base_cols: HashMap<&str, &ColumnInfo> = base.iter().map(|c| (c.name.as_str(), c)).collect();
@@ -335,12 +942,22 @@ fn compare_columns(base: &[ColumnInfo], compare: &[ColumnInfo]) -> Vec<ColumnDif
         });
     }
 
+    // Positions among the columns both tables share, so adding or dropping a
+    // column does not make every later column look moved
+    let (base_rank, compare_rank) = if options.detect_reorder {
+        let shared: HashSet<&str> = base_names.intersection(&compare_names).cloned().collect();
+        (shared_rank(base, &shared), shared_rank(compare, &shared))
+    } else {
+        (HashMap::new(), HashMap::new())
+    };
+
     // Modified columns
     for name in base_names.intersection(&compare_names) {
         let base_col = base_cols[name];
         let compare_col = compare_cols[name];
+        let moved = base_rank.get(name) != compare_rank.get(name);
 
-        if is_column_modified(base_col, compare_col) {
+        if is_column_modified(base_col, compare_col) || moved {
             diffs.push(ColumnDiff {
                 column_name: base_col.name.clone(),
                 change_type: ChangeType::Modified,
@@ -354,6 +971,20 @@ fn compare_columns(base: &[ColumnInfo], compare: &[ColumnInfo]) -> Vec<ColumnDif
     diffs
 }
 
+/// Rank of each shared column by position among the shared columns only
+fn shared_rank<'a>(columns: &'a [ColumnInfo], shared: &HashSet<&str>) -> HashMap<&'a str, usize> {
+    let mut ordered: Vec<&ColumnInfo> = columns
+        .iter()
+        .filter(|c| shared.contains(c.name.as_str()))
+        .collect();
+    ordered.sort_by_key(|c| c.ordinal_position);
+    ordered
+        .iter()
+        .enumerate()
+        .map(|(rank, c)| (c.name.as_str(), rank))
+        .collect()
+}
+
 /// Check if a column has been modified
 fn is_column_modified(base: &ColumnInfo, compare: &ColumnInfo) -> bool {
     base.data_type != compare.data_type
@@ -362,9 +993,20 @@ fn is_column_modified(base: &ColumnInfo, compare: &ColumnInfo) -> bool {
         || base.default_value != compare.default_value
 }
 
+/// Check if a foreign key has been modified
+fn is_foreign_key_modified(base: &ForeignKey, compare: &ForeignKey) -> bool {
+    base.on_delete != compare.on_delete
+        || base.on_update != compare.on_update
+        || base.source_columns != compare.source_columns
+        || base.target_columns != compare.target_columns
+        || (&base.target_schema, &base.target_table)
+            != (&compare.target_schema, &compare.target_table)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::domain::FkAction;
 
     fn make_column(name: &str, data_type: &str) -> ColumnInfo {
         ColumnInfo {
@@ -373,6 +1015,38 @@ mod tests {
             is_nullable: true,
             is_primary_key: false,
             default_value: None,
+            comment: None,
+            ordinal_position: None,
+            is_identity: false,
+            identity_generation: None,
+            generation_expression: None,
+        }
+    }
+
+    fn pk_column(name: &str) -> ColumnInfo {
+        ColumnInfo {
+            is_nullable: false,
+            is_primary_key: true,
+            ..make_column(name, "bigint")
+        }
+    }
+
+    fn make_fk(
+        name: &str,
+        source: (&str, &str, &str),
+        target: (&str, &str, &str),
+        on_delete: FkAction,
+    ) -> ForeignKey {
+        ForeignKey {
+            constraint_name: name.to_string(),
+            source_schema: source.0.to_string(),
+            source_table: source.1.to_string(),
+            source_columns: vec![source.2.to_string()],
+            target_schema: target.0.to_string(),
+            target_table: target.1.to_string(),
+            target_columns: vec![target.2.to_string()],
+            on_delete,
+            on_update: FkAction::NoAction,
         }
     }
 
@@ -382,6 +1056,10 @@ mod tests {
             table_name: name.to_string(),
             estimated_row_count: rows,
             columns,
+            comment: None,
+            is_partition: false,
+            partition_of: None,
+            partition_strategy: None,
         }
     }
 
@@ -395,6 +1073,11 @@ mod tests {
                 100,
             )],
             foreign_keys: vec![],
+            indexes: vec![],
+            views: vec![],
+            triggers: vec![],
+            enum_types: vec![],
+            sequences: vec![],
         };
 
         let compare = SchemaGraph {
@@ -403,6 +1086,11 @@ mod tests {
                 make_table("public", "orders", vec![make_column("id", "bigint")], 50),
             ],
             foreign_keys: vec![],
+            indexes: vec![],
+            views: vec![],
+            triggers: vec![],
+            enum_types: vec![],
+            sequences: vec![],
         };
 
         let diff = compare_schemas(&base, &compare);
@@ -420,6 +1108,11 @@ mod tests {
                 make_table("public", "old_table", vec![make_column("id", "bigint")], 30),
             ],
             foreign_keys: vec![],
+            indexes: vec![],
+            views: vec![],
+            triggers: vec![],
+            enum_types: vec![],
+            sequences: vec![],
         };
 
         let compare = SchemaGraph {
@@ -430,6 +1123,11 @@ mod tests {
                 100,
             )],
             foreign_keys: vec![],
+            indexes: vec![],
+            views: vec![],
+            triggers: vec![],
+            enum_types: vec![],
+            sequences: vec![],
         };
 
         let diff = compare_schemas(&base, &compare);
@@ -439,12 +1137,59 @@ mod tests {
         assert_eq!(diff.summary.row_count_change, -30);
     }
 
+    #[test]
+    fn test_biggest_row_movers_orders_by_absolute_delta() {
+        let column = || vec![make_column("id", "bigint")];
+        let base = SchemaGraph {
+            tables: vec![
+                make_table("public", "events", column(), 1_000),
+                make_table("public", "sessions", column(), 5_000_000),
+                make_table("public", "users", column(), 100),
+                make_table("public", "unchanged", column(), 42),
+                make_table("public", "old_table", column(), 30),
+            ],
+            ..Default::default()
+        };
+        let compare = SchemaGraph {
+            tables: vec![
+                make_table("public", "events", column(), 1_001_000),
+                make_table("public", "sessions", column(), 3_000_000),
+                make_table("public", "users", column(), 130),
+                make_table("public", "unchanged", column(), 42),
+                make_table("public", "new_table", column(), 30),
+            ],
+            ..Default::default()
+        };
+        let diff = compare_schemas(&base, &compare);
+
+        let movers = biggest_row_movers(&diff, 10);
+        let order: Vec<(&str, i64)> = movers
+            .iter()
+            .map(|m| (m.table_name.as_str(), m.delta))
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                ("sessions", -2_000_000),
+                ("events", 1_000_000),
+                ("new_table", 30),
+                ("old_table", -30),
+                ("users", 30),
+            ]
+        );
+        assert_eq!(movers[2].base_row_count, None);
+        assert_eq!(movers[3].compare_row_count, None);
+
+        let top = biggest_row_movers(&diff, 2);
+        assert_eq!(top, movers[..2]);
+    }
+
     #[test]
     fn test_compare_columns_added() {
         let base = vec![make_column("id", "bigint")];
         let compare = vec![make_column("id", "bigint"), make_column("email", "varchar")];
 
-        let diffs = compare_columns(&base, &compare);
+        let diffs = compare_columns(&base, &compare, &CompareOptions::default());
 
         assert_eq!(diffs.len(), 1);
         assert_eq!(diffs[0].column_name, "email");
@@ -459,6 +1204,11 @@ mod tests {
             is_nullable: true,
             is_primary_key: false,
             default_value: None,
+            comment: None,
+            ordinal_position: None,
+            is_identity: false,
+            identity_generation: None,
+            generation_expression: None,
         }];
 
         let compare = vec![ColumnInfo {
@@ -467,11 +1217,394 @@ mod tests {
             is_nullable: false,                    // Changed
             is_primary_key: false,
             default_value: Some("'active'".to_string()), // Added
+            comment: None,
+            ordinal_position: None,
+            is_identity: false,
+            identity_generation: None,
+            generation_expression: None,
         }];
 
-        let diffs = compare_columns(&base, &compare);
+        let diffs = compare_columns(&base, &compare, &CompareOptions::default());
 
         assert_eq!(diffs.len(), 1);
         assert_eq!(diffs[0].change_type, ChangeType::Modified);
     }
+
+    #[test]
+    fn test_compare_schemas_modified_fk_on_delete() {
+        let fk = ForeignKey {
+            constraint_name: "fk_orders_user".to_string(),
+            source_schema: "public".to_string(),
+            source_table: "orders".to_string(),
+            source_columns: vec!["user_id".to_string()],
+            target_schema: "public".to_string(),
+            target_table: "users".to_string(),
+            target_columns: vec!["id".to_string()],
+            on_delete: FkAction::Cascade,
+            on_update: FkAction::NoAction,
+        };
+        let mut changed = fk.clone();
+        changed.on_delete = FkAction::SetNull;
+
+        let base = SchemaGraph {
+            tables: vec![],
+            foreign_keys: vec![fk],
+            indexes: vec![],
+            views: vec![],
+            triggers: vec![],
+            enum_types: vec![],
+            sequences: vec![],
+        };
+        let compare = SchemaGraph {
+            tables: vec![],
+            foreign_keys: vec![changed],
+            indexes: vec![],
+            views: vec![],
+            triggers: vec![],
+            enum_types: vec![],
+            sequences: vec![],
+        };
+
+        let diff = compare_schemas(&base, &compare);
+        assert_eq!(diff.summary.fk_modified, 1);
+        assert_eq!(diff.summary.fk_added, 0);
+        assert_eq!(diff.summary.fk_removed, 0);
+        assert_eq!(diff.fk_diffs.len(), 1);
+        assert_eq!(diff.fk_diffs[0].change_type, ChangeType::Modified);
+        assert_eq!(
+            diff.fk_diffs[0].base_fk.as_ref().unwrap().on_delete,
+            FkAction::Cascade
+        );
+        assert_eq!(
+            diff.fk_diffs[0].compare_fk.as_ref().unwrap().on_delete,
+            FkAction::SetNull
+        );
+    }
+
+    #[test]
+    fn test_schema_diff_sort_is_deterministic() {
+        let base = SchemaGraph {
+            tables: vec![
+                make_table("public", "zeta", vec![make_column("id", "bigint")], 1),
+                make_table(
+                    "public",
+                    "alpha",
+                    vec![make_column("id", "bigint"), make_column("b", "text")],
+                    1,
+                ),
+            ],
+            ..Default::default()
+        };
+        let compare = SchemaGraph {
+            tables: vec![
+                make_table(
+                    "public",
+                    "alpha",
+                    vec![
+                        make_column("id", "bigint"),
+                        make_column("z", "text"),
+                        make_column("c", "text"),
+                    ],
+                    1,
+                ),
+                make_table("app", "mid", vec![], 1),
+            ],
+            ..Default::default()
+        };
+
+        let mut diff = compare_schemas(&base, &compare);
+        diff.sort();
+
+        let tables: Vec<(&str, &str)> = diff
+            .table_diffs
+            .iter()
+            .map(|t| (t.schema_name.as_str(), t.table_name.as_str()))
+            .collect();
+        assert_eq!(
+            tables,
+            vec![("app", "mid"), ("public", "alpha"), ("public", "zeta")]
+        );
+        let columns: Vec<&str> = diff.table_diffs[1]
+            .column_diffs
+            .iter()
+            .map(|c| c.column_name.as_str())
+            .collect();
+        assert_eq!(columns, vec!["b", "c", "z"]);
+
+        // Input order does not leak into the sorted result
+        let mut reversed = compare_schemas(&base, &compare);
+        reversed.table_diffs.reverse();
+        reversed.sort();
+        assert_eq!(
+            serde_json::to_string(&diff).unwrap(),
+            serde_json::to_string(&reversed).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_migration_sql_creates_added_table_before_its_fk() {
+        let base = SchemaGraph {
+            tables: vec![make_table("public", "users", vec![pk_column("id")], 1)],
+            ..Default::default()
+        };
+        let mut compare = base.clone();
+        compare.tables.push(make_table(
+            "public",
+            "orders",
+            vec![pk_column("id"), make_column("user_id", "bigint")],
+            1,
+        ));
+        compare.foreign_keys.push(make_fk(
+            "fk_orders_user",
+            ("public", "orders", "user_id"),
+            ("public", "users", "id"),
+            FkAction::Cascade,
+        ));
+
+        let sql = generate_migration_sql(&compare_schemas(&base, &compare));
+        assert!(sql.starts_with("-- Migration generated by PgDumpLens"));
+        assert!(sql.contains(
+            "CREATE TABLE \"public\".\"orders\" (\n    \"id\" bigint NOT NULL,\n    \"user_id\" bigint,\n    PRIMARY KEY (\"id\")\n);"
+        ));
+        let fk = "ALTER TABLE \"public\".\"orders\" ADD CONSTRAINT \"fk_orders_user\" FOREIGN KEY (\"user_id\") REFERENCES \"public\".\"users\" (\"id\") ON DELETE CASCADE ON UPDATE NO ACTION;";
+        assert!(sql.contains(fk));
+        assert!(sql.find("CREATE TABLE").unwrap() < sql.find(fk).unwrap());
+    }
+
+    #[test]
+    fn test_migration_sql_drops_column() {
+        let base = SchemaGraph {
+            tables: vec![make_table(
+                "public",
+                "users",
+                vec![pk_column("id"), make_column("legacy flag", "boolean")],
+                1,
+            )],
+            ..Default::default()
+        };
+        let compare = SchemaGraph {
+            tables: vec![make_table("public", "users", vec![pk_column("id")], 1)],
+            ..Default::default()
+        };
+
+        let sql = generate_migration_sql(&compare_schemas(&base, &compare));
+        assert!(sql.contains("ALTER TABLE \"public\".\"users\" DROP COLUMN \"legacy flag\";"));
+        assert!(!sql.contains("CREATE TABLE"));
+    }
+
+    #[test]
+    fn test_migration_sql_changes_column_type() {
+        let base = SchemaGraph {
+            tables: vec![make_table(
+                "public",
+                "users",
+                vec![make_column("age", "integer")],
+                1,
+            )],
+            ..Default::default()
+        };
+        let compare = SchemaGraph {
+            tables: vec![make_table(
+                "public",
+                "users",
+                vec![make_column("age", "bigint")],
+                1,
+            )],
+            ..Default::default()
+        };
+
+        let sql = generate_migration_sql(&compare_schemas(&base, &compare));
+        assert!(sql.contains(
+            "ALTER TABLE \"public\".\"users\" ALTER COLUMN \"age\" TYPE bigint USING \"age\"::bigint;"
+        ));
+    }
+
+    #[test]
+    fn test_migration_sql_drops_fk_before_table() {
+        let mut base = SchemaGraph {
+            tables: vec![
+                make_table("public", "users", vec![pk_column("id")], 1),
+                make_table(
+                    "public",
+                    "sessions",
+                    vec![make_column("user_id", "bigint")],
+                    1,
+                ),
+            ],
+            ..Default::default()
+        };
+        base.foreign_keys.push(make_fk(
+            "fk_sessions_user",
+            ("public", "sessions", "user_id"),
+            ("public", "users", "id"),
+            FkAction::NoAction,
+        ));
+        let compare = SchemaGraph {
+            tables: vec![make_table("public", "users", vec![pk_column("id")], 1)],
+            ..Default::default()
+        };
+
+        let sql = generate_migration_sql(&compare_schemas(&base, &compare));
+        let drop_fk = sql.find("DROP CONSTRAINT \"fk_sessions_user\"").unwrap();
+        let drop_table = sql.find("DROP TABLE \"public\".\"sessions\";").unwrap();
+        assert!(drop_fk < drop_table);
+    }
+
+    fn positioned(name: &str, position: i32) -> ColumnInfo {
+        ColumnInfo {
+            ordinal_position: Some(position),
+            ..make_column(name, "text")
+        }
+    }
+
+    #[test]
+    fn test_compare_columns_detects_reorder_when_enabled() {
+        let base = vec![
+            positioned("id", 1),
+            positioned("name", 2),
+            positioned("email", 3),
+        ];
+        let compare = vec![
+            positioned("id", 1),
+            positioned("email", 2),
+            positioned("name", 3),
+        ];
+
+        assert!(compare_columns(&base, &compare, &CompareOptions::default()).is_empty());
+
+        let options = CompareOptions {
+            detect_reorder: true,
+        };
+        let diffs = compare_columns(&base, &compare, &options);
+        let names: Vec<&str> = diffs.iter().map(|d| d.column_name.as_str()).collect();
+        assert_eq!(names, vec!["email", "name"]);
+        assert!(diffs.iter().all(|d| d.change_type == ChangeType::Modified));
+        let email = &diffs[0];
+        assert_eq!(email.base_info.as_ref().unwrap().ordinal_position, Some(3));
+        assert_eq!(
+            email.compare_info.as_ref().unwrap().ordinal_position,
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn test_compare_columns_ignores_shift_from_dropped_column() {
+        let base = vec![
+            positioned("id", 1),
+            positioned("legacy", 2),
+            positioned("name", 3),
+        ];
+        let compare = vec![positioned("id", 1), positioned("name", 2)];
+
+        let options = CompareOptions {
+            detect_reorder: true,
+        };
+        let diffs = compare_columns(&base, &compare, &options);
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].column_name, "legacy");
+        assert_eq!(diffs[0].change_type, ChangeType::Removed);
+    }
+
+    fn enum_graph(values: &[&str]) -> SchemaGraph {
+        SchemaGraph {
+            enum_types: vec![EnumType {
+                schema: "public".to_string(),
+                name: "order_status".to_string(),
+                values: values.iter().map(|v| v.to_string()).collect(),
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_compare_schemas_enum_value_added() {
+        let base = enum_graph(&["pending", "shipped"]);
+        let compare = enum_graph(&["pending", "paid", "shipped"]);
+
+        let diff = compare_schemas(&base, &compare);
+
+        assert_eq!(diff.summary.enums_modified, 1);
+        assert_eq!(diff.enum_diffs.len(), 1);
+        let enum_diff = &diff.enum_diffs[0];
+        assert_eq!(enum_diff.change_type, ChangeType::Modified);
+        assert_eq!(enum_diff.added_values, vec!["paid"]);
+        assert!(enum_diff.removed_values.is_empty());
+        // Inserting a value does not reorder the existing ones
+        assert!(!enum_diff.reordered);
+
+        let sql = generate_migration_sql(&diff);
+        assert!(sql
+            .contains("ALTER TYPE \"public\".\"order_status\" ADD VALUE 'paid' AFTER 'pending';"));
+    }
+
+    #[test]
+    fn test_compare_schemas_enum_removed_and_reordered() {
+        let base = enum_graph(&["low", "high", "urgent"]);
+        let compare = enum_graph(&["high", "low"]);
+
+        let diff = compare_schemas(&base, &compare);
+        let enum_diff = &diff.enum_diffs[0];
+        assert_eq!(enum_diff.removed_values, vec!["urgent"]);
+        assert!(enum_diff.reordered);
+
+        let unchanged = compare_schemas(&base, &base);
+        assert!(unchanged.enum_diffs.is_empty());
+
+        let added = compare_schemas(&SchemaGraph::default(), &compare);
+        assert_eq!(added.summary.enums_added, 1);
+        assert!(generate_migration_sql(&added)
+            .contains("CREATE TYPE \"public\".\"order_status\" AS ENUM ('high', 'low');"));
+    }
+
+    fn sequence(schema: &str, name: &str, last_value: Option<i64>) -> SequenceInfo {
+        SequenceInfo {
+            schema: schema.to_string(),
+            name: name.to_string(),
+            owned_by: None,
+            last_value,
+            increment: 1,
+        }
+    }
+
+    #[test]
+    fn test_compare_schemas_sequences_added_and_removed() {
+        let base = SchemaGraph {
+            sequences: vec![
+                sequence("public", "users_id_seq", Some(10)),
+                sequence("public", "legacy_seq", None),
+            ],
+            ..Default::default()
+        };
+        let compare = SchemaGraph {
+            sequences: vec![
+                // A different current value is not a change
+                sequence("public", "users_id_seq", Some(250)),
+                sequence("billing", "invoice_no_seq", Some(1)),
+            ],
+            ..Default::default()
+        };
+
+        let diff = compare_schemas(&base, &compare);
+
+        assert_eq!(diff.summary.sequences_added, 1);
+        assert_eq!(diff.summary.sequences_removed, 1);
+        let changes: Vec<(&str, ChangeType)> = diff
+            .sequence_diffs
+            .iter()
+            .map(|d| (d.sequence_name.as_str(), d.change_type))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                ("invoice_no_seq", ChangeType::Added),
+                ("legacy_seq", ChangeType::Removed),
+            ]
+        );
+
+        let sql = generate_migration_sql(&diff);
+        assert!(sql.contains(
+            "CREATE SEQUENCE IF NOT EXISTS \"billing\".\"invoice_no_seq\" INCREMENT BY 1;"
+        ));
+        assert!(sql.contains("DROP SEQUENCE IF EXISTS \"public\".\"legacy_seq\";"));
+    }
 }
@@ -25,6 +25,9 @@ pub enum DumpStatus {
     Error,
     /// Marked for deletion
     Deleted,
+    /// Sandbox databases dropped after going unviewed; the upload is kept so
+    /// the dump can be restored again
+    Archived,
 }
 
 impl DumpStatus {
@@ -38,6 +41,88 @@ impl DumpStatus {
             DumpStatus::Ready => "READY",
             DumpStatus::Error => "ERROR",
             DumpStatus::Deleted => "DELETED",
+            DumpStatus::Archived => "ARCHIVED",
+        }
+    }
+}
+
+/// Format of an uploaded dump file, detected from its leading bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum DumpFormat {
+    /// pg_dump custom format archive (restored with pg_restore)
+    CustomFormat,
+    /// pg_dump directory format (`-Fd`): `toc.dat` plus one file per table
+    Directory,
+    /// Plain SQL script from pg_dump
+    PlainSql,
+    /// Plain SQL cluster dump from pg_dumpall
+    PgDumpall,
+    /// Not recognizable as a PostgreSQL dump
+    Unknown,
+}
+
+impl DumpFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DumpFormat::CustomFormat => "CUSTOM_FORMAT",
+            DumpFormat::Directory => "DIRECTORY",
+            DumpFormat::PlainSql => "PLAIN_SQL",
+            DumpFormat::PgDumpall => "PG_DUMPALL",
+            DumpFormat::Unknown => "UNKNOWN",
+        }
+    }
+
+    /// Parse the value stored in `dumps.dump_format`
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "CUSTOM_FORMAT" => Some(DumpFormat::CustomFormat),
+            "DIRECTORY" => Some(DumpFormat::Directory),
+            "PLAIN_SQL" => Some(DumpFormat::PlainSql),
+            "PG_DUMPALL" => Some(DumpFormat::PgDumpall),
+            "UNKNOWN" => Some(DumpFormat::Unknown),
+            _ => None,
+        }
+    }
+}
+
+/// How an upload is stored under `<upload_dir>/<dump id>/`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum DumpLayout {
+    /// A single dump file, `dump.sql`
+    SingleFile,
+    /// A directory-format dump extracted to `dump/`
+    Directory,
+    /// Several SQL files, concatenated into `dump.sql` in upload order
+    MultiFile,
+}
+
+impl DumpLayout {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DumpLayout::SingleFile => "SINGLE_FILE",
+            DumpLayout::Directory => "DIRECTORY",
+            DumpLayout::MultiFile => "MULTI_FILE",
+        }
+    }
+
+    /// Parse the value stored in `dumps.dump_layout`
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "SINGLE_FILE" => Some(DumpLayout::SingleFile),
+            "DIRECTORY" => Some(DumpLayout::Directory),
+            "MULTI_FILE" => Some(DumpLayout::MultiFile),
+            _ => None,
+        }
+    }
+
+    /// Name of the file or directory the worker restores, relative to the
+    /// dump's upload directory
+    pub fn dump_name(&self) -> &'static str {
+        match self {
+            DumpLayout::Directory => "dump",
+            DumpLayout::SingleFile | DumpLayout::MultiFile => "dump.sql",
         }
     }
 }
@@ -67,6 +152,72 @@ pub struct Dump {
     pub expires_at: DateTime<Utc>,
     /// Sandbox database name
     pub sandbox_db_name: Option<String>,
+    /// Wall-clock durations of each restore phase
+    #[serde(default)]
+    pub restore_timings: Option<RestoreTimings>,
+    /// Non-fatal pg_restore / psql messages, one per line
+    #[serde(default)]
+    pub restore_warnings: Option<String>,
+    /// Format detected when the upload completed
+    #[serde(default)]
+    pub dump_format: Option<DumpFormat>,
+    /// How the upload is stored; NULL for dumps uploaded as a single file
+    /// before layouts were recorded
+    #[serde(default)]
+    pub dump_layout: Option<DumpLayout>,
+    /// When the worker deleted the uploaded files after restoring them
+    #[serde(default)]
+    pub upload_purged_at: Option<DateTime<Utc>>,
+    /// When the sandbox databases were last queried
+    #[serde(default)]
+    pub last_viewed_at: Option<DateTime<Utc>>,
+}
+
+/// Result of restoring a dump into the sandbox
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RestoreOutcome {
+    /// Databases the data was restored into (several for pg_dumpall dumps)
+    pub databases: Vec<String>,
+    /// Non-fatal stderr lines from pg_restore / psql, in output order
+    pub warnings: Vec<String>,
+}
+
+/// Per-phase wall-clock durations (milliseconds) recorded by the worker
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RestoreTimings {
+    /// Decompressing the uploaded file (0 when it was not compressed)
+    #[serde(default)]
+    pub decompress_ms: u64,
+    /// Running pg_restore / psql
+    #[serde(default)]
+    pub restore_ms: u64,
+    /// Running ANALYZE on the restored database(s)
+    #[serde(default)]
+    pub analyze_ms: u64,
+    /// Building the schema graph(s)
+    #[serde(default)]
+    pub schema_graph_ms: u64,
+}
+
+/// Incremental progress of a restore or analysis job
+///
+/// Written by the worker to `dumps.progress` as the job moves through its
+/// phases and streamed to clients by the dump events endpoint.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DumpProgress {
+    /// Current phase: `decompress`, `restore` or `analyze`
+    pub phase: String,
+    /// Size of the file processed by the current phase, when known
+    #[serde(default)]
+    pub bytes_total: Option<u64>,
+    /// Databases finished so far during analysis
+    #[serde(default)]
+    pub databases_done: Option<usize>,
+    /// Databases to analyze in total
+    #[serde(default)]
+    pub databases_total: Option<usize>,
+    /// When the current phase started
+    pub phase_started_at: DateTime<Utc>,
 }
 
 /// Table information from schema introspection
@@ -76,6 +227,18 @@ pub struct TableInfo {
     pub table_name: String,
     pub estimated_row_count: i64,
     pub columns: Vec<ColumnInfo>,
+    /// `COMMENT ON TABLE` text
+    #[serde(default)]
+    pub comment: Option<String>,
+    /// Whether this is a partition of a declaratively partitioned table
+    #[serde(default)]
+    pub is_partition: bool,
+    /// Parent of a partition, as `schema.table`
+    #[serde(default)]
+    pub partition_of: Option<String>,
+    /// `range`, `list` or `hash` for partitioned (parent) tables
+    #[serde(default)]
+    pub partition_strategy: Option<String>,
 }
 
 /// Column information
@@ -86,6 +249,143 @@ pub struct ColumnInfo {
     pub is_nullable: bool,
     pub is_primary_key: bool,
     pub default_value: Option<String>,
+    /// `COMMENT ON COLUMN` text
+    #[serde(default)]
+    pub comment: Option<String>,
+    /// 1-based position in the table (`information_schema.columns.ordinal_position`)
+    #[serde(default)]
+    pub ordinal_position: Option<i32>,
+    /// Whether the column is `GENERATED ... AS IDENTITY`
+    #[serde(default)]
+    pub is_identity: bool,
+    /// `ALWAYS` or `BY DEFAULT` for identity columns
+    #[serde(default)]
+    pub identity_generation: Option<String>,
+    /// Expression of a generated (`GENERATED ALWAYS AS (...) STORED`) column
+    #[serde(default)]
+    pub generation_expression: Option<String>,
+}
+
+impl ColumnInfo {
+    /// Type category of the column; `enum_types` are the dump's enum types,
+    /// used to recognize enum columns
+    pub fn normalized_type(&self, enum_types: &[EnumType]) -> NormalizedType {
+        NormalizedType::from_pg_type(&self.data_type, enum_types)
+    }
+}
+
+/// Type category of a column, independent of how PostgreSQL spelled the type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NormalizedType {
+    /// `text`, `character varying`, `character` and `citext`
+    Text,
+    /// `smallint`, `integer` or `bigint`, by width in bits
+    Integer {
+        bits: u8,
+    },
+    /// `real` or `double precision`, by width in bits
+    Float {
+        bits: u8,
+    },
+    /// `numeric`, with its `(precision, scale)` modifier when constrained
+    Decimal {
+        precision: Option<u16>,
+        scale: Option<i16>,
+    },
+    Bool,
+    Uuid,
+    Date,
+    /// `time` with or without time zone
+    Time,
+    Timestamp {
+        with_time_zone: bool,
+    },
+    /// `json` or `jsonb`
+    Json,
+    /// Enum type, as unquoted `schema.name`
+    Enum(String),
+    /// Array of the element type; `Other` when only `ARRAY` is known
+    Array(Box<NormalizedType>),
+    Other,
+}
+
+impl NormalizedType {
+    /// Categorize a type name as reported by `information_schema` (`character
+    /// varying`, `ARRAY`, `schema.name` for user-defined types) or
+    /// `format_type` (`character varying(255)`, `integer[]`)
+    pub fn from_pg_type(data_type: &str, enum_types: &[EnumType]) -> Self {
+        let trimmed = data_type.trim();
+        if let Some(element) = trimmed.strip_suffix("[]") {
+            return Self::Array(Box::new(Self::from_pg_type(element, enum_types)));
+        }
+        if trimmed.eq_ignore_ascii_case("array") {
+            return Self::Array(Box::new(Self::Other));
+        }
+
+        let unquoted = trimmed.replace('"', "");
+        if let Some(e) = enum_types
+            .iter()
+            .find(|e| unquoted == format!("{}.{}", e.schema, e.name))
+        {
+            return Self::Enum(format!("{}.{}", e.schema, e.name));
+        }
+
+        // Drop modifiers such as the `(10,2)` of `numeric(10,2)` or the `(3)`
+        // of `timestamp(3) with time zone`
+        let lower = trimmed.to_ascii_lowercase();
+        let (base, modifiers) = match (lower.find('('), lower.find(')')) {
+            (Some(open), Some(close)) if open < close => (
+                format!("{}{}", lower[..open].trim_end(), &lower[close + 1..]),
+                Some(&lower[open + 1..close]),
+            ),
+            _ => (lower.clone(), None),
+        };
+
+        match base.as_str() {
+            "text" | "character varying" | "varchar" | "character" | "char" | "bpchar"
+            | "citext" => Self::Text,
+            "smallint" | "int2" => Self::Integer { bits: 16 },
+            "integer" | "int" | "int4" => Self::Integer { bits: 32 },
+            "bigint" | "int8" => Self::Integer { bits: 64 },
+            "real" | "float4" => Self::Float { bits: 32 },
+            "double precision" | "float8" => Self::Float { bits: 64 },
+            "numeric" | "decimal" => {
+                let mut parts = modifiers
+                    .into_iter()
+                    .flat_map(|m| m.split(','))
+                    .map(str::trim);
+                let precision = parts.next().and_then(|p| p.parse().ok());
+                // `numeric(p)` has scale 0
+                let scale = match parts.next() {
+                    Some(s) => s.parse().ok(),
+                    None => precision.map(|_| 0),
+                };
+                Self::Decimal { precision, scale }
+            }
+            "boolean" | "bool" => Self::Bool,
+            "uuid" => Self::Uuid,
+            "date" => Self::Date,
+            "time" | "time without time zone" | "time with time zone" | "timetz" => Self::Time,
+            "timestamp" | "timestamp without time zone" => Self::Timestamp {
+                with_time_zone: false,
+            },
+            "timestamptz" | "timestamp with time zone" => Self::Timestamp {
+                with_time_zone: true,
+            },
+            "json" | "jsonb" => Self::Json,
+            _ => Self::Other,
+        }
+    }
+
+    /// Whether values are text that can be matched as a string (arrays
+    /// when their elements are)
+    pub fn is_textual(&self) -> bool {
+        match self {
+            Self::Text | Self::Json | Self::Enum(_) => true,
+            Self::Array(element) => element.is_textual(),
+            _ => false,
+        }
+    }
 }
 
 /// Foreign key relationship
@@ -126,11 +426,106 @@ impl std::fmt::Display for FkAction {
     }
 }
 
+/// Index information
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexInfo {
+    pub name: String,
+    pub schema_name: String,
+    pub table_name: String,
+    /// Indexed columns (or expressions) in key order
+    pub columns: Vec<String>,
+    pub is_unique: bool,
+    pub is_primary: bool,
+    /// Access method (btree, hash, gin, gist, ...)
+    pub method: String,
+    /// `WHERE` clause of a partial index
+    #[serde(default)]
+    pub predicate: Option<String>,
+}
+
+/// View or materialized view information
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ViewInfo {
+    pub schema: String,
+    pub name: String,
+    /// The view's SELECT statement
+    pub definition: String,
+    pub is_materialized: bool,
+    pub columns: Vec<ColumnInfo>,
+}
+
+/// Table trigger, one entry per firing event
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TriggerInfo {
+    pub name: String,
+    pub schema: String,
+    pub table: String,
+    /// `BEFORE`, `AFTER` or `INSTEAD OF`
+    pub timing: String,
+    /// `INSERT`, `UPDATE`, `DELETE` or `TRUNCATE`
+    pub event: String,
+    /// The trigger's action, e.g. `EXECUTE FUNCTION audit_delete()`
+    pub function: String,
+}
+
+/// `CREATE TYPE ... AS ENUM` type and its labels in sort order
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnumType {
+    pub schema: String,
+    pub name: String,
+    pub values: Vec<String>,
+}
+
+/// Sequence with its owning column and current value
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SequenceInfo {
+    pub schema: String,
+    pub name: String,
+    /// Owning column as `schema.table.column` (serial and identity columns)
+    pub owned_by: Option<String>,
+    /// Last value handed out; None if `nextval` has never been called
+    pub last_value: Option<i64>,
+    pub increment: i64,
+}
+
+/// UNIQUE constraint of a table
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UniqueConstraint {
+    pub name: String,
+    /// Constrained columns in constraint order
+    pub columns: Vec<String>,
+    /// Whether every column is NOT NULL, so the columns identify a row
+    pub all_not_null: bool,
+}
+
+/// CHECK constraint of a table
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckConstraint {
+    pub name: String,
+    /// `CHECK (...)` clause as printed by `pg_get_constraintdef`
+    pub definition: String,
+}
+
 /// Schema graph containing all relationships
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SchemaGraph {
     pub tables: Vec<TableInfo>,
     pub foreign_keys: Vec<ForeignKey>,
+    /// Missing in schema graphs cached before indexes were introspected
+    #[serde(default)]
+    pub indexes: Vec<IndexInfo>,
+    /// Missing in schema graphs cached before views were introspected
+    #[serde(default)]
+    pub views: Vec<ViewInfo>,
+    /// Missing in schema graphs cached before triggers were introspected
+    #[serde(default)]
+    pub triggers: Vec<TriggerInfo>,
+    /// Missing in schema graphs cached before enum types were introspected
+    #[serde(default)]
+    pub enum_types: Vec<EnumType>,
+    /// Missing in schema graphs cached before sequences were introspected
+    #[serde(default)]
+    pub sequences: Vec<SequenceInfo>,
 }
 
 /// Relationship direction
@@ -156,6 +551,10 @@ pub struct RelationExplanation {
     pub sql_example: String,
     pub risk_score: u8,
     pub risk_reasons: Vec<String>,
+    /// Rows in the source table referencing the value (inbound, direct
+    /// relationships only; `None` when it could not be counted)
+    #[serde(default)]
+    pub referencing_count: Option<i64>,
 }
 
 #[cfg(test)]
@@ -169,6 +568,83 @@ mod tests {
         assert_eq!(DumpStatus::Error.as_str(), "ERROR");
     }
 
+    #[test]
+    fn test_normalized_type_mapping() {
+        let enums = vec![EnumType {
+            schema: "public".to_string(),
+            name: "mood".to_string(),
+            values: vec!["happy".to_string()],
+        }];
+        let cases = [
+            ("character varying", NormalizedType::Text),
+            ("character varying(255)", NormalizedType::Text),
+            ("text", NormalizedType::Text),
+            ("smallint", NormalizedType::Integer { bits: 16 }),
+            ("integer", NormalizedType::Integer { bits: 32 }),
+            ("bigint", NormalizedType::Integer { bits: 64 }),
+            ("double precision", NormalizedType::Float { bits: 64 }),
+            (
+                "numeric",
+                NormalizedType::Decimal {
+                    precision: None,
+                    scale: None,
+                },
+            ),
+            (
+                "numeric(10,2)",
+                NormalizedType::Decimal {
+                    precision: Some(10),
+                    scale: Some(2),
+                },
+            ),
+            ("boolean", NormalizedType::Bool),
+            ("uuid", NormalizedType::Uuid),
+            ("date", NormalizedType::Date),
+            (
+                "timestamp without time zone",
+                NormalizedType::Timestamp {
+                    with_time_zone: false,
+                },
+            ),
+            (
+                "timestamp(3) with time zone",
+                NormalizedType::Timestamp {
+                    with_time_zone: true,
+                },
+            ),
+            ("jsonb", NormalizedType::Json),
+            (
+                "public.mood",
+                NormalizedType::Enum("public.mood".to_string()),
+            ),
+            (
+                "integer[]",
+                NormalizedType::Array(Box::new(NormalizedType::Integer { bits: 32 })),
+            ),
+            (
+                "ARRAY",
+                NormalizedType::Array(Box::new(NormalizedType::Other)),
+            ),
+            ("bytea", NormalizedType::Other),
+            ("public.address", NormalizedType::Other),
+        ];
+        for (data_type, expected) in cases {
+            assert_eq!(
+                NormalizedType::from_pg_type(data_type, &enums),
+                expected,
+                "{}",
+                data_type
+            );
+        }
+    }
+
+    #[test]
+    fn test_normalized_type_is_textual() {
+        assert!(NormalizedType::from_pg_type("text[]", &[]).is_textual());
+        assert!(NormalizedType::Enum("public.mood".to_string()).is_textual());
+        assert!(!NormalizedType::from_pg_type("integer", &[]).is_textual());
+    }
+
     #[test]
     fn test_fk_action_display() {
         assert_eq!(FkAction::Cascade.to_string(), "CASCADE");
@@ -190,6 +666,12 @@ mod tests {
             updated_at: Utc::now(),
             expires_at: Utc::now(),
             sandbox_db_name: Some("sandbox_test".to_string()),
+            restore_timings: None,
+            restore_warnings: None,
+            dump_format: None,
+            dump_layout: None,
+            upload_purged_at: None,
+            last_viewed_at: None,
         };
 
         let json = serde_json::to_string(&dump).unwrap();
@@ -197,6 +679,24 @@ mod tests {
         assert!(json.contains("READY"));
     }
 
+    #[test]
+    fn test_restore_timings_partial_json() {
+        // The worker writes restore and analysis phases separately
+        let timings: RestoreTimings =
+            serde_json::from_str(r#"{"decompress_ms": 5, "restore_ms": 120}"#).unwrap();
+        assert_eq!(timings.restore_ms, 120);
+        assert_eq!(timings.analyze_ms, 0);
+    }
+
+    #[test]
+    fn test_schema_graph_without_indexes_deserializes() {
+        // Schema graphs cached before index introspection have no "indexes" key
+        let graph: SchemaGraph =
+            serde_json::from_str(r#"{"tables": [], "foreign_keys": []}"#).unwrap();
+        assert!(graph.indexes.is_empty());
+        assert!(graph.views.is_empty());
+    }
+
     #[test]
     fn test_schema_graph_default() {
         let graph = SchemaGraph::default();
@@ -0,0 +1,432 @@
+//! Multi-file uploads: tar archives, pg_dump directory-format dumps and sets
+//! of SQL files
+//!
+//! An upload arrives as one or more files. [`assemble_upload`] turns them into
+//! the single path the worker restores (see [`DumpLayout::dump_name`]).
+
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use flate2::read::GzDecoder;
+use tar::EntryType;
+use tracing::info;
+
+use crate::adapter::postgres::classify_dump_header;
+use crate::domain::{DumpFormat, DumpLayout};
+use crate::error::{CoreError, Result};
+
+/// Table of contents file that marks a directory-format dump
+pub const DIRECTORY_DUMP_TOC: &str = "toc.dat";
+
+/// Script `pg_dump -Ft` writes next to `toc.dat`; directory-format dumps
+/// have none
+const TAR_DUMP_SCRIPT: &str = "restore.sql";
+
+/// Offset of the `ustar` magic in a tar header block
+const TAR_MAGIC_OFFSET: usize = 257;
+
+/// Magic of POSIX and GNU tar headers
+const TAR_MAGIC: &[u8] = b"ustar";
+
+/// Gzip magic bytes
+const GZIP_MAGIC: [u8; 2] = [0x1F, 0x8B];
+
+/// Bytes of each SQL file inspected before it is concatenated
+const SQL_HEADER_PEEK_BYTES: u64 = 64 * 1024;
+
+/// Whether `path` is a pg_dump directory-format dump: a directory holding
+/// `toc.dat`
+pub fn is_directory_dump(path: &Path) -> bool {
+    path.join(DIRECTORY_DUMP_TOC).is_file()
+}
+
+/// Whether a directory holding `toc.dat` is an extracted tar-format dump
+/// (`pg_dump -Ft`) rather than a directory-format one
+pub fn is_extracted_tar_dump(path: &Path) -> bool {
+    path.join(TAR_DUMP_SCRIPT).is_file()
+}
+
+/// Whether a file is a tar archive, optionally gzip-compressed
+pub fn is_tar_archive(path: &Path) -> Result<bool> {
+    let mut magic = [0u8; 2];
+    let compressed = File::open(path)?.read(&mut magic)? == 2 && magic == GZIP_MAGIC;
+
+    let file = File::open(path)?;
+    let mut reader: Box<dyn Read> = if compressed {
+        Box::new(GzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+    let mut header = Vec::with_capacity(512);
+    // A corrupt gzip stream is simply not a tar archive
+    let _ = reader.by_ref().take(512).read_to_end(&mut header);
+
+    Ok(header.len() == 512
+        && &header[TAR_MAGIC_OFFSET..TAR_MAGIC_OFFSET + TAR_MAGIC.len()] == TAR_MAGIC)
+}
+
+/// Extract a tar archive, optionally gzip-compressed, into `dest`
+///
+/// Entries that would land outside `dest` (absolute paths, `..`) are skipped.
+/// Archives holding anything but regular files and directories (symlinks,
+/// hardlinks, devices) are rejected, and so are archives unpacking to more
+/// than `max_bytes`.
+pub fn extract_tar(archive: &Path, dest: &Path, max_bytes: u64) -> Result<()> {
+    let mut magic = [0u8; 2];
+    let compressed = File::open(archive)?.read(&mut magic)? == 2 && magic == GZIP_MAGIC;
+
+    let file = BufReader::new(File::open(archive)?);
+    let reader: Box<dyn Read> = if compressed {
+        Box::new(GzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+
+    let invalid =
+        |e: std::io::Error| CoreError::Validation(format!("Failed to extract archive: {}", e));
+    fs::create_dir_all(dest)?;
+    let mut archive = tar::Archive::new(reader);
+    let mut unpacked: u64 = 0;
+    for entry in archive.entries().map_err(invalid)? {
+        let mut entry = entry.map_err(invalid)?;
+        let kind = entry.header().entry_type();
+        if kind == EntryType::XGlobalHeader {
+            // pax metadata, e.g. the commit id `git archive` records
+            continue;
+        }
+        if !kind.is_file() && !kind.is_dir() {
+            return Err(CoreError::Validation(format!(
+                "Archive entry {} is not a regular file or directory",
+                entry.path().map_err(invalid)?.display()
+            )));
+        }
+        unpacked = unpacked.saturating_add(entry.size());
+        if unpacked > max_bytes {
+            return Err(CoreError::Validation(format!(
+                "Archive unpacks to more than {} bytes",
+                max_bytes
+            )));
+        }
+        entry.unpack_in(dest).map_err(invalid)?;
+    }
+    Ok(())
+}
+
+/// Find a directory-format dump under `root`: `root` itself or the first
+/// nested directory (in name order) holding `toc.dat`
+pub fn find_directory_dump(root: &Path) -> Result<Option<PathBuf>> {
+    if is_directory_dump(root) {
+        return Ok(Some(root.to_path_buf()));
+    }
+    for entry in sorted_entries(root)? {
+        if fs::symlink_metadata(&entry)?.is_dir() {
+            if let Some(found) = find_directory_dump(&entry)? {
+                return Ok(Some(found));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Regular files under `root`, recursively, sorted by path
+///
+/// Symlinks are not followed.
+pub fn list_files(root: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in sorted_entries(root)? {
+        let file_type = fs::symlink_metadata(&entry)?.file_type();
+        if file_type.is_dir() {
+            files.extend(list_files(&entry)?);
+        } else if file_type.is_file() {
+            files.push(entry);
+        }
+    }
+    Ok(files)
+}
+
+/// Entries of a directory sorted by name
+fn sorted_entries(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort();
+    Ok(entries)
+}
+
+/// Check that a file can be concatenated with others: uncompressed plain SQL
+fn check_sql_file(path: &Path) -> Result<()> {
+    let mut head = Vec::new();
+    File::open(path)?
+        .take(SQL_HEADER_PEEK_BYTES)
+        .read_to_end(&mut head)?;
+
+    match classify_dump_header(&head) {
+        DumpFormat::PlainSql | DumpFormat::PgDumpall => Ok(()),
+        _ => Err(CoreError::Validation(format!(
+            "{} is not an uncompressed SQL file; only plain SQL files can be combined",
+            path.file_name().unwrap_or_default().to_string_lossy()
+        ))),
+    }
+}
+
+/// Concatenate `files` into `dest` in order, separating them with a newline
+/// so a file without a trailing newline cannot merge into the next statement
+///
+/// Returns the number of bytes written.
+pub fn concatenate(files: &[PathBuf], dest: &Path) -> Result<u64> {
+    let mut writer = BufWriter::new(File::create(dest)?);
+    let mut written = 0;
+    for (i, file) in files.iter().enumerate() {
+        if i > 0 {
+            writer.write_all(b"\n")?;
+            written += 1;
+        }
+        written += std::io::copy(&mut File::open(file)?, &mut writer)?;
+    }
+    writer.flush()?;
+    Ok(written)
+}
+
+/// Arrange uploaded files in `upload_dir` for the worker
+///
+/// `parts` are the uploaded files, in upload order, already inside
+/// `upload_dir`. A single plain file is moved to `dump.sql`. Tar archives are
+/// extracted; if one holds a directory-format dump it is moved to `dump/`.
+/// Tar-format pg_dump archives are rejected: they are not restored as
+/// directory-format dumps, so they must be converted to `-Fc` or `-Fd`.
+/// Otherwise every file (archive members in name order) must be plain SQL and
+/// they are concatenated into `dump.sql`. The parts and extracted files are
+/// removed afterwards. Each archive may unpack to at most `max_bytes`.
+pub fn assemble_upload(parts: &[PathBuf], upload_dir: &Path, max_bytes: u64) -> Result<DumpLayout> {
+    let dump_file = upload_dir.join(DumpLayout::SingleFile.dump_name());
+    let dump_dir = upload_dir.join(DumpLayout::Directory.dump_name());
+    let staging = upload_dir.join("extracted");
+
+    let archives = parts
+        .iter()
+        .map(|part| is_tar_archive(part))
+        .collect::<Result<Vec<_>>>()?;
+    if let ([part], [false]) = (parts, archives.as_slice()) {
+        fs::rename(part, &dump_file)?;
+        return Ok(DumpLayout::SingleFile);
+    }
+
+    let result = (|| {
+        let mut files = Vec::new();
+        for (i, (part, is_archive)) in parts.iter().zip(&archives).enumerate() {
+            if !is_archive {
+                files.push(part.clone());
+                continue;
+            }
+            let dest = staging.join(i.to_string());
+            extract_tar(part, &dest, max_bytes)?;
+            if let Some(dir) = find_directory_dump(&dest)? {
+                if is_extracted_tar_dump(&dir) {
+                    return Err(CoreError::Validation(
+                        "Tar-format pg_dump archives (pg_dump -Ft) are not supported; \
+                         upload a custom-format (-Fc) or directory-format (-Fd) dump"
+                            .to_string(),
+                    ));
+                }
+                if parts.len() > 1 {
+                    return Err(CoreError::Validation(
+                        "A directory-format dump must be uploaded on its own".to_string(),
+                    ));
+                }
+                info!("Found directory-format dump at {}", dir.display());
+                fs::rename(&dir, &dump_dir)?;
+                return Ok(DumpLayout::Directory);
+            }
+            files.extend(list_files(&dest)?);
+        }
+
+        if files.is_empty() {
+            return Err(CoreError::Validation(
+                "The upload contains no files".to_string(),
+            ));
+        }
+        for file in &files {
+            check_sql_file(file)?;
+        }
+        info!("Concatenating {} SQL files", files.len());
+        concatenate(&files, &dump_file)?;
+        Ok(DumpLayout::MultiFile)
+    })();
+
+    for part in parts {
+        let _ = fs::remove_file(part);
+    }
+    let _ = fs::remove_dir_all(&staging);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Unpacked size limit of the tests
+    const LIMIT: u64 = 1024 * 1024;
+
+    fn append_file(builder: &mut tar::Builder<Vec<u8>>, path: &str, contents: &[u8]) {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, path, contents).unwrap();
+    }
+
+    fn write_tar(path: &Path, files: &[(&str, &[u8])]) {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (name, contents) in files {
+            append_file(&mut builder, name, contents);
+        }
+        fs::write(path, builder.into_inner().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_directory_dump_archive_is_extracted() {
+        let upload_dir = tempfile::tempdir().unwrap();
+        let archive = upload_dir.path().join("part-0");
+        // Layout of `tar czf` over a `pg_dump -Fd -f app.dir` output
+        let tar = {
+            let mut builder = tar::Builder::new(Vec::new());
+            append_file(&mut builder, "app.dir/toc.dat", b"PGDMP\x01\x0e\x00");
+            append_file(&mut builder, "app.dir/3001.dat.gz", b"\x1f\x8b");
+            builder.into_inner().unwrap()
+        };
+        let mut encoder =
+            flate2::write::GzEncoder::new(File::create(&archive).unwrap(), Default::default());
+        encoder.write_all(&tar).unwrap();
+        encoder.finish().unwrap();
+
+        assert!(is_tar_archive(&archive).unwrap());
+        let layout =
+            assemble_upload(std::slice::from_ref(&archive), upload_dir.path(), LIMIT).unwrap();
+
+        let dump_dir = upload_dir.path().join("dump");
+        assert_eq!(layout, DumpLayout::Directory);
+        assert!(is_directory_dump(&dump_dir));
+        assert!(dump_dir.join("3001.dat.gz").is_file());
+        assert!(!archive.exists());
+        assert!(!upload_dir.path().join("extracted").exists());
+    }
+
+    #[test]
+    fn test_tar_format_dump_is_rejected() {
+        let upload_dir = tempfile::tempdir().unwrap();
+        let archive = upload_dir.path().join("part-0");
+        // Layout of a `pg_dump -Ft` archive
+        write_tar(
+            &archive,
+            &[
+                ("toc.dat", b"PGDMP\x01\x0e\x00\x04\x08\x03"),
+                ("3001.dat", b"1\n\\.\n"),
+                ("restore.sql", b"--\n-- PostgreSQL database dump\n--\n"),
+            ],
+        );
+
+        let result = assemble_upload(std::slice::from_ref(&archive), upload_dir.path(), LIMIT);
+
+        assert!(
+            matches!(&result, Err(CoreError::Validation(msg)) if msg.contains("-Ft")),
+            "{:?}",
+            result
+        );
+        assert!(!upload_dir.path().join("dump").exists());
+        assert!(!upload_dir.path().join("extracted").exists());
+    }
+
+    #[test]
+    fn test_sql_files_are_concatenated_in_order() {
+        let upload_dir = tempfile::tempdir().unwrap();
+        let archive = upload_dir.path().join("part-0");
+        write_tar(
+            &archive,
+            &[
+                ("02_data.sql", b"INSERT INTO t VALUES (1);"),
+                ("01_schema.sql", b"CREATE TABLE t (id int);"),
+            ],
+        );
+        let extra = upload_dir.path().join("part-1");
+        fs::write(&extra, "CREATE INDEX ON t (id);\n").unwrap();
+
+        let layout = assemble_upload(&[archive, extra], upload_dir.path(), LIMIT).unwrap();
+
+        assert_eq!(layout, DumpLayout::MultiFile);
+        assert_eq!(
+            fs::read_to_string(upload_dir.path().join("dump.sql")).unwrap(),
+            "CREATE TABLE t (id int);\nINSERT INTO t VALUES (1);\nCREATE INDEX ON t (id);\n"
+        );
+    }
+
+    #[test]
+    fn test_single_plain_file_is_moved() {
+        let upload_dir = tempfile::tempdir().unwrap();
+        let part = upload_dir.path().join("part-0");
+        fs::write(&part, "CREATE TABLE t (id int);\n").unwrap();
+
+        let layout =
+            assemble_upload(std::slice::from_ref(&part), upload_dir.path(), LIMIT).unwrap();
+
+        assert_eq!(layout, DumpLayout::SingleFile);
+        assert!(!part.exists());
+        assert!(upload_dir.path().join("dump.sql").is_file());
+    }
+
+    #[test]
+    fn test_custom_format_cannot_be_combined() {
+        let upload_dir = tempfile::tempdir().unwrap();
+        let first = upload_dir.path().join("part-0");
+        let second = upload_dir.path().join("part-1");
+        fs::write(&first, "CREATE TABLE t (id int);\n").unwrap();
+        fs::write(&second, b"PGDMP\x01\x0e\x00").unwrap();
+
+        let result = assemble_upload(&[first, second], upload_dir.path(), LIMIT);
+
+        assert!(matches!(result, Err(CoreError::Validation(_))));
+        assert!(!upload_dir.path().join("dump.sql").exists());
+    }
+
+    #[test]
+    fn test_symlink_entry_is_rejected() {
+        let upload_dir = tempfile::tempdir().unwrap();
+        let archive = upload_dir.path().join("part-0");
+        let tar = {
+            let mut builder = tar::Builder::new(Vec::new());
+            append_file(&mut builder, "01_schema.sql", b"CREATE TABLE t (id int);");
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(EntryType::Symlink);
+            header.set_size(0);
+            header.set_mode(0o777);
+            builder
+                .append_link(&mut header, "02_data.sql", "/etc/passwd")
+                .unwrap();
+            builder.into_inner().unwrap()
+        };
+        fs::write(&archive, tar).unwrap();
+
+        let result = assemble_upload(std::slice::from_ref(&archive), upload_dir.path(), LIMIT);
+
+        assert!(matches!(result, Err(CoreError::Validation(_))));
+        assert!(!upload_dir.path().join("dump.sql").exists());
+        assert!(!upload_dir.path().join("extracted").exists());
+    }
+
+    #[test]
+    fn test_extraction_stops_over_size_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("archive.tar");
+        write_tar(
+            &archive,
+            &[("small.sql", &[b' '; 512]), ("large.sql", &[b' '; 4096])],
+        );
+        let dest = dir.path().join("out");
+
+        let result = extract_tar(&archive, &dest, 1024);
+
+        assert!(matches!(result, Err(CoreError::Validation(_))));
+        assert!(dest.join("small.sql").is_file());
+        assert!(!dest.join("large.sql").exists());
+    }
+}
@@ -17,8 +17,25 @@ pub enum CoreError {
     #[error("Invalid dump state: expected {expected}, got {actual}")]
     InvalidDumpState { expected: String, actual: String },
 
+    /// pg_restore, psql or a decompressor is not installed on the worker
+    #[error("Restore tool missing: {0}")]
+    RestoreToolMissing(String),
+
+    /// The dump itself cannot be restored (syntax error, corrupt archive, ...)
+    #[error("Restore syntax error: {0}")]
+    RestoreSyntaxError(String),
+
+    /// The sandbox server was unreachable or dropped the connection
+    #[error("Restore connection error: {0}")]
+    RestoreConnection(String),
+
     #[error("Restore failed: {0}")]
-    RestoreFailed(String),
+    RestoreUnknown(String),
+
+    /// The restore was stopped before it finished (the dump was deleted or
+    /// the restore tool was killed)
+    #[error("Restore cancelled: {0}")]
+    RestoreCancelled(String),
 
     #[error("Schema introspection failed: {0}")]
     IntrospectionFailed(String),
@@ -32,6 +49,11 @@ pub enum CoreError {
     #[error("Slug already exists: {0}")]
     SlugExists(String),
 
+    /// `create_database` found the database already there and was not
+    /// allowed to replace it
+    #[error("Database already exists: {0}")]
+    DatabaseExists(String),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
@@ -45,6 +67,30 @@ pub enum CoreError {
     Internal(String),
 }
 
+impl CoreError {
+    /// Stable code for restore failures, used to prefix stored error messages
+    /// so clients can tell operator problems from bad dumps
+    pub fn restore_kind(&self) -> Option<&'static str> {
+        match self {
+            CoreError::RestoreToolMissing(_) => Some("RESTORE_TOOL_MISSING"),
+            CoreError::RestoreSyntaxError(_) => Some("RESTORE_SYNTAX_ERROR"),
+            CoreError::RestoreConnection(_) => Some("RESTORE_CONNECTION"),
+            CoreError::RestoreUnknown(_) => Some("RESTORE_UNKNOWN"),
+            CoreError::RestoreCancelled(_) => Some("RESTORE_CANCELLED"),
+            _ => None,
+        }
+    }
+
+    /// Whether retrying the same operation may succeed
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            CoreError::RestoreConnection(_)
+                | CoreError::Database(sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut)
+        )
+    }
+}
+
 /// Result type alias using CoreError
 pub type Result<T> = std::result::Result<T, CoreError>;
 
@@ -0,0 +1,369 @@
+//! Typed row filter expressions for table data browsing
+//!
+//! A filter is a list of `column op value` clauses joined by `AND`, e.g.
+//! `status = 'active' AND age >= 18 AND deleted_at IS NULL`. Expressions are
+//! parsed into [`FilterPredicate`]s and rendered as parameterized SQL, so
+//! values are always bound and never interpolated.
+
+use crate::error::{CoreError, Result};
+use crate::sql_gen::quote_ident;
+use serde::{Deserialize, Serialize};
+
+/// Comparison operator allowed in a filter clause
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterOp {
+    Eq,
+    NotEq,
+    Lt,
+    Gt,
+    LtEq,
+    GtEq,
+    Like,
+    IsNull,
+}
+
+impl FilterOp {
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            FilterOp::Eq => "=",
+            FilterOp::NotEq => "<>",
+            FilterOp::Lt => "<",
+            FilterOp::Gt => ">",
+            FilterOp::LtEq => "<=",
+            FilterOp::GtEq => ">=",
+            FilterOp::Like => "LIKE",
+            FilterOp::IsNull => "IS NULL",
+        }
+    }
+}
+
+/// Direction of an `ORDER BY` column
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl SortDirection {
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            SortDirection::Asc => "ASC",
+            SortDirection::Desc => "DESC",
+        }
+    }
+}
+
+/// Column and direction of an `ORDER BY`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderBy {
+    pub column: String,
+    #[serde(default)]
+    pub direction: SortDirection,
+}
+
+/// A single parsed `column op value` clause
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterPredicate {
+    pub column: String,
+    pub op: FilterOp,
+    /// Comparison value; `None` only for `IS NULL`
+    pub value: Option<String>,
+}
+
+/// Parameterized SQL rendered from a filter expression
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RenderedFilter {
+    /// One SQL condition per predicate, meant to be joined with `AND`
+    pub conditions: Vec<String>,
+    /// Values to bind, in placeholder order
+    pub params: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Op(FilterOp),
+}
+
+fn invalid(msg: impl Into<String>) -> CoreError {
+    CoreError::Validation(format!("Invalid filter: {}", msg.into()))
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '$' | '.' | '-' | '+' | ':')
+}
+
+/// Read a quoted token whose delimiter is escaped by doubling it
+fn read_quoted(
+    chars: &mut std::iter::Peekable<std::str::Chars<'_>>,
+    quote: char,
+) -> Result<String> {
+    let mut out = String::new();
+    loop {
+        match chars.next() {
+            Some(c) if c == quote => {
+                if chars.peek() == Some(&quote) {
+                    chars.next();
+                    out.push(quote);
+                } else {
+                    return Ok(out);
+                }
+            }
+            Some(c) => out.push(c),
+            None => return Err(invalid(format!("unterminated {} quote", quote))),
+        }
+    }
+}
+
+fn tokenize(expr: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = expr.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '\'' => {
+                chars.next();
+                tokens.push(Token::Str(read_quoted(&mut chars, '\'')?));
+            }
+            '"' => {
+                chars.next();
+                tokens.push(Token::Ident(read_quoted(&mut chars, '"')?));
+            }
+            '=' => {
+                chars.next();
+                tokens.push(Token::Op(FilterOp::Eq));
+            }
+            '!' => {
+                chars.next();
+                if chars.next() != Some('=') {
+                    return Err(invalid("expected '=' after '!'"));
+                }
+                tokens.push(Token::Op(FilterOp::NotEq));
+            }
+            '<' | '>' => {
+                chars.next();
+                let op = match (c, chars.peek()) {
+                    ('<', Some('=')) => FilterOp::LtEq,
+                    ('>', Some('=')) => FilterOp::GtEq,
+                    ('<', Some('>')) => FilterOp::NotEq,
+                    ('<', _) => FilterOp::Lt,
+                    _ => FilterOp::Gt,
+                };
+                if matches!(op, FilterOp::LtEq | FilterOp::GtEq | FilterOp::NotEq) {
+                    chars.next();
+                }
+                tokens.push(Token::Op(op));
+            }
+            c if is_word_char(c) => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if !is_word_char(c) {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Ident(word));
+            }
+            other => return Err(invalid(format!("unexpected character '{}'", other))),
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn is_keyword(token: Option<&Token>, keyword: &str) -> bool {
+    matches!(token, Some(Token::Ident(w)) if w.eq_ignore_ascii_case(keyword))
+}
+
+/// Parse a filter expression into predicates
+///
+/// Keywords (`AND`, `LIKE`, `IS NULL`) are case-insensitive. Column names are
+/// bare words or double-quoted identifiers; values are single-quoted strings
+/// or bare words such as numbers. An empty expression yields no predicates.
+pub fn parse_filter(expr: &str) -> Result<Vec<FilterPredicate>> {
+    let tokens = tokenize(expr)?;
+    let mut iter = tokens.into_iter().peekable();
+    let mut predicates = Vec::new();
+
+    while iter.peek().is_some() {
+        if !predicates.is_empty() {
+            if !is_keyword(iter.peek(), "AND") {
+                return Err(invalid("clauses must be joined with AND"));
+            }
+            iter.next();
+        }
+
+        let column = match iter.next() {
+            Some(Token::Ident(name)) => name,
+            _ => return Err(invalid("expected a column name")),
+        };
+
+        let op = match iter.next() {
+            Some(Token::Op(op)) => op,
+            Some(Token::Ident(w)) if w.eq_ignore_ascii_case("LIKE") => FilterOp::Like,
+            Some(Token::Ident(w)) if w.eq_ignore_ascii_case("IS") => {
+                if !is_keyword(iter.peek(), "NULL") {
+                    return Err(invalid("only IS NULL is supported"));
+                }
+                iter.next();
+                FilterOp::IsNull
+            }
+            _ => return Err(invalid(format!("expected an operator after '{}'", column))),
+        };
+
+        let value = if op == FilterOp::IsNull {
+            None
+        } else {
+            match iter.next() {
+                Some(Token::Str(v)) => Some(v),
+                Some(Token::Ident(v)) if !v.eq_ignore_ascii_case("AND") => Some(v),
+                _ => return Err(invalid(format!("expected a value for '{}'", column))),
+            }
+        };
+
+        predicates.push(FilterPredicate { column, op, value });
+    }
+
+    Ok(predicates)
+}
+
+/// Render predicates against a table aliased as `t`
+///
+/// `columns` lists `(name, type)` pairs for the table; predicates naming any
+/// other column are rejected. Values are cast to the column type so that
+/// numeric and date comparisons order correctly, except for `LIKE`, which
+/// matches against the column's text representation. Placeholders start at
+/// `$first_param`.
+pub fn render_filter(
+    predicates: &[FilterPredicate],
+    columns: &[(String, String)],
+    first_param: usize,
+) -> Result<RenderedFilter> {
+    let mut rendered = RenderedFilter::default();
+
+    for predicate in predicates {
+        let (name, data_type) = columns
+            .iter()
+            .find(|(name, _)| *name == predicate.column)
+            .ok_or_else(|| invalid(format!("unknown column '{}'", predicate.column)))?;
+        let column = format!("t.{}", quote_ident(name));
+
+        let condition = match (&predicate.op, &predicate.value) {
+            (FilterOp::IsNull, _) => format!("{} IS NULL", column),
+            (op, Some(value)) => {
+                let placeholder = format!("${}", first_param + rendered.params.len());
+                rendered.params.push(value.clone());
+                if *op == FilterOp::Like {
+                    format!("{}::text LIKE {}", column, placeholder)
+                } else {
+                    format!(
+                        "{} {} CAST({} AS {})",
+                        column,
+                        op.as_sql(),
+                        placeholder,
+                        data_type
+                    )
+                }
+            }
+            (_, None) => return Err(invalid(format!("missing value for '{}'", name))),
+        };
+        rendered.conditions.push(condition);
+    }
+
+    Ok(rendered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns() -> Vec<(String, String)> {
+        vec![
+            ("id".to_string(), "pg_catalog.int4".to_string()),
+            ("name".to_string(), "pg_catalog.text".to_string()),
+            (
+                "Created At".to_string(),
+                "pg_catalog.timestamptz".to_string(),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_parse_filter_multiple_clauses() {
+        let predicates =
+            parse_filter("id >= 10 and name LIKE 'A%' AND \"Created At\" is null").unwrap();
+        assert_eq!(
+            predicates,
+            vec![
+                FilterPredicate {
+                    column: "id".to_string(),
+                    op: FilterOp::GtEq,
+                    value: Some("10".to_string()),
+                },
+                FilterPredicate {
+                    column: "name".to_string(),
+                    op: FilterOp::Like,
+                    value: Some("A%".to_string()),
+                },
+                FilterPredicate {
+                    column: "Created At".to_string(),
+                    op: FilterOp::IsNull,
+                    value: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_filter_quoted_value_is_not_sql() {
+        let predicates = parse_filter("name != 'O''Brien; DROP TABLE users'").unwrap();
+        assert_eq!(predicates[0].op, FilterOp::NotEq);
+        assert_eq!(
+            predicates[0].value.as_deref(),
+            Some("O'Brien; DROP TABLE users")
+        );
+    }
+
+    #[test]
+    fn test_parse_filter_rejects_malformed_input() {
+        assert!(parse_filter("id").is_err());
+        assert!(parse_filter("id = ").is_err());
+        assert!(parse_filter("id = 1 OR id = 2").is_err());
+        assert!(parse_filter("id IS NOT NULL").is_err());
+        assert!(parse_filter("name = 'open").is_err());
+        assert!(parse_filter("id = 1; DROP TABLE t").is_err());
+        assert!(parse_filter("   ").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_render_filter_binds_values() {
+        let predicates =
+            parse_filter("id > 5 AND name LIKE 'A%' AND \"Created At\" IS NULL").unwrap();
+        let rendered = render_filter(&predicates, &columns(), 2).unwrap();
+        assert_eq!(
+            rendered.conditions,
+            vec![
+                "t.\"id\" > CAST($2 AS pg_catalog.int4)",
+                "t.\"name\"::text LIKE $3",
+                "t.\"Created At\" IS NULL",
+            ]
+        );
+        assert_eq!(rendered.params, vec!["5", "A%"]);
+    }
+
+    #[test]
+    fn test_render_filter_rejects_unknown_column() {
+        let predicates = parse_filter("password = 'x'").unwrap();
+        let err = render_filter(&predicates, &columns(), 1).unwrap_err();
+        assert!(
+            matches!(err, CoreError::Validation(msg) if msg.contains("unknown column 'password'"))
+        );
+    }
+}
@@ -4,11 +4,18 @@
 //! for the DB Dump Visualization service.
 
 pub mod adapter;
+pub mod connection;
+pub mod ddl;
 pub mod diff;
 pub mod domain;
+pub mod dump_files;
 pub mod error;
+pub mod filter;
+pub mod masking;
 pub mod risk;
 pub mod schema;
+pub mod schema_codec;
+pub mod shutdown;
 pub mod sql_gen;
 
 pub use error::{CoreError, Result};
@@ -0,0 +1,185 @@
+//! Column masking applied to restored sandboxes
+//!
+//! Production dumps may contain PII. A dump can carry a list of
+//! [`ColumnMask`]s; after the restore, the worker overwrites those columns
+//! with `UPDATE` statements built here, before the dump is analyzed or
+//! becomes viewable.
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{CoreError, Result};
+use crate::sql_gen::{quote_ident, quote_literal};
+
+/// How a masked column is overwritten
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "strategy", rename_all = "lowercase")]
+pub enum MaskStrategy {
+    /// Set the column to NULL (the column must be nullable)
+    Null,
+    /// Replace each value with the MD5 of its text representation, keeping
+    /// NULLs and equal values equal (text-like columns only)
+    Hash,
+    /// Replace every value with the same string (text-like columns only)
+    Fixed { value: String },
+}
+
+/// A column to mask, e.g. `{"column": "public.users.email", "strategy": "hash"}`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColumnMask {
+    /// Target column as `schema.table.column`
+    pub column: String,
+    #[serde(flatten)]
+    pub strategy: MaskStrategy,
+}
+
+impl ColumnMask {
+    /// Split the target into `(schema, table, column)`
+    pub fn target(&self) -> Result<(&str, &str, &str)> {
+        let parts: Vec<&str> = self.column.split('.').map(str::trim).collect();
+        match parts.as_slice() {
+            [schema, table, column] if parts.iter().all(|p| !p.is_empty()) => {
+                Ok((schema, table, column))
+            }
+            _ => Err(CoreError::Validation(format!(
+                "Invalid mask column '{}': expected schema.table.column",
+                self.column
+            ))),
+        }
+    }
+}
+
+/// Masked columns of one table, keyed by `(schema, table)`
+type TableMasks<'a> = ((&'a str, &'a str), Vec<(&'a str, &'a MaskStrategy)>);
+
+/// `UPDATE` statement masking some columns of one table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaskStatement {
+    pub schema: String,
+    pub table: String,
+    pub sql: String,
+}
+
+/// Build one `UPDATE` per table covering every masked column of that table
+///
+/// Statements are returned in the order their tables first appear in
+/// `masks`. A column listed twice is an error, as is a malformed target.
+pub fn mask_statements(masks: &[ColumnMask]) -> Result<Vec<MaskStatement>> {
+    let mut tables: Vec<TableMasks> = Vec::new();
+
+    for mask in masks {
+        let (schema, table, column) = mask.target()?;
+        let index = match tables.iter().position(|(key, _)| *key == (schema, table)) {
+            Some(index) => index,
+            None => {
+                tables.push(((schema, table), Vec::new()));
+                tables.len() - 1
+            }
+        };
+        let columns = &mut tables[index].1;
+        if columns.iter().any(|(c, _)| *c == column) {
+            return Err(CoreError::Validation(format!(
+                "Column '{}' is masked more than once",
+                mask.column
+            )));
+        }
+        columns.push((column, &mask.strategy));
+    }
+
+    Ok(tables
+        .into_iter()
+        .map(|((schema, table), columns)| {
+            let assignments: Vec<String> = columns
+                .iter()
+                .map(|(column, strategy)| {
+                    let ident = quote_ident(column);
+                    let value = match strategy {
+                        MaskStrategy::Null => "NULL".to_string(),
+                        MaskStrategy::Hash => format!("md5({}::text)", ident),
+                        MaskStrategy::Fixed { value } => quote_literal(value),
+                    };
+                    format!("{} = {}", ident, value)
+                })
+                .collect();
+            MaskStatement {
+                schema: schema.to_string(),
+                table: table.to_string(),
+                sql: format!(
+                    "UPDATE {}.{} SET {}",
+                    quote_ident(schema),
+                    quote_ident(table),
+                    assignments.join(", ")
+                ),
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mask(column: &str, strategy: MaskStrategy) -> ColumnMask {
+        ColumnMask {
+            column: column.to_string(),
+            strategy,
+        }
+    }
+
+    #[test]
+    fn test_mask_statements_groups_columns_by_table() {
+        let masks = vec![
+            mask("public.users.email", MaskStrategy::Hash),
+            mask("billing.cards.number", MaskStrategy::Null),
+            mask(
+                "public.users.full_name",
+                MaskStrategy::Fixed {
+                    value: "O'Masked".to_string(),
+                },
+            ),
+        ];
+
+        let statements = mask_statements(&masks).unwrap();
+
+        assert_eq!(
+            statements
+                .iter()
+                .map(|s| s.sql.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "UPDATE \"public\".\"users\" SET \"email\" = md5(\"email\"::text), \"full_name\" = 'O''Masked'",
+                "UPDATE \"billing\".\"cards\" SET \"number\" = NULL",
+            ]
+        );
+        assert_eq!(statements[1].schema, "billing");
+        assert_eq!(statements[1].table, "cards");
+    }
+
+    #[test]
+    fn test_mask_statements_rejects_bad_targets() {
+        assert!(mask_statements(&[mask("users.email", MaskStrategy::Null)]).is_err());
+        assert!(mask_statements(&[mask("public..email", MaskStrategy::Null)]).is_err());
+        assert!(mask_statements(&[
+            mask("public.users.email", MaskStrategy::Null),
+            mask("public.users.email", MaskStrategy::Hash),
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_column_mask_json() {
+        let masks: Vec<ColumnMask> = serde_json::from_str(
+            r#"[
+                {"column": "public.users.email", "strategy": "hash"},
+                {"column": "public.users.phone", "strategy": "fixed", "value": "000"}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(masks[0].strategy, MaskStrategy::Hash);
+        assert_eq!(
+            masks[1].strategy,
+            MaskStrategy::Fixed {
+                value: "000".to_string()
+            }
+        );
+    }
+}
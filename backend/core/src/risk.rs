@@ -1,6 +1,7 @@
 //! Risk scoring module for data modification assessment
 
 use crate::domain::{FkAction, ForeignKey, SchemaGraph, TableInfo};
+use crate::schema::cascade_chain;
 use serde::{Deserialize, Serialize};
 
 /// Risk score result
@@ -26,11 +27,127 @@ pub enum RiskLevel {
 
 impl RiskLevel {
     pub fn from_score(score: u8) -> Self {
-        match score {
-            0..=25 => RiskLevel::Low,
-            26..=50 => RiskLevel::Medium,
-            51..=75 => RiskLevel::High,
-            _ => RiskLevel::Critical,
+        Self::from_score_with(score, &RiskWeights::default())
+    }
+
+    /// Classify a score using the level thresholds from `weights`
+    pub fn from_score_with(score: u8, weights: &RiskWeights) -> Self {
+        if score <= weights.low_max_score {
+            RiskLevel::Low
+        } else if score <= weights.medium_max_score {
+            RiskLevel::Medium
+        } else if score <= weights.high_max_score {
+            RiskLevel::High
+        } else {
+            RiskLevel::Critical
+        }
+    }
+}
+
+/// Kind of modification a risk score is calculated for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RiskOperation {
+    /// Deleting rows (`ON DELETE` rules apply)
+    #[default]
+    Delete,
+    /// Changing key values (`ON UPDATE` rules apply)
+    Update,
+}
+
+/// Points and thresholds used by [`RiskCalculator`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RiskWeights {
+    /// Points per table referencing the target via a foreign key
+    pub inbound_fk: u32,
+    /// Cap on the total inbound foreign key points
+    pub inbound_fk_max: u32,
+    /// Points per inbound foreign key with ON DELETE CASCADE
+    pub cascade_fk: u32,
+    /// Cap on the total cascade points
+    pub cascade_fk_max: u32,
+    /// Maximum number of ON DELETE CASCADE levels followed transitively
+    pub cascade_max_depth: usize,
+    /// Points per table reached only through a cascade chain (two or more
+    /// levels away)
+    pub transitive_cascade_table: u32,
+    /// Cap on the total transitive cascade points
+    pub transitive_cascade_max: u32,
+    /// Points per inbound foreign key with ON UPDATE CASCADE
+    pub update_cascade_fk: u32,
+    /// Cap on the total update cascade points
+    pub update_cascade_fk_max: u32,
+    /// Points when RESTRICT / NO ACTION foreign keys will block deletion
+    /// (or a key change)
+    pub restrict_fk: u32,
+    /// Row estimate above which a table counts as large
+    pub large_table_rows: i64,
+    /// Points for a large table
+    pub large_table: u32,
+    /// Points when the table's primary key is referenced by other tables
+    pub referenced_primary_key: u32,
+    /// Points when the table has a foreign key referencing itself
+    pub self_referencing_fk: u32,
+    /// Points per BEFORE/AFTER DELETE trigger on the table
+    pub delete_trigger: u32,
+    /// Cap on the total delete trigger points
+    pub delete_trigger_max: u32,
+    /// Points per BEFORE/AFTER UPDATE trigger on the table
+    pub update_trigger: u32,
+    /// Cap on the total update trigger points
+    pub update_trigger_max: u32,
+    /// Points when 1-10 rows reference a column value
+    pub referencing_rows_few: u32,
+    /// Points when 11-100 rows reference a column value
+    pub referencing_rows_some: u32,
+    /// Points when 101-1000 rows reference a column value
+    pub referencing_rows_many: u32,
+    /// Points when more than 1000 rows reference a column value
+    pub referencing_rows_massive: u32,
+    /// Points per cascading foreign key referencing a column (ON DELETE
+    /// CASCADE for deletes, ON UPDATE CASCADE for updates)
+    pub column_cascade: u32,
+    /// Points when the column is a primary key
+    pub column_primary_key: u32,
+    /// Highest score classified as [`RiskLevel::Low`]
+    pub low_max_score: u8,
+    /// Highest score classified as [`RiskLevel::Medium`]
+    pub medium_max_score: u8,
+    /// Highest score classified as [`RiskLevel::High`]
+    pub high_max_score: u8,
+}
+
+impl Default for RiskWeights {
+    fn default() -> Self {
+        Self {
+            inbound_fk: 10,
+            inbound_fk_max: 30,
+            cascade_fk: 15,
+            cascade_fk_max: 30,
+            cascade_max_depth: 5,
+            transitive_cascade_table: 5,
+            transitive_cascade_max: 20,
+            update_cascade_fk: 15,
+            update_cascade_fk_max: 30,
+            restrict_fk: 10,
+            large_table_rows: 10000,
+            large_table: 10,
+            referenced_primary_key: 10,
+            self_referencing_fk: 10,
+            delete_trigger: 10,
+            delete_trigger_max: 20,
+            update_trigger: 10,
+            update_trigger_max: 20,
+            referencing_rows_few: 10,
+            referencing_rows_some: 20,
+            referencing_rows_many: 30,
+            referencing_rows_massive: 40,
+            column_cascade: 20,
+            column_primary_key: 15,
+            low_max_score: 25,
+            medium_max_score: 50,
+            high_max_score: 75,
         }
     }
 }
@@ -38,30 +155,49 @@ impl RiskLevel {
 /// Risk calculator for assessing modification/deletion risk
 pub struct RiskCalculator<'a> {
     schema_graph: &'a SchemaGraph,
+    weights: RiskWeights,
 }
 
 impl<'a> RiskCalculator<'a> {
     pub fn new(schema_graph: &'a SchemaGraph) -> Self {
-        Self { schema_graph }
+        Self::with_weights(schema_graph, RiskWeights::default())
+    }
+
+    /// Create a calculator with custom scoring weights
+    pub fn with_weights(schema_graph: &'a SchemaGraph, weights: RiskWeights) -> Self {
+        Self {
+            schema_graph,
+            weights,
+        }
     }
 
     /// Calculate risk score for deleting a row from a table
+    ///
+    /// Same as [`Self::calculate_delete_risk`].
     pub fn calculate_table_risk(&self, schema: &str, table: &str) -> RiskScore {
+        self.calculate_delete_risk(schema, table)
+    }
+
+    /// Calculate risk score for a table for the given operation
+    pub fn calculate_risk(&self, operation: RiskOperation, schema: &str, table: &str) -> RiskScore {
+        match operation {
+            RiskOperation::Delete => self.calculate_delete_risk(schema, table),
+            RiskOperation::Update => self.calculate_update_risk(schema, table),
+        }
+    }
+
+    /// Calculate risk score for deleting a row from a table
+    pub fn calculate_delete_risk(&self, schema: &str, table: &str) -> RiskScore {
         let mut score: u32 = 0;
         let mut reasons = Vec::new();
 
-        // Find inbound foreign keys (tables that reference this table)
-        let inbound_fks: Vec<&ForeignKey> = self
-            .schema_graph
-            .foreign_keys
-            .iter()
-            .filter(|fk| fk.target_schema == schema && fk.target_table == table)
-            .collect();
+        let inbound_fks = self.inbound_fks(schema, table);
 
         // Factor 1: Number of inbound foreign keys
         let inbound_count = inbound_fks.len();
         if inbound_count > 0 {
-            let fk_score = (inbound_count * 10).min(30) as u32;
+            let fk_score =
+                (inbound_count as u32 * self.weights.inbound_fk).min(self.weights.inbound_fk_max);
             score += fk_score;
             reasons.push(format!(
                 "{} table(s) reference this table via foreign keys",
@@ -75,7 +211,8 @@ impl<'a> RiskCalculator<'a> {
             .filter(|fk| fk.on_delete == FkAction::Cascade)
             .count();
         if cascade_count > 0 {
-            let cascade_score = (cascade_count * 15).min(30) as u32;
+            let cascade_score =
+                (cascade_count as u32 * self.weights.cascade_fk).min(self.weights.cascade_fk_max);
             score += cascade_score;
             reasons.push(format!(
                 "{} foreign key(s) have ON DELETE CASCADE - deletion will propagate",
@@ -83,13 +220,52 @@ impl<'a> RiskCalculator<'a> {
             ));
         }
 
+        // Factor 2b: Cascades that propagate beyond the direct children
+        let cascaded = cascade_chain(
+            self.schema_graph,
+            schema,
+            table,
+            self.weights.cascade_max_depth,
+        );
+        let levels = cascaded.last().map_or(0, |step| step.depth);
+        if levels > 1 {
+            let indirect = cascaded.iter().filter(|step| step.depth > 1).count() as u32;
+            score += (indirect * self.weights.transitive_cascade_table)
+                .min(self.weights.transitive_cascade_max);
+            let tables: Vec<String> = cascaded
+                .iter()
+                .map(|step| format!("{}.{}", step.table.0, step.table.1))
+                .collect();
+            reasons.push(format!(
+                "Deletion cascades through {} levels to {} tables: {}",
+                levels,
+                cascaded.len(),
+                tables.join(", ")
+            ));
+        }
+
+        // Factor 2c: Self-referencing foreign keys (e.g. parent_id -> id) make
+        // a single delete recurse through the table's own rows
+        let self_refs: Vec<&str> = inbound_fks
+            .iter()
+            .filter(|fk| fk.source_schema == schema && fk.source_table == table)
+            .map(|fk| fk.constraint_name.as_str())
+            .collect();
+        if !self_refs.is_empty() {
+            score += self.weights.self_referencing_fk;
+            reasons.push(format!(
+                "{}: self-referential FK can cascade within the same table",
+                self_refs.join(", ")
+            ));
+        }
+
         // Factor 3: RESTRICT behavior (prevents deletion)
         let restrict_count = inbound_fks
             .iter()
             .filter(|fk| fk.on_delete == FkAction::Restrict || fk.on_delete == FkAction::NoAction)
             .count();
         if restrict_count > 0 && inbound_count > cascade_count {
-            score += 10;
+            score += self.weights.restrict_fk;
             reasons.push(format!(
                 "{} foreign key(s) will block deletion if referenced",
                 restrict_count
@@ -98,8 +274,8 @@ impl<'a> RiskCalculator<'a> {
 
         // Factor 4: Estimated row count
         if let Some(table_info) = self.find_table(schema, table) {
-            if table_info.estimated_row_count > 10000 {
-                score += 10;
+            if table_info.estimated_row_count > self.weights.large_table_rows {
+                score += self.weights.large_table;
                 reasons.push(format!(
                     "Large table with ~{} rows",
                     table_info.estimated_row_count
@@ -111,28 +287,158 @@ impl<'a> RiskCalculator<'a> {
         if let Some(table_info) = self.find_table(schema, table) {
             let has_pk = table_info.columns.iter().any(|c| c.is_primary_key);
             if has_pk && inbound_count > 0 {
-                score += 10;
+                score += self.weights.referenced_primary_key;
                 reasons.push("Table has primary key referenced by other tables".to_string());
             }
         }
 
+        // Factor 6: DELETE triggers run arbitrary extra work per deleted row
+        let delete_triggers = self.triggers_on(schema, table, "DELETE");
+        if !delete_triggers.is_empty() {
+            score += (delete_triggers.len() as u32 * self.weights.delete_trigger)
+                .min(self.weights.delete_trigger_max);
+            reasons.push(format!(
+                "Deletion fires {} trigger(s): {}",
+                delete_triggers.len(),
+                delete_triggers.join(", ")
+            ));
+        }
+
         // Cap the score at 100
         let final_score = score.min(100) as u8;
 
         RiskScore {
             score: final_score,
-            level: RiskLevel::from_score(final_score),
+            level: RiskLevel::from_score_with(final_score, &self.weights),
+            reasons,
+        }
+    }
+
+    /// Calculate risk score for changing the key of a row in a table
+    ///
+    /// Mirrors [`Self::calculate_delete_risk`] with the `ON UPDATE` rules of
+    /// the inbound foreign keys: an `ON UPDATE CASCADE` rewrites every
+    /// referencing row, while RESTRICT / NO ACTION rejects the change.
+    pub fn calculate_update_risk(&self, schema: &str, table: &str) -> RiskScore {
+        let mut score: u32 = 0;
+        let mut reasons = Vec::new();
+
+        let inbound_fks = self.inbound_fks(schema, table);
+
+        // Factor 1: Number of inbound foreign keys
+        let inbound_count = inbound_fks.len();
+        if inbound_count > 0 {
+            score +=
+                (inbound_count as u32 * self.weights.inbound_fk).min(self.weights.inbound_fk_max);
+            reasons.push(format!(
+                "{} table(s) reference this table via foreign keys",
+                inbound_count
+            ));
+        }
+
+        // Factor 2: Key changes propagated to the referencing rows
+        let cascading: Vec<String> = inbound_fks
+            .iter()
+            .filter(|fk| fk.on_update == FkAction::Cascade)
+            .map(|fk| format!("{}.{}", fk.source_schema, fk.source_table))
+            .collect();
+        if !cascading.is_empty() {
+            score += (cascading.len() as u32 * self.weights.update_cascade_fk)
+                .min(self.weights.update_cascade_fk_max);
+            reasons.push(format!(
+                "{} foreign key(s) have ON UPDATE CASCADE - key changes will propagate to {}",
+                cascading.len(),
+                cascading.join(", ")
+            ));
+        }
+        let nulled = inbound_fks
+            .iter()
+            .filter(|fk| matches!(fk.on_update, FkAction::SetNull | FkAction::SetDefault))
+            .count();
+        if nulled > 0 {
+            reasons.push(format!(
+                "{} foreign key(s) reset referencing columns (ON UPDATE SET NULL / SET DEFAULT)",
+                nulled
+            ));
+        }
+
+        // Factor 3: RESTRICT behavior (prevents key changes)
+        let restrict_count = inbound_fks
+            .iter()
+            .filter(|fk| fk.on_update == FkAction::Restrict || fk.on_update == FkAction::NoAction)
+            .count();
+        if restrict_count > 0 {
+            score += self.weights.restrict_fk;
+            reasons.push(format!(
+                "{} foreign key(s) will block key changes if referenced",
+                restrict_count
+            ));
+        }
+
+        // Factor 4: Estimated row count
+        if let Some(table_info) = self.find_table(schema, table) {
+            if table_info.estimated_row_count > self.weights.large_table_rows {
+                score += self.weights.large_table;
+                reasons.push(format!(
+                    "Large table with ~{} rows",
+                    table_info.estimated_row_count
+                ));
+            }
+
+            // Factor 5: Primary key involvement
+            let has_pk = table_info.columns.iter().any(|c| c.is_primary_key);
+            if has_pk && inbound_count > 0 {
+                score += self.weights.referenced_primary_key;
+                reasons.push("Table has primary key referenced by other tables".to_string());
+            }
+        }
+
+        // Factor 6: UPDATE triggers run arbitrary extra work per updated row
+        let update_triggers = self.triggers_on(schema, table, "UPDATE");
+        if !update_triggers.is_empty() {
+            score += (update_triggers.len() as u32 * self.weights.update_trigger)
+                .min(self.weights.update_trigger_max);
+            reasons.push(format!(
+                "Updates fire {} trigger(s): {}",
+                update_triggers.len(),
+                update_triggers.join(", ")
+            ));
+        }
+
+        let final_score = score.min(100) as u8;
+
+        RiskScore {
+            score: final_score,
+            level: RiskLevel::from_score_with(final_score, &self.weights),
             reasons,
         }
     }
 
-    /// Calculate risk for a specific column value
+    /// Calculate risk for deleting a specific column value
     pub fn calculate_column_risk(
         &self,
         schema: &str,
         table: &str,
         column: &str,
         referencing_count: i64,
+    ) -> RiskScore {
+        self.calculate_column_risk_for(
+            RiskOperation::Delete,
+            schema,
+            table,
+            column,
+            referencing_count,
+        )
+    }
+
+    /// Calculate risk for deleting or changing a specific column value
+    pub fn calculate_column_risk_for(
+        &self,
+        operation: RiskOperation,
+        schema: &str,
+        table: &str,
+        column: &str,
+        referencing_count: i64,
     ) -> RiskScore {
         let mut score: u32 = 0;
         let mut reasons = Vec::new();
@@ -152,12 +458,12 @@ impl<'a> RiskCalculator<'a> {
         // Factor 1: Number of referencing rows
         if referencing_count > 0 {
             let ref_score = match referencing_count {
-                1..=10 => 10,
-                11..=100 => 20,
-                101..=1000 => 30,
-                _ => 40,
+                1..=10 => self.weights.referencing_rows_few,
+                11..=100 => self.weights.referencing_rows_some,
+                101..=1000 => self.weights.referencing_rows_many,
+                _ => self.weights.referencing_rows_massive,
             };
-            score += ref_score as u32;
+            score += ref_score;
             reasons.push(format!(
                 "{} row(s) in other tables reference this value",
                 referencing_count
@@ -166,36 +472,78 @@ impl<'a> RiskCalculator<'a> {
 
         // Factor 2: CASCADE behavior on referencing FKs
         for fk in &column_refs {
-            if fk.on_delete == FkAction::Cascade {
-                score += 20;
+            let (action, verb) = match operation {
+                RiskOperation::Delete => (fk.on_delete, "Deletion"),
+                RiskOperation::Update => (fk.on_update, "Changing this value"),
+            };
+            if action == FkAction::Cascade {
+                score += self.weights.column_cascade;
                 reasons.push(format!(
-                    "Deletion will cascade to {}.{}",
-                    fk.source_schema, fk.source_table
+                    "{} will cascade to {}.{}",
+                    verb, fk.source_schema, fk.source_table
                 ));
             }
         }
 
         // Factor 3: Primary key column
-        if let Some(table_info) = self.find_table(schema, table) {
-            let is_pk = table_info
-                .columns
-                .iter()
-                .any(|c| c.name == column && c.is_primary_key);
-            if is_pk {
-                score += 15;
+        let column_info = self
+            .find_table(schema, table)
+            .and_then(|t| t.columns.iter().find(|c| c.name == column));
+        if let Some(col) = column_info {
+            if col.is_primary_key {
+                score += self.weights.column_primary_key;
                 reasons.push("This is a primary key column".to_string());
             }
+
+            // Notes only: values the database assigns itself
+            if col.is_identity {
+                reasons.push(format!(
+                    "Identity column (GENERATED {} AS IDENTITY); values are assigned by the database and should not be edited manually",
+                    col.identity_generation.as_deref().unwrap_or("BY DEFAULT")
+                ));
+            }
+            if let Some(expr) = &col.generation_expression {
+                reasons.push(format!(
+                    "Generated column computed as ({}); it cannot be written directly",
+                    expr
+                ));
+            }
         }
 
         let final_score = score.min(100) as u8;
 
         RiskScore {
             score: final_score,
-            level: RiskLevel::from_score(final_score),
+            level: RiskLevel::from_score_with(final_score, &self.weights),
             reasons,
         }
     }
 
+    /// Foreign keys of other tables (or the table itself) referencing a table
+    fn inbound_fks(&self, schema: &str, table: &str) -> Vec<&ForeignKey> {
+        self.schema_graph
+            .foreign_keys
+            .iter()
+            .filter(|fk| fk.target_schema == schema && fk.target_table == table)
+            .collect()
+    }
+
+    /// BEFORE/AFTER row triggers on `event`, described for risk reasons
+    fn triggers_on(&self, schema: &str, table: &str, event: &str) -> Vec<String> {
+        self.schema_graph
+            .triggers
+            .iter()
+            .filter(|t| {
+                t.schema == schema
+                    && t.table == table
+                    && t.event.eq_ignore_ascii_case(event)
+                    && (t.timing.eq_ignore_ascii_case("BEFORE")
+                        || t.timing.eq_ignore_ascii_case("AFTER"))
+            })
+            .map(|t| format!("{} ({} {}: {})", t.name, t.timing, event, t.function))
+            .collect()
+    }
+
     fn find_table(&self, schema: &str, table: &str) -> Option<&TableInfo> {
         self.schema_graph
             .tables
@@ -207,7 +555,7 @@ impl<'a> RiskCalculator<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::domain::ColumnInfo;
+    use crate::domain::{ColumnInfo, TriggerInfo};
 
     fn create_test_schema() -> SchemaGraph {
         SchemaGraph {
@@ -222,7 +570,16 @@ mod tests {
                         is_nullable: false,
                         is_primary_key: true,
                         default_value: None,
+                        comment: None,
+                        ordinal_position: None,
+                        is_identity: false,
+                        identity_generation: None,
+                        generation_expression: None,
                     }],
+                    comment: None,
+                    is_partition: false,
+                    partition_of: None,
+                    partition_strategy: None,
                 },
                 TableInfo {
                     schema_name: "public".to_string(),
@@ -235,6 +592,11 @@ mod tests {
                             is_nullable: false,
                             is_primary_key: true,
                             default_value: None,
+                            comment: None,
+                            ordinal_position: None,
+                            is_identity: false,
+                            identity_generation: None,
+                            generation_expression: None,
                         },
                         ColumnInfo {
                             name: "user_id".to_string(),
@@ -242,8 +604,17 @@ mod tests {
                             is_nullable: false,
                             is_primary_key: false,
                             default_value: None,
+                            comment: None,
+                            ordinal_position: None,
+                            is_identity: false,
+                            identity_generation: None,
+                            generation_expression: None,
                         },
                     ],
+                    comment: None,
+                    is_partition: false,
+                    partition_of: None,
+                    partition_strategy: None,
                 },
             ],
             foreign_keys: vec![ForeignKey {
@@ -257,6 +628,11 @@ mod tests {
                 on_delete: FkAction::Cascade,
                 on_update: FkAction::NoAction,
             }],
+            indexes: vec![],
+            views: vec![],
+            triggers: vec![],
+            enum_types: vec![],
+            sequences: vec![],
         }
     }
 
@@ -284,6 +660,53 @@ mod tests {
         assert!(risk.reasons.iter().any(|r| r.contains("CASCADE")));
     }
 
+    #[test]
+    fn test_table_risk_transitive_cascade() {
+        let mut schema = create_test_schema();
+        schema.tables.push(TableInfo {
+            schema_name: "public".to_string(),
+            table_name: "order_items".to_string(),
+            estimated_row_count: 20000,
+            columns: vec![],
+            comment: None,
+            is_partition: false,
+            partition_of: None,
+            partition_strategy: None,
+        });
+        schema.foreign_keys.push(ForeignKey {
+            constraint_name: "fk_order_items_order".to_string(),
+            source_schema: "public".to_string(),
+            source_table: "order_items".to_string(),
+            source_columns: vec!["order_id".to_string()],
+            target_schema: "public".to_string(),
+            target_table: "orders".to_string(),
+            target_columns: vec!["id".to_string()],
+            on_delete: FkAction::Cascade,
+            on_update: FkAction::NoAction,
+        });
+        let calc = RiskCalculator::new(&schema);
+
+        let risk = calc.calculate_table_risk("public", "users");
+        let reason = risk
+            .reasons
+            .iter()
+            .find(|r| r.contains("cascades through"))
+            .expect("transitive cascade reason");
+        assert!(reason.contains("2 levels to 2 tables"));
+        assert!(reason.contains("public.order_items"));
+        assert_eq!(risk.score, 40);
+
+        // Limiting the depth to direct children drops the transitive reason
+        let weights = RiskWeights {
+            cascade_max_depth: 1,
+            ..RiskWeights::default()
+        };
+        let risk =
+            RiskCalculator::with_weights(&schema, weights).calculate_table_risk("public", "users");
+        assert!(!risk.reasons.iter().any(|r| r.contains("cascades through")));
+        assert_eq!(risk.score, 35);
+    }
+
     #[test]
     fn test_table_risk_no_references() {
         let schema = create_test_schema();
@@ -306,6 +729,116 @@ mod tests {
         assert!(risk.reasons.iter().any(|r| r.contains("reference")));
     }
 
+    #[test]
+    fn test_table_risk_with_custom_weights() {
+        let schema = create_test_schema();
+
+        let default_risk = RiskCalculator::new(&schema).calculate_table_risk("public", "users");
+        assert_eq!(default_risk.score, 35);
+        assert_eq!(default_risk.level, RiskLevel::Medium);
+
+        let weights = RiskWeights {
+            cascade_fk: 40,
+            cascade_fk_max: 40,
+            medium_max_score: 40,
+            ..RiskWeights::default()
+        };
+        let custom_risk =
+            RiskCalculator::with_weights(&schema, weights).calculate_table_risk("public", "users");
+        assert_eq!(custom_risk.score, 60);
+        assert_eq!(custom_risk.level, RiskLevel::High);
+    }
+
+    #[test]
+    fn test_risk_level_custom_thresholds() {
+        let weights = RiskWeights {
+            low_max_score: 10,
+            medium_max_score: 20,
+            high_max_score: 30,
+            ..RiskWeights::default()
+        };
+        assert_eq!(RiskLevel::from_score_with(10, &weights), RiskLevel::Low);
+        assert_eq!(RiskLevel::from_score_with(11, &weights), RiskLevel::Medium);
+        assert_eq!(RiskLevel::from_score_with(25, &weights), RiskLevel::High);
+        assert_eq!(
+            RiskLevel::from_score_with(31, &weights),
+            RiskLevel::Critical
+        );
+    }
+
+    #[test]
+    fn test_table_risk_self_referencing_fk() {
+        let mut schema = create_test_schema();
+        schema.tables[0].columns.push(ColumnInfo {
+            name: "manager_id".to_string(),
+            data_type: "integer".to_string(),
+            is_nullable: true,
+            is_primary_key: false,
+            default_value: None,
+            comment: None,
+            ordinal_position: None,
+            is_identity: false,
+            identity_generation: None,
+            generation_expression: None,
+        });
+        let before = RiskCalculator::new(&schema).calculate_table_risk("public", "users");
+
+        schema.foreign_keys.push(ForeignKey {
+            constraint_name: "fk_users_manager".to_string(),
+            source_schema: "public".to_string(),
+            source_table: "users".to_string(),
+            source_columns: vec!["manager_id".to_string()],
+            target_schema: "public".to_string(),
+            target_table: "users".to_string(),
+            target_columns: vec!["id".to_string()],
+            on_delete: FkAction::Cascade,
+            on_update: FkAction::NoAction,
+        });
+        let risk = RiskCalculator::new(&schema).calculate_table_risk("public", "users");
+
+        assert!(risk.reasons.iter().any(
+            |r| r == "fk_users_manager: self-referential FK can cascade within the same table"
+        ));
+        assert!(risk.score > before.score);
+        // Other tables are unaffected
+        let orders = RiskCalculator::new(&schema).calculate_table_risk("public", "orders");
+        assert!(!orders
+            .reasons
+            .iter()
+            .any(|r| r.contains("self-referential")));
+    }
+
+    #[test]
+    fn test_table_risk_with_delete_trigger() {
+        let mut schema = create_test_schema();
+        let before = RiskCalculator::new(&schema).calculate_table_risk("public", "orders");
+
+        schema.triggers.push(TriggerInfo {
+            name: "trg_orders_archive".to_string(),
+            schema: "public".to_string(),
+            table: "orders".to_string(),
+            timing: "BEFORE".to_string(),
+            event: "DELETE".to_string(),
+            function: "EXECUTE FUNCTION archive_order()".to_string(),
+        });
+        // Triggers on other events or tables do not count
+        schema.triggers.push(TriggerInfo {
+            name: "trg_orders_touch".to_string(),
+            schema: "public".to_string(),
+            table: "orders".to_string(),
+            timing: "BEFORE".to_string(),
+            event: "UPDATE".to_string(),
+            function: "EXECUTE FUNCTION touch()".to_string(),
+        });
+        let after = RiskCalculator::new(&schema).calculate_table_risk("public", "orders");
+
+        assert_eq!(after.score, before.score + 10);
+        assert!(after
+            .reasons
+            .iter()
+            .any(|r| r.contains("trg_orders_archive") && !r.contains("trg_orders_touch")));
+    }
+
     #[test]
     fn test_column_risk_primary_key() {
         let schema = create_test_schema();
@@ -315,4 +848,83 @@ mod tests {
 
         assert!(risk.reasons.iter().any(|r| r.contains("primary key")));
     }
+
+    #[test]
+    fn test_column_risk_identity_primary_key() {
+        let mut schema = create_test_schema();
+        let id = &mut schema.tables[0].columns[0];
+        id.is_identity = true;
+        id.identity_generation = Some("ALWAYS".to_string());
+        let calc = RiskCalculator::new(&schema);
+
+        let risk = calc.calculate_column_risk("public", "users", "id", 0);
+        let plain = RiskCalculator::new(&create_test_schema())
+            .calculate_column_risk("public", "users", "id", 0);
+
+        assert!(risk
+            .reasons
+            .iter()
+            .any(|r| r.contains("GENERATED ALWAYS AS IDENTITY")));
+        // The note does not change the score
+        assert_eq!(risk.score, plain.score);
+    }
+
+    #[test]
+    fn test_update_risk_with_on_update_cascade() {
+        let mut schema = create_test_schema();
+        let restricted = RiskCalculator::new(&schema).calculate_update_risk("public", "users");
+        schema.foreign_keys[0].on_update = FkAction::Cascade;
+        let calc = RiskCalculator::new(&schema);
+
+        let risk = calc.calculate_update_risk("public", "users");
+
+        assert!(risk
+            .reasons
+            .iter()
+            .any(|r| r.contains("ON UPDATE CASCADE") && r.contains("public.orders")));
+        // NO ACTION blocks key changes instead of propagating them
+        assert!(restricted.reasons.iter().any(|r| r.contains("block key")));
+        assert!(!risk.reasons.iter().any(|r| r.contains("block key")));
+        assert_eq!(risk.score, restricted.score - 10 + 15);
+        // The delete score is unaffected by ON UPDATE rules
+        assert_eq!(
+            calc.calculate_table_risk("public", "users").score,
+            calc.calculate_risk(RiskOperation::Delete, "public", "users")
+                .score
+        );
+    }
+
+    #[test]
+    fn test_update_risk_ignores_on_delete_cascade() {
+        let schema = create_test_schema();
+        let calc = RiskCalculator::new(&schema);
+
+        let risk = calc.calculate_risk(RiskOperation::Update, "public", "users");
+
+        assert!(!risk.reasons.iter().any(|r| r.contains("CASCADE")));
+        assert!(calc
+            .calculate_delete_risk("public", "users")
+            .reasons
+            .iter()
+            .any(|r| r.contains("ON DELETE CASCADE")));
+    }
+
+    #[test]
+    fn test_column_update_risk_with_on_update_cascade() {
+        let mut schema = create_test_schema();
+        schema.foreign_keys[0].on_update = FkAction::Cascade;
+        schema.foreign_keys[0].on_delete = FkAction::Restrict;
+        let calc = RiskCalculator::new(&schema);
+
+        let update =
+            calc.calculate_column_risk_for(RiskOperation::Update, "public", "users", "id", 0);
+        let delete = calc.calculate_column_risk("public", "users", "id", 0);
+
+        assert!(update
+            .reasons
+            .iter()
+            .any(|r| r == "Changing this value will cascade to public.orders"));
+        assert!(!delete.reasons.iter().any(|r| r.contains("cascade")));
+        assert_eq!(update.score, delete.score + 20);
+    }
 }
@@ -1,26 +1,77 @@
 //! Schema introspection and ER diagram generation
 
-use crate::domain::{ForeignKey, SchemaGraph, TableInfo};
-use std::collections::{HashMap, HashSet};
+use crate::domain::{
+    FkAction, ForeignKey, IndexInfo, NormalizedType, SchemaGraph, SequenceInfo, TableInfo, ViewInfo,
+};
+use crate::sql_gen::quote_ident;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 
 /// Generate Mermaid ER diagram syntax from schema graph
 pub fn generate_mermaid_er(schema_graph: &SchemaGraph) -> String {
     let mut output = String::from("erDiagram\n");
+    let mut entities = MermaidEntities::default();
 
     // Generate entity definitions
     for table in &schema_graph.tables {
-        let full_name = format!("{}_{}", table.schema_name, table.table_name);
-        output.push_str(&format!("    {} {{\n", full_name));
+        let header = entities.header(&table.schema_name, &table.table_name);
+        output.push_str(&format!("    {} {{\n", header));
 
         for col in &table.columns {
             let pk_marker = if col.is_primary_key { " PK" } else { "" };
-            let nullable = if col.is_nullable { "" } else { " \"NOT NULL\"" };
+            // Mermaid allows a single quoted comment per attribute, so the
+            // original name (when it had to be sanitized), the NOT NULL /
+            // identity / generated markers and the column comment share it
+            let identity = col.is_identity.then(|| match &col.identity_generation {
+                Some(generation) => format!("IDENTITY {}", mermaid_comment_text(generation)),
+                None => "IDENTITY".to_string(),
+            });
+            let notes: Vec<String> = renamed_note(&col.name)
+                .into_iter()
+                .chain((!col.is_nullable).then(|| "NOT NULL".to_string()))
+                .chain(identity)
+                .chain(
+                    col.generation_expression
+                        .is_some()
+                        .then(|| "GENERATED".to_string()),
+                )
+                .chain(col.comment.as_deref().map(mermaid_comment_text))
+                .collect();
+            let notes = if notes.is_empty() {
+                String::new()
+            } else {
+                format!(" \"{}\"", notes.join("; "))
+            };
             output.push_str(&format!(
                 "        {} {}{}{}\n",
-                col.data_type.replace(' ', "_"),
-                col.name,
+                mermaid_type(&col.data_type),
+                mermaid_identifier(&col.name),
                 pk_marker,
-                nullable
+                notes
+            ));
+        }
+        output.push_str("    }\n");
+    }
+
+    // Views are entities drawn with a dashed border
+    for view in &schema_graph.views {
+        let header = entities.header(&view.schema, &view.name);
+        output.push_str(&format!("    {} {{\n", header));
+        for col in &view.columns {
+            let notes: Vec<String> = renamed_note(&col.name)
+                .into_iter()
+                .chain(col.comment.as_deref().map(mermaid_comment_text))
+                .collect();
+            let notes = if notes.is_empty() {
+                String::new()
+            } else {
+                format!(" \"{}\"", notes.join("; "))
+            };
+            output.push_str(&format!(
+                "        {} {}{}\n",
+                mermaid_type(&col.data_type),
+                mermaid_identifier(&col.name),
+                notes
             ));
         }
         output.push_str("    }\n");
@@ -28,48 +79,665 @@ pub fn generate_mermaid_er(schema_graph: &SchemaGraph) -> String {
 
     // Generate relationships
     for fk in &schema_graph.foreign_keys {
-        let source = format!("{}_{}", fk.source_schema, fk.source_table);
-        let target = format!("{}_{}", fk.target_schema, fk.target_table);
+        let source = entities.id(&fk.source_schema, &fk.source_table);
+        let target = entities.id(&fk.target_schema, &fk.target_table);
 
-        // Mermaid cardinality notation
-        // ||--o{ means one-to-many
         output.push_str(&format!(
-            "    {} ||--o{{ {} : \"{}\"\n",
-            target, source, fk.constraint_name
+            "    {} {} {} : \"{}\"\n",
+            target,
+            mermaid_cardinality(schema_graph, fk),
+            source,
+            mermaid_comment_text(&fk.constraint_name)
         ));
     }
 
+    if !schema_graph.views.is_empty() {
+        output.push_str("    classDef view stroke-dasharray: 5 5\n");
+        for view in &schema_graph.views {
+            output.push_str(&format!(
+                "    class {} view\n",
+                entities.id(&view.schema, &view.name)
+            ));
+        }
+    }
+
     output
 }
 
+/// Mermaid cardinality of a foreign key drawn from the referenced table to
+/// the referencing one
+///
+/// Nullable source columns make the referenced side zero-or-one (`|o`)
+/// instead of exactly one (`||`); source columns covered by a primary key or
+/// unique index make the referencing side one (`||`) instead of many (`o{`).
+fn mermaid_cardinality(schema_graph: &SchemaGraph, fk: &ForeignKey) -> &'static str {
+    let source_table = schema_graph
+        .tables
+        .iter()
+        .find(|t| t.schema_name == fk.source_schema && t.table_name == fk.source_table);
+    let nullable = source_table.is_some_and(|table| {
+        table
+            .columns
+            .iter()
+            .any(|c| c.is_nullable && fk.source_columns.contains(&c.name))
+    });
+    match (
+        nullable,
+        fk_source_is_unique(schema_graph, source_table, fk),
+    ) {
+        (false, true) => "||--||",
+        (true, true) => "|o--||",
+        (false, false) => "||--o{",
+        (true, false) => "|o--o{",
+    }
+}
+
+/// Whether a foreign key's source columns can hold each value only once:
+/// they include every column of a primary key or of a non-partial unique
+/// index
+///
+/// Graphs cached before indexes were introspected fall back to the
+/// primary key flags of the columns.
+fn fk_source_is_unique(
+    schema_graph: &SchemaGraph,
+    source_table: Option<&TableInfo>,
+    fk: &ForeignKey,
+) -> bool {
+    let covers = |keys: &[String]| {
+        !keys.is_empty()
+            && keys.iter().all(|key| {
+                fk.source_columns
+                    .iter()
+                    .any(|c| key == c || *key == quote_ident(c))
+            })
+    };
+    if schema_graph.indexes.is_empty() {
+        let primary_key: Vec<String> = source_table
+            .map(|t| {
+                t.columns
+                    .iter()
+                    .filter(|c| c.is_primary_key)
+                    .map(|c| c.name.clone())
+                    .collect()
+            })
+            .unwrap_or_default();
+        return covers(&primary_key);
+    }
+    schema_graph.indexes.iter().any(|index| {
+        (index.is_unique || index.is_primary)
+            && index.predicate.is_none()
+            && index.schema_name == fk.source_schema
+            && index.table_name == fk.source_table
+            && covers(&index.columns)
+    })
+}
+
+/// Sanitized, unique Mermaid entity names for `schema.table` pairs, so that
+/// entity blocks and relationship edges agree on the same identifier
+#[derive(Default)]
+struct MermaidEntities {
+    ids: HashMap<(String, String), String>,
+    used: HashSet<String>,
+}
+
+impl MermaidEntities {
+    /// Entity identifier (`schema_table`, sanitized and de-duplicated)
+    fn id(&mut self, schema: &str, table: &str) -> String {
+        let key = (schema.to_string(), table.to_string());
+        if let Some(id) = self.ids.get(&key) {
+            return id.clone();
+        }
+
+        let base = mermaid_identifier(&format!("{}_{}", schema, table));
+        let mut id = base.clone();
+        let mut suffix = 2;
+        while !self.used.insert(id.clone()) {
+            id = format!("{}_{}", base, suffix);
+            suffix += 1;
+        }
+        self.ids.insert(key, id.clone());
+        id
+    }
+
+    /// Entity block header: the identifier, plus a readable `schema.table`
+    /// alias when the identifier differs from the plain `schema_table` name
+    fn header(&mut self, schema: &str, table: &str) -> String {
+        let id = self.id(schema, table);
+        if id == format!("{}_{}", schema, table) {
+            id
+        } else {
+            format!(
+                "{}[\"{}\"]",
+                id,
+                mermaid_comment_text(&format!("{}.{}", schema, table))
+            )
+        }
+    }
+}
+
+/// Make a name usable as a Mermaid entity or attribute identifier
+///
+/// Characters outside `[A-Za-z0-9_]` become `_`, and names that do not start
+/// with a letter get an `x` prefix.
+fn mermaid_identifier(name: &str) -> String {
+    let mut id: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if !id.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        id.insert(0, 'x');
+    }
+    id
+}
+
+/// Make a column type usable as a Mermaid attribute type, keeping the
+/// brackets of e.g. `varchar(255)` and `integer[]`
+fn mermaid_type(data_type: &str) -> String {
+    let ty: String = data_type
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '_' | '(' | ')' | '[' | ']') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if ty.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        ty
+    } else {
+        format!("x{}", ty)
+    }
+}
+
+/// Note carrying a column's original name when it had to be sanitized
+fn renamed_note(name: &str) -> Option<String> {
+    (mermaid_identifier(name) != name).then(|| format!("name: {}", mermaid_comment_text(name)))
+}
+
+/// Make free text safe inside a Mermaid attribute comment
+///
+/// Comments are double-quoted and single-line, with no escape syntax.
+fn mermaid_comment_text(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace('"', "'")
+}
+
+/// Generate a Graphviz DOT digraph from schema graph
+///
+/// Each `schema.table` becomes a node with an HTML-like label listing its
+/// columns (primary keys marked), and each foreign key an edge from the
+/// referencing table to the referenced one, labeled with the constraint name.
+pub fn generate_dot(schema_graph: &SchemaGraph) -> String {
+    let mut output = String::from("digraph schema {\n");
+    output.push_str("    rankdir=LR;\n");
+    output.push_str("    node [shape=plain, fontname=\"Helvetica\"];\n");
+    output.push_str("    edge [fontname=\"Helvetica\", fontsize=10];\n");
+
+    for table in &schema_graph.tables {
+        let full_name = format!("{}.{}", table.schema_name, table.table_name);
+        output.push_str(&format!(
+            "    {} [label=<\n        <table border=\"0\" cellborder=\"1\" cellspacing=\"0\">\n",
+            dot_id(&full_name)
+        ));
+        output.push_str(&format!(
+            "            <tr><td bgcolor=\"lightgrey\"><b>{}</b></td></tr>\n",
+            html_escape(&full_name)
+        ));
+
+        for col in &table.columns {
+            let pk_marker = if col.is_primary_key { " (PK)" } else { "" };
+            output.push_str(&format!(
+                "            <tr><td align=\"left\">{}: {}{}</td></tr>\n",
+                html_escape(&col.name),
+                html_escape(&col.data_type),
+                pk_marker
+            ));
+        }
+        output.push_str("        </table>\n    >];\n");
+    }
+
+    for fk in &schema_graph.foreign_keys {
+        let source = format!("{}.{}", fk.source_schema, fk.source_table);
+        let target = format!("{}.{}", fk.target_schema, fk.target_table);
+        output.push_str(&format!(
+            "    {} -> {} [label={}];\n",
+            dot_id(&source),
+            dot_id(&target),
+            dot_id(&fk.constraint_name)
+        ));
+    }
+
+    output.push_str("}\n");
+    output
+}
+
+/// Quote a string as a DOT identifier
+fn dot_id(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Escape text for use inside a DOT HTML-like label
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// JSON Schema (draft 2020-12) of a table's rows
+///
+/// Each column becomes a property typed after its Postgres type (see
+/// [`json_schema_type`]); NOT NULL columns are `required`, nullable ones also
+/// accept `null`, and enum columns list their labels. Primary key columns
+/// are listed in the non-standard `x-primary-key` field.
+pub fn generate_json_schema(schema_graph: &SchemaGraph, table: &TableInfo) -> serde_json::Value {
+    let mut properties = serde_json::Map::new();
+    let mut required = Vec::new();
+    let mut primary_key = Vec::new();
+
+    for column in &table.columns {
+        let ty = column.normalized_type(&schema_graph.enum_types);
+        let mut property = json_schema_type(&ty);
+        if let Some(values) = enum_labels(schema_graph, &ty) {
+            property.insert(
+                "enum".to_string(),
+                values
+                    .iter()
+                    .map(|v| serde_json::Value::from(v.as_str()))
+                    .collect(),
+            );
+        }
+        if column.is_nullable {
+            if let Some(ty) = property.get_mut("type") {
+                *ty = serde_json::json!([ty.take(), "null"]);
+            }
+            if let Some(serde_json::Value::Array(values)) = property.get_mut("enum") {
+                values.push(serde_json::Value::Null);
+            }
+        } else {
+            required.push(column.name.clone());
+        }
+        if let Some(comment) = &column.comment {
+            property.insert("description".to_string(), comment.as_str().into());
+        }
+        if column.is_primary_key {
+            primary_key.push(column.name.clone());
+        }
+        properties.insert(column.name.clone(), property.into());
+    }
+
+    let mut document = serde_json::json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": format!("{}.{}", table.schema_name, table.table_name),
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false,
+        "x-primary-key": primary_key,
+    });
+    if let Some(comment) = &table.comment {
+        document["description"] = comment.as_str().into();
+    }
+    document
+}
+
+/// JSON Schema keywords (`type`, `format`) for a column type
+///
+/// `json` and `jsonb` have no JSON equivalent and get no `type`, accepting
+/// any value; unknown types are strings.
+pub fn json_schema_type(ty: &NormalizedType) -> serde_json::Map<String, serde_json::Value> {
+    match ty {
+        NormalizedType::Integer { bits: 64 } => keywords("integer", Some("int64")),
+        NormalizedType::Integer { .. } => keywords("integer", Some("int32")),
+        NormalizedType::Float { bits: 32 } => keywords("number", Some("float")),
+        NormalizedType::Float { .. } => keywords("number", Some("double")),
+        NormalizedType::Decimal { .. } => keywords("number", None),
+        NormalizedType::Bool => keywords("boolean", None),
+        NormalizedType::Json => serde_json::Map::new(),
+        NormalizedType::Uuid => keywords("string", Some("uuid")),
+        NormalizedType::Date => keywords("string", Some("date")),
+        NormalizedType::Timestamp { .. } => keywords("string", Some("date-time")),
+        NormalizedType::Time => keywords("string", Some("time")),
+        NormalizedType::Array(_) => keywords("array", None),
+        NormalizedType::Text | NormalizedType::Enum(_) | NormalizedType::Other => {
+            keywords("string", None)
+        }
+    }
+}
+
+fn keywords(ty: &str, format: Option<&str>) -> serde_json::Map<String, serde_json::Value> {
+    let mut map = serde_json::Map::new();
+    map.insert("type".to_string(), ty.into());
+    if let Some(format) = format {
+        map.insert("format".to_string(), format.into());
+    }
+    map
+}
+
+/// Labels of an enum column's type
+fn enum_labels<'a>(schema_graph: &'a SchemaGraph, ty: &NormalizedType) -> Option<&'a Vec<String>> {
+    let NormalizedType::Enum(name) = ty else {
+        return None;
+    };
+    schema_graph
+        .enum_types
+        .iter()
+        .find(|e| *name == format!("{}.{}", e.schema, e.name))
+        .map(|e| &e.values)
+}
+
+/// A `(schema, table)` pair
+pub type TableKey = (String, String);
+
+/// FK lookup maps keyed by table
+pub(crate) type FkMap<'a> = HashMap<TableKey, Vec<&'a ForeignKey>>;
+
+/// Build the outbound (keyed by source table) and inbound (keyed by target
+/// table) foreign key maps
+pub(crate) fn build_fk_maps(schema_graph: &SchemaGraph) -> (FkMap<'_>, FkMap<'_>) {
+    let mut outbound_fks: FkMap = HashMap::new();
+    let mut inbound_fks: FkMap = HashMap::new();
+
+    for fk in &schema_graph.foreign_keys {
+        let source_key = (fk.source_schema.clone(), fk.source_table.clone());
+        let target_key = (fk.target_schema.clone(), fk.target_table.clone());
+
+        outbound_fks.entry(source_key).or_default().push(fk);
+        inbound_fks.entry(target_key).or_default().push(fk);
+    }
+
+    (outbound_fks, inbound_fks)
+}
+
+/// A table reached by following ON DELETE CASCADE foreign keys
+#[derive(Debug, Clone)]
+pub struct CascadeStep<'a> {
+    pub table: TableKey,
+    /// Level at which the table is first reached (1 = direct child)
+    pub depth: usize,
+    /// Foreign key through which the table is first reached
+    pub via: &'a ForeignKey,
+}
+
+/// Follow ON DELETE CASCADE edges breadth-first from a table, up to
+/// `max_depth` levels
+///
+/// Returns every affected table once, at the level where it is first
+/// reached, ordered by level. Already visited tables are skipped, so cycles
+/// terminate.
+pub fn cascade_chain<'a>(
+    schema_graph: &'a SchemaGraph,
+    schema: &str,
+    table: &str,
+    max_depth: usize,
+) -> Vec<CascadeStep<'a>> {
+    let (_, inbound_fks) = build_fk_maps(schema_graph);
+
+    let start: TableKey = (schema.to_string(), table.to_string());
+    let mut visited: HashSet<TableKey> = HashSet::from([start.clone()]);
+    let mut frontier = vec![start];
+    let mut steps = Vec::new();
+    let mut depth = 0;
+
+    while !frontier.is_empty() && depth < max_depth {
+        depth += 1;
+        let mut next = Vec::new();
+        for key in &frontier {
+            for fk in inbound_fks.get(key).into_iter().flatten() {
+                if fk.on_delete != FkAction::Cascade {
+                    continue;
+                }
+                let child = (fk.source_schema.clone(), fk.source_table.clone());
+                if visited.insert(child.clone()) {
+                    steps.push(CascadeStep {
+                        table: child.clone(),
+                        depth,
+                        via: fk,
+                    });
+                    next.push(child);
+                }
+            }
+        }
+        frontier = next;
+    }
+
+    steps
+}
+
+/// All tables in the graph, including ones only known through a foreign key,
+/// in sorted order
+fn graph_nodes(schema_graph: &SchemaGraph) -> Vec<TableKey> {
+    let mut nodes: Vec<TableKey> = schema_graph
+        .tables
+        .iter()
+        .map(|t| (t.schema_name.clone(), t.table_name.clone()))
+        .chain(schema_graph.foreign_keys.iter().flat_map(|fk| {
+            [
+                (fk.source_schema.clone(), fk.source_table.clone()),
+                (fk.target_schema.clone(), fk.target_table.clone()),
+            ]
+        }))
+        .collect();
+    nodes.sort();
+    nodes.dedup();
+    nodes
+}
+
+/// Error returned when tables cannot be ordered because of an FK cycle
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("foreign key cycle prevents ordering tables: {}", format_tables(.tables))]
+pub struct CycleError {
+    /// Tables left unordered: those on a cycle and those depending on one
+    pub tables: Vec<TableKey>,
+}
+
+fn format_tables(tables: &[TableKey]) -> String {
+    tables
+        .iter()
+        .map(|(schema, table)| format!("{}.{}", schema, table))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Compute a safe table creation order
+///
+/// Uses Kahn's algorithm over the FK dependency graph, where a table depends
+/// on the tables it references, so referenced tables come first. Self
+/// references do not constrain the order and are ignored. Ties are broken by
+/// `(schema, table)` so the order is deterministic.
+pub fn topological_table_order(schema_graph: &SchemaGraph) -> Result<Vec<TableKey>, CycleError> {
+    let (outbound_fks, inbound_fks) = build_fk_maps(schema_graph);
+    let nodes = graph_nodes(schema_graph);
+
+    // Number of distinct tables each table still waits on
+    let mut pending: HashMap<&TableKey, usize> = nodes
+        .iter()
+        .map(|node| {
+            let deps: HashSet<TableKey> = outbound_fks
+                .get(node)
+                .into_iter()
+                .flatten()
+                .map(|fk| (fk.target_schema.clone(), fk.target_table.clone()))
+                .filter(|target| target != node)
+                .collect();
+            (node, deps.len())
+        })
+        .collect();
+
+    let mut ready: BTreeSet<TableKey> = pending
+        .iter()
+        .filter(|(_, count)| **count == 0)
+        .map(|(node, _)| (*node).clone())
+        .collect();
+    let mut order = Vec::with_capacity(nodes.len());
+
+    while let Some(node) = ready.pop_first() {
+        // Each distinct dependent is released once per table it waits on
+        let dependents: HashSet<TableKey> = inbound_fks
+            .get(&node)
+            .into_iter()
+            .flatten()
+            .map(|fk| (fk.source_schema.clone(), fk.source_table.clone()))
+            .filter(|source| *source != node)
+            .collect();
+
+        for dependent in dependents {
+            if let Some(count) = pending.get_mut(&dependent) {
+                *count -= 1;
+                if *count == 0 {
+                    ready.insert(dependent);
+                }
+            }
+        }
+
+        order.push(node);
+    }
+
+    if order.len() < nodes.len() {
+        let placed: HashSet<&TableKey> = order.iter().collect();
+        let tables = nodes
+            .iter()
+            .filter(|node| !placed.contains(node))
+            .cloned()
+            .collect();
+        return Err(CycleError { tables });
+    }
+
+    Ok(order)
+}
+
+/// Find foreign key cycles
+///
+/// Runs a DFS with a recursion stack over the outbound FK graph and returns
+/// each cycle found as the ordered list of tables along it (a self-referencing
+/// table is a cycle of one). Tables are visited in sorted order so the result
+/// is deterministic.
+pub fn find_fk_cycles(schema_graph: &SchemaGraph) -> Vec<Vec<TableKey>> {
+    let (outbound_fks, _) = build_fk_maps(schema_graph);
+
+    // Sorted adjacency list (deduplicated targets per table)
+    let nodes = graph_nodes(schema_graph);
+
+    let adjacency: HashMap<&TableKey, Vec<TableKey>> = nodes
+        .iter()
+        .map(|node| {
+            let mut targets: Vec<TableKey> = outbound_fks
+                .get(node)
+                .map(|fks| {
+                    fks.iter()
+                        .map(|fk| (fk.target_schema.clone(), fk.target_table.clone()))
+                        .collect()
+                })
+                .unwrap_or_default();
+            targets.sort();
+            targets.dedup();
+            (node, targets)
+        })
+        .collect();
+
+    let mut cycles = Vec::new();
+    let mut visited: HashSet<TableKey> = HashSet::new();
+    let mut stack: Vec<TableKey> = Vec::new();
+    let mut on_stack: HashSet<TableKey> = HashSet::new();
+
+    for node in &nodes {
+        if !visited.contains(node) {
+            dfs_cycles(
+                node,
+                &adjacency,
+                &mut visited,
+                &mut stack,
+                &mut on_stack,
+                &mut cycles,
+            );
+        }
+    }
+
+    cycles
+}
+
+fn dfs_cycles(
+    node: &TableKey,
+    adjacency: &HashMap<&TableKey, Vec<TableKey>>,
+    visited: &mut HashSet<TableKey>,
+    stack: &mut Vec<TableKey>,
+    on_stack: &mut HashSet<TableKey>,
+    cycles: &mut Vec<Vec<TableKey>>,
+) {
+    visited.insert(node.clone());
+    stack.push(node.clone());
+    on_stack.insert(node.clone());
+
+    for next in adjacency.get(node).into_iter().flatten() {
+        if on_stack.contains(next) {
+            // Back edge: the cycle is the stack from `next` to the top
+            let start = stack.iter().position(|n| n == next).unwrap_or(0);
+            cycles.push(stack[start..].to_vec());
+        } else if !visited.contains(next) {
+            dfs_cycles(next, adjacency, visited, stack, on_stack, cycles);
+        }
+    }
+
+    stack.pop();
+    on_stack.remove(node);
+}
+
 /// Find related tables within N hops
 pub fn find_related_tables(
     schema_graph: &SchemaGraph,
     schema: &str,
     table: &str,
     max_hops: usize,
+) -> Vec<RelatedTable> {
+    walk_related_tables(schema_graph, schema, table, max_hops, true)
+}
+
+/// Find the tables that reference a table, directly or through other
+/// referencing tables, within N hops
+///
+/// Unlike [`find_related_tables`], only inbound foreign keys are followed, so
+/// every result is a [`RelationType::References`] whose path leads back to
+/// the table.
+pub fn find_referencing_tables(
+    schema_graph: &SchemaGraph,
+    schema: &str,
+    table: &str,
+    max_hops: usize,
+) -> Vec<RelatedTable> {
+    walk_related_tables(schema_graph, schema, table, max_hops, false)
+}
+
+/// Breadth-first walk over inbound and, if `follow_outbound`, outbound
+/// foreign keys
+fn walk_related_tables(
+    schema_graph: &SchemaGraph,
+    schema: &str,
+    table: &str,
+    max_hops: usize,
+    follow_outbound: bool,
 ) -> Vec<RelatedTable> {
     let mut visited: HashSet<(String, String)> = HashSet::new();
     let mut result: Vec<RelatedTable> = Vec::new();
-    let mut queue: Vec<((String, String), usize, Vec<String>)> = Vec::new();
+    // Breadth-first so each table is reported with its shortest hop count
+    let mut queue: VecDeque<((String, String), usize, Vec<String>)> = VecDeque::new();
 
     let start = (schema.to_string(), table.to_string());
     visited.insert(start.clone());
-    queue.push((start, 0, vec![]));
+    queue.push_back((start, 0, vec![]));
 
     // Build FK lookup maps for efficient traversal
-    let mut outbound_fks: HashMap<(String, String), Vec<&ForeignKey>> = HashMap::new();
-    let mut inbound_fks: HashMap<(String, String), Vec<&ForeignKey>> = HashMap::new();
-
-    for fk in &schema_graph.foreign_keys {
-        let source_key = (fk.source_schema.clone(), fk.source_table.clone());
-        let target_key = (fk.target_schema.clone(), fk.target_table.clone());
-
-        outbound_fks.entry(source_key).or_default().push(fk);
-        inbound_fks.entry(target_key).or_default().push(fk);
-    }
+    let (outbound_fks, inbound_fks) = build_fk_maps(schema_graph);
 
-    while let Some(((current_schema, current_table), depth, path)) = queue.pop() {
+    while let Some(((current_schema, current_table), depth, path)) = queue.pop_front() {
         if depth >= max_hops {
             continue;
         }
@@ -77,7 +745,7 @@ pub fn find_related_tables(
         let current_key = (current_schema.clone(), current_table.clone());
 
         // Follow outbound FKs (this table references another)
-        if let Some(fks) = outbound_fks.get(&current_key) {
+        if let Some(fks) = outbound_fks.get(&current_key).filter(|_| follow_outbound) {
             for fk in fks {
                 let next_key = (fk.target_schema.clone(), fk.target_table.clone());
                 if !visited.contains(&next_key) {
@@ -93,7 +761,7 @@ pub fn find_related_tables(
                         hop_count: depth + 1,
                     });
 
-                    queue.push((next_key, depth + 1, new_path));
+                    queue.push_back((next_key, depth + 1, new_path));
                 }
             }
         }
@@ -115,7 +783,7 @@ pub fn find_related_tables(
                         hop_count: depth + 1,
                     });
 
-                    queue.push((next_key, depth + 1, new_path));
+                    queue.push_back((next_key, depth + 1, new_path));
                 }
             }
         }
@@ -143,6 +811,243 @@ pub enum RelationType {
     ReferencedBy,
 }
 
+/// Size summary of one schema
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SchemaStats {
+    pub schema_name: String,
+    pub table_count: usize,
+    /// Sum of the tables' estimated row counts
+    pub total_estimated_rows: i64,
+    /// Foreign keys whose source table is in this schema
+    pub fk_count: usize,
+}
+
+/// Per-schema table, row and foreign key counts, sorted by schema name
+pub fn schema_stats(schema_graph: &SchemaGraph) -> Vec<SchemaStats> {
+    fn stats_for<'a>(
+        stats: &'a mut BTreeMap<String, SchemaStats>,
+        schema: &str,
+    ) -> &'a mut SchemaStats {
+        stats
+            .entry(schema.to_string())
+            .or_insert_with(|| SchemaStats {
+                schema_name: schema.to_string(),
+                table_count: 0,
+                total_estimated_rows: 0,
+                fk_count: 0,
+            })
+    }
+
+    let mut stats = BTreeMap::new();
+    for table in &schema_graph.tables {
+        let s = stats_for(&mut stats, &table.schema_name);
+        s.table_count += 1;
+        // A negative estimate means unknown, not fewer rows
+        s.total_estimated_rows += table.estimated_row_count.max(0);
+    }
+    for fk in &schema_graph.foreign_keys {
+        stats_for(&mut stats, &fk.source_schema).fk_count += 1;
+    }
+
+    stats.into_values().collect()
+}
+
+/// Inconsistency found in a schema graph by [`validate_graph`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum GraphWarning {
+    /// An FK whose source or target table is not in `tables`, typically a
+    /// table that failed to restore
+    DanglingForeignKey {
+        constraint_name: String,
+        source_table: String,
+        target_table: String,
+        /// The absent table, `schema.table`
+        missing_table: String,
+    },
+    /// More than one FK with the same name on one table
+    DuplicateConstraintName {
+        constraint_name: String,
+        table: String,
+        count: usize,
+    },
+    /// An FK with different numbers of source and target columns
+    ColumnCountMismatch {
+        constraint_name: String,
+        source_table: String,
+        source_columns: usize,
+        target_columns: usize,
+    },
+}
+
+/// Check a schema graph for foreign keys that cannot be right
+///
+/// Reports FKs referencing a table missing from the graph, constraint names
+/// repeated on one table (PostgreSQL requires them to be unique per table)
+/// and composite keys whose column lists differ in length. An empty result
+/// means the graph is consistent.
+pub fn validate_graph(schema_graph: &SchemaGraph) -> Vec<GraphWarning> {
+    let tables: HashSet<(&str, &str)> = schema_graph
+        .tables
+        .iter()
+        .map(|t| (t.schema_name.as_str(), t.table_name.as_str()))
+        .collect();
+    let mut warnings = Vec::new();
+
+    for fk in &schema_graph.foreign_keys {
+        let source = format!("{}.{}", fk.source_schema, fk.source_table);
+        let target = format!("{}.{}", fk.target_schema, fk.target_table);
+        let mut sides = vec![(&fk.source_schema, &fk.source_table, &source)];
+        // A self-reference to a missing table is reported once
+        if target != source {
+            sides.push((&fk.target_schema, &fk.target_table, &target));
+        }
+        for (schema, table, name) in sides {
+            if !tables.contains(&(schema.as_str(), table.as_str())) {
+                warnings.push(GraphWarning::DanglingForeignKey {
+                    constraint_name: fk.constraint_name.clone(),
+                    source_table: source.clone(),
+                    target_table: target.clone(),
+                    missing_table: name.clone(),
+                });
+            }
+        }
+
+        if fk.source_columns.len() != fk.target_columns.len() {
+            warnings.push(GraphWarning::ColumnCountMismatch {
+                constraint_name: fk.constraint_name.clone(),
+                source_table: source,
+                source_columns: fk.source_columns.len(),
+                target_columns: fk.target_columns.len(),
+            });
+        }
+    }
+
+    let mut names: BTreeMap<(&str, &str, &str), usize> = BTreeMap::new();
+    for fk in &schema_graph.foreign_keys {
+        *names
+            .entry((&fk.source_schema, &fk.source_table, &fk.constraint_name))
+            .or_default() += 1;
+    }
+    warnings.extend(names.into_iter().filter(|(_, count)| *count > 1).map(
+        |((schema, table, name), count)| GraphWarning::DuplicateConstraintName {
+            constraint_name: name.to_string(),
+            table: format!("{}.{}", schema, table),
+            count,
+        },
+    ));
+
+    warnings
+}
+
+/// Roll partitions into their partitioned table
+///
+/// Each partition is removed from `tables` and its estimated row count added
+/// to its top-level parent, following sub-partitions up every level. Foreign
+/// keys on or to a partition are moved to the parent, dropping the copies
+/// PostgreSQL clones onto every partition; partition indexes and triggers are
+/// dropped, as the parent has its own. Partitions whose parent is not in the
+/// graph are kept.
+pub fn group_partitions(schema_graph: &SchemaGraph) -> SchemaGraph {
+    let key = |t: &TableInfo| (t.schema_name.clone(), t.table_name.clone());
+    let present: HashSet<TableKey> = schema_graph.tables.iter().map(key).collect();
+    let parents: HashMap<TableKey, TableKey> = schema_graph
+        .tables
+        .iter()
+        .filter_map(|t| {
+            let (schema, table) = t.partition_of.as_deref()?.split_once('.')?;
+            Some((key(t), (schema.to_string(), table.to_string())))
+        })
+        .collect();
+
+    // Partition -> top-level parent, for partitions that can be rolled up
+    let roots: HashMap<TableKey, TableKey> = parents
+        .keys()
+        .filter_map(|partition| {
+            let mut root = partition;
+            // Bounded so a malformed parent loop cannot hang
+            for _ in 0..parents.len() {
+                match parents.get(root) {
+                    Some(parent) => root = parent,
+                    None => break,
+                }
+            }
+            (root != partition && present.contains(root) && !parents.contains_key(root))
+                .then(|| (partition.clone(), root.clone()))
+        })
+        .collect();
+    let rolled_up =
+        |schema: &str, table: &str| roots.get(&(schema.to_string(), table.to_string())).cloned();
+
+    let mut partition_rows: HashMap<&TableKey, i64> = HashMap::new();
+    for table in &schema_graph.tables {
+        if let Some(root) = roots.get(&key(table)) {
+            // A negative estimate means unknown, not fewer rows
+            *partition_rows.entry(root).or_default() += table.estimated_row_count.max(0);
+        }
+    }
+
+    let tables = schema_graph
+        .tables
+        .iter()
+        .filter(|t| !roots.contains_key(&key(t)))
+        .map(|t| {
+            let mut table = t.clone();
+            if let Some(rows) = partition_rows.get(&key(t)) {
+                table.estimated_row_count = table.estimated_row_count.max(0) + rows;
+            }
+            table
+        })
+        .collect();
+
+    let mut seen = HashSet::new();
+    let foreign_keys = schema_graph
+        .foreign_keys
+        .iter()
+        .map(|fk| {
+            let mut fk = fk.clone();
+            if let Some((schema, table)) = rolled_up(&fk.source_schema, &fk.source_table) {
+                fk.source_schema = schema;
+                fk.source_table = table;
+            }
+            if let Some((schema, table)) = rolled_up(&fk.target_schema, &fk.target_table) {
+                fk.target_schema = schema;
+                fk.target_table = table;
+            }
+            fk
+        })
+        .filter(|fk| {
+            seen.insert((
+                fk.constraint_name.clone(),
+                fk.source_schema.clone(),
+                fk.source_table.clone(),
+                fk.source_columns.clone(),
+                fk.target_schema.clone(),
+                fk.target_table.clone(),
+                fk.target_columns.clone(),
+            ))
+        })
+        .collect();
+
+    SchemaGraph {
+        tables,
+        foreign_keys,
+        indexes: schema_graph
+            .indexes
+            .iter()
+            .filter(|i| rolled_up(&i.schema_name, &i.table_name).is_none())
+            .cloned()
+            .collect(),
+        triggers: schema_graph
+            .triggers
+            .iter()
+            .filter(|t| rolled_up(&t.schema, &t.table).is_none())
+            .cloned()
+            .collect(),
+        ..schema_graph.clone()
+    }
+}
+
 /// Filter schema graph by schemas
 pub fn filter_by_schemas(schema_graph: &SchemaGraph, schemas: &[&str]) -> SchemaGraph {
     let schema_set: HashSet<&str> = schemas.iter().copied().collect();
@@ -169,16 +1074,42 @@ pub fn filter_by_schemas(schema_graph: &SchemaGraph, schemas: &[&str]) -> Schema
         .cloned()
         .collect();
 
+    let indexes: Vec<IndexInfo> = schema_graph
+        .indexes
+        .iter()
+        .filter(|idx| table_set.contains(&(idx.schema_name.clone(), idx.table_name.clone())))
+        .cloned()
+        .collect();
+
+    let views: Vec<ViewInfo> = schema_graph
+        .views
+        .iter()
+        .filter(|v| schema_set.contains(v.schema.as_str()))
+        .cloned()
+        .collect();
+
+    let sequences: Vec<SequenceInfo> = schema_graph
+        .sequences
+        .iter()
+        .filter(|s| schema_set.contains(s.schema.as_str()))
+        .cloned()
+        .collect();
+
     SchemaGraph {
         tables,
         foreign_keys,
+        indexes,
+        views,
+        triggers: vec![],
+        enum_types: vec![],
+        sequences,
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::domain::{ColumnInfo, FkAction};
+    use crate::domain::{ColumnInfo, EnumType, FkAction};
 
     fn create_test_schema() -> SchemaGraph {
         SchemaGraph {
@@ -194,6 +1125,11 @@ mod tests {
                             is_nullable: false,
                             is_primary_key: true,
                             default_value: None,
+                            comment: None,
+                            ordinal_position: None,
+                            is_identity: false,
+                            identity_generation: None,
+                            generation_expression: None,
                         },
                         ColumnInfo {
                             name: "name".to_string(),
@@ -201,8 +1137,17 @@ mod tests {
                             is_nullable: false,
                             is_primary_key: false,
                             default_value: None,
+                            comment: None,
+                            ordinal_position: None,
+                            is_identity: false,
+                            identity_generation: None,
+                            generation_expression: None,
                         },
                     ],
+                    comment: None,
+                    is_partition: false,
+                    partition_of: None,
+                    partition_strategy: None,
                 },
                 TableInfo {
                     schema_name: "public".to_string(),
@@ -215,6 +1160,11 @@ mod tests {
                             is_nullable: false,
                             is_primary_key: true,
                             default_value: None,
+                            comment: None,
+                            ordinal_position: None,
+                            is_identity: false,
+                            identity_generation: None,
+                            generation_expression: None,
                         },
                         ColumnInfo {
                             name: "user_id".to_string(),
@@ -222,8 +1172,17 @@ mod tests {
                             is_nullable: false,
                             is_primary_key: false,
                             default_value: None,
+                            comment: None,
+                            ordinal_position: None,
+                            is_identity: false,
+                            identity_generation: None,
+                            generation_expression: None,
                         },
                     ],
+                    comment: None,
+                    is_partition: false,
+                    partition_of: None,
+                    partition_strategy: None,
                 },
                 TableInfo {
                     schema_name: "public".to_string(),
@@ -236,6 +1195,11 @@ mod tests {
                             is_nullable: false,
                             is_primary_key: true,
                             default_value: None,
+                            comment: None,
+                            ordinal_position: None,
+                            is_identity: false,
+                            identity_generation: None,
+                            generation_expression: None,
                         },
                         ColumnInfo {
                             name: "order_id".to_string(),
@@ -243,8 +1207,17 @@ mod tests {
                             is_nullable: false,
                             is_primary_key: false,
                             default_value: None,
+                            comment: None,
+                            ordinal_position: None,
+                            is_identity: false,
+                            identity_generation: None,
+                            generation_expression: None,
                         },
                     ],
+                    comment: None,
+                    is_partition: false,
+                    partition_of: None,
+                    partition_strategy: None,
                 },
             ],
             foreign_keys: vec![
@@ -271,6 +1244,11 @@ mod tests {
                     on_update: FkAction::NoAction,
                 },
             ],
+            indexes: vec![],
+            views: vec![],
+            triggers: vec![],
+            enum_types: vec![],
+            sequences: vec![],
         }
     }
 
@@ -285,6 +1263,533 @@ mod tests {
         assert!(mermaid.contains("fk_orders_user"));
     }
 
+    #[test]
+    fn test_generate_mermaid_er_renders_column_comments() {
+        let mut schema = create_test_schema();
+        schema.tables[0].comment = Some("Registered accounts".to_string());
+        schema.tables[0].columns[0].comment = Some("Surrogate key".to_string());
+        schema.tables[0].columns[1].is_nullable = true;
+        schema.tables[0].columns[1].comment = Some("Display \"nick\",\nnot unique".to_string());
+
+        let mermaid = generate_mermaid_er(&schema);
+        assert!(mermaid.contains("        integer id PK \"NOT NULL; Surrogate key\"\n"));
+        assert!(mermaid.contains("        varchar name \"Display 'nick', not unique\"\n"));
+
+        // Comments survive the JSON round trip used by the schema cache
+        let json = serde_json::to_string(&schema).unwrap();
+        let cached: SchemaGraph = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            cached.tables[0].comment.as_deref(),
+            Some("Registered accounts")
+        );
+        assert_eq!(
+            cached.tables[0].columns[0].comment.as_deref(),
+            Some("Surrogate key")
+        );
+    }
+
+    #[test]
+    fn test_schema_graph_without_comments_deserializes() {
+        let json = r#"{
+            "tables": [{
+                "schema_name": "public",
+                "table_name": "users",
+                "estimated_row_count": 1,
+                "columns": [{
+                    "name": "id",
+                    "data_type": "integer",
+                    "is_nullable": false,
+                    "is_primary_key": true,
+                    "default_value": null
+                }]
+            }],
+            "foreign_keys": []
+        }"#;
+        let graph: SchemaGraph = serde_json::from_str(json).unwrap();
+        assert_eq!(graph.tables[0].comment, None);
+        assert_eq!(graph.tables[0].columns[0].comment, None);
+        assert!(!graph.tables[0].columns[0].is_identity);
+        assert_eq!(graph.tables[0].columns[0].generation_expression, None);
+    }
+
+    #[test]
+    fn test_generate_mermaid_er_marks_identity_and_generated_columns() {
+        let mut schema = create_test_schema();
+        schema.tables[0].columns[0].is_identity = true;
+        schema.tables[0].columns[0].identity_generation = Some("ALWAYS".to_string());
+        schema.tables[0].columns[1].is_nullable = true;
+        schema.tables[0].columns[1].generation_expression = Some("lower(email)".to_string());
+
+        let mermaid = generate_mermaid_er(&schema);
+        assert!(mermaid.contains("        integer id PK \"NOT NULL; IDENTITY ALWAYS\"\n"));
+        assert!(mermaid.contains("        varchar name \"GENERATED\"\n"));
+    }
+
+    fn active_users_view(schema: &str, materialized: bool) -> ViewInfo {
+        ViewInfo {
+            schema: schema.to_string(),
+            name: "active_users".to_string(),
+            definition: "SELECT id FROM users WHERE active".to_string(),
+            is_materialized: materialized,
+            columns: vec![ColumnInfo {
+                name: "id".to_string(),
+                data_type: "integer".to_string(),
+                is_nullable: true,
+                is_primary_key: false,
+                default_value: None,
+                comment: None,
+                ordinal_position: None,
+                is_identity: false,
+                identity_generation: None,
+                generation_expression: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_generate_mermaid_er_sanitizes_names() {
+        let mut schema = create_test_schema();
+        schema.tables[0].table_name = "user-profiles".to_string();
+        schema.tables[0].columns[1].name = "order date".to_string();
+        schema.tables[0].columns[1].data_type = "timestamp with time zone".to_string();
+        schema.foreign_keys[0].target_table = "user-profiles".to_string();
+        // Sanitizes to the same identifier as "user-profiles"
+        schema.tables.push(TableInfo {
+            schema_name: "public".to_string(),
+            table_name: "user_profiles".to_string(),
+            estimated_row_count: 0,
+            columns: vec![],
+            comment: None,
+            is_partition: false,
+            partition_of: None,
+            partition_strategy: None,
+        });
+
+        let mermaid = generate_mermaid_er(&schema);
+        assert!(mermaid.contains("    public_user_profiles[\"public.user-profiles\"] {\n"));
+        assert!(mermaid.contains(
+            "        timestamp_with_time_zone order_date \"name: order date; NOT NULL\"\n"
+        ));
+        // The FK edge points at the same sanitized entity
+        assert!(mermaid
+            .contains("    public_user_profiles ||--o{ public_orders : \"fk_orders_user\"\n"));
+        // A colliding table keeps a distinct identifier
+        assert!(mermaid.contains("    public_user_profiles_2[\"public.user_profiles\"] {\n"));
+        assert!(!mermaid.contains("user-profiles {"));
+    }
+
+    #[test]
+    fn test_mermaid_cardinality_follows_uniqueness_and_nullability() {
+        let mut schema = create_test_schema();
+        let edge = |schema: &SchemaGraph| {
+            generate_mermaid_er(schema)
+                .lines()
+                .find(|line| line.ends_with("\"fk_orders_user\""))
+                .unwrap()
+                .trim()
+                .to_string()
+        };
+        assert_eq!(
+            edge(&schema),
+            "public_users ||--o{ public_orders : \"fk_orders_user\""
+        );
+
+        // One-to-one: orders.user_id is unique
+        let unique_user = IndexInfo {
+            name: "orders_user_id_key".to_string(),
+            schema_name: "public".to_string(),
+            table_name: "orders".to_string(),
+            columns: vec!["user_id".to_string()],
+            is_unique: true,
+            is_primary: false,
+            method: "btree".to_string(),
+            predicate: None,
+        };
+        schema.indexes.push(unique_user.clone());
+        assert_eq!(
+            edge(&schema),
+            "public_users ||--|| public_orders : \"fk_orders_user\""
+        );
+
+        // Zero-or-one: the unique column is nullable
+        let user_id = schema.tables[1]
+            .columns
+            .iter_mut()
+            .find(|c| c.name == "user_id")
+            .unwrap();
+        user_id.is_nullable = true;
+        assert_eq!(
+            edge(&schema),
+            "public_users |o--|| public_orders : \"fk_orders_user\""
+        );
+
+        // A partial unique index does not make the column unique
+        schema.indexes = vec![IndexInfo {
+            predicate: Some("user_id IS NOT NULL".to_string()),
+            ..unique_user
+        }];
+        assert_eq!(
+            edge(&schema),
+            "public_users |o--o{ public_orders : \"fk_orders_user\""
+        );
+    }
+
+    #[test]
+    fn test_mermaid_identifier() {
+        assert_eq!(mermaid_identifier("orders"), "orders");
+        assert_eq!(mermaid_identifier("order date"), "order_date");
+        assert_eq!(mermaid_identifier("2fa-codes"), "x2fa_codes");
+        assert_eq!(mermaid_identifier("_private"), "x_private");
+        assert_eq!(
+            mermaid_type("character varying(255)"),
+            "character_varying(255)"
+        );
+        assert_eq!(mermaid_type("\"char\""), "x_char_");
+    }
+
+    #[test]
+    fn test_generate_mermaid_er_renders_views_dashed() {
+        let mut schema = create_test_schema();
+        schema.views.push(active_users_view("public", true));
+        let mermaid = generate_mermaid_er(&schema);
+
+        assert!(mermaid.contains("    public_active_users {\n        integer id\n    }\n"));
+        assert!(mermaid.contains("classDef view stroke-dasharray: 5 5"));
+        assert!(mermaid.contains("class public_active_users view"));
+        assert!(!generate_mermaid_er(&create_test_schema()).contains("classDef"));
+    }
+
+    #[test]
+    fn test_generate_dot() {
+        let schema = create_test_schema();
+        let dot = generate_dot(&schema);
+
+        assert!(dot.starts_with("digraph"));
+        for table in &schema.tables {
+            assert!(dot.contains(&format!(
+                "\"{}.{}\" [label=<",
+                table.schema_name, table.table_name
+            )));
+        }
+        for fk in &schema.foreign_keys {
+            assert!(dot.contains(&format!(
+                "\"{}.{}\" -> \"{}.{}\" [label=\"{}\"]",
+                fk.source_schema,
+                fk.source_table,
+                fk.target_schema,
+                fk.target_table,
+                fk.constraint_name
+            )));
+        }
+        assert!(dot.contains("(PK)"));
+    }
+
+    #[test]
+    fn test_generate_dot_escapes_labels() {
+        assert_eq!(dot_id("a\"b"), "\"a\\\"b\"");
+        assert_eq!(html_escape("<b>&"), "&lt;b&gt;&amp;");
+    }
+
+    #[test]
+    fn test_json_schema_type_mapping() {
+        let cases = [
+            (
+                "integer",
+                serde_json::json!({"type": "integer", "format": "int32"}),
+            ),
+            (
+                "bigint",
+                serde_json::json!({"type": "integer", "format": "int64"}),
+            ),
+            ("numeric", serde_json::json!({"type": "number"})),
+            ("boolean", serde_json::json!({"type": "boolean"})),
+            (
+                "character varying(255)",
+                serde_json::json!({"type": "string"}),
+            ),
+            (
+                "timestamp(3) with time zone",
+                serde_json::json!({"type": "string", "format": "date-time"}),
+            ),
+            (
+                "uuid",
+                serde_json::json!({"type": "string", "format": "uuid"}),
+            ),
+            ("ARRAY", serde_json::json!({"type": "array"})),
+            ("text[]", serde_json::json!({"type": "array"})),
+            ("jsonb", serde_json::json!({})),
+        ];
+        for (data_type, expected) in cases {
+            assert_eq!(
+                serde_json::Value::from(json_schema_type(&NormalizedType::from_pg_type(
+                    data_type,
+                    &[]
+                ))),
+                expected,
+                "{}",
+                data_type
+            );
+        }
+    }
+
+    #[test]
+    fn test_generate_json_schema() {
+        let mut schema = create_test_schema();
+        schema.enum_types.push(EnumType {
+            schema: "public".to_string(),
+            name: "mood".to_string(),
+            values: vec!["happy".to_string(), "sad".to_string()],
+        });
+        let mut table = schema.tables[0].clone();
+        table.columns.push(ColumnInfo {
+            name: "mood".to_string(),
+            data_type: "public.mood".to_string(),
+            is_nullable: true,
+            is_primary_key: false,
+            default_value: None,
+            comment: Some("Current mood".to_string()),
+            ordinal_position: None,
+            is_identity: false,
+            identity_generation: None,
+            generation_expression: None,
+        });
+
+        let document = generate_json_schema(&schema, &table);
+
+        assert_eq!(document["title"], "public.users");
+        assert_eq!(document["required"], serde_json::json!(["id", "name"]));
+        assert_eq!(document["x-primary-key"], serde_json::json!(["id"]));
+        assert_eq!(
+            document["properties"]["mood"],
+            serde_json::json!({
+                "type": ["string", "null"],
+                "enum": ["happy", "sad", null],
+                "description": "Current mood",
+            })
+        );
+    }
+
+    fn fk(name: &str, source: &str, target: &str) -> ForeignKey {
+        ForeignKey {
+            constraint_name: name.to_string(),
+            source_schema: "public".to_string(),
+            source_table: source.to_string(),
+            source_columns: vec![format!("{}_id", target)],
+            target_schema: "public".to_string(),
+            target_table: target.to_string(),
+            target_columns: vec!["id".to_string()],
+            on_delete: FkAction::NoAction,
+            on_update: FkAction::NoAction,
+        }
+    }
+
+    #[test]
+    fn test_schema_stats_two_schemas() {
+        let mut schema = create_test_schema();
+        let mut invoices = schema.tables[0].clone();
+        invoices.schema_name = "billing".to_string();
+        invoices.table_name = "invoices".to_string();
+        invoices.estimated_row_count = 40;
+        schema.tables.push(invoices);
+        schema.foreign_keys.push(ForeignKey {
+            source_schema: "billing".to_string(),
+            ..fk("fk_invoices_user", "invoices", "users")
+        });
+
+        assert_eq!(
+            schema_stats(&schema),
+            vec![
+                SchemaStats {
+                    schema_name: "billing".to_string(),
+                    table_count: 1,
+                    total_estimated_rows: 40,
+                    fk_count: 1,
+                },
+                SchemaStats {
+                    schema_name: "public".to_string(),
+                    table_count: 3,
+                    total_estimated_rows: 2600,
+                    fk_count: 2,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_group_partitions() {
+        let mut schema = create_test_schema();
+        let orders = schema
+            .tables
+            .iter_mut()
+            .find(|t| t.table_name == "orders")
+            .unwrap();
+        orders.estimated_row_count = 0;
+        orders.partition_strategy = Some("range".to_string());
+        let parent = orders.clone();
+        for (name, rows) in [("orders_2023", 300), ("orders_2024", 500)] {
+            schema.tables.push(TableInfo {
+                table_name: name.to_string(),
+                estimated_row_count: rows,
+                is_partition: true,
+                partition_of: Some("public.orders".to_string()),
+                partition_strategy: None,
+                ..parent.clone()
+            });
+            // PostgreSQL clones the parent's foreign keys onto each partition
+            let mut cloned = schema
+                .foreign_keys
+                .iter()
+                .find(|fk| fk.source_table == "orders")
+                .unwrap()
+                .clone();
+            cloned.source_table = name.to_string();
+            schema.foreign_keys.push(cloned);
+        }
+
+        let grouped = group_partitions(&schema);
+
+        let names: Vec<&str> = grouped
+            .tables
+            .iter()
+            .map(|t| t.table_name.as_str())
+            .collect();
+        assert_eq!(names, vec!["users", "orders", "order_items"]);
+        let orders = &grouped.tables[1];
+        assert_eq!(orders.estimated_row_count, 800);
+        assert_eq!(orders.partition_strategy.as_deref(), Some("range"));
+        assert_eq!(
+            grouped.foreign_keys.len(),
+            create_test_schema().foreign_keys.len()
+        );
+        // Without partitions the graph is unchanged
+        assert_eq!(
+            group_partitions(&create_test_schema()).tables.len(),
+            create_test_schema().tables.len()
+        );
+    }
+
+    #[test]
+    fn test_validate_graph_consistent() {
+        assert!(validate_graph(&create_test_schema()).is_empty());
+    }
+
+    #[test]
+    fn test_validate_graph_dangling_fk() {
+        let mut schema = create_test_schema();
+        schema
+            .foreign_keys
+            .push(fk("fk_orders_category", "orders", "categories"));
+
+        assert_eq!(
+            validate_graph(&schema),
+            vec![GraphWarning::DanglingForeignKey {
+                constraint_name: "fk_orders_category".to_string(),
+                source_table: "public.orders".to_string(),
+                target_table: "public.categories".to_string(),
+                missing_table: "public.categories".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_validate_graph_column_count_mismatch() {
+        let mut schema = create_test_schema();
+        let mut composite = fk("fk_order_items_order_version", "order_items", "orders");
+        composite.source_columns = vec!["order_id".to_string(), "order_version".to_string()];
+        schema.foreign_keys.push(composite);
+
+        assert_eq!(
+            validate_graph(&schema),
+            vec![GraphWarning::ColumnCountMismatch {
+                constraint_name: "fk_order_items_order_version".to_string(),
+                source_table: "public.order_items".to_string(),
+                source_columns: 2,
+                target_columns: 1,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_validate_graph_duplicate_constraint_name() {
+        let mut schema = create_test_schema();
+        let duplicate = schema.foreign_keys[0].clone();
+        let table = format!("{}.{}", duplicate.source_schema, duplicate.source_table);
+        let name = duplicate.constraint_name.clone();
+        schema.foreign_keys.push(duplicate);
+
+        assert_eq!(
+            validate_graph(&schema),
+            vec![GraphWarning::DuplicateConstraintName {
+                constraint_name: name,
+                table,
+                count: 2,
+            }]
+        );
+    }
+
+    fn key(table: &str) -> TableKey {
+        ("public".to_string(), table.to_string())
+    }
+
+    #[test]
+    fn test_find_fk_cycles_none() {
+        assert!(find_fk_cycles(&create_test_schema()).is_empty());
+    }
+
+    #[test]
+    fn test_find_fk_cycles_two_tables() {
+        let mut schema = create_test_schema();
+        schema
+            .foreign_keys
+            .push(fk("fk_users_last_order", "users", "orders"));
+
+        let cycles = find_fk_cycles(&schema);
+        assert_eq!(cycles, vec![vec![key("orders"), key("users")]]);
+    }
+
+    #[test]
+    fn test_find_fk_cycles_self_reference() {
+        let mut schema = create_test_schema();
+        schema
+            .foreign_keys
+            .push(fk("fk_users_manager", "users", "users"));
+
+        let cycles = find_fk_cycles(&schema);
+        assert_eq!(cycles, vec![vec![key("users")]]);
+    }
+
+    #[test]
+    fn test_topological_table_order() {
+        let order = topological_table_order(&create_test_schema()).unwrap();
+        assert_eq!(order, vec![key("users"), key("orders"), key("order_items")]);
+    }
+
+    #[test]
+    fn test_topological_table_order_ignores_self_reference() {
+        let mut schema = create_test_schema();
+        schema
+            .foreign_keys
+            .push(fk("fk_users_manager", "users", "users"));
+
+        let order = topological_table_order(&schema).unwrap();
+        assert_eq!(order, vec![key("users"), key("orders"), key("order_items")]);
+    }
+
+    #[test]
+    fn test_topological_table_order_cycle() {
+        let mut schema = create_test_schema();
+        schema
+            .foreign_keys
+            .push(fk("fk_users_last_order", "users", "orders"));
+
+        let err = topological_table_order(&schema).unwrap_err();
+        assert_eq!(
+            err.tables,
+            vec![key("order_items"), key("orders"), key("users")]
+        );
+        assert!(err.to_string().contains("public.orders"));
+    }
+
     #[test]
     fn test_find_related_tables_one_hop() {
         let schema = create_test_schema();
@@ -308,6 +1813,45 @@ mod tests {
         assert!(table_names.contains(&"order_items"));
     }
 
+    #[test]
+    fn test_find_referencing_tables() {
+        let schema = create_test_schema();
+
+        let referents = find_referencing_tables(&schema, "public", "users", 5);
+        let found: Vec<(&str, usize, Vec<&str>)> = referents
+            .iter()
+            .map(|r| {
+                assert_eq!(r.relationship, RelationType::References);
+                (
+                    r.table.as_str(),
+                    r.hop_count,
+                    r.path.iter().map(String::as_str).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("orders", 1, vec!["fk_orders_user"]),
+                (
+                    "order_items",
+                    2,
+                    vec!["fk_orders_user", "fk_order_items_order"]
+                ),
+            ]
+        );
+
+        // Tables orders references are not referents
+        let referents = find_referencing_tables(&schema, "public", "orders", 5);
+        assert_eq!(referents.len(), 1);
+        assert_eq!(referents[0].table, "order_items");
+        assert!(find_referencing_tables(&schema, "public", "order_items", 5).is_empty());
+        assert_eq!(
+            find_referencing_tables(&schema, "public", "users", 1).len(),
+            1
+        );
+    }
+
     #[test]
     fn test_filter_by_schemas() {
         let mut schema = create_test_schema();
@@ -316,11 +1860,21 @@ mod tests {
             table_name: "other_table".to_string(),
             estimated_row_count: 10,
             columns: vec![],
+            comment: None,
+            is_partition: false,
+            partition_of: None,
+            partition_strategy: None,
         });
 
         let filtered = filter_by_schemas(&schema, &["public"]);
 
         assert_eq!(filtered.tables.len(), 3);
         assert!(filtered.tables.iter().all(|t| t.schema_name == "public"));
+
+        schema.views.push(active_users_view("public", false));
+        schema.views.push(active_users_view("other", false));
+        let filtered = filter_by_schemas(&schema, &["public"]);
+        assert_eq!(filtered.views.len(), 1);
+        assert_eq!(filtered.views[0].schema, "public");
     }
 }
@@ -0,0 +1,129 @@
+//! Compressed storage format of schema graphs
+//!
+//! `dump_schemas` keeps a graph either as `jsonb` (`schema_graph`) or, when
+//! the worker compresses graphs, as tagged gzip-compressed JSON
+//! (`schema_graph_compressed`). Readers accept both.
+
+use std::io::{Read, Write};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use crate::domain::SchemaGraph;
+use crate::error::{CoreError, Result};
+
+/// Leading bytes of a compressed graph: a magic and the format version
+pub const COMPRESSED_GRAPH_TAG: &[u8] = b"SGZ\x01";
+
+/// Serialize a graph to JSON and gzip it behind [`COMPRESSED_GRAPH_TAG`]
+pub fn compress_schema_graph(graph: &SchemaGraph) -> Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(COMPRESSED_GRAPH_TAG.to_vec(), Compression::default());
+    serde_json::to_writer(&mut encoder, graph)?;
+    encoder.flush()?;
+    Ok(encoder.finish()?)
+}
+
+/// Read a graph written by [`compress_schema_graph`]
+pub fn decompress_schema_graph(bytes: &[u8]) -> Result<SchemaGraph> {
+    let compressed = bytes.strip_prefix(COMPRESSED_GRAPH_TAG).ok_or_else(|| {
+        CoreError::Validation("Unsupported stored schema graph format".to_string())
+    })?;
+    let mut json = Vec::new();
+    GzDecoder::new(compressed).read_to_end(&mut json)?;
+    Ok(serde_json::from_slice(&json)?)
+}
+
+/// Graph of a `dump_schemas` row from whichever column holds it
+///
+/// Compressed bytes take precedence; rows written before compression was
+/// introduced, or with it disabled, only have the `jsonb` value.
+pub fn stored_schema_graph(
+    json: Option<serde_json::Value>,
+    compressed: Option<&[u8]>,
+) -> Result<Option<SchemaGraph>> {
+    match (compressed, json) {
+        (Some(bytes), _) => decompress_schema_graph(bytes).map(Some),
+        (None, Some(value)) => Ok(Some(serde_json::from_value(value)?)),
+        (None, None) => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{ColumnInfo, FkAction, ForeignKey, TableInfo};
+
+    fn large_graph(tables: usize) -> SchemaGraph {
+        let column = |name: &str, is_primary_key: bool| ColumnInfo {
+            name: name.to_string(),
+            data_type: "bigint".to_string(),
+            is_nullable: !is_primary_key,
+            is_primary_key,
+            default_value: None,
+            comment: None,
+            ordinal_position: None,
+            is_identity: false,
+            identity_generation: None,
+            generation_expression: None,
+        };
+        SchemaGraph {
+            tables: (0..tables)
+                .map(|i| TableInfo {
+                    schema_name: "public".to_string(),
+                    table_name: format!("table_{}", i),
+                    estimated_row_count: i as i64,
+                    columns: vec![column("id", true), column("parent_id", false)],
+                    comment: None,
+                    is_partition: false,
+                    partition_of: None,
+                    partition_strategy: None,
+                })
+                .collect(),
+            foreign_keys: (1..tables)
+                .map(|i| ForeignKey {
+                    constraint_name: format!("table_{}_parent_id_fkey", i),
+                    source_schema: "public".to_string(),
+                    source_table: format!("table_{}", i),
+                    source_columns: vec!["parent_id".to_string()],
+                    target_schema: "public".to_string(),
+                    target_table: format!("table_{}", i - 1),
+                    target_columns: vec!["id".to_string()],
+                    on_delete: FkAction::Cascade,
+                    on_update: FkAction::NoAction,
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_compressed_graph_round_trip() {
+        let graph = large_graph(2000);
+        let json = serde_json::to_vec(&graph).unwrap();
+
+        let compressed = compress_schema_graph(&graph).unwrap();
+        assert!(compressed.starts_with(COMPRESSED_GRAPH_TAG));
+        assert!(compressed.len() * 10 < json.len());
+
+        let restored = decompress_schema_graph(&compressed).unwrap();
+        assert_eq!(serde_json::to_vec(&restored).unwrap(), json);
+    }
+
+    #[test]
+    fn test_stored_schema_graph_detects_format() {
+        let graph = large_graph(3);
+        let json = serde_json::to_value(&graph).unwrap();
+        let compressed = compress_schema_graph(&graph).unwrap();
+
+        let from_json = stored_schema_graph(Some(json.clone()), None).unwrap();
+        let from_bytes = stored_schema_graph(None, Some(&compressed)).unwrap();
+        assert_eq!(serde_json::to_value(from_json.unwrap()).unwrap(), json);
+        assert_eq!(serde_json::to_value(from_bytes.unwrap()).unwrap(), json);
+        assert!(stored_schema_graph(None, None).unwrap().is_none());
+        assert!(matches!(
+            stored_schema_graph(None, Some(b"{\"tables\": []}")),
+            Err(CoreError::Validation(_))
+        ));
+    }
+}
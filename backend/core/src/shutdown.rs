@@ -0,0 +1,58 @@
+//! Shutdown signal shared by the API server and the worker
+//!
+//! Kubernetes stops pods with SIGTERM, local runs with Ctrl-C (SIGINT). Both
+//! binaries stop taking new work when [`shutdown_signal`] resolves and let
+//! in-flight requests and jobs finish, so a restore is never cut off halfway
+//! through and left as a half-populated sandbox.
+
+use std::future::Future;
+
+/// Future resolving on the first SIGTERM or SIGINT
+///
+/// The handlers are installed before this returns, so a signal delivered
+/// between the call and the first poll is not lost and does not kill the
+/// process.
+#[cfg(unix)]
+pub fn shutdown_signal() -> std::io::Result<impl Future<Output = ()>> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut terminate = signal(SignalKind::terminate())?;
+    let mut interrupt = signal(SignalKind::interrupt())?;
+    Ok(async move {
+        tokio::select! {
+            _ = terminate.recv() => tracing::info!("Received SIGTERM, shutting down"),
+            _ = interrupt.recv() => tracing::info!("Received SIGINT, shutting down"),
+        }
+    })
+}
+
+/// Future resolving on Ctrl-C
+#[cfg(not(unix))]
+pub fn shutdown_signal() -> std::io::Result<impl Future<Output = ()>> {
+    Ok(async {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            tracing::info!("Received Ctrl-C, shutting down");
+        }
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_shutdown_signal_resolves_on_sigterm() {
+        let shutdown = shutdown_signal().unwrap();
+
+        let status = std::process::Command::new("kill")
+            .args(["-TERM", &std::process::id().to_string()])
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        tokio::time::timeout(Duration::from_secs(5), shutdown)
+            .await
+            .expect("shutdown signal did not resolve");
+    }
+}
@@ -1,71 +1,196 @@
 //! SQL example generation for relationship exploration
 
-use crate::domain::{ForeignKey, RelationDirection, SchemaGraph};
+use crate::domain::{FkAction, ForeignKey, RelationDirection, SchemaGraph};
+use crate::filter::OrderBy;
+use crate::schema::{cascade_chain, find_related_tables, CascadeStep, TableKey};
+use std::collections::HashSet;
+
+/// Quote a SQL identifier by wrapping it in double quotes and doubling any
+/// embedded double quotes
+pub fn quote_ident(ident: &str) -> String {
+    format!("\"{}\"", ident.replace('"', "\"\""))
+}
+
+/// Quote an identifier for a `--` or `/* */` comment: as [`quote_ident`],
+/// with control characters escaped and `*/` split so a name can neither end
+/// the line nor close the comment
+fn comment_ident(ident: &str) -> String {
+    let mut quoted = String::new();
+    for c in quote_ident(ident).chars() {
+        if c.is_control() {
+            quoted.extend(c.escape_default());
+        } else {
+            quoted.push(c);
+        }
+    }
+    quoted.replace("*/", "*\\/")
+}
+
+/// Quote a SQL string literal by wrapping it in single quotes and doubling any
+/// embedded single quotes
+pub fn quote_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
 
 /// SQL example generator
 pub struct SqlGenerator;
 
 impl SqlGenerator {
+    /// Build `alias."col" = placeholder` conditions joined with `AND`, one per
+    /// column. Columns without a matching placeholder get a positional `$n`.
+    fn column_conditions<C: AsRef<str>, S: AsRef<str>>(
+        alias: &str,
+        columns: &[C],
+        value_placeholders: &[S],
+    ) -> String {
+        columns
+            .iter()
+            .enumerate()
+            .map(|(i, col)| {
+                let placeholder = value_placeholders
+                    .get(i)
+                    .map(|p| p.as_ref().to_string())
+                    .unwrap_or_else(|| format!("${}", i + 1));
+                format!("{}{} = {}", alias, quote_ident(col.as_ref()), placeholder)
+            })
+            .collect::<Vec<_>>()
+            .join(" AND ")
+    }
+
     /// Generate a SELECT query for rows referencing a value
-    pub fn generate_referencing_query(
+    pub fn generate_referencing_query<S: AsRef<str>>(
         fk: &ForeignKey,
-        value_placeholder: &str,
+        value_placeholders: &[S],
         limit: usize,
     ) -> String {
-        let _source_cols = fk.source_columns.join(", ");
-
         format!(
             r#"-- Rows in {}.{} that reference this value
 SELECT *
-FROM "{}"."{}" t
-WHERE t."{}" = {}
+FROM {}.{} t
+WHERE {}
 LIMIT {};"#,
-            fk.source_schema,
-            fk.source_table,
-            fk.source_schema,
-            fk.source_table,
-            fk.source_columns.first().unwrap_or(&"id".to_string()),
-            value_placeholder,
+            comment_ident(&fk.source_schema),
+            comment_ident(&fk.source_table),
+            quote_ident(&fk.source_schema),
+            quote_ident(&fk.source_table),
+            Self::column_conditions("t.", &fk.source_columns, value_placeholders),
             limit
         )
     }
 
     /// Generate a JOIN query along a relationship path
-    pub fn generate_join_query(fk: &ForeignKey, value_placeholder: &str, limit: usize) -> String {
+    pub fn generate_join_query<S: AsRef<str>>(
+        fk: &ForeignKey,
+        value_placeholders: &[S],
+        limit: usize,
+    ) -> String {
+        let join_conditions = fk
+            .target_columns
+            .iter()
+            .zip(&fk.source_columns)
+            .map(|(target, source)| {
+                format!("t.{} = s.{}", quote_ident(target), quote_ident(source))
+            })
+            .collect::<Vec<_>>()
+            .join(" AND ");
+
         format!(
             r#"-- Join preview: {}.{} -> {}.{}
 SELECT 
     s.*,
     t.*
-FROM "{}"."{}" s
-JOIN "{}"."{}" t
-    ON t."{}" = s."{}"
-WHERE s."{}" = {}
+FROM {}.{} s
+JOIN {}.{} t
+    ON {}
+WHERE {}
 LIMIT {};"#,
-            fk.source_schema,
-            fk.source_table,
-            fk.target_schema,
-            fk.target_table,
-            fk.source_schema,
-            fk.source_table,
-            fk.target_schema,
-            fk.target_table,
-            fk.target_columns.first().unwrap_or(&"id".to_string()),
-            fk.source_columns.first().unwrap_or(&"fk_id".to_string()),
-            fk.source_columns.first().unwrap_or(&"fk_id".to_string()),
-            value_placeholder,
+            comment_ident(&fk.source_schema),
+            comment_ident(&fk.source_table),
+            comment_ident(&fk.target_schema),
+            comment_ident(&fk.target_table),
+            quote_ident(&fk.source_schema),
+            quote_ident(&fk.source_table),
+            quote_ident(&fk.target_schema),
+            quote_ident(&fk.target_table),
+            join_conditions,
+            Self::column_conditions("s.", &fk.source_columns, value_placeholders),
             limit
         )
     }
 
+    /// Generate the query fetching rows on the other side of a foreign key
+    ///
+    /// `direction` is relative to the table the known row belongs to:
+    /// `Outbound` selects the referenced rows, `Inbound` the referencing rows.
+    /// The key columns on the selected side are compared as text with `$1`,
+    /// `$2`, ... in foreign key column order, so values of any type can be
+    /// bound as strings. Rows are returned as a single `row_data` JSON column.
+    pub fn generate_related_rows_query(
+        fk: &ForeignKey,
+        direction: RelationDirection,
+        limit: usize,
+    ) -> String {
+        let (schema, table, columns) = match direction {
+            RelationDirection::Outbound => {
+                (&fk.target_schema, &fk.target_table, &fk.target_columns)
+            }
+            RelationDirection::Inbound => (&fk.source_schema, &fk.source_table, &fk.source_columns),
+        };
+        let conditions = columns
+            .iter()
+            .enumerate()
+            .map(|(i, col)| format!("t.{}::text = ${}", quote_ident(col), i + 1))
+            .collect::<Vec<_>>()
+            .join(" AND ");
+
+        format!(
+            "SELECT to_jsonb(t.*) AS row_data FROM {}.{} t WHERE {} LIMIT {}",
+            quote_ident(schema),
+            quote_ident(table),
+            conditions,
+            limit
+        )
+    }
+
+    /// Generate the query sampling rows of a table, aliased as `t`
+    ///
+    /// `conditions` are joined with `AND` (see [`crate::filter::render_filter`]).
+    /// NULLs sort last in either direction. Rows are returned as a single
+    /// `row_data` JSON column.
+    pub fn generate_sample_rows_query(
+        schema: &str,
+        table: &str,
+        conditions: &[String],
+        order_by: Option<&OrderBy>,
+        limit: usize,
+    ) -> String {
+        let mut query = format!(
+            "SELECT to_jsonb(t.*) AS row_data FROM {}.{} t",
+            quote_ident(schema),
+            quote_ident(table)
+        );
+        if !conditions.is_empty() {
+            query.push_str(&format!(" WHERE {}", conditions.join(" AND ")));
+        }
+        if let Some(order) = order_by {
+            query.push_str(&format!(
+                " ORDER BY t.{} {} NULLS LAST",
+                quote_ident(&order.column),
+                order.direction.as_sql()
+            ));
+        }
+        query.push_str(&format!(" LIMIT {}", limit));
+        query
+    }
+
     /// Generate SQL examples for explaining a relationship
-    pub fn generate_relationship_sql(
+    pub fn generate_relationship_sql<S: AsRef<str>>(
         schema_graph: &SchemaGraph,
         schema: &str,
         table: &str,
         column: &str,
         direction: RelationDirection,
-        value_placeholder: &str,
+        value_placeholders: &[S],
     ) -> Vec<String> {
         let mut examples = Vec::new();
 
@@ -77,7 +202,7 @@ LIMIT {};"#,
                         && fk.target_table == table
                         && fk.target_columns.contains(&column.to_string())
                     {
-                        examples.push(Self::generate_referencing_query(fk, value_placeholder, 50));
+                        examples.push(Self::generate_referencing_query(fk, value_placeholders, 50));
                     }
                 }
             }
@@ -88,7 +213,7 @@ LIMIT {};"#,
                         && fk.source_table == table
                         && fk.source_columns.contains(&column.to_string())
                     {
-                        examples.push(Self::generate_join_query(fk, value_placeholder, 50));
+                        examples.push(Self::generate_join_query(fk, value_placeholders, 50));
                     }
                 }
             }
@@ -98,45 +223,359 @@ LIMIT {};"#,
     }
 
     /// Generate a DELETE impact query
-    pub fn generate_delete_impact_query(
+    ///
+    /// `columns` are the key columns of the row being deleted and
+    /// `value_placeholders` their values, in the same order as the referencing
+    /// foreign key columns.
+    ///
+    /// Affected tables are listed in deletion order: the deepest cascade
+    /// level (as reached through `schema_graph`) first, working inward to the
+    /// direct children. Each level is headed by the estimated row count of
+    /// its tables and the running total so far. Counts for tables reached
+    /// through other tables select the referencing rows through the chain of
+    /// cascading foreign keys.
+    pub fn generate_delete_impact_query<S: AsRef<str>>(
+        schema_graph: &SchemaGraph,
         schema: &str,
         table: &str,
-        column: &str,
-        value_placeholder: &str,
+        columns: &[&str],
+        value_placeholders: &[S],
         cascade_fks: &[&ForeignKey],
     ) -> String {
+        let key = columns
+            .iter()
+            .zip(value_placeholders)
+            .map(|(col, value)| format!("{} = {}", comment_ident(col), value.as_ref()))
+            .collect::<Vec<_>>()
+            .join(" AND ");
+
         let mut query = format!(
-            r#"-- Impact analysis for deleting from {}.{} where {} = {}
--- This deletion will affect the following tables:
+            r#"-- Impact analysis for deleting from {}.{} where {}
+-- This deletion will affect the following tables, in deletion order:
 "#,
-            schema, table, column, value_placeholder
+            comment_ident(schema),
+            comment_ident(table),
+            key
         );
 
-        for fk in cascade_fks {
+        let chain = cascade_chain(schema_graph, schema, table, usize::MAX);
+        // Level of a foreign key: one below the table it references. Keys
+        // referencing tables outside the cascade chain count as direct.
+        let level = |fk: &ForeignKey| {
+            chain
+                .iter()
+                .find(|step| step.table.0 == fk.target_schema && step.table.1 == fk.target_table)
+                .map_or(1, |step| step.depth + 1)
+        };
+        let mut ordered: Vec<(usize, &ForeignKey)> =
+            cascade_fks.iter().map(|fk| (level(fk), *fk)).collect();
+        ordered.sort_by_key(|(depth, _)| std::cmp::Reverse(*depth));
+
+        let mut cumulative = 0;
+        for group in ordered.chunk_by(|a, b| a.0 == b.0) {
+            let depth = group[0].0;
+            let mut level_tables: Vec<(&str, &str)> = group
+                .iter()
+                .map(|(_, fk)| (fk.source_schema.as_str(), fk.source_table.as_str()))
+                .collect();
+            level_tables.sort();
+            level_tables.dedup();
+            let estimated: i64 = level_tables
+                .iter()
+                .filter_map(|(s, t)| {
+                    schema_graph
+                        .tables
+                        .iter()
+                        .find(|info| info.schema_name == *s && info.table_name == *t)
+                })
+                .map(|info| info.estimated_row_count.max(0))
+                .sum();
+            cumulative += estimated;
             query.push_str(&format!(
-                r#"
+                "\n-- Level {}: up to ~{} row(s) estimated (~{} cumulative)\n",
+                depth, estimated, cumulative
+            ));
+
+            for (_, fk) in group {
+                let condition = Self::cascaded_rows_condition(&chain, fk, value_placeholders);
+                query.push_str(&format!(
+                    r#"
 -- {} rows in {}.{} (ON DELETE {})
-SELECT COUNT(*) FROM "{}"."{}" WHERE "{}" = {};
+SELECT COUNT(*) FROM {}.{} WHERE {};
 "#,
+                    fk.on_delete,
+                    comment_ident(&fk.source_schema),
+                    comment_ident(&fk.source_table),
+                    fk.on_delete,
+                    quote_ident(&fk.source_schema),
+                    quote_ident(&fk.source_table),
+                    condition
+                ));
+            }
+        }
+
+        query
+    }
+
+    /// Condition matching the rows of `fk`'s source table that reference a
+    /// row removed by the delete
+    ///
+    /// Keys referencing the deleted row compare against `value_placeholders`
+    /// directly; keys referencing a table on the cascade chain select the
+    /// removed rows of that table, recursively.
+    fn cascaded_rows_condition<S: AsRef<str>>(
+        chain: &[CascadeStep],
+        fk: &ForeignKey,
+        value_placeholders: &[S],
+    ) -> String {
+        let target: TableKey = (fk.target_schema.clone(), fk.target_table.clone());
+        let Some(step) = chain.iter().find(|step| step.table == target) else {
+            return Self::column_conditions("", &fk.source_columns, value_placeholders);
+        };
+        let quote_all = |columns: &[String]| {
+            columns
+                .iter()
+                .map(|c| quote_ident(c))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        format!(
+            "({}) IN (SELECT {} FROM {}.{} WHERE {})",
+            quote_all(&fk.source_columns),
+            quote_all(&fk.target_columns),
+            quote_ident(&fk.target_schema),
+            quote_ident(&fk.target_table),
+            Self::cascaded_rows_condition(chain, step.via, value_placeholders)
+        )
+    }
+
+    /// Summarize what deleting a row does to the tables referencing it, as
+    /// SQL comment lines
+    fn delete_impact_comment(schema: &str, table: &str, inbound_fks: &[&ForeignKey]) -> String {
+        let mut comment = format!(
+            "-- Delete from {}.{}\n",
+            comment_ident(schema),
+            comment_ident(table)
+        );
+        if inbound_fks.is_empty() {
+            comment.push_str("-- No foreign keys reference this table\n");
+            return comment;
+        }
+
+        for fk in inbound_fks {
+            let effect = match fk.on_delete {
+                FkAction::Cascade => "referencing rows are deleted",
+                FkAction::SetNull => "referencing columns are set to NULL",
+                FkAction::SetDefault => "referencing columns are reset to their default",
+                FkAction::Restrict | FkAction::NoAction => "fails while referencing rows exist",
+            };
+            comment.push_str(&format!(
+                "-- {}.{} ({}, ON DELETE {}): {}\n",
+                comment_ident(&fk.source_schema),
+                comment_ident(&fk.source_table),
+                comment_ident(&fk.constraint_name),
                 fk.on_delete,
-                fk.source_schema,
-                fk.source_table,
-                fk.on_delete,
-                fk.source_schema,
-                fk.source_table,
-                fk.source_columns.first().unwrap_or(&"id".to_string()),
-                value_placeholder
+                effect
             ));
         }
+        comment
+    }
 
-        query
+    /// Generate a DELETE statement for one row identified by its primary key
+    ///
+    /// `inbound_fks` are the foreign keys referencing the table; they are
+    /// summarized in a leading comment.
+    pub fn generate_delete_statement<S: AsRef<str>>(
+        schema: &str,
+        table: &str,
+        pk_columns: &[&str],
+        value_placeholders: &[S],
+        inbound_fks: &[&ForeignKey],
+    ) -> String {
+        format!(
+            "{}DELETE FROM {}.{} WHERE {};",
+            Self::delete_impact_comment(schema, table, inbound_fks),
+            quote_ident(schema),
+            quote_ident(table),
+            Self::column_conditions("", pk_columns, value_placeholders)
+        )
+    }
+
+    /// Generate a DELETE wrapped in a transaction, preceded by a
+    /// `SELECT count(*)` of the rows it will remove so the result can be
+    /// checked before committing
+    pub fn generate_safe_delete_with_count<S: AsRef<str>>(
+        schema: &str,
+        table: &str,
+        pk_columns: &[&str],
+        value_placeholders: &[S],
+        inbound_fks: &[&ForeignKey],
+    ) -> String {
+        let conditions = Self::column_conditions("", pk_columns, value_placeholders);
+        format!(
+            r#"{}BEGIN;
+
+-- Guard: expect exactly 1; ROLLBACK if the count differs
+SELECT count(*) FROM {}.{} WHERE {};
+
+DELETE FROM {}.{} WHERE {};
+
+-- Check the DELETE row count, then COMMIT (or ROLLBACK)
+COMMIT;"#,
+            Self::delete_impact_comment(schema, table, inbound_fks),
+            quote_ident(schema),
+            quote_ident(table),
+            conditions,
+            quote_ident(schema),
+            quote_ident(table),
+            conditions
+        )
+    }
+
+    /// Generate a `WITH RECURSIVE` query exploring rows up to `max_hops`
+    /// foreign key hops away from `schema.table` where `column` equals
+    /// `value_placeholder`
+    ///
+    /// Each step of the recursion follows every foreign key (in both
+    /// directions) touching the current row's table, matching key values via
+    /// their text form so rows of any table can be carried as `jsonb`. Tables
+    /// already on a row's path are not revisited, which also stops cycles.
+    pub fn generate_nhop_cte(
+        schema_graph: &SchemaGraph,
+        schema: &str,
+        table: &str,
+        column: &str,
+        value_placeholder: &str,
+        max_hops: usize,
+    ) -> String {
+        let start_name = format!("{}.{}", schema, table);
+
+        // Only foreign keys between tables reachable within max_hops matter
+        let mut reachable: HashSet<(String, String)> =
+            find_related_tables(schema_graph, schema, table, max_hops)
+                .into_iter()
+                .map(|r| (r.schema, r.table))
+                .collect();
+        reachable.insert((schema.to_string(), table.to_string()));
+
+        let mut steps = Vec::new();
+        for fk in &schema_graph.foreign_keys {
+            let source = (fk.source_schema.clone(), fk.source_table.clone());
+            let target = (fk.target_schema.clone(), fk.target_table.clone());
+            if !reachable.contains(&source) || !reachable.contains(&target) {
+                continue;
+            }
+            // Inbound: rows of the source table referencing the current row
+            steps.push(Self::nhop_step(
+                fk,
+                (&fk.source_schema, &fk.source_table, &fk.source_columns),
+                (&fk.target_schema, &fk.target_table, &fk.target_columns),
+            ));
+            // Outbound: the row of the target table the current row references
+            steps.push(Self::nhop_step(
+                fk,
+                (&fk.target_schema, &fk.target_table, &fk.target_columns),
+                (&fk.source_schema, &fk.source_table, &fk.source_columns),
+            ));
+        }
+
+        let recursive_term = if steps.is_empty() {
+            String::new()
+        } else {
+            format!(
+                r#"
+    UNION ALL
+    SELECT w.hops + 1, step.table_name, w.path || step.table_name, step.row_data
+    FROM walk w
+    CROSS JOIN LATERAL (
+{}
+    ) step
+    WHERE w.hops < {} AND NOT step.table_name = ANY(w.path)"#,
+                steps.join("\n        UNION ALL\n"),
+                max_hops
+            )
+        };
+
+        format!(
+            r#"-- Rows up to {} hop(s) from {}.{} where {} = {}
+WITH RECURSIVE walk(hops, table_name, path, row_data) AS (
+    SELECT 0, {}::text, ARRAY[{}::text], to_jsonb(t)
+    FROM {}.{} t
+    WHERE t.{} = {}{}
+)
+SELECT hops, table_name, array_to_string(path, ' -> ') AS path, row_data
+FROM walk
+ORDER BY hops, table_name;"#,
+            max_hops,
+            comment_ident(schema),
+            comment_ident(table),
+            comment_ident(column),
+            value_placeholder,
+            quote_literal(&start_name),
+            quote_literal(&start_name),
+            quote_ident(schema),
+            quote_ident(table),
+            quote_ident(column),
+            value_placeholder,
+            recursive_term
+        )
+    }
+
+    /// One branch of the recursive step in [`Self::generate_nhop_cte`]:
+    /// rows of `next` whose `next` columns match the current row's `current`
+    /// columns
+    fn nhop_step(
+        fk: &ForeignKey,
+        next: (&str, &str, &[String]),
+        current: (&str, &str, &[String]),
+    ) -> String {
+        let (next_schema, next_table, next_columns) = next;
+        let (current_schema, current_table, current_columns) = current;
+        let next_name = format!("{}.{}", next_schema, next_table);
+        let current_name = format!("{}.{}", current_schema, current_table);
+
+        let matches = next_columns
+            .iter()
+            .zip(current_columns)
+            .map(|(n, c)| {
+                format!(
+                    "t.{}::text = w.row_data->>{}",
+                    quote_ident(n),
+                    quote_literal(c)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" AND ");
+
+        format!(
+            r#"        -- {}.{} -> {}.{} via {}
+        SELECT {}::text AS table_name, to_jsonb(t) AS row_data
+        FROM {}.{} t
+        WHERE w.table_name = {} AND {}"#,
+            comment_ident(current_schema),
+            comment_ident(current_table),
+            comment_ident(next_schema),
+            comment_ident(next_table),
+            comment_ident(&fk.constraint_name),
+            quote_literal(&next_name),
+            quote_ident(next_schema),
+            quote_ident(next_table),
+            quote_literal(&current_name),
+            matches
+        )
+    }
+
+    /// Positional placeholders (`$1`, `$2`, ...) for a multi-column key
+    pub fn positional_placeholders(count: usize) -> Vec<String> {
+        (1..=count).map(|i| format!("${}", i)).collect()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::domain::FkAction;
+    use crate::domain::TableInfo;
+    use crate::filter::SortDirection;
 
     fn create_test_fk() -> ForeignKey {
         ForeignKey {
@@ -155,7 +594,7 @@ mod tests {
     #[test]
     fn test_generate_referencing_query() {
         let fk = create_test_fk();
-        let sql = SqlGenerator::generate_referencing_query(&fk, "$1", 50);
+        let sql = SqlGenerator::generate_referencing_query(&fk, &["$1"], 50);
 
         assert!(sql.contains("SELECT *"));
         assert!(sql.contains("public"));
@@ -167,7 +606,7 @@ mod tests {
     #[test]
     fn test_generate_join_query() {
         let fk = create_test_fk();
-        let sql = SqlGenerator::generate_join_query(&fk, "$1", 50);
+        let sql = SqlGenerator::generate_join_query(&fk, &["$1"], 50);
 
         assert!(sql.contains("JOIN"));
         assert!(sql.contains("public"));
@@ -180,6 +619,11 @@ mod tests {
         let schema_graph = SchemaGraph {
             tables: vec![],
             foreign_keys: vec![create_test_fk()],
+            indexes: vec![],
+            views: vec![],
+            triggers: vec![],
+            enum_types: vec![],
+            sequences: vec![],
         };
 
         let sqls = SqlGenerator::generate_relationship_sql(
@@ -188,7 +632,7 @@ mod tests {
             "users",
             "id",
             RelationDirection::Inbound,
-            "$1",
+            &["$1"],
         );
 
         assert_eq!(sqls.len(), 1);
@@ -198,10 +642,344 @@ mod tests {
     #[test]
     fn test_generate_delete_impact_query() {
         let fk = create_test_fk();
-        let sql = SqlGenerator::generate_delete_impact_query("public", "users", "id", "$1", &[&fk]);
+        let sql = SqlGenerator::generate_delete_impact_query(
+            &SchemaGraph::default(),
+            "public",
+            "users",
+            &["id"],
+            &["$1"],
+            &[&fk],
+        );
 
         assert!(sql.contains("Impact analysis"));
         assert!(sql.contains("CASCADE"));
         assert!(sql.contains("COUNT(*)"));
     }
+
+    #[test]
+    fn test_generated_comments_cannot_be_escaped() {
+        let sql = SqlGenerator::generate_delete_impact_query(
+            &SchemaGraph::default(),
+            "public",
+            "users */ DROP TABLE x; /*",
+            &["id\nDROP TABLE y; --"],
+            &["$1"],
+            &[],
+        );
+
+        assert!(sql
+            .lines()
+            .all(|line| line.is_empty() || line.starts_with("--")));
+        assert!(!sql.contains("*/"));
+        assert!(sql.contains(r#""users *\/ DROP TABLE x; /*""#));
+        assert!(sql.contains(r#""id\nDROP TABLE y; --" = $1"#));
+
+        let fk = ForeignKey {
+            source_table: "orders\nDROP TABLE x; --".to_string(),
+            target_schema: "public */ DROP TABLE y; /*".to_string(),
+            ..create_test_fk()
+        };
+        let referencing = SqlGenerator::generate_referencing_query(&fk, &["$1"], 10);
+        assert_eq!(
+            referencing.lines().next().unwrap(),
+            r#"-- Rows in "public"."orders\nDROP TABLE x; --" that reference this value"#
+        );
+        let join = SqlGenerator::generate_join_query(&fk, &["$1"], 10);
+        assert_eq!(
+            join.lines().next().unwrap(),
+            r#"-- Join preview: "public"."orders\nDROP TABLE x; --" -> "public *\/ DROP TABLE y; /*"."users""#
+        );
+    }
+
+    #[test]
+    fn test_quote_ident_escapes_quotes() {
+        assert_eq!(quote_ident("users"), "\"users\"");
+        assert_eq!(
+            quote_ident("a\"; DROP TABLE x; --"),
+            "\"a\"\"; DROP TABLE x; --\""
+        );
+    }
+
+    fn create_composite_fk() -> ForeignKey {
+        ForeignKey {
+            constraint_name: "fk_order_items_order".to_string(),
+            source_schema: "public".to_string(),
+            source_table: "order_items".to_string(),
+            source_columns: vec!["order_region".to_string(), "order_no".to_string()],
+            target_schema: "public".to_string(),
+            target_table: "orders".to_string(),
+            target_columns: vec!["region".to_string(), "no".to_string()],
+            on_delete: FkAction::Cascade,
+            on_update: FkAction::NoAction,
+        }
+    }
+
+    #[test]
+    fn test_generate_related_rows_query() {
+        let fk = create_composite_fk();
+
+        assert_eq!(
+            SqlGenerator::generate_related_rows_query(&fk, RelationDirection::Outbound, 5),
+            r#"SELECT to_jsonb(t.*) AS row_data FROM "public"."orders" t WHERE t."region"::text = $1 AND t."no"::text = $2 LIMIT 5"#
+        );
+        assert_eq!(
+            SqlGenerator::generate_related_rows_query(&fk, RelationDirection::Inbound, 5),
+            r#"SELECT to_jsonb(t.*) AS row_data FROM "public"."order_items" t WHERE t."order_region"::text = $1 AND t."order_no"::text = $2 LIMIT 5"#
+        );
+    }
+
+    #[test]
+    fn test_generate_sample_rows_query() {
+        assert_eq!(
+            SqlGenerator::generate_sample_rows_query("public", "orders", &[], None, 10),
+            r#"SELECT to_jsonb(t.*) AS row_data FROM "public"."orders" t LIMIT 10"#
+        );
+        let order = OrderBy {
+            column: "Created At".to_string(),
+            direction: SortDirection::Desc,
+        };
+        assert_eq!(
+            SqlGenerator::generate_sample_rows_query(
+                "public",
+                "orders",
+                &[
+                    "t.\"status\" = CAST($1 AS text)".to_string(),
+                    "t.\"total\" > CAST($2 AS numeric)".to_string(),
+                ],
+                Some(&order),
+                5,
+            ),
+            r#"SELECT to_jsonb(t.*) AS row_data FROM "public"."orders" t WHERE t."status" = CAST($1 AS text) AND t."total" > CAST($2 AS numeric) ORDER BY t."Created At" DESC NULLS LAST LIMIT 5"#
+        );
+    }
+
+    #[test]
+    fn test_generate_referencing_query_composite_fk() {
+        let fk = create_composite_fk();
+        let sql = SqlGenerator::generate_referencing_query(&fk, &["$1", "$2"], 50);
+
+        assert!(sql.contains(r#"WHERE t."order_region" = $1 AND t."order_no" = $2"#));
+    }
+
+    #[test]
+    fn test_generate_join_query_composite_fk() {
+        let fk = create_composite_fk();
+        let sql = SqlGenerator::generate_join_query(&fk, &["$1", "$2"], 50);
+
+        assert!(sql.contains(r#"ON t."region" = s."order_region" AND t."no" = s."order_no""#));
+        assert!(sql.contains(r#"WHERE s."order_region" = $1 AND s."order_no" = $2"#));
+    }
+
+    #[test]
+    fn test_generate_delete_impact_query_composite_fk() {
+        let fk = create_composite_fk();
+        let sql = SqlGenerator::generate_delete_impact_query(
+            &SchemaGraph::default(),
+            "public",
+            "orders",
+            &["region", "no"],
+            &["$1", "$2"],
+            &[&fk],
+        );
+
+        assert!(sql.contains(r#"where "region" = $1 AND "no" = $2"#));
+        assert!(sql.contains(r#"WHERE "order_region" = $1 AND "order_no" = $2"#));
+    }
+
+    #[test]
+    fn test_generate_delete_statement_single_key() {
+        let fk = create_test_fk();
+        let sql =
+            SqlGenerator::generate_delete_statement("public", "users", &["id"], &["$1"], &[&fk]);
+
+        assert!(sql.starts_with("-- Delete from \"public\".\"users\"\n"));
+        assert!(sql.contains(
+            r#"-- "public"."orders" ("fk_orders_user", ON DELETE CASCADE): referencing rows are deleted"#
+        ));
+        assert!(sql.ends_with(r#"DELETE FROM "public"."users" WHERE "id" = $1;"#));
+    }
+
+    #[test]
+    fn test_generate_delete_statement_composite_key() {
+        let sql = SqlGenerator::generate_delete_statement(
+            "public",
+            "order\"items",
+            &["region", "no"],
+            &SqlGenerator::positional_placeholders(2),
+            &[],
+        );
+
+        assert!(sql.contains("-- No foreign keys reference this table"));
+        assert!(sql.ends_with(
+            r#"DELETE FROM "public"."order""items" WHERE "region" = $1 AND "no" = $2;"#
+        ));
+    }
+
+    #[test]
+    fn test_generate_safe_delete_with_count() {
+        let fk = create_composite_fk();
+        let sql = SqlGenerator::generate_safe_delete_with_count(
+            "public",
+            "orders",
+            &["region", "no"],
+            &["$1", "$2"],
+            &[&fk],
+        );
+
+        let begin = sql.find("BEGIN;").unwrap();
+        let count = sql
+            .find(r#"SELECT count(*) FROM "public"."orders" WHERE "region" = $1 AND "no" = $2;"#)
+            .unwrap();
+        let delete = sql
+            .find(r#"DELETE FROM "public"."orders" WHERE "region" = $1 AND "no" = $2;"#)
+            .unwrap();
+        assert!(begin < count && count < delete);
+        assert!(sql.contains("-- Guard: expect exactly 1"));
+        assert!(sql.ends_with("COMMIT;"));
+    }
+
+    fn create_chain_schema() -> SchemaGraph {
+        SchemaGraph {
+            foreign_keys: vec![
+                create_test_fk(),
+                ForeignKey {
+                    constraint_name: "fk_order_items_order".to_string(),
+                    source_schema: "public".to_string(),
+                    source_table: "order_items".to_string(),
+                    source_columns: vec!["order_id".to_string()],
+                    target_schema: "public".to_string(),
+                    target_table: "orders".to_string(),
+                    target_columns: vec!["id".to_string()],
+                    on_delete: FkAction::Cascade,
+                    on_update: FkAction::NoAction,
+                },
+            ],
+            ..SchemaGraph::default()
+        }
+    }
+
+    #[test]
+    fn test_generate_delete_impact_query_orders_by_cascade_depth() {
+        let mut schema = create_chain_schema();
+        for (table, rows) in [("orders", 200), ("order_items", 1000)] {
+            schema.tables.push(TableInfo {
+                schema_name: "public".to_string(),
+                table_name: table.to_string(),
+                estimated_row_count: rows,
+                columns: vec![],
+                comment: None,
+                is_partition: false,
+                partition_of: None,
+                partition_strategy: None,
+            });
+        }
+        let fks: Vec<&ForeignKey> = schema.foreign_keys.iter().collect();
+
+        let sql = SqlGenerator::generate_delete_impact_query(
+            &schema,
+            "public",
+            "users",
+            &["id"],
+            &["$1"],
+            &fks,
+        );
+
+        let items = sql.find(r#"rows in "public"."order_items""#).unwrap();
+        let orders = sql.find(r#"rows in "public"."orders""#).unwrap();
+        assert!(items < orders, "deeper table should come first:\n{}", sql);
+        assert!(sql.contains("-- Level 2: up to ~1000 row(s) estimated (~1000 cumulative)"));
+        assert!(sql.contains("-- Level 1: up to ~200 row(s) estimated (~1200 cumulative)"));
+        assert!(sql.contains(
+            r#"WHERE ("order_id") IN (SELECT "id" FROM "public"."orders" WHERE "user_id" = $1);"#
+        ));
+        assert!(sql.contains(r#"SELECT COUNT(*) FROM "public"."orders" WHERE "user_id" = $1;"#));
+    }
+
+    #[test]
+    fn test_generate_nhop_cte_two_hops() {
+        let sql = SqlGenerator::generate_nhop_cte(
+            &create_chain_schema(),
+            "public",
+            "users",
+            "id",
+            "$1",
+            2,
+        );
+
+        assert!(sql.contains("WITH RECURSIVE walk"));
+        assert!(sql.contains(r#"FROM "public"."users" t"#));
+        assert!(sql.contains(r#"WHERE t."id" = $1"#));
+        // Intermediate and final tables of the users -> orders -> order_items path
+        assert!(sql.contains(r#"FROM "public"."orders" t"#));
+        assert!(sql.contains(r#"FROM "public"."order_items" t"#));
+        assert!(sql.contains(
+            r#"WHERE w.table_name = 'public.orders' AND t."order_id"::text = w.row_data->>'id'"#
+        ));
+        assert!(sql.contains("WHERE w.hops < 2"));
+    }
+
+    #[test]
+    fn test_generate_nhop_cte_limits_to_reachable_tables() {
+        let sql = SqlGenerator::generate_nhop_cte(
+            &create_chain_schema(),
+            "public",
+            "users",
+            "id",
+            "$1",
+            1,
+        );
+
+        assert!(sql.contains(r#"FROM "public"."orders" t"#));
+        assert!(!sql.contains("order_items"));
+    }
+
+    #[test]
+    fn test_generate_nhop_cte_cannot_escape_its_comments() {
+        let mut schema = create_chain_schema();
+        schema.foreign_keys[0].constraint_name = "fk\nDROP TABLE z; --".to_string();
+        let sql = SqlGenerator::generate_nhop_cte(
+            &schema,
+            "public",
+            "users",
+            "id\nDROP TABLE y; --",
+            "$1",
+            2,
+        );
+
+        assert!(sql
+            .lines()
+            .next()
+            .unwrap()
+            .ends_with(r#"from "public"."users" where "id\nDROP TABLE y; --" = $1"#));
+        assert!(
+            sql.contains(r#"-- "public"."users" -> "public"."orders" via "fk\nDROP TABLE z; --""#)
+        );
+        assert!(!sql
+            .lines()
+            .any(|line| line.trim_start().starts_with("DROP TABLE z")));
+    }
+
+    #[test]
+    fn test_generate_nhop_cte_without_relations() {
+        let sql = SqlGenerator::generate_nhop_cte(
+            &SchemaGraph::default(),
+            "public",
+            "users",
+            "id",
+            "$1",
+            3,
+        );
+
+        assert!(sql.contains("WITH RECURSIVE walk"));
+        assert!(!sql.contains("UNION ALL"));
+    }
+
+    #[test]
+    fn test_missing_placeholders_are_positional() {
+        let fk = create_composite_fk();
+        let sql = SqlGenerator::generate_referencing_query(&fk, &["$1"], 10);
+
+        assert!(sql.contains(r#"t."order_no" = $2"#));
+        assert_eq!(SqlGenerator::positional_placeholders(2), vec!["$1", "$2"]);
+    }
 }
@@ -0,0 +1,6 @@
+-- Add restore_timings column to record per-phase restore durations
+-- Populated by the worker: decompress_ms, restore_ms, analyze_ms, schema_graph_ms
+
+ALTER TABLE dumps ADD COLUMN IF NOT EXISTS restore_timings JSONB DEFAULT NULL;
+
+COMMENT ON COLUMN dumps.restore_timings IS 'Wall-clock duration (ms) of each restore phase';
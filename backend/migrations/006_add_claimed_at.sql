@@ -0,0 +1,6 @@
+-- Add claimed_at column so multiple workers can claim jobs without overlap
+-- Set by the worker when it claims a RESTORING/ANALYZING dump, cleared on the next status change
+
+ALTER TABLE dumps ADD COLUMN IF NOT EXISTS claimed_at TIMESTAMPTZ DEFAULT NULL;
+
+COMMENT ON COLUMN dumps.claimed_at IS 'When a worker claimed this dump for processing (NULL when unclaimed)';
@@ -0,0 +1,6 @@
+-- Add restore_warnings column to keep non-fatal pg_restore / psql output
+-- Populated by the worker after restore: one stderr line per line, NULL when the restore was clean
+
+ALTER TABLE dumps ADD COLUMN IF NOT EXISTS restore_warnings TEXT DEFAULT NULL;
+
+COMMENT ON COLUMN dumps.restore_warnings IS 'Non-fatal messages emitted while restoring the dump';
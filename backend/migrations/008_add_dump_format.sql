@@ -0,0 +1,6 @@
+-- Add dump_format column recording the format detected when the upload completed
+-- One of CUSTOM_FORMAT, PLAIN_SQL, PG_DUMPALL; NULL for dumps uploaded before detection existed
+
+ALTER TABLE dumps ADD COLUMN IF NOT EXISTS dump_format VARCHAR(50) DEFAULT NULL;
+
+COMMENT ON COLUMN dumps.dump_format IS 'Dump file format detected from its leading bytes on upload';
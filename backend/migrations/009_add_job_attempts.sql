@@ -0,0 +1,8 @@
+-- Add attempts / next_retry_at so failed restore and analysis jobs are retried with backoff
+-- attempts counts failures of the current job; next_retry_at holds the dump back from being claimed until then
+
+ALTER TABLE dumps ADD COLUMN IF NOT EXISTS attempts INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE dumps ADD COLUMN IF NOT EXISTS next_retry_at TIMESTAMPTZ DEFAULT NULL;
+
+COMMENT ON COLUMN dumps.attempts IS 'Failed attempts of the current restore/analysis job';
+COMMENT ON COLUMN dumps.next_retry_at IS 'Earliest time a failed job may be claimed again (NULL when not backing off)';
@@ -0,0 +1,6 @@
+-- Add progress so clients can follow long-running restores
+-- The worker overwrites it at every phase change; NULL until a job starts
+
+ALTER TABLE dumps ADD COLUMN IF NOT EXISTS progress JSONB DEFAULT NULL;
+
+COMMENT ON COLUMN dumps.progress IS 'Current restore/analysis phase and progress details (DumpProgress)';
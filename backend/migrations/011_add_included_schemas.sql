@@ -0,0 +1,6 @@
+-- Add included_schemas column to support restoring a subset of schemas
+-- NULL or empty restores every schema in the dump
+
+ALTER TABLE dumps ADD COLUMN IF NOT EXISTS included_schemas TEXT[] DEFAULT NULL;
+
+COMMENT ON COLUMN dumps.included_schemas IS 'Schemas to restore (pg_restore -n); NULL restores all schemas';
@@ -0,0 +1,8 @@
+-- Add restore_started_at / restore_finished_at so restore latency can be monitored
+-- Set by the worker around the restore phase; NULL for dumps restored before this change
+
+ALTER TABLE dumps ADD COLUMN IF NOT EXISTS restore_started_at TIMESTAMPTZ DEFAULT NULL;
+ALTER TABLE dumps ADD COLUMN IF NOT EXISTS restore_finished_at TIMESTAMPTZ DEFAULT NULL;
+
+COMMENT ON COLUMN dumps.restore_started_at IS 'When the worker started the latest restore attempt';
+COMMENT ON COLUMN dumps.restore_finished_at IS 'When the latest restore completed successfully';
@@ -0,0 +1,6 @@
+-- Add mask_columns to anonymize sensitive columns after restore
+-- JSON array of {"column": "schema.table.column", "strategy": "null" | "hash" | "fixed", "value"?}
+
+ALTER TABLE dumps ADD COLUMN IF NOT EXISTS mask_columns JSONB DEFAULT NULL;
+
+COMMENT ON COLUMN dumps.mask_columns IS 'Columns masked by the worker before the dump becomes READY; NULL masks nothing';
@@ -0,0 +1,8 @@
+-- Add owner column so dumps are scoped to the API key identity that created them
+-- NULL for dumps created before API key auth or with auth disabled (visible to admin only once auth is enabled)
+
+ALTER TABLE dumps ADD COLUMN IF NOT EXISTS owner TEXT DEFAULT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_dumps_owner ON dumps(owner);
+
+COMMENT ON COLUMN dumps.owner IS 'Identity of the API key that created the dump';
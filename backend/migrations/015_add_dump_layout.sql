@@ -0,0 +1,7 @@
+-- Add dump_layout column recording how an upload is stored on disk
+-- One of SINGLE_FILE (dump.sql), DIRECTORY (pg_dump -Fd extracted to dump/), MULTI_FILE
+-- (several SQL files concatenated into dump.sql); NULL for dumps uploaded before layouts existed
+
+ALTER TABLE dumps ADD COLUMN IF NOT EXISTS dump_layout VARCHAR(50) DEFAULT NULL;
+
+COMMENT ON COLUMN dumps.dump_layout IS 'How the uploaded files are stored in the upload directory';
@@ -0,0 +1,7 @@
+-- Add upload_purged_at column recording when the worker deleted the uploaded files
+-- Set after a successful restore when DELETE_UPLOAD_AFTER_RESTORE is enabled; NULL while the
+-- files are kept
+
+ALTER TABLE dumps ADD COLUMN IF NOT EXISTS upload_purged_at TIMESTAMPTZ DEFAULT NULL;
+
+COMMENT ON COLUMN dumps.upload_purged_at IS 'When the uploaded files were deleted after a successful restore';
@@ -0,0 +1,8 @@
+-- Add schema_graph_compressed column holding gzip-compressed schema graphs
+-- Written instead of the jsonb schema_graph when COMPRESS_SCHEMA_GRAPHS is enabled; rows keep
+-- exactly one of the two columns set
+
+ALTER TABLE dump_schemas ADD COLUMN IF NOT EXISTS schema_graph_compressed BYTEA DEFAULT NULL;
+ALTER TABLE dump_schemas ALTER COLUMN schema_graph DROP NOT NULL;
+
+COMMENT ON COLUMN dump_schemas.schema_graph_compressed IS 'Schema graph as a format tag followed by gzip-compressed JSON';
@@ -0,0 +1,7 @@
+-- Add last_viewed_at column recording when a dump's sandbox was last queried
+-- Updated by the API's query handlers; the worker archives READY dumps idle for longer than
+-- SANDBOX_IDLE_TIMEOUT_HOURS by dropping their sandbox databases
+
+ALTER TABLE dumps ADD COLUMN IF NOT EXISTS last_viewed_at TIMESTAMPTZ DEFAULT NULL;
+
+COMMENT ON COLUMN dumps.last_viewed_at IS 'When the sandbox databases were last queried';
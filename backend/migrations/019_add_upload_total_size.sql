@@ -0,0 +1,6 @@
+-- Add upload_total_size column holding the total size a resumable upload declared
+-- Set from the Content-Range of the first chunk that gives a total; later chunks must agree
+
+ALTER TABLE dumps ADD COLUMN IF NOT EXISTS upload_total_size BIGINT DEFAULT NULL;
+
+COMMENT ON COLUMN dumps.upload_total_size IS 'Total size declared by a resumable upload';
@@ -0,0 +1,8 @@
+-- Add upload_claim columns so chunks of a resumable upload are written one at a time
+-- Set while a request writes a chunk, cleared when it finishes; no transaction is held meanwhile
+
+ALTER TABLE dumps ADD COLUMN IF NOT EXISTS upload_claim UUID DEFAULT NULL;
+ALTER TABLE dumps ADD COLUMN IF NOT EXISTS upload_claimed_at TIMESTAMPTZ DEFAULT NULL;
+
+COMMENT ON COLUMN dumps.upload_claim IS 'Token of the request writing an upload chunk (NULL when none is)';
+COMMENT ON COLUMN dumps.upload_claimed_at IS 'When the current upload chunk was claimed';
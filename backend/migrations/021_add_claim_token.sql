@@ -0,0 +1,6 @@
+-- Add claim_token column identifying the worker claim on a RESTORING/ANALYZING dump
+-- The claiming worker refreshes claimed_at under this token; its final updates only apply while it matches
+
+ALTER TABLE dumps ADD COLUMN IF NOT EXISTS claim_token UUID DEFAULT NULL;
+
+COMMENT ON COLUMN dumps.claim_token IS 'Token of the worker claim on this dump (NULL when unclaimed)';
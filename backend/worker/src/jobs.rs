@@ -1,14 +1,51 @@
 //! Job processing logic
 
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use sqlx::{postgres::PgPool, Row};
+use std::collections::HashSet;
 use std::path::Path;
+use std::time::{Duration, Instant};
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
 use crate::config::WorkerConfig;
+use crate::metrics;
+use crate::progress::{file_size, ProgressTracker};
 use db_viewer_core::adapter::DbAdapter;
-use db_viewer_core::domain::DumpStatus;
+use db_viewer_core::domain::{DumpLayout, DumpStatus, RestoreOutcome, RestoreTimings, SchemaGraph};
+use db_viewer_core::masking::ColumnMask;
+use db_viewer_core::schema_codec::compress_schema_graph;
+use db_viewer_core::CoreError;
+
+/// Extra restore attempts after a transient (connection) failure
+const RESTORE_RETRY_ATTEMPTS: u32 = 3;
+
+/// Delay before the first restore retry, doubled for each further attempt
+const RESTORE_RETRY_BASE_DELAY: Duration = Duration::from_secs(2);
+
+/// Upper bound for the backoff between job attempts
+const JOB_RETRY_MAX_DELAY: Duration = Duration::from_secs(3600);
+
+/// How often a running restore checks whether its dump was deleted
+const DELETION_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Jobs of each status processed per poll
+const JOBS_PER_POLL: usize = 10;
+
+/// A job claimed by this worker
+///
+/// While the job runs its claim is refreshed under `token`; the job's final
+/// updates only apply while the dump still carries that token.
+#[derive(Debug, Clone, Copy)]
+struct JobClaim {
+    dump_id: Uuid,
+    token: Uuid,
+}
+
+/// The claim on a job was cleared or taken over before the job finished
+#[derive(Debug, thiserror::Error)]
+#[error("claim on dump {0} was lost before the job finished")]
+struct ClaimLost(Uuid);
 
 /// Process pending restore and analysis jobs
 pub async fn process_pending_jobs<A: DbAdapter>(
@@ -18,32 +55,55 @@ pub async fn process_pending_jobs<A: DbAdapter>(
 ) -> anyhow::Result<usize> {
     let mut processed = 0;
 
-    // Process RESTORING jobs
-    let restoring_jobs = fetch_jobs_by_status(db_pool, DumpStatus::Restoring).await?;
-    for dump_id in restoring_jobs {
-        match process_restore(db_pool, adapter, config, dump_id).await {
+    // Process RESTORING jobs. Each job is claimed only when it starts, so its
+    // claim cannot expire while it waits behind the others.
+    for _ in 0..JOBS_PER_POLL {
+        let Some(claim) = claim_job_by_status(
+            db_pool,
+            DumpStatus::Restoring,
+            config.job_claim_timeout_mins,
+        )
+        .await?
+        else {
+            break;
+        };
+        let dump_id = claim.dump_id;
+        let job = process_restore(db_pool, adapter, config, claim);
+        match hold_claim(db_pool, claim, claim_refresh_interval(config), job).await {
             Ok(_) => {
                 info!("Successfully restored dump {}", dump_id);
                 processed += 1;
             }
+            Err(e) if e.is::<ClaimLost>() => warn!("Discarding restore: {}", e),
             Err(e) => {
                 error!("Failed to restore dump {}: {}", dump_id, e);
-                mark_error(db_pool, dump_id, &e.to_string()).await?;
+                record_failure(db_pool, config, claim, &e).await?;
             }
         }
     }
 
     // Process ANALYZING jobs
-    let analyzing_jobs = fetch_jobs_by_status(db_pool, DumpStatus::Analyzing).await?;
-    for dump_id in analyzing_jobs {
-        match process_analysis(db_pool, adapter, config, dump_id).await {
+    for _ in 0..JOBS_PER_POLL {
+        let Some(claim) = claim_job_by_status(
+            db_pool,
+            DumpStatus::Analyzing,
+            config.job_claim_timeout_mins,
+        )
+        .await?
+        else {
+            break;
+        };
+        let dump_id = claim.dump_id;
+        let job = process_analysis(db_pool, adapter, config, claim);
+        match hold_claim(db_pool, claim, claim_refresh_interval(config), job).await {
             Ok(_) => {
                 info!("Successfully analyzed dump {}", dump_id);
                 processed += 1;
             }
+            Err(e) if e.is::<ClaimLost>() => warn!("Discarding analysis: {}", e),
             Err(e) => {
                 error!("Failed to analyze dump {}: {}", dump_id, e);
-                mark_error(db_pool, dump_id, &e.to_string()).await?;
+                record_failure(db_pool, config, claim, &e).await?;
             }
         }
     }
@@ -51,52 +111,212 @@ pub async fn process_pending_jobs<A: DbAdapter>(
     Ok(processed)
 }
 
-async fn fetch_jobs_by_status(pool: &PgPool, status: DumpStatus) -> anyhow::Result<Vec<Uuid>> {
-    let rows =
-        sqlx::query("SELECT id FROM dumps WHERE status = $1 ORDER BY updated_at ASC LIMIT 10")
-            .bind(status.as_str())
-            .fetch_all(pool)
+/// How often a running job refreshes its claim: four times per claim
+/// timeout, so a job outliving the timeout is never taken for abandoned
+fn claim_refresh_interval(config: &WorkerConfig) -> Duration {
+    Duration::from_secs((config.job_claim_timeout_mins * 60 / 4).max(1))
+}
+
+/// Run a claimed job, refreshing `claimed_at` every `refresh` until it ends
+async fn hold_claim<F>(
+    db_pool: &PgPool,
+    claim: JobClaim,
+    refresh: Duration,
+    job: F,
+) -> anyhow::Result<()>
+where
+    F: std::future::Future<Output = anyhow::Result<()>>,
+{
+    tokio::pin!(job);
+    tokio::select! {
+        result = &mut job => return result,
+        () = refresh_claim(db_pool, claim, refresh) => {}
+    }
+    // The job's final update will notice and discard its results
+    warn!(
+        "Claim on dump {} was cleared or taken over while its job ran",
+        claim.dump_id
+    );
+    job.await
+}
+
+/// Refresh a claim periodically, returning once it no longer holds
+async fn refresh_claim(db_pool: &PgPool, claim: JobClaim, period: Duration) {
+    let mut interval = tokio::time::interval(period);
+    // The first tick completes immediately; the claim was just taken
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        let refreshed =
+            sqlx::query("UPDATE dumps SET claimed_at = $1 WHERE id = $2 AND claim_token = $3")
+                .bind(Utc::now())
+                .bind(claim.dump_id)
+                .bind(claim.token)
+                .execute(db_pool)
+                .await;
+        match refreshed {
+            Ok(result) if result.rows_affected() == 0 => return,
+            Ok(_) => {}
+            Err(e) => warn!(
+                "Failed to refresh the claim on dump {}: {}",
+                claim.dump_id, e
+            ),
+        }
+    }
+}
+
+/// Claim the oldest job in the given status for this worker
+///
+/// The row is locked with `FOR UPDATE SKIP LOCKED` and stamped with
+/// `claimed_at` and a fresh claim token before the transaction commits, so
+/// concurrent workers never pick up the same dump. Running jobs refresh their
+/// claim (see [`hold_claim`]); one not refreshed for `claim_timeout_mins` is
+/// treated as abandoned (e.g. the worker crashed) and can be taken over.
+/// Dumps backing off after a failed attempt are skipped until `next_retry_at`.
+async fn claim_job_by_status(
+    pool: &PgPool,
+    status: DumpStatus,
+    claim_timeout_mins: u64,
+) -> anyhow::Result<Option<JobClaim>> {
+    let now = Utc::now();
+    let claim_cutoff = now - chrono::Duration::minutes(claim_timeout_mins as i64);
+    let mut tx = pool.begin().await?;
+
+    let id: Option<Uuid> = sqlx::query_scalar(
+        r#"
+        SELECT id FROM dumps
+        WHERE status = $1
+          AND (claimed_at IS NULL OR claimed_at < $2)
+          AND (next_retry_at IS NULL OR next_retry_at <= $3)
+        ORDER BY updated_at ASC
+        LIMIT 1
+        FOR UPDATE SKIP LOCKED
+        "#,
+    )
+    .bind(status.as_str())
+    .bind(claim_cutoff)
+    .bind(now)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let claim = id.map(|dump_id| JobClaim {
+        dump_id,
+        token: Uuid::new_v4(),
+    });
+    if let Some(claim) = claim {
+        sqlx::query("UPDATE dumps SET claimed_at = $1, claim_token = $2 WHERE id = $3")
+            .bind(now)
+            .bind(claim.token)
+            .bind(claim.dump_id)
+            .execute(&mut *tx)
             .await?;
+    }
 
-    Ok(rows.iter().map(|row| row.get("id")).collect())
+    tx.commit().await?;
+
+    Ok(claim)
 }
 
+#[tracing::instrument(skip_all, fields(dump_id = %claim.dump_id))]
 async fn process_restore<A: DbAdapter>(
     db_pool: &PgPool,
     adapter: &A,
     config: &WorkerConfig,
-    dump_id: Uuid,
+    claim: JobClaim,
 ) -> anyhow::Result<()> {
+    let dump_id = claim.dump_id;
     info!("Processing restore for dump {}", dump_id);
 
-    // Check for excluded tables
-    let row = sqlx::query(r#"SELECT excluded_tables FROM dumps WHERE id = $1"#)
-        .bind(dump_id)
-        .fetch_one(db_pool)
-        .await?;
+    // Check for excluded tables and schema filters
+    let row = sqlx::query(
+        r#"SELECT excluded_tables, included_schemas, dump_format, dump_layout, mask_columns,
+                  sandbox_databases
+           FROM dumps WHERE id = $1"#,
+    )
+    .bind(dump_id)
+    .fetch_one(db_pool)
+    .await?;
 
     let excluded_tables: Option<Vec<String>> = row.get("excluded_tables");
+    let included_schemas: Option<Vec<String>> = row.get("included_schemas");
+    let masks: Vec<ColumnMask> = match row.get::<Option<serde_json::Value>, _>("mask_columns") {
+        Some(value) => serde_json::from_value(value)?,
+        None => Vec::new(),
+    };
+
+    // Detected by the API on upload; NULL for dumps uploaded before detection
+    let dump_format: Option<String> = row.get("dump_format");
+    info!(
+        "Dump {} format: {}",
+        dump_id,
+        dump_format.as_deref().unwrap_or("not recorded")
+    );
 
-    let dump_path = format!("{}/{}/dump.sql", config.upload_dir, dump_id);
+    // NULL for dumps uploaded as a single file before layouts were recorded
+    let layout = row
+        .get::<Option<String>, _>("dump_layout")
+        .as_deref()
+        .and_then(DumpLayout::parse)
+        .unwrap_or(DumpLayout::SingleFile);
+    let dump_path = format!("{}/{}/{}", config.upload_dir, dump_id, layout.dump_name());
     let sandbox_db_name = format!("sandbox_{}", dump_id.to_string().replace('-', "_"));
 
-    // Restore the dump - with or without exclusions
-    let restored_databases = if let Some(ref exclusions) = excluded_tables {
-        if !exclusions.is_empty() {
-            info!(
-                "Restoring dump with {} excluded tables: {:?}",
-                exclusions.len(),
-                exclusions
-            );
-            adapter
-                .restore_dump_with_exclusions(&dump_path, &sandbox_db_name, exclusions)
-                .await?
-        } else {
-            adapter.restore_dump(&dump_path, &sandbox_db_name).await?
+    // Set only by a successful restore: this is an archived dump restored
+    // again. Its databases should be gone, but pg_dumpall restores create
+    // theirs without overwriting, so drop any eviction left behind.
+    let previous_databases: Option<Vec<String>> = row.get("sandbox_databases");
+    for db_name in previous_databases.unwrap_or_default() {
+        if let Err(e) = adapter.drop_database(&db_name).await {
+            warn!("Failed to drop {} before restoring again: {}", db_name, e);
+        }
+    }
+
+    sqlx::query(
+        "UPDATE dumps SET restore_started_at = $1, restore_finished_at = NULL WHERE id = $2",
+    )
+    .bind(Utc::now())
+    .bind(dump_id)
+    .execute(db_pool)
+    .await?;
+
+    let mut timings = RestoreTimings::default();
+    let (progress, progress_writer) = ProgressTracker::spawn(db_pool.clone(), dump_id);
+    let restore = restore_with_retries(
+        adapter,
+        &dump_path,
+        &sandbox_db_name,
+        excluded_tables.as_deref(),
+        included_schemas.as_deref(),
+        &mut timings,
+        &progress,
+    );
+    let RestoreOutcome {
+        databases: restored_databases,
+        warnings,
+    } = tokio::select! {
+        result = restore => result?,
+        () = wait_for_deletion(db_pool, dump_id) => {
+            // Dropping the restore future killed the pg_restore/psql child
+            warn!("Dump {} was deleted, restore cancelled", dump_id);
+            if let Err(e) = adapter.drop_database(&sandbox_db_name).await {
+                warn!("Failed to drop {} after cancelling: {}", sandbox_db_name, e);
+            }
+            return Err(CoreError::RestoreCancelled(format!(
+                "dump {} was deleted during restore",
+                dump_id
+            ))
+            .into());
         }
-    } else {
-        adapter.restore_dump(&dump_path, &sandbox_db_name).await?
     };
+    drop(progress);
+    let _ = progress_writer.await;
+
+    info!(
+        dump_id = %dump_id,
+        decompress_ms = timings.decompress_ms,
+        restore_ms = timings.restore_ms,
+        "Restore phases completed"
+    );
 
     info!(
         "Data restored to {} database(s): {:?}",
@@ -104,37 +324,73 @@ async fn process_restore<A: DbAdapter>(
         restored_databases
     );
 
+    // Mask before the sandbox is analyzed or becomes visible; never leave an
+    // unmasked copy behind if that fails
+    if let Err(e) = apply_masks(adapter, &restored_databases, &masks).await {
+        for db_name in &restored_databases {
+            if let Err(drop_err) = adapter.drop_database(db_name).await {
+                warn!(
+                    "Failed to drop {} after masking failed: {}",
+                    db_name, drop_err
+                );
+            }
+        }
+        return Err(e.into());
+    }
+
+    // Query endpoints in the API connect as the read-only role
+    if let Some(role) = config.sandbox_read_only_role() {
+        for db_name in &restored_databases {
+            adapter.grant_read_only(db_name, &role).await?;
+        }
+    }
+
     // Use the first database as the default for backwards compatibility
-    let primary_db = restored_databases
-        .first()
-        .cloned()
-        .unwrap_or(sandbox_db_name);
+    let primary_db = primary_database(&restored_databases, &sandbox_db_name);
+
+    if !warnings.is_empty() {
+        warn!(
+            "Restore of dump {} finished with {} warning line(s)",
+            dump_id,
+            warnings.len()
+        );
+    }
 
     // Update status to ANALYZING with the database names
-    sqlx::query(
+    let updated = sqlx::query(
         r#"
         UPDATE dumps
-        SET status = $1, sandbox_db_name = $2, sandbox_databases = $3, updated_at = $4
-        WHERE id = $5
+        SET status = $1, sandbox_db_name = $2, sandbox_databases = $3,
+            restore_timings = $4, restore_warnings = $5, claimed_at = NULL, claim_token = NULL,
+            attempts = 0, next_retry_at = NULL, restore_finished_at = $6, updated_at = $6
+        WHERE id = $7 AND claim_token = $8
         "#,
     )
     .bind(DumpStatus::Analyzing.as_str())
     .bind(&primary_db)
     .bind(&restored_databases)
+    .bind(serde_json::to_value(timings)?)
+    .bind(join_warnings(&warnings))
     .bind(Utc::now())
     .bind(dump_id)
+    .bind(claim.token)
     .execute(db_pool)
     .await?;
+    if updated.rows_affected() == 0 {
+        return Err(ClaimLost(dump_id).into());
+    }
 
     Ok(())
 }
 
+#[tracing::instrument(skip_all, fields(dump_id = %claim.dump_id))]
 async fn process_analysis<A: DbAdapter>(
     db_pool: &PgPool,
     adapter: &A,
-    _config: &WorkerConfig,
-    dump_id: Uuid,
+    config: &WorkerConfig,
+    claim: JobClaim,
 ) -> anyhow::Result<()> {
+    let dump_id = claim.dump_id;
     info!("Processing analysis for dump {}", dump_id);
 
     // Get all sandbox databases
@@ -146,12 +402,7 @@ async fn process_analysis<A: DbAdapter>(
     let primary_db: String = row.get("sandbox_db_name");
     let all_databases: Option<Vec<String>> = row.get("sandbox_databases");
 
-    // List of databases to analyze
-    let databases_to_analyze = if let Some(ref dbs) = all_databases {
-        dbs.clone()
-    } else {
-        vec![primary_db.clone()]
-    };
+    let databases_to_analyze = databases_to_analyze(&primary_db, all_databases);
 
     info!(
         "Analyzing {} database(s) for dump {}",
@@ -159,28 +410,41 @@ async fn process_analysis<A: DbAdapter>(
         dump_id
     );
 
-    // Process each database
-    for db_name in databases_to_analyze {
-        info!("Analyzing database: {}", db_name);
-
-        // Run ANALYZE to update table statistics (required for accurate row counts)
-        adapter.analyze_database(&db_name).await?;
+    let mut timings = RestoreTimings::default();
+    let (progress, progress_writer) = ProgressTracker::spawn(db_pool.clone(), dump_id);
+    let schema_graphs =
+        analyze_with_timings(adapter, &databases_to_analyze, &mut timings, &progress).await?;
+    drop(progress);
+    let _ = progress_writer.await;
 
-        // Build schema graph
-        let schema_graph = adapter.build_schema_graph(&db_name).await?;
+    info!(
+        dump_id = %dump_id,
+        analyze_ms = timings.analyze_ms,
+        schema_graph_ms = timings.schema_graph_ms,
+        "Analysis phases completed"
+    );
 
-        // Store schema graph in metadata with database name
+    for (db_name, schema_graph) in schema_graphs {
+        // Store schema graph in metadata with database name, in exactly one
+        // of the jsonb and compressed columns
+        let (json, compressed) = if config.compress_schema_graphs {
+            (None, Some(compress_schema_graph(&schema_graph)?))
+        } else {
+            (Some(serde_json::to_value(&schema_graph)?), None)
+        };
         sqlx::query(
             r#"
-            INSERT INTO dump_schemas (dump_id, database_name, schema_graph, created_at)
-            VALUES ($1, $2, $3, $4)
+            INSERT INTO dump_schemas
+                (dump_id, database_name, schema_graph, schema_graph_compressed, created_at)
+            VALUES ($1, $2, $3, $4, $5)
             ON CONFLICT (dump_id, database_name) DO UPDATE
-            SET schema_graph = $3, created_at = $4
+            SET schema_graph = $3, schema_graph_compressed = $4, created_at = $5
             "#,
         )
         .bind(dump_id)
         .bind(&db_name)
-        .bind(serde_json::to_value(&schema_graph)?)
+        .bind(json)
+        .bind(compressed)
         .bind(Utc::now())
         .execute(db_pool)
         .await?;
@@ -188,35 +452,415 @@ async fn process_analysis<A: DbAdapter>(
         info!("Successfully analyzed database: {}", db_name);
     }
 
-    // Update status to READY
-    sqlx::query(
+    // Update status to READY, merging analysis timings into the restore ones
+    let updated = sqlx::query(
         r#"
         UPDATE dumps
-        SET status = $1, updated_at = $2
-        WHERE id = $3
+        SET status = $1,
+            restore_timings = COALESCE(restore_timings, '{}'::jsonb) || $2,
+            claimed_at = NULL,
+            claim_token = NULL,
+            attempts = 0,
+            next_retry_at = NULL,
+            updated_at = $3
+        WHERE id = $4 AND claim_token = $5
         "#,
     )
     .bind(DumpStatus::Ready.as_str())
+    .bind(serde_json::json!({
+        "analyze_ms": timings.analyze_ms,
+        "schema_graph_ms": timings.schema_graph_ms,
+    }))
     .bind(Utc::now())
     .bind(dump_id)
+    .bind(claim.token)
     .execute(db_pool)
     .await?;
+    if updated.rows_affected() == 0 {
+        return Err(ClaimLost(dump_id).into());
+    }
+
+    if config.delete_upload_after_restore {
+        // The dump is READY either way; a leftover file is only wasted space
+        match remove_upload(&config.upload_dir, dump_id) {
+            Ok(removed) => {
+                if removed {
+                    info!("Removed uploaded files of dump {}", dump_id);
+                }
+                sqlx::query("UPDATE dumps SET upload_purged_at = $1 WHERE id = $2")
+                    .bind(Utc::now())
+                    .bind(dump_id)
+                    .execute(db_pool)
+                    .await?;
+            }
+            Err(e) => warn!("Failed to remove uploaded files of dump {}: {}", dump_id, e),
+        }
+    }
 
     Ok(())
 }
 
-async fn mark_error(pool: &PgPool, dump_id: Uuid, error_message: &str) -> anyhow::Result<()> {
+/// Remove the upload directory of a dump
+///
+/// Returns whether there was anything to remove.
+fn remove_upload(upload_dir: &str, dump_id: Uuid) -> std::io::Result<bool> {
+    let dir = Path::new(upload_dir).join(dump_id.to_string());
+    match std::fs::remove_dir_all(&dir) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// List of databases to analyze for a dump
+///
+/// Every restored database gets its own `dump_schemas` row. Dumps restored
+/// before `sandbox_databases` existed (or with an empty list) fall back to the
+/// primary database.
+fn databases_to_analyze(primary_db: &str, all_databases: Option<Vec<String>>) -> Vec<String> {
+    match all_databases {
+        Some(dbs) if !dbs.is_empty() => dbs,
+        _ => vec![primary_db.to_string()],
+    }
+}
+
+/// Pick the database stored in `sandbox_db_name` for a restore
+///
+/// This is the first non-empty restored database (for pg_dumpall dumps, the
+/// first database in the dump), falling back to the sandbox name.
+fn primary_database(restored_databases: &[String], sandbox_db_name: &str) -> String {
+    restored_databases
+        .iter()
+        .find(|db| !db.trim().is_empty())
+        .cloned()
+        .unwrap_or_else(|| sandbox_db_name.to_string())
+}
+
+/// Decompress and restore a dump, recording the duration of each phase
+async fn restore_with_timings<A: DbAdapter>(
+    adapter: &A,
+    dump_path: &str,
+    sandbox_db_name: &str,
+    excluded_tables: Option<&[String]>,
+    included_schemas: Option<&[String]>,
+    timings: &mut RestoreTimings,
+    progress: &ProgressTracker,
+) -> anyhow::Result<RestoreOutcome> {
+    progress.phase("decompress", file_size(dump_path));
+    let started = Instant::now();
+    let restore_path = adapter.decompress_dump(dump_path).await?;
+    let elapsed = started.elapsed();
+    timings.decompress_ms = elapsed.as_millis() as u64;
+    metrics::observe_phase("decompress", elapsed);
+
+    progress.phase("restore", file_size(&restore_path));
+
+    // Restore the dump - limited to some schemas, with or without exclusions
+    let started = Instant::now();
+    let outcome = match (included_schemas, excluded_tables) {
+        (Some(schemas), _) if !schemas.is_empty() => {
+            adapter
+                .restore_dump_filtered(&restore_path, sandbox_db_name, schemas)
+                .await?
+        }
+        (_, Some(exclusions)) if !exclusions.is_empty() => {
+            info!(
+                "Restoring dump with {} excluded tables: {:?}",
+                exclusions.len(),
+                exclusions
+            );
+            adapter
+                .restore_dump_with_exclusions(&restore_path, sandbox_db_name, exclusions)
+                .await?
+        }
+        _ => adapter.restore_dump(&restore_path, sandbox_db_name).await?,
+    };
+    let elapsed = started.elapsed();
+    timings.restore_ms = elapsed.as_millis() as u64;
+    metrics::observe_phase("restore", elapsed);
+
+    Ok(outcome)
+}
+
+/// Restore, retrying with exponential backoff while the failure is transient
+/// (e.g. the sandbox server is restarting)
+async fn restore_with_retries<A: DbAdapter>(
+    adapter: &A,
+    dump_path: &str,
+    sandbox_db_name: &str,
+    excluded_tables: Option<&[String]>,
+    included_schemas: Option<&[String]>,
+    timings: &mut RestoreTimings,
+    progress: &ProgressTracker,
+) -> anyhow::Result<RestoreOutcome> {
+    let mut attempt = 0;
+    loop {
+        match restore_with_timings(
+            adapter,
+            dump_path,
+            sandbox_db_name,
+            excluded_tables,
+            included_schemas,
+            timings,
+            progress,
+        )
+        .await
+        {
+            Err(e) if attempt < RESTORE_RETRY_ATTEMPTS && is_transient(&e) => {
+                let delay = RESTORE_RETRY_BASE_DELAY * 2u32.pow(attempt);
+                attempt += 1;
+                warn!(
+                    "Restore into {} failed ({}), retry {}/{} in {:?}",
+                    sandbox_db_name, e, attempt, RESTORE_RETRY_ATTEMPTS, delay
+                );
+                // A half-restored database would make the next CREATE DATABASE fail
+                if let Err(drop_err) = adapter.drop_database(sandbox_db_name).await {
+                    warn!(
+                        "Failed to drop {} before retrying: {}",
+                        sandbox_db_name, drop_err
+                    );
+                }
+                tokio::time::sleep(delay).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Resolve once the dump row is gone or marked DELETED
+async fn wait_for_deletion(db_pool: &PgPool, dump_id: Uuid) {
+    let mut interval = tokio::time::interval(DELETION_POLL_INTERVAL);
+    loop {
+        interval.tick().await;
+        let status: Result<Option<String>, _> =
+            sqlx::query_scalar("SELECT status FROM dumps WHERE id = $1")
+                .bind(dump_id)
+                .fetch_optional(db_pool)
+                .await;
+        match status {
+            Ok(None) => return,
+            Ok(Some(status)) if status == DumpStatus::Deleted.as_str() => return,
+            Ok(Some(_)) => {}
+            Err(e) => warn!(
+                "Failed to check whether dump {} was deleted: {}",
+                dump_id, e
+            ),
+        }
+    }
+}
+
+fn is_transient(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<CoreError>()
+        .is_some_and(CoreError::is_transient)
+}
+
+/// Message stored in `dumps.error_message`, prefixed with the restore error
+/// kind (e.g. `[RESTORE_SYNTAX_ERROR] ...`) when there is one
+fn error_message(error: &anyhow::Error) -> String {
+    match error
+        .downcast_ref::<CoreError>()
+        .and_then(CoreError::restore_kind)
+    {
+        Some(kind) => format!("[{}] {}", kind, error),
+        None => error.to_string(),
+    }
+}
+
+/// Join restore warnings for the `restore_warnings` column (NULL when none)
+fn join_warnings(warnings: &[String]) -> Option<String> {
+    (!warnings.is_empty()).then(|| warnings.join("\n"))
+}
+
+/// Apply the dump's column masks to every restored database
+///
+/// Fails if the table of any mask exists in none of the databases.
+async fn apply_masks<A: DbAdapter>(
+    adapter: &A,
+    databases: &[String],
+    masks: &[ColumnMask],
+) -> Result<(), CoreError> {
+    if masks.is_empty() {
+        return Ok(());
+    }
+
+    let mut masked: HashSet<(String, String)> = HashSet::new();
+    for db_name in databases {
+        masked.extend(adapter.mask_columns(db_name, masks).await?);
+    }
+
+    // A mask matching no table (e.g. a typo) would leave its column readable
+    let unmatched: Vec<&str> = masks
+        .iter()
+        .filter(|m| {
+            m.target().map_or(true, |(schema, table, _)| {
+                !masked.contains(&(schema.to_string(), table.to_string()))
+            })
+        })
+        .map(|m| m.column.as_str())
+        .collect();
+    if !unmatched.is_empty() {
+        return Err(CoreError::Validation(format!(
+            "Column mask(s) {} match no table in the restored database(s)",
+            unmatched.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+/// Run ANALYZE and build the schema graph for each database, recording the
+/// total duration of each phase across all databases
+async fn analyze_with_timings<A: DbAdapter>(
+    adapter: &A,
+    databases: &[String],
+    timings: &mut RestoreTimings,
+    progress: &ProgressTracker,
+) -> anyhow::Result<Vec<(String, SchemaGraph)>> {
+    let mut analyze_elapsed = std::time::Duration::ZERO;
+    let mut schema_graph_elapsed = std::time::Duration::ZERO;
+    let mut schema_graphs = Vec::with_capacity(databases.len());
+
+    progress.phase("analyze", None);
+    progress.databases(0, databases.len());
+    for db_name in databases {
+        info!("Analyzing database: {}", db_name);
+
+        // Run ANALYZE to update table statistics (required for accurate row counts)
+        let started = Instant::now();
+        adapter.analyze_database(db_name).await?;
+        analyze_elapsed += started.elapsed();
+
+        // Build schema graph
+        let started = Instant::now();
+        let schema_graph = adapter.build_schema_graph(db_name).await?;
+        schema_graph_elapsed += started.elapsed();
+
+        schema_graphs.push((db_name.clone(), schema_graph));
+        progress.databases(schema_graphs.len(), databases.len());
+    }
+
+    timings.analyze_ms = analyze_elapsed.as_millis() as u64;
+    timings.schema_graph_ms = schema_graph_elapsed.as_millis() as u64;
+    metrics::observe_phase("analyze", analyze_elapsed);
+    metrics::observe_phase("schema_graph", schema_graph_elapsed);
+
+    Ok(schema_graphs)
+}
+
+/// What to do with a job after a failed attempt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FailureAction {
+    /// Leave the dump in its processing state and retry after the delay
+    RetryAfter(Duration),
+    /// Mark the dump ERROR
+    GiveUp,
+}
+
+/// Decide how to handle the `attempts`-th failure of a job
+///
+/// Bad dumps and missing tools fail the same way every time, so they give up
+/// immediately; anything else is retried with exponential backoff until
+/// `max_attempts` failures.
+fn failure_action(
+    attempts: u32,
+    max_attempts: u32,
+    base_delay: Duration,
+    error: &anyhow::Error,
+) -> FailureAction {
+    let permanent = matches!(
+        error.downcast_ref::<CoreError>(),
+        Some(
+            CoreError::RestoreSyntaxError(_)
+                | CoreError::RestoreToolMissing(_)
+                | CoreError::RestoreCancelled(_)
+                | CoreError::Validation(_)
+        )
+    );
+    if permanent || attempts >= max_attempts {
+        return FailureAction::GiveUp;
+    }
+
+    let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+    FailureAction::RetryAfter(base_delay.saturating_mul(factor).min(JOB_RETRY_MAX_DELAY))
+}
+
+/// Count a failed attempt, scheduling a retry or marking the dump ERROR
+///
+/// Nothing is recorded once the claim no longer holds: the dump was re-queued
+/// or another worker owns it now.
+async fn record_failure(
+    pool: &PgPool,
+    config: &WorkerConfig,
+    claim: JobClaim,
+    error: &anyhow::Error,
+) -> anyhow::Result<()> {
+    let dump_id = claim.dump_id;
+    let attempts: Option<i32> = sqlx::query_scalar("SELECT attempts FROM dumps WHERE id = $1")
+        .bind(dump_id)
+        .fetch_optional(pool)
+        .await?;
+    let Some(attempts) = attempts else {
+        // Deleted while the job ran (e.g. a cancelled restore)
+        info!("Dump {} no longer exists, not recording failure", dump_id);
+        return Ok(());
+    };
+    let attempts = attempts.max(0) as u32 + 1;
+
+    match failure_action(
+        attempts,
+        config.job_max_attempts,
+        Duration::from_secs(config.job_retry_base_delay_secs),
+        error,
+    ) {
+        FailureAction::GiveUp => mark_error(pool, claim, attempts, &error_message(error)).await,
+        FailureAction::RetryAfter(delay) => {
+            warn!(
+                "Attempt {}/{} for dump {} failed, retrying in {:?}",
+                attempts, config.job_max_attempts, dump_id, delay
+            );
+            let now = Utc::now();
+            let next_retry_at = now + chrono::Duration::from_std(delay)?;
+            sqlx::query(
+                r#"
+                UPDATE dumps
+                SET attempts = $1, next_retry_at = $2, error_message = $3,
+                    claimed_at = NULL, claim_token = NULL, updated_at = $4
+                WHERE id = $5 AND claim_token = $6
+                "#,
+            )
+            .bind(attempts as i32)
+            .bind(next_retry_at)
+            .bind(error_message(error))
+            .bind(now)
+            .bind(dump_id)
+            .bind(claim.token)
+            .execute(pool)
+            .await?;
+            Ok(())
+        }
+    }
+}
+
+async fn mark_error(
+    pool: &PgPool,
+    claim: JobClaim,
+    attempts: u32,
+    error_message: &str,
+) -> anyhow::Result<()> {
     sqlx::query(
         r#"
         UPDATE dumps
-        SET status = $1, error_message = $2, updated_at = $3
-        WHERE id = $4
+        SET status = $1, error_message = $2, attempts = $3, next_retry_at = NULL,
+            claimed_at = NULL, claim_token = NULL, updated_at = $4
+        WHERE id = $5 AND claim_token = $6
         "#,
     )
     .bind(DumpStatus::Error.as_str())
     .bind(error_message)
+    .bind(attempts as i32)
     .bind(Utc::now())
-    .bind(dump_id)
+    .bind(claim.dump_id)
+    .bind(claim.token)
     .execute(pool)
     .await?;
 
@@ -306,8 +950,9 @@ pub async fn cleanup_expired_dumps<A: DbAdapter>(
             warn!("Failed to delete schema cache for dump {}: {}", dump_id, e);
         }
 
-        // Mark dump as DELETED in metadata
-        sqlx::query(
+        // Mark dump as DELETED in metadata; a failure here is retried on the
+        // next sweep instead of aborting the remaining dumps
+        if let Err(e) = sqlx::query(
             r#"
             UPDATE dumps
             SET status = $1, updated_at = $2
@@ -318,7 +963,11 @@ pub async fn cleanup_expired_dumps<A: DbAdapter>(
         .bind(Utc::now())
         .bind(dump_id)
         .execute(db_pool)
-        .await?;
+        .await
+        {
+            warn!("Failed to mark expired dump {} as deleted: {}", dump_id, e);
+            continue;
+        }
 
         info!("Successfully cleaned up expired dump: {}", dump_id);
         cleaned += 1;
@@ -435,9 +1084,1078 @@ pub async fn cleanup_stale_dumps<A: DbAdapter>(
     Ok(cleaned)
 }
 
+/// A dump archived for going unviewed, with the sandbox databases dropped
+#[derive(Debug)]
+pub struct IdleDump {
+    pub id: Uuid,
+    pub sandbox_databases: Vec<String>,
+}
+
+/// Archive the least recently viewed READY dump not viewed since `cutoff`
+///
+/// A dump never viewed counts from when it became READY. Dumps whose upload
+/// was purged are skipped since they could not be restored again. The dump
+/// row stays locked while its sandbox databases are dropped and its cached
+/// schema graphs deleted, and only then becomes ARCHIVED: a view meanwhile
+/// waits for the lock, and no restore can start before the old databases are
+/// gone. Returns `None` once no idle dump is left.
+pub async fn archive_next_idle_dump<A: DbAdapter>(
+    db_pool: &PgPool,
+    adapter: &A,
+    cutoff: DateTime<Utc>,
+) -> anyhow::Result<Option<IdleDump>> {
+    let mut tx = db_pool.begin().await?;
+    let row = sqlx::query(
+        r#"
+        SELECT id, sandbox_db_name, sandbox_databases
+        FROM dumps
+        WHERE status = $1
+          AND upload_purged_at IS NULL
+          AND COALESCE(last_viewed_at, updated_at) < $2
+        ORDER BY COALESCE(last_viewed_at, updated_at) ASC
+        LIMIT 1
+        FOR UPDATE SKIP LOCKED
+        "#,
+    )
+    .bind(DumpStatus::Ready.as_str())
+    .bind(cutoff)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some(row) = row else {
+        return Ok(None);
+    };
+
+    let sandbox_db_name: Option<String> = row.get("sandbox_db_name");
+    let sandbox_databases: Option<Vec<String>> = row.get("sandbox_databases");
+    let dump = IdleDump {
+        id: row.get("id"),
+        sandbox_databases: sandbox_databases
+            .unwrap_or_else(|| sandbox_db_name.into_iter().collect()),
+    };
+
+    for db_name in &dump.sandbox_databases {
+        if let Err(e) = adapter.drop_database(db_name).await {
+            warn!("Failed to drop sandbox database {}: {}", db_name, e);
+        }
+    }
+    sqlx::query("DELETE FROM dump_schemas WHERE dump_id = $1")
+        .bind(dump.id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("UPDATE dumps SET status = $1, updated_at = NOW() WHERE id = $2")
+        .bind(DumpStatus::Archived.as_str())
+        .bind(dump.id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(Some(dump))
+}
+
+/// Drop the sandbox databases of dumps idle for longer than
+/// `sandbox_idle_timeout_hours`
+///
+/// The dump row and the uploaded files are kept so the dump can be restored
+/// again; its cached schema graph is removed with the databases it
+/// describes. Dumps are archived one at a time (see
+/// [`archive_next_idle_dump`]).
+pub async fn evict_idle_sandboxes<A: DbAdapter>(
+    db_pool: &PgPool,
+    adapter: &A,
+    config: &WorkerConfig,
+) -> anyhow::Result<usize> {
+    let Some(hours) = config.sandbox_idle_timeout_hours else {
+        return Ok(0);
+    };
+    let cutoff = Utc::now() - chrono::Duration::hours(hours as i64);
+
+    let mut archived = 0;
+    while let Some(dump) = archive_next_idle_dump(db_pool, adapter, cutoff).await? {
+        info!("Archived dump {} after {} idle hours", dump.id, hours);
+        archived += 1;
+    }
+
+    Ok(archived)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use async_trait::async_trait;
+    use db_viewer_core::connection::ReadOnlyRole;
+    use db_viewer_core::domain::{
+        CheckConstraint, EnumType, ForeignKey, IndexInfo, RelationDirection, RestoreOutcome,
+        SequenceInfo, TableInfo, TriggerInfo, UniqueConstraint, ViewInfo,
+    };
+    use db_viewer_core::filter::{FilterPredicate, OrderBy};
+    use db_viewer_core::Result as CoreResult;
+    use mockall::mock;
+
+    mock! {
+        pub Adapter {}
+
+        #[async_trait]
+        impl DbAdapter for Adapter {
+            async fn decompress_dump(&self, dump_path: &str) -> CoreResult<String>;
+            async fn restore_dump(&self, dump_path: &str, db_name: &str) -> CoreResult<RestoreOutcome>;
+            async fn restore_dump_with_exclusions(
+                &self,
+                dump_path: &str,
+                db_name: &str,
+                excluded_tables: &[String],
+            ) -> CoreResult<RestoreOutcome>;
+            async fn restore_dump_filtered(
+                &self,
+                dump_path: &str,
+                db_name: &str,
+                include_schemas: &[String],
+            ) -> CoreResult<RestoreOutcome>;
+            async fn list_tables(&self, db_name: &str) -> CoreResult<Vec<TableInfo>>;
+            async fn list_foreign_keys(&self, db_name: &str) -> CoreResult<Vec<ForeignKey>>;
+            async fn list_indexes(&self, db_name: &str) -> CoreResult<Vec<IndexInfo>>;
+            async fn list_views(&self, db_name: &str) -> CoreResult<Vec<ViewInfo>>;
+            async fn list_triggers(&self, db_name: &str) -> CoreResult<Vec<TriggerInfo>>;
+            async fn list_enum_types(&self, db_name: &str) -> CoreResult<Vec<EnumType>>;
+            async fn list_sequences(&self, db_name: &str) -> CoreResult<Vec<SequenceInfo>>;
+            async fn list_unique_constraints(
+                &self,
+                db_name: &str,
+                schema: &str,
+                table: &str,
+            ) -> CoreResult<Vec<UniqueConstraint>>;
+            async fn list_check_constraints(
+                &self,
+                db_name: &str,
+                schema: &str,
+                table: &str,
+            ) -> CoreResult<Vec<CheckConstraint>>;
+            async fn build_schema_graph(&self, db_name: &str) -> CoreResult<SchemaGraph>;
+            async fn estimate_row_counts(&self, db_name: &str) -> CoreResult<Vec<(String, String, i64)>>;
+            async fn exact_row_count(&self, db_name: &str, schema: &str, table: &str) -> CoreResult<i64>;
+            async fn fetch_sample_rows(
+                &self,
+                db_name: &str,
+                schema: &str,
+                table: &str,
+                limit: usize,
+            ) -> CoreResult<Vec<serde_json::Value>>;
+            async fn fetch_sample_rows_filtered(
+                &self,
+                db_name: &str,
+                schema: &str,
+                table: &str,
+                filter: &[FilterPredicate],
+                order_by: Option<OrderBy>,
+                limit: usize,
+            ) -> CoreResult<Vec<serde_json::Value>>;
+            async fn fetch_related_rows(
+                &self,
+                db_name: &str,
+                fk: &ForeignKey,
+                direction: RelationDirection,
+                key_values: &[String],
+                limit: usize,
+            ) -> CoreResult<Vec<serde_json::Value>>;
+            async fn drop_database(&self, db_name: &str) -> CoreResult<()>;
+            async fn database_exists(&self, db_name: &str) -> CoreResult<bool>;
+            async fn create_database(&self, db_name: &str, overwrite: bool) -> CoreResult<()>;
+            async fn analyze_database(&self, db_name: &str) -> CoreResult<()>;
+            async fn mask_columns(
+                &self,
+                db_name: &str,
+                masks: &[ColumnMask],
+            ) -> CoreResult<Vec<(String, String)>>;
+            async fn grant_read_only(&self, db_name: &str, role: &ReadOnlyRole) -> CoreResult<()>;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_apply_masks_rejects_mask_matching_no_table() {
+        let mut adapter = MockAdapter::new();
+        adapter
+            .expect_mask_columns()
+            .returning(|db_name, _| match db_name {
+                "app" => Ok(vec![("public".to_string(), "users".to_string())]),
+                _ => Ok(Vec::new()),
+            });
+        let mask = |column: &str| ColumnMask {
+            column: column.to_string(),
+            strategy: db_viewer_core::masking::MaskStrategy::Hash,
+        };
+        let databases = vec!["app".to_string(), "other".to_string()];
+
+        apply_masks(&adapter, &databases, &[mask("public.users.email")])
+            .await
+            .unwrap();
+        let err = apply_masks(
+            &adapter,
+            &databases,
+            &[mask("public.users.email"), mask("public.user.email")],
+        )
+        .await
+        .unwrap_err();
+        assert!(
+            matches!(&err, CoreError::Validation(msg) if msg.contains("public.user.email") && !msg.contains("users")),
+            "{}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn test_restore_keeps_all_pg_dumpall_databases() {
+        let mut adapter = MockAdapter::new();
+        adapter
+            .expect_decompress_dump()
+            .returning(|path| Ok(path.to_string()));
+        adapter.expect_restore_dump().returning(|_, prefix| {
+            Ok(RestoreOutcome {
+                databases: vec![format!("{}_app", prefix), format!("{}_analytics", prefix)],
+                warnings: vec![],
+            })
+        });
+
+        let mut timings = RestoreTimings::default();
+        let RestoreOutcome { databases, .. } = restore_with_timings(
+            &adapter,
+            "/data/uploads/x/dump.sql",
+            "sandbox_x",
+            None,
+            None,
+            &mut timings,
+            &ProgressTracker::detached(),
+        )
+        .await
+        .unwrap();
+
+        // Every restored database is kept for the sandbox_databases column
+        assert_eq!(databases, vec!["sandbox_x_app", "sandbox_x_analytics"]);
+        assert_eq!(primary_database(&databases, "sandbox_x"), "sandbox_x_app");
+    }
+
+    #[tokio::test]
+    async fn test_restore_uses_schema_filter_over_exclusions() {
+        let mut adapter = MockAdapter::new();
+        adapter
+            .expect_decompress_dump()
+            .returning(|path| Ok(path.to_string()));
+        adapter
+            .expect_restore_dump_filtered()
+            .withf(|_, db_name, schemas| db_name == "sandbox_x" && schemas == ["sales"])
+            .times(1)
+            .returning(|_, db_name, _| {
+                Ok(RestoreOutcome {
+                    databases: vec![db_name.to_string()],
+                    warnings: vec![],
+                })
+            });
+
+        let mut timings = RestoreTimings::default();
+        let outcome = restore_with_timings(
+            &adapter,
+            "/data/uploads/x/dump.sql",
+            "sandbox_x",
+            Some(&["public.logs".to_string()]),
+            Some(&["sales".to_string()]),
+            &mut timings,
+            &ProgressTracker::detached(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(outcome.databases, vec!["sandbox_x"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_restore_retries_transient_failures() {
+        let mut adapter = MockAdapter::new();
+        adapter
+            .expect_decompress_dump()
+            .returning(|path| Ok(path.to_string()));
+        let mut seq = mockall::Sequence::new();
+        adapter
+            .expect_restore_dump()
+            .times(2)
+            .in_sequence(&mut seq)
+            .returning(|_, _| {
+                Err(CoreError::RestoreConnection(
+                    "the database system is starting up".to_string(),
+                ))
+            });
+        adapter
+            .expect_restore_dump()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|_, db_name| {
+                Ok(RestoreOutcome {
+                    databases: vec![db_name.to_string()],
+                    warnings: vec![],
+                })
+            });
+        adapter
+            .expect_drop_database()
+            .times(2)
+            .returning(|_| Ok(()));
+
+        let mut timings = RestoreTimings::default();
+        let outcome = restore_with_retries(
+            &adapter,
+            "/data/uploads/x/dump.sql",
+            "sandbox_x",
+            None,
+            None,
+            &mut timings,
+            &ProgressTracker::detached(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(outcome.databases, vec!["sandbox_x"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_restore_does_not_retry_bad_dumps() {
+        let mut adapter = MockAdapter::new();
+        adapter
+            .expect_decompress_dump()
+            .returning(|path| Ok(path.to_string()));
+        adapter.expect_restore_dump().times(1).returning(|_, _| {
+            Err(CoreError::RestoreSyntaxError(
+                "syntax error at or near \"CRATE\"".to_string(),
+            ))
+        });
+        adapter.expect_drop_database().never();
+
+        let mut timings = RestoreTimings::default();
+        let error = restore_with_retries(
+            &adapter,
+            "dump.sql",
+            "sandbox_x",
+            None,
+            None,
+            &mut timings,
+            &ProgressTracker::detached(),
+        )
+        .await
+        .unwrap_err();
+        assert!(error_message(&error).starts_with("[RESTORE_SYNTAX_ERROR] "));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_job_succeeds_after_two_failed_attempts() {
+        let mut adapter = MockAdapter::new();
+        adapter
+            .expect_decompress_dump()
+            .returning(|path| Ok(path.to_string()));
+        let mut seq = mockall::Sequence::new();
+        adapter
+            .expect_restore_dump()
+            .times(2)
+            .in_sequence(&mut seq)
+            .returning(|_, _| Err(CoreError::RestoreUnknown("out of memory".to_string())));
+        adapter
+            .expect_restore_dump()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|_, db_name| {
+                Ok(RestoreOutcome {
+                    databases: vec![db_name.to_string()],
+                    warnings: vec![],
+                })
+            });
+
+        // Each loop iteration is one poll that claimed the dump
+        let base_delay = Duration::from_secs(30);
+        let mut attempts = 0;
+        let mut delays = Vec::new();
+        let outcome = loop {
+            let mut timings = RestoreTimings::default();
+            match restore_with_retries(
+                &adapter,
+                "dump.sql",
+                "sandbox_x",
+                None,
+                None,
+                &mut timings,
+                &ProgressTracker::detached(),
+            )
+            .await
+            {
+                Ok(outcome) => break outcome,
+                Err(e) => {
+                    attempts += 1;
+                    match failure_action(attempts, 3, base_delay, &e) {
+                        FailureAction::RetryAfter(delay) => delays.push(delay),
+                        FailureAction::GiveUp => panic!("gave up after {} attempts", attempts),
+                    }
+                }
+            }
+        };
+
+        assert_eq!(outcome.databases, vec!["sandbox_x"]);
+        assert_eq!(delays, vec![base_delay, base_delay * 2]);
+    }
+
+    #[test]
+    fn test_failure_action() {
+        let base = Duration::from_secs(30);
+        let transient = anyhow::Error::new(CoreError::RestoreConnection("refused".to_string()));
+        assert_eq!(
+            failure_action(1, 3, base, &transient),
+            FailureAction::RetryAfter(base)
+        );
+        assert_eq!(
+            failure_action(2, 3, base, &transient),
+            FailureAction::RetryAfter(base * 2)
+        );
+        assert_eq!(
+            failure_action(3, 3, base, &transient),
+            FailureAction::GiveUp
+        );
+        assert_eq!(
+            failure_action(20, 30, base, &transient),
+            FailureAction::RetryAfter(JOB_RETRY_MAX_DELAY)
+        );
+
+        let bad_dump = anyhow::Error::new(CoreError::RestoreSyntaxError("bad".to_string()));
+        assert_eq!(failure_action(1, 3, base, &bad_dump), FailureAction::GiveUp);
+
+        let cancelled = anyhow::Error::new(CoreError::RestoreCancelled("killed".to_string()));
+        assert_eq!(
+            failure_action(1, 3, base, &cancelled),
+            FailureAction::GiveUp
+        );
+    }
+
+    #[test]
+    fn test_error_message_without_kind() {
+        let error = anyhow::anyhow!("no schema graph");
+        assert_eq!(error_message(&error), "no schema graph");
+    }
+
+    #[test]
+    fn test_join_warnings() {
+        assert_eq!(join_warnings(&[]), None);
+        assert_eq!(
+            join_warnings(&["a".to_string(), "b".to_string()]).as_deref(),
+            Some("a\nb")
+        );
+    }
+
+    #[test]
+    fn test_primary_database_fallback() {
+        assert_eq!(primary_database(&[], "sandbox_x"), "sandbox_x");
+        assert_eq!(
+            primary_database(&["".to_string(), "sandbox_x_app".to_string()], "sandbox_x"),
+            "sandbox_x_app"
+        );
+    }
+
+    #[test]
+    fn test_databases_to_analyze_fallback() {
+        let all = vec![
+            "sandbox_x_app".to_string(),
+            "sandbox_x_analytics".to_string(),
+        ];
+        assert_eq!(
+            databases_to_analyze("sandbox_x_app", Some(all.clone())),
+            all
+        );
+        assert_eq!(
+            databases_to_analyze("sandbox_x", Some(vec![])),
+            vec!["sandbox_x"]
+        );
+        assert_eq!(databases_to_analyze("sandbox_x", None), vec!["sandbox_x"]);
+    }
+
+    #[tokio::test]
+    async fn test_analyze_builds_graph_per_database() {
+        let mut adapter = MockAdapter::new();
+        adapter
+            .expect_analyze_database()
+            .times(2)
+            .returning(|_| Ok(()));
+        adapter
+            .expect_build_schema_graph()
+            .times(2)
+            .returning(|db| {
+                Ok(SchemaGraph {
+                    tables: vec![TableInfo {
+                        schema_name: "public".to_string(),
+                        table_name: db.to_string(),
+                        estimated_row_count: 0,
+                        columns: vec![],
+                        comment: None,
+                        is_partition: false,
+                        partition_of: None,
+                        partition_strategy: None,
+                    }],
+                    foreign_keys: vec![],
+                    indexes: vec![],
+                    views: vec![],
+                    triggers: vec![],
+                    enum_types: vec![],
+                    sequences: vec![],
+                })
+            });
+
+        let databases = vec![
+            "sandbox_x_app".to_string(),
+            "sandbox_x_analytics".to_string(),
+        ];
+        let mut timings = RestoreTimings::default();
+        let progress = ProgressTracker::detached();
+        let graphs = analyze_with_timings(&adapter, &databases, &mut timings, &progress)
+            .await
+            .unwrap();
+
+        let names: Vec<&str> = graphs.iter().map(|(db, _)| db.as_str()).collect();
+        assert_eq!(names, vec!["sandbox_x_app", "sandbox_x_analytics"]);
+        assert_eq!(graphs[1].1.tables[0].table_name, "sandbox_x_analytics");
+
+        let progress = progress.current().unwrap();
+        assert_eq!(progress.phase, "analyze");
+        assert_eq!(progress.databases_done, Some(2));
+        assert_eq!(progress.databases_total, Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_analyze_runs_before_schema_graph() {
+        let mut adapter = MockAdapter::new();
+        let mut seq = mockall::Sequence::new();
+        adapter
+            .expect_analyze_database()
+            .with(mockall::predicate::eq("sandbox_x"))
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|_| Ok(()));
+        adapter
+            .expect_build_schema_graph()
+            .with(mockall::predicate::eq("sandbox_x"))
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|_| Ok(SchemaGraph::default()));
+
+        let mut timings = RestoreTimings::default();
+        analyze_with_timings(
+            &adapter,
+            &["sandbox_x".to_string()],
+            &mut timings,
+            &ProgressTracker::detached(),
+        )
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_restore_timings_cover_all_phases() {
+        let mut adapter = MockAdapter::new();
+        adapter
+            .expect_decompress_dump()
+            .returning(|path| Ok(path.to_string()));
+        adapter.expect_restore_dump().returning(|_, db_name| {
+            Ok(RestoreOutcome {
+                databases: vec![db_name.to_string()],
+                warnings: vec!["WARNING:  no privileges were granted".to_string()],
+            })
+        });
+        adapter.expect_analyze_database().returning(|_| Ok(()));
+        adapter
+            .expect_build_schema_graph()
+            .returning(|_| Ok(SchemaGraph::default()));
+
+        let mut timings = RestoreTimings::default();
+        let outcome = restore_with_timings(
+            &adapter,
+            "/data/uploads/x/dump.sql",
+            "sandbox_x",
+            None,
+            None,
+            &mut timings,
+            &ProgressTracker::detached(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            join_warnings(&outcome.warnings).as_deref(),
+            Some("WARNING:  no privileges were granted")
+        );
+        let graphs = analyze_with_timings(
+            &adapter,
+            &outcome.databases,
+            &mut timings,
+            &ProgressTracker::detached(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(graphs.len(), 1);
+
+        let json = serde_json::to_value(timings).unwrap();
+        assert_timings_cover_all_phases(&json);
+    }
+
+    /// Every phase must be recorded as a non-negative whole number of ms
+    fn assert_timings_cover_all_phases(json: &serde_json::Value) {
+        for phase in [
+            "decompress_ms",
+            "restore_ms",
+            "analyze_ms",
+            "schema_graph_ms",
+        ] {
+            assert!(
+                json.get(phase).and_then(|ms| ms.as_u64()).is_some(),
+                "phase {} is missing or not a duration: {}",
+                phase,
+                json
+            );
+        }
+    }
+
+    #[test]
+    fn test_remove_upload_deletes_dump_directory() {
+        let upload_dir = tempfile::tempdir().unwrap();
+        let upload_root = upload_dir.path().to_str().unwrap();
+        let dump_id = Uuid::new_v4();
+        let dump_dir = upload_dir.path().join(dump_id.to_string());
+        std::fs::create_dir_all(dump_dir.join("dump")).unwrap();
+        std::fs::write(dump_dir.join("dump").join("toc.dat"), b"PGDMP").unwrap();
+        let other = upload_dir.path().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(&other).unwrap();
+
+        assert!(remove_upload(upload_root, dump_id).unwrap());
+        assert!(!dump_dir.exists());
+        assert!(other.exists());
+        assert!(!remove_upload(upload_root, dump_id).unwrap());
+    }
+
+    /// Requires a migrated metadata database in `TEST_DATABASE_URL`
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_restore_timings_are_stored_for_all_phases() {
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL is required");
+        let pool = PgPool::connect(&url).await.unwrap();
+        let upload_dir = tempfile::tempdir().unwrap();
+        let config = WorkerConfig {
+            upload_dir: upload_dir.path().to_str().unwrap().to_string(),
+            ..crate::config::tests::test_config()
+        };
+
+        let mut adapter = MockAdapter::new();
+        adapter
+            .expect_decompress_dump()
+            .returning(|path| Ok(path.to_string()));
+        adapter.expect_restore_dump().returning(|_, db_name| {
+            Ok(RestoreOutcome {
+                databases: vec![db_name.to_string()],
+                warnings: Vec::new(),
+            })
+        });
+        adapter.expect_analyze_database().returning(|_| Ok(()));
+        adapter
+            .expect_build_schema_graph()
+            .returning(|_| Ok(SchemaGraph::default()));
+
+        let id = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO dumps (id, slug, status, expires_at) VALUES ($1, $2, $3, NOW() + INTERVAL '1 hour')",
+        )
+        .bind(id)
+        .bind(format!("timings-test-{}", id))
+        .bind(DumpStatus::Restoring.as_str())
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let restored = process_restore(&pool, &adapter, &config, claim_dump(&pool, id).await).await;
+        let analyzed =
+            process_analysis(&pool, &adapter, &config, claim_dump(&pool, id).await).await;
+        let timings: Option<serde_json::Value> =
+            sqlx::query_scalar("SELECT restore_timings FROM dumps WHERE id = $1")
+                .bind(id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+
+        sqlx::query("DELETE FROM dumps WHERE id = $1")
+            .bind(id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        restored.unwrap();
+        analyzed.unwrap();
+        assert_timings_cover_all_phases(&timings.expect("restore_timings was not stored"));
+    }
+
+    /// Requires a migrated metadata database in `TEST_DATABASE_URL`
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_restore_stores_all_pg_dumpall_databases() {
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL is required");
+        let pool = PgPool::connect(&url).await.unwrap();
+        let upload_dir = tempfile::tempdir().unwrap();
+        let config = WorkerConfig {
+            upload_dir: upload_dir.path().to_str().unwrap().to_string(),
+            ..crate::config::tests::test_config()
+        };
+
+        let mut adapter = MockAdapter::new();
+        adapter
+            .expect_decompress_dump()
+            .returning(|path| Ok(path.to_string()));
+        adapter.expect_restore_dump().returning(|_, prefix| {
+            Ok(RestoreOutcome {
+                databases: vec![format!("{}_app", prefix), format!("{}_analytics", prefix)],
+                warnings: Vec::new(),
+            })
+        });
+
+        let id = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO dumps (id, slug, status, expires_at) VALUES ($1, $2, $3, NOW() + INTERVAL '1 hour')",
+        )
+        .bind(id)
+        .bind(format!("dumpall-test-{}", id))
+        .bind(DumpStatus::Restoring.as_str())
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let restored = process_restore(&pool, &adapter, &config, claim_dump(&pool, id).await).await;
+        let (primary, databases): (Option<String>, Option<Vec<String>>) =
+            sqlx::query_as("SELECT sandbox_db_name, sandbox_databases FROM dumps WHERE id = $1")
+                .bind(id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+
+        sqlx::query("DELETE FROM dumps WHERE id = $1")
+            .bind(id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        restored.unwrap();
+        let prefix = format!("sandbox_{}", id.to_string().replace('-', "_"));
+        assert_eq!(
+            databases.unwrap(),
+            vec![format!("{}_app", prefix), format!("{}_analytics", prefix)]
+        );
+        assert_eq!(primary.unwrap(), format!("{}_app", prefix));
+    }
+
+    /// Claim a dump directly, as [`claim_job_by_status`] would
+    async fn claim_dump(pool: &PgPool, dump_id: Uuid) -> JobClaim {
+        let claim = JobClaim {
+            dump_id,
+            token: Uuid::new_v4(),
+        };
+        sqlx::query("UPDATE dumps SET claimed_at = NOW(), claim_token = $1 WHERE id = $2")
+            .bind(claim.token)
+            .bind(dump_id)
+            .execute(pool)
+            .await
+            .unwrap();
+        claim
+    }
+
+    /// Requires a migrated metadata database in `TEST_DATABASE_URL`
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_running_job_keeps_its_claim() {
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL is required");
+        let pool = PgPool::connect(&url).await.unwrap();
+        let id = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO dumps (id, slug, status, expires_at) VALUES ($1, $2, $3, NOW() + INTERVAL '1 hour')",
+        )
+        .bind(id)
+        .bind(format!("claim-refresh-test-{}", id))
+        .bind(DumpStatus::Restoring.as_str())
+        .execute(&pool)
+        .await
+        .unwrap();
+        let claim = claim_dump(&pool, id).await;
+        let claimed_at = || async {
+            sqlx::query_scalar::<_, chrono::DateTime<Utc>>(
+                "SELECT claimed_at FROM dumps WHERE id = $1",
+            )
+            .bind(id)
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+        };
+
+        // A job running longer than the claim timeout: its claim looks
+        // abandoned until the refresh catches up
+        sqlx::query("UPDATE dumps SET claimed_at = NOW() - INTERVAL '1 day' WHERE id = $1")
+            .bind(id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let started = Utc::now();
+        hold_claim(&pool, claim, Duration::from_millis(20), async {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            Ok(())
+        })
+        .await
+        .unwrap();
+        let refreshed = claimed_at().await;
+
+        // Once another claim replaced it, the job's final update is refused
+        let other = claim_dump(&pool, id).await;
+        let mut adapter = MockAdapter::new();
+        adapter.expect_analyze_database().returning(|_| Ok(()));
+        adapter
+            .expect_build_schema_graph()
+            .returning(|_| Ok(SchemaGraph::default()));
+        sqlx::query("UPDATE dumps SET sandbox_db_name = 'sandbox_x' WHERE id = $1")
+            .bind(id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let stale =
+            process_analysis(&pool, &adapter, &crate::config::tests::test_config(), claim).await;
+        let status: String = sqlx::query_scalar("SELECT status FROM dumps WHERE id = $1")
+            .bind(id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        let token: Option<Uuid> = sqlx::query_scalar("SELECT claim_token FROM dumps WHERE id = $1")
+            .bind(id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+        sqlx::query("DELETE FROM dumps WHERE id = $1")
+            .bind(id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        assert!(refreshed >= started, "claim was not refreshed");
+        assert!(stale.unwrap_err().is::<ClaimLost>());
+        assert_eq!(status, DumpStatus::Restoring.as_str());
+        assert_eq!(token, Some(other.token));
+    }
+
+    /// Requires a migrated metadata database in `TEST_DATABASE_URL`
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_concurrent_claims_are_disjoint() {
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL is required");
+        let pool = PgPool::connect(&url).await.unwrap();
+
+        let ids: Vec<Uuid> = (0..6).map(|_| Uuid::new_v4()).collect();
+        for id in &ids {
+            sqlx::query(
+                "INSERT INTO dumps (id, slug, status, expires_at) VALUES ($1, $2, $3, NOW() + INTERVAL '1 hour')",
+            )
+            .bind(id)
+            .bind(format!("claim-test-{}", id))
+            .bind(DumpStatus::Restoring.as_str())
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let claim_all = || async {
+            let mut claimed = Vec::new();
+            while let Some(claim) = claim_job_by_status(&pool, DumpStatus::Restoring, 360)
+                .await
+                .unwrap()
+            {
+                claimed.push(claim.dump_id);
+            }
+            claimed
+        };
+        let (first, second) = tokio::join!(claim_all(), claim_all());
+
+        sqlx::query("DELETE FROM dumps WHERE id = ANY($1)")
+            .bind(&ids)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        assert!(first.iter().all(|id| !second.contains(id)));
+        let claimed = first
+            .iter()
+            .chain(&second)
+            .filter(|id| ids.contains(id))
+            .count();
+        assert_eq!(claimed, ids.len());
+    }
+
+    /// Requires a migrated metadata database in `TEST_DATABASE_URL`
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_failed_job_waits_for_retry_then_gives_up() {
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL is required");
+        let pool = PgPool::connect(&url).await.unwrap();
+        let config = WorkerConfig {
+            job_max_attempts: 2,
+            job_retry_base_delay_secs: 3600,
+            ..crate::config::tests::test_config()
+        };
+        let id = Uuid::new_v4();
+        // Oldest first, so it is claimed ahead of anything else waiting
+        sqlx::query(
+            r#"
+            INSERT INTO dumps (id, slug, status, updated_at, expires_at)
+            VALUES ($1, $2, $3, '2000-01-01', NOW() + INTERVAL '1 hour')
+            "#,
+        )
+        .bind(id)
+        .bind(format!("retry-test-{}", id))
+        .bind(DumpStatus::Analyzing.as_str())
+        .execute(&pool)
+        .await
+        .unwrap();
+        let claim = || async {
+            claim_job_by_status(&pool, DumpStatus::Analyzing, config.job_claim_timeout_mins)
+                .await
+                .unwrap()
+        };
+        let dump_state = || async {
+            sqlx::query_as::<_, (String, i32, Option<chrono::DateTime<Utc>>)>(
+                "SELECT status, attempts, next_retry_at FROM dumps WHERE id = $1",
+            )
+            .bind(id)
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+        };
+
+        let first = claim().await;
+        if let Some(first) = first {
+            record_failure(&pool, &config, first, &anyhow::anyhow!("connection reset"))
+                .await
+                .unwrap();
+        }
+        let after_first = dump_state().await;
+        let during_backoff = claim().await;
+
+        // Once the backoff has passed the job is claimed again
+        sqlx::query(
+            "UPDATE dumps SET next_retry_at = NOW() - INTERVAL '1 second', updated_at = '2000-01-01' WHERE id = $1",
+        )
+        .bind(id)
+        .execute(&pool)
+        .await
+        .unwrap();
+        let second = claim().await;
+        if let Some(second) = second {
+            record_failure(&pool, &config, second, &anyhow::anyhow!("connection reset"))
+                .await
+                .unwrap();
+        }
+        let after_second = dump_state().await;
+
+        sqlx::query("DELETE FROM dumps WHERE id = $1")
+            .bind(id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        assert_eq!(first.map(|c| c.dump_id), Some(id));
+        let (status, attempts, next_retry_at) = after_first;
+        assert_eq!(status, DumpStatus::Analyzing.as_str());
+        assert_eq!(attempts, 1);
+        assert!(next_retry_at.unwrap() > Utc::now() + chrono::Duration::minutes(50));
+        assert_ne!(during_backoff.map(|c| c.dump_id), Some(id));
+
+        assert_eq!(second.map(|c| c.dump_id), Some(id));
+        let (status, attempts, next_retry_at) = after_second;
+        assert_eq!(status, DumpStatus::Error.as_str());
+        assert_eq!(attempts, 2);
+        assert_eq!(next_retry_at, None);
+    }
+
+    /// Requires a migrated metadata database in `TEST_DATABASE_URL`
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_archive_next_idle_dump_selects_unviewed_ready_dumps() {
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL is required");
+        let pool = PgPool::connect(&url).await.unwrap();
+        let sandbox_name = |id: Uuid| format!("sandbox_{}", id.simple());
+
+        let idle = Uuid::new_v4();
+        let never_viewed = Uuid::new_v4();
+        let recently_viewed = Uuid::new_v4();
+        let purged = Uuid::new_v4();
+        let failed = Uuid::new_v4();
+        let long_ago = Utc::now() - chrono::Duration::days(3);
+        let dumps = [
+            (idle, DumpStatus::Ready, Some(long_ago), None),
+            (never_viewed, DumpStatus::Ready, None, None),
+            (recently_viewed, DumpStatus::Ready, Some(Utc::now()), None),
+            (purged, DumpStatus::Ready, Some(long_ago), Some(long_ago)),
+            (failed, DumpStatus::Error, Some(long_ago), None),
+        ];
+        for (id, status, last_viewed_at, upload_purged_at) in dumps {
+            sqlx::query(
+                r#"
+                INSERT INTO dumps (id, slug, status, sandbox_db_name, last_viewed_at,
+                                   upload_purged_at, updated_at, expires_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, NOW() + INTERVAL '1 week')
+                "#,
+            )
+            .bind(id)
+            .bind(format!("idle-test-{}", id))
+            .bind(status.as_str())
+            .bind(sandbox_name(id))
+            .bind(last_viewed_at)
+            .bind(upload_purged_at)
+            .bind(long_ago)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        let ids: Vec<Uuid> = dumps.iter().map(|d| d.0).collect();
+
+        let mut adapter = MockAdapter::new();
+        adapter.expect_drop_database().returning(|_| Ok(()));
+        let mut archived = Vec::new();
+        let cutoff = Utc::now() - chrono::Duration::days(1);
+        while let Some(dump) = archive_next_idle_dump(&pool, &adapter, cutoff)
+            .await
+            .unwrap()
+        {
+            archived.push(dump);
+        }
+        let statuses: Vec<(Uuid, String)> =
+            sqlx::query_as("SELECT id, status FROM dumps WHERE id = ANY($1)")
+                .bind(&ids)
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        sqlx::query("DELETE FROM dumps WHERE id = ANY($1)")
+            .bind(&ids)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let mut archived: Vec<Uuid> = archived
+            .into_iter()
+            .filter(|d| ids.contains(&d.id))
+            .map(|d| {
+                assert_eq!(d.sandbox_databases, vec![sandbox_name(d.id)]);
+                d.id
+            })
+            .collect();
+        archived.sort();
+        let mut expected = vec![idle, never_viewed];
+        expected.sort();
+        assert_eq!(archived, expected);
+        for (id, status) in statuses {
+            let expected = if expected.contains(&id) {
+                DumpStatus::Archived
+            } else if id == failed {
+                DumpStatus::Error
+            } else {
+                DumpStatus::Ready
+            };
+            assert_eq!(status, expected.as_str());
+        }
+    }
 
     #[test]
     fn test_sandbox_db_name_format() {
@@ -5,9 +5,12 @@
 
 mod config;
 mod jobs;
+mod metrics;
+mod progress;
 
 use sqlx::postgres::PgPool;
-use std::time::{Duration, Instant};
+use std::time::Duration;
+use tokio::time::{interval, interval_at, Instant, MissedTickBehavior};
 use tracing::{error, info};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
@@ -15,16 +18,24 @@ use db_viewer_core::adapter::PostgresAdapter;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    // Load .env first so RUST_LOG and LOG_FORMAT can come from it
+    dotenvy::dotenv().ok();
+
     // Initialize tracing
-    tracing_subscriber::registry()
-        .with(tracing_subscriber::EnvFilter::new(
-            std::env::var("RUST_LOG").unwrap_or_else(|_| "info,db_viewer_worker=debug".into()),
-        ))
-        .with(tracing_subscriber::fmt::layer())
-        .init();
+    let registry = tracing_subscriber::registry().with(tracing_subscriber::EnvFilter::new(
+        std::env::var("RUST_LOG").unwrap_or_else(|_| "info,db_viewer_worker=debug".into()),
+    ));
+    // LOG_FORMAT=json emits one JSON object per line (with span fields such
+    // as dump_id) for log aggregation; text is the default
+    if std::env::var("LOG_FORMAT").is_ok_and(|f| f.eq_ignore_ascii_case("json")) {
+        registry
+            .with(tracing_subscriber::fmt::layer().json().flatten_event(true))
+            .init();
+    } else {
+        registry.with(tracing_subscriber::fmt::layer()).init();
+    }
 
     // Load configuration
-    dotenvy::dotenv().ok();
     let config = config::WorkerConfig::from_env()?;
 
     info!("Starting DB Viewer Worker");
@@ -37,65 +48,103 @@ async fn main() -> anyhow::Result<()> {
     let db_pool = PgPool::connect(&config.database_url).await?;
 
     // Connect to sandbox postgres (for management operations)
-    let sandbox_pool = PgPool::connect(&config.sandbox_url()).await?;
-
-    let adapter = PostgresAdapter::new(
-        sandbox_pool,
-        config.sandbox_host.clone(),
-        config.sandbox_port,
-        config.sandbox_user.clone(),
-        config.sandbox_password.clone(),
-    );
+    let sandbox_pool = PgPool::connect(&config.sandbox_url("postgres")).await?;
+
+    let adapter = PostgresAdapter::new(sandbox_pool.clone(), config.sandbox_connection())
+        .with_restore_parallelism(config.restore_parallelism)
+        .with_catalog_introspection(config.catalog_introspection)
+        // Restores only target the per-dump sandbox names the worker
+        // generates, so an existing one is left over from an interrupted
+        // attempt at the same restore
+        .with_restore_overwrite(true);
 
-    // Track when cleanup was last run
-    let mut last_cleanup = Instant::now();
+    if let Some(port) = config.metrics_port {
+        tokio::spawn(async move {
+            if let Err(e) = metrics::serve(port).await {
+                error!("Metrics listener failed: {}", e);
+            }
+        });
+    }
+
+    // Jobs and TTL cleanup run on independent timers so the cleanup cadence
+    // follows cleanup_interval_secs regardless of the poll interval
+    let mut poll_timer = interval(Duration::from_secs(config.poll_interval_secs));
+    poll_timer.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let cleanup_period = Duration::from_secs(config.cleanup_interval_secs);
+    let mut cleanup_timer = interval_at(Instant::now() + cleanup_period, cleanup_period);
+    cleanup_timer.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    // Stop between jobs on SIGTERM/SIGINT. A job that is running when the
+    // signal arrives is finished first: select! only picks the next branch
+    // once the previous one's body has completed.
+    let shutdown = db_viewer_core::shutdown::shutdown_signal()?;
+    tokio::pin!(shutdown);
 
     // Main worker loop
     loop {
-        // Process pending jobs (restore, analyze)
-        match jobs::process_pending_jobs(&db_pool, &adapter, &config).await {
-            Ok(processed) => {
-                if processed > 0 {
-                    info!("Processed {} jobs", processed);
+        tokio::select! {
+            _ = &mut shutdown => break,
+            _ = poll_timer.tick() => {
+                // Process pending jobs (restore, analyze)
+                match jobs::process_pending_jobs(&db_pool, &adapter, &config).await {
+                    Ok(processed) => {
+                        if processed > 0 {
+                            info!("Processed {} jobs", processed);
+                        }
+                    }
+                    Err(e) => {
+                        error!("Error processing jobs: {}", e);
+                    }
                 }
             }
-            Err(e) => {
-                error!("Error processing jobs: {}", e);
+            _ = cleanup_timer.tick() => {
+                run_cleanup(&db_pool, &adapter, &config).await;
             }
         }
+    }
 
-        // Run cleanup if enough time has passed
-        if last_cleanup.elapsed() >= Duration::from_secs(config.cleanup_interval_secs) {
-            info!("Running TTL cleanup...");
-            match jobs::cleanup_expired_dumps(&db_pool, &adapter, &config).await {
-                Ok(cleaned) => {
-                    if cleaned > 0 {
-                        info!("Cleaned up {} expired dumps", cleaned);
-                    } else {
-                        info!("No expired dumps to cleanup");
-                    }
-                }
-                Err(e) => {
-                    error!("Error during cleanup: {}", e);
-                }
-            }
+    info!("Worker stopped, closing database connections");
+    sandbox_pool.close().await;
+    db_pool.close().await;
+    Ok(())
+}
 
-            // Also cleanup stale dumps (UPLOADED, ERROR, CREATED for more than configured timeout)
-            match jobs::cleanup_stale_dumps(&db_pool, &adapter, &config).await {
-                Ok(cleaned) => {
-                    if cleaned > 0 {
-                        info!("Cleaned up {} stale dumps", cleaned);
-                    }
-                }
-                Err(e) => {
-                    error!("Error during stale dump cleanup: {}", e);
-                }
+/// Run TTL cleanup, idle sandbox eviction and stale dump cleanup
+async fn run_cleanup(db_pool: &PgPool, adapter: &PostgresAdapter, config: &config::WorkerConfig) {
+    info!("Running TTL cleanup...");
+    match jobs::cleanup_expired_dumps(db_pool, adapter, config).await {
+        Ok(cleaned) => {
+            if cleaned > 0 {
+                info!("Cleaned up {} expired dumps", cleaned);
+            } else {
+                info!("No expired dumps to cleanup");
             }
+        }
+        Err(e) => {
+            error!("Error during cleanup: {}", e);
+        }
+    }
 
-            last_cleanup = Instant::now();
+    match jobs::evict_idle_sandboxes(db_pool, adapter, config).await {
+        Ok(archived) => {
+            if archived > 0 {
+                info!("Archived {} idle dumps", archived);
+            }
         }
+        Err(e) => {
+            error!("Error during idle sandbox eviction: {}", e);
+        }
+    }
 
-        // Sleep before next poll
-        tokio::time::sleep(Duration::from_secs(config.poll_interval_secs)).await;
+    // Also cleanup stale dumps (UPLOADED, ERROR, CREATED for more than configured timeout)
+    match jobs::cleanup_stale_dumps(db_pool, adapter, config).await {
+        Ok(cleaned) => {
+            if cleaned > 0 {
+                info!("Cleaned up {} stale dumps", cleaned);
+            }
+        }
+        Err(e) => {
+            error!("Error during stale dump cleanup: {}", e);
+        }
     }
 }
@@ -0,0 +1,71 @@
+//! Prometheus metrics exposed by the worker
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use axum::{routing::get, Router};
+use prometheus::{Encoder, HistogramOpts, HistogramVec, TextEncoder};
+use tracing::{info, warn};
+
+/// Histogram buckets (seconds) sized for restores ranging from seconds to hours
+const PHASE_BUCKETS: &[f64] = &[
+    0.1, 0.5, 1.0, 5.0, 15.0, 30.0, 60.0, 300.0, 900.0, 1800.0, 3600.0,
+];
+
+static RESTORE_PHASE_SECONDS: OnceLock<HistogramVec> = OnceLock::new();
+
+fn restore_phase_seconds() -> &'static HistogramVec {
+    RESTORE_PHASE_SECONDS.get_or_init(|| {
+        let histogram = HistogramVec::new(
+            HistogramOpts::new(
+                "pgdumplens_restore_phase_seconds",
+                "Wall-clock duration of each dump restore phase",
+            )
+            .buckets(PHASE_BUCKETS.to_vec()),
+            &["phase"],
+        )
+        .expect("valid histogram definition");
+        if let Err(e) = prometheus::register(Box::new(histogram.clone())) {
+            warn!("Failed to register restore phase histogram: {}", e);
+        }
+        histogram
+    })
+}
+
+/// Record the duration of a single restore phase
+pub fn observe_phase(phase: &str, duration: Duration) {
+    restore_phase_seconds()
+        .with_label_values(&[phase])
+        .observe(duration.as_secs_f64());
+}
+
+/// Render all registered metrics in the Prometheus text format
+fn render() -> String {
+    let mut buffer = Vec::new();
+    if let Err(e) = TextEncoder::new().encode(&prometheus::gather(), &mut buffer) {
+        warn!("Failed to encode metrics: {}", e);
+    }
+    String::from_utf8(buffer).unwrap_or_default()
+}
+
+/// Serve `GET /metrics` on the given port until the process exits
+pub async fn serve(port: u16) -> anyhow::Result<()> {
+    let app = Router::new().route("/metrics", get(|| async { render() }));
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", port)).await?;
+    info!("Serving worker metrics on port {}", port);
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_observe_phase_is_rendered() {
+        observe_phase("restore", Duration::from_millis(250));
+        let output = render();
+        assert!(output.contains("pgdumplens_restore_phase_seconds"));
+        assert!(output.contains("phase=\"restore\""));
+    }
+}
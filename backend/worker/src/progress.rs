@@ -0,0 +1,125 @@
+//! Job progress reporting
+//!
+//! Jobs update a [`ProgressTracker`] synchronously; a background task writes
+//! the latest value to `dumps.progress` so slow metadata writes never hold up
+//! a restore. Intermediate values may be skipped, only the latest is stored.
+
+use chrono::Utc;
+use sqlx::postgres::PgPool;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::warn;
+use uuid::Uuid;
+
+use db_viewer_core::domain::DumpProgress;
+
+/// Handle used by a job to publish its progress
+pub struct ProgressTracker {
+    tx: watch::Sender<Option<DumpProgress>>,
+}
+
+impl ProgressTracker {
+    /// Create a tracker whose updates are written to the dump's row until it
+    /// is dropped; await the returned handle to flush the final value
+    pub fn spawn(db_pool: PgPool, dump_id: Uuid) -> (Self, JoinHandle<()>) {
+        let (tx, mut rx) = watch::channel(None);
+        let handle = tokio::spawn(async move {
+            while rx.changed().await.is_ok() {
+                let progress = rx.borrow_and_update().clone();
+                write_progress(&db_pool, dump_id, progress.as_ref()).await;
+            }
+            // Flush an update that raced with the tracker being dropped
+            let progress = rx.borrow().clone();
+            write_progress(&db_pool, dump_id, progress.as_ref()).await;
+        });
+        (Self { tx }, handle)
+    }
+
+    /// Create a tracker that only keeps the latest value in memory
+    #[cfg(test)]
+    pub fn detached() -> Self {
+        Self {
+            tx: watch::Sender::new(None),
+        }
+    }
+
+    /// Enter a new phase, optionally recording the size of its input file
+    pub fn phase(&self, phase: &str, bytes_total: Option<u64>) {
+        self.tx.send_replace(Some(DumpProgress {
+            phase: phase.to_string(),
+            bytes_total,
+            databases_done: None,
+            databases_total: None,
+            phase_started_at: Utc::now(),
+        }));
+    }
+
+    /// Record how many databases have been analyzed
+    pub fn databases(&self, done: usize, total: usize) {
+        self.tx.send_modify(|progress| {
+            if let Some(progress) = progress {
+                progress.databases_done = Some(done);
+                progress.databases_total = Some(total);
+            }
+        });
+    }
+
+    /// Latest published progress
+    #[cfg(test)]
+    pub fn current(&self) -> Option<DumpProgress> {
+        self.tx.borrow().clone()
+    }
+}
+
+/// Size of a file on disk, if it exists
+pub fn file_size(path: &str) -> Option<u64> {
+    std::fs::metadata(path).ok().map(|m| m.len())
+}
+
+async fn write_progress(db_pool: &PgPool, dump_id: Uuid, progress: Option<&DumpProgress>) {
+    let Some(progress) = progress else {
+        return;
+    };
+    let value = match serde_json::to_value(progress) {
+        Ok(value) => value,
+        Err(e) => {
+            warn!("Failed to serialize progress for dump {}: {}", dump_id, e);
+            return;
+        }
+    };
+    if let Err(e) = sqlx::query("UPDATE dumps SET progress = $1 WHERE id = $2")
+        .bind(value)
+        .bind(dump_id)
+        .execute(db_pool)
+        .await
+    {
+        warn!("Failed to record progress for dump {}: {}", dump_id, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phase_resets_database_counts() {
+        let tracker = ProgressTracker::detached();
+        assert_eq!(tracker.current(), None);
+
+        // Counts are ignored until a phase has started
+        tracker.databases(1, 2);
+        assert_eq!(tracker.current(), None);
+
+        tracker.phase("analyze", None);
+        tracker.databases(1, 2);
+        let progress = tracker.current().unwrap();
+        assert_eq!(progress.phase, "analyze");
+        assert_eq!(progress.databases_done, Some(1));
+        assert_eq!(progress.databases_total, Some(2));
+
+        tracker.phase("restore", Some(42));
+        let progress = tracker.current().unwrap();
+        assert_eq!(progress.bytes_total, Some(42));
+        assert_eq!(progress.databases_done, None);
+    }
+}
@@ -8,6 +8,7 @@ import SearchResults from '@/components/SearchResults';
 import { SqlConsole } from '@/components/SqlConsole';
 import DiffViewer from '@/components/DiffViewer';
 import { SchemaDiffResponse, TableDataDiffResponse } from '@/types';
+import { parseRestoreError } from '@/lib/utils';
 import { Table2, X, Check, ArrowRight, ArrowLeft, AlertTriangle } from 'lucide-react';
 
 interface Dump {
@@ -1143,7 +1144,14 @@ export default function DumpDetailPage() {
       ) : dump.status === 'ERROR' ? (
         <div className="bg-red-50 dark:bg-red-900/20 border border-red-200 dark:border-red-800 rounded-2xl p-6">
           <h2 className="text-lg font-semibold text-red-600 dark:text-red-400 mb-2">Restore Failed</h2>
-          <p className="text-red-500 dark:text-red-400/80">{dump.error_message}</p>
+          <p className="text-red-500 dark:text-red-400/80">
+            {parseRestoreError(dump.error_message ?? '').message}
+          </p>
+          {parseRestoreError(dump.error_message ?? '').hint && (
+            <p className="text-sm text-red-400 dark:text-red-400/70 mt-2">
+              {parseRestoreError(dump.error_message ?? '').hint}
+            </p>
+          )}
         </div>
       ) : (
         <div className="bg-slate-50 dark:bg-slate-800/50 rounded-2xl border border-slate-200 dark:border-slate-700 p-12 text-center">
@@ -40,8 +40,10 @@ function RowDiffCard({ row, primaryKeyColumns, allColumnsAsPK }: { row: RowDiff;
   const [expanded, setExpanded] = useState(false);
   const colors = changeTypeColors[row.change_type];
   
-  const pkDisplay = typeof row.pk === 'object' 
-    ? JSON.stringify(row.pk) 
+  const pkDisplay = row.pk !== null && typeof row.pk === 'object'
+    ? primaryKeyColumns
+        .map((col) => `${col}=${JSON.stringify((row.pk as Record<string, unknown>)[col])}`)
+        .join(', ')
     : String(row.pk);
 
   const values = row.base_values || row.compare_values || {};
@@ -77,9 +77,14 @@ function DiffSummaryCard({ diff }: { diff: SchemaDiffResponse }) {
           <div className="text-blue-600 dark:text-blue-400/80">net change</div>
         </div>
       </div>
-      {(summary.fk_added > 0 || summary.fk_removed > 0) && (
+      {(summary.fk_added > 0 || summary.fk_removed > 0 || summary.fk_modified > 0) && (
         <div className="mt-3 pt-3 border-t border-gray-200 dark:border-slate-700 text-sm text-gray-600 dark:text-slate-400">
-          Foreign Keys: +{summary.fk_added} / -{summary.fk_removed}
+          Foreign Keys: +{summary.fk_added} / -{summary.fk_removed} / ~{summary.fk_modified}
+        </div>
+      )}
+      {((summary.enums_added ?? 0) > 0 || (summary.enums_removed ?? 0) > 0 || (summary.enums_modified ?? 0) > 0) && (
+        <div className="mt-3 pt-3 border-t border-gray-200 dark:border-slate-700 text-sm text-gray-600 dark:text-slate-400">
+          Enum Types: +{summary.enums_added ?? 0} / -{summary.enums_removed ?? 0} / ~{summary.enums_modified ?? 0}
         </div>
       )}
     </div>
@@ -14,6 +14,7 @@ interface RelationExplanation {
   sql_example: string;
   risk_score: number;
   risk_reasons: string[];
+  referencing_count?: number | null;
 }
 
 interface ExplainRelationResponse {
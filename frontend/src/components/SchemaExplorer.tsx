@@ -13,6 +13,9 @@ interface Column {
   is_nullable: boolean;
   is_primary_key: boolean;
   default_value?: string;
+  is_identity?: boolean;
+  identity_generation?: string | null;
+  generation_expression?: string | null;
 }
 
 interface ForeignKey {
@@ -597,7 +600,11 @@ export function SchemaExplorer({
                             )}
                           </td>
                           <td className="py-2 px-4 font-mono text-xs text-slate-500 dark:text-slate-400">
-                            {col.default_value || '-'}
+                            {col.is_identity
+                              ? `GENERATED ${col.identity_generation ?? 'BY DEFAULT'} AS IDENTITY`
+                              : col.generation_expression
+                                ? `GENERATED ALWAYS AS (${col.generation_expression}) STORED`
+                                : col.default_value || '-'}
                           </td>
                           <td className="py-2 px-4">
                             {col.is_primary_key && (
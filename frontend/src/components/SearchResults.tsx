@@ -18,6 +18,8 @@ interface SearchResponse {
   total_results: number;
   results: SearchResult[];
   searched_tables: number;
+  offset: number;
+  next_offset: number | null;
 }
 
 interface SearchResultsProps {
@@ -1,5 +1,5 @@
 import { describe, it, expect } from 'vitest';
-import { formatDistanceToNow, formatBytes, getRiskColor, parseTablePath } from './utils';
+import { formatDistanceToNow, formatBytes, getRiskColor, parseTablePath, parseRestoreError } from './utils';
 
 describe('formatDistanceToNow', () => {
   it('should return "just now" for recent dates', () => {
@@ -93,3 +93,16 @@ describe('parseTablePath', () => {
     expect(result.table).toBe('orders');
   });
 });
+
+describe('parseRestoreError', () => {
+  it('should extract the kind and hint from a prefixed message', () => {
+    const parsed = parseRestoreError('[RESTORE_SYNTAX_ERROR] Restore syntax error: bad input');
+    expect(parsed.kind).toBe('RESTORE_SYNTAX_ERROR');
+    expect(parsed.message).toBe('Restore syntax error: bad input');
+    expect(parsed.hint).not.toBeNull();
+  });
+
+  it('should pass through unprefixed messages', () => {
+    expect(parseRestoreError('boom')).toEqual({ kind: null, message: 'boom', hint: null });
+  });
+});
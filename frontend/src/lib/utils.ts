@@ -67,3 +67,24 @@ export function parseTablePath(path: string): { schema: string; table: string }
   }
   return { schema: 'public', table: parts[0] };
 }
+
+const RESTORE_ERROR_HINTS: Record<string, string> = {
+  RESTORE_TOOL_MISSING: 'The server is missing pg_restore/psql. Please contact the operator.',
+  RESTORE_SYNTAX_ERROR: 'The dump file could not be parsed. Check that it was created with pg_dump or pg_dumpall and is not truncated.',
+  RESTORE_CONNECTION: 'The sandbox database was unreachable. Please try again in a few minutes.',
+};
+
+/**
+ * Split a "[KIND] message" restore error into its kind, message and a user hint
+ */
+export function parseRestoreError(errorMessage: string): {
+  kind: string | null;
+  message: string;
+  hint: string | null;
+} {
+  const match = /^\[([A-Z_]+)\]\s*(.*)$/s.exec(errorMessage);
+  if (!match) {
+    return { kind: null, message: errorMessage, hint: null };
+  }
+  return { kind: match[1], message: match[2], hint: RESTORE_ERROR_HINTS[match[1]] ?? null };
+}
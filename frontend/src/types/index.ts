@@ -15,6 +15,36 @@ export interface Dump extends DumpSummary {
   error_message: string | null;
   updated_at: string;
   sandbox_db_name: string | null;
+  restore_timings?: RestoreTimings | null;
+  restore_warnings?: string | null;
+  dump_format?: DumpFormat | null;
+  dump_layout?: DumpLayout | null;
+  /** Set once the uploaded files were deleted after a successful restore */
+  upload_purged_at?: string | null;
+  /** When the sandbox databases were last queried */
+  last_viewed_at?: string | null;
+}
+
+export interface RestoreTimings {
+  decompress_ms: number;
+  restore_ms: number;
+  analyze_ms: number;
+  schema_graph_ms: number;
+}
+
+export interface DumpProgress {
+  phase: 'decompress' | 'restore' | 'analyze';
+  bytes_total?: number | null;
+  databases_done?: number | null;
+  databases_total?: number | null;
+  phase_started_at: string;
+}
+
+/** Payload of `status` events from `GET /api/dumps/:id/events` */
+export interface DumpEvent {
+  status: DumpStatus;
+  progress: DumpProgress | null;
+  error_message: string | null;
 }
 
 export type DumpStatus =
@@ -25,13 +55,32 @@ export type DumpStatus =
   | 'ANALYZING'
   | 'READY'
   | 'ERROR'
-  | 'DELETED';
+  | 'DELETED'
+  | 'ARCHIVED';
+
+export type DumpFormat = 'CUSTOM_FORMAT' | 'DIRECTORY' | 'PLAIN_SQL' | 'PG_DUMPALL' | 'UNKNOWN';
+
+export type DumpLayout = 'SINGLE_FILE' | 'DIRECTORY' | 'MULTI_FILE';
 
 export interface TableInfo {
   schema_name: string;
   table_name: string;
   estimated_row_count: number;
   columns: ColumnInfo[];
+  comment?: string | null;
+  is_partition?: boolean;
+  /** Parent of a partition, as `schema.table` */
+  partition_of?: string | null;
+  /** `range`, `list` or `hash` for partitioned tables */
+  partition_strategy?: string | null;
+}
+
+/** Response of `GET /api/dumps/:id/tables` (columns empty unless `columns=true`) */
+export interface TableListResponse {
+  tables: TableInfo[];
+  total_count: number;
+  limit: number;
+  offset: number;
 }
 
 export interface ColumnInfo {
@@ -40,6 +89,11 @@ export interface ColumnInfo {
   is_nullable: boolean;
   is_primary_key: boolean;
   default_value: string | null;
+  comment?: string | null;
+  ordinal_position?: number | null;
+  is_identity?: boolean;
+  identity_generation?: string | null;
+  generation_expression?: string | null;
 }
 
 export interface ForeignKey {
@@ -61,22 +115,128 @@ export type FkAction =
   | 'SET_NULL'
   | 'SET_DEFAULT';
 
+export interface IndexInfo {
+  name: string;
+  schema_name: string;
+  table_name: string;
+  columns: string[];
+  is_unique: boolean;
+  is_primary: boolean;
+  method: string;
+  /** `WHERE` clause of a partial index */
+  predicate?: string | null;
+}
+
+export interface UniqueConstraint {
+  name: string;
+  columns: string[];
+  all_not_null: boolean;
+}
+
+export interface CheckConstraint {
+  name: string;
+  /** `CHECK (...)` clause */
+  definition: string;
+}
+
+/** Response of `GET /api/dumps/:id/tables/:table/describe` */
+export interface TableDescription {
+  schema: string;
+  table: string;
+  comment: string | null;
+  estimated_row_count: number;
+  columns: ColumnInfo[];
+  primary_key: { name: string | null; columns: string[] } | null;
+  unique_constraints: UniqueConstraint[];
+  check_constraints: CheckConstraint[];
+  indexes: IndexInfo[];
+  outbound_foreign_keys: ForeignKey[];
+  inbound_foreign_keys: ForeignKey[];
+  /** Reconstructed CREATE TABLE / CREATE INDEX statements */
+  ddl: string;
+}
+
+/** Response of `GET /api/dumps/:id/tables/:table/referents` */
+export interface ReferentsResponse {
+  schema: string;
+  table: string;
+  hops: number;
+  referents: Referent[];
+}
+
+export interface Referent {
+  schema: string;
+  table: string;
+  hop_count: number;
+  /** Constraint names from the requested table out to this one */
+  path: string[];
+}
+
+export interface ViewInfo {
+  schema: string;
+  name: string;
+  definition: string;
+  is_materialized: boolean;
+  columns: ColumnInfo[];
+}
+
 export interface SchemaGraph {
   tables: TableInfo[];
   foreign_keys: ForeignKey[];
+  indexes?: IndexInfo[];
+  views?: ViewInfo[];
+  triggers?: TriggerInfo[];
+  enum_types?: EnumType[];
+  sequences?: SequenceInfo[];
+}
+
+export interface SequenceInfo {
+  schema: string;
+  name: string;
+  owned_by: string | null;
+  last_value: number | null;
+  increment: number;
+}
+
+export interface EnumType {
+  schema: string;
+  name: string;
+  values: string[];
+}
+
+export interface TriggerInfo {
+  name: string;
+  schema: string;
+  table: string;
+  timing: string;
+  event: string;
+  function: string;
+}
+
+export interface SchemaStats {
+  schema_name: string;
+  table_count: number;
+  total_estimated_rows: number;
+  fk_count: number;
 }
 
 export interface SchemaResponse {
   schema_graph: SchemaGraph;
   mermaid_er: string;
+  schema_stats: SchemaStats[];
 }
 
 export interface RiskScore {
   score: number;
   level: RiskLevel;
   reasons: string[];
+  /** Operation the risk endpoints scored */
+  operation?: RiskOperation;
 }
 
+/** `operation` parameter of the risk endpoints */
+export type RiskOperation = 'delete' | 'update';
+
 export type RiskLevel = 'low' | 'medium' | 'high' | 'critical';
 
 export interface RelationExplanation {
@@ -90,6 +250,24 @@ export interface RelationExplanation {
   sql_example: string;
   risk_score: number;
   risk_reasons: string[];
+  referencing_count?: number | null;
+}
+
+/** Response of `POST /api/dumps/:id/delete-preview` */
+export interface DeletePreviewResponse {
+  schema: string;
+  table: string;
+  total_deleted: number;
+  tables: AffectedTable[];
+  /** Foreign key that would reject the delete */
+  blocked_by: string | null;
+}
+
+export interface AffectedTable {
+  schema_name: string;
+  table_name: string;
+  deleted: number;
+  updated: number;
 }
 
 export interface TableDataResponse {
@@ -98,8 +276,31 @@ export interface TableDataResponse {
   columns: string[];
   rows: Record<string, unknown>[];
   total_count: number;
+  total_count_exact?: boolean;
   limit: number;
   offset: number;
+  filter?: string | null;
+  where?: string | null;
+  pagination?: 'offset' | 'keyset';
+  next_cursor?: string | null;
+}
+
+/** Entry of a `POST /api/dumps/:id/tables/batch` request */
+export interface BatchTableRequest {
+  schema?: string;
+  table: string;
+  limit?: number;
+}
+
+/** Response of `POST /api/dumps/:id/tables/batch`, in request order */
+export interface BatchTableDataResponse {
+  tables: {
+    schema: string;
+    table: string;
+    columns: string[];
+    rows: Record<string, unknown>[];
+    limit: number;
+  }[];
 }
 
 export interface DatabaseListResponse {
@@ -120,6 +321,12 @@ export interface DiffSummary {
   columns_modified: number;
   fk_added: number;
   fk_removed: number;
+  fk_modified: number;
+  enums_added?: number;
+  enums_removed?: number;
+  enums_modified?: number;
+  sequences_added?: number;
+  sequences_removed?: number;
   row_count_change: number;
 }
 
@@ -128,6 +335,7 @@ export interface ColumnDiffInfo {
   is_nullable: boolean;
   is_primary_key: boolean;
   default_value: string | null;
+  ordinal_position?: number | null;
 }
 
 export interface ColumnDiff {
@@ -153,20 +361,56 @@ export interface ForeignKeyDiff {
   source_table: string;
   target_table: string;
   fk_info: ForeignKey | null;
+  base_fk: ForeignKey | null;
+  compare_fk: ForeignKey | null;
+}
+
+export interface EnumTypeDiff {
+  schema_name: string;
+  type_name: string;
+  change_type: ChangeType;
+  added_values: string[];
+  removed_values: string[];
+  reordered: boolean;
+  base_values: string[] | null;
+  compare_values: string[] | null;
+}
+
+export interface SequenceDiff {
+  schema_name: string;
+  sequence_name: string;
+  change_type: ChangeType;
+  sequence: SequenceInfo;
 }
 
 export interface SchemaDiffResponse {
   base_dump_id: string;
-  compare_dump_id: string;
+  /** Absent when the dump was compared against a live database */
+  compare_dump_id?: string;
+  /** host:port/database of the live database compared against */
+  live_database?: string;
   database_name: string;
   summary: DiffSummary;
   table_diffs: TableDiff[];
   fk_diffs: ForeignKeyDiff[];
+  enum_diffs?: EnumTypeDiff[];
+  sequence_diffs?: SequenceDiff[];
+  /** Tables whose row count changed the most, largest first */
+  top_movers?: RowCountChange[];
+}
+
+export interface RowCountChange {
+  schema_name: string;
+  table_name: string;
+  base_row_count: number | null;
+  compare_row_count: number | null;
+  delta: number;
 }
 
 // ==================== Data Diff Types ====================
 
 export interface RowDiff {
+  /** Scalar for single-column keys, otherwise column name -> value */
   pk: unknown;
   change_type: 'added' | 'removed' | 'modified';
   base_values: Record<string, unknown> | null;
@@ -177,9 +421,16 @@ export interface RowDiff {
 export interface TableDataDiffResponse {
   base_dump_id: string;
   compare_dump_id: string;
+  /** Original database names when two databases of one dump are compared */
+  base_database?: string;
+  compare_database?: string;
   schema_name: string;
   table_name: string;
+  /** Columns rows were matched on */
   primary_key_columns: string[];
+  key_source?: 'primary_key' | 'unique_constraint' | 'unique_index' | 'all_columns';
+  /** Unique constraint or index used as the key */
+  key_name?: string | null;
   total_added: number;
   total_removed: number;
   total_modified: number;